@@ -6,5 +6,5 @@
 pub mod namespace;
 pub mod source_catalog;
 
-pub use namespace::DirNamespace;
+pub use namespace::{DirNamespace, StorageOptions, TenantNamespace};
 pub use source_catalog::{GraphSourceCatalog, InMemoryCatalog, SimpleTableSource};
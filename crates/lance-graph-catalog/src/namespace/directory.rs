@@ -4,11 +4,21 @@ use async_trait::async_trait;
 use lance_namespace::models::{DescribeTableRequest, DescribeTableResponse};
 use lance_namespace::{Error as NamespaceError, LanceNamespace, Result};
 use snafu::location;
+use std::collections::HashMap;
+
+use crate::namespace::StorageOptions;
 
 /// A namespace that resolves table names relative to a base directory or URI.
+///
+/// Every table is opened with the namespace's storage options, layered with
+/// the options registered for that table, so each label's dataset can use its
+/// own credentials or encryption keys.
 #[derive(Debug, Clone)]
 pub struct DirNamespace {
     base_uri: String,
+    storage_options: StorageOptions,
+    table_storage_options: HashMap<String, StorageOptions>,
+    table_locations: HashMap<String, String>,
 }
 
 impl DirNamespace {
@@ -20,6 +30,9 @@ impl DirNamespace {
         let clean_uri = uri.trim_end_matches('/').to_string();
         Self {
             base_uri: clean_uri,
+            storage_options: StorageOptions::default(),
+            table_storage_options: HashMap::new(),
+            table_locations: HashMap::new(),
         }
     }
 
@@ -27,6 +40,63 @@ impl DirNamespace {
     pub fn base_uri(&self) -> &str {
         &self.base_uri
     }
+
+    /// Open every table with `options`.
+    pub fn with_storage_options(mut self, options: impl Into<StorageOptions>) -> Self {
+        self.storage_options = options.into();
+        self
+    }
+
+    /// Open `table` with `options` on top of the namespace-wide options.
+    ///
+    /// Table names are matched case-insensitively.
+    pub fn with_table_storage_options(
+        mut self,
+        table: &str,
+        options: impl Into<StorageOptions>,
+    ) -> Self {
+        self.table_storage_options
+            .insert(table.to_lowercase(), options.into());
+        self
+    }
+
+    /// Return the storage options `table` is opened with.
+    pub fn storage_options_for(&self, table: &str) -> StorageOptions {
+        match self.table_storage_options.get(&table.to_lowercase()) {
+            Some(options) => self.storage_options.merged(options),
+            None => self.storage_options.clone(),
+        }
+    }
+
+    /// Resolve `table` to the dataset at `uri` instead of one under the base
+    /// URI.
+    ///
+    /// Table names are matched case-insensitively.
+    pub fn with_table_location(mut self, table: &str, uri: impl Into<String>) -> Self {
+        let uri = uri.into().trim_end_matches('/').to_string();
+        self.table_locations.insert(table.to_lowercase(), uri);
+        self
+    }
+
+    /// Return the URI of the dataset backing `table`.
+    pub fn table_uri(&self, table: &str) -> String {
+        match self.table_locations.get(&table.to_lowercase()) {
+            Some(uri) => uri.clone(),
+            None => format!("{}/{}.lance", self.base_uri, table),
+        }
+    }
+
+    /// Return a namespace rooted at `base_uri` that opens every table with
+    /// the same storage options, and at the same explicit locations, as this
+    /// one.
+    pub fn rebased(&self, base_uri: impl Into<String>) -> Self {
+        Self {
+            base_uri: Self::new(base_uri).base_uri,
+            storage_options: self.storage_options.clone(),
+            table_storage_options: self.table_storage_options.clone(),
+            table_locations: self.table_locations.clone(),
+        }
+    }
 }
 
 #[async_trait]
@@ -54,11 +124,16 @@ impl LanceNamespace for DirNamespace {
         }
 
         let table_name = &id[0];
-        let location = format!("{}/{}.lance", self.base_uri, table_name);
+        let location = self.table_uri(table_name);
 
         let mut response = DescribeTableResponse::new();
         response.location = Some(location);
-        response.storage_options = None;
+        let storage_options = self.storage_options_for(table_name);
+        response.storage_options = if storage_options.is_empty() {
+            None
+        } else {
+            Some(storage_options.into())
+        };
         Ok(response)
     }
 }
@@ -80,6 +155,83 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn describe_table_returns_per_table_storage_options() {
+        let namespace = DirNamespace::new("s3://bucket/graph")
+            .with_storage_options(StorageOptions::new().with_option("aws_region", "eu-west-1"))
+            .with_table_storage_options("Person", StorageOptions::new().with_sse_kms("pii-key"));
+
+        let describe = |table: &str| {
+            let mut request = DescribeTableRequest::new();
+            request.id = Some(vec![table.to_string()]);
+            namespace.describe_table(request)
+        };
+
+        let person = describe("Person").await.unwrap().storage_options.unwrap();
+        assert_eq!(
+            person.get("aws_region").map(String::as_str),
+            Some("eu-west-1")
+        );
+        assert_eq!(
+            person.get("aws_sse_kms_key_id").map(String::as_str),
+            Some("pii-key")
+        );
+
+        let knows = describe("KNOWS").await.unwrap().storage_options.unwrap();
+        assert!(!knows.contains_key("aws_sse_kms_key_id"));
+
+        let plain = DirNamespace::new("file:///tmp");
+        let mut request = DescribeTableRequest::new();
+        request.id = Some(vec!["users".to_string()]);
+        assert!(plain
+            .describe_table(request)
+            .await
+            .unwrap()
+            .storage_options
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rebased_namespace_keeps_storage_options() {
+        let namespace = DirNamespace::new("s3://bucket/graph")
+            .with_table_storage_options("Person", StorageOptions::new().with_sse_kms("pii-key"));
+        let staging = namespace.rebased("s3://bucket/graph/_staging/");
+
+        let mut request = DescribeTableRequest::new();
+        request.id = Some(vec!["Person".to_string()]);
+        let response = staging.describe_table(request).await.unwrap();
+        assert_eq!(
+            response.location.as_deref(),
+            Some("s3://bucket/graph/_staging/Person.lance")
+        );
+        assert_eq!(
+            response
+                .storage_options
+                .unwrap()
+                .get("aws_sse_kms_key_id")
+                .map(String::as_str),
+            Some("pii-key")
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_table_returns_table_location() {
+        let namespace = DirNamespace::new("s3://bucket/graph")
+            .with_table_location("Person", "s3://bucket/staging/Person.lance/");
+
+        let mut request = DescribeTableRequest::new();
+        request.id = Some(vec!["person".to_string()]);
+        let response = namespace.describe_table(request).await.unwrap();
+        assert_eq!(
+            response.location.as_deref(),
+            Some("s3://bucket/staging/Person.lance")
+        );
+        assert_eq!(
+            namespace.table_uri("KNOWS"),
+            "s3://bucket/graph/KNOWS.lance"
+        );
+    }
+
     #[tokio::test]
     async fn describe_table_rejects_missing_identifier() {
         let namespace = DirNamespace::new("file:///tmp");
@@ -1,3 +1,7 @@
 pub mod directory;
+pub mod storage;
+pub mod tenant;
 
 pub use directory::DirNamespace;
+pub use storage::StorageOptions;
+pub use tenant::TenantNamespace;
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+/// Object store settings used when opening a dataset.
+///
+/// The options are handed to Lance as storage options, so any key understood
+/// by the underlying object store (`aws_region`, `aws_endpoint`,
+/// `google_service_account`, `azure_storage_account_name`, ...) can be set
+/// with [`StorageOptions::with_option`]. The typed helpers cover the common
+/// credential and server-side encryption keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageOptions {
+    options: HashMap<String, String>,
+}
+
+impl StorageOptions {
+    /// Create an empty set of storage options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a raw object store option.
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    /// Use static AWS credentials.
+    pub fn with_aws_credentials(
+        self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        let options = self
+            .with_option("aws_access_key_id", access_key_id)
+            .with_option("aws_secret_access_key", secret_access_key);
+        match session_token {
+            Some(token) => options.with_option("aws_session_token", token),
+            None => options,
+        }
+    }
+
+    /// Encrypt written objects with SSE-KMS using `kms_key_id`.
+    pub fn with_sse_kms(self, kms_key_id: impl Into<String>) -> Self {
+        self.with_option("aws_server_side_encryption", "aws:kms")
+            .with_option("aws_sse_kms_key_id", kms_key_id)
+    }
+
+    /// Encrypt objects with SSE-C using a base64 encoded customer key.
+    pub fn with_sse_customer_key(self, key_base64: impl Into<String>) -> Self {
+        self.with_option("aws_sse_customer_key_base64", key_base64)
+    }
+
+    /// Return the option set for `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.options.get(key).map(String::as_str)
+    }
+
+    /// Return whether no option is set.
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Layer `other` on top of these options, its values taking precedence.
+    pub fn merged(&self, other: &StorageOptions) -> StorageOptions {
+        let mut options = self.options.clone();
+        options.extend(other.options.clone());
+        StorageOptions { options }
+    }
+
+    /// Return the options as the map Lance expects.
+    pub fn as_map(&self) -> &HashMap<String, String> {
+        &self.options
+    }
+}
+
+impl From<HashMap<String, String>> for StorageOptions {
+    fn from(options: HashMap<String, String>) -> Self {
+        Self { options }
+    }
+}
+
+impl From<StorageOptions> for HashMap<String, String> {
+    fn from(options: StorageOptions) -> Self {
+        options.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_helpers_set_object_store_keys() {
+        let options = StorageOptions::new()
+            .with_aws_credentials("AKIA", "secret", Some("token".to_string()))
+            .with_sse_kms("arn:aws:kms:us-east-1:1:key/abc");
+
+        assert_eq!(options.get("aws_access_key_id"), Some("AKIA"));
+        assert_eq!(options.get("aws_session_token"), Some("token"));
+        assert_eq!(options.get("aws_server_side_encryption"), Some("aws:kms"));
+        assert_eq!(
+            options.get("aws_sse_kms_key_id"),
+            Some("arn:aws:kms:us-east-1:1:key/abc")
+        );
+    }
+
+    #[test]
+    fn merged_prefers_the_overriding_options() {
+        let defaults = StorageOptions::new()
+            .with_option("aws_region", "us-east-1")
+            .with_sse_kms("default-key");
+        let merged = defaults.merged(&StorageOptions::new().with_sse_kms("table-key"));
+
+        assert_eq!(merged.get("aws_region"), Some("us-east-1"));
+        assert_eq!(merged.get("aws_sse_kms_key_id"), Some("table-key"));
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::namespace::DirNamespace;
+use lance_namespace::{Error as NamespaceError, Result};
+use snafu::location;
+use std::collections::HashMap;
+
+/// Resolves one logical graph to per-tenant dataset directories.
+///
+/// The URI template names the tenant's base directory with a `{tenant}`
+/// placeholder (e.g. `s3://bucket/tenants/{tenant}/graph`); every tenant's
+/// datasets are laid out like a [`DirNamespace`] below it. The tenant is read
+/// from a session attribute, `tenant` unless configured otherwise.
+#[derive(Debug, Clone)]
+pub struct TenantNamespace {
+    uri_template: String,
+    attribute: String,
+}
+
+impl TenantNamespace {
+    /// Create a tenant namespace from a URI template containing `{tenant}`.
+    pub fn new(uri_template: impl Into<String>) -> Self {
+        Self {
+            uri_template: uri_template.into(),
+            attribute: "tenant".to_string(),
+        }
+    }
+
+    /// Read the tenant from the session attribute `name`.
+    ///
+    /// The template placeholder becomes `{name}`.
+    pub fn with_attribute(mut self, name: impl Into<String>) -> Self {
+        self.attribute = name.into();
+        self
+    }
+
+    /// Return the session attribute holding the tenant.
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    /// Resolve the namespace of the tenant named by `attributes`.
+    pub fn resolve(&self, attributes: &HashMap<String, String>) -> Result<DirNamespace> {
+        let tenant = attributes.get(&self.attribute).ok_or_else(|| {
+            NamespaceError::invalid_input(
+                format!(
+                    "TenantNamespace requires the session attribute '{}'",
+                    self.attribute
+                ),
+                location!(),
+            )
+        })?;
+        self.for_tenant(tenant)
+    }
+
+    /// Resolve the namespace of `tenant`.
+    ///
+    /// Tenant ids may only contain ASCII letters, digits, `-` and `_`, so a
+    /// tenant can never address another tenant's directory.
+    pub fn for_tenant(&self, tenant: &str) -> Result<DirNamespace> {
+        if tenant.is_empty()
+            || !tenant
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(NamespaceError::invalid_input(
+                format!("Invalid tenant id '{}'", tenant),
+                location!(),
+            ));
+        }
+
+        let placeholder = format!("{{{}}}", self.attribute);
+        if !self.uri_template.contains(&placeholder) {
+            return Err(NamespaceError::invalid_input(
+                format!(
+                    "Tenant URI template '{}' does not contain {}",
+                    self.uri_template, placeholder
+                ),
+                location!(),
+            ));
+        }
+        Ok(DirNamespace::new(
+            self.uri_template.replace(&placeholder, tenant),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_tenant_directory_from_attribute() {
+        let namespace = TenantNamespace::new("s3://bucket/tenants/{tenant}/graph/");
+        let attributes = HashMap::from([("tenant".to_string(), "acme".to_string())]);
+
+        let resolved = namespace.resolve(&attributes).unwrap();
+        assert_eq!(resolved.base_uri(), "s3://bucket/tenants/acme/graph");
+    }
+
+    #[test]
+    fn custom_attribute_names_the_placeholder() {
+        let namespace = TenantNamespace::new("file:///data/{org}").with_attribute("org");
+        assert_eq!(
+            namespace.for_tenant("org_42").unwrap().base_uri(),
+            "file:///data/org_42"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_attribute_and_unsafe_ids() {
+        let namespace = TenantNamespace::new("memory://{tenant}");
+
+        let err = namespace.resolve(&HashMap::new()).unwrap_err();
+        assert!(
+            err.to_string().contains("session attribute 'tenant'"),
+            "unexpected error: {err}"
+        );
+        assert!(namespace.for_tenant("../other").is_err());
+        assert!(namespace.for_tenant("").is_err());
+        assert!(TenantNamespace::new("memory://shared")
+            .for_tenant("acme")
+            .is_err());
+    }
+}
@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Editor-independent query analysis
+//!
+//! Everything the server answers (diagnostics, completion, hover, definition)
+//! is computed here from the document text and the optional graph manifest.
+//! Queries are often incomplete while being edited, so variable bindings are
+//! recovered with a lightweight scan of the text rather than from the AST.
+
+use lance_graph::lint::lint_query;
+use lance_graph::parser::parse_cypher_query_with_diagnostics;
+use lance_graph::semantic::SemanticAnalyzer;
+use lance_graph::GraphConfig;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, NumberOrString, Position,
+    Range,
+};
+
+/// Cypher keywords offered by completion
+const KEYWORDS: &[&str] = &[
+    "MATCH",
+    "WHERE",
+    "WITH",
+    "UNWIND",
+    "RETURN",
+    "DISTINCT",
+    "ORDER BY",
+    "SKIP",
+    "LIMIT",
+    "AS",
+    "AND",
+    "OR",
+    "NOT",
+    "IN",
+    "IS NULL",
+    "IS NOT NULL",
+    "STARTS WITH",
+    "ENDS WITH",
+    "CONTAINS",
+    "CASE",
+    "WHEN",
+    "THEN",
+    "ELSE",
+    "END",
+    "ASC",
+    "DESC",
+];
+
+/// Functions offered by completion
+const FUNCTIONS: &[&str] = &[
+    "count",
+    "sum",
+    "avg",
+    "min",
+    "max",
+    "collect",
+    "toLower",
+    "toUpper",
+    "vector_distance",
+    "vector_similarity",
+];
+
+/// What a query variable is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    /// `(n:Label)`; the label is `None` when the pattern has none
+    Node(Option<String>),
+    /// `[r:TYPE]`; the type is `None` when the pattern has none
+    Relationship(Option<String>),
+    /// `... AS alias` in WITH or UNWIND, with the byte offset of the alias
+    Alias(usize),
+}
+
+/// Compute diagnostics for a query document
+pub fn diagnostics(text: &str, config: Option<&GraphConfig>) -> Vec<Diagnostic> {
+    let query = match parse_cypher_query_with_diagnostics(text) {
+        Ok(query) => query,
+        Err(errors) => {
+            return errors
+                .into_iter()
+                .map(|e| Diagnostic {
+                    range: Range::new(
+                        offset_to_position(text, e.span.start),
+                        offset_to_position(text, e.span.end),
+                    ),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("lance-graph".to_string()),
+                    message: e.message,
+                    ..Default::default()
+                })
+                .collect();
+        }
+    };
+
+    let Some(config) = config else {
+        return Vec::new();
+    };
+
+    // Semantic findings are not spanned; attach them to the whole document
+    let whole = Range::new(Position::new(0, 0), offset_to_position(text, text.len()));
+    let semantic = match SemanticAnalyzer::new(config.clone()).analyze(&query) {
+        Ok(result) => result,
+        Err(e) => {
+            return vec![Diagnostic {
+                range: whole,
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("lance-graph".to_string()),
+                message: e.to_string(),
+                ..Default::default()
+            }]
+        }
+    };
+
+    let errors = semantic
+        .errors
+        .into_iter()
+        .map(|m| (m, DiagnosticSeverity::ERROR));
+    let warnings = semantic
+        .warnings
+        .into_iter()
+        .map(|m| (m, DiagnosticSeverity::WARNING));
+    let mut diagnostics: Vec<Diagnostic> = errors
+        .chain(warnings)
+        .map(|(message, severity)| Diagnostic {
+            range: whole,
+            severity: Some(severity),
+            source: Some("lance-graph".to_string()),
+            message,
+            ..Default::default()
+        })
+        .collect();
+    diagnostics.extend(lint_query(&query, config).into_iter().map(|w| Diagnostic {
+        range: whole,
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String(w.kind.code().to_string())),
+        source: Some("lance-graph-lint".to_string()),
+        message: w.message,
+        ..Default::default()
+    }));
+    diagnostics
+}
+
+/// Completion candidates at a byte offset
+pub fn completions(text: &str, offset: usize, config: Option<&GraphConfig>) -> Vec<CompletionItem> {
+    let before = &text[..offset];
+    let prefix_start = before
+        .rfind(|c: char| !is_word_char(c))
+        .map(|i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let trigger = before[..prefix_start].chars().next_back();
+
+    match trigger {
+        Some('.') => {
+            let variable = word_before(&before[..prefix_start - 1]);
+            property_completions(text, variable, config)
+        }
+        Some(':') => {
+            let in_relationship = innermost_open_bracket(&before[..prefix_start]) == Some('[');
+            let Some(config) = config else {
+                return Vec::new();
+            };
+            if in_relationship {
+                config
+                    .relationship_mappings
+                    .values()
+                    .map(|m| {
+                        item(
+                            &m.relationship_type,
+                            CompletionItemKind::CLASS,
+                            "relationship type",
+                        )
+                    })
+                    .collect()
+            } else {
+                config
+                    .node_mappings
+                    .values()
+                    .map(|m| item(&m.label, CompletionItemKind::CLASS, "node label"))
+                    .collect()
+            }
+        }
+        _ => {
+            let mut items: Vec<CompletionItem> = KEYWORDS
+                .iter()
+                .map(|k| item(k, CompletionItemKind::KEYWORD, "keyword"))
+                .chain(
+                    FUNCTIONS
+                        .iter()
+                        .map(|f| item(f, CompletionItemKind::FUNCTION, "function")),
+                )
+                .collect();
+            let mut variables: Vec<_> = bindings(text).into_keys().collect();
+            variables.sort();
+            items.extend(
+                variables
+                    .iter()
+                    .map(|v| item(v, CompletionItemKind::VARIABLE, "variable")),
+            );
+            items
+        }
+    }
+}
+
+/// Hover text for the word at a byte offset
+pub fn hover(text: &str, offset: usize, config: Option<&GraphConfig>) -> Option<String> {
+    let (start, end) = word_at(text, offset)?;
+    let word = &text[start..end];
+    let scope = bindings(text);
+
+    // Property access: `n.prop`
+    if text[..start].ends_with('.') {
+        let variable = word_before(&text[..start - 1]);
+        return Some(match scope.get(&variable.to_lowercase()) {
+            Some(Binding::Node(Some(label))) => format!("property `{}` of node `{}`", word, label),
+            Some(Binding::Relationship(Some(rel_type))) => {
+                format!("property `{}` of relationship `{}`", word, rel_type)
+            }
+            _ => format!("property `{}`", word),
+        });
+    }
+
+    if let Some(binding) = scope.get(&word.to_lowercase()) {
+        return Some(match binding {
+            Binding::Node(Some(label)) => format!("`{}`: node `{}`", word, label),
+            Binding::Node(None) => format!("`{}`: node", word),
+            Binding::Relationship(Some(rel_type)) => {
+                format!("`{}`: relationship `{}`", word, rel_type)
+            }
+            Binding::Relationship(None) => format!("`{}`: relationship", word),
+            Binding::Alias(_) => format!("`{}`: alias", word),
+        });
+    }
+
+    let config = config?;
+    if let Some(mapping) = config.get_node_mapping(word) {
+        return Some(format!(
+            "node label `{}` (id: `{}`; properties: {})",
+            mapping.label,
+            mapping.id_field,
+            list_or_none(&mapping.property_fields)
+        ));
+    }
+    if let Some(mapping) = config.get_relationship_mapping(word) {
+        return Some(format!(
+            "relationship type `{}` (`{}` -> `{}`; properties: {})",
+            mapping.relationship_type,
+            mapping.source_id_field,
+            mapping.target_id_field,
+            list_or_none(&mapping.property_fields)
+        ));
+    }
+    None
+}
+
+/// Byte range where the alias under the cursor is defined (`... AS alias`)
+pub fn definition(text: &str, offset: usize) -> Option<(usize, usize)> {
+    let (start, end) = word_at(text, offset)?;
+    let word = &text[start..end];
+    match bindings(text).get(&word.to_lowercase()) {
+        Some(Binding::Alias(def_start)) => Some((*def_start, def_start + word.len())),
+        _ => None,
+    }
+}
+
+/// Recover variable bindings from the query text
+///
+/// Recognizes `(var:Label`, `[var:TYPE` and `AS alias`; the first binding of a
+/// name wins. Keys are lowercase, matching the engine's case-insensitive variables.
+pub fn bindings(text: &str) -> HashMap<String, Binding> {
+    let mut scope = HashMap::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'\'' || c == b'"' {
+            // Skip string literals
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i += 1;
+            continue;
+        }
+        if c == b'(' || c == b'[' {
+            let var_start = skip_whitespace(text, i + 1);
+            let var_end = word_end(text, var_start);
+            if var_end > var_start {
+                let variable = text[var_start..var_end].to_lowercase();
+                let colon = skip_whitespace(text, var_end);
+                let label = if text[colon..].starts_with(':') {
+                    let label_start = skip_whitespace(text, colon + 1);
+                    let label_end = word_end(text, label_start);
+                    (label_end > label_start).then(|| text[label_start..label_end].to_string())
+                } else {
+                    None
+                };
+                let binding = if c == b'(' {
+                    Binding::Node(label)
+                } else {
+                    Binding::Relationship(label)
+                };
+                scope.entry(variable).or_insert(binding);
+            }
+        } else if c.is_ascii_alphabetic()
+            && (i == 0 || !is_word_char(bytes[i - 1] as char))
+            && text
+                .get(i..i + 2)
+                .is_some_and(|w| w.eq_ignore_ascii_case("as"))
+            && word_end(text, i) == i + 2
+        {
+            let alias_start = skip_whitespace(text, i + 2);
+            let alias_end = word_end(text, alias_start);
+            if alias_end > alias_start && alias_start > i + 2 {
+                scope
+                    .entry(text[alias_start..alias_end].to_lowercase())
+                    .or_insert(Binding::Alias(alias_start));
+            }
+        }
+        i += 1;
+    }
+    scope
+}
+
+/// Convert an LSP position (UTF-16 columns) to a byte offset
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (line_no, line) in text.split_inclusive('\n').enumerate() {
+        if line_no == position.line as usize {
+            let mut units = 0;
+            for (i, c) in line.char_indices() {
+                if units >= position.character as usize || c == '\n' {
+                    return offset + i;
+                }
+                units += c.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+    text.len()
+}
+
+/// Convert a byte offset to an LSP position (UTF-16 columns)
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+    let prefix = &text[..offset.min(text.len())];
+    let line = prefix.matches('\n').count() as u32;
+    let line_start = prefix.rfind('\n').map_or(0, |i| i + 1);
+    let character = prefix[line_start..]
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>() as u32;
+    Position::new(line, character)
+}
+
+fn property_completions(
+    text: &str,
+    variable: &str,
+    config: Option<&GraphConfig>,
+) -> Vec<CompletionItem> {
+    let Some(config) = config else {
+        return Vec::new();
+    };
+    let fields: Vec<&String> = match bindings(text).get(&variable.to_lowercase()) {
+        Some(Binding::Node(Some(label))) => match config.get_node_mapping(label) {
+            Some(m) => std::iter::once(&m.id_field)
+                .chain(m.property_fields.iter())
+                .collect(),
+            None => Vec::new(),
+        },
+        Some(Binding::Relationship(Some(rel_type))) => {
+            match config.get_relationship_mapping(rel_type) {
+                Some(m) => [&m.source_id_field, &m.target_id_field]
+                    .into_iter()
+                    .chain(m.property_fields.iter())
+                    .collect(),
+                None => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    };
+    fields
+        .into_iter()
+        .map(|f| item(f, CompletionItemKind::FIELD, "property"))
+        .collect()
+}
+
+fn item(label: &str, kind: CompletionItemKind, detail: &str) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(kind),
+        detail: Some(detail.to_string()),
+        ..Default::default()
+    }
+}
+
+fn list_or_none(fields: &[String]) -> String {
+    if fields.is_empty() {
+        "none".to_string()
+    } else {
+        fields.join(", ")
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn skip_whitespace(text: &str, from: usize) -> usize {
+    text[from..]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(text.len(), |i| from + i)
+}
+
+fn word_end(text: &str, from: usize) -> usize {
+    text[from..]
+        .find(|c: char| !is_word_char(c))
+        .map_or(text.len(), |i| from + i)
+}
+
+/// The identifier immediately preceding the end of `text`
+fn word_before(text: &str) -> &str {
+    let start = text
+        .rfind(|c: char| !is_word_char(c))
+        .map(|i| i + text[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    &text[start..]
+}
+
+/// Byte range of the identifier touching `offset`
+fn word_at(text: &str, offset: usize) -> Option<(usize, usize)> {
+    let offset = offset.min(text.len());
+    let start = offset - word_before(&text[..offset]).len();
+    let end = word_end(text, offset);
+    (end > start).then_some((start, end))
+}
+
+/// The innermost bracket still open at the end of `text`
+fn innermost_open_bracket(text: &str) -> Option<char> {
+    let mut stack = Vec::new();
+    for c in text.chars() {
+        match c {
+            '(' | '[' | '{' => stack.push(c),
+            ')' | ']' | '}' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    stack.pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lance_graph::{NodeMapping, RelationshipMapping};
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("Person", "id")
+                    .with_properties(vec!["name".to_string(), "age".to_string()]),
+            )
+            .with_relationship_mapping(
+                RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                    .with_properties(vec!["since".to_string()]),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diagnostics_for_syntax_errors() {
+        let text = "MATCH (n:Person WHERE n.age > RETURN n.name";
+        let diagnostics = diagnostics(text, Some(&config()));
+        assert!(diagnostics.len() >= 2, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity == Some(DiagnosticSeverity::ERROR)));
+    }
+
+    #[test]
+    fn test_diagnostics_for_unknown_label() {
+        let text = "MATCH (n:Robot) RETURN n.name";
+        let diagnostics = diagnostics(text, Some(&config()));
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.severity == Some(DiagnosticSeverity::WARNING)));
+    }
+
+    #[test]
+    fn test_diagnostics_include_lint_findings() {
+        let text = "MATCH (a:Person), (b:Person) RETURN a.name, b.name";
+        let diagnostics = diagnostics(text, Some(&config()));
+        assert!(diagnostics.iter().any(|d| {
+            d.severity == Some(DiagnosticSeverity::INFORMATION)
+                && d.code == Some(NumberOrString::String("cartesian-product".to_string()))
+        }));
+    }
+
+    #[test]
+    fn test_complete_labels_and_relationship_types() {
+        let cfg = config();
+        let text = "MATCH (n:P";
+        assert_eq!(
+            labels(&completions(text, text.len(), Some(&cfg))),
+            vec!["Person"]
+        );
+
+        let text = "MATCH (n:Person)-[r:";
+        assert_eq!(
+            labels(&completions(text, text.len(), Some(&cfg))),
+            vec!["KNOWS"]
+        );
+    }
+
+    #[test]
+    fn test_complete_properties() {
+        let cfg = config();
+        let text = "MATCH (n:Person)-[r:KNOWS]->(m) RETURN n.";
+        assert_eq!(
+            labels(&completions(text, text.len(), Some(&cfg))),
+            vec!["id", "name", "age"]
+        );
+
+        let text = "MATCH (n:Person)-[r:KNOWS]->(m) RETURN r.si";
+        assert_eq!(
+            labels(&completions(text, text.len(), Some(&cfg))),
+            vec!["src_id", "dst_id", "since"]
+        );
+    }
+
+    #[test]
+    fn test_complete_keywords_functions_and_variables() {
+        let text = "MATCH (person:Person) RETURN ";
+        let items = completions(text, text.len(), None);
+        let names = labels(&items);
+        assert!(names.contains(&"count"));
+        assert!(names.contains(&"LIMIT"));
+        assert!(names.contains(&"person"));
+    }
+
+    #[test]
+    fn test_hover() {
+        let cfg = config();
+        let text = "MATCH (n:Person)-[r:KNOWS]->(m) RETURN n.name";
+        let offset = text.rfind("name").unwrap() + 1;
+        assert_eq!(
+            hover(text, offset, Some(&cfg)).unwrap(),
+            "property `name` of node `Person`"
+        );
+        assert_eq!(
+            hover(text, text.find("r:").unwrap(), Some(&cfg)).unwrap(),
+            "`r`: relationship `KNOWS`"
+        );
+        assert!(hover(text, text.find("Person").unwrap(), Some(&cfg))
+            .unwrap()
+            .starts_with("node label `Person`"));
+    }
+
+    #[test]
+    fn test_definition_of_with_alias() {
+        let text = "MATCH (n:Person) WITH n.age AS years RETURN years";
+        let use_offset = text.rfind("years").unwrap();
+        let (start, end) = definition(text, use_offset).unwrap();
+        assert_eq!(&text[start..end], "years");
+        assert_eq!(start, text.find("years").unwrap());
+        assert!(definition(text, text.find("n:").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_position_offset_round_trip() {
+        let text = "MATCH (n)\nRETURN n.näme";
+        let offset = text.find("me").unwrap();
+        let position = offset_to_position(text, offset);
+        assert_eq!(position, Position::new(1, 11));
+        assert_eq!(position_to_offset(text, position), offset);
+    }
+}
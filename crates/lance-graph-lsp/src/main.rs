@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Language server for lance-graph Cypher queries
+//!
+//! Speaks LSP over stdio. The graph manifest (a `GraphConfig` in JSON) is taken
+//! from the `manifest` initialization option, or from `lance-graph.json` at the
+//! workspace root, and powers schema-aware completion, hover and diagnostics.
+
+mod analysis;
+
+use lance_graph::GraphConfig;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+/// Manifest file looked up at the workspace root
+const DEFAULT_MANIFEST: &str = "lance-graph.json";
+
+struct Backend {
+    client: Client,
+    documents: RwLock<HashMap<Url, String>>,
+    config: RwLock<Option<GraphConfig>>,
+}
+
+impl Backend {
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            documents: RwLock::new(HashMap::new()),
+            config: RwLock::new(None),
+        }
+    }
+
+    fn document(&self, uri: &Url) -> Option<String> {
+        self.documents.read().unwrap().get(uri).cloned()
+    }
+
+    async fn publish(&self, uri: Url, text: &str) {
+        let diagnostics = analysis::diagnostics(text, self.config.read().unwrap().as_ref());
+        self.client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
+    }
+}
+
+/// Load a graph manifest from a JSON file
+fn load_manifest(path: &Path) -> std::result::Result<GraphConfig, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))
+}
+
+/// Resolve the manifest path from initialization options or the workspace root
+fn manifest_path(params: &InitializeParams) -> Option<PathBuf> {
+    let root = params
+        .root_uri
+        .as_ref()
+        .and_then(|uri| uri.to_file_path().ok());
+    if let Some(path) = params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("manifest"))
+        .and_then(|value| value.as_str())
+    {
+        let path = PathBuf::from(path);
+        return Some(match (&root, path.is_relative()) {
+            (Some(root), true) => root.join(path),
+            _ => path,
+        });
+    }
+    root.map(|root| root.join(DEFAULT_MANIFEST))
+        .filter(|path| path.exists())
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        if let Some(path) = manifest_path(&params) {
+            match load_manifest(&path) {
+                Ok(config) => {
+                    *self.config.write().unwrap() = Some(config);
+                }
+                Err(message) => {
+                    self.client.log_message(MessageType::WARNING, message).await;
+                }
+            }
+        }
+
+        Ok(InitializeResult {
+            server_info: Some(ServerInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![":".to_string(), ".".to_string()]),
+                    ..Default::default()
+                }),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let text = params.text_document.text;
+        self.documents
+            .write()
+            .unwrap()
+            .insert(uri.clone(), text.clone());
+        self.publish(uri, &text).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // Full sync: the last change carries the whole document
+        let Some(change) = params.content_changes.into_iter().last() else {
+            return;
+        };
+        let uri = params.text_document.uri;
+        self.documents
+            .write()
+            .unwrap()
+            .insert(uri.clone(), change.text.clone());
+        self.publish(uri, &change.text).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.write().unwrap().remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let Some(text) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let offset = analysis::position_to_offset(&text, position.position);
+        let items = analysis::completions(&text, offset, self.config.read().unwrap().as_ref());
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let Some(text) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let offset = analysis::position_to_offset(&text, position.position);
+        let contents = analysis::hover(&text, offset, self.config.read().unwrap().as_ref());
+        Ok(contents.map(|value| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        }))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let Some(text) = self.document(&uri) else {
+            return Ok(None);
+        };
+        let offset = analysis::position_to_offset(&text, position.position);
+        Ok(analysis::definition(&text, offset).map(|(start, end)| {
+            GotoDefinitionResponse::Scalar(Location::new(
+                uri,
+                Range::new(
+                    analysis::offset_to_position(&text, start),
+                    analysis::offset_to_position(&text, end),
+                ),
+            ))
+        }))
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let stdin = tokio::io::stdin();
+    let stdout = tokio::io::stdout();
+    let (service, socket) = LspService::new(Backend::new);
+    Server::new(stdin, stdout, socket).serve(service).await;
+}
@@ -366,6 +366,30 @@ impl GraphConfigBuilder {
         }
     }
 
+    /// Add a view label defined as a filtered subset of another label
+    ///
+    /// Parameters
+    /// ----------
+    /// label : str
+    ///     The view label (e.g., "ActiveUser")
+    /// base_label : str
+    ///     The label whose dataset backs the view (e.g., "User")
+    /// filter : str
+    ///     Boolean expression over the base dataset's columns (e.g., "active = true")
+    ///
+    /// Returns
+    /// -------
+    /// GraphConfigBuilder
+    ///     A new builder with the view label applied
+    fn with_view_label(&self, label: &str, base_label: &str, filter: &str) -> Self {
+        Self {
+            inner: self
+                .inner
+                .clone()
+                .with_view_label(label, base_label, filter),
+        }
+    }
+
     /// Add a relationship mapping
     ///
     /// Parameters
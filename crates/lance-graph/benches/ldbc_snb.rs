@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! LDBC Social Network Benchmark query mixes
+//!
+//! Loads an LDBC SNB social network into Lance datasets and runs interactive
+//! (short/complex read) and BI style query mixes against it:
+//! - Reads the CsvBasic (`|`-delimited) output of the LDBC Datagen from
+//!   `$LDBC_SNB_DATA_DIR` (the directory containing `dynamic/` and `static/`)
+//! - Without that variable, generates a synthetic network with the same
+//!   schema; `$LDBC_SNB_PERSONS` sets its size (default 1,000 persons)
+//! - Round-trips every table through a Lance dataset before querying
+//!
+//! Run with:
+//! ```
+//! LDBC_SNB_DATA_DIR=/data/social_network-sf0.1 cargo bench --bench ldbc_snb
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::compute::concat_batches;
+use arrow::csv::ReaderBuilder;
+use arrow_array::{Int64Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use futures::TryStreamExt;
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance_graph::{CypherQuery, GraphConfig};
+use tempfile::TempDir;
+
+/// A loaded table: graph name, file stem in the LDBC output, schema
+struct TableSpec {
+    name: &'static str,
+    dir: &'static str,
+    file: &'static str,
+    fields: &'static [(&'static str, DataType)],
+}
+
+const TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "Person",
+        dir: "dynamic",
+        file: "person",
+        fields: &[
+            ("id", DataType::Int64),
+            ("firstName", DataType::Utf8),
+            ("lastName", DataType::Utf8),
+            ("gender", DataType::Utf8),
+            ("birthday", DataType::Utf8),
+            ("creationDate", DataType::Utf8),
+            ("locationIP", DataType::Utf8),
+            ("browserUsed", DataType::Utf8),
+        ],
+    },
+    TableSpec {
+        name: "Post",
+        dir: "dynamic",
+        file: "post",
+        fields: &[
+            ("id", DataType::Int64),
+            ("imageFile", DataType::Utf8),
+            ("creationDate", DataType::Utf8),
+            ("locationIP", DataType::Utf8),
+            ("browserUsed", DataType::Utf8),
+            ("language", DataType::Utf8),
+            ("content", DataType::Utf8),
+            ("length", DataType::Int64),
+        ],
+    },
+    TableSpec {
+        name: "Tag",
+        dir: "static",
+        file: "tag",
+        fields: &[
+            ("id", DataType::Int64),
+            ("name", DataType::Utf8),
+            ("url", DataType::Utf8),
+        ],
+    },
+    TableSpec {
+        name: "KNOWS",
+        dir: "dynamic",
+        file: "person_knows_person",
+        fields: &[
+            ("src_id", DataType::Int64),
+            ("dst_id", DataType::Int64),
+            ("creationDate", DataType::Utf8),
+        ],
+    },
+    TableSpec {
+        name: "HAS_CREATOR",
+        dir: "dynamic",
+        file: "post_hasCreator_person",
+        fields: &[("src_id", DataType::Int64), ("dst_id", DataType::Int64)],
+    },
+    TableSpec {
+        name: "HAS_TAG",
+        dir: "dynamic",
+        file: "post_hasTag_tag",
+        fields: &[("src_id", DataType::Int64), ("dst_id", DataType::Int64)],
+    },
+    TableSpec {
+        name: "LIKES",
+        dir: "dynamic",
+        file: "person_likes_post",
+        fields: &[
+            ("src_id", DataType::Int64),
+            ("dst_id", DataType::Int64),
+            ("creationDate", DataType::Utf8),
+        ],
+    },
+];
+
+/// Interactive short and complex reads, parameterized by `$personId`
+const INTERACTIVE_QUERIES: &[(&str, &str)] = &[
+    (
+        "is1_profile",
+        "MATCH (p:Person) WHERE p.id = $personId \
+         RETURN p.firstName, p.lastName, p.birthday, p.browserUsed",
+    ),
+    (
+        "is2_recent_posts",
+        "MATCH (m:Post)-[:HAS_CREATOR]->(p:Person) WHERE p.id = $personId \
+         RETURN m.id, m.content, m.creationDate ORDER BY m.creationDate DESC LIMIT 10",
+    ),
+    (
+        "is3_friends",
+        "MATCH (p:Person)-[k:KNOWS]->(f:Person) WHERE p.id = $personId \
+         RETURN f.id, f.firstName, f.lastName, k.creationDate ORDER BY k.creationDate DESC",
+    ),
+    (
+        "ic2_friends_recent_posts",
+        "MATCH (p:Person)-[:KNOWS]->(f:Person)<-[:HAS_CREATOR]-(m:Post) WHERE p.id = $personId \
+         RETURN f.id, f.firstName, m.id, m.content ORDER BY m.creationDate DESC LIMIT 20",
+    ),
+    (
+        "ic9_friends_of_friends",
+        "MATCH (p:Person)-[:KNOWS*1..2]->(f:Person) WHERE p.id = $personId \
+         RETURN DISTINCT f.id, f.firstName LIMIT 20",
+    ),
+];
+
+/// Analytical queries over the whole network
+const BI_QUERIES: &[(&str, &str)] = &[
+    (
+        "bi_tag_popularity",
+        "MATCH (m:Post)-[:HAS_TAG]->(t:Tag) \
+         RETURN t.name, count(m) AS posts ORDER BY posts DESC LIMIT 10",
+    ),
+    (
+        "bi_top_creators",
+        "MATCH (m:Post)-[:HAS_CREATOR]->(p:Person) \
+         RETURN p.id, p.firstName, count(m) AS posts ORDER BY posts DESC LIMIT 100",
+    ),
+    (
+        "bi_likes_by_language",
+        "MATCH (p:Person)-[:LIKES]->(m:Post) \
+         RETURN m.language, count(p) AS likes ORDER BY likes DESC",
+    ),
+    (
+        "bi_friend_tag_reach",
+        "MATCH (p:Person)-[:KNOWS]->(f:Person)-[:LIKES]->(m:Post)-[:HAS_TAG]->(t:Tag) \
+         RETURN t.name, count(DISTINCT p.id) AS reach ORDER BY reach DESC LIMIT 10",
+    ),
+];
+
+fn schema(spec: &TableSpec) -> SchemaRef {
+    Arc::new(ArrowSchema::new(
+        spec.fields
+            .iter()
+            .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn make_config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_node_label("Post", "id")
+        .with_node_label("Tag", "id")
+        .with_relationship("KNOWS", "src_id", "dst_id")
+        .with_relationship("HAS_CREATOR", "src_id", "dst_id")
+        .with_relationship("HAS_TAG", "src_id", "dst_id")
+        .with_relationship("LIKES", "src_id", "dst_id")
+        .build()
+        .unwrap()
+}
+
+/// Read every `<file>_*.csv` part of one table from the Datagen output
+fn load_csv_table(root: &Path, spec: &TableSpec) -> RecordBatch {
+    let schema = schema(spec);
+    let dir = root.join(spec.dir);
+    let prefix = format!("{}_", spec.file);
+    let mut parts: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            file_name.starts_with(&prefix)
+                // Skip longer table names sharing the prefix (person_knows_person_...)
+                && file_name[prefix.len()..].starts_with(|c: char| c.is_ascii_digit())
+                && file_name.ends_with(".csv")
+        })
+        .collect();
+    parts.sort();
+
+    let mut batches = Vec::new();
+    for part in parts {
+        let reader = ReaderBuilder::new(schema.clone())
+            .with_header(true)
+            .with_delimiter(b'|')
+            .build(std::fs::File::open(&part).unwrap())
+            .unwrap();
+        for batch in reader {
+            batches.push(batch.unwrap());
+        }
+    }
+    concat_batches(&schema, &batches).unwrap()
+}
+
+fn int_column(values: Vec<i64>) -> Arc<Int64Array> {
+    Arc::new(Int64Array::from(values))
+}
+
+fn string_column(values: Vec<String>) -> Arc<StringArray> {
+    Arc::new(StringArray::from(values))
+}
+
+fn date(i: usize) -> String {
+    format!("2011-{:02}-{:02}T10:00:00.000+0000", i % 12 + 1, i % 28 + 1)
+}
+
+/// Deterministic network with the LDBC schema and roughly its fan-outs
+fn generate_tables(persons: usize) -> HashMap<String, RecordBatch> {
+    const TAGS: usize = 100;
+    const POSTS_PER_PERSON: usize = 5;
+    const FRIENDS_PER_PERSON: usize = 10;
+    const LANGUAGES: &[&str] = &["en", "de", "es", "zh", "pt"];
+    let posts = persons * POSTS_PER_PERSON;
+    let spec = |name: &str| TABLES.iter().find(|t| t.name == name).unwrap();
+    let mut tables = HashMap::new();
+
+    let ids: Vec<i64> = (0..persons as i64).collect();
+    tables.insert(
+        "Person".to_string(),
+        RecordBatch::try_new(
+            schema(spec("Person")),
+            vec![
+                int_column(ids.clone()),
+                string_column(ids.iter().map(|i| format!("First{}", i)).collect()),
+                string_column(ids.iter().map(|i| format!("Last{}", i % 97)).collect()),
+                string_column(
+                    ids.iter()
+                        .map(|i| if i % 2 == 0 { "female" } else { "male" }.to_string())
+                        .collect(),
+                ),
+                string_column(
+                    (0..persons)
+                        .map(|i| format!("19{}-01-01", 70 + i % 30))
+                        .collect(),
+                ),
+                string_column((0..persons).map(date).collect()),
+                string_column(
+                    (0..persons)
+                        .map(|i| format!("10.0.{}.{}", i / 256 % 256, i % 256))
+                        .collect(),
+                ),
+                string_column(vec!["Firefox".to_string(); persons]),
+            ],
+        )
+        .unwrap(),
+    );
+
+    tables.insert(
+        "Post".to_string(),
+        RecordBatch::try_new(
+            schema(spec("Post")),
+            vec![
+                int_column((0..posts as i64).collect()),
+                string_column(vec![String::new(); posts]),
+                string_column((0..posts).map(date).collect()),
+                string_column(vec!["10.0.0.1".to_string(); posts]),
+                string_column(vec!["Chrome".to_string(); posts]),
+                string_column(
+                    (0..posts)
+                        .map(|i| LANGUAGES[i % LANGUAGES.len()].to_string())
+                        .collect(),
+                ),
+                string_column((0..posts).map(|i| format!("Post content {}", i)).collect()),
+                int_column((0..posts as i64).map(|i| 20 + i % 200).collect()),
+            ],
+        )
+        .unwrap(),
+    );
+
+    tables.insert(
+        "Tag".to_string(),
+        RecordBatch::try_new(
+            schema(spec("Tag")),
+            vec![
+                int_column((0..TAGS as i64).collect()),
+                string_column((0..TAGS).map(|i| format!("Tag{}", i)).collect()),
+                string_column(
+                    (0..TAGS)
+                        .map(|i| format!("http://dbpedia.org/resource/Tag{}", i))
+                        .collect(),
+                ),
+            ],
+        )
+        .unwrap(),
+    );
+
+    // Skewed friendships: low ids are "popular", as in the Datagen degree distribution
+    let (knows_src, knows_dst): (Vec<i64>, Vec<i64>) = (0..persons)
+        .flat_map(|p| {
+            (1..=FRIENDS_PER_PERSON)
+                .map(move |k| (p as i64, ((p * k * 7 + k * k) % persons) as i64))
+        })
+        .filter(|(src, dst)| src != dst)
+        .unzip();
+    let knows_len = knows_src.len();
+    tables.insert(
+        "KNOWS".to_string(),
+        RecordBatch::try_new(
+            schema(spec("KNOWS")),
+            vec![
+                int_column(knows_src),
+                int_column(knows_dst),
+                string_column((0..knows_len).map(date).collect()),
+            ],
+        )
+        .unwrap(),
+    );
+
+    tables.insert(
+        "HAS_CREATOR".to_string(),
+        RecordBatch::try_new(
+            schema(spec("HAS_CREATOR")),
+            vec![
+                int_column((0..posts as i64).collect()),
+                int_column(
+                    (0..posts as i64)
+                        .map(|m| m / POSTS_PER_PERSON as i64)
+                        .collect(),
+                ),
+            ],
+        )
+        .unwrap(),
+    );
+
+    tables.insert(
+        "HAS_TAG".to_string(),
+        RecordBatch::try_new(
+            schema(spec("HAS_TAG")),
+            vec![
+                int_column((0..posts as i64).flat_map(|m| [m, m]).collect()),
+                int_column(
+                    (0..posts as i64)
+                        .flat_map(|m| [m % TAGS as i64, (m * 31 + 7) % TAGS as i64])
+                        .collect(),
+                ),
+            ],
+        )
+        .unwrap(),
+    );
+
+    let likes = persons * 3;
+    tables.insert(
+        "LIKES".to_string(),
+        RecordBatch::try_new(
+            schema(spec("LIKES")),
+            vec![
+                int_column((0..likes as i64).map(|i| i % persons as i64).collect()),
+                int_column(
+                    (0..likes as i64)
+                        .map(|i| (i * 13 + 5) % posts as i64)
+                        .collect(),
+                ),
+                string_column((0..likes).map(date).collect()),
+            ],
+        )
+        .unwrap(),
+    );
+
+    tables
+}
+
+/// Write each table to a Lance dataset and read it back
+fn load_into_lance(
+    rt: &tokio::runtime::Runtime,
+    tables: HashMap<String, RecordBatch>,
+) -> (TempDir, HashMap<String, RecordBatch>) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let datasets = rt.block_on(async {
+        let mut datasets = HashMap::new();
+        for (name, batch) in tables {
+            let path = tmpdir.path().join(format!("{}.lance", name.to_lowercase()));
+            let path = path.to_str().unwrap();
+            Dataset::write(
+                RecordBatchIterator::new(vec![Ok(batch.clone())].into_iter(), batch.schema()),
+                path,
+                Some(WriteParams {
+                    mode: WriteMode::Create,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+
+            let dataset = Dataset::open(path).await.unwrap();
+            let batches = dataset
+                .scan()
+                .try_into_stream()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            datasets.insert(name, concat_batches(&batch.schema(), &batches).unwrap());
+        }
+        datasets
+    });
+    (tmpdir, datasets)
+}
+
+fn bench_query_mix(
+    c: &mut Criterion,
+    group_name: &str,
+    queries: &[(&str, &str)],
+    rt: &tokio::runtime::Runtime,
+    datasets: &HashMap<String, RecordBatch>,
+    person_id: i64,
+) {
+    let mut group = c.benchmark_group(group_name);
+    for (name, cypher) in queries {
+        let query = CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(make_config())
+            .with_parameter("personId", person_id);
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let out = rt.block_on(query.execute(datasets.clone(), None)).unwrap();
+                black_box(out.num_rows());
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_ldbc_snb(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
+    let tables = match std::env::var_os("LDBC_SNB_DATA_DIR") {
+        Some(dir) => TABLES
+            .iter()
+            .map(|spec| (spec.name.to_string(), load_csv_table(Path::new(&dir), spec)))
+            .collect(),
+        None => {
+            let persons = std::env::var("LDBC_SNB_PERSONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_000);
+            generate_tables(persons)
+        }
+    };
+    let (_tmpdir, datasets) = load_into_lance(&rt, tables);
+
+    // Parameterize the interactive reads with the best-connected person
+    let knows = &datasets["KNOWS"];
+    let sources = knows
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    let mut degrees: HashMap<i64, usize> = HashMap::new();
+    for src in sources.iter().flatten() {
+        *degrees.entry(src).or_default() += 1;
+    }
+    let person_id = degrees
+        .into_iter()
+        .max_by_key(|(id, degree)| (*degree, -*id))
+        .map(|(id, _)| id)
+        .unwrap_or(0);
+
+    bench_query_mix(
+        c,
+        "ldbc_interactive",
+        INTERACTIVE_QUERIES,
+        &rt,
+        &datasets,
+        person_id,
+    );
+    bench_query_mix(c, "ldbc_bi", BI_QUERIES, &rt, &datasets, person_id);
+}
+
+criterion_group!(benches, bench_ldbc_snb);
+criterion_main!(benches);
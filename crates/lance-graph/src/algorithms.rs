@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Iterative graph algorithms over a [`GraphProjection`]
+//!
+//! Algorithms accept an optional [`IterationCheckpoint`]: state is saved to it
+//! as the run progresses, and a run started with a checkpoint holding earlier
+//! progress resumes from the last completed iteration.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::algorithms::{pagerank, PageRankOptions};
+//!
+//! let options = PageRankOptions::default().max_iterations(50).tolerance(1e-8);
+//! let result = pagerank(&projection, &options, None).await?;
+//! let ranks = result.to_record_batch(&projection)?;
+//! ```
+
+use crate::checkpoint::IterationCheckpoint;
+use crate::error::{GraphError, Result};
+use crate::graph_projection::GraphProjection;
+use arrow::array::{Array, Float64Array, Int64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+/// Parameters of [`pagerank`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRankOptions {
+    damping: f64,
+    max_iterations: u64,
+    tolerance: f64,
+}
+
+impl Default for PageRankOptions {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            max_iterations: 20,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl PageRankOptions {
+    /// Probability of following an edge rather than jumping to a random node
+    pub fn damping(mut self, damping: f64) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: u64) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Stop once the ranks change by less than this in total (L1 norm)
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+/// Ranks computed by [`pagerank`], indexed like the projection's nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRankResult {
+    pub ranks: Vec<f64>,
+    /// Iterations completed, including those of a resumed run
+    pub iterations: u64,
+    /// Whether the ranks changed by less than the tolerance in the last iteration
+    pub converged: bool,
+    /// Iteration the run resumed from, if a checkpoint held earlier progress
+    pub resumed_from: Option<u64>,
+}
+
+impl PageRankResult {
+    /// Ranks as an `id` / `rank` batch
+    pub fn to_record_batch(&self, projection: &GraphProjection) -> Result<RecordBatch> {
+        rank_batch(projection, &self.ranks)
+    }
+}
+
+/// Weighted PageRank of every node in `projection`
+///
+/// Rank flows along projected edges in proportion to their weights; nodes
+/// without outgoing weight spread their rank evenly over all nodes.
+pub async fn pagerank(
+    projection: &GraphProjection,
+    options: &PageRankOptions,
+    checkpoint: Option<&IterationCheckpoint>,
+) -> Result<PageRankResult> {
+    if !(0.0..=1.0).contains(&options.damping) {
+        return Err(GraphError::ConfigError {
+            message: format!(
+                "PageRank damping must be in [0, 1], got {}",
+                options.damping
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    let n = projection.node_count();
+    if n == 0 {
+        return Ok(PageRankResult {
+            ranks: Vec::new(),
+            iterations: 0,
+            converged: true,
+            resumed_from: None,
+        });
+    }
+
+    let mut ranks = vec![1.0 / n as f64; n];
+    let mut iteration = 0;
+    let mut resumed_from = None;
+    if let Some(checkpoint) = checkpoint {
+        if let Some((saved, state)) = checkpoint.load_latest().await? {
+            ranks = restore_ranks(projection, &state)?;
+            iteration = saved;
+            resumed_from = Some(saved);
+        }
+    }
+
+    let out_weight: Vec<f64> = (0..n)
+        .map(|node| projection.neighbors(node).map(|(_, w)| w).sum())
+        .collect();
+    let mut converged = false;
+    while iteration < options.max_iterations && !converged {
+        let dangling: f64 = (0..n)
+            .filter(|&node| out_weight[node] <= 0.0)
+            .map(|node| ranks[node])
+            .sum();
+        let base = (1.0 - options.damping) / n as f64 + options.damping * dangling / n as f64;
+        let mut next = vec![base; n];
+        for node in 0..n {
+            if out_weight[node] <= 0.0 {
+                continue;
+            }
+            let share = options.damping * ranks[node] / out_weight[node];
+            for (neighbor, weight) in projection.neighbors(node) {
+                next[neighbor] += share * weight;
+            }
+        }
+
+        let delta: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+        ranks = next;
+        iteration += 1;
+        converged = delta < options.tolerance;
+
+        if let Some(checkpoint) = checkpoint {
+            let last = converged || iteration == options.max_iterations;
+            if last || checkpoint.should_save(iteration) {
+                checkpoint
+                    .save(iteration, &rank_batch(projection, &ranks)?)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(PageRankResult {
+        ranks,
+        iterations: iteration,
+        converged,
+        resumed_from,
+    })
+}
+
+fn rank_batch(projection: &GraphProjection, ranks: &[f64]) -> Result<RecordBatch> {
+    let ids: Vec<i64> = (0..ranks.len())
+        .filter_map(|node| projection.node_id(node))
+        .collect();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("rank", DataType::Float64, false),
+    ]));
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(ids)),
+            Arc::new(Float64Array::from(ranks.to_vec())),
+        ],
+    )?)
+}
+
+/// Ranks of a checkpointed `id` / `rank` state, indexed like `projection`
+fn restore_ranks(projection: &GraphProjection, state: &RecordBatch) -> Result<Vec<f64>> {
+    let mismatch = |reason: &str| GraphError::ExecutionError {
+        message: format!(
+            "PageRank checkpoint does not match the projection: {}",
+            reason
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    };
+    let ids = state
+        .column_by_name("id")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+        .ok_or_else(|| mismatch("no Int64 'id' column"))?;
+    let saved = state
+        .column_by_name("rank")
+        .and_then(|c| c.as_any().downcast_ref::<Float64Array>())
+        .ok_or_else(|| mismatch("no Float64 'rank' column"))?;
+    if state.num_rows() != projection.node_count() {
+        return Err(mismatch("different number of nodes"));
+    }
+
+    let mut ranks = vec![f64::NAN; projection.node_count()];
+    for row in 0..state.num_rows() {
+        let node = projection
+            .node_index(ids.value(row))
+            .ok_or_else(|| mismatch(&format!("unknown node {}", ids.value(row))))?;
+        ranks[node] = saved.value(row);
+    }
+    if ranks.iter().any(|r| r.is_nan()) {
+        return Err(mismatch("missing nodes"));
+    }
+    Ok(ranks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GraphConfig;
+    use crate::graph_projection::ProjectionOptions;
+
+    fn projection() -> GraphProjection {
+        let config = GraphConfig::builder()
+            .with_node_label("Page", "id")
+            .with_relationship("LINKS", "src", "dst")
+            .build()
+            .unwrap();
+        let nodes = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4]))],
+        )
+        .unwrap();
+        // 1 -> 2, 1 -> 3, 2 -> 3, 3 -> 1; 4 has no outgoing links
+        let links = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("src", DataType::Int64, false),
+                Field::new("dst", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 1, 2, 3])),
+                Arc::new(Int64Array::from(vec![2, 3, 3, 1])),
+            ],
+        )
+        .unwrap();
+        let options = ProjectionOptions::new("Page", "LINKS");
+        GraphProjection::from_batches(&config, &options, &nodes, &links).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pagerank_ranks_sum_to_one() {
+        let projection = projection();
+        let result = pagerank(&projection, &PageRankOptions::default(), None)
+            .await
+            .unwrap();
+
+        assert!((result.ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        let rank = |id: i64| result.ranks[projection.node_index(id).unwrap()];
+        assert!(rank(3) > rank(2));
+        assert!(rank(2) > rank(4));
+        assert_eq!(result.resumed_from, None);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_run_resumes_from_checkpoint() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri = tmp_dir.path().join("pagerank.lance");
+        let checkpoint = IterationCheckpoint::new(uri.to_str().unwrap()).with_interval(2);
+        let projection = projection();
+        let options = PageRankOptions::default().tolerance(0.0);
+
+        // A run stopped after 3 iterations saves its final state
+        let partial = pagerank(&projection, &options.max_iterations(3), Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(partial.iterations, 3);
+
+        let resumed = pagerank(&projection, &options.max_iterations(10), Some(&checkpoint))
+            .await
+            .unwrap();
+        let uninterrupted = pagerank(&projection, &options.max_iterations(10), None)
+            .await
+            .unwrap();
+        assert_eq!(resumed.resumed_from, Some(3));
+        assert_eq!(resumed.iterations, 10);
+        assert_eq!(resumed.ranks, uninterrupted.ranks);
+
+        let (saved, _) = checkpoint.load_latest().await.unwrap().unwrap();
+        assert_eq!(saved, 10);
+    }
+}
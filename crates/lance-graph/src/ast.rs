@@ -31,6 +31,37 @@ pub struct CypherQuery {
     pub order_by: Option<OrderByClause>,
     /// SKIP/OFFSET clause (optional)
     pub skip: Option<u64>,
+    /// `INCLUDE DELETED` option: keep soft-deleted rows in scans
+    pub include_deleted: bool,
+    /// SAMPLE clause (optional) - random subset of the matched rows, taken before RETURN
+    pub sample: Option<SampleMethod>,
+    /// Standalone procedure call (e.g. `CALL graph.summary()`); the query has
+    /// no other clauses when set
+    pub procedure: Option<ProcedureCall>,
+    /// CREATE clause (optional): the query writes its patterns, once per row
+    /// matched by the reading clauses, instead of returning rows
+    #[serde(default)]
+    pub create_clause: Option<CreateClause>,
+    /// MERGE clause (optional): like CREATE, but elements whose key already
+    /// exists are matched instead of created
+    #[serde(default)]
+    pub merge_clause: Option<MergeClause>,
+    /// SET clause (optional): the query updates the nodes matched by the
+    /// reading clauses instead of returning rows
+    #[serde(default)]
+    pub set_clause: Option<SetClause>,
+    /// REMOVE clause (optional): the query removes properties and labels of
+    /// the nodes matched by the reading clauses instead of returning rows
+    #[serde(default)]
+    pub remove_clause: Option<RemoveClause>,
+    /// DELETE clause (optional): the query deletes the elements matched by
+    /// the reading clauses instead of returning rows
+    #[serde(default)]
+    pub delete_clause: Option<DeleteClause>,
+    /// `CALL { ... }` subquery (optional) between the reading clauses and
+    /// RETURN: its rows are joined to each row matched so far
+    #[serde(default)]
+    pub call_subquery: Option<CallSubquery>,
 }
 
 impl CypherQuery {
@@ -83,6 +114,73 @@ impl CypherQuery {
         types
     }
 
+    /// Call `f` on the label list of every node pattern (`true`) and the type
+    /// list of every relationship pattern (`false`) in MATCH, CREATE and MERGE
+    /// clauses and `EXISTS { ... }` and `COUNT { ... }` subqueries, and on the
+    /// labels of SET and REMOVE label items
+    pub(crate) fn try_for_each_pattern_names_mut<E>(
+        &mut self,
+        mut f: impl FnMut(&mut Vec<String>, bool) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        let clauses = self
+            .reading_clauses
+            .iter_mut()
+            .chain(self.post_with_reading_clauses.iter_mut());
+        for clause in clauses {
+            let ReadingClause::Match(match_clause) = clause else {
+                continue;
+            };
+            for pattern in &mut match_clause.patterns {
+                pattern.try_for_each_name_mut(&mut f)?;
+            }
+            if let Some(where_clause) = &mut match_clause.where_clause {
+                where_clause
+                    .expression
+                    .try_for_each_subquery_pattern_mut(&mut |p| p.try_for_each_name_mut(&mut f))?;
+            }
+        }
+        for where_clause in [&mut self.where_clause, &mut self.post_with_where_clause]
+            .into_iter()
+            .flatten()
+        {
+            where_clause
+                .expression
+                .try_for_each_subquery_pattern_mut(&mut |p| p.try_for_each_name_mut(&mut f))?;
+        }
+        let values =
+            self.return_clause
+                .items
+                .iter_mut()
+                .map(|item| &mut item.expression)
+                .chain(self.order_by.iter_mut().flat_map(|order_by| {
+                    order_by.items.iter_mut().map(|item| &mut item.expression)
+                }));
+        for value in values {
+            value.try_for_each_subquery_pattern_mut(&mut |p| p.try_for_each_name_mut(&mut f))?;
+        }
+        let written = self
+            .create_clause
+            .iter_mut()
+            .flat_map(|create| &mut create.patterns)
+            .chain(self.merge_clause.iter_mut().map(|merge| &mut merge.pattern));
+        for pattern in written {
+            pattern.try_for_each_name_mut(&mut f)?;
+        }
+        let set_items = self.set_clause.iter_mut().flat_map(|set| &mut set.items);
+        for item in set_items {
+            if let SetItem::Labels { labels, .. } = item {
+                f(labels, true)?;
+            }
+        }
+        let remove_items = self.remove_clause.iter_mut().flat_map(|r| &mut r.items);
+        for item in remove_items {
+            if let RemoveItem::Labels { labels, .. } = item {
+                f(labels, true)?;
+            }
+        }
+        Ok(())
+    }
+
     fn collect_relationship_types_from_pattern(
         &self,
         pattern: &GraphPattern,
@@ -100,11 +198,47 @@ impl CypherQuery {
     }
 }
 
-/// A clause that reads from the graph (MATCH, UNWIND)
+/// How a SAMPLE clause picks rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SampleMethod {
+    /// `SAMPLE 10 PERCENT`: keep each row with the given probability (in percent)
+    Percent(f64),
+    /// `SAMPLE 100 ROWS`: keep a uniform sample of at most this many rows
+    Rows(u64),
+    /// `SAMPLE 5 ROWS PER n.country`: keep a uniform sample of at most `count`
+    /// rows for every value of `by`
+    RowsPer { count: u64, by: ValueExpression },
+}
+
+/// A standalone `CALL name(args)` statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcedureCall {
+    /// Dotted procedure name as written (e.g. `graph.summary`)
+    pub name: String,
+    pub arguments: Vec<ValueExpression>,
+}
+
+/// A `CALL { ... }` subquery
+///
+/// `MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f) RETURN f.name AS friend
+/// ORDER BY f.age DESC LIMIT 3 } RETURN p.name, friend` runs the subquery once
+/// per matched person, which is correlated with the outer query through the
+/// variables imported by its leading `WITH`. Without imports the subquery runs
+/// once and its rows are combined with every outer row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallSubquery {
+    /// Outer node variables imported by `WITH p, q`
+    pub imports: Vec<String>,
+    /// The subquery; the values of its RETURN clause become outer variables
+    pub query: Box<CypherQuery>,
+}
+
+/// A clause that reads from the graph (MATCH, UNWIND, CALL ... YIELD)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ReadingClause {
     Match(MatchClause),
     Unwind(UnwindClause),
+    TableFunction(TableFunctionCall),
 }
 
 /// A MATCH clause containing graph patterns
@@ -112,6 +246,14 @@ pub enum ReadingClause {
 pub struct MatchClause {
     /// Graph patterns to match
     pub patterns: Vec<GraphPattern>,
+    /// `OPTIONAL MATCH`: rows without a match are kept, with nulls for the
+    /// variables the clause introduces
+    #[serde(default)]
+    pub optional: bool,
+    /// WHERE clause of an `OPTIONAL MATCH`; rows failing it get nulls
+    /// instead of being dropped
+    #[serde(default)]
+    pub where_clause: Option<WhereClause>,
 }
 
 /// An UNWIND clause
@@ -123,6 +265,129 @@ pub struct UnwindClause {
     pub alias: String,
 }
 
+/// A `CALL name(args) YIELD column [AS alias], ...` clause over a registered
+/// table function; see [`crate::table_function`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableFunctionCall {
+    /// Dotted function name as written (e.g. `weather.forecast`)
+    pub name: String,
+    /// Literals and parameters, evaluated before the query runs
+    pub arguments: Vec<ValueExpression>,
+    /// Columns bound as variables
+    pub yields: Vec<YieldItem>,
+}
+
+/// A column of a table function bound by `YIELD`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YieldItem {
+    pub column: String,
+    /// Variable name, the column name when absent
+    pub alias: Option<String>,
+}
+
+impl YieldItem {
+    /// Name of the variable the column is bound to
+    pub fn variable(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.column)
+    }
+}
+
+/// A CREATE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateClause {
+    /// Patterns to create; variables bound by MATCH refer to existing nodes
+    pub patterns: Vec<GraphPattern>,
+}
+
+/// A MERGE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeClause {
+    /// Pattern to match or create; variables bound by MATCH refer to
+    /// existing nodes
+    pub pattern: GraphPattern,
+}
+
+/// A SET clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetClause {
+    /// Updates, applied in order
+    pub items: Vec<SetItem>,
+}
+
+/// One update of a SET clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SetItem {
+    /// `SET n.prop = value`
+    Property {
+        target: PropertyRef,
+        value: PropertyValue,
+    },
+    /// `SET n += {prop: value}` or `SET n += $map`: set every entry of the map
+    Properties {
+        variable: String,
+        properties: PropertyMap,
+    },
+    /// `SET n:Label`
+    Labels {
+        variable: String,
+        labels: Vec<String>,
+    },
+}
+
+impl SetItem {
+    /// The variable this item updates
+    pub fn variable(&self) -> &str {
+        match self {
+            Self::Property { target, .. } => &target.variable,
+            Self::Properties { variable, .. } | Self::Labels { variable, .. } => variable,
+        }
+    }
+}
+
+/// A REMOVE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoveClause {
+    pub items: Vec<RemoveItem>,
+}
+
+/// One removal of a REMOVE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RemoveItem {
+    /// `REMOVE n.prop`
+    Property { target: PropertyRef },
+    /// `REMOVE n:Label`
+    Labels {
+        variable: String,
+        labels: Vec<String>,
+    },
+}
+
+impl RemoveItem {
+    /// The variable this item updates
+    pub fn variable(&self) -> &str {
+        match self {
+            Self::Property { target } => &target.variable,
+            Self::Labels { variable, .. } => variable,
+        }
+    }
+}
+
+/// A DELETE or DETACH DELETE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeleteClause {
+    /// Whether the relationships of deleted nodes are deleted with them
+    pub detach: bool,
+    /// Variables of the deleted nodes and relationships
+    pub variables: Vec<String>,
+}
+
+/// A map of property values, written out or passed as a parameter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PropertyMap {
+    Literal(HashMap<String, PropertyValue>),
+    Parameter(String),
+}
+
 /// A graph pattern (nodes and relationships)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GraphPattern {
@@ -132,6 +397,27 @@ pub enum GraphPattern {
     Path(PathPattern),
 }
 
+impl GraphPattern {
+    /// Call `f` on the label list of every node (`true`) and the type list of
+    /// every relationship (`false`) of the pattern
+    pub(crate) fn try_for_each_name_mut<E>(
+        &mut self,
+        f: &mut impl FnMut(&mut Vec<String>, bool) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        match self {
+            GraphPattern::Node(node) => f(&mut node.labels, true),
+            GraphPattern::Path(path) => {
+                f(&mut path.start_node.labels, true)?;
+                for segment in &mut path.segments {
+                    f(&mut segment.relationship.types, false)?;
+                    f(&mut segment.end_node.labels, true)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 /// A node pattern in a graph query
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NodePattern {
@@ -146,10 +432,26 @@ pub struct NodePattern {
 /// A path pattern connecting nodes through relationships
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PathPattern {
+    /// Optional path variable (`p = (a)-[*1..3]->(b)`)
+    #[serde(default)]
+    pub variable: Option<String>,
     /// Starting node
     pub start_node: NodePattern,
     /// Relationships and intermediate nodes
     pub segments: Vec<PathSegment>,
+    /// Set when the pattern is wrapped in `shortestPath(...)` or
+    /// `allShortestPaths(...)`
+    #[serde(default)]
+    pub shortest: Option<ShortestPathKind>,
+}
+
+/// Which of the shortest paths between two nodes a pattern matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShortestPathKind {
+    /// `shortestPath(...)`: one shortest path per pair of end nodes
+    Single,
+    /// `allShortestPaths(...)`: every path of the shortest length per pair
+    All,
 }
 
 /// A segment of a path (relationship + end node)
@@ -215,6 +517,23 @@ pub enum PropertyValue {
     Property(PropertyRef),
 }
 
+impl PropertyValue {
+    /// Literal for a scalar JSON value (e.g. a bound parameter); `None` for
+    /// arrays and objects
+    pub(crate) fn from_json(value: &serde_json::Value) -> Option<Self> {
+        match value {
+            serde_json::Value::Null => Some(Self::Null),
+            serde_json::Value::Bool(b) => Some(Self::Boolean(*b)),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .map(Self::Integer)
+                .or_else(|| n.as_f64().map(Self::Float)),
+            serde_json::Value::String(s) => Some(Self::String(s.clone())),
+            _ => None,
+        }
+    }
+}
+
 /// Reference to a property of a node or relationship
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PropertyRef {
@@ -278,10 +597,66 @@ pub enum BooleanExpression {
         expression: ValueExpression,
         suffix: String,
     },
+    /// `=~` regular expression matching: the pattern must match the whole
+    /// string
+    RegexMatch {
+        expression: ValueExpression,
+        pattern: String,
+    },
     /// IS NULL pattern matching
     IsNull(ValueExpression),
     /// IS NOT NULL pattern matching
     IsNotNull(ValueExpression),
+    /// Path predicate: `all(x IN nodes(p) WHERE ...)`
+    AllInPath {
+        /// Element variable bound inside the predicate
+        variable: String,
+        /// Path variable the elements are drawn from
+        path: String,
+        /// Whether the predicate ranges over nodes or relationships
+        elements: PathElements,
+        predicate: Box<BooleanExpression>,
+    },
+    /// List predicate: `any(x IN list WHERE ...)`, likewise `all`, `none`
+    /// and `single`; null for a null list, or when the elements the
+    /// predicate is null for could change the outcome
+    ListPredicate {
+        quantifier: ListQuantifier,
+        /// Element variable bound inside the predicate
+        variable: String,
+        list: ValueExpression,
+        predicate: Box<BooleanExpression>,
+    },
+    /// Existential subquery: `EXISTS { MATCH (n)-[:KNOWS]->(m) WHERE ... }`
+    ///
+    /// True for the rows whose variables extend to at least one match of the
+    /// patterns; the variables the patterns introduce are local to it.
+    ExistsSubquery {
+        patterns: Vec<GraphPattern>,
+        where_clause: Option<Box<BooleanExpression>>,
+    },
+}
+
+/// Path elements a list predicate ranges over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathElements {
+    /// `nodes(p)`
+    Nodes,
+    /// `relationships(p)`
+    Relationships,
+}
+
+/// How many elements of a list a list predicate must hold for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ListQuantifier {
+    /// `all(...)`: every element (true for an empty list)
+    All,
+    /// `any(...)`: at least one element
+    Any,
+    /// `none(...)`: no element (true for an empty list)
+    None,
+    /// `single(...)`: exactly one element
+    Single,
 }
 
 /// Distance metric for vector similarity
@@ -322,7 +697,8 @@ pub enum ValueExpression {
         name: String,
         args: Vec<ValueExpression>,
     },
-    /// Aggregate function call (COUNT, SUM, AVG, MIN, MAX, COLLECT)
+    /// Aggregate function call (COUNT, SUM, AVG, MIN, MAX, COLLECT, STDEV,
+    /// STDEVP, PERCENTILECONT, PERCENTILEDISC)
     /// These functions operate across multiple rows and support DISTINCT
     AggregateFunction {
         name: String,
@@ -355,6 +731,72 @@ pub enum ValueExpression {
     /// Vector literal: [0.1, 0.2, 0.3]
     /// Represents an inline vector for similarity search
     VectorLiteral(Vec<f32>),
+    /// CASE expression: the value of the first branch whose condition holds,
+    /// else `default` (null when absent)
+    /// The simple form `CASE x WHEN v THEN ...` is parsed into one `x = v`
+    /// condition per branch
+    Case {
+        branches: Vec<CaseBranch>,
+        default: Option<Box<ValueExpression>>,
+    },
+    /// List comprehension: `[variable IN list WHERE predicate | projection]`
+    /// The projection of every element the predicate holds for; the elements
+    /// themselves without a projection, all of them without a predicate
+    ListComprehension {
+        /// Element variable bound inside the predicate and projection
+        variable: String,
+        list: Box<ValueExpression>,
+        predicate: Option<Box<BooleanExpression>>,
+        projection: Option<Box<ValueExpression>>,
+    },
+    /// Reduction: `reduce(accumulator = initial, variable IN list | expression)`
+    /// Starts from `initial` and replaces the accumulator with `expression`
+    /// for every element of the list in order; null for a null list
+    Reduce {
+        accumulator: String,
+        initial: Box<ValueExpression>,
+        /// Element variable bound inside the expression, with the accumulator
+        variable: String,
+        list: Box<ValueExpression>,
+        expression: Box<ValueExpression>,
+    },
+    /// Pattern comprehension: `[(a)-[:KNOWS]->(b) WHERE predicate | projection]`
+    /// The projection for every match of the pattern the predicate holds
+    /// for; pattern variables bound by the query refer to its current row
+    PatternComprehension {
+        pattern: PathPattern,
+        predicate: Option<Box<BooleanExpression>>,
+        projection: Box<ValueExpression>,
+    },
+    /// Map projection: `n {.name, key: expression, other}`
+    /// A map with one entry per item; `.name` is short for `name: n.name` and
+    /// `other` for `other: other`
+    MapProjection {
+        variable: String,
+        items: Vec<MapProjectionItem>,
+    },
+    /// Counting subquery: `COUNT { MATCH (n)-[:FOLLOWS]->(m) WHERE ... }`
+    ///
+    /// The number of matches of the patterns that extend the current row;
+    /// the variables the patterns introduce are local to it.
+    CountSubquery {
+        patterns: Vec<GraphPattern>,
+        where_clause: Option<Box<BooleanExpression>>,
+    },
+}
+
+/// One `key: value` entry of a map projection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapProjectionItem {
+    pub key: String,
+    pub value: ValueExpression,
+}
+
+/// One `WHEN condition THEN value` branch of a CASE expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseBranch {
+    pub condition: BooleanExpression,
+    pub value: ValueExpression,
 }
 
 /// Function type classification
@@ -371,8 +813,15 @@ pub enum FunctionType {
 /// Classify a function by name
 pub fn classify_function(name: &str) -> FunctionType {
     match name.to_lowercase().as_str() {
-        "count" | "sum" | "avg" | "min" | "max" | "collect" => FunctionType::Aggregate,
-        "tolower" | "lower" | "toupper" | "upper" => FunctionType::Scalar,
+        "count" | "sum" | "avg" | "min" | "max" | "collect" | "stdev" | "stdevp"
+        | "percentilecont" | "percentiledisc" => FunctionType::Aggregate,
+        "tolower" | "lower" | "toupper" | "upper" | "rand" | "randomuuid" | "timestamp"
+        | "length" | "split" | "replace" | "substring" | "left" | "right" | "trim" | "ltrim"
+        | "rtrim" | "reverse" | "range" | "size" | "head" | "last" | "tail" | "coalesce"
+        | "nullif" | "date" | "datetime" | "duration" | "duration.between" | "date.truncate"
+        | "datetime.truncate" | "labels" | "type" | "keys" | "properties" | "id" => {
+            FunctionType::Scalar
+        }
         // Vector functions are handled separately as special variants
         _ => FunctionType::Unknown,
     }
@@ -394,6 +843,9 @@ pub enum ArithmeticOperator {
 /// the input for subsequent clauses.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WithClause {
+    /// Whether DISTINCT was specified: only distinct rows pass on
+    #[serde(default)]
+    pub distinct: bool,
     /// Items to project (similar to RETURN)
     pub items: Vec<ReturnItem>,
     /// Optional ORDER BY within WITH
@@ -407,6 +859,12 @@ pub struct WithClause {
 pub struct ReturnClause {
     /// Whether DISTINCT was specified
     pub distinct: bool,
+    /// Keys of `DISTINCT ON (...)`: one row is returned per distinct key
+    ///
+    /// A node variable stands for its id, so `DISTINCT ON (n)` returns each
+    /// matched entity once however many paths reach it.
+    #[serde(default)]
+    pub distinct_on: Vec<ValueExpression>,
     /// Items to return
     pub items: Vec<ReturnItem>,
 }
@@ -505,6 +963,391 @@ impl PropertyRef {
             property: property.into(),
         }
     }
+
+    fn rename(&self, from: &str, to: &str) -> PropertyRef {
+        if self.variable == from {
+            PropertyRef::new(to, self.property.as_str())
+        } else {
+            self.clone()
+        }
+    }
+}
+
+impl BooleanExpression {
+    /// Call `f` on every pattern of the `EXISTS { ... }` and `COUNT { ... }`
+    /// subqueries in the expression, including nested ones
+    pub(crate) fn try_for_each_subquery_pattern_mut<E>(
+        &mut self,
+        f: &mut impl FnMut(&mut GraphPattern) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        match self {
+            BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+                left.try_for_each_subquery_pattern_mut(f)?;
+                right.try_for_each_subquery_pattern_mut(f)
+            }
+            BooleanExpression::Not(inner) => inner.try_for_each_subquery_pattern_mut(f),
+            BooleanExpression::AllInPath { predicate, .. } => {
+                predicate.try_for_each_subquery_pattern_mut(f)
+            }
+            BooleanExpression::ListPredicate {
+                list, predicate, ..
+            } => {
+                list.try_for_each_subquery_pattern_mut(f)?;
+                predicate.try_for_each_subquery_pattern_mut(f)
+            }
+            BooleanExpression::Comparison { left, right, .. } => {
+                left.try_for_each_subquery_pattern_mut(f)?;
+                right.try_for_each_subquery_pattern_mut(f)
+            }
+            BooleanExpression::ExistsSubquery {
+                patterns,
+                where_clause,
+            } => {
+                for pattern in patterns {
+                    f(pattern)?;
+                }
+                match where_clause {
+                    Some(where_clause) => where_clause.try_for_each_subquery_pattern_mut(f),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return a copy with every reference to variable `from` replaced by `to`
+    pub fn rename_variable(&self, from: &str, to: &str) -> BooleanExpression {
+        let value = |v: &ValueExpression| v.rename_variable(from, to);
+        match self {
+            BooleanExpression::Comparison {
+                left,
+                operator,
+                right,
+            } => BooleanExpression::Comparison {
+                left: value(left),
+                operator: operator.clone(),
+                right: value(right),
+            },
+            BooleanExpression::And(l, r) => BooleanExpression::And(
+                Box::new(l.rename_variable(from, to)),
+                Box::new(r.rename_variable(from, to)),
+            ),
+            BooleanExpression::Or(l, r) => BooleanExpression::Or(
+                Box::new(l.rename_variable(from, to)),
+                Box::new(r.rename_variable(from, to)),
+            ),
+            BooleanExpression::Not(inner) => {
+                BooleanExpression::Not(Box::new(inner.rename_variable(from, to)))
+            }
+            BooleanExpression::Exists(prop) => BooleanExpression::Exists(prop.rename(from, to)),
+            BooleanExpression::In { expression, list } => BooleanExpression::In {
+                expression: value(expression),
+                list: list.iter().map(value).collect(),
+            },
+            BooleanExpression::Like {
+                expression,
+                pattern,
+            } => BooleanExpression::Like {
+                expression: value(expression),
+                pattern: pattern.clone(),
+            },
+            BooleanExpression::ILike {
+                expression,
+                pattern,
+            } => BooleanExpression::ILike {
+                expression: value(expression),
+                pattern: pattern.clone(),
+            },
+            BooleanExpression::Contains {
+                expression,
+                substring,
+            } => BooleanExpression::Contains {
+                expression: value(expression),
+                substring: substring.clone(),
+            },
+            BooleanExpression::StartsWith { expression, prefix } => BooleanExpression::StartsWith {
+                expression: value(expression),
+                prefix: prefix.clone(),
+            },
+            BooleanExpression::EndsWith { expression, suffix } => BooleanExpression::EndsWith {
+                expression: value(expression),
+                suffix: suffix.clone(),
+            },
+            BooleanExpression::RegexMatch {
+                expression,
+                pattern,
+            } => BooleanExpression::RegexMatch {
+                expression: value(expression),
+                pattern: pattern.clone(),
+            },
+            BooleanExpression::IsNull(expression) => BooleanExpression::IsNull(value(expression)),
+            BooleanExpression::IsNotNull(expression) => {
+                BooleanExpression::IsNotNull(value(expression))
+            }
+            BooleanExpression::AllInPath {
+                variable,
+                path,
+                elements,
+                predicate,
+            } => {
+                // The element variable shadows `from` inside the predicate
+                let predicate = if variable == from {
+                    predicate.as_ref().clone()
+                } else {
+                    predicate.rename_variable(from, to)
+                };
+                BooleanExpression::AllInPath {
+                    variable: variable.clone(),
+                    path: if path == from {
+                        to.to_string()
+                    } else {
+                        path.clone()
+                    },
+                    elements: *elements,
+                    predicate: Box::new(predicate),
+                }
+            }
+            BooleanExpression::ListPredicate {
+                quantifier,
+                variable,
+                list,
+                predicate,
+            } => BooleanExpression::ListPredicate {
+                quantifier: *quantifier,
+                variable: variable.clone(),
+                list: value(list),
+                // The element variable shadows `from` inside the predicate
+                predicate: Box::new(if variable == from {
+                    predicate.as_ref().clone()
+                } else {
+                    predicate.rename_variable(from, to)
+                }),
+            },
+            BooleanExpression::ExistsSubquery {
+                patterns,
+                where_clause,
+            } => BooleanExpression::ExistsSubquery {
+                patterns: rename_pattern_variables(patterns, from, to),
+                where_clause: where_clause
+                    .as_deref()
+                    .map(|w| Box::new(w.rename_variable(from, to))),
+            },
+        }
+    }
+}
+
+impl ValueExpression {
+    /// Call `f` on every pattern of the `COUNT { ... }` subqueries in the
+    /// expression, including nested ones
+    pub(crate) fn try_for_each_subquery_pattern_mut<E>(
+        &mut self,
+        f: &mut impl FnMut(&mut GraphPattern) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        match self {
+            ValueExpression::ScalarFunction { args, .. }
+            | ValueExpression::AggregateFunction { args, .. } => {
+                for arg in args {
+                    arg.try_for_each_subquery_pattern_mut(f)?;
+                }
+                Ok(())
+            }
+            ValueExpression::Arithmetic { left, right, .. } => {
+                left.try_for_each_subquery_pattern_mut(f)?;
+                right.try_for_each_subquery_pattern_mut(f)
+            }
+            ValueExpression::CountSubquery {
+                patterns,
+                where_clause,
+            } => {
+                for pattern in patterns {
+                    f(pattern)?;
+                }
+                match where_clause {
+                    Some(where_clause) => where_clause.try_for_each_subquery_pattern_mut(f),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return a copy with every reference to variable `from` replaced by `to`
+    pub fn rename_variable(&self, from: &str, to: &str) -> ValueExpression {
+        let boxed = |v: &ValueExpression| Box::new(v.rename_variable(from, to));
+        match self {
+            ValueExpression::Variable(name) if name == from => {
+                ValueExpression::Variable(to.to_string())
+            }
+            ValueExpression::Property(prop) => ValueExpression::Property(prop.rename(from, to)),
+            ValueExpression::ScalarFunction { name, args } => ValueExpression::ScalarFunction {
+                name: name.clone(),
+                args: args.iter().map(|a| a.rename_variable(from, to)).collect(),
+            },
+            ValueExpression::AggregateFunction {
+                name,
+                args,
+                distinct,
+            } => ValueExpression::AggregateFunction {
+                name: name.clone(),
+                args: args.iter().map(|a| a.rename_variable(from, to)).collect(),
+                distinct: *distinct,
+            },
+            ValueExpression::Arithmetic {
+                left,
+                operator,
+                right,
+            } => ValueExpression::Arithmetic {
+                left: boxed(left),
+                operator: operator.clone(),
+                right: boxed(right),
+            },
+            ValueExpression::VectorDistance {
+                left,
+                right,
+                metric,
+            } => ValueExpression::VectorDistance {
+                left: boxed(left),
+                right: boxed(right),
+                metric: metric.clone(),
+            },
+            ValueExpression::VectorSimilarity {
+                left,
+                right,
+                metric,
+            } => ValueExpression::VectorSimilarity {
+                left: boxed(left),
+                right: boxed(right),
+                metric: metric.clone(),
+            },
+            ValueExpression::Case { branches, default } => ValueExpression::Case {
+                branches: branches
+                    .iter()
+                    .map(|branch| CaseBranch {
+                        condition: branch.condition.rename_variable(from, to),
+                        value: branch.value.rename_variable(from, to),
+                    })
+                    .collect(),
+                default: default.as_deref().map(boxed),
+            },
+            ValueExpression::ListComprehension {
+                variable,
+                list,
+                predicate,
+                projection,
+            } => {
+                // The element variable shadows `from` inside the comprehension
+                let shadowed = variable == from;
+                ValueExpression::ListComprehension {
+                    variable: variable.clone(),
+                    list: boxed(list),
+                    predicate: predicate.as_deref().map(|p| {
+                        Box::new(if shadowed {
+                            p.clone()
+                        } else {
+                            p.rename_variable(from, to)
+                        })
+                    }),
+                    projection: projection.as_deref().map(|p| {
+                        if shadowed {
+                            Box::new(p.clone())
+                        } else {
+                            boxed(p)
+                        }
+                    }),
+                }
+            }
+            ValueExpression::Reduce {
+                accumulator,
+                initial,
+                variable,
+                list,
+                expression,
+            } => ValueExpression::Reduce {
+                accumulator: accumulator.clone(),
+                initial: boxed(initial),
+                variable: variable.clone(),
+                list: boxed(list),
+                // Both variables shadow `from` inside the expression
+                expression: if accumulator == from || variable == from {
+                    expression.clone()
+                } else {
+                    boxed(expression)
+                },
+            },
+            ValueExpression::PatternComprehension {
+                pattern,
+                predicate,
+                projection,
+            } => {
+                // Pattern variables named `from` are the renamed variable
+                let rename = |variable: &Option<String>| {
+                    variable
+                        .as_ref()
+                        .map(|v| if v == from { to.to_string() } else { v.clone() })
+                };
+                let mut pattern = pattern.clone();
+                pattern.start_node.variable = rename(&pattern.start_node.variable);
+                for segment in &mut pattern.segments {
+                    segment.relationship.variable = rename(&segment.relationship.variable);
+                    segment.end_node.variable = rename(&segment.end_node.variable);
+                }
+                ValueExpression::PatternComprehension {
+                    pattern,
+                    predicate: predicate
+                        .as_deref()
+                        .map(|p| Box::new(p.rename_variable(from, to))),
+                    projection: boxed(projection),
+                }
+            }
+            ValueExpression::MapProjection { variable, items } => ValueExpression::MapProjection {
+                variable: if variable == from {
+                    to.to_string()
+                } else {
+                    variable.clone()
+                },
+                items: items
+                    .iter()
+                    .map(|item| MapProjectionItem {
+                        key: item.key.clone(),
+                        value: item.value.rename_variable(from, to),
+                    })
+                    .collect(),
+            },
+            ValueExpression::CountSubquery {
+                patterns,
+                where_clause,
+            } => ValueExpression::CountSubquery {
+                patterns: rename_pattern_variables(patterns, from, to),
+                where_clause: where_clause
+                    .as_deref()
+                    .map(|w| Box::new(w.rename_variable(from, to))),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+/// `patterns` with their variables named `from` renamed to `to`
+fn rename_pattern_variables(patterns: &[GraphPattern], from: &str, to: &str) -> Vec<GraphPattern> {
+    let rename = |variable: &mut Option<String>| {
+        if variable.as_deref() == Some(from) {
+            *variable = Some(to.to_string());
+        }
+    };
+    let mut patterns = patterns.to_vec();
+    for pattern in &mut patterns {
+        match pattern {
+            GraphPattern::Node(node) => rename(&mut node.variable),
+            GraphPattern::Path(path) => {
+                rename(&mut path.start_node.variable);
+                for segment in &mut path.segments {
+                    rename(&mut segment.relationship.variable);
+                    rename(&mut segment.end_node.variable);
+                }
+            }
+        }
+    }
+    patterns
 }
 
 #[cfg(test)]
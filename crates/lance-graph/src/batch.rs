@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Batched execution of many queries over the same graph
+//!
+//! Dashboard pages issue many similar queries at once. Executed one by one,
+//! every query opens and scans its datasets and runs its own index searches.
+//! [`CypherQuery::execute_batch_with_namespace`] instead opens each dataset
+//! once for the whole batch, scans a dataset read by more than one query only
+//! once, and runs a single index search for vector top-k queries ranking by
+//! the same query vector.
+//! [`CypherQuery::execute_batch`] registers in-memory datasets once for all
+//! queries of the batch.
+//!
+//! Results are returned in query order; the batch fails with the first
+//! failing query. Datasets are opened with the credentials provider of the
+//! first query. Shared datasets stay in Lance when a query of the batch
+//! requests provenance columns, which are read from the Lance dataset.
+//!
+//! # Memory
+//!
+//! A shared dataset is scanned whole, every column and row, and kept in
+//! memory until the batch returns, however selective the queries reading it
+//! are. A dataset is shared only if its rows fit in [`SHARED_SCAN_LIMIT`]
+//! (256 MiB); a larger one is scanned by each query from Lance, as outside a
+//! batch. The scan is abandoned, and its rows released, as soon as it passes
+//! the limit.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::CypherQuery;
+//!
+//! let queries = vec![
+//!     CypherQuery::new("MATCH (p:Person) RETURN count(p) AS people")?.with_config(config.clone()),
+//!     CypherQuery::new("MATCH (p:Person) WHERE p.age > 30 RETURN p.name")?.with_config(config),
+//! ];
+//! // Person is scanned once for both queries
+//! let results = CypherQuery::execute_batch_with_namespace(&queries, namespace).await?;
+//! ```
+
+use crate::ast::{GraphPattern, ReadingClause};
+use crate::error::Result;
+use crate::query::CypherQuery;
+use crate::vector_candidates::SeedCache;
+use arrow_array::RecordBatch;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::execution::context::SessionContext;
+use futures::TryStreamExt;
+use lance_graph_catalog::{DirNamespace, GraphSourceCatalog};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Largest in-memory size, in bytes, of a dataset whose scan is shared by the
+/// queries of a batch
+const SHARED_SCAN_LIMIT: usize = 256 * 1024 * 1024;
+
+impl CypherQuery {
+    /// Execute `queries` against in-memory `datasets` with the DataFusion
+    /// planner, registering the datasets once for the whole batch
+    ///
+    /// Returns one result per query, in order.
+    pub async fn execute_batch(
+        queries: &[CypherQuery],
+        datasets: HashMap<String, RecordBatch>,
+    ) -> Result<Vec<RecordBatch>> {
+        let Some(first) = queries.first() else {
+            return Ok(Vec::new());
+        };
+        let (catalog, ctx) = first
+            .build_catalog_and_context_from_datasets(datasets)
+            .await?;
+        let catalog: Arc<dyn GraphSourceCatalog> = Arc::new(catalog);
+
+        let mut results = Vec::with_capacity(queries.len());
+        for query in queries {
+            results.push(
+                query
+                    .execute_with_catalog_and_context(catalog.clone(), ctx.clone())
+                    .await?,
+            );
+        }
+        Ok(results)
+    }
+
+    /// Execute `queries` against the datasets of `namespace`, sharing dataset
+    /// scans and vector index searches across the batch
+    ///
+    /// Returns one result per query, in order. See the
+    /// [module documentation](crate::batch) for what is shared. Each dataset
+    /// read by more than one query is held in memory until the batch returns,
+    /// up to 256 MiB per dataset; larger ones are not shared.
+    pub async fn execute_batch_with_namespace(
+        queries: &[CypherQuery],
+        namespace: DirNamespace,
+    ) -> Result<Vec<RecordBatch>> {
+        let Some(first) = queries.first() else {
+            return Ok(Vec::new());
+        };
+        let mut tables = HashSet::new();
+        for query in queries {
+            tables.extend(query.namespace_tables()?);
+        }
+        let mut providers = first
+            .open_namespace_tables(Arc::new(namespace), tables)
+            .await?;
+
+        // Narrow vector top-k queries first: their index searches need the
+        // Lance datasets, which shared scans replace below
+        let seeds = SeedCache::default();
+        let mut prepared = Vec::with_capacity(queries.len());
+        for query in queries {
+            let (catalog, _) = query.catalog_and_context_from_providers(&providers)?;
+            let narrowed = query.with_vector_candidates(&catalog, Some(&seeds)).await?;
+            prepared.push(narrowed.into_owned());
+        }
+
+        if !queries.iter().any(CypherQuery::includes_provenance) {
+            for table in shared_tables(&prepared)? {
+                let Some(provider) = providers.get(&table) else {
+                    continue;
+                };
+                if let Some(scanned) = scan_into_memory(provider.clone(), SHARED_SCAN_LIMIT).await?
+                {
+                    providers.insert(table, scanned);
+                }
+            }
+        }
+
+        let mut results = Vec::with_capacity(prepared.len());
+        for query in &prepared {
+            let (catalog, ctx) = query.catalog_and_context_from_providers(&providers)?;
+            results.push(
+                query
+                    .execute_with_catalog_and_context(Arc::new(catalog), ctx)
+                    .await?,
+            );
+        }
+        Ok(results)
+    }
+}
+
+/// Lowercase names of the datasets read by more than one of `queries`
+fn shared_tables(queries: &[CypherQuery]) -> Result<Vec<String>> {
+    let mut readers: HashMap<String, usize> = HashMap::new();
+    for query in queries {
+        for table in tables_read(query)? {
+            *readers.entry(table).or_default() += 1;
+        }
+    }
+    let mut shared: Vec<String> = readers
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(table, _)| table)
+        .collect();
+    shared.sort();
+    Ok(shared)
+}
+
+/// Lowercase names of the datasets the MATCH patterns of `query` read
+fn tables_read(query: &CypherQuery) -> Result<HashSet<String>> {
+    Ok(table_reads(query)?.into_keys().collect())
+}
+
+/// How many pattern elements of the MATCH clauses of `query` read each
+/// dataset, keyed by lowercase dataset name
+///
+/// View labels read the dataset of their base label; unlabeled nodes and
+/// labels missing from the configuration read nothing.
+pub(crate) fn table_reads(query: &CypherQuery) -> Result<HashMap<String, usize>> {
+    let config = query.require_config()?;
+    let ast = query.ast();
+    let mut labels = Vec::new();
+    let mut types = Vec::new();
+    for clause in ast
+        .reading_clauses
+        .iter()
+        .chain(&ast.post_with_reading_clauses)
+    {
+        let ReadingClause::Match(match_clause) = clause else {
+            continue;
+        };
+        for pattern in &match_clause.patterns {
+            match pattern {
+                GraphPattern::Node(node) => labels.extend(&node.labels),
+                GraphPattern::Path(path) => {
+                    labels.extend(&path.start_node.labels);
+                    for segment in &path.segments {
+                        labels.extend(&segment.end_node.labels);
+                        types.extend(&segment.relationship.types);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut reads: HashMap<String, usize> = HashMap::new();
+    for label in labels {
+        let (base, _) = config.resolve_view_chain(label)?;
+        if let Some(mapping) = config.get_node_mapping(&base) {
+            *reads
+                .entry(mapping.table_name().to_lowercase())
+                .or_default() += 1;
+        }
+    }
+    for rel_type in types {
+        if let Some(mapping) = config.get_relationship_mapping(rel_type) {
+            *reads
+                .entry(mapping.relationship_type.to_lowercase())
+                .or_default() += 1;
+        }
+    }
+    Ok(reads)
+}
+
+/// Read every row of `provider` once into an in-memory table; `None` if the
+/// rows take more than `limit` bytes
+async fn scan_into_memory(
+    provider: Arc<dyn TableProvider>,
+    limit: usize,
+) -> Result<Option<Arc<dyn TableProvider>>> {
+    let known_size = provider
+        .statistics()
+        .and_then(|statistics| statistics.total_byte_size.get_value().copied());
+    if known_size.is_some_and(|size| size > limit) {
+        return Ok(None);
+    }
+    let schema = provider.schema();
+    let mut stream = SessionContext::new()
+        .read_table(provider)?
+        .execute_stream()
+        .await?;
+    let mut batches = Vec::new();
+    let mut size = 0;
+    while let Some(batch) = stream.try_next().await? {
+        size += batch.get_array_memory_size();
+        if size > limit {
+            return Ok(None);
+        }
+        batches.push(batch);
+    }
+    Ok(Some(Arc::new(MemTable::try_new(schema, vec![batches])?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GraphConfig, NodeMapping};
+    use arrow_array::{ArrayRef, Int64Array};
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_node_mapping(NodeMapping::new("Adult", "id").with_view_of("Person"))
+            .with_node_label("Company", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap()
+    }
+
+    fn query(cypher: &str) -> CypherQuery {
+        CypherQuery::new(cypher).unwrap().with_config(config())
+    }
+
+    #[test]
+    fn test_tables_read_resolves_views() {
+        let tables = tables_read(&query(
+            "MATCH (a:Adult)-[:KNOWS]->(b:Person) WITH b.name AS name MATCH (c:Company) RETURN c.name",
+        ))
+        .unwrap();
+        let mut tables: Vec<_> = tables.into_iter().collect();
+        tables.sort();
+        assert_eq!(tables, vec!["company", "knows", "person"]);
+    }
+
+    #[test]
+    fn test_shared_tables_counts_queries_not_patterns() {
+        let queries = vec![
+            query("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name"),
+            query("MATCH (p:Adult) RETURN count(p)"),
+            query("MATCH (c:Company) RETURN c.name"),
+        ];
+        assert_eq!(shared_tables(&queries).unwrap(), vec!["person"]);
+        assert!(shared_tables(&queries[2..]).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_into_memory_stops_at_the_limit() {
+        let batch = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(Int64Array::from_iter_values(0..1024)) as ArrayRef,
+        )])
+        .unwrap();
+        let provider: Arc<dyn TableProvider> = Arc::new(
+            MemTable::try_new(batch.schema(), vec![vec![batch.clone(), batch.clone()]]).unwrap(),
+        );
+        let size = batch.get_array_memory_size();
+
+        let scanned = scan_into_memory(provider.clone(), 2 * size)
+            .await
+            .unwrap()
+            .unwrap();
+        let rows = SessionContext::new()
+            .read_table(scanned)
+            .unwrap()
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(rows, 2048);
+        assert!(scan_into_memory(provider, size).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,972 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Nested execution of `CALL { ... }` subqueries
+//!
+//! `MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name AS friend
+//! ORDER BY f.age DESC LIMIT 3 } RETURN p.name, friend` returns the three
+//! oldest friends of every person. Such a per-row sub-pipeline has no single
+//! DataFusion plan, so the query runs in three steps:
+//!
+//! 1. The outer query up to CALL returns the keys of the imported nodes and
+//!    the outer properties used after CALL.
+//! 2. The subquery runs once per distinct combination of imported nodes. Each
+//!    import is pinned to its node: the node pattern gets the label of the
+//!    outer pattern and the key values are substituted into a
+//!    `WHERE p.id = <key>` filter. An uncorrelated subquery (no importing
+//!    `WITH`) runs once.
+//! 3. Every outer row is repeated for each row its subquery run returned, and
+//!    RETURN, ORDER BY, SKIP and LIMIT are planned over those combined rows.
+//!
+//! Outer rows whose subquery run returns no rows are dropped. Only node
+//! variables can be imported, and the subquery returns values named by their
+//! aliases; after CALL, the query reads those values and properties of the
+//! outer variables.
+//!
+//! CALL subqueries are experimental: queries must opt in with
+//! [`crate::ExperimentalFeature::CallSubquery`].
+//!
+//! ```ignore
+//! let query = CypherQuery::new(
+//!     "MATCH (p:Person) \
+//!      CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name AS friend ORDER BY f.age DESC LIMIT 3 } \
+//!      RETURN p.name, friend",
+//! )?
+//! .with_config(config)
+//! .with_experimental_feature(ExperimentalFeature::CallSubquery);
+//! let top_friends = query.execute(datasets, None).await?;
+//! ```
+
+use crate::ast::{
+    BooleanExpression, CallSubquery, ComparisonOperator, CypherQuery as CypherAST, GraphPattern,
+    MatchClause, NodePattern, PropertyRef, PropertyValue, ReadingClause, ReturnClause, ReturnItem,
+    ValueExpression, WhereClause,
+};
+use crate::config::GraphConfig;
+use crate::datafusion_planner::expression::to_cypher_column_name;
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use arrow::array::{Array, ArrayRef, AsArray, Int64Array, ListArray, UInt32Array};
+use arrow::compute::{concat_batches, take};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow_array::RecordBatch;
+use datafusion::execution::context::SessionContext;
+use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Label and variable of the combined rows RETURN is planned over
+const ROWS_LABEL: &str = "CallRows";
+const ROWS_VARIABLE: &str = "callrow";
+/// Id column of the combined rows
+const ROW_ID: &str = "callrowid";
+/// Prefixes of the outer query columns carrying import keys and properties
+const IMPORT_PREFIX: &str = "callimport";
+const OUTER_PREFIX: &str = "callouter";
+
+/// Error for a query with a CALL subquery planned as a single plan (EXPLAIN,
+/// SQL, streaming, the simple executor)
+pub(crate) fn check_no_subquery(ast: &CypherAST) -> Result<()> {
+    if ast.call_subquery.is_none() {
+        return Ok(());
+    }
+    Err(GraphError::UnsupportedFeature {
+        feature: "CALL subqueries in a single plan; they run once per imported row \
+                  through execute()"
+            .to_string(),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Execute `query`, whose CALL subquery is `call`, against the tables of
+/// `catalog` and `ctx`
+pub(crate) async fn execute(
+    query: &CypherQuery,
+    call: &CallSubquery,
+    catalog: Arc<dyn GraphSourceCatalog>,
+    ctx: &SessionContext,
+) -> Result<RecordBatch> {
+    check_subquery(&call.query)?;
+    let subquery = Correlated {
+        query: call.query.clone(),
+        imports: call.imports.clone(),
+    };
+    execute_nested(query, query.ast(), &[subquery], None, false, catalog, ctx).await
+}
+
+/// A subquery run once per distinct combination of the outer nodes it imports
+#[derive(Debug, Clone)]
+pub(crate) struct Correlated {
+    pub query: CypherAST,
+    /// Outer node variables the subquery reads
+    pub imports: Vec<String>,
+}
+
+/// Execute `ast`, whose clauses after the reading clauses read the values
+/// `subqueries` return, against the tables of `catalog` and `ctx`
+///
+/// Several subqueries must return one row per run, which are put side by
+/// side. `filter` is applied to the outer rows joined with those values.
+/// With `empty_lists`, the null lists a run returns (`collect()` over no
+/// rows) are made empty.
+pub(crate) async fn execute_nested(
+    query: &CypherQuery,
+    ast: &CypherAST,
+    subqueries: &[Correlated],
+    filter: Option<&BooleanExpression>,
+    empty_lists: bool,
+    catalog: Arc<dyn GraphSourceCatalog>,
+    ctx: &SessionContext,
+) -> Result<RecordBatch> {
+    let mut variables: Vec<String> = Vec::new();
+    for subquery in subqueries {
+        for variable in &subquery.imports {
+            if !variables.iter().any(|v| v.eq_ignore_ascii_case(variable)) {
+                variables.push(variable.clone());
+            }
+        }
+    }
+    let imports = resolve_imports(ast, &variables, query.require_config()?)?;
+    let mut returned = Vec::new();
+    for subquery in subqueries {
+        returned.extend(returned_values(query, &subquery.query)?);
+    }
+    let mut scope = Scope {
+        returned,
+        aliases: Vec::new(),
+        outer: Vec::new(),
+    };
+    let rows_ast = rows_ast(ast, filter, &mut scope)?;
+
+    // Step 1: the outer rows, or a single empty row without outer clauses
+    let outer = if ast.reading_clauses.is_empty() {
+        None
+    } else {
+        let driver = driver_ast(ast, &imports, &scope.outer);
+        Some(
+            query
+                .collect_ast_batch(driver, catalog.clone(), ctx)
+                .await?,
+        )
+    };
+    let keys = match &outer {
+        Some(batch) => import_keys(batch, &imports)?,
+        None => vec![Some(Vec::new())],
+    };
+
+    // Step 2: one subquery run per distinct import key
+    let mut runs: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut results = Vec::new();
+    let mut offset = 0;
+    let mut outer_indices = Vec::new();
+    let mut inner_indices = Vec::new();
+    for (row, key) in keys.iter().enumerate() {
+        // A null key (e.g. from OPTIONAL MATCH) matches no node
+        let Some(key) = key else {
+            continue;
+        };
+        let id = serde_json::to_string(key).map_err(|e| GraphError::ExecutionError {
+            message: format!("Failed to encode CALL subquery imports: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+        let (start, len) = match runs.get(&id) {
+            Some(run) => *run,
+            None => {
+                let batch =
+                    run_subqueries(query, subqueries, &imports, key, empty_lists, &catalog, ctx)
+                        .await?;
+                let run = (offset, batch.num_rows());
+                offset += batch.num_rows();
+                results.push(batch);
+                runs.insert(id, run);
+                run
+            }
+        };
+        for inner in start..start + len {
+            outer_indices.push(row as u32);
+            inner_indices.push(inner as u32);
+        }
+    }
+    if results.is_empty() {
+        // No run: a run matching nothing still yields the returned columns
+        let nulls: Vec<PropertyValue> = imports
+            .iter()
+            .flat_map(|import| import.keys.iter().map(|_| PropertyValue::Null))
+            .collect();
+        results.push(
+            run_subqueries(
+                query,
+                subqueries,
+                &imports,
+                &nulls,
+                empty_lists,
+                &catalog,
+                ctx,
+            )
+            .await?,
+        );
+    }
+
+    // Step 3: RETURN over the outer rows joined to their runs
+    let rows = combine(
+        outer.as_ref(),
+        &concat_batches(&results[0].schema(), &results)?,
+        outer_indices,
+        inner_indices,
+    )?;
+    let rows_config = GraphConfig::builder()
+        .with_node_label(ROWS_LABEL, ROW_ID)
+        .build()?;
+    let rows_query = query.clone().with_config(rows_config);
+    let (rows_catalog, rows_ctx) = rows_query
+        .build_catalog_and_context_from_datasets(HashMap::from([(ROWS_LABEL.to_string(), rows)]))
+        .await?;
+    rows_query
+        .collect_ast_batch(rows_ast, Arc::new(rows_catalog), &rows_ctx)
+        .await
+}
+
+/// Run every subquery with its imports pinned to their nodes in `key`, which
+/// holds the key values of all `imports` in order
+async fn run_subqueries(
+    query: &CypherQuery,
+    subqueries: &[Correlated],
+    imports: &[Import],
+    key: &[PropertyValue],
+    empty_lists: bool,
+    catalog: &Arc<dyn GraphSourceCatalog>,
+    ctx: &SessionContext,
+) -> Result<RecordBatch> {
+    let mut batches = Vec::new();
+    for subquery in subqueries {
+        let mut own_imports = Vec::new();
+        let mut own_key = Vec::new();
+        let mut values = key;
+        for import in imports {
+            let (import_key, rest) = values.split_at(import.keys.len());
+            values = rest;
+            if subquery
+                .imports
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(&import.variable))
+            {
+                own_imports.push(import.clone());
+                own_key.extend_from_slice(import_key);
+            }
+        }
+        let pinned = pin_imports(&subquery.query, &own_imports, &own_key);
+        batches.push(
+            query
+                .collect_ast_batch(pinned, catalog.clone(), ctx)
+                .await?,
+        );
+    }
+    if batches.len() == 1 && !empty_lists {
+        return Ok(batches.remove(0));
+    }
+
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    for batch in &batches {
+        if batches.len() > 1 && batch.num_rows() != 1 {
+            return Err(GraphError::ExecutionError {
+                message: format!(
+                    "Correlated subqueries run side by side must return one row each, got {}",
+                    batch.num_rows()
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let schema = batch.schema();
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            fields.push(field.as_ref().clone());
+            columns.push(if empty_lists {
+                empty_if_null(column)
+            } else {
+                column.clone()
+            });
+        }
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+/// `column` with its null lists made empty; null lists span no values
+fn empty_if_null(column: &ArrayRef) -> ArrayRef {
+    if !matches!(column.data_type(), DataType::List(_)) || column.null_count() == 0 {
+        return column.clone();
+    }
+    let lists = column.as_list::<i32>();
+    let (field, offsets, values, _) = lists.clone().into_parts();
+    Arc::new(ListArray::new(field, offsets, values, None))
+}
+
+/// Reject subqueries that are not read-only single-stage queries
+fn check_subquery(subquery: &CypherAST) -> Result<()> {
+    let feature = if subquery.call_subquery.is_some() {
+        "Nested CALL subqueries"
+    } else if subquery.procedure.is_some() {
+        "Procedure calls inside a CALL subquery"
+    } else if subquery.create_clause.is_some()
+        || subquery.merge_clause.is_some()
+        || subquery.set_clause.is_some()
+        || subquery.remove_clause.is_some()
+        || subquery.delete_clause.is_some()
+    {
+        "Writing clauses inside a CALL subquery"
+    } else {
+        return Ok(());
+    };
+    Err(GraphError::UnsupportedFeature {
+        feature: feature.to_string(),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// An outer node variable imported into the subquery
+#[derive(Debug, Clone)]
+struct Import {
+    variable: String,
+    label: String,
+    /// Columns identifying the node
+    keys: Vec<String>,
+}
+
+fn resolve_imports(
+    ast: &CypherAST,
+    variables: &[String],
+    config: &GraphConfig,
+) -> Result<Vec<Import>> {
+    variables
+        .iter()
+        .map(|variable| {
+            let label = outer_label(ast, variable).ok_or_else(|| GraphError::PlanError {
+                message: format!(
+                    "Subquery imports '{}', which is not a labeled node of the outer MATCH",
+                    variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+            let mapping = config
+                .get_node_mapping(&label)
+                .ok_or_else(|| GraphError::PlanError {
+                    message: format!("No node mapping found for label '{}'", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+            let keys = if mapping.key_fields.is_empty() {
+                vec![mapping.id_field.clone()]
+            } else {
+                mapping.key_fields.clone()
+            };
+            Ok(Import {
+                variable: variable.clone(),
+                label,
+                keys,
+            })
+        })
+        .collect()
+}
+
+/// Label of the outer MATCH node pattern bound to `variable`
+fn outer_label(ast: &CypherAST, variable: &str) -> Option<String> {
+    ast.reading_clauses
+        .iter()
+        .filter_map(|clause| match clause {
+            ReadingClause::Match(match_clause) => Some(match_clause),
+            ReadingClause::Unwind(_) | ReadingClause::TableFunction(_) => None,
+        })
+        .flat_map(|match_clause| &match_clause.patterns)
+        .flat_map(|pattern| match pattern {
+            GraphPattern::Node(node) => vec![node],
+            GraphPattern::Path(path) => std::iter::once(&path.start_node)
+                .chain(path.segments.iter().map(|segment| &segment.end_node))
+                .collect(),
+        })
+        .find(|node| binds(node, variable) && !node.labels.is_empty())
+        .map(|node| node.labels[0].clone())
+}
+
+fn binds(node: &NodePattern, variable: &str) -> bool {
+    node.variable
+        .as_deref()
+        .is_some_and(|v| v.eq_ignore_ascii_case(variable))
+}
+
+/// Names of the values the subquery returns, lowercased like result columns
+fn returned_values(query: &CypherQuery, subquery: &CypherAST) -> Result<Vec<String>> {
+    let outer_variables = query.variables();
+    let mut returned = Vec::new();
+    for item in &subquery.return_clause.items {
+        let name = match (&item.alias, &item.expression) {
+            (Some(alias), _) => alias.to_lowercase(),
+            (None, ValueExpression::Variable(variable)) => variable.to_lowercase(),
+            (None, expression) => {
+                return Err(GraphError::PlanError {
+                    message: format!(
+                        "CALL subquery returns '{}' without an alias; name it, e.g. `RETURN f.name AS friend`",
+                        to_cypher_column_name(expression)
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        };
+        let reserved = name == ROW_ID || name.starts_with(OUTER_PREFIX);
+        if reserved
+            || outer_variables
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(&name))
+        {
+            return Err(GraphError::PlanError {
+                message: format!(
+                    "Variable '{}' returned by the CALL subquery is already defined",
+                    name
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        returned.push(name);
+    }
+    Ok(returned)
+}
+
+/// What the clauses after CALL can read, and the outer properties they read
+struct Scope {
+    /// Values returned by the subquery
+    returned: Vec<String>,
+    /// Aliases of RETURN items, visible to ORDER BY
+    aliases: Vec<String>,
+    /// Outer properties, in the order of their `callouter<i>` columns
+    outer: Vec<PropertyRef>,
+}
+
+impl Scope {
+    /// Point `expression` at the columns of the combined rows
+    fn rewrite(&mut self, expression: &mut ValueExpression) -> Result<()> {
+        match expression {
+            ValueExpression::Variable(name) if name.as_str() == "*" => {}
+            ValueExpression::Variable(name) => {
+                let lower = name.to_lowercase();
+                if self.returned.contains(&lower) {
+                    *expression =
+                        ValueExpression::Property(PropertyRef::new(ROWS_VARIABLE, lower.as_str()));
+                } else if !self.aliases.contains(&lower) {
+                    return Err(GraphError::UnsupportedFeature {
+                        feature: format!(
+                            "'{}' after a CALL subquery; only the values it returns and properties \
+                             of outer variables are in scope",
+                            name
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+            }
+            ValueExpression::Property(property)
+            | ValueExpression::Literal(PropertyValue::Property(property)) => {
+                if self.returned.contains(&property.variable.to_lowercase()) {
+                    return Err(GraphError::UnsupportedFeature {
+                        feature: format!(
+                            "Property access on '{}', a value returned by a CALL subquery",
+                            property.variable
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                let index = match self.outer.iter().position(|p| {
+                    p.variable.eq_ignore_ascii_case(&property.variable)
+                        && p.property.eq_ignore_ascii_case(&property.property)
+                }) {
+                    Some(index) => index,
+                    None => {
+                        self.outer.push(property.clone());
+                        self.outer.len() - 1
+                    }
+                };
+                *property = PropertyRef::new(ROWS_VARIABLE.to_string(), outer_column(index));
+            }
+            ValueExpression::ScalarFunction { args, .. }
+            | ValueExpression::AggregateFunction { args, .. } => {
+                for arg in args {
+                    self.rewrite(arg)?;
+                }
+            }
+            ValueExpression::Arithmetic { left, right, .. }
+            | ValueExpression::VectorDistance { left, right, .. }
+            | ValueExpression::VectorSimilarity { left, right, .. } => {
+                self.rewrite(left)?;
+                self.rewrite(right)?;
+            }
+            ValueExpression::Case { branches, default } => {
+                for branch in branches {
+                    self.rewrite_condition(&mut branch.condition)?;
+                    self.rewrite(&mut branch.value)?;
+                }
+                if let Some(default) = default {
+                    self.rewrite(default)?;
+                }
+            }
+            ValueExpression::ListComprehension {
+                variable,
+                list,
+                predicate,
+                projection,
+            } => {
+                self.rewrite(list)?;
+                // The element variable shadows returned values of the same name
+                let element = variable.to_lowercase();
+                let shadowed = self.returned.iter().position(|r| *r == element);
+                let shadowed = shadowed.map(|index| self.returned.remove(index));
+                self.aliases.push(element);
+                let mut result = Ok(());
+                if let Some(predicate) = predicate {
+                    result = self.rewrite_condition(predicate);
+                }
+                if let (Ok(()), Some(projection)) = (&result, projection) {
+                    result = self.rewrite(projection);
+                }
+                self.aliases.pop();
+                self.returned.extend(shadowed);
+                result?;
+            }
+            ValueExpression::Reduce {
+                accumulator,
+                initial,
+                variable,
+                list,
+                expression,
+            } => {
+                self.rewrite(initial)?;
+                self.rewrite(list)?;
+                // Both variables shadow returned values of the same name
+                let locals = [accumulator.to_lowercase(), variable.to_lowercase()];
+                let mut shadowed = Vec::new();
+                self.returned.retain(|r| {
+                    let local = locals.contains(r);
+                    if local {
+                        shadowed.push(r.clone());
+                    }
+                    !local
+                });
+                self.aliases.extend(locals);
+                let result = self.rewrite(expression);
+                self.aliases.truncate(self.aliases.len() - 2);
+                self.returned.extend(shadowed);
+                result?;
+            }
+            ValueExpression::MapProjection { items, .. } => {
+                for item in items {
+                    self.rewrite(&mut item.value)?;
+                }
+            }
+            ValueExpression::PatternComprehension { .. } => {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: "pattern comprehensions after a CALL subquery".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            ValueExpression::CountSubquery { .. } => {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: "COUNT subqueries after a CALL subquery".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            ValueExpression::Literal(_)
+            | ValueExpression::Parameter(_)
+            | ValueExpression::VectorLiteral(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Point the operands of a condition at the columns of the combined rows
+    fn rewrite_condition(&mut self, condition: &mut BooleanExpression) -> Result<()> {
+        match condition {
+            BooleanExpression::Comparison { left, right, .. } => {
+                self.rewrite(left)?;
+                self.rewrite(right)?;
+            }
+            BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+                self.rewrite_condition(left)?;
+                self.rewrite_condition(right)?;
+            }
+            BooleanExpression::Not(inner) => self.rewrite_condition(inner)?,
+            BooleanExpression::In { expression, list } => {
+                self.rewrite(expression)?;
+                for item in list {
+                    self.rewrite(item)?;
+                }
+            }
+            BooleanExpression::Like { expression, .. }
+            | BooleanExpression::ILike { expression, .. }
+            | BooleanExpression::Contains { expression, .. }
+            | BooleanExpression::StartsWith { expression, .. }
+            | BooleanExpression::EndsWith { expression, .. }
+            | BooleanExpression::RegexMatch { expression, .. }
+            | BooleanExpression::IsNull(expression)
+            | BooleanExpression::IsNotNull(expression) => self.rewrite(expression)?,
+            BooleanExpression::Exists(property) => {
+                let mut value = ValueExpression::Property(property.clone());
+                self.rewrite(&mut value)?;
+                *condition = BooleanExpression::IsNotNull(value);
+            }
+            BooleanExpression::ListPredicate {
+                variable,
+                list,
+                predicate,
+                ..
+            } => {
+                self.rewrite(list)?;
+                // The element variable shadows returned values of the same name
+                let element = variable.to_lowercase();
+                let shadowed = self.returned.iter().position(|r| *r == element);
+                let shadowed = shadowed.map(|index| self.returned.remove(index));
+                self.aliases.push(element);
+                let result = self.rewrite_condition(predicate);
+                self.aliases.pop();
+                self.returned.extend(shadowed);
+                result?;
+            }
+            BooleanExpression::AllInPath { .. } => {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: "path predicates after a CALL subquery".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            BooleanExpression::ExistsSubquery { .. } => {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: "EXISTS subqueries after a CALL subquery".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn outer_column(index: usize) -> String {
+    format!("{}{}", OUTER_PREFIX, index)
+}
+
+fn import_column(import: usize, key: usize) -> String {
+    format!("{}{}_{}", IMPORT_PREFIX, import, key)
+}
+
+/// The clauses after CALL, reading the combined rows and keeping those
+/// `filter` holds for
+fn rows_ast(
+    ast: &CypherAST,
+    filter: Option<&BooleanExpression>,
+    scope: &mut Scope,
+) -> Result<CypherAST> {
+    let mut rows = ast.clone();
+    rows.reading_clauses = vec![ReadingClause::Match(MatchClause {
+        patterns: vec![GraphPattern::Node(
+            NodePattern::new(Some(ROWS_VARIABLE.to_string())).with_label(ROWS_LABEL),
+        )],
+        optional: false,
+        where_clause: None,
+    })];
+    rows.where_clause = match filter {
+        Some(filter) => {
+            let mut expression = filter.clone();
+            scope.rewrite_condition(&mut expression)?;
+            Some(WhereClause { expression })
+        }
+        None => None,
+    };
+    rows.sample = None;
+    rows.include_deleted = false;
+    rows.call_subquery = None;
+
+    for item in &mut rows.return_clause.items {
+        // Keep the column names the query would have without CALL
+        let alias = item
+            .alias
+            .get_or_insert_with(|| to_cypher_column_name(&item.expression));
+        scope.aliases.push(alias.to_lowercase());
+    }
+    let aliases = std::mem::take(&mut scope.aliases);
+    for item in &mut rows.return_clause.items {
+        scope.rewrite(&mut item.expression)?;
+    }
+    for key in &mut rows.return_clause.distinct_on {
+        scope.rewrite(key)?;
+    }
+    scope.aliases = aliases;
+    if let Some(order_by) = &mut rows.order_by {
+        for item in &mut order_by.items {
+            scope.rewrite(&mut item.expression)?;
+        }
+    }
+    Ok(rows)
+}
+
+/// The outer query up to CALL, returning the import keys and the outer
+/// properties read after CALL
+fn driver_ast(ast: &CypherAST, imports: &[Import], outer: &[PropertyRef]) -> CypherAST {
+    let mut driver = ast.clone();
+    driver.call_subquery = None;
+    driver.order_by = None;
+    driver.skip = None;
+    driver.limit = None;
+
+    let mut items = Vec::new();
+    for (i, import) in imports.iter().enumerate() {
+        for (j, key) in import.keys.iter().enumerate() {
+            items.push(ReturnItem {
+                expression: ValueExpression::Property(PropertyRef::new(&import.variable, key)),
+                alias: Some(import_column(i, j)),
+            });
+        }
+    }
+    for (i, property) in outer.iter().enumerate() {
+        items.push(ReturnItem {
+            expression: ValueExpression::Property(property.clone()),
+            alias: Some(outer_column(i)),
+        });
+    }
+    if items.is_empty() {
+        // Only the number of outer rows matters
+        items.push(ReturnItem {
+            expression: ValueExpression::Literal(PropertyValue::Integer(1)),
+            alias: Some(ROW_ID.to_string()),
+        });
+    }
+    driver.return_clause = ReturnClause {
+        distinct: false,
+        distinct_on: Vec::new(),
+        items,
+    };
+    driver
+}
+
+/// Import key values of every outer row; `None` where a key is null
+fn import_keys(outer: &RecordBatch, imports: &[Import]) -> Result<Vec<Option<Vec<PropertyValue>>>> {
+    let columns: Vec<String> = imports
+        .iter()
+        .enumerate()
+        .flat_map(|(i, import)| (0..import.keys.len()).map(move |j| import_column(i, j)))
+        .collect();
+    if columns.is_empty() {
+        return Ok(vec![Some(Vec::new()); outer.num_rows()]);
+    }
+    let indices = columns
+        .iter()
+        .map(|name| outer.schema().index_of(name))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let keys = outer.project(&indices)?;
+
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    writer.write(&keys)?;
+    writer.finish()?;
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_slice(&writer.into_inner()).map_err(|e| GraphError::ExecutionError {
+            message: format!("Failed to read CALL subquery imports: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    // A batch without rows is written as no JSON objects at all
+    Ok(rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|name| row.get(name).and_then(PropertyValue::from_json))
+                .collect::<Option<Vec<_>>>()
+                .filter(|key| !key.contains(&PropertyValue::Null))
+        })
+        .collect())
+}
+
+/// `subquery` with every import pinned to the node of `key`
+///
+/// `key` holds the values of the key columns of all imports, in order.
+fn pin_imports(subquery: &CypherAST, imports: &[Import], key: &[PropertyValue]) -> CypherAST {
+    let mut pinned = subquery.clone();
+    let mut values = key.iter();
+    for import in imports {
+        let mut bound = false;
+        for clause in &mut pinned.reading_clauses {
+            let ReadingClause::Match(match_clause) = clause else {
+                continue;
+            };
+            for pattern in &mut match_clause.patterns {
+                let nodes: Vec<&mut NodePattern> = match pattern {
+                    GraphPattern::Node(node) => vec![node],
+                    GraphPattern::Path(path) => std::iter::once(&mut path.start_node)
+                        .chain(
+                            path.segments
+                                .iter_mut()
+                                .map(|segment| &mut segment.end_node),
+                        )
+                        .collect(),
+                };
+                for node in nodes
+                    .into_iter()
+                    .filter(|node| binds(node, &import.variable))
+                {
+                    bound = true;
+                    if node.labels.is_empty() {
+                        node.labels.push(import.label.clone());
+                    }
+                }
+            }
+        }
+        if !bound {
+            pinned.reading_clauses.insert(
+                0,
+                ReadingClause::Match(MatchClause {
+                    patterns: vec![GraphPattern::Node(
+                        NodePattern::new(Some(import.variable.clone())).with_label(&import.label),
+                    )],
+                    optional: false,
+                    where_clause: None,
+                }),
+            );
+        }
+
+        for (column, value) in import.keys.iter().zip(&mut values) {
+            let filter = BooleanExpression::Comparison {
+                left: ValueExpression::Property(PropertyRef::new(&import.variable, column)),
+                operator: ComparisonOperator::Equal,
+                right: ValueExpression::Literal(value.clone()),
+            };
+            let expression = match pinned.where_clause.take() {
+                Some(existing) => {
+                    BooleanExpression::And(Box::new(existing.expression), Box::new(filter))
+                }
+                None => filter,
+            };
+            pinned.where_clause = Some(WhereClause { expression });
+        }
+    }
+    pinned
+}
+
+/// The combined rows: an id, the outer properties and the returned values of
+/// each pair of outer and subquery rows
+fn combine(
+    outer: Option<&RecordBatch>,
+    inner: &RecordBatch,
+    outer_indices: Vec<u32>,
+    inner_indices: Vec<u32>,
+) -> Result<RecordBatch> {
+    let outer_indices = UInt32Array::from(outer_indices);
+    let inner_indices = UInt32Array::from(inner_indices);
+    let mut fields = vec![Field::new(ROW_ID, DataType::Int64, false)];
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(Int64Array::from_iter_values(
+        0..inner_indices.len() as i64,
+    ))];
+    if let Some(outer) = outer {
+        let schema = outer.schema();
+        for (field, column) in schema.fields().iter().zip(outer.columns()) {
+            if field.name().starts_with(OUTER_PREFIX) {
+                fields.push(field.as_ref().clone());
+                columns.push(take(column, &outer_indices, None)?);
+            }
+        }
+    }
+    let schema = inner.schema();
+    for (field, column) in schema.fields().iter().zip(inner.columns()) {
+        fields.push(field.as_ref().clone());
+        columns.push(take(column, &inner_indices, None)?);
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_cypher_query;
+
+    fn parse(cypher: &str) -> (CypherAST, CallSubquery) {
+        let ast = parse_cypher_query(cypher).unwrap();
+        let call = ast.call_subquery.clone().unwrap();
+        (ast, call)
+    }
+
+    #[test]
+    fn test_pin_imports_labels_and_filters_the_imported_node() {
+        let (ast, call) = parse(
+            "MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) \
+             WHERE f.age > 30 RETURN f.name AS friend } RETURN p.name, friend",
+        );
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        let imports = resolve_imports(&ast, &call.imports, &config).unwrap();
+        assert_eq!(imports[0].label, "Person");
+        assert_eq!(imports[0].keys, vec!["id".to_string()]);
+
+        let pinned = pin_imports(&call.query, &imports, &[PropertyValue::Integer(7)]);
+        let ReadingClause::Match(match_clause) = &pinned.reading_clauses[0] else {
+            panic!("Expected match clause");
+        };
+        let GraphPattern::Path(path) = &match_clause.patterns[0] else {
+            panic!("Expected path pattern");
+        };
+        assert_eq!(path.start_node.labels, vec!["Person".to_string()]);
+        let BooleanExpression::And(_, filter) = pinned.where_clause.unwrap().expression else {
+            panic!("Expected the key filter to be added to the WHERE clause");
+        };
+        assert_eq!(
+            *filter,
+            BooleanExpression::Comparison {
+                left: ValueExpression::Property(PropertyRef::new("p", "id")),
+                operator: ComparisonOperator::Equal,
+                right: ValueExpression::Literal(PropertyValue::Integer(7)),
+            }
+        );
+
+        // Importing anything but a labeled node is an error
+        let (ast, call) = parse(
+            "MATCH (p:Person)-[r:KNOWS]->(f:Person) CALL { WITH r RETURN 1 AS one } RETURN one",
+        );
+        assert!(resolve_imports(&ast, &call.imports, &config).is_err());
+    }
+
+    #[test]
+    fn test_clauses_after_call_read_the_combined_rows() {
+        let (ast, _) = parse(
+            "MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name AS friend } \
+             RETURN p.name, friend AS name ORDER BY name",
+        );
+        let mut scope = Scope {
+            returned: vec!["friend".to_string()],
+            aliases: Vec::new(),
+            outer: Vec::new(),
+        };
+        let rows = rows_ast(&ast, None, &mut scope).unwrap();
+        assert_eq!(scope.outer, vec![PropertyRef::new("p", "name")]);
+
+        let items = &rows.return_clause.items;
+        assert_eq!(items[0].alias.as_deref(), Some("p.name"));
+        assert_eq!(
+            items[0].expression,
+            ValueExpression::Property(PropertyRef::new(ROWS_VARIABLE, "callouter0"))
+        );
+        assert_eq!(
+            items[1].expression,
+            ValueExpression::Property(PropertyRef::new(ROWS_VARIABLE, "friend"))
+        );
+        // ORDER BY may name a RETURN alias
+        assert_eq!(
+            rows.order_by.unwrap().items[0].expression,
+            ValueExpression::Variable("name".to_string())
+        );
+
+        let (ast, _) = parse(
+            "MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name AS friend } \
+             RETURN p, friend",
+        );
+        let mut scope = Scope {
+            returned: vec!["friend".to_string()],
+            aliases: Vec::new(),
+            outer: Vec::new(),
+        };
+        assert!(rows_ast(&ast, None, &mut scope).is_err());
+    }
+}
@@ -107,6 +107,51 @@ pub fn qualify_column(alias: &str, property: &str) -> String {
     format!("{}__{}", alias.to_lowercase(), property.to_lowercase())
 }
 
+/// Qualify paired key columns on both sides of a (possibly composite) equi-join.
+///
+/// Fails when the two sides declare keys of different arity, e.g. a relationship
+/// referencing `(src_tenant, src_id)` joined to a node keyed by `id` alone.
+///
+/// # Examples
+///
+/// ```
+/// use lance_graph::case_insensitive::qualify_key_pairs;
+///
+/// let (left, right) =
+///     qualify_key_pairs("a", &["tenant", "id"], "r", &["src_tenant", "src_id"]).unwrap();
+/// assert_eq!(left, vec!["a__tenant", "a__id"]);
+/// assert_eq!(right, vec!["r__src_tenant", "r__src_id"]);
+/// ```
+pub fn qualify_key_pairs(
+    left_alias: &str,
+    left_columns: &[&str],
+    right_alias: &str,
+    right_columns: &[&str],
+) -> crate::error::Result<(Vec<String>, Vec<String>)> {
+    if left_columns.len() != right_columns.len() {
+        return Err(crate::error::GraphError::PlanError {
+            message: format!(
+                "Composite key mismatch joining '{}' ({}) with '{}' ({})",
+                left_alias,
+                left_columns.join(", "),
+                right_alias,
+                right_columns.join(", ")
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+
+    let left_keys = left_columns
+        .iter()
+        .map(|c| qualify_column(left_alias, c))
+        .collect();
+    let right_keys = right_columns
+        .iter()
+        .map(|c| qualify_column(right_alias, c))
+        .collect();
+    Ok((left_keys, right_keys))
+}
+
 /// Helper trait for case-insensitive lookups on standard HashMap<String, V>
 ///
 /// This trait provides extension methods for performing case-insensitive
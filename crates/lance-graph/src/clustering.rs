@@ -0,0 +1,471 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Declared sort orders of label and relationship datasets
+//!
+//! Datasets are often written in the order of a key, e.g. nodes by id or
+//! edges by source id. [`NodeMapping::sorted_by`] and
+//! [`RelationshipMapping::sorted_by`] declare that order: the rows of the
+//! dataset are stored in ascending order of the listed columns, nulls last.
+//! Scans of such a dataset report that order to DataFusion, which then skips
+//! sorts it already satisfies (`ORDER BY n.id` over nodes sorted by `id`)
+//! and keeps the order through filters and projections.
+//!
+//! When every label is sorted by its key and every relationship type by its
+//! source key, the first join of each hop reads both sides in join key
+//! order, and the session prefers sort-merge joins over hash joins.
+//!
+//! The engine trusts the declaration: results of a query over a dataset that
+//! is not in its declared order may be out of order. [`validate_sort_orders`]
+//! checks the declarations against the datasets, fragment by fragment: the
+//! rows of every fragment must be in order, and each fragment must start at
+//! or after the row the fragment before it ended with.
+//!
+//! ```ignore
+//! use lance_graph::clustering::validate_sort_orders;
+//! use lance_graph::config::{GraphConfig, NodeMapping};
+//!
+//! let config = GraphConfig::builder()
+//!     .with_node_mapping(
+//!         NodeMapping::new("Person", "id").with_sorted_by(vec!["id".to_string()]),
+//!     )
+//!     .build()?;
+//! for violation in validate_sort_orders(&config, &namespace).await? {
+//!     eprintln!("{}", violation);
+//! }
+//! ```
+//!
+//! Scans only report the order when they read the dataset as a single
+//! partition; a scan split into several partitions is sorted as before.
+
+use crate::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use crate::error::Result;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use arrow_schema::{SchemaRef, SortOptions};
+use async_trait::async_trait;
+use datafusion::catalog::Session;
+use datafusion::common::{Constraints, Statistics};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result as DFResult;
+use datafusion::execution::context::{SessionConfig, SessionContext};
+use datafusion::execution::TaskContext;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{EquivalenceProperties, PhysicalSortExpr};
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties, SendableRecordBatchStream,
+};
+use futures::TryStreamExt;
+use lance_graph_catalog::DirNamespace;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Order rows are declared in: ascending, nulls last
+const DECLARED_ORDER: SortOptions = SortOptions {
+    descending: false,
+    nulls_first: false,
+};
+
+/// A dataset whose rows are not in their declared order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOrderViolation {
+    /// Dataset the order is declared for
+    pub table: String,
+    /// Fragment holding the first row out of order
+    pub fragment_id: u64,
+    /// What is out of order
+    pub message: String,
+}
+
+impl fmt::Display for SortOrderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' fragment {}: {}",
+            self.table, self.fragment_id, self.message
+        )
+    }
+}
+
+/// Columns `table` is declared sorted by, if any
+///
+/// Labels sharing a table take the declaration of the first label, by name,
+/// that has one.
+pub fn declared_order<'c>(config: &'c GraphConfig, table: &str) -> Option<&'c [String]> {
+    let mut labels: Vec<&NodeMapping> = config
+        .node_mappings
+        .values()
+        .filter(|mapping| mapping.view_of.is_none())
+        .filter(|mapping| mapping.table_name().eq_ignore_ascii_case(table))
+        .collect();
+    labels.sort_by(|a, b| a.label.cmp(&b.label));
+    let relationships = config
+        .relationship_mappings
+        .values()
+        .filter(|mapping| mapping.relationship_type.eq_ignore_ascii_case(table))
+        .map(|mapping| &mapping.sorted_by);
+    labels
+        .into_iter()
+        .map(|mapping| &mapping.sorted_by)
+        .chain(relationships)
+        .find(|columns| !columns.is_empty())
+        .map(Vec::as_slice)
+}
+
+/// Whether every label is declared sorted by its key and every relationship
+/// type by its source key, so hops join inputs already in join key order
+pub fn prefers_merge_joins(config: &GraphConfig) -> bool {
+    let starts_with = |columns: &[String], prefix: Vec<&str>| {
+        columns.len() >= prefix.len()
+            && columns
+                .iter()
+                .zip(prefix)
+                .all(|(column, key)| column.eq_ignore_ascii_case(key))
+    };
+    let labels_sorted = config
+        .node_mappings
+        .values()
+        .filter(|mapping| mapping.view_of.is_none())
+        .all(|mapping| {
+            declared_order(config, mapping.table_name())
+                .is_some_and(|columns| starts_with(columns, mapping.key_columns()))
+        });
+    let relationships_sorted =
+        config
+            .relationship_mappings
+            .values()
+            .all(|mapping: &RelationshipMapping| {
+                starts_with(&mapping.sorted_by, mapping.source_key_columns())
+            });
+    !config.relationship_mappings.is_empty() && labels_sorted && relationships_sorted
+}
+
+/// Session context for queries over the datasets of `config`
+pub(crate) fn session_context(config: Option<&GraphConfig>) -> SessionContext {
+    let Some(config) = config else {
+        return SessionContext::new();
+    };
+    let declares_orders = config
+        .node_mappings
+        .values()
+        .map(|mapping| &mapping.sorted_by)
+        .chain(config.relationship_mappings.values().map(|m| &m.sorted_by))
+        .any(|columns| !columns.is_empty());
+    if !declares_orders {
+        return SessionContext::new();
+    }
+    // Keep declared orders through repartitioning instead of re-sorting
+    let session = SessionConfig::new()
+        .set_bool("datafusion.optimizer.prefer_existing_sort", true)
+        .set_bool(
+            "datafusion.optimizer.prefer_hash_join",
+            !prefers_merge_joins(config),
+        );
+    SessionContext::new_with_config(session)
+}
+
+/// `provider` of `table`, reporting the order `config` declares for it
+pub(crate) fn with_declared_order(
+    config: Option<&GraphConfig>,
+    table: &str,
+    provider: Arc<dyn TableProvider>,
+) -> Arc<dyn TableProvider> {
+    match config.and_then(|config| declared_order(config, table)) {
+        Some(columns) => Arc::new(SortedTable {
+            inner: provider,
+            columns: columns.to_vec(),
+        }),
+        None => provider,
+    }
+}
+
+/// A table whose scans report that rows come in ascending order of `columns`
+#[derive(Debug)]
+struct SortedTable {
+    inner: Arc<dyn TableProvider>,
+    columns: Vec<String>,
+}
+
+#[async_trait]
+impl TableProvider for SortedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn constraints(&self) -> Option<&Constraints> {
+        self.inner.constraints()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let plan = self.inner.scan(state, projection, filters, limit).await?;
+        Ok(SortedScanExec::wrap(plan, &self.columns))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        self.inner.statistics()
+    }
+}
+
+/// A single-partition scan known to return rows in a declared order
+#[derive(Debug)]
+struct SortedScanExec {
+    input: Arc<dyn ExecutionPlan>,
+    columns: Vec<String>,
+    properties: PlanProperties,
+}
+
+impl SortedScanExec {
+    /// `input` reporting the order of the longest prefix of `columns` it
+    /// returns, or `input` itself if it returns none of them or has several
+    /// partitions
+    fn wrap(input: Arc<dyn ExecutionPlan>, columns: &[String]) -> Arc<dyn ExecutionPlan> {
+        if input.output_partitioning().partition_count() != 1 {
+            return input;
+        }
+        let schema = input.schema();
+        let ordering: Vec<PhysicalSortExpr> = columns
+            .iter()
+            .map_while(|name| {
+                let index = schema
+                    .fields()
+                    .iter()
+                    .position(|field| field.name().eq_ignore_ascii_case(name))?;
+                let column = Column::new(schema.field(index).name(), index);
+                Some(PhysicalSortExpr::new(Arc::new(column), DECLARED_ORDER))
+            })
+            .collect();
+        if ordering.is_empty() {
+            return input;
+        }
+        let input_properties = input.properties();
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new_with_orderings(schema, [ordering]),
+            input_properties.partitioning.clone(),
+            input_properties.emission_type,
+            input_properties.boundedness,
+        );
+        Arc::new(Self {
+            input,
+            columns: columns.to_vec(),
+            properties,
+        })
+    }
+}
+
+impl DisplayAs for SortedScanExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SortedScanExec: sorted_by=[{}]", self.columns.join(", "))
+    }
+}
+
+impl ExecutionPlan for SortedScanExec {
+    fn name(&self) -> &str {
+        "SortedScanExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        // Re-checked, as the new input may no longer be a single partition
+        Ok(Self::wrap(children.swap_remove(0), &self.columns))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        self.input.execute(partition, context)
+    }
+}
+
+/// Check the declared sort orders of `config` against the latest versions
+/// of the datasets in `namespace`
+///
+/// Returns the first row out of order of each dataset that is not in its
+/// declared order. Reads the sort columns of every declared dataset in full.
+pub async fn validate_sort_orders(
+    config: &GraphConfig,
+    namespace: &DirNamespace,
+) -> Result<Vec<SortOrderViolation>> {
+    let mut tables: Vec<&str> = config
+        .node_mappings
+        .values()
+        .filter(|mapping| mapping.view_of.is_none())
+        .map(NodeMapping::table_name)
+        .chain(
+            config
+                .relationship_mappings
+                .values()
+                .map(|mapping| mapping.relationship_type.as_str()),
+        )
+        .collect();
+    tables.sort_unstable();
+    tables.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+    let mut violations = Vec::new();
+    for table in tables {
+        let Some(columns) = declared_order(config, table) else {
+            continue;
+        };
+        let Some(dataset) = crate::graph_diff::open_latest(namespace, table).await? else {
+            continue;
+        };
+        if let Some(violation) = check_order(&dataset, table, columns).await? {
+            violations.push(violation);
+        }
+    }
+    Ok(violations)
+}
+
+/// The first row of `dataset` out of ascending order of `columns`, if any
+async fn check_order(
+    dataset: &lance::dataset::Dataset,
+    table: &str,
+    columns: &[String],
+) -> Result<Option<SortOrderViolation>> {
+    let schema = arrow_schema::Schema::from(dataset.schema());
+    let mut projection = Vec::with_capacity(columns.len());
+    let mut fields = Vec::with_capacity(columns.len());
+    for name in columns {
+        let field = schema
+            .fields()
+            .iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| crate::error::GraphError::ConfigError {
+                message: format!(
+                    "'{}' is declared sorted by missing column '{}'",
+                    table, name
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        projection.push(field.name().clone());
+        fields.push(SortField::new_with_options(
+            field.data_type().clone(),
+            DECLARED_ORDER,
+        ));
+    }
+    let converter = RowConverter::new(fields)?;
+    let violation = |fragment_id: u64, message: String| {
+        Some(SortOrderViolation {
+            table: table.to_string(),
+            fragment_id,
+            message,
+        })
+    };
+
+    // Last row of the fragments read so far, and the fragment holding it
+    let mut previous: Option<(u64, OwnedRow)> = None;
+    for fragment in dataset.get_fragments() {
+        let fragment_id = fragment.id() as u64;
+        let mut scanner = fragment.scan();
+        scanner.project(&projection)?;
+        let batches: Vec<_> = scanner.try_into_stream().await?.try_collect().await?;
+        for batch in batches {
+            let rows = converter.convert_columns(batch.columns())?;
+            for row in rows.iter() {
+                if let Some((previous_fragment, last)) = &previous {
+                    if row < last.row() {
+                        let message = if *previous_fragment == fragment_id {
+                            format!(
+                                "rows are not in order of ({}) within the fragment",
+                                columns.join(", ")
+                            )
+                        } else {
+                            format!(
+                                "fragment starts before fragment {} ends in order of ({})",
+                                previous_fragment,
+                                columns.join(", ")
+                            )
+                        };
+                        return Ok(violation(fragment_id, message));
+                    }
+                }
+                previous = Some((fragment_id, row.owned()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RelationshipMapping;
+
+    fn sorted(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_declared_order_and_merge_join_preference() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_sorted_by(sorted(&["id"])))
+            .with_relationship_mapping(
+                RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                    .with_sorted_by(sorted(&["SRC_ID", "dst_id"])),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            declared_order(&config, "person"),
+            Some(sorted(&["id"]).as_slice())
+        );
+        assert!(prefers_merge_joins(&config));
+
+        // Edges sorted by their target do not line up with the source join
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_sorted_by(sorted(&["id"])))
+            .with_relationship_mapping(
+                RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                    .with_sorted_by(sorted(&["dst_id"])),
+            )
+            .build()
+            .unwrap();
+        assert!(!prefers_merge_joins(&config));
+        assert_eq!(declared_order(&config, "Company"), None);
+    }
+}
@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Coalescing of streamed result batches
+//!
+//! Selective filters and joins leave DataFusion producing many batches of a
+//! few rows each, and drivers pay a fixed cost for every batch they receive.
+//! [`BatchCoalescing`] sets a target size for the batches
+//! [`CypherQuery::execute_into`] and [`CypherQuery::execute_with_callback`]
+//! deliver: smaller batches are buffered and concatenated until the buffer
+//! reaches the target rows or bytes, whichever comes first. Batches already at
+//! the target are passed on as they are, and whatever is buffered when the
+//! query ends is delivered as a last, smaller batch.
+//!
+//! ```ignore
+//! use lance_graph::coalesce::BatchCoalescing;
+//!
+//! let query = CypherQuery::new("MATCH (p:Person) WHERE p.age > 90 RETURN p.name")?
+//!     .with_config(config)
+//!     .with_batch_coalescing(BatchCoalescing::new().with_target_rows(8192));
+//! query.execute_into(datasets, sender).await?;
+//! ```
+//!
+//! [`CypherQuery::execute_into`]: crate::query::CypherQuery::execute_into
+//! [`CypherQuery::execute_with_callback`]: crate::query::CypherQuery::execute_with_callback
+
+use crate::error::{GraphError, Result};
+use arrow::record_batch::RecordBatch;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::collections::VecDeque;
+
+/// Target size of the result batches delivered to the caller
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BatchCoalescing {
+    target_rows: Option<usize>,
+    target_bytes: Option<usize>,
+}
+
+impl BatchCoalescing {
+    /// No target; batches are delivered as they are produced
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffer batches until they hold at least `rows` rows
+    pub fn with_target_rows(mut self, rows: usize) -> Self {
+        self.target_rows = Some(rows);
+        self
+    }
+
+    /// Buffer batches until they take at least `bytes` bytes of memory
+    pub fn with_target_bytes(mut self, bytes: usize) -> Self {
+        self.target_bytes = Some(bytes);
+        self
+    }
+
+    /// Row count a delivered batch reaches
+    pub fn target_rows(&self) -> Option<usize> {
+        self.target_rows
+    }
+
+    /// Memory size a delivered batch reaches
+    pub fn target_bytes(&self) -> Option<usize> {
+        self.target_bytes
+    }
+
+    /// Whether no target is set
+    pub fn is_disabled(&self) -> bool {
+        self.target_rows.is_none() && self.target_bytes.is_none()
+    }
+
+    /// Whether a batch of `rows` rows taking `bytes` bytes reaches a target
+    fn is_reached(&self, rows: usize, bytes: usize) -> bool {
+        self.target_rows.is_some_and(|target| rows >= target)
+            || self.target_bytes.is_some_and(|target| bytes >= target)
+    }
+}
+
+/// Buffer of batches below the coalescing target
+#[derive(Debug)]
+struct Coalescer {
+    options: BatchCoalescing,
+    pending: Vec<RecordBatch>,
+    rows: usize,
+    bytes: usize,
+}
+
+impl Coalescer {
+    fn new(options: BatchCoalescing) -> Self {
+        Self {
+            options,
+            pending: Vec::new(),
+            rows: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Add `batch`, returning the batches that are ready to deliver
+    fn push(&mut self, batch: RecordBatch) -> Result<Vec<RecordBatch>> {
+        if batch.num_rows() == 0 {
+            return Ok(Vec::new());
+        }
+        let bytes = batch.get_array_memory_size();
+        if self.options.is_reached(batch.num_rows(), bytes) {
+            // Large enough on its own: deliver it without copying, after the
+            // smaller batches that came before it
+            let mut ready: Vec<RecordBatch> = self.flush()?.into_iter().collect();
+            ready.push(batch);
+            return Ok(ready);
+        }
+        self.rows += batch.num_rows();
+        self.bytes += bytes;
+        self.pending.push(batch);
+        if self.options.is_reached(self.rows, self.bytes) {
+            return Ok(self.flush()?.into_iter().collect());
+        }
+        Ok(Vec::new())
+    }
+
+    /// Concatenate the buffered batches into one
+    fn flush(&mut self) -> Result<Option<RecordBatch>> {
+        let mut pending = std::mem::take(&mut self.pending);
+        self.rows = 0;
+        self.bytes = 0;
+        if pending.len() <= 1 {
+            return Ok(pending.pop());
+        }
+        let schema = pending[0].schema();
+        arrow::compute::concat_batches(&schema, &pending)
+            .map(Some)
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to coalesce result batches: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+    }
+}
+
+/// `stream` with its batches coalesced to the targets of `options`
+pub(crate) fn coalesce_stream(
+    stream: BoxStream<'static, Result<RecordBatch>>,
+    options: BatchCoalescing,
+) -> BoxStream<'static, Result<RecordBatch>> {
+    if options.is_disabled() {
+        return stream;
+    }
+
+    struct State {
+        input: BoxStream<'static, Result<RecordBatch>>,
+        coalescer: Coalescer,
+        ready: VecDeque<RecordBatch>,
+        finished: bool,
+    }
+
+    let state = State {
+        input: stream,
+        coalescer: Coalescer::new(options),
+        ready: VecDeque::new(),
+        finished: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(batch) = state.ready.pop_front() {
+                return Some((Ok(batch), state));
+            }
+            if state.finished {
+                return None;
+            }
+            let ready = match state.input.next().await {
+                Some(Ok(batch)) => state.coalescer.push(batch),
+                Some(Err(e)) => Err(e),
+                None => {
+                    state.finished = true;
+                    state.coalescer.flush().map(Vec::from_iter)
+                }
+            };
+            match ready {
+                Ok(ready) => state.ready.extend(ready),
+                Err(e) => {
+                    // Nothing is delivered after an error
+                    state.finished = true;
+                    state.ready.clear();
+                    return Some((Err(e), state));
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use futures::TryStreamExt;
+    use std::sync::Arc;
+
+    fn batch(values: Vec<i64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(values))]).unwrap()
+    }
+
+    async fn coalesce(batches: Vec<RecordBatch>, options: BatchCoalescing) -> Vec<usize> {
+        let input = futures::stream::iter(batches.into_iter().map(Ok)).boxed();
+        let output: Vec<RecordBatch> = coalesce_stream(input, options).try_collect().await.unwrap();
+        output.iter().map(|b| b.num_rows()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_small_batches_are_merged_to_the_target_rows() {
+        let batches = vec![
+            batch(vec![1]),
+            batch(vec![]),
+            batch(vec![2, 3]),
+            batch(vec![4]),
+            batch(vec![5, 6, 7, 8, 9]),
+            batch(vec![10]),
+        ];
+        let sizes = coalesce(batches, BatchCoalescing::new().with_target_rows(3)).await;
+
+        // The five-row batch passes through, the rest is merged around it
+        assert_eq!(sizes, vec![3, 1, 5, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_target_bytes_flushes_before_target_rows() {
+        let one = batch(vec![1]);
+        let bytes = one.get_array_memory_size();
+        let batches = vec![one.clone(), one.clone(), one.clone(), one];
+        let options = BatchCoalescing::new()
+            .with_target_rows(100)
+            .with_target_bytes(2 * bytes);
+
+        assert_eq!(coalesce(batches, options).await, vec![2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_coalescing_keeps_batches() {
+        let batches = vec![batch(vec![1]), batch(vec![]), batch(vec![2])];
+        assert!(BatchCoalescing::new().is_disabled());
+        assert_eq!(
+            coalesce(batches, BatchCoalescing::new()).await,
+            vec![1, 0, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_end_the_stream() {
+        let input = futures::stream::iter(vec![
+            Ok(batch(vec![1])),
+            Err(GraphError::ExecutionError {
+                message: "boom".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+            Ok(batch(vec![2])),
+        ])
+        .boxed();
+        let output: Vec<Result<RecordBatch>> =
+            coalesce_stream(input, BatchCoalescing::new().with_target_rows(10))
+                .collect()
+                .await;
+
+        assert_eq!(output.len(), 1);
+        assert!(output[0].is_err());
+    }
+}
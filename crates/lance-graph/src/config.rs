@@ -67,6 +67,56 @@ pub struct NodeMapping {
     pub property_fields: Vec<String>,
     /// Optional filter conditions for this node type
     pub filter_conditions: Option<String>,
+    /// Base label this label is a filtered view of (e.g., `ActiveUser` over `User`)
+    ///
+    /// View labels do not have a dataset of their own: the planner scans the base
+    /// label's dataset and inlines `filter_conditions` into the scan.
+    #[serde(default)]
+    pub view_of: Option<String>,
+    /// Columns forming a composite node key (e.g., `["tenant_id", "user_id"]`)
+    ///
+    /// When empty, `id_field` alone identifies the node.
+    #[serde(default)]
+    pub key_fields: Vec<String>,
+    /// Dataset backing this label when it differs from the label name
+    ///
+    /// Lets several labels share one "nodes" table; see `label_column`.
+    #[serde(default)]
+    pub source_table: Option<String>,
+    /// Discriminator column holding the label of each row in a shared table
+    ///
+    /// When set, scans keep only rows whose value equals this mapping's label.
+    #[serde(default)]
+    pub label_column: Option<String>,
+    /// Column marking rows as deleted (e.g., `deleted_at`)
+    ///
+    /// Scans keep only rows where this column is null unless the query says
+    /// `INCLUDE DELETED`.
+    #[serde(default)]
+    pub soft_delete_column: Option<String>,
+    /// Properties backed by a scalar index in the dataset
+    ///
+    /// Lets the planner split `n.a = 1 OR n.b = 2` into index-backed scans.
+    #[serde(default)]
+    pub indexed_properties: Vec<String>,
+    /// String properties backed by an NGram index in the dataset
+    ///
+    /// Unanchored `CONTAINS` / `LIKE '%x%'` filters on these are planned so
+    /// Lance can answer them from the index.
+    #[serde(default)]
+    pub ngram_properties: Vec<String>,
+    /// Embedding columns backed by a vector index in the dataset
+    ///
+    /// `ORDER BY vector_distance(n.col, $q) LIMIT k` on these retrieves its
+    /// candidates through the index on the named column before exact scoring.
+    #[serde(default)]
+    pub vector_properties: Vec<String>,
+    /// Columns the dataset's rows are stored in ascending order of
+    ///
+    /// Scans declare this order, so the planner skips sorts it already
+    /// satisfies; see [`crate::clustering`].
+    #[serde(default)]
+    pub sorted_by: Vec<String>,
 }
 
 /// Configuration for mapping relationship types to dataset fields
@@ -84,6 +134,44 @@ pub struct RelationshipMapping {
     pub property_fields: Vec<String>,
     /// Optional filter conditions for this relationship type
     pub filter_conditions: Option<String>,
+    /// Columns forming a composite key referencing the source node
+    ///
+    /// When empty, `source_id_field` alone references the source node.
+    #[serde(default)]
+    pub source_key_fields: Vec<String>,
+    /// Columns forming a composite key referencing the target node
+    ///
+    /// When empty, `target_id_field` alone references the target node.
+    #[serde(default)]
+    pub target_key_fields: Vec<String>,
+    /// Label of the nodes the edges start at
+    ///
+    /// DELETE finds the relationships of a node through the endpoint labels,
+    /// so it needs both of them declared.
+    #[serde(default)]
+    pub source_label: Option<String>,
+    /// Label of the nodes the edges end at
+    #[serde(default)]
+    pub target_label: Option<String>,
+    /// Whether several edges may connect the same (source, target) pair
+    ///
+    /// When false, writers keep one edge per pair (the latest write wins).
+    #[serde(default = "default_allow_parallel_edges")]
+    pub allow_parallel_edges: bool,
+    /// Column marking edges as deleted; see [`NodeMapping::soft_delete_column`]
+    #[serde(default)]
+    pub soft_delete_column: Option<String>,
+    /// Embedding columns backed by a vector index in the edge dataset
+    #[serde(default)]
+    pub vector_properties: Vec<String>,
+    /// Columns the edge dataset is stored in ascending order of; see
+    /// [`NodeMapping::sorted_by`]
+    #[serde(default)]
+    pub sorted_by: Vec<String>,
+}
+
+fn default_allow_parallel_edges() -> bool {
+    true
 }
 
 impl Default for GraphConfig {
@@ -119,12 +207,99 @@ impl GraphConfig {
         self.relationship_mappings.get(&rel_type.to_lowercase())
     }
 
+    /// Resolve a (possibly view) label to the label that owns the dataset
+    ///
+    /// Returns the base label together with the filter conditions of every view
+    /// traversed on the way, outermost view first. Labels that are not views
+    /// resolve to themselves with no filters.
+    pub fn resolve_view_chain(&self, label: &str) -> Result<(String, Vec<String>)> {
+        let mut current = label.to_string();
+        let mut filters = Vec::new();
+        let mut visited: Vec<String> = Vec::new();
+
+        loop {
+            let key = current.to_lowercase();
+            if visited.contains(&key) {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "View label '{}' has a cyclic definition through '{}'",
+                        label, current
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            visited.push(key);
+
+            let Some(mapping) = self.get_node_mapping(&current) else {
+                if visited.len() == 1 {
+                    // Unknown label: nothing to resolve, scan it as-is
+                    return Ok((current, filters));
+                }
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "View label '{}' refers to unknown base label '{}'",
+                        label, current
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            };
+
+            match &mapping.view_of {
+                Some(base) => {
+                    if let Some(filter) = &mapping.filter_conditions {
+                        filters.push(filter.clone());
+                    }
+                    current = base.clone();
+                }
+                None => return Ok((mapping.label.clone(), filters)),
+            }
+        }
+    }
+
+    /// Whether a label is a view over another label (case-insensitive)
+    pub fn is_view_label(&self, label: &str) -> bool {
+        self.get_node_mapping(label)
+            .is_some_and(|mapping| mapping.view_of.is_some())
+    }
+
+    /// Fill in id and property fields of view labels from their base label
+    fn inherit_view_fields(&mut self) -> Result<()> {
+        let views: Vec<String> = self
+            .node_mappings
+            .iter()
+            .filter(|(_, mapping)| mapping.view_of.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in views {
+            let (base_label, _) = self.resolve_view_chain(&key)?;
+            let Some(base) = self.get_node_mapping(&base_label).cloned() else {
+                continue;
+            };
+            if let Some(view) = self.node_mappings.get_mut(&key) {
+                if view.id_field.is_empty() {
+                    view.id_field = base.id_field;
+                }
+                if view.key_fields.is_empty() {
+                    view.key_fields = base.key_fields;
+                }
+                if view.property_fields.is_empty() {
+                    view.property_fields = base.property_fields;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Validate the configuration
     ///
     /// Checks for:
     /// - Empty ID fields
     /// - Non-normalized keys (must be lowercase)
     /// - Case-insensitive duplicates
+    /// - View labels whose base label is missing or cyclic
+    /// - Empty columns in composite keys or sort orders
+    /// - Empty source table or label column names
     pub fn validate(&self) -> Result<()> {
         // Validate node mappings
         for (label, mapping) in &self.node_mappings {
@@ -146,6 +321,36 @@ impl GraphConfig {
                     location: snafu::Location::new(file!(), line!(), column!()),
                 });
             }
+
+            if mapping.key_fields.iter().any(|f| f.is_empty()) {
+                return Err(GraphError::ConfigError {
+                    message: format!("Node mapping for '{}' has an empty key field", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+
+            if mapping.sorted_by.iter().any(|f| f.is_empty()) {
+                return Err(GraphError::ConfigError {
+                    message: format!("Node mapping for '{}' has an empty sort column", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+
+            if mapping.label_column.as_deref().is_some_and(str::is_empty)
+                || mapping.source_table.as_deref().is_some_and(str::is_empty)
+            {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Node mapping for '{}' has an empty source table or label column",
+                        label
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+
+            if mapping.view_of.is_some() {
+                self.resolve_view_chain(label)?;
+            }
         }
 
         // Validate relationship mappings
@@ -171,6 +376,45 @@ impl GraphConfig {
                     location: snafu::Location::new(file!(), line!(), column!()),
                 });
             }
+
+            if mapping
+                .source_key_fields
+                .iter()
+                .chain(mapping.target_key_fields.iter())
+                .any(|f| f.is_empty())
+            {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Relationship mapping for '{}' has an empty key field",
+                        rel_type
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+
+            if mapping.sorted_by.iter().any(|f| f.is_empty()) {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Relationship mapping for '{}' has an empty sort column",
+                        rel_type
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+
+            if let Some(label) = [&mapping.source_label, &mapping.target_label]
+                .into_iter()
+                .flatten()
+                .find(|label| self.get_node_mapping(label).is_none())
+            {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Relationship mapping for '{}' has unknown endpoint label '{}'",
+                        rel_type, label
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
         }
 
         Ok(())
@@ -206,6 +450,44 @@ impl GraphConfigBuilder {
                 id_field: id_field.into(),
                 property_fields: Vec::new(),
                 filter_conditions: None,
+                view_of: None,
+                key_fields: Vec::new(),
+                source_table: None,
+                label_column: None,
+                soft_delete_column: None,
+                indexed_properties: Vec::new(),
+                ngram_properties: Vec::new(),
+                vector_properties: Vec::new(),
+                sorted_by: Vec::new(),
+            },
+        );
+        self
+    }
+
+    /// Add a view label defined as a filtered subset of another label
+    ///
+    /// The view reuses the base label's dataset, id field and properties; the
+    /// `filter` is a boolean expression over the base dataset's columns, e.g.
+    /// `with_view_label("ActiveUser", "User", "active = true")`.
+    pub fn with_view_label<S: Into<String>>(mut self, label: S, base_label: S, filter: S) -> Self {
+        let label_str = label.into();
+        let normalized_key = label_str.to_lowercase();
+        self.node_mappings.insert(
+            normalized_key,
+            NodeMapping {
+                label: label_str,
+                id_field: String::new(), // Inherited from the base label in build()
+                property_fields: Vec::new(),
+                filter_conditions: Some(filter.into()),
+                view_of: Some(base_label.into()),
+                key_fields: Vec::new(),
+                source_table: None,
+                label_column: None,
+                soft_delete_column: None,
+                indexed_properties: Vec::new(),
+                ngram_properties: Vec::new(),
+                vector_properties: Vec::new(),
+                sorted_by: Vec::new(),
             },
         );
         self
@@ -236,6 +518,14 @@ impl GraphConfigBuilder {
                 type_field: None,
                 property_fields: Vec::new(),
                 filter_conditions: None,
+                source_key_fields: Vec::new(),
+                target_key_fields: Vec::new(),
+                source_label: None,
+                target_label: None,
+                allow_parallel_edges: true,
+                soft_delete_column: None,
+                vector_properties: Vec::new(),
+                sorted_by: Vec::new(),
             },
         );
         self
@@ -262,7 +552,7 @@ impl GraphConfigBuilder {
 
     /// Build the GraphConfig
     pub fn build(self) -> Result<GraphConfig> {
-        let config = GraphConfig {
+        let mut config = GraphConfig {
             node_mappings: self.node_mappings,
             relationship_mappings: self.relationship_mappings,
             default_node_id_field: self
@@ -273,6 +563,7 @@ impl GraphConfigBuilder {
                 .unwrap_or_else(|| "type".to_string()),
         };
 
+        config.inherit_view_fields()?;
         config.validate()?;
         Ok(config)
     }
@@ -286,6 +577,15 @@ impl NodeMapping {
             id_field: id_field.into(),
             property_fields: Vec::new(),
             filter_conditions: None,
+            view_of: None,
+            key_fields: Vec::new(),
+            source_table: None,
+            label_column: None,
+            soft_delete_column: None,
+            indexed_properties: Vec::new(),
+            ngram_properties: Vec::new(),
+            vector_properties: Vec::new(),
+            sorted_by: Vec::new(),
         }
     }
 
@@ -300,6 +600,103 @@ impl NodeMapping {
         self.filter_conditions = Some(filter.into());
         self
     }
+
+    /// Declare this label as a view over `base_label`
+    pub fn with_view_of<S: Into<String>>(mut self, base_label: S) -> Self {
+        self.view_of = Some(base_label.into());
+        self
+    }
+
+    /// Identify nodes by a composite key (e.g., `(tenant_id, user_id)`)
+    ///
+    /// The first column also becomes `id_field`.
+    pub fn with_composite_key(mut self, fields: Vec<String>) -> Self {
+        if let Some(first) = fields.first() {
+            self.id_field = first.clone();
+        }
+        self.key_fields = fields;
+        self
+    }
+
+    /// Store this label in a shared table disambiguated by `label_column`
+    ///
+    /// E.g., `NodeMapping::new("Person", "id").with_label_column("nodes", "kind")`
+    /// scans `nodes` keeping rows where `kind = 'Person'`. Set `property_fields`
+    /// to restrict the columns exposed for this label.
+    pub fn with_label_column<S: Into<String>>(mut self, table: S, label_column: S) -> Self {
+        self.source_table = Some(table.into());
+        self.label_column = Some(label_column.into());
+        self
+    }
+
+    /// Mark deleted rows with `column` instead of removing them
+    ///
+    /// A row counts as deleted when the column is non-null, so a nullable
+    /// `deleted_at` timestamp works as-is.
+    pub fn with_soft_delete_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.soft_delete_column = Some(column.into());
+        self
+    }
+
+    /// Declare properties that have a scalar index in the dataset
+    pub fn with_indexed_properties(mut self, properties: Vec<String>) -> Self {
+        self.indexed_properties = properties;
+        self
+    }
+
+    /// Whether `property` is declared as indexed (case-insensitive)
+    pub fn is_indexed(&self, property: &str) -> bool {
+        self.indexed_properties
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(property))
+    }
+
+    /// Declare string properties that have an NGram index in the dataset
+    pub fn with_ngram_properties(mut self, properties: Vec<String>) -> Self {
+        self.ngram_properties = properties;
+        self
+    }
+
+    /// Whether `property` is declared as NGram-indexed (case-insensitive)
+    pub fn has_ngram_index(&self, property: &str) -> bool {
+        self.ngram_properties
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(property))
+    }
+
+    /// Declare embedding columns that have a vector index in the dataset
+    pub fn with_vector_properties(mut self, properties: Vec<String>) -> Self {
+        self.vector_properties = properties;
+        self
+    }
+
+    /// Whether `property` is declared as vector-indexed (case-insensitive)
+    pub fn has_vector_index(&self, property: &str) -> bool {
+        self.vector_properties
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(property))
+    }
+
+    /// Declare that the dataset's rows are stored in ascending order of
+    /// `columns`
+    pub fn with_sorted_by(mut self, columns: Vec<String>) -> Self {
+        self.sorted_by = columns;
+        self
+    }
+
+    /// Name of the dataset backing this label
+    pub fn table_name(&self) -> &str {
+        self.source_table.as_deref().unwrap_or(&self.label)
+    }
+
+    /// Columns identifying a node, in declaration order
+    pub fn key_columns(&self) -> Vec<&str> {
+        if self.key_fields.is_empty() {
+            vec![self.id_field.as_str()]
+        } else {
+            self.key_fields.iter().map(String::as_str).collect()
+        }
+    }
 }
 
 impl RelationshipMapping {
@@ -312,6 +709,14 @@ impl RelationshipMapping {
             type_field: None,
             property_fields: Vec::new(),
             filter_conditions: None,
+            source_key_fields: Vec::new(),
+            target_key_fields: Vec::new(),
+            source_label: None,
+            target_label: None,
+            allow_parallel_edges: true,
+            soft_delete_column: None,
+            vector_properties: Vec::new(),
+            sorted_by: Vec::new(),
         }
     }
 
@@ -332,6 +737,90 @@ impl RelationshipMapping {
         self.filter_conditions = Some(filter.into());
         self
     }
+
+    /// Declare the labels of the nodes the edges start and end at
+    pub fn with_endpoints<S: Into<String>>(mut self, source_label: S, target_label: S) -> Self {
+        self.source_label = Some(source_label.into());
+        self.target_label = Some(target_label.into());
+        self
+    }
+
+    /// Declare whether parallel edges between the same node pair are allowed
+    pub fn with_parallel_edges(mut self, allowed: bool) -> Self {
+        self.allow_parallel_edges = allowed;
+        self
+    }
+
+    /// Mark deleted edges with `column` instead of removing them
+    pub fn with_soft_delete_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.soft_delete_column = Some(column.into());
+        self
+    }
+
+    /// Declare embedding columns that have a vector index in the edge dataset
+    pub fn with_vector_properties(mut self, properties: Vec<String>) -> Self {
+        self.vector_properties = properties;
+        self
+    }
+
+    /// Whether `property` is declared as vector-indexed (case-insensitive)
+    pub fn has_vector_index(&self, property: &str) -> bool {
+        self.vector_properties
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(property))
+    }
+
+    /// Declare that the edge dataset's rows are stored in ascending order of
+    /// `columns`
+    pub fn with_sorted_by(mut self, columns: Vec<String>) -> Self {
+        self.sorted_by = columns;
+        self
+    }
+
+    /// Columns identifying an edge when parallel edges are not allowed
+    pub fn edge_key_columns(&self) -> Vec<&str> {
+        let mut columns = self.source_key_columns();
+        columns.extend(self.target_key_columns());
+        columns
+    }
+
+    /// Reference source and target nodes by composite keys
+    ///
+    /// E.g., `(src_tenant, src_id)` and `(dst_tenant, dst_id)`. The first column
+    /// of each key also becomes `source_id_field` / `target_id_field`.
+    pub fn with_composite_keys(
+        mut self,
+        source_fields: Vec<String>,
+        target_fields: Vec<String>,
+    ) -> Self {
+        if let Some(first) = source_fields.first() {
+            self.source_id_field = first.clone();
+        }
+        if let Some(first) = target_fields.first() {
+            self.target_id_field = first.clone();
+        }
+        self.source_key_fields = source_fields;
+        self.target_key_fields = target_fields;
+        self
+    }
+
+    /// Columns referencing the source node, in declaration order
+    pub fn source_key_columns(&self) -> Vec<&str> {
+        if self.source_key_fields.is_empty() {
+            vec![self.source_id_field.as_str()]
+        } else {
+            self.source_key_fields.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// Columns referencing the target node, in declaration order
+    pub fn target_key_columns(&self) -> Vec<&str> {
+        if self.target_key_fields.is_empty() {
+            vec![self.target_id_field.as_str()]
+        } else {
+            self.target_key_fields.iter().map(String::as_str).collect()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -368,6 +857,15 @@ mod tests {
                 id_field: "".to_string(),
                 property_fields: Vec::new(),
                 filter_conditions: None,
+                view_of: None,
+                key_fields: Vec::new(),
+                source_table: None,
+                label_column: None,
+                soft_delete_column: None,
+                indexed_properties: Vec::new(),
+                ngram_properties: Vec::new(),
+                vector_properties: Vec::new(),
+                sorted_by: Vec::new(),
             },
         );
 
@@ -462,4 +960,161 @@ mod tests {
         let mapping = builder.node_mappings.get("person").unwrap();
         assert_eq!(mapping.id_field, "id2");
     }
+
+    #[test]
+    fn test_view_label_inherits_base_mapping() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("User", "user_id").with_properties(vec!["name".to_string()]),
+            )
+            .with_view_label("ActiveUser", "User", "active = true")
+            .build()
+            .unwrap();
+
+        let view = config.get_node_mapping("activeuser").unwrap();
+        assert_eq!(view.view_of.as_deref(), Some("User"));
+        assert_eq!(view.id_field, "user_id");
+        assert_eq!(view.property_fields, vec!["name".to_string()]);
+        assert!(config.is_view_label("ActiveUser"));
+        assert!(!config.is_view_label("User"));
+
+        let (base, filters) = config.resolve_view_chain("ActiveUser").unwrap();
+        assert_eq!(base, "User");
+        assert_eq!(filters, vec!["active = true".to_string()]);
+    }
+
+    #[test]
+    fn test_view_label_chain_collects_filters() {
+        let config = GraphConfig::builder()
+            .with_node_label("User", "id")
+            .with_view_label("ActiveUser", "User", "active = true")
+            .with_view_label("ActiveAdult", "ActiveUser", "age >= 18")
+            .build()
+            .unwrap();
+
+        let (base, filters) = config.resolve_view_chain("ActiveAdult").unwrap();
+        assert_eq!(base, "User");
+        assert_eq!(
+            filters,
+            vec!["age >= 18".to_string(), "active = true".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_view_label_unknown_base_fails() {
+        let result = GraphConfig::builder()
+            .with_view_label("ActiveUser", "User", "active = true")
+            .build();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("unknown base label"));
+    }
+
+    #[test]
+    fn test_view_label_cycle_fails() {
+        let result = GraphConfig::builder()
+            .with_node_label("User", "id")
+            .with_view_label("A", "B", "x = 1")
+            .with_view_label("B", "A", "y = 1")
+            .build();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("cyclic"));
+    }
+
+    #[test]
+    fn test_composite_key_mappings() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("User", "user_id")
+                    .with_composite_key(vec!["tenant_id".to_string(), "user_id".to_string()]),
+            )
+            .with_relationship_mapping(
+                RelationshipMapping::new("FOLLOWS", "src_id", "dst_id").with_composite_keys(
+                    vec!["src_tenant".to_string(), "src_id".to_string()],
+                    vec!["dst_tenant".to_string(), "dst_id".to_string()],
+                ),
+            )
+            .build()
+            .unwrap();
+
+        let user = config.get_node_mapping("User").unwrap();
+        assert_eq!(user.id_field, "tenant_id");
+        assert_eq!(user.key_columns(), vec!["tenant_id", "user_id"]);
+
+        let follows = config.get_relationship_mapping("FOLLOWS").unwrap();
+        assert_eq!(follows.source_key_columns(), vec!["src_tenant", "src_id"]);
+        assert_eq!(follows.target_key_columns(), vec!["dst_tenant", "dst_id"]);
+
+        // Single-column mappings fall back to the id fields
+        let plain = RelationshipMapping::new("KNOWS", "src", "dst");
+        assert_eq!(plain.source_key_columns(), vec!["src"]);
+        assert_eq!(plain.target_key_columns(), vec!["dst"]);
+    }
+
+    #[test]
+    fn test_label_column_mapping() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", "kind"))
+            .with_node_label("City", "city_id")
+            .build()
+            .unwrap();
+
+        let person = config.get_node_mapping("person").unwrap();
+        assert_eq!(person.table_name(), "nodes");
+        assert_eq!(person.label_column.as_deref(), Some("kind"));
+        assert_eq!(
+            config.get_node_mapping("city").unwrap().table_name(),
+            "City"
+        );
+
+        let result = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", ""))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parallel_edges_default_to_allowed() {
+        let json = r#"{
+            "relationship_type": "KNOWS",
+            "source_id_field": "src_id",
+            "target_id_field": "dst_id",
+            "type_field": null,
+            "property_fields": [],
+            "filter_conditions": null
+        }"#;
+        let mapping: RelationshipMapping = serde_json::from_str(json).unwrap();
+        assert!(mapping.allow_parallel_edges);
+
+        let mapping =
+            RelationshipMapping::new("KNOWS", "src_id", "dst_id").with_parallel_edges(false);
+        assert!(!mapping.allow_parallel_edges);
+        assert_eq!(mapping.edge_key_columns(), vec!["src_id", "dst_id"]);
+    }
+
+    #[test]
+    fn test_endpoint_labels_must_be_known() {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_node_label("Company", "id")
+            .with_relationship_mapping(
+                RelationshipMapping::new("WORKS_AT", "person_id", "company_id")
+                    .with_endpoints("Person", "Company"),
+            )
+            .build()
+            .unwrap();
+        let works_at = config.get_relationship_mapping("works_at").unwrap();
+        assert_eq!(works_at.target_label.as_deref(), Some("Company"));
+
+        let result = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship_mapping(
+                RelationshipMapping::new("WORKS_AT", "person_id", "company_id")
+                    .with_endpoints("Person", "Company"),
+            )
+            .build();
+        assert!(result.is_err());
+    }
 }
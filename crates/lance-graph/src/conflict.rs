@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Optimistic concurrency for graph writes
+//!
+//! SET, REMOVE and DELETE read the graph first and then change the rows they
+//! matched. Another writer committing in between could have changed those
+//! rows, so the write would be based on a state that no longer exists.
+//! Before reading, a write records the version of every dataset it may
+//! change ([`WriteBase`]); before changing a dataset, it checks whether the
+//! dataset advanced since. If it did, the fragments holding the rows the
+//! write touches are compared with the fragments the later commits rewrote
+//! or deleted from: disjoint changes (e.g. rows appended by someone else)
+//! let the write go ahead on the latest version, overlapping ones fail with
+//! [`GraphError::WriteConflict`] and nothing of that dataset is changed.
+//! The write's own commits move its recorded versions forward, so changing
+//! a dataset twice does not conflict with itself.
+//!
+//! Appends commute with any other commit, so an append Lance rejects because
+//! another writer committed first is retried on the new latest version, up to
+//! [`APPEND_RETRIES`] times. Commits Lance itself rejects as conflicting are
+//! reported as [`GraphError::WriteConflict`] as well.
+
+use crate::error::{GraphError, Result};
+use lance::dataset::Dataset;
+use lance_graph_catalog::DirNamespace;
+use std::collections::{BTreeSet, HashMap};
+
+/// Attempts after the first at committing an append that raced with
+/// another commit
+pub(crate) const APPEND_RETRIES: usize = 5;
+
+/// Versions of the datasets a write may change, recorded before it reads
+/// the graph
+#[derive(Debug, Default)]
+pub(crate) struct WriteBase {
+    /// Version per lowercase table name; `None` if the dataset did not exist
+    versions: HashMap<String, Option<u64>>,
+}
+
+impl WriteBase {
+    /// Record the latest version of the datasets of `tables` in `namespace`
+    pub(crate) async fn capture<'t>(
+        namespace: &DirNamespace,
+        tables: impl IntoIterator<Item = &'t str>,
+    ) -> Result<Self> {
+        let mut versions = HashMap::new();
+        for table in tables {
+            let key = table.to_lowercase();
+            if versions.contains_key(&key) {
+                continue;
+            }
+            let version = crate::graph_diff::open_latest(namespace, table)
+                .await?
+                .map(|dataset| dataset.version().version);
+            versions.insert(key, version);
+        }
+        Ok(Self { versions })
+    }
+
+    /// Record that the write itself committed `version` of `table`, so its
+    /// later changes to `table` are not checked against its own
+    pub(crate) fn advance(&mut self, table: &str, version: u64) {
+        self.versions.insert(table.to_lowercase(), Some(version));
+    }
+
+    /// Fail with [`GraphError::WriteConflict`] if a commit since the recorded
+    /// version of `table` changed a fragment holding a row of it that
+    /// matches `filter`; `latest` is the version the write is about to
+    /// change
+    pub(crate) async fn check(&self, latest: &Dataset, table: &str, filter: &str) -> Result<()> {
+        let Some(Some(base_version)) = self.versions.get(&table.to_lowercase()).copied() else {
+            // Not recorded, or created after the write read the graph: the
+            // read saw none of its rows
+            return Ok(());
+        };
+        let latest_version = latest.version().version;
+        if latest_version == base_version {
+            return Ok(());
+        }
+
+        let base = latest.checkout_version(base_version).await?;
+        let current: HashMap<u64, _> = latest
+            .get_fragments()
+            .into_iter()
+            .map(|fragment| (fragment.id() as u64, fragment.metadata().clone()))
+            .collect();
+        let mut overlapping = BTreeSet::new();
+        for fragment in base.get_fragments() {
+            let id = fragment.id() as u64;
+            // Fragments only ever appended around are unchanged
+            if current.get(&id) == Some(fragment.metadata()) {
+                continue;
+            }
+            if fragment.count_rows(Some(filter.to_string())).await? > 0 {
+                overlapping.insert(id);
+            }
+        }
+        if overlapping.is_empty() {
+            return Ok(());
+        }
+        Err(GraphError::WriteConflict {
+            message: format!(
+                "'{}' advanced from version {} to {} while the write ran, changing rows it \
+                 matched (fragments {:?}); retry the query",
+                table, base_version, latest_version, overlapping
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+}
+
+/// Whether Lance rejected a commit because of a concurrent commit
+pub(crate) fn is_commit_conflict(error: &lance::Error) -> bool {
+    matches!(
+        error,
+        lance::Error::CommitConflict { .. }
+            | lance::Error::RetryableCommitConflict { .. }
+            | lance::Error::IncompatibleTransaction { .. }
+            | lance::Error::TooMuchWriteContention { .. }
+    )
+}
+
+/// `error` of a commit to `table`, as a [`GraphError::WriteConflict`] if a
+/// concurrent commit caused it
+pub(crate) fn commit_error(table: &str, error: lance::Error) -> GraphError {
+    if is_commit_conflict(&error) {
+        return GraphError::WriteConflict {
+            message: format!(
+                "another writer committed to '{}' first: {}; retry the query",
+                table, error
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        };
+    }
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Int64Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{ArrowError, DataType, Field, Schema};
+    use lance::dataset::{WriteMode, WriteParams};
+    use std::sync::Arc;
+
+    async fn append(uri: &str, ids: Vec<i64>) {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))]).unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+        let mode = if Dataset::open(uri).await.is_ok() {
+            WriteMode::Append
+        } else {
+            WriteMode::Create
+        };
+        Dataset::write(
+            reader,
+            uri,
+            Some(WriteParams {
+                mode,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_only_overlapping_changes_conflict() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let uri = format!("{}/Person.lance", namespace.base_uri());
+        // Two fragments: ids 1-2 and 3-4
+        append(&uri, vec![1, 2]).await;
+        append(&uri, vec![3, 4]).await;
+        let base = WriteBase::capture(&namespace, ["Person"]).await.unwrap();
+
+        // Someone else appends: no row the write matched changed
+        append(&uri, vec![5]).await;
+        let latest = Dataset::open(&uri).await.unwrap();
+        base.check(&latest, "Person", "id = 1").await.unwrap();
+
+        // Someone else deletes from the first fragment
+        let mut dataset = Dataset::open(&uri).await.unwrap();
+        dataset.delete("id = 2").await.unwrap();
+        let latest = Dataset::open(&uri).await.unwrap();
+        let err = base.check(&latest, "Person", "id = 1").await.unwrap_err();
+        assert!(matches!(err, GraphError::WriteConflict { .. }), "{}", err);
+        base.check(&latest, "Person", "id = 4").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_and_new_datasets_pass() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let uri = format!("{}/Person.lance", namespace.base_uri());
+        let base = WriteBase::capture(&namespace, ["Person", "person"])
+            .await
+            .unwrap();
+
+        // Created after the write read the graph
+        append(&uri, vec![1]).await;
+        let latest = Dataset::open(&uri).await.unwrap();
+        base.check(&latest, "Person", "id = 1").await.unwrap();
+
+        let base = WriteBase::capture(&namespace, ["Person"]).await.unwrap();
+        base.check(&latest, "Person", "id = 1").await.unwrap();
+    }
+}
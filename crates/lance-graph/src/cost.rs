@@ -0,0 +1,1958 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Cost estimation without execution
+//!
+//! [`estimate_cost`] walks a graph logical plan and predicts, from a
+//! [`GraphStatistics`] catalog, the rows the query returns, the bytes it reads
+//! from the label and relationship datasets and the memory it holds at its
+//! high-water mark. Platform services use it to reject or schedule queries
+//! before running them:
+//!
+//! ```ignore
+//! use lance_graph::cost::GraphStatistics;
+//!
+//! let summary = CypherQuery::new("CALL graph.summary()")?
+//!     .with_config(config.clone())
+//!     .execute(datasets, None)
+//!     .await?;
+//! let statistics = GraphStatistics::from_summary(&summary)?;
+//!
+//! let estimate = query.estimate_cost(&statistics)?;
+//! if estimate.peak_memory_bytes > budget {
+//!     // reject, or queue for an off-peak window
+//! }
+//! ```
+//!
+//! The statistics are the metrics of `CALL graph.summary()`. Predicates
+//! comparing a property to a literal are estimated from the property's
+//! distribution:
+//!
+//! - Equality keeps the share of a most common value, or else an even share
+//!   of the remaining distinct values; values outside the histogram range
+//!   match nothing
+//! - `<`, `<=`, `>` and `>=` interpolate the equi-depth histogram of numeric
+//!   and temporal properties; temporal literals are ISO 8601 strings
+//! - `IS NULL`, `IS NOT NULL` and `exists()` use the null fraction
+//!
+//! A hop multiplies rows by the mean degree at the end of the relationship it
+//! leaves from: the out-degree for `->`, the in-degree for `<-` and both for
+//! `-`. Only nodes with a relationship expand, so a traversal starting from a
+//! low-fan-out end of a relationship type is estimated cheaper than one
+//! starting from its hubs. The 95th percentile degree sizes the batches that
+//! land on hubs, which adds to peak memory.
+//!
+//! Everything the statistics do not cover is a fixed guess:
+//!
+//! - Other predicates keep [`EQUALITY_SELECTIVITY`] of the rows for equality
+//!   and [`RANGE_SELECTIVITY`] for anything else
+//! - `AND`, `OR` and `NOT` combine selectivities as independent events
+//! - A hop without degree distributions multiplies rows by the average
+//!   degree, the relationship count over the size of the source label (twice
+//!   that when undirected)
+//! - Aggregations with grouping keys keep [`GROUPING_REDUCTION`] of the rows
+//! - `UNWIND` produces [`UNWIND_FANOUT`] rows per input row
+//! - A table function called with `CALL ... YIELD` produces
+//!   [`TABLE_FUNCTION_ROWS`] rows, read from outside the graph
+//!
+//! Scans are counted in full, as if no column were pruned and no `LIMIT`
+//! stopped them early, so `bytes_scanned` is an upper bound. Memory counts the
+//! state of blocking operators (hash join build sides, sorts, aggregations and
+//! `DISTINCT`) plus one batch per scan.
+//!
+//! # Selectivity feedback
+//!
+//! The fixed predicate guesses can be replaced by what queries actually did.
+//! Queries run with [`CypherQuery::with_selectivity_feedback`] count, for
+//! every `WHERE` predicate, the rows it was evaluated on, kept and left null,
+//! and record them in a [`SelectivityFeedback`] store under the query's
+//! [fingerprint](crate::query::CypherQuery::fingerprint). Statistics carrying
+//! the same store ([`GraphStatistics::with_feedback`]) then estimate repeated
+//! queries with the observed selectivities:
+//!
+//! ```ignore
+//! let feedback = Arc::new(SelectivityFeedback::new());
+//! let statistics = GraphStatistics::from_summary(&summary)?.with_feedback(feedback.clone());
+//!
+//! query.clone().with_selectivity_feedback(feedback).execute(datasets, None).await?;
+//! let estimate = query.estimate_cost(&statistics)?; // uses the observed selectivity
+//! ```
+//!
+//! # Exporting statistics
+//!
+//! When lance-graph is one source among many, a federated engine or an
+//! external optimizer decides how to split and order the work.
+//! [`GraphStatistics::export`] hands it everything the statistics hold as a
+//! [`StatisticsDocument`] that serializes to JSON:
+//!
+//! - cardinalities and dataset sizes of every label and relationship type
+//! - per property: the null fraction, the approximate number of distinct
+//!   values (NDV), an equi-depth histogram of numeric and temporal values and
+//!   the most common string values, as read from the `graph.summary()` metrics
+//! - the out- and in-degree distributions of every relationship type
+//! - the observed predicate selectivities of the feedback store, if any
+//!
+//! ```ignore
+//! let document = GraphStatistics::from_summary(&summary)?.export();
+//! std::fs::write("graph-statistics.json", serde_json::to_vec_pretty(&document)?)?;
+//! ```
+//!
+//! [`GraphStatistics::from_document`] reads a document back, e.g. one
+//! exported by another process.
+//!
+//! [`CypherQuery::with_selectivity_feedback`]: crate::query::CypherQuery::with_selectivity_feedback
+
+use crate::ast::{
+    BooleanExpression, ComparisonOperator, PropertyRef, PropertyValue, RelationshipDirection,
+    SampleMethod, ValueExpression,
+};
+use crate::datafusion_planner::expression::contains_aggregate;
+use crate::error::{GraphError, Result};
+use crate::logical_plan::{JoinType, LogicalOperator};
+use crate::summary::{HISTOGRAM_METRICS, IN_DEGREE_METRICS, OUT_DEGREE_METRICS};
+use crate::MAX_VARIABLE_LENGTH_HOPS;
+use arrow::compute::kernels::cast_utils::Parser;
+use arrow::datatypes::TimestampMillisecondType;
+use arrow_array::{Array, BooleanArray, Float64Array, RecordBatch, StringArray};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Share of rows kept by an equality predicate
+pub const EQUALITY_SELECTIVITY: f64 = 0.1;
+
+/// Share of rows kept by any other predicate
+pub const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Share of rows left after grouping by non-aggregated keys
+pub const GROUPING_REDUCTION: f64 = 0.1;
+
+/// Rows produced for each input row by `UNWIND`
+pub const UNWIND_FANOUT: f64 = 10.0;
+
+/// Rows produced by a table function call
+pub const TABLE_FUNCTION_ROWS: f64 = 1000.0;
+
+/// Bytes per row assumed when the statistics have no size for an element
+const DEFAULT_ROW_BYTES: f64 = 64.0;
+
+/// Bytes of a computed projection value
+const VALUE_BYTES: f64 = 16.0;
+
+/// Rows buffered by a streaming scan
+const BATCH_ROWS: f64 = 8192.0;
+
+/// Row count and size of one label or relationship type
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ElementStatistics {
+    /// Live rows
+    pub count: u64,
+    /// Size of the backing dataset, if known
+    pub size_bytes: Option<u64>,
+    /// Version of the Lance dataset the statistics were collected from;
+    /// `None` for in-memory tables
+    pub dataset_version: Option<u64>,
+}
+
+impl ElementStatistics {
+    pub fn new(count: u64) -> Self {
+        Self {
+            count,
+            size_bytes: None,
+            dataset_version: None,
+        }
+    }
+
+    pub fn with_size_bytes(mut self, size_bytes: u64) -> Self {
+        self.size_bytes = Some(size_bytes);
+        self
+    }
+
+    pub fn with_dataset_version(mut self, version: u64) -> Self {
+        self.dataset_version = Some(version);
+        self
+    }
+
+    fn row_bytes(&self) -> f64 {
+        match self.size_bytes {
+            Some(bytes) if self.count > 0 => bytes as f64 / self.count as f64,
+            _ => DEFAULT_ROW_BYTES,
+        }
+    }
+
+    fn total_bytes(&self) -> f64 {
+        self.count as f64 * self.row_bytes()
+    }
+}
+
+/// Value distribution of one property of a label or relationship type
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PropertyStatistics {
+    /// Share of rows where the property is null
+    pub null_fraction: f64,
+    /// Approximate number of distinct values, if counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distinct_count: Option<u64>,
+    /// Histogram of the values, for numeric and temporal properties;
+    /// temporal values are milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Histogram>,
+    /// Most frequent values, for string properties
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub most_common: Vec<MostCommonValue>,
+}
+
+impl PropertyStatistics {
+    /// Share of rows where the property equals `value`, if the statistics
+    /// cover it
+    pub fn equality_selectivity(&self, value: &PropertyValue) -> Option<f64> {
+        if let PropertyValue::String(text) = value {
+            if let Some(common) = self.most_common.iter().find(|c| &c.value == text) {
+                return Some(common.frequency);
+            }
+        }
+        if let Some((histogram, value)) = self.histogram.as_ref().zip(histogram_value(value)) {
+            let (first, last) = (histogram.bounds.first()?, histogram.bounds.last()?);
+            if value < *first || value > *last {
+                return Some(0.0);
+            }
+        }
+        // The rows left after the most common values, shared evenly by the
+        // other distinct values
+        let distinct = self.distinct_count? as f64;
+        let listed: f64 = self.most_common.iter().map(|c| c.frequency).sum();
+        let others = (distinct - self.most_common.len() as f64).max(1.0);
+        Some(((1.0 - self.null_fraction - listed).max(0.0) / others).min(1.0))
+    }
+
+    /// Share of rows where the property compares to `value` as `operator`
+    /// (`<`, `<=`, `>` or `>=`), if the statistics cover it
+    pub fn range_selectivity(
+        &self,
+        operator: &ComparisonOperator,
+        value: &PropertyValue,
+    ) -> Option<f64> {
+        let below = self
+            .histogram
+            .as_ref()?
+            .fraction_below(histogram_value(value)?)?;
+        let share = match operator {
+            ComparisonOperator::LessThan | ComparisonOperator::LessThanOrEqual => below,
+            ComparisonOperator::GreaterThan | ComparisonOperator::GreaterThanOrEqual => 1.0 - below,
+            ComparisonOperator::Equal | ComparisonOperator::NotEqual => return None,
+        };
+        Some((1.0 - self.null_fraction) * share)
+    }
+
+    /// Smallest and largest value of the property, the outer bounds of its
+    /// histogram
+    pub fn bounds(&self) -> Option<(f64, f64)> {
+        let bounds = &self.histogram.as_ref()?.bounds;
+        let (min, max) = (*bounds.first()?, *bounds.last()?);
+        (!min.is_nan() && !max.is_nan()).then_some((min, max))
+    }
+}
+
+/// A literal as a histogram value: numbers as they are, ISO 8601 dates and
+/// timestamps as milliseconds since the Unix epoch
+pub(crate) fn histogram_value(value: &PropertyValue) -> Option<f64> {
+    match value {
+        PropertyValue::Integer(i) => Some(*i as f64),
+        PropertyValue::Float(f) => Some(*f),
+        PropertyValue::String(text) => TimestampMillisecondType::parse(text).map(|ms| ms as f64),
+        _ => None,
+    }
+}
+
+/// Equi-depth histogram: consecutive bounds, from the minimum to the maximum,
+/// delimit buckets holding the same number of rows
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bounds: Vec<f64>,
+}
+
+impl Histogram {
+    /// Share of values below `value`, assuming values spread evenly within
+    /// each bucket; `None` for incomplete histograms
+    pub fn fraction_below(&self, value: f64) -> Option<f64> {
+        let bounds = &self.bounds;
+        if bounds.len() < 2 || bounds.iter().any(|b| b.is_nan()) || value.is_nan() {
+            return None;
+        }
+        if value <= bounds[0] {
+            return Some(0.0);
+        }
+        if value >= bounds[bounds.len() - 1] {
+            return Some(1.0);
+        }
+        let bucket = bounds.windows(2).position(|w| value < w[1])?;
+        let (low, high) = (bounds[bucket], bounds[bucket + 1]);
+        let within = if high > low {
+            (value - low) / (high - low)
+        } else {
+            1.0
+        };
+        Some((bucket as f64 + within) / (bounds.len() - 1) as f64)
+    }
+}
+
+/// One of the most frequent values of a property
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MostCommonValue {
+    pub value: String,
+    /// Share of all rows holding the value
+    pub frequency: f64,
+}
+
+/// Relationships per node at one end of a relationship type, over the nodes
+/// that have any
+///
+/// Fields missing from documents written before they were collected are 0.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DegreeDistribution {
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl DegreeDistribution {
+    /// Set the statistic at `position` of [`OUT_DEGREE_METRICS`] /
+    /// [`IN_DEGREE_METRICS`]
+    fn set(&mut self, position: usize, value: f64) {
+        let field = match position {
+            0 => &mut self.mean,
+            1 => &mut self.p50,
+            2 => &mut self.p90,
+            3 => &mut self.p95,
+            4 => &mut self.p99,
+            _ => &mut self.max,
+        };
+        *field = value;
+    }
+}
+
+/// Version of the [`StatisticsDocument`] layout
+pub const STATISTICS_FORMAT_VERSION: u32 = 1;
+
+/// Everything [`GraphStatistics`] holds, in a form external optimizers can
+/// read, see [`GraphStatistics::export`]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct StatisticsDocument {
+    pub format_version: u32,
+    pub nodes: Vec<ElementDocument>,
+    pub relationships: Vec<ElementDocument>,
+    #[serde(default)]
+    pub observed_predicates: Vec<PredicateDocument>,
+}
+
+/// Statistics of one label or relationship type in a [`StatisticsDocument`]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ElementDocument {
+    /// Lowercase label or relationship type
+    pub name: String,
+    pub cardinality: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset_version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out_degree: Option<DegreeDistribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_degree: Option<DegreeDistribution>,
+    /// By lowercase property name
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyStatistics>,
+}
+
+/// One observed predicate selectivity in a [`StatisticsDocument`]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PredicateDocument {
+    /// Fingerprint of the query the predicate ran in
+    pub fingerprint: String,
+    /// Canonical JSON of the predicate
+    pub predicate: String,
+    pub observation: PredicateObservation,
+}
+
+/// A label or relationship type, to look property statistics up by
+#[derive(Debug, Clone, Copy)]
+enum Element<'a> {
+    Node(&'a str),
+    Relationship(&'a str),
+}
+
+/// Distributions of one label or relationship type
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ElementDistributions {
+    properties: BTreeMap<String, PropertyStatistics>,
+    out_degree: Option<DegreeDistribution>,
+    in_degree: Option<DegreeDistribution>,
+}
+
+/// Statistics of every label and relationship type of a graph
+///
+/// Names are matched case-insensitively, like labels in queries.
+#[derive(Debug, Clone, Default)]
+pub struct GraphStatistics {
+    nodes: HashMap<String, ElementStatistics>,
+    relationships: HashMap<String, ElementStatistics>,
+    /// Property and degree distributions
+    node_distributions: HashMap<String, ElementDistributions>,
+    relationship_distributions: HashMap<String, ElementDistributions>,
+    /// Selectivities observed by earlier runs
+    feedback: Option<Arc<SelectivityFeedback>>,
+}
+
+impl PartialEq for GraphStatistics {
+    fn eq(&self, other: &Self) -> bool {
+        let same_feedback = match (&self.feedback, &other.feedback) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.nodes == other.nodes
+            && self.relationships == other.relationships
+            && self.node_distributions == other.node_distributions
+            && self.relationship_distributions == other.relationship_distributions
+            && same_feedback
+    }
+}
+
+impl GraphStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_node(mut self, label: &str, statistics: ElementStatistics) -> Self {
+        self.nodes.insert(label.to_lowercase(), statistics);
+        self
+    }
+
+    pub fn with_relationship(mut self, rel_type: &str, statistics: ElementStatistics) -> Self {
+        self.relationships
+            .insert(rel_type.to_lowercase(), statistics);
+        self
+    }
+
+    pub fn with_node_property(
+        mut self,
+        label: &str,
+        property: &str,
+        statistics: PropertyStatistics,
+    ) -> Self {
+        self.node_distributions
+            .entry(label.to_lowercase())
+            .or_default()
+            .properties
+            .insert(property.to_lowercase(), statistics);
+        self
+    }
+
+    pub fn with_relationship_property(
+        mut self,
+        rel_type: &str,
+        property: &str,
+        statistics: PropertyStatistics,
+    ) -> Self {
+        self.relationship_distributions
+            .entry(rel_type.to_lowercase())
+            .or_default()
+            .properties
+            .insert(property.to_lowercase(), statistics);
+        self
+    }
+
+    pub fn with_out_degree(mut self, rel_type: &str, distribution: DegreeDistribution) -> Self {
+        self.relationship_distributions
+            .entry(rel_type.to_lowercase())
+            .or_default()
+            .out_degree = Some(distribution);
+        self
+    }
+
+    pub fn with_in_degree(mut self, rel_type: &str, distribution: DegreeDistribution) -> Self {
+        self.relationship_distributions
+            .entry(rel_type.to_lowercase())
+            .or_default()
+            .in_degree = Some(distribution);
+        self
+    }
+
+    /// Estimate predicates with the selectivities observed in `feedback`
+    /// where it has them
+    pub fn with_feedback(mut self, feedback: Arc<SelectivityFeedback>) -> Self {
+        self.feedback = Some(feedback);
+        self
+    }
+
+    /// Read the metrics of a `CALL graph.summary()` result
+    pub fn from_summary(batch: &RecordBatch) -> Result<Self> {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Summary batch has no '{}' column", name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+        };
+        let strings = |name: &str| -> Result<StringArray> {
+            column(name)?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .cloned()
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Summary column '{}' must be Utf8", name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+        };
+        let kind = strings("kind")?;
+        let name = strings("name")?;
+        let property = strings("property")?;
+        let metric = strings("metric")?;
+        // Absent from summaries written before the most common values were listed
+        let item = batch
+            .column_by_name("item")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .cloned();
+        let value = column("value")?
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .cloned()
+            .ok_or_else(|| GraphError::ConfigError {
+                message: "Summary column 'value' must be Float64".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let mut statistics = Self::new();
+        for row in 0..batch.num_rows() {
+            let (elements, distributions) = match kind.value(row) {
+                "node" => (&mut statistics.nodes, &mut statistics.node_distributions),
+                "relationship" => (
+                    &mut statistics.relationships,
+                    &mut statistics.relationship_distributions,
+                ),
+                _ => continue,
+            };
+            let element = name.value(row).to_lowercase();
+            let value = value.value(row);
+            if property.is_valid(row) {
+                let entry = distributions
+                    .entry(element)
+                    .or_default()
+                    .properties
+                    .entry(property.value(row).to_lowercase())
+                    .or_default();
+                match metric.value(row) {
+                    "null_ratio" => entry.null_fraction = value,
+                    "distinct_count" => entry.distinct_count = Some(value.max(0.0) as u64),
+                    "most_common" => {
+                        if let Some(item) = item.as_ref().filter(|item| item.is_valid(row)) {
+                            entry.most_common.push(MostCommonValue {
+                                value: item.value(row).to_string(),
+                                frequency: value,
+                            });
+                        }
+                    }
+                    metric => {
+                        if let Some(bound) = HISTOGRAM_METRICS.iter().position(|m| *m == metric) {
+                            let histogram = entry.histogram.get_or_insert_with(|| Histogram {
+                                bounds: vec![f64::NAN; HISTOGRAM_METRICS.len()],
+                            });
+                            histogram.bounds[bound] = value;
+                        }
+                    }
+                }
+                continue;
+            }
+            let metric = metric.value(row);
+            let degree_metric = OUT_DEGREE_METRICS
+                .iter()
+                .position(|m| *m == metric)
+                .map(|position| (position, false))
+                .or_else(|| {
+                    IN_DEGREE_METRICS
+                        .iter()
+                        .position(|m| *m == metric)
+                        .map(|position| (position, true))
+                });
+            if let Some((position, incoming)) = degree_metric {
+                let entry = distributions.entry(element).or_default();
+                let degree = if incoming {
+                    &mut entry.in_degree
+                } else {
+                    &mut entry.out_degree
+                };
+                degree
+                    .get_or_insert_with(DegreeDistribution::default)
+                    .set(position, value);
+                continue;
+            }
+            let entry = elements.entry(element).or_default();
+            match metric {
+                "count" => entry.count = value.max(0.0) as u64,
+                "size_bytes" => entry.size_bytes = Some(value.max(0.0) as u64),
+                "dataset_version" => entry.dataset_version = Some(value.max(0.0) as u64),
+                _ => {}
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// Everything these statistics hold, sorted by name
+    pub fn export(&self) -> StatisticsDocument {
+        let elements =
+            |statistics: &HashMap<String, ElementStatistics>,
+             distributions: &HashMap<String, ElementDistributions>| {
+                let mut names: Vec<&String> =
+                    statistics.keys().chain(distributions.keys()).collect();
+                names.sort();
+                names.dedup();
+                names
+                    .into_iter()
+                    .map(|name| {
+                        let element = statistics.get(name).copied().unwrap_or_default();
+                        let distribution = distributions.get(name).cloned().unwrap_or_default();
+                        ElementDocument {
+                            name: name.clone(),
+                            cardinality: element.count,
+                            size_bytes: element.size_bytes,
+                            dataset_version: element.dataset_version,
+                            out_degree: distribution.out_degree,
+                            in_degree: distribution.in_degree,
+                            properties: distribution.properties,
+                        }
+                    })
+                    .collect()
+            };
+        let observed_predicates = self
+            .feedback
+            .as_ref()
+            .map(|feedback| {
+                feedback
+                    .all_observations()
+                    .into_iter()
+                    .map(|(fingerprint, predicate, observation)| PredicateDocument {
+                        fingerprint,
+                        predicate,
+                        observation,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        StatisticsDocument {
+            format_version: STATISTICS_FORMAT_VERSION,
+            nodes: elements(&self.nodes, &self.node_distributions),
+            relationships: elements(&self.relationships, &self.relationship_distributions),
+            observed_predicates,
+        }
+    }
+
+    /// Read statistics from an exported document
+    pub fn from_document(document: &StatisticsDocument) -> Result<Self> {
+        if document.format_version != STATISTICS_FORMAT_VERSION {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Unsupported statistics format version {}, expected {}",
+                    document.format_version, STATISTICS_FORMAT_VERSION
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let mut statistics = Self::new();
+        for element in &document.nodes {
+            statistics.nodes.insert(
+                element.name.to_lowercase(),
+                ElementStatistics {
+                    count: element.cardinality,
+                    size_bytes: element.size_bytes,
+                    dataset_version: element.dataset_version,
+                },
+            );
+            for (property, values) in &element.properties {
+                statistics = statistics.with_node_property(&element.name, property, values.clone());
+            }
+        }
+        for element in &document.relationships {
+            statistics.relationships.insert(
+                element.name.to_lowercase(),
+                ElementStatistics {
+                    count: element.cardinality,
+                    size_bytes: element.size_bytes,
+                    dataset_version: element.dataset_version,
+                },
+            );
+            for (property, values) in &element.properties {
+                statistics =
+                    statistics.with_relationship_property(&element.name, property, values.clone());
+            }
+            if let Some(degree) = element.out_degree {
+                statistics = statistics.with_out_degree(&element.name, degree);
+            }
+            if let Some(degree) = element.in_degree {
+                statistics = statistics.with_in_degree(&element.name, degree);
+            }
+        }
+        if !document.observed_predicates.is_empty() {
+            let feedback = SelectivityFeedback::new();
+            {
+                let mut observations = feedback.observations.lock().unwrap();
+                for observed in &document.observed_predicates {
+                    observations.insert(
+                        (observed.fingerprint.clone(), observed.predicate.clone()),
+                        observed.observation,
+                    );
+                }
+            }
+            statistics = statistics.with_feedback(Arc::new(feedback));
+        }
+        Ok(statistics)
+    }
+
+    pub fn node(&self, label: &str) -> Option<&ElementStatistics> {
+        self.nodes.get(&label.to_lowercase())
+    }
+
+    pub fn relationship(&self, rel_type: &str) -> Option<&ElementStatistics> {
+        self.relationships.get(&rel_type.to_lowercase())
+    }
+
+    pub fn node_property(&self, label: &str, property: &str) -> Option<&PropertyStatistics> {
+        self.node_distributions
+            .get(&label.to_lowercase())?
+            .properties
+            .get(&property.to_lowercase())
+    }
+
+    pub fn relationship_property(
+        &self,
+        rel_type: &str,
+        property: &str,
+    ) -> Option<&PropertyStatistics> {
+        self.relationship_distributions
+            .get(&rel_type.to_lowercase())?
+            .properties
+            .get(&property.to_lowercase())
+    }
+
+    fn property(&self, element: Element<'_>, property: &str) -> Option<&PropertyStatistics> {
+        match element {
+            Element::Node(label) => self.node_property(label, property),
+            Element::Relationship(rel_type) => self.relationship_property(rel_type, property),
+        }
+    }
+
+    pub fn out_degree(&self, rel_type: &str) -> Option<&DegreeDistribution> {
+        self.relationship_distributions
+            .get(&rel_type.to_lowercase())?
+            .out_degree
+            .as_ref()
+    }
+
+    pub fn in_degree(&self, rel_type: &str) -> Option<&DegreeDistribution> {
+        self.relationship_distributions
+            .get(&rel_type.to_lowercase())?
+            .in_degree
+            .as_ref()
+    }
+
+    /// Degree distribution at the end of `rel_type` a hop in `direction`
+    /// leaves from
+    fn leaving_degree(
+        &self,
+        rel_type: &str,
+        direction: &RelationshipDirection,
+    ) -> Option<&DegreeDistribution> {
+        match direction {
+            RelationshipDirection::Incoming => self.in_degree(rel_type),
+            _ => self.out_degree(rel_type),
+        }
+    }
+
+    fn require_node(&self, label: &str) -> Result<&ElementStatistics> {
+        self.node(label).ok_or_else(|| GraphError::PlanError {
+            message: format!("No statistics for label '{}'", label),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+
+    /// Combined statistics of `types`, or of every relationship type if empty
+    fn require_relationships(&self, types: &[String]) -> Result<ElementStatistics> {
+        let selected: Vec<&ElementStatistics> = if types.is_empty() {
+            self.relationships.values().collect()
+        } else {
+            types
+                .iter()
+                .map(|t| {
+                    self.relationship(t).ok_or_else(|| GraphError::PlanError {
+                        message: format!("No statistics for relationship type '{}'", t),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                })
+                .collect::<Result<_>>()?
+        };
+        let count = selected.iter().map(|s| s.count).sum();
+        let bytes = selected.iter().map(|s| s.total_bytes()).sum::<f64>();
+        Ok(ElementStatistics {
+            count,
+            size_bytes: Some(bytes as u64),
+            dataset_version: None,
+        })
+    }
+}
+
+/// Predicted cost of running a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CostEstimate {
+    /// Rows returned
+    pub rows: u64,
+    /// Bytes read from label and relationship datasets
+    pub bytes_scanned: u64,
+    /// Memory held at the high-water mark
+    pub peak_memory_bytes: u64,
+}
+
+/// Estimate the cost of `plan` from `statistics` without executing it
+///
+/// Fails when the plan reads a label or relationship type the statistics do
+/// not cover.
+pub fn estimate_cost(plan: &LogicalOperator, statistics: &GraphStatistics) -> Result<CostEstimate> {
+    estimate_cost_with_fingerprint(plan, statistics, None)
+}
+
+/// [`estimate_cost`] for the query with `fingerprint`, whose observed
+/// selectivities replace the fixed guesses
+pub(crate) fn estimate_cost_with_fingerprint(
+    plan: &LogicalOperator,
+    statistics: &GraphStatistics,
+    fingerprint: Option<&str>,
+) -> Result<CostEstimate> {
+    let mut estimator = Estimator {
+        statistics,
+        fingerprint,
+        labels: HashMap::new(),
+        relationship_types: HashMap::new(),
+        widths: HashMap::new(),
+    };
+    let estimate = estimator.estimate(plan)?;
+    let round = |v: f64| v.max(0.0).ceil().min(u64::MAX as f64) as u64;
+    Ok(CostEstimate {
+        rows: round(estimate.rows),
+        bytes_scanned: round(estimate.scanned),
+        peak_memory_bytes: round(estimate.peak),
+    })
+}
+
+/// Relationships a hop reaches per source row
+#[derive(Debug, Clone, Copy, Default)]
+struct Fanout {
+    /// On average over the source rows
+    mean: f64,
+    /// From a source at the 95th percentile of the degree distribution, or
+    /// the mean without one
+    p95: f64,
+}
+
+/// Running estimate of an operator's output
+#[derive(Debug, Clone, Copy)]
+struct Estimate {
+    rows: f64,
+    /// Bytes per output row
+    width: f64,
+    scanned: f64,
+    peak: f64,
+}
+
+struct Estimator<'a> {
+    statistics: &'a GraphStatistics,
+    /// Fingerprint of the estimated query, for observed selectivities
+    fingerprint: Option<&'a str>,
+    /// Label of every node variable bound so far
+    labels: HashMap<String, String>,
+    /// Type of every relationship variable bound so far to a single type
+    relationship_types: HashMap<String, String>,
+    /// Bytes per row of every variable bound so far
+    widths: HashMap<String, f64>,
+}
+
+impl Estimator<'_> {
+    fn estimate(&mut self, plan: &LogicalOperator) -> Result<Estimate> {
+        match plan {
+            LogicalOperator::ScanByLabel {
+                variable,
+                label,
+                properties,
+            } => {
+                let stats = self.statistics.require_node(label)?;
+                let width = stats.row_bytes();
+                self.labels.insert(variable.clone(), label.clone());
+                self.widths.insert(variable.clone(), width);
+                Ok(Estimate {
+                    rows: stats.count as f64
+                        * self.inline_selectivity(Some(Element::Node(label)), properties),
+                    width,
+                    scanned: stats.total_bytes(),
+                    peak: (stats.count as f64).min(BATCH_ROWS) * width,
+                })
+            }
+            LogicalOperator::Unwind {
+                input,
+                expression: _,
+                alias,
+            } => {
+                let mut estimate = match input {
+                    Some(input) => self.estimate(input)?,
+                    None => Estimate {
+                        rows: 1.0,
+                        width: 0.0,
+                        scanned: 0.0,
+                        peak: 0.0,
+                    },
+                };
+                estimate.rows *= UNWIND_FANOUT;
+                estimate.width += VALUE_BYTES;
+                self.widths.insert(alias.clone(), VALUE_BYTES);
+                Ok(estimate)
+            }
+            LogicalOperator::TableFunction { input, call } => {
+                let width = VALUE_BYTES * call.yields.len() as f64;
+                for item in &call.yields {
+                    self.widths.insert(item.variable().to_string(), VALUE_BYTES);
+                }
+                let yielded = Estimate {
+                    rows: TABLE_FUNCTION_ROWS,
+                    width,
+                    scanned: 0.0,
+                    peak: TABLE_FUNCTION_ROWS.min(BATCH_ROWS) * width,
+                };
+                Ok(match input {
+                    // Cross product with the rows before the call
+                    Some(input) => {
+                        let input = self.estimate(input)?;
+                        Estimate {
+                            rows: input.rows * yielded.rows,
+                            width: input.width + yielded.width,
+                            scanned: input.scanned,
+                            peak: input.peak + yielded.peak + yielded.rows * yielded.width,
+                        }
+                    }
+                    None => yielded,
+                })
+            }
+            LogicalOperator::Filter { input, predicate } => {
+                let mut estimate = self.estimate(input)?;
+                estimate.rows *= self.selectivity(predicate);
+                Ok(estimate)
+            }
+            LogicalOperator::Sample { input, method } => {
+                let mut estimate = self.estimate(input)?;
+                match method {
+                    SampleMethod::Percent(percent) => {
+                        estimate.rows *= (percent / 100.0).clamp(0.0, 1.0);
+                    }
+                    SampleMethod::Rows(count) => {
+                        estimate.rows = estimate.rows.min(*count as f64);
+                        estimate.peak += estimate.rows * estimate.width;
+                    }
+                    SampleMethod::RowsPer { count, .. } => {
+                        let groups = (estimate.rows * GROUPING_REDUCTION).max(1.0);
+                        estimate.rows = estimate.rows.min(groups * *count as f64);
+                        estimate.peak += estimate.rows * estimate.width;
+                    }
+                }
+                Ok(estimate)
+            }
+            LogicalOperator::Expand {
+                input,
+                source_variable,
+                target_variable,
+                target_label,
+                relationship_types,
+                direction,
+                relationship_variable,
+                properties,
+                target_properties,
+            } => {
+                let mut estimate = self.estimate(input)?;
+                let relationships = self.statistics.require_relationships(relationship_types)?;
+                let target = *self.statistics.require_node(target_label)?;
+                let fanout = self.fanout(
+                    source_variable,
+                    relationship_types,
+                    &relationships,
+                    direction,
+                );
+                // A batch of sources on the heavy tail of the degree
+                // distribution expands to more rows than average
+                let skew = estimate.rows.min(BATCH_ROWS) * (fanout.p95 - fanout.mean).max(0.0);
+
+                let relationship = match relationship_types.as_slice() {
+                    [rel_type] => Some(Element::Relationship(rel_type)),
+                    _ => None,
+                };
+                estimate.rows *= fanout.mean
+                    * self.inline_selectivity(relationship, properties)
+                    * self.inline_selectivity(Some(Element::Node(target_label)), target_properties);
+                estimate.width += relationships.row_bytes() + target.row_bytes();
+                estimate.scanned += relationships.total_bytes() + target.total_bytes();
+                // Both joined tables are hashed while the input streams through
+                estimate.peak +=
+                    relationships.total_bytes() + target.total_bytes() + skew * estimate.width;
+
+                self.labels
+                    .insert(target_variable.clone(), target_label.clone());
+                self.widths
+                    .insert(target_variable.clone(), target.row_bytes());
+                if let Some(variable) = relationship_variable {
+                    self.widths
+                        .insert(variable.clone(), relationships.row_bytes());
+                    if let [rel_type] = relationship_types.as_slice() {
+                        self.relationship_types
+                            .insert(variable.clone(), rel_type.clone());
+                    }
+                }
+                Ok(estimate)
+            }
+            LogicalOperator::VariableLengthExpand {
+                input,
+                source_variable,
+                target_variable,
+                relationship_types,
+                direction,
+                relationship_variable,
+                min_length,
+                max_length,
+                target_properties,
+                path_filter: _,
+            } => {
+                let mut estimate = self.estimate(input)?;
+                let relationships = self.statistics.require_relationships(relationship_types)?;
+                let degree = self
+                    .fanout(
+                        source_variable,
+                        relationship_types,
+                        &relationships,
+                        direction,
+                    )
+                    .mean;
+                let min_hops = min_length.unwrap_or(1);
+                let max_hops = max_length.unwrap_or(MAX_VARIABLE_LENGTH_HOPS).max(min_hops);
+
+                // One join per hop; paths of every length in range are unioned
+                let paths: f64 = (min_hops..=max_hops)
+                    .map(|hops| degree.powi(hops as i32))
+                    .sum();
+                let hops = max_hops.max(1) as f64;
+                let target = self
+                    .labels
+                    .get(source_variable)
+                    .map(|label| Element::Node(label.as_str()));
+                estimate.rows *= paths * self.inline_selectivity(target, target_properties);
+                estimate.width += hops * relationships.row_bytes();
+                estimate.scanned += hops * relationships.total_bytes();
+                estimate.peak += hops * relationships.total_bytes();
+
+                // The target shares the source's label unless told otherwise
+                if let Some(label) = self.labels.get(source_variable).cloned() {
+                    self.labels.insert(target_variable.clone(), label);
+                }
+                let source_width = self
+                    .widths
+                    .get(source_variable)
+                    .copied()
+                    .unwrap_or(DEFAULT_ROW_BYTES);
+                self.widths.insert(target_variable.clone(), source_width);
+                if let Some(variable) = relationship_variable {
+                    self.widths
+                        .insert(variable.clone(), hops * relationships.row_bytes());
+                }
+                Ok(estimate)
+            }
+            LogicalOperator::Project { input, projections } => {
+                let mut estimate = self.estimate(input)?;
+                estimate.width = projections
+                    .iter()
+                    .map(|p| match &p.expression {
+                        ValueExpression::Variable(v) => {
+                            self.widths.get(v).copied().unwrap_or(VALUE_BYTES)
+                        }
+                        _ => VALUE_BYTES,
+                    })
+                    .sum();
+                if projections
+                    .iter()
+                    .any(|p| contains_aggregate(&p.expression))
+                {
+                    let grouped = projections
+                        .iter()
+                        .any(|p| !contains_aggregate(&p.expression));
+                    estimate.rows = if grouped {
+                        (estimate.rows * GROUPING_REDUCTION).max(1.0)
+                    } else {
+                        1.0
+                    };
+                    estimate.peak += estimate.rows * estimate.width;
+                }
+                Ok(estimate)
+            }
+            LogicalOperator::Join {
+                left,
+                right,
+                join_type,
+            } => {
+                let left = self.estimate(left)?;
+                let right = self.estimate(right)?;
+                let rows = match join_type {
+                    JoinType::Cross => left.rows * right.rows,
+                    JoinType::Inner => left.rows.max(right.rows),
+                    JoinType::Left => left.rows,
+                    JoinType::Right => right.rows,
+                    JoinType::Full => left.rows + right.rows,
+                    JoinType::Semi | JoinType::Anti => left.rows * RANGE_SELECTIVITY,
+                };
+                let build = (left.rows * left.width).min(right.rows * right.width);
+                // Semi- and anti-joins only keep the columns of the left side
+                let width = match join_type {
+                    JoinType::Semi | JoinType::Anti => left.width,
+                    _ => left.width + right.width,
+                };
+                Ok(Estimate {
+                    rows,
+                    width,
+                    scanned: left.scanned + right.scanned,
+                    peak: left.peak + right.peak + build,
+                })
+            }
+            LogicalOperator::Distinct { input } | LogicalOperator::DistinctOn { input, .. } => {
+                let mut estimate = self.estimate(input)?;
+                estimate.peak += estimate.rows * estimate.width;
+                Ok(estimate)
+            }
+            LogicalOperator::Sort { input, .. } => {
+                let mut estimate = self.estimate(input)?;
+                estimate.peak += estimate.rows * estimate.width;
+                Ok(estimate)
+            }
+            LogicalOperator::Offset { input, offset } => {
+                let mut estimate = self.estimate(input)?;
+                estimate.rows = (estimate.rows - *offset as f64).max(0.0);
+                Ok(estimate)
+            }
+            LogicalOperator::Limit { input, count } => {
+                let (sorted, skipped) = match input.as_ref() {
+                    LogicalOperator::Sort { input, .. } => (Some(input), 0),
+                    LogicalOperator::Offset { input, offset } => match input.as_ref() {
+                        LogicalOperator::Sort { input, .. } => (Some(input), *offset),
+                        _ => (None, 0),
+                    },
+                    _ => (None, 0),
+                };
+                let mut estimate = match sorted {
+                    // ORDER BY ... LIMIT keeps only the top rows
+                    Some(input) => {
+                        let mut estimate = self.estimate(input)?;
+                        let kept = estimate.rows.min((count + skipped) as f64);
+                        estimate.peak += kept * estimate.width;
+                        estimate.rows = (estimate.rows - skipped as f64).max(0.0);
+                        estimate
+                    }
+                    None => self.estimate(input)?,
+                };
+                estimate.rows = estimate.rows.min(*count as f64);
+                Ok(estimate)
+            }
+        }
+    }
+
+    /// Observed selectivity of `predicate` in this query, or the estimate
+    fn selectivity(&self, predicate: &BooleanExpression) -> f64 {
+        self.fingerprint
+            .zip(self.statistics.feedback.as_ref())
+            .and_then(|(fingerprint, feedback)| {
+                feedback.observation(fingerprint, &predicate_key(predicate))
+            })
+            .and_then(|observation| observation.selectivity())
+            .unwrap_or_else(|| self.estimated_selectivity(predicate))
+    }
+
+    /// Share of rows `predicate` keeps, from the distributions of the
+    /// properties it compares to literals, else the fixed guess
+    fn estimated_selectivity(&self, predicate: &BooleanExpression) -> f64 {
+        let estimate = match predicate {
+            BooleanExpression::Comparison {
+                left,
+                operator,
+                right,
+            } => {
+                let (property, operator, value) = match (left, right) {
+                    (ValueExpression::Property(p), ValueExpression::Literal(v)) => {
+                        (p, operator.clone(), v)
+                    }
+                    (ValueExpression::Literal(v), ValueExpression::Property(p)) => {
+                        (p, flipped(operator), v)
+                    }
+                    _ => return selectivity(predicate),
+                };
+                self.property_statistics(property)
+                    .and_then(|stats| match operator {
+                        ComparisonOperator::Equal => stats.equality_selectivity(value),
+                        ComparisonOperator::NotEqual => stats
+                            .equality_selectivity(value)
+                            .map(|equal| (1.0 - stats.null_fraction - equal).max(0.0)),
+                        _ => stats.range_selectivity(&operator, value),
+                    })
+            }
+            BooleanExpression::In {
+                expression: ValueExpression::Property(property),
+                list,
+            } => self.property_statistics(property).and_then(|stats| {
+                list.iter()
+                    .map(|item| match item {
+                        ValueExpression::Literal(value) => stats.equality_selectivity(value),
+                        _ => None,
+                    })
+                    .sum::<Option<f64>>()
+                    .map(|share| share.min(1.0))
+            }),
+            BooleanExpression::IsNull(ValueExpression::Property(property)) => self
+                .property_statistics(property)
+                .map(|stats| stats.null_fraction),
+            BooleanExpression::IsNotNull(ValueExpression::Property(property))
+            | BooleanExpression::Exists(property) => self
+                .property_statistics(property)
+                .map(|stats| 1.0 - stats.null_fraction),
+            BooleanExpression::And(left, right) => {
+                Some(self.estimated_selectivity(left) * self.estimated_selectivity(right))
+            }
+            BooleanExpression::Or(left, right) => {
+                let (l, r) = (
+                    self.estimated_selectivity(left),
+                    self.estimated_selectivity(right),
+                );
+                Some(l + r - l * r)
+            }
+            BooleanExpression::Not(inner) => Some(1.0 - self.estimated_selectivity(inner)),
+            _ => None,
+        };
+        estimate.unwrap_or_else(|| selectivity(predicate))
+    }
+
+    /// Share of rows kept by inline property maps (`{name: 'Alice'}`) on
+    /// `element`
+    fn inline_selectivity(
+        &self,
+        element: Option<Element<'_>>,
+        properties: &HashMap<String, PropertyValue>,
+    ) -> f64 {
+        properties
+            .iter()
+            .map(|(property, value)| {
+                element
+                    .and_then(|element| self.statistics.property(element, property))
+                    .and_then(|stats| stats.equality_selectivity(value))
+                    .unwrap_or(EQUALITY_SELECTIVITY)
+            })
+            .product()
+    }
+
+    /// Statistics of the property of a node or single-type relationship variable
+    fn property_statistics(&self, property: &PropertyRef) -> Option<&PropertyStatistics> {
+        let element = match self.labels.get(&property.variable) {
+            Some(label) => Element::Node(label),
+            None => Element::Relationship(self.relationship_types.get(&property.variable)?),
+        };
+        self.statistics.property(element, &property.property)
+    }
+
+    /// Relationships per source row for a hop from `source_variable`
+    fn fanout(
+        &self,
+        source_variable: &str,
+        relationship_types: &[String],
+        relationships: &ElementStatistics,
+        direction: &RelationshipDirection,
+    ) -> Fanout {
+        let population = self
+            .labels
+            .get(source_variable)
+            .and_then(|label| self.statistics.node(label))
+            .map(|stats| stats.count)
+            .unwrap_or(relationships.count)
+            .max(1) as f64;
+        let directions: &[RelationshipDirection] = match direction {
+            RelationshipDirection::Undirected => &[
+                RelationshipDirection::Outgoing,
+                RelationshipDirection::Incoming,
+            ],
+            direction => std::slice::from_ref(direction),
+        };
+        let mut fanout = Fanout::default();
+        for direction in directions {
+            if relationship_types.is_empty() {
+                let degree = relationships.count as f64 / population;
+                fanout.mean += degree;
+                fanout.p95 += degree;
+            }
+            for rel_type in relationship_types {
+                let count = self
+                    .statistics
+                    .relationship(rel_type)
+                    .map_or(0, |s| s.count) as f64;
+                match self.statistics.leaving_degree(rel_type, direction) {
+                    Some(degree) if degree.mean > 0.0 => {
+                        // Only the nodes with a relationship expand, and there
+                        // are no more of them than the source label holds
+                        let covered = (count / degree.mean / population).min(1.0);
+                        fanout.mean += covered * degree.mean;
+                        fanout.p95 += degree.p95.max(degree.mean);
+                    }
+                    _ => {
+                        fanout.mean += count / population;
+                        fanout.p95 += count / population;
+                    }
+                }
+            }
+        }
+        fanout
+    }
+}
+
+/// Rows a predicate was evaluated on, kept and left null
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PredicateObservation {
+    /// Rows the predicate was evaluated on
+    pub rows: f64,
+    /// Rows it held for
+    pub kept: f64,
+    /// Rows it evaluated to null for, e.g. comparisons on missing properties
+    pub nulls: f64,
+}
+
+impl PredicateObservation {
+    /// Share of rows kept, if the predicate saw any
+    pub fn selectivity(&self) -> Option<f64> {
+        (self.rows > 0.0).then(|| self.kept / self.rows)
+    }
+
+    /// Share of rows the predicate was null for, if it saw any
+    pub fn null_fraction(&self) -> Option<f64> {
+        (self.rows > 0.0).then(|| self.nulls / self.rows)
+    }
+}
+
+/// Predicate selectivities observed while queries ran, by query fingerprint
+/// and predicate
+///
+/// Each run is merged with the earlier ones at half their weight, so the
+/// observations follow the data as it changes.
+#[derive(Debug, Default)]
+pub struct SelectivityFeedback {
+    observations: Mutex<HashMap<(String, String), PredicateObservation>>,
+}
+
+impl SelectivityFeedback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge one run's `observation` of `predicate` in the query with
+    /// `fingerprint`
+    pub fn record(&self, fingerprint: &str, predicate: &str, observation: PredicateObservation) {
+        let mut observations = self.observations.lock().unwrap();
+        let entry = observations
+            .entry((fingerprint.to_string(), predicate.to_string()))
+            .or_default();
+        entry.rows = entry.rows / 2.0 + observation.rows;
+        entry.kept = entry.kept / 2.0 + observation.kept;
+        entry.nulls = entry.nulls / 2.0 + observation.nulls;
+    }
+
+    /// Observations of `predicate` in the query with `fingerprint`
+    pub fn observation(&self, fingerprint: &str, predicate: &str) -> Option<PredicateObservation> {
+        let observations = self.observations.lock().unwrap();
+        observations
+            .get(&(fingerprint.to_string(), predicate.to_string()))
+            .copied()
+    }
+
+    /// Every predicate observed in the query with `fingerprint`, sorted
+    pub fn observations(&self, fingerprint: &str) -> Vec<(String, PredicateObservation)> {
+        let observations = self.observations.lock().unwrap();
+        let mut found: Vec<_> = observations
+            .iter()
+            .filter(|((f, _), _)| f == fingerprint)
+            .map(|((_, predicate), observation)| (predicate.clone(), *observation))
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        found
+    }
+
+    /// Every observation as (fingerprint, predicate, observation), sorted
+    pub fn all_observations(&self) -> Vec<(String, String, PredicateObservation)> {
+        let observations = self.observations.lock().unwrap();
+        let mut found: Vec<_> = observations
+            .iter()
+            .map(|((fingerprint, predicate), observation)| {
+                (fingerprint.clone(), predicate.clone(), *observation)
+            })
+            .collect();
+        found.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        found
+    }
+}
+
+/// Key a predicate is observed under: its canonical JSON
+pub(crate) fn predicate_key(predicate: &BooleanExpression) -> String {
+    serde_json::to_value(predicate)
+        .map(|value| crate::result_cache::canonical_json(&value))
+        .unwrap_or_else(|_| format!("{:?}", predicate))
+}
+
+/// Row counters of one predicate while a plan runs
+#[derive(Debug, Default)]
+pub(crate) struct PredicateCounters {
+    rows: AtomicU64,
+    kept: AtomicU64,
+    nulls: AtomicU64,
+}
+
+impl PredicateCounters {
+    /// Count the values of one evaluated batch
+    pub(crate) fn add(&self, values: &BooleanArray) {
+        self.rows.fetch_add(values.len() as u64, Ordering::Relaxed);
+        self.kept
+            .fetch_add(values.true_count() as u64, Ordering::Relaxed);
+        self.nulls
+            .fetch_add(values.null_count() as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> PredicateObservation {
+        PredicateObservation {
+            rows: self.rows.load(Ordering::Relaxed) as f64,
+            kept: self.kept.load(Ordering::Relaxed) as f64,
+            nulls: self.nulls.load(Ordering::Relaxed) as f64,
+        }
+    }
+}
+
+/// Counters of the predicates of one execution, by [`predicate_key`]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ObservedPredicates(Arc<Mutex<Vec<(String, Arc<PredicateCounters>)>>>);
+
+impl ObservedPredicates {
+    /// Counters of the predicate with `key`, shared by its repeated uses
+    pub(crate) fn counters(&self, key: String) -> Arc<PredicateCounters> {
+        let mut predicates = self.0.lock().unwrap();
+        if let Some((_, counters)) = predicates.iter().find(|(k, _)| *k == key) {
+            return counters.clone();
+        }
+        let counters = Arc::new(PredicateCounters::default());
+        predicates.push((key, counters.clone()));
+        counters
+    }
+
+    /// Record the predicates that saw rows into `feedback`
+    pub(crate) fn record_into(&self, feedback: &SelectivityFeedback, fingerprint: &str) {
+        for (key, counters) in self.0.lock().unwrap().iter() {
+            let observation = counters.snapshot();
+            if observation.rows > 0.0 {
+                feedback.record(fingerprint, key, observation);
+            }
+        }
+    }
+}
+
+/// `operator` with its operands swapped: `1 < x` is `x > 1`
+pub(crate) fn flipped(operator: &ComparisonOperator) -> ComparisonOperator {
+    match operator {
+        ComparisonOperator::LessThan => ComparisonOperator::GreaterThan,
+        ComparisonOperator::LessThanOrEqual => ComparisonOperator::GreaterThanOrEqual,
+        ComparisonOperator::GreaterThan => ComparisonOperator::LessThan,
+        ComparisonOperator::GreaterThanOrEqual => ComparisonOperator::LessThanOrEqual,
+        other => other.clone(),
+    }
+}
+
+/// Share of rows `predicate` is assumed to keep without statistics
+fn selectivity(predicate: &BooleanExpression) -> f64 {
+    match predicate {
+        BooleanExpression::Comparison {
+            operator: ComparisonOperator::Equal,
+            ..
+        } => EQUALITY_SELECTIVITY,
+        BooleanExpression::In { list, .. } => (EQUALITY_SELECTIVITY * list.len() as f64).min(1.0),
+        BooleanExpression::And(left, right) => selectivity(left) * selectivity(right),
+        BooleanExpression::Or(left, right) => {
+            let (l, r) = (selectivity(left), selectivity(right));
+            l + r - l * r
+        }
+        BooleanExpression::Not(inner) => 1.0 - selectivity(inner),
+        BooleanExpression::AllInPath { .. } => 1.0,
+        _ => RANGE_SELECTIVITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{PropertyRef, PropertyValue, RelationshipDirection, SortDirection};
+    use crate::logical_plan::{PathFilter, SortItem};
+    use crate::summary::summary_schema;
+    use arrow_array::ArrayRef;
+    use std::sync::Arc;
+
+    fn statistics() -> GraphStatistics {
+        GraphStatistics::new()
+            .with_node(
+                "Person",
+                ElementStatistics::new(1_000).with_size_bytes(100_000),
+            )
+            .with_relationship(
+                "KNOWS",
+                ElementStatistics::new(10_000).with_size_bytes(160_000),
+            )
+    }
+
+    fn scan(variable: &str) -> LogicalOperator {
+        LogicalOperator::ScanByLabel {
+            variable: variable.to_string(),
+            label: "Person".to_string(),
+            properties: HashMap::new(),
+        }
+    }
+
+    fn knows(input: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::Expand {
+            input: Box::new(input),
+            source_variable: "a".to_string(),
+            target_variable: "b".to_string(),
+            target_label: "Person".to_string(),
+            relationship_types: vec!["KNOWS".to_string()],
+            direction: RelationshipDirection::Outgoing,
+            relationship_variable: None,
+            properties: HashMap::new(),
+            target_properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_scan_and_filter() {
+        let plan = LogicalOperator::Filter {
+            input: Box::new(scan("a")),
+            predicate: BooleanExpression::Comparison {
+                left: ValueExpression::Property(PropertyRef::new("a", "age")),
+                operator: ComparisonOperator::Equal,
+                right: ValueExpression::Literal(PropertyValue::Integer(30)),
+            },
+        };
+        let estimate = estimate_cost(&plan, &statistics()).unwrap();
+        assert_eq!(estimate.rows, 100);
+        assert_eq!(estimate.bytes_scanned, 100_000);
+        assert_eq!(estimate.peak_memory_bytes, 100_000);
+    }
+
+    #[test]
+    fn test_expand_multiplies_by_average_degree() {
+        let estimate = estimate_cost(&knows(scan("a")), &statistics()).unwrap();
+        assert_eq!(estimate.rows, 10_000);
+        assert_eq!(estimate.bytes_scanned, 100_000 + 160_000 + 100_000);
+    }
+
+    #[test]
+    fn test_expand_follows_degree_distributions() {
+        let statistics = statistics()
+            .with_out_degree(
+                "KNOWS",
+                DegreeDistribution {
+                    mean: 2.0,
+                    p95: 2.0,
+                    ..Default::default()
+                },
+            )
+            .with_in_degree(
+                "KNOWS",
+                DegreeDistribution {
+                    mean: 100.0,
+                    p95: 400.0,
+                    ..Default::default()
+                },
+            );
+        let hop = |direction| {
+            let mut plan = knows(scan("a"));
+            if let LogicalOperator::Expand { direction: d, .. } = &mut plan {
+                *d = direction;
+            }
+            estimate_cost(&plan, &statistics).unwrap()
+        };
+
+        // 5000 sources have a KNOWS, more than there are people, so every
+        // person expands to the mean of 2
+        let outgoing = hop(RelationshipDirection::Outgoing);
+        assert_eq!(outgoing.rows, 2_000);
+        assert_eq!(outgoing.peak_memory_bytes, 100_000 + 160_000 + 100_000);
+
+        // 100 people are known, by 100 others each on average, and the hub
+        // batches hold 400 rows per source of 216 bytes
+        let incoming = hop(RelationshipDirection::Incoming);
+        assert_eq!(incoming.rows, 10_000);
+        assert_eq!(
+            incoming.peak_memory_bytes,
+            100_000 + 160_000 + 100_000 + 1_000 * 390 * 216
+        );
+
+        assert_eq!(hop(RelationshipDirection::Undirected).rows, 12_000);
+    }
+
+    #[test]
+    fn test_variable_length_expand_grows_per_hop() {
+        let plan = LogicalOperator::VariableLengthExpand {
+            input: Box::new(scan("a")),
+            source_variable: "a".to_string(),
+            target_variable: "b".to_string(),
+            relationship_types: vec!["KNOWS".to_string()],
+            direction: RelationshipDirection::Outgoing,
+            relationship_variable: None,
+            min_length: Some(1),
+            max_length: Some(2),
+            target_properties: HashMap::new(),
+            path_filter: PathFilter::default(),
+        };
+        let estimate = estimate_cost(&plan, &statistics()).unwrap();
+        // 1000 sources * (10 + 100) paths
+        assert_eq!(estimate.rows, 110_000);
+        assert_eq!(estimate.bytes_scanned, 100_000 + 2 * 160_000);
+    }
+
+    #[test]
+    fn test_top_k_holds_only_kept_rows() {
+        let sort = LogicalOperator::Sort {
+            input: Box::new(scan("a")),
+            sort_items: vec![SortItem {
+                expression: ValueExpression::Property(PropertyRef::new("a", "age")),
+                direction: SortDirection::Ascending,
+            }],
+        };
+        let full = estimate_cost(&sort, &statistics()).unwrap();
+        let top = estimate_cost(
+            &LogicalOperator::Limit {
+                input: Box::new(sort),
+                count: 10,
+            },
+            &statistics(),
+        )
+        .unwrap();
+        assert_eq!(top.rows, 10);
+        assert_eq!(full.peak_memory_bytes, 200_000);
+        assert_eq!(top.peak_memory_bytes, 100_000 + 1_000);
+    }
+
+    #[test]
+    fn test_observed_selectivity_replaces_the_guess() {
+        let predicate = BooleanExpression::Comparison {
+            left: ValueExpression::Property(PropertyRef::new("a", "age")),
+            operator: ComparisonOperator::Equal,
+            right: ValueExpression::Literal(PropertyValue::Integer(30)),
+        };
+        let plan = LogicalOperator::Filter {
+            input: Box::new(scan("a")),
+            predicate: predicate.clone(),
+        };
+        let feedback = Arc::new(SelectivityFeedback::new());
+        let statistics = statistics().with_feedback(feedback.clone());
+        let estimate = |fingerprint| {
+            estimate_cost_with_fingerprint(&plan, &statistics, fingerprint)
+                .unwrap()
+                .rows
+        };
+        assert_eq!(estimate(Some("q1")), 100);
+
+        let observed = ObservedPredicates::default();
+        let counters = observed.counters(predicate_key(&predicate));
+        counters.add(&BooleanArray::from(vec![
+            Some(true),
+            Some(false),
+            None,
+            Some(false),
+        ]));
+        observed.record_into(&feedback, "q1");
+        assert_eq!(estimate(Some("q1")), 250);
+        assert_eq!(estimate(Some("q2")), 100, "other queries keep the guess");
+        assert_eq!(estimate(None), 100);
+
+        let [(_, observation)] = feedback.observations("q1").try_into().unwrap();
+        assert_eq!(observation.null_fraction(), Some(0.25));
+
+        // Later runs outweigh earlier ones
+        feedback.record(
+            "q1",
+            &predicate_key(&predicate),
+            PredicateObservation {
+                rows: 4.0,
+                kept: 4.0,
+                nulls: 0.0,
+            },
+        );
+        assert_eq!(estimate(Some("q1")), 750);
+    }
+
+    #[test]
+    fn test_histogram_fraction_below_interpolates() {
+        let histogram = Histogram {
+            bounds: vec![20.0, 25.0, 30.0, 35.0, 40.0],
+        };
+        assert_eq!(histogram.fraction_below(10.0), Some(0.0));
+        assert_eq!(histogram.fraction_below(30.0), Some(0.5));
+        assert_eq!(histogram.fraction_below(32.5), Some(0.625));
+        assert_eq!(histogram.fraction_below(50.0), Some(1.0));
+        assert_eq!(Histogram { bounds: vec![1.0] }.fraction_below(1.0), None);
+    }
+
+    #[test]
+    fn test_distributions_drive_filter_selectivity() {
+        let statistics = statistics()
+            .with_node_property(
+                "Person",
+                "age",
+                PropertyStatistics {
+                    null_fraction: 0.2,
+                    distinct_count: Some(20),
+                    histogram: Some(Histogram {
+                        bounds: vec![20.0, 25.0, 30.0, 35.0, 40.0],
+                    }),
+                    most_common: Vec::new(),
+                },
+            )
+            .with_node_property(
+                "Person",
+                "city",
+                PropertyStatistics {
+                    null_fraction: 0.0,
+                    distinct_count: Some(4),
+                    histogram: None,
+                    most_common: vec![MostCommonValue {
+                        value: "Oslo".to_string(),
+                        frequency: 0.25,
+                    }],
+                },
+            );
+        let rows = |property: &str, operator, value| {
+            let plan = LogicalOperator::Filter {
+                input: Box::new(scan("a")),
+                predicate: BooleanExpression::Comparison {
+                    left: ValueExpression::Property(PropertyRef::new("a", property)),
+                    operator,
+                    right: ValueExpression::Literal(value),
+                },
+            };
+            estimate_cost(&plan, &statistics).unwrap().rows
+        };
+
+        assert_eq!(
+            rows(
+                "age",
+                ComparisonOperator::LessThan,
+                PropertyValue::Integer(30)
+            ),
+            400
+        );
+        assert_eq!(
+            rows("age", ComparisonOperator::Equal, PropertyValue::Integer(99)),
+            0,
+            "values outside the histogram match nothing"
+        );
+        assert_eq!(
+            rows(
+                "city",
+                ComparisonOperator::Equal,
+                PropertyValue::String("Oslo".to_string())
+            ),
+            250
+        );
+        assert_eq!(
+            rows(
+                "city",
+                ComparisonOperator::Equal,
+                PropertyValue::String("Rome".to_string())
+            ),
+            250,
+            "unlisted values share the remaining rows"
+        );
+        // Properties without statistics keep the fixed guess
+        assert_eq!(
+            rows(
+                "name",
+                ComparisonOperator::LessThan,
+                PropertyValue::Integer(30)
+            ),
+            334
+        );
+    }
+
+    #[test]
+    fn test_missing_statistics_is_an_error() {
+        let err = estimate_cost(&knows(scan("a")), &GraphStatistics::new()).unwrap_err();
+        assert!(err.to_string().contains("No statistics for label 'Person'"));
+    }
+
+    #[test]
+    fn test_statistics_from_summary_batch() {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["node", "node", "relationship"])),
+            Arc::new(StringArray::from(vec!["Person", "Person", "KNOWS"])),
+            Arc::new(StringArray::from(vec![None::<&str>, None, None])),
+            Arc::new(StringArray::from(vec!["count", "size_bytes", "count"])),
+            Arc::new(Float64Array::from(vec![4.0, 400.0, 6.0])),
+            Arc::new(StringArray::from(vec![None::<&str>, None, None])),
+        ];
+        let batch = RecordBatch::try_new(summary_schema(), columns).unwrap();
+
+        let statistics = GraphStatistics::from_summary(&batch).unwrap();
+        assert_eq!(
+            statistics.node("person"),
+            Some(&ElementStatistics::new(4).with_size_bytes(400))
+        );
+        assert_eq!(
+            statistics.relationship("KNOWS"),
+            Some(&ElementStatistics::new(6))
+        );
+    }
+
+    #[test]
+    fn test_distributions_from_summary_batch() {
+        let rows = [
+            ("node", "Person", Some("age"), "null_ratio", 0.25, None),
+            ("node", "Person", Some("age"), "distinct_count", 3.0, None),
+            ("node", "Person", Some("age"), "value_min", 20.0, None),
+            ("node", "Person", Some("age"), "value_p25", 25.0, None),
+            ("node", "Person", Some("age"), "value_p50", 30.0, None),
+            ("node", "Person", Some("age"), "value_p75", 35.0, None),
+            ("node", "Person", Some("age"), "value_max", 40.0, None),
+            (
+                "node",
+                "Person",
+                Some("city"),
+                "most_common",
+                0.5,
+                Some("Oslo"),
+            ),
+            ("relationship", "KNOWS", None, "out_degree_p50", 1.0, None),
+            ("relationship", "KNOWS", None, "out_degree_max", 3.0, None),
+            ("relationship", "KNOWS", None, "in_degree_p95", 2.0, None),
+        ];
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.0))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.1))),
+            Arc::new(StringArray::from_iter(rows.iter().map(|r| r.2))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.3))),
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.4))),
+            Arc::new(StringArray::from_iter(rows.iter().map(|r| r.5))),
+        ];
+        let batch = RecordBatch::try_new(summary_schema(), columns).unwrap();
+
+        let statistics = GraphStatistics::from_summary(&batch).unwrap();
+        assert_eq!(
+            statistics.node_property("Person", "AGE"),
+            Some(&PropertyStatistics {
+                null_fraction: 0.25,
+                distinct_count: Some(3),
+                histogram: Some(Histogram {
+                    bounds: vec![20.0, 25.0, 30.0, 35.0, 40.0],
+                }),
+                most_common: Vec::new(),
+            })
+        );
+        assert_eq!(
+            statistics
+                .node_property("Person", "city")
+                .unwrap()
+                .most_common,
+            vec![MostCommonValue {
+                value: "Oslo".to_string(),
+                frequency: 0.5,
+            }]
+        );
+        let degree = statistics.out_degree("knows").unwrap();
+        assert_eq!((degree.p50, degree.max), (1.0, 3.0));
+        assert_eq!(statistics.in_degree("knows").unwrap().p95, 2.0);
+        // Property and degree metrics do not make up element statistics
+        assert_eq!(statistics.node("Person"), None);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let feedback = Arc::new(SelectivityFeedback::new());
+        feedback.record(
+            "q1",
+            "{}",
+            PredicateObservation {
+                rows: 10.0,
+                kept: 2.0,
+                nulls: 1.0,
+            },
+        );
+        let statistics = statistics()
+            .with_node_property(
+                "Person",
+                "name",
+                PropertyStatistics {
+                    null_fraction: 0.0,
+                    distinct_count: Some(990),
+                    histogram: None,
+                    most_common: Vec::new(),
+                },
+            )
+            .with_out_degree(
+                "KNOWS",
+                DegreeDistribution {
+                    mean: 10.0,
+                    p50: 8.0,
+                    p90: 20.0,
+                    p95: 30.0,
+                    p99: 60.0,
+                    max: 200.0,
+                },
+            )
+            .with_in_degree(
+                "KNOWS",
+                DegreeDistribution {
+                    mean: 10.0,
+                    p50: 10.0,
+                    p90: 12.0,
+                    p95: 12.0,
+                    p99: 15.0,
+                    max: 20.0,
+                },
+            )
+            .with_feedback(feedback);
+
+        let document = statistics.export();
+        assert_eq!(document.format_version, STATISTICS_FORMAT_VERSION);
+        assert_eq!(document.nodes[0].name, "person");
+        assert_eq!(document.nodes[0].cardinality, 1_000);
+        assert_eq!(document.observed_predicates.len(), 1);
+
+        let json = serde_json::to_string(&document).unwrap();
+        let parsed: StatisticsDocument = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, document);
+        let restored = GraphStatistics::from_document(&parsed).unwrap();
+        assert_eq!(restored.export(), document);
+        assert_eq!(
+            restored
+                .feedback
+                .as_ref()
+                .and_then(|f| f.observation("q1", "{}"))
+                .and_then(|o| o.selectivity()),
+            Some(0.2)
+        );
+
+        let err = GraphStatistics::from_document(&StatisticsDocument {
+            format_version: 99,
+            ..document
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("format version 99"));
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Refreshable credentials for remote datasets
+//!
+//! Static keys in storage options stop working when they expire, which fails
+//! long-running engines in the middle of a query. A [`CredentialsProvider`]
+//! attached with [`crate::CypherQuery::with_credentials_provider`] is asked for
+//! credentials whenever a dataset is opened from a namespace. Credentials
+//! carrying an expiry are fetched again from the provider shortly before they
+//! expire, for as long as the dataset stays open; credentials without one are
+//! used as they are.
+//!
+//! Refresh currently applies to S3 datasets, where Lance supports dynamic
+//! credentials. Other stores use the first credentials returned.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::credentials::{Credentials, CredentialsProvider};
+//!
+//! #[derive(Debug)]
+//! struct AssumeRole { role_arn: String }
+//!
+//! #[async_trait::async_trait]
+//! impl CredentialsProvider for AssumeRole {
+//!     async fn credentials(&self, table: &str, uri: &str) -> Result<Credentials> {
+//!         let session = sts_assume_role(&self.role_arn).await?;
+//!         Ok(Credentials::new()
+//!             .with_aws_keys(session.key_id, session.secret, Some(session.token))
+//!             .with_expires_at(session.expiration))
+//!     }
+//!
+//!     fn provider_id(&self) -> String {
+//!         format!("AssumeRole({})", self.role_arn)
+//!     }
+//! }
+//! ```
+
+use crate::error::Result;
+use async_trait::async_trait;
+use lance_graph_catalog::StorageOptions;
+use lance_io::object_store::{StorageOptionsProvider, EXPIRES_AT_MILLIS_KEY};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Credentials for one dataset and when they stop being valid
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    /// Storage options carrying the credentials, e.g. `aws_session_token`
+    pub options: StorageOptions,
+    /// When the credentials expire; `None` if they never do
+    pub expires_at: Option<SystemTime>,
+}
+
+impl Credentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set AWS access keys, with a session token for temporary credentials
+    pub fn with_aws_keys(
+        mut self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        self.options =
+            self.options
+                .with_aws_credentials(access_key_id, secret_access_key, session_token);
+        self
+    }
+
+    /// Set a raw storage option, e.g. an Azure SAS token
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options = self.options.with_option(key, value);
+        self
+    }
+
+    pub fn with_expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Storage options including the expiry Lance schedules refreshes by
+    pub(crate) fn to_storage_options(&self) -> HashMap<String, String> {
+        let mut options: HashMap<String, String> = self.options.clone().into();
+        if let Some(expires_at) = self.expires_at {
+            let millis = expires_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            options.insert(EXPIRES_AT_MILLIS_KEY.to_string(), millis.to_string());
+        }
+        options
+    }
+}
+
+/// Source of credentials for the datasets a query opens
+///
+/// Implementations typically assume a role through STS or exchange a token
+/// with an identity provider, and may cache credentials themselves.
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync + fmt::Debug {
+    /// Current credentials for the dataset of `table` stored at `uri`
+    async fn credentials(&self, table: &str, uri: &str) -> Result<Credentials>;
+
+    /// Identifier of the provider's configuration, e.g. the role it assumes
+    ///
+    /// Datasets opened through providers with the same id share object store
+    /// connections.
+    fn provider_id(&self) -> String;
+}
+
+/// Lets Lance refresh a dataset's credentials through a [`CredentialsProvider`]
+#[derive(Debug)]
+pub(crate) struct DatasetCredentials {
+    provider: Arc<dyn CredentialsProvider>,
+    table: String,
+    uri: String,
+}
+
+impl DatasetCredentials {
+    pub(crate) fn new(provider: Arc<dyn CredentialsProvider>, table: &str, uri: &str) -> Self {
+        Self {
+            provider,
+            table: table.to_string(),
+            uri: uri.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl StorageOptionsProvider for DatasetCredentials {
+    async fn fetch_storage_options(&self) -> lance::Result<Option<HashMap<String, String>>> {
+        let credentials = self
+            .provider
+            .credentials(&self.table, &self.uri)
+            .await
+            .map_err(|e| lance::Error::IO {
+                source: Box::new(std::io::Error::other(format!(
+                    "Failed to refresh credentials for table '{}': {}",
+                    self.table, e
+                ))),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        Ok(Some(credentials.to_storage_options()))
+    }
+
+    fn provider_id(&self) -> String {
+        format!("{},table[{}]", self.provider.provider_id(), self.uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct Rotating {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CredentialsProvider for Rotating {
+        async fn credentials(&self, _table: &str, _uri: &str) -> Result<Credentials> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Credentials::new()
+                .with_aws_keys("AKIA", format!("secret-{}", call), Some("token".into()))
+                .with_expires_at(UNIX_EPOCH + Duration::from_millis(1_000)))
+        }
+
+        fn provider_id(&self) -> String {
+            "Rotating".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_fetches_new_credentials_with_expiry() {
+        let provider = Arc::new(Rotating::default());
+        let adapter = DatasetCredentials::new(provider.clone(), "Person", "s3://b/Person.lance");
+
+        let first = adapter.fetch_storage_options().await.unwrap().unwrap();
+        let second = adapter.fetch_storage_options().await.unwrap().unwrap();
+        assert_eq!(first["aws_secret_access_key"], "secret-0");
+        assert_eq!(second["aws_secret_access_key"], "secret-1");
+        assert_eq!(second[EXPIRES_AT_MILLIS_KEY], "1000");
+        assert_eq!(adapter.provider_id(), "Rotating,table[s3://b/Person.lance]");
+    }
+
+    #[test]
+    fn test_credentials_without_expiry_have_no_expiry_key() {
+        let options = Credentials::new()
+            .with_option("azure_storage_sas_token", "sig")
+            .to_storage_options();
+        assert_eq!(options.len(), 1);
+        assert!(!options.contains_key(EXPIRES_AT_MILLIS_KEY));
+    }
+}
@@ -195,18 +195,20 @@ fn analyze_operator(
             }
         }
         LogicalOperator::Filter { input, .. }
+        | LogicalOperator::Sample { input, .. }
         | LogicalOperator::Project { input, .. }
         | LogicalOperator::Sort { input, .. }
         | LogicalOperator::Limit { input, .. }
         | LogicalOperator::Offset { input, .. }
-        | LogicalOperator::Distinct { input } => {
+        | LogicalOperator::Distinct { input }
+        | LogicalOperator::DistinctOn { input, .. } => {
             analyze_operator(input, analysis, rel_counter)?;
         }
         LogicalOperator::Join { left, right, .. } => {
             analyze_operator(left, analysis, rel_counter)?;
             analyze_operator(right, analysis, rel_counter)?;
         }
-        LogicalOperator::Unwind { input, .. } => {
+        LogicalOperator::Unwind { input, .. } | LogicalOperator::TableFunction { input, .. } => {
             if let Some(op) = input {
                 analyze_operator(op, analysis, rel_counter)?;
             }
@@ -331,6 +333,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(2),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
 
         let cfg = crate::config::GraphConfig::builder()
@@ -6,7 +6,8 @@
 use crate::datafusion_planner::DataFusionPlanner;
 use crate::error::Result;
 use crate::logical_plan::*;
-use datafusion::logical_expr::{col, LogicalPlan, LogicalPlanBuilder};
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::logical_expr::{col, Expr, LogicalPlan, LogicalPlanBuilder};
 
 impl DataFusionPlanner {
     pub(crate) fn build_project_with_aggregates(
@@ -17,8 +18,8 @@ impl DataFusionPlanner {
         // Separate group expressions (non-aggregates) from aggregate expressions
         let mut group_exprs = Vec::new();
         let mut agg_exprs = Vec::new();
-        // Store computed aliases for aggregates to reuse in final projection
-        let mut agg_aliases = Vec::new();
+        // Output expressions of aggregate projections, over the aggregated columns
+        let mut agg_outputs = Vec::new();
 
         for p in projections {
             let expr = super::super::expression::to_df_value_expr(&p.expression);
@@ -30,8 +31,26 @@ impl DataFusionPlanner {
                 } else {
                     super::super::expression::to_cypher_column_name(&p.expression)
                 };
-                agg_exprs.push(expr.alias(&alias));
-                agg_aliases.push(alias);
+                if matches!(expr, Expr::AggregateFunction(_)) {
+                    agg_exprs.push(expr.alias(&alias));
+                    agg_outputs.push(col(&alias));
+                } else {
+                    // Aggregates inside other expressions (e.g. a list
+                    // comprehension over collect(...)) are computed first and
+                    // the expression is applied to their results
+                    let outer = expr
+                        .transform_up(|e| match e {
+                            Expr::AggregateFunction(_) => {
+                                let name = format!("__aggregate_{}", agg_exprs.len());
+                                agg_exprs.push(e.alias(&name));
+                                Ok(Transformed::yes(col(name)))
+                            }
+                            e => Ok(Transformed::no(e)),
+                        })
+                        .map_err(|e| self.plan_error("Failed to split nested aggregates", e))?
+                        .data;
+                    agg_outputs.push(outer.alias(&alias));
+                }
             } else {
                 // Group expressions: use raw expression for grouping, no alias
                 group_exprs.push(expr);
@@ -54,8 +73,8 @@ impl DataFusionPlanner {
                 };
                 final_projection.push(aliased);
             } else {
-                // For aggregates, reference the column using the same alias we computed earlier
-                final_projection.push(col(&agg_aliases[agg_idx]));
+                // For aggregates, reference the columns computed by the aggregation
+                final_projection.push(agg_outputs[agg_idx].clone());
                 agg_idx += 1;
             }
         }
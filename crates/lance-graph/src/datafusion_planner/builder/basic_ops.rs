@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
-//! Basic operations: Filter, Project, Distinct, Sort, Limit, Offset
+//! Basic operations: Filter, Project, Distinct, DistinctOn, Sort, Limit, Offset
 
 use crate::datafusion_planner::analysis::PlanningContext;
 use crate::datafusion_planner::DataFusionPlanner;
@@ -16,13 +16,28 @@ impl DataFusionPlanner {
         input: &LogicalOperator,
         predicate: &crate::ast::BooleanExpression,
     ) -> Result<LogicalPlan> {
+        if let Some(plan) = self.try_build_pruned_filter(ctx, input, predicate)? {
+            return Ok(plan);
+        }
+        if let Some(observed) = &self.observed_predicates {
+            let counters = observed.counters(crate::cost::predicate_key(predicate));
+            let predicate = self.bind_in_list_parameters(predicate)?;
+            let condition = super::super::udf::create_observed_predicate_udf(counters).call(vec![
+                super::super::expression::to_df_boolean_expr(&predicate),
+            ]);
+            let input_plan = self.build_operator(ctx, input)?;
+            return LogicalPlanBuilder::from(input_plan)
+                .filter(condition)
+                .map_err(|e| self.plan_error("Failed to build filter", e))?
+                .build()
+                .map_err(|e| self.plan_error("Failed to build filter", e));
+        }
+        let predicate = self.bind_in_list_parameters(predicate)?;
+        if let Some(plan) = self.try_build_disjunctive_scan(ctx, input, &predicate)? {
+            return Ok(plan);
+        }
         let input_plan = self.build_operator(ctx, input)?;
-        let expr = super::super::expression::to_df_boolean_expr(predicate);
-        LogicalPlanBuilder::from(input_plan)
-            .filter(expr)
-            .map_err(|e| self.plan_error("Failed to build filter", e))?
-            .build()
-            .map_err(|e| self.plan_error("Failed to build plan", e))
+        self.apply_predicate(ctx, input_plan, &predicate)
     }
 
     pub(crate) fn build_project(
@@ -31,6 +46,9 @@ impl DataFusionPlanner {
         input: &LogicalOperator,
         projections: &[ProjectionItem],
     ) -> Result<LogicalPlan> {
+        if let Some(plan) = self.try_build_statistics_aggregate(input, projections)? {
+            return Ok(plan);
+        }
         let input_plan = self.build_operator(ctx, input)?;
 
         // Check if any projection contains an aggregate function
@@ -50,7 +68,7 @@ impl DataFusionPlanner {
         input_plan: LogicalPlan,
         projections: &[ProjectionItem],
     ) -> Result<LogicalPlan> {
-        let exprs: Vec<datafusion::logical_expr::Expr> = projections
+        let mut exprs: Vec<datafusion::logical_expr::Expr> = projections
             .iter()
             .map(|p| {
                 let expr = super::super::expression::to_df_value_expr(&p.expression);
@@ -66,6 +84,14 @@ impl DataFusionPlanner {
                 }
             })
             .collect();
+        if self.include_provenance {
+            let returned: Vec<String> = exprs.iter().map(|e| e.schema_name().to_string()).collect();
+            exprs.extend(
+                provenance_projection(&input_plan)
+                    .into_iter()
+                    .filter(|e| !returned.contains(&e.schema_name().to_string())),
+            );
+        }
         LogicalPlanBuilder::from(input_plan)
             .project(exprs)
             .map_err(|e| self.plan_error("Failed to build projection", e))?
@@ -86,6 +112,30 @@ impl DataFusionPlanner {
             .map_err(|e| self.plan_error("Failed to build plan", e))
     }
 
+    pub(crate) fn build_distinct_on(
+        &self,
+        ctx: &mut PlanningContext,
+        input: &LogicalOperator,
+        keys: &[crate::ast::ValueExpression],
+    ) -> Result<LogicalPlan> {
+        let input_plan = self.build_operator(ctx, input)?;
+        let on_exprs = keys
+            .iter()
+            .map(super::super::expression::to_df_value_expr)
+            .collect();
+        let columns = input_plan
+            .schema()
+            .columns()
+            .into_iter()
+            .map(datafusion::logical_expr::Expr::Column)
+            .collect();
+        LogicalPlanBuilder::from(input_plan)
+            .distinct_on(on_exprs, columns, None)
+            .map_err(|e| self.plan_error("Failed to build distinct on", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))
+    }
+
     pub(crate) fn build_sort(
         &self,
         ctx: &mut PlanningContext,
@@ -183,6 +233,25 @@ impl DataFusionPlanner {
     }
 }
 
+/// Carry every entity's provenance columns (e.g., `n___rowid`) into the result
+/// using Cypher dot notation (`n._rowid`)
+fn provenance_projection(input_plan: &LogicalPlan) -> Vec<datafusion::logical_expr::Expr> {
+    input_plan
+        .schema()
+        .fields()
+        .iter()
+        .filter_map(|field| {
+            let (entity, column) = field.name().split_once("__")?;
+            crate::datafusion_planner::PROVENANCE_COLUMNS
+                .contains(&column)
+                .then(|| {
+                    datafusion::logical_expr::col(field.name())
+                        .alias(format!("{}.{}", entity, column))
+                })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ast::{
@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Disjunction rewrite: OR across indexed columns becomes a UNION of scans
+//!
+//! A predicate such as `n.email = 'a@example.com' OR n.phone = '555-0100'`
+//! cannot use either column's scalar index as a whole, so the scan reads
+//! every row. When each disjunct filters a single indexed property of the
+//! scanned node, the planner instead scans once per disjunct (each filter
+//! pushed down to its index) and unions the branches. Each branch skips the
+//! rows an earlier disjunct matches, so a row matching several disjuncts is
+//! returned once, and duplicate rows as often as the table holds them.
+
+use crate::ast::{BooleanExpression, ComparisonOperator, ValueExpression};
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::expression::to_df_boolean_expr;
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::Result;
+use crate::logical_plan::*;
+use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder};
+use std::collections::HashSet;
+
+impl DataFusionPlanner {
+    /// Plan `Filter(ScanByLabel, a OR b ...)` as a UNION of index-backed scans
+    /// of disjoint rows, or `None` when the rewrite does not apply
+    ///
+    /// Applies when every disjunct compares one indexed property (see
+    /// [`crate::config::NodeMapping::with_indexed_properties`]) of the scanned
+    /// variable with `=` or `IN`, and at least two distinct properties are
+    /// involved. ORs over a single column are left to the IN-list pushdown.
+    pub(crate) fn try_build_disjunctive_scan(
+        &self,
+        ctx: &mut PlanningContext,
+        input: &LogicalOperator,
+        predicate: &BooleanExpression,
+    ) -> Result<Option<LogicalPlan>> {
+        let LogicalOperator::ScanByLabel {
+            variable,
+            label,
+            properties,
+        } = input
+        else {
+            return Ok(None);
+        };
+
+        // View labels inherit the indexes of their base label's dataset
+        let (base_label, _) = self.config.resolve_view_chain(label)?;
+        let Some(node_map) = self.config.get_node_mapping(&base_label) else {
+            return Ok(None);
+        };
+
+        let mut disjuncts = Vec::new();
+        flatten_or(predicate, &mut disjuncts);
+        if disjuncts.len() < 2 {
+            return Ok(None);
+        }
+
+        let mut columns = HashSet::new();
+        for disjunct in &disjuncts {
+            match filtered_property(disjunct, variable) {
+                Some(property) if node_map.is_indexed(property) => {
+                    columns.insert(property.to_lowercase());
+                }
+                _ => return Ok(None),
+            }
+        }
+        if columns.len() < 2 {
+            return Ok(None);
+        }
+
+        let mut union: Option<LogicalPlanBuilder> = None;
+        for (i, disjunct) in disjuncts.iter().enumerate() {
+            let scan = self.build_scan(ctx, variable, label, properties)?;
+            // `IS NOT TRUE` keeps rows where an earlier disjunct is null
+            let expr = disjuncts[..i]
+                .iter()
+                .fold(to_df_boolean_expr(disjunct), |expr, earlier| {
+                    expr.and(to_df_boolean_expr(earlier).is_not_true())
+                });
+            let branch = LogicalPlanBuilder::from(scan)
+                .filter(expr)
+                .map_err(|e| self.plan_error("Failed to filter disjunct scan", e))?
+                .build()
+                .map_err(|e| self.plan_error("Failed to build disjunct scan", e))?;
+            union = Some(match union {
+                Some(acc) => acc
+                    .union(branch)
+                    .map_err(|e| self.plan_error("Failed to union disjunct scans", e))?,
+                None => LogicalPlanBuilder::from(branch),
+            });
+        }
+
+        let plan = union
+            .expect("at least two disjuncts")
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))?;
+        Ok(Some(plan))
+    }
+}
+
+/// Collect the operands of a (possibly nested) OR
+fn flatten_or<'a>(expr: &'a BooleanExpression, out: &mut Vec<&'a BooleanExpression>) {
+    match expr {
+        BooleanExpression::Or(left, right) => {
+            flatten_or(left, out);
+            flatten_or(right, out);
+        }
+        other => out.push(other),
+    }
+}
+
+/// Property of `variable` that `expr` filters by equality or IN-list, if any
+fn filtered_property<'a>(expr: &'a BooleanExpression, variable: &str) -> Option<&'a str> {
+    let constant = |value: &ValueExpression| {
+        matches!(
+            value,
+            ValueExpression::Literal(_) | ValueExpression::Parameter(_)
+        )
+    };
+    let property = |value: &'a ValueExpression| match value {
+        ValueExpression::Property(p) if p.variable == variable => Some(p.property.as_str()),
+        _ => None,
+    };
+
+    match expr {
+        BooleanExpression::Comparison {
+            left,
+            operator: ComparisonOperator::Equal,
+            right,
+        } => {
+            if constant(right) {
+                property(left)
+            } else if constant(left) {
+                property(right)
+            } else {
+                None
+            }
+        }
+        BooleanExpression::In { expression, list } if list.iter().all(constant) => {
+            property(expression)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        BooleanExpression, ComparisonOperator, PropertyRef, PropertyValue, ValueExpression,
+    };
+    use crate::config::{GraphConfig, NodeMapping};
+    use crate::datafusion_planner::test_fixtures::{make_catalog, person_scan};
+    use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+    use crate::logical_plan::LogicalOperator;
+
+    fn eq(property: &str, value: PropertyValue) -> BooleanExpression {
+        BooleanExpression::Comparison {
+            left: ValueExpression::Property(PropertyRef {
+                variable: "n".to_string(),
+                property: property.to_string(),
+            }),
+            operator: ComparisonOperator::Equal,
+            right: ValueExpression::Literal(value),
+        }
+    }
+
+    fn planner(indexed: &[&str]) -> DataFusionPlanner {
+        let cfg = GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("Person", "id")
+                    .with_indexed_properties(indexed.iter().map(|p| p.to_string()).collect()),
+            )
+            .build()
+            .unwrap();
+        DataFusionPlanner::with_catalog(cfg, make_catalog())
+    }
+
+    fn or_filter() -> LogicalOperator {
+        LogicalOperator::Filter {
+            input: Box::new(person_scan("n")),
+            predicate: BooleanExpression::Or(
+                Box::new(eq("name", PropertyValue::String("Alice".to_string()))),
+                Box::new(eq("age", PropertyValue::Integer(30))),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_or_across_indexed_columns_becomes_union() {
+        let plan = planner(&["name", "age"]).plan(&or_filter()).unwrap();
+        let s = format!("{:?}", plan);
+        assert!(s.contains("Union"), "expected UNION rewrite: {}", s);
+        // Branches are disjoint instead of de-duplicated
+        assert!(s.contains("IsNotTrue"), "expected disjoint branches: {}", s);
+        assert!(!s.contains("Distinct"), "unexpected de-duplication: {}", s);
+    }
+
+    #[test]
+    fn test_or_with_unindexed_column_keeps_single_scan() {
+        let plan = planner(&["name"]).plan(&or_filter()).unwrap();
+        let s = format!("{:?}", plan);
+        assert!(!s.contains("Union"), "unexpected UNION rewrite: {}", s);
+    }
+}
@@ -3,15 +3,34 @@
 
 //! Graph traversal operations: Expand and Variable-Length Expand
 
-use crate::ast::RelationshipDirection;
+use crate::ast::{BooleanExpression, RelationshipDirection, ShortestPathKind, ValueExpression};
+use crate::case_insensitive::qualify_column;
 use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::expression::{
+    to_df_boolean_expr, to_df_value_expr, PATH_LENGTH_PROPERTY,
+};
 use crate::datafusion_planner::join_ops::{SourceJoinParams, TargetJoinParams};
+use crate::datafusion_planner::udf;
 use crate::datafusion_planner::DataFusionPlanner;
 use crate::error::Result;
 use crate::logical_plan::*;
-use datafusion::logical_expr::{col, LogicalPlan, LogicalPlanBuilder};
+use datafusion::functions::core::expr_fn::named_struct;
+use datafusion::functions_nested::expr_fn::make_array;
+use datafusion::functions_window::expr_fn::{rank, row_number};
+use datafusion::logical_expr::{col, lit, Expr, ExprFunctionExt, LogicalPlan, LogicalPlanBuilder};
 use std::collections::HashMap;
 
+/// Hop count of each unrolled path, present only while path limits apply
+const PATH_HOPS_COLUMN: &str = "__path_hops";
+/// Rank of a path among those of its source node
+const PATH_SOURCE_RANK_COLUMN: &str = "__path_source_rank";
+/// Rank of a path among all paths of the expansion
+const PATH_RANK_COLUMN: &str = "__path_rank";
+/// Rank of a path by length among those between the same end nodes
+const SHORTEST_PATH_RANK_COLUMN: &str = "__shortest_path_rank";
+/// Field of the path object listing the keys of the nodes along the path
+const PATH_NODES_FIELD: &str = "nodes";
+
 impl DataFusionPlanner {
     /// Build a relationship expansion (graph traversal) as a series of joins
     #[allow(clippy::too_many_arguments)]
@@ -65,7 +84,7 @@ impl DataFusionPlanner {
         let source_params = SourceJoinParams {
             source_variable,
             rel_qualifier: &rel_instance.alias,
-            node_id_field: &node_map.id_field,
+            node_map,
             rel_map,
             direction,
         };
@@ -95,6 +114,9 @@ impl DataFusionPlanner {
     /// For a query like: (a)-[:KNOWS*1..3]->(b)
     /// This generates:
     ///   1-hop plan UNION 2-hop plan UNION 3-hop plan
+    ///
+    /// The path filter is applied hop by hop, so each unrolled plan drops a
+    /// partial path as soon as it reaches a rejected relationship or node.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn build_variable_length_expand(
         &self,
@@ -107,6 +129,7 @@ impl DataFusionPlanner {
         min_length: Option<u32>,
         max_length: Option<u32>,
         target_properties: &HashMap<String, crate::ast::PropertyValue>,
+        path_filter: &PathFilter,
     ) -> Result<LogicalPlan> {
         let min_hops = min_length.unwrap_or(1).max(1);
         let max_hops = max_length.unwrap_or(crate::MAX_VARIABLE_LENGTH_HOPS);
@@ -133,14 +156,33 @@ impl DataFusionPlanner {
             });
         }
 
-        // Build the input plan (source node scan)
+        // Build the input plan (source node scan); the start node is the
+        // first element of nodes(p)
         let input_plan = self.build_operator(ctx, input)?;
+        let input_plan = if path_filter.node_predicates.is_empty() {
+            input_plan
+        } else {
+            self.filter_path_builder(
+                LogicalPlanBuilder::from(input_plan),
+                path_filter
+                    .node_predicates
+                    .iter()
+                    .map(|(element, predicate)| {
+                        path_predicate(predicate, element, source_variable)
+                    }),
+            )?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build path start filter", e))?
+        };
 
         // Derive expected column names from source and target node schemas
         // This ensures we only project columns that actually belong to source/target nodes
         let expected_columns =
             self.get_expected_varlength_columns(ctx, source_variable, target_variable)?;
 
+        // Path limits and shortest paths rank the paths by hop count
+        let ranks_paths = !self.expansion_limits.is_unlimited() || path_filter.shortest.is_some();
+
         // Generate a plan for each hop count and UNION them
         let mut plans = Vec::new();
 
@@ -154,18 +196,40 @@ impl DataFusionPlanner {
                 direction,
                 hop_count,
                 target_properties,
+                path_filter,
             )?;
 
             // Project only source and target columns to ensure consistent schema for UNION
             // This removes intermediate node columns that vary by hop count
             // Use the pre-computed expected column set derived from actual node schemas
-            let projection: Vec<datafusion::logical_expr::Expr> = plan
+            let mut projection: Vec<datafusion::logical_expr::Expr> = plan
                 .schema()
                 .fields()
                 .iter()
                 .filter(|f| expected_columns.contains(f.name().as_str()))
                 .map(|f| col(f.name()))
                 .collect();
+            if let Some(path) = &path_filter.path_variable {
+                projection
+                    .push(lit(hop_count as i64).alias(qualify_column(path, PATH_LENGTH_PROPERTY)));
+                // The path object: `RETURN p` reads the keys of its nodes and its length
+                if let Some(nodes) =
+                    self.path_node_keys(ctx, source_variable, target_variable, hop_count)?
+                {
+                    projection.push(
+                        named_struct(vec![
+                            lit(PATH_NODES_FIELD),
+                            make_array(nodes),
+                            lit(PATH_LENGTH_PROPERTY),
+                            lit(hop_count as i64),
+                        ])
+                        .alias(path.to_lowercase()),
+                    );
+                }
+            }
+            if ranks_paths {
+                projection.push(lit(hop_count as u64).alias(PATH_HOPS_COLUMN));
+            }
 
             plan = LogicalPlanBuilder::from(plan)
                 .project(projection)
@@ -183,26 +247,181 @@ impl DataFusionPlanner {
         }
 
         // UNION all plans together
-        if plans.len() == 1 {
-            Ok(plans.into_iter().next().unwrap())
-        } else {
-            // Build UNION of all plans
-            let mut union_plan = plans[0].clone();
-            for plan in plans.into_iter().skip(1) {
-                union_plan = LogicalPlanBuilder::from(union_plan)
-                    .union(plan)
-                    .map_err(|e| crate::error::GraphError::PlanError {
-                        message: format!("Failed to UNION variable-length paths: {}", e),
-                        location: snafu::Location::new(file!(), line!(), column!()),
-                    })?
-                    .build()
-                    .map_err(|e| crate::error::GraphError::PlanError {
-                        message: format!("Failed to build UNION plan: {}", e),
-                        location: snafu::Location::new(file!(), line!(), column!()),
-                    })?;
+        let mut union_plan = plans[0].clone();
+        for plan in plans.into_iter().skip(1) {
+            union_plan = LogicalPlanBuilder::from(union_plan)
+                .union(plan)
+                .map_err(|e| crate::error::GraphError::PlanError {
+                    message: format!("Failed to UNION variable-length paths: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?
+                .build()
+                .map_err(|e| crate::error::GraphError::PlanError {
+                    message: format!("Failed to build UNION plan: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        }
+
+        if let Some(kind) = path_filter.shortest {
+            union_plan =
+                self.keep_shortest_paths(ctx, union_plan, source_variable, target_variable, kind)?;
+        }
+
+        if self.expansion_limits.is_unlimited() {
+            if ranks_paths {
+                union_plan = drop_column(union_plan, PATH_HOPS_COLUMN)
+                    .map_err(|e| self.plan_error("Failed to project shortest paths", e))?;
             }
             Ok(union_plan)
+        } else {
+            self.limit_paths(ctx, union_plan, source_variable, target_variable)
+        }
+    }
+
+    /// Keep the paths of the fewest hops between each pair of end nodes: one
+    /// of them for `shortestPath`, all of them for `allShortestPaths`
+    fn keep_shortest_paths(
+        &self,
+        ctx: &PlanningContext,
+        plan: LogicalPlan,
+        source_variable: &str,
+        target_variable: &str,
+        kind: ShortestPathKind,
+    ) -> Result<LogicalPlan> {
+        let columns: Vec<Expr> = plan
+            .schema()
+            .columns()
+            .into_iter()
+            .map(Expr::Column)
+            .collect();
+        let pair: Vec<Expr> = self
+            .node_key_columns(ctx, source_variable)?
+            .into_iter()
+            .chain(self.node_key_columns(ctx, target_variable)?)
+            .collect();
+        let ranking = match kind {
+            ShortestPathKind::Single => row_number(),
+            ShortestPathKind::All => rank(),
+        };
+        let shortest_rank = ranking
+            .partition_by(pair)
+            .order_by(vec![col(PATH_HOPS_COLUMN).sort(true, false)])
+            .build()
+            .map_err(|e| self.plan_error("Failed to rank paths by length", e))?
+            .alias(SHORTEST_PATH_RANK_COLUMN);
+        LogicalPlanBuilder::from(plan)
+            .window(vec![shortest_rank])
+            .and_then(|b| b.filter(col(SHORTEST_PATH_RANK_COLUMN).eq(lit(1u64))))
+            .and_then(|b| b.project(columns))
+            .map_err(|e| self.plan_error("Failed to keep shortest paths", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build shortest paths", e))
+    }
+
+    /// Key columns of the nodes along the unrolled path of `hop_count` hops,
+    /// or `None` when one of them has a composite key
+    fn path_node_keys(
+        &self,
+        ctx: &PlanningContext,
+        source_variable: &str,
+        target_variable: &str,
+        hop_count: u32,
+    ) -> Result<Option<Vec<Expr>>> {
+        let variables = std::iter::once(source_variable.to_string())
+            .chain((1..hop_count).map(|hop| format!("_temp_{}_{}", source_variable, hop)))
+            .chain(std::iter::once(target_variable.to_string()));
+        let mut keys = Vec::new();
+        for variable in variables {
+            let (_, mapping) = self.get_target_node_mapping(ctx, &variable)?;
+            match mapping.key_columns().as_slice() {
+                [key] => keys.push(col(qualify_column(&variable, key))),
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(keys))
+    }
+
+    /// Apply the planner's [`ExpansionLimits`](crate::expansion::ExpansionLimits) to the unioned paths
+    ///
+    /// Paths are ranked by hop count, then by target key, per source node for
+    /// the per-source cap and across all sources for the global cap. Each cap
+    /// is a `path_limit` filter on the rank, which raises the matching
+    /// truncation flag when it drops a row.
+    fn limit_paths(
+        &self,
+        ctx: &PlanningContext,
+        plan: LogicalPlan,
+        source_variable: &str,
+        target_variable: &str,
+    ) -> Result<LogicalPlan> {
+        let columns: Vec<Expr> = plan
+            .schema()
+            .columns()
+            .into_iter()
+            .filter(|c| c.name != PATH_HOPS_COLUMN)
+            .map(Expr::Column)
+            .collect();
+        let source_keys = self.node_key_columns(ctx, source_variable)?;
+        let target_keys = self.node_key_columns(ctx, target_variable)?;
+        let order = |keys: &[Expr]| {
+            std::iter::once(col(PATH_HOPS_COLUMN))
+                .chain(keys.iter().cloned())
+                .map(|e| e.sort(true, false))
+                .collect::<Vec<_>>()
+        };
+
+        let mut builder = LogicalPlanBuilder::from(plan);
+        if let Some(limit) = self.expansion_limits.max_paths_per_source() {
+            let rank = row_number()
+                .partition_by(source_keys.clone())
+                .order_by(order(&target_keys))
+                .build()
+                .map_err(|e| self.plan_error("Failed to rank paths per source", e))?
+                .alias(PATH_SOURCE_RANK_COLUMN);
+            let within = udf::create_path_limit_udf(self.truncation.per_source.clone())
+                .call(vec![col(PATH_SOURCE_RANK_COLUMN), lit(limit as u64)]);
+            builder = builder
+                .window(vec![rank])
+                .and_then(|b| b.filter(within))
+                .map_err(|e| self.plan_error("Failed to cap paths per source", e))?;
         }
+        if let Some(limit) = self.expansion_limits.max_paths() {
+            let all_keys: Vec<Expr> = source_keys.into_iter().chain(target_keys).collect();
+            let rank = row_number()
+                .order_by(order(&all_keys))
+                .build()
+                .map_err(|e| self.plan_error("Failed to rank paths", e))?
+                .alias(PATH_RANK_COLUMN);
+            let within = udf::create_path_limit_udf(self.truncation.max_paths.clone())
+                .call(vec![col(PATH_RANK_COLUMN), lit(limit as u64)]);
+            builder = builder
+                .window(vec![rank])
+                .and_then(|b| b.filter(within))
+                .map_err(|e| self.plan_error("Failed to cap paths", e))?;
+        }
+        builder
+            .project(columns)
+            .map_err(|e| self.plan_error("Failed to project capped paths", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build capped paths", e))
+    }
+
+    /// Qualified key columns of the node bound to `variable`
+    fn node_key_columns(&self, ctx: &PlanningContext, variable: &str) -> Result<Vec<Expr>> {
+        let mapping = ctx
+            .analysis
+            .var_to_label
+            .get(variable)
+            .and_then(|label| self.config.get_node_mapping(label))
+            .ok_or_else(|| crate::error::GraphError::PlanError {
+                message: format!("No node mapping for path endpoint '{}'", variable),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        Ok(mapping
+            .key_columns()
+            .into_iter()
+            .map(|key| col(qualify_column(variable, key)))
+            .collect())
     }
 
     /// Build a fixed-length path of N hops
@@ -219,6 +438,7 @@ impl DataFusionPlanner {
         direction: &RelationshipDirection,
         hop_count: u32,
         target_properties: &HashMap<String, crate::ast::PropertyValue>,
+        path_filter: &PathFilter,
     ) -> Result<LogicalPlan> {
         let mut current_plan = input_plan;
         let mut current_source = source_variable.to_string();
@@ -249,6 +469,7 @@ impl DataFusionPlanner {
                 relationship_types,
                 direction,
                 props_to_apply,
+                path_filter,
             )?;
 
             // Move to next hop
@@ -269,6 +490,7 @@ impl DataFusionPlanner {
         relationship_types: &[String],
         direction: &RelationshipDirection,
         target_properties: &HashMap<String, crate::ast::PropertyValue>,
+        path_filter: &PathFilter,
     ) -> Result<LogicalPlan> {
         let rel_type =
             relationship_types
@@ -295,6 +517,24 @@ impl DataFusionPlanner {
             direction,
         )?;
 
+        // Drop edges rejected by the path filter before joining the next node
+        let rel_filters = path_filter
+            .relationship_properties
+            .iter()
+            .map(|(key, value)| {
+                col(qualify_column(&rel_instance.alias, key))
+                    .eq(to_df_value_expr(&ValueExpression::Literal(value.clone())))
+            })
+            .chain(
+                path_filter
+                    .relationship_predicates
+                    .iter()
+                    .map(|(element, predicate)| {
+                        path_predicate(predicate, element, &rel_instance.alias)
+                    }),
+            );
+        builder = self.filter_path_builder(builder, rel_filters)?;
+
         // Build target node scan and join
         let target_scan = self.build_qualified_target_scan(
             catalog,
@@ -311,6 +551,13 @@ impl DataFusionPlanner {
             node_map,
             direction,
         )?;
+        builder = self.filter_path_builder(
+            builder,
+            path_filter
+                .node_predicates
+                .iter()
+                .map(|(element, predicate)| path_predicate(predicate, element, target_variable)),
+        )?;
 
         builder
             .build()
@@ -319,6 +566,37 @@ impl DataFusionPlanner {
                 location: snafu::Location::new(file!(), line!(), column!()),
             })
     }
+
+    /// AND together the given path filters and apply them to the builder
+    fn filter_path_builder(
+        &self,
+        builder: LogicalPlanBuilder,
+        filters: impl Iterator<Item = Expr>,
+    ) -> Result<LogicalPlanBuilder> {
+        match filters.reduce(Expr::and) {
+            Some(predicate) => builder
+                .filter(predicate)
+                .map_err(|e| self.plan_error("Failed to apply path filter", e)),
+            None => Ok(builder),
+        }
+    }
+}
+
+/// Project every column of `plan` but `name`
+fn drop_column(plan: LogicalPlan, name: &str) -> datafusion::error::Result<LogicalPlan> {
+    let columns: Vec<Expr> = plan
+        .schema()
+        .columns()
+        .into_iter()
+        .filter(|c| c.name != name)
+        .map(Expr::Column)
+        .collect();
+    LogicalPlanBuilder::from(plan).project(columns)?.build()
+}
+
+/// Bind a path predicate's element variable to one concrete node or relationship
+fn path_predicate(predicate: &BooleanExpression, element: &str, variable: &str) -> Expr {
+    to_df_boolean_expr(&predicate.rename_variable(element, variable))
 }
 
 #[cfg(test)]
@@ -395,6 +673,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(2),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -416,6 +695,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_varlength_expand_with_limits_ranks_and_caps_paths() {
+        let vlexpand = LogicalOperator::VariableLengthExpand {
+            input: Box::new(person_scan("a")),
+            source_variable: "a".into(),
+            target_variable: "b".into(),
+            relationship_types: vec!["KNOWS".into()],
+            direction: crate::ast::RelationshipDirection::Outgoing,
+            relationship_variable: None,
+            min_length: Some(1),
+            max_length: Some(3),
+            target_properties: HashMap::new(),
+            path_filter: Default::default(),
+        };
+        let limits = crate::expansion::ExpansionLimits::new()
+            .with_max_paths_per_source(5)
+            .with_max_paths(50);
+        let planner = DataFusionPlanner::with_catalog(person_knows_config(), make_catalog())
+            .with_expansion_limits(limits, Default::default());
+        let df_plan = planner.plan(&vlexpand).unwrap();
+
+        let s = format!("{}", df_plan.display_indent());
+        assert!(s.contains("PARTITION BY [a__id]"), "{}", s);
+        assert!(s.contains("path_limit(__path_source_rank"), "{}", s);
+        assert!(s.contains("path_limit(__path_rank"), "{}", s);
+        // Ranking columns do not leak into the expansion output
+        assert!(df_plan
+            .schema()
+            .fields()
+            .iter()
+            .all(|f| !f.name().starts_with("__path")));
+    }
+
     #[test]
     fn test_varlength_expand_single_hop() {
         // MATCH (a:Person)-[:KNOWS*1..1]->(b:Person) - equivalent to single hop
@@ -430,6 +742,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(1),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -465,6 +778,7 @@ mod tests {
             min_length: Some(2),
             max_length: Some(3),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -508,6 +822,7 @@ mod tests {
             min_length: None, // Should default to 1
             max_length: Some(3),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -550,6 +865,7 @@ mod tests {
             min_length: Some(2),
             max_length: None, // Should default to MAX_VARIABLE_LENGTH_HOPS (20)
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -593,6 +909,7 @@ mod tests {
             min_length: Some(3),
             max_length: Some(2), // Invalid: min > max
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -636,6 +953,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(25), // Exceeds MAX_VARIABLE_LENGTH_HOPS
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let project = LogicalOperator::Project {
             input: Box::new(vlexpand),
@@ -679,6 +997,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(2),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
         let filter = LogicalOperator::Filter {
             input: Box::new(vlexpand),
@@ -34,12 +34,18 @@ impl DataFusionPlanner {
             LogicalOperator::Filter { input, .. } => {
                 Self::collect_variables(input, vars);
             }
+            LogicalOperator::Sample { input, .. } => {
+                Self::collect_variables(input, vars);
+            }
             LogicalOperator::Project { input, .. } => {
                 Self::collect_variables(input, vars);
             }
             LogicalOperator::Distinct { input } => {
                 Self::collect_variables(input, vars);
             }
+            LogicalOperator::DistinctOn { input, .. } => {
+                Self::collect_variables(input, vars);
+            }
             LogicalOperator::Sort { input, .. } => {
                 Self::collect_variables(input, vars);
             }
@@ -80,6 +86,15 @@ impl DataFusionPlanner {
                     vars.push(rel_var.clone());
                 }
             }
+            // Semi- and anti-joins only keep the rows of their left side, so
+            // the variables of the right side go out of scope
+            LogicalOperator::Join {
+                left,
+                join_type: JoinType::Semi | JoinType::Anti,
+                ..
+            } => {
+                Self::collect_variables(left, vars);
+            }
             // Binary operator: recurse into both left and right
             LogicalOperator::Join { left, right, .. } => {
                 Self::collect_variables(left, vars);
@@ -91,6 +106,12 @@ impl DataFusionPlanner {
                 }
                 vars.push(alias.clone());
             }
+            LogicalOperator::TableFunction { input, call } => {
+                if let Some(op) = input {
+                    Self::collect_variables(op, vars);
+                }
+                vars.extend(call.yields.iter().map(|item| item.variable().to_string()));
+            }
         }
     }
 }
@@ -223,6 +244,7 @@ mod tests {
             min_length: Some(1),
             max_length: Some(3),
             target_properties: HashMap::new(),
+            path_filter: Default::default(),
         };
 
         let vars = planner.extract_variables(&varlength);
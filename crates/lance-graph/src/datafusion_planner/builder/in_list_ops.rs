@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! IN-list planning: parameter binding and size-based strategy selection
+//!
+//! `n.id IN $ids` binds the parameter's array into a literal list. Lists of up
+//! to [`HASH_IN_LIST_THRESHOLD`] items stay a DataFusion `InList`. Longer lists
+//! over an indexed property also stay an `InList`, which Lance pushes to the
+//! scalar index as a single batch lookup. Longer lists over unindexed
+//! properties become a semi-join against an in-memory VALUES relation, so each
+//! row costs one hash probe instead of a comparison per list item.
+
+use crate::ast::{BooleanExpression, PropertyRef, PropertyValue, ValueExpression};
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::Result;
+use datafusion::logical_expr::{Expr, JoinType, LogicalPlan, LogicalPlanBuilder};
+
+/// Lists longer than this are planned as hash semi-joins (unless indexed)
+pub(crate) const HASH_IN_LIST_THRESHOLD: usize = 64;
+
+impl DataFusionPlanner {
+    /// Replace every `IN $param` list with the parameter's array elements
+    pub(crate) fn bind_in_list_parameters(
+        &self,
+        expr: &BooleanExpression,
+    ) -> Result<BooleanExpression> {
+        use BooleanExpression as BE;
+        Ok(match expr {
+            BE::And(l, r) => BE::And(
+                Box::new(self.bind_in_list_parameters(l)?),
+                Box::new(self.bind_in_list_parameters(r)?),
+            ),
+            BE::Or(l, r) => BE::Or(
+                Box::new(self.bind_in_list_parameters(l)?),
+                Box::new(self.bind_in_list_parameters(r)?),
+            ),
+            BE::Not(inner) => BE::Not(Box::new(self.bind_in_list_parameters(inner)?)),
+            BE::In { expression, list } => match list.as_slice() {
+                [ValueExpression::Parameter(name)] => BE::In {
+                    expression: expression.clone(),
+                    list: self.list_parameter(name)?,
+                },
+                _ => expr.clone(),
+            },
+            other => other.clone(),
+        })
+    }
+
+    /// Filter `input_plan` by `predicate`, planning long unindexed IN lists
+    /// as hash semi-joins and string patterns in index-friendly form
+    pub(crate) fn apply_predicate(
+        &self,
+        ctx: &PlanningContext,
+        input_plan: LogicalPlan,
+        predicate: &BooleanExpression,
+    ) -> Result<LogicalPlan> {
+        let mut conjuncts = Vec::new();
+        flatten_and(predicate, &mut conjuncts);
+
+        let mut remaining: Option<Expr> = None;
+        let mut hash_lists = Vec::new();
+        for conjunct in conjuncts {
+            match self.hash_in_list(ctx, conjunct) {
+                Some(hash_list) => hash_lists.push(hash_list),
+                None => {
+                    let expr = self
+                        .pattern_pushdown(ctx, conjunct)
+                        .unwrap_or_else(|| super::super::expression::to_df_boolean_expr(conjunct));
+                    remaining = Some(match remaining {
+                        Some(acc) => acc.and(expr),
+                        None => expr,
+                    })
+                }
+            }
+        }
+
+        let mut builder = LogicalPlanBuilder::from(input_plan);
+        if let Some(remaining) = remaining {
+            builder = builder
+                .filter(remaining)
+                .map_err(|e| self.plan_error("Failed to build filter", e))?;
+        }
+
+        for (i, (property, list)) in hash_lists.into_iter().enumerate() {
+            let alias = format!("__in_list_{}", i);
+            let rows = list
+                .iter()
+                .map(|value| vec![super::super::expression::to_df_value_expr(value)])
+                .collect();
+            let values = LogicalPlanBuilder::values(rows)
+                .map_err(|e| self.plan_error("Failed to build IN-list values", e))?
+                .alias(alias.as_str())
+                .map_err(|e| self.plan_error("Failed to alias IN-list values", e))?
+                .build()
+                .map_err(|e| self.plan_error("Failed to build IN-list values", e))?;
+            let column =
+                crate::case_insensitive::qualify_column(&property.variable, &property.property);
+            builder = builder
+                .join(
+                    values,
+                    JoinType::LeftSemi,
+                    (vec![column], vec![format!("{}.column1", alias)]),
+                    None,
+                )
+                .map_err(|e| self.plan_error("Failed to build IN-list semi-join", e))?;
+        }
+
+        builder
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))
+    }
+
+    /// `(property, list)` when `conjunct` should be planned as a hash semi-join
+    fn hash_in_list(
+        &self,
+        ctx: &PlanningContext,
+        conjunct: &BooleanExpression,
+    ) -> Option<(PropertyRef, Vec<ValueExpression>)> {
+        let BooleanExpression::In {
+            expression: ValueExpression::Property(property),
+            list,
+        } = conjunct
+        else {
+            return None;
+        };
+        if list.len() <= HASH_IN_LIST_THRESHOLD
+            || !list
+                .iter()
+                .all(|v| matches!(v, ValueExpression::Literal(_)))
+        {
+            return None;
+        }
+
+        // Indexed properties keep the InList so Lance can batch the index lookup
+        let indexed = ctx
+            .analysis
+            .var_to_label
+            .get(&property.variable)
+            .and_then(|label| self.config.resolve_view_chain(label).ok())
+            .and_then(|(base, _)| self.config.get_node_mapping(&base))
+            .is_some_and(|m| m.is_indexed(&property.property));
+        (!indexed).then(|| (property.clone(), list.clone()))
+    }
+
+    /// Literal list bound to `$name`
+    fn list_parameter(&self, name: &str) -> Result<Vec<ValueExpression>> {
+        let value =
+            self.parameters
+                .get(name)
+                .ok_or_else(|| crate::error::GraphError::PlanError {
+                    message: format!("Missing value for parameter ${}", name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        let serde_json::Value::Array(items) = value else {
+            return Err(crate::error::GraphError::PlanError {
+                message: format!("Parameter ${} used with IN must be a list", name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+        items
+            .iter()
+            .map(|item| {
+                PropertyValue::from_json(item)
+                    .map(ValueExpression::Literal)
+                    .ok_or_else(|| crate::error::GraphError::PlanError {
+                        message: format!(
+                            "Parameter ${} contains an unsupported list element: {}",
+                            name, item
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Collect the operands of a (possibly nested) AND
+fn flatten_and<'a>(expr: &'a BooleanExpression, out: &mut Vec<&'a BooleanExpression>) {
+    match expr {
+        BooleanExpression::And(left, right) => {
+            flatten_and(left, out);
+            flatten_and(right, out);
+        }
+        other => out.push(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{BooleanExpression, PropertyRef, ValueExpression};
+    use crate::datafusion_planner::test_fixtures::{make_catalog, person_config, person_scan};
+    use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+    use crate::logical_plan::LogicalOperator;
+    use std::collections::HashMap;
+
+    fn ids_filter() -> LogicalOperator {
+        LogicalOperator::Filter {
+            input: Box::new(person_scan("n")),
+            predicate: BooleanExpression::In {
+                expression: ValueExpression::Property(PropertyRef {
+                    variable: "n".to_string(),
+                    property: "id".to_string(),
+                }),
+                list: vec![ValueExpression::Parameter("ids".to_string())],
+            },
+        }
+    }
+
+    fn planner_with_ids(count: i64) -> DataFusionPlanner {
+        let ids: Vec<i64> = (0..count).collect();
+        let parameters = HashMap::from([("ids".to_string(), serde_json::json!(ids))]);
+        DataFusionPlanner::with_catalog(person_config(), make_catalog()).with_parameters(parameters)
+    }
+
+    #[test]
+    fn test_short_parameter_list_stays_in_list() {
+        let plan = planner_with_ids(3).plan(&ids_filter()).unwrap();
+        let s = format!("{:?}", plan);
+        assert!(s.contains("IN ("), "expected InList filter: {}", s);
+        assert!(!s.contains("LeftSemi"), "unexpected semi-join: {}", s);
+    }
+
+    #[test]
+    fn test_long_parameter_list_becomes_hash_semi_join() {
+        let plan = planner_with_ids(500).plan(&ids_filter()).unwrap();
+        let s = format!("{:?}", plan);
+        assert!(s.contains("LeftSemi"), "expected hash semi-join: {}", s);
+        assert!(!s.contains("IN ("), "unexpected InList filter: {}", s);
+    }
+
+    #[test]
+    fn test_missing_list_parameter_is_an_error() {
+        let planner = DataFusionPlanner::with_catalog(person_config(), make_catalog());
+        let err = planner.plan(&ids_filter()).unwrap_err();
+        assert!(
+            err.to_string().contains("$ids"),
+            "unexpected error: {}",
+            err
+        );
+    }
+}
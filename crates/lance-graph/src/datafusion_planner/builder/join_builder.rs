@@ -8,7 +8,12 @@ use crate::datafusion_planner::analysis::PlanningContext;
 use crate::datafusion_planner::DataFusionPlanner;
 use crate::error::Result;
 use crate::logical_plan::*;
-use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder};
+use datafusion::common::Column;
+use datafusion::functions::expr_fn::coalesce;
+use datafusion::logical_expr::{Expr, LogicalPlan, LogicalPlanBuilder};
+
+/// Prefix of the right-side join key columns while a keyed join is built
+const RIGHT_KEY_PREFIX: &str = "__right_";
 
 impl DataFusionPlanner {
     /// Build a join between two logical operators
@@ -60,18 +65,22 @@ impl DataFusionPlanner {
                 }
 
                 // Build inner join with inferred keys
-                let df_join_type = datafusion::logical_expr::JoinType::Inner;
-                LogicalPlanBuilder::from(left_plan)
-                    .join(right_plan, df_join_type, (left_keys, right_keys), None)
-                    .map_err(|e| self.plan_error("Failed to build inner join", e))?
-                    .build()
-                    .map_err(|e| self.plan_error("Failed to build plan", e))
+                self.build_keyed_join(
+                    left_plan,
+                    right_plan,
+                    datafusion::logical_expr::JoinType::Inner,
+                    &left_keys,
+                    &right_keys,
+                )
             }
             crate::logical_plan::JoinType::Left
             | crate::logical_plan::JoinType::Right
-            | crate::logical_plan::JoinType::Full => {
-                // Outer joins MUST have join keys - cross join has different semantics
-                // (Cartesian product vs. NULL-padded unmatched rows)
+            | crate::logical_plan::JoinType::Full
+            | crate::logical_plan::JoinType::Semi
+            | crate::logical_plan::JoinType::Anti => {
+                // Outer, semi- and anti-joins MUST have join keys - cross join has
+                // different semantics (Cartesian product vs. NULL-padded or
+                // filtered rows)
                 if left_keys.is_empty() {
                     return Err(crate::error::GraphError::PlanError {
                         message: format!(
@@ -91,22 +100,100 @@ impl DataFusionPlanner {
                         datafusion::logical_expr::JoinType::Right
                     }
                     crate::logical_plan::JoinType::Full => datafusion::logical_expr::JoinType::Full,
+                    crate::logical_plan::JoinType::Semi => {
+                        datafusion::logical_expr::JoinType::LeftSemi
+                    }
+                    crate::logical_plan::JoinType::Anti => {
+                        datafusion::logical_expr::JoinType::LeftAnti
+                    }
                     _ => unreachable!("Inner and Cross joins handled above"),
                 };
 
                 // Build join with inferred keys
                 // Example: JOIN ON left.b__id = right.b__id
-                LogicalPlanBuilder::from(left_plan)
-                    .join(right_plan, df_join_type, (left_keys, right_keys), None)
-                    .map_err(|e| {
-                        self.plan_error(&format!("Failed to build {:?} join", join_type), e)
-                    })?
-                    .build()
-                    .map_err(|e| self.plan_error("Failed to build plan", e))
+                self.build_keyed_join(left_plan, right_plan, df_join_type, &left_keys, &right_keys)
             }
         }
     }
 
+    /// Join two plans on key columns both sides produce under the same name
+    ///
+    /// Both sides of a join on a shared variable carry that variable's columns
+    /// (e.g. `a__id`, `a__name`), which DataFusion rejects as duplicates. The
+    /// right side keeps only its own columns plus the keys, renamed for the
+    /// join and dropped afterwards. Columns of shared variables therefore come
+    /// from the left side, except that Right and Full joins fill missing keys
+    /// from the right side, so a key is never null when either side matched.
+    fn build_keyed_join(
+        &self,
+        left_plan: LogicalPlan,
+        right_plan: LogicalPlan,
+        join_type: datafusion::logical_expr::JoinType,
+        left_keys: &[String],
+        right_keys: &[String],
+    ) -> Result<LogicalPlan> {
+        let left_schema = left_plan.schema().clone();
+        let renamed_key = |name: &str| format!("{}{}", RIGHT_KEY_PREFIX, name);
+
+        let right_projection: Vec<Expr> = right_plan
+            .schema()
+            .iter()
+            .filter_map(|(qualifier, field)| {
+                let column = Expr::Column(Column::new(qualifier.cloned(), field.name()));
+                if right_keys.contains(field.name()) {
+                    Some(column.alias(renamed_key(field.name())))
+                } else if left_schema.has_column_with_unqualified_name(field.name()) {
+                    None
+                } else {
+                    Some(column)
+                }
+            })
+            .collect();
+        let right_plan = LogicalPlanBuilder::from(right_plan)
+            .project(right_projection)
+            .map_err(|e| self.plan_error("Failed to project right side of join", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))?;
+
+        let renamed_keys: Vec<String> = right_keys.iter().map(|k| renamed_key(k)).collect();
+        let joined = LogicalPlanBuilder::from(left_plan)
+            .join(
+                right_plan,
+                join_type,
+                (left_keys.to_vec(), renamed_keys.clone()),
+                None,
+            )
+            .map_err(|e| self.plan_error(&format!("Failed to build {:?} join", join_type), e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))?;
+
+        let fill_keys = matches!(
+            join_type,
+            datafusion::logical_expr::JoinType::Right | datafusion::logical_expr::JoinType::Full
+        );
+        let output: Vec<Expr> = joined
+            .schema()
+            .iter()
+            .filter(|(_, field)| !renamed_keys.contains(field.name()))
+            .map(|(qualifier, field)| {
+                let column = Expr::Column(Column::new(qualifier.cloned(), field.name()));
+                match left_keys.iter().position(|k| k == field.name()) {
+                    Some(i) if fill_keys => coalesce(vec![
+                        column,
+                        Expr::Column(Column::from_name(renamed_keys[i].clone())),
+                    ])
+                    .alias(field.name()),
+                    _ => column,
+                }
+            })
+            .collect();
+        LogicalPlanBuilder::from(joined)
+            .project(output)
+            .map_err(|e| self.plan_error("Failed to project join output", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))
+    }
+
     /// Infer join keys by finding shared variables between left and right plans
     ///
     /// This analyzes both patterns to find variables that appear in both, then
@@ -166,11 +253,13 @@ impl DataFusionPlanner {
             if let Some(label) = ctx.analysis.var_to_label.get(var) {
                 // This is a node variable - get the node mapping for its label (case-insensitive)
                 if let Some(node_map) = self.config.get_node_mapping(label) {
-                    // Generate qualified column names for node ID
+                    // Generate qualified column names for node ID (every composite key column)
                     // Example: var="b", id_field="id" -> "b__id"
-                    let key = qualify_column(var, &node_map.id_field);
-                    left_keys.push(key.clone());
-                    right_keys.push(key);
+                    for key_column in node_map.key_columns() {
+                        let key = qualify_column(var, key_column);
+                        left_keys.push(key.clone());
+                        right_keys.push(key);
+                    }
                 }
             } else {
                 // Not a node variable - check if it's a relationship variable
@@ -192,15 +281,15 @@ impl DataFusionPlanner {
                         // The columns are qualified as: {alias}__{original_field_name}
                         // Example: var="r", source_id_field="src_person_id"
                         //          -> "r__src_person_id"
-                        let left_src = qualify_column(var, &rel_map.source_id_field);
-                        let right_src = qualify_column(var, &rel_map.source_id_field);
-                        let left_dst = qualify_column(var, &rel_map.target_id_field);
-                        let right_dst = qualify_column(var, &rel_map.target_id_field);
-
-                        left_keys.push(left_src);
-                        right_keys.push(right_src);
-                        left_keys.push(left_dst);
-                        right_keys.push(right_dst);
+                        for key_column in rel_map
+                            .source_key_columns()
+                            .into_iter()
+                            .chain(rel_map.target_key_columns())
+                        {
+                            let key = qualify_column(var, key_column);
+                            left_keys.push(key.clone());
+                            right_keys.push(key);
+                        }
                     }
                 }
                 // If not found in either node or relationship variables, skip it
@@ -9,14 +9,28 @@
 //! - `basic_ops`: Basic operations (filter, project, sort, limit, offset, distinct)
 //! - `expand_ops`: Graph traversal operations (expand, variable-length expand)
 //! - `aggregate_ops`: Aggregation and grouping operations
+//! - `disjunction_ops`: OR across indexed columns rewritten as a UNION of scans
+//! - `in_list_ops`: IN-list parameter binding and size-based strategy selection
 //! - `join_builder`: Join inference and building
+//! - `pattern_ops`: LIKE / STARTS WITH / CONTAINS rewritten into index-friendly filters
+//! - `pruning_ops`: Filters that column statistics rule out planned as empty relations
+//! - `sample_ops`: Random sampling (SAMPLE clause)
+//! - `statistics_ops`: Row counts answered from Lance metadata
+//! - `table_function_ops`: Registered table functions called with `CALL ... YIELD`
 //! - `helpers`: Utility functions
 
 mod aggregate_ops;
 mod basic_ops;
+mod disjunction_ops;
 mod expand_ops;
 mod helpers;
+mod in_list_ops;
 mod join_builder;
+mod pattern_ops;
+mod pruning_ops;
+mod sample_ops;
+mod statistics_ops;
+mod table_function_ops;
 
 use super::DataFusionPlanner;
 use crate::error::Result;
@@ -42,10 +56,12 @@ impl DataFusionPlanner {
             LogicalOperator::Filter { input, predicate } => {
                 self.build_filter(ctx, input, predicate)
             }
+            LogicalOperator::Sample { input, method } => self.build_sample(ctx, input, method),
             LogicalOperator::Project { input, projections } => {
                 self.build_project(ctx, input, projections)
             }
             LogicalOperator::Distinct { input } => self.build_distinct(ctx, input),
+            LogicalOperator::DistinctOn { input, keys } => self.build_distinct_on(ctx, input, keys),
             LogicalOperator::Sort { input, sort_items } => self.build_sort(ctx, input, sort_items),
             LogicalOperator::Limit { input, count } => self.build_limit(ctx, input, count),
             LogicalOperator::Offset { input, offset } => self.build_offset(ctx, input, offset),
@@ -79,6 +95,7 @@ impl DataFusionPlanner {
                 min_length,
                 max_length,
                 target_properties,
+                path_filter,
                 ..
             } => self.build_variable_length_expand(
                 ctx,
@@ -90,6 +107,7 @@ impl DataFusionPlanner {
                 *min_length,
                 *max_length,
                 target_properties,
+                path_filter,
             ),
             LogicalOperator::Join {
                 left,
@@ -101,6 +119,9 @@ impl DataFusionPlanner {
                 expression,
                 alias,
             } => self.build_unwind(ctx, input, expression, alias),
+            LogicalOperator::TableFunction { input, call } => {
+                self.build_table_function(ctx, input, call)
+            }
         }
     }
 }
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! String pattern pushdown: LIKE / STARTS WITH / CONTAINS in index-friendly form
+//!
+//! Lance cannot push a LIKE down to its scalar indexes. An anchored pattern
+//! (`STARTS WITH 'ab'`, `LIKE 'ab%'`) is therefore planned as the equivalent
+//! range `>= 'ab' AND < 'ac'`, which Lance answers from a BTree index or
+//! prunes with fragment statistics. An unanchored pattern (`CONTAINS 'ab'`,
+//! `LIKE '%ab%'`) over a property with an NGram index is planned as the
+//! `contains` function, which Lance answers from that index.
+
+use crate::ast::{BooleanExpression, ValueExpression};
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::DataFusionPlanner;
+use datafusion::functions::string::expr_fn::contains;
+use datafusion::logical_expr::{lit, Expr};
+
+impl DataFusionPlanner {
+    /// Index-friendly equivalent of a string pattern conjunct, if any
+    pub(crate) fn pattern_pushdown(
+        &self,
+        ctx: &PlanningContext,
+        conjunct: &BooleanExpression,
+    ) -> Option<Expr> {
+        let (expression, pattern) = match conjunct {
+            BooleanExpression::StartsWith { expression, prefix } => {
+                (expression, Pattern::Prefix(prefix.as_str()))
+            }
+            BooleanExpression::Contains {
+                expression,
+                substring,
+            } => (expression, Pattern::Substring(substring.as_str())),
+            BooleanExpression::Like {
+                expression,
+                pattern,
+            } => (expression, parse_like(pattern)?),
+            _ => return None,
+        };
+        let ValueExpression::Property(property) = expression else {
+            return None;
+        };
+        let column = super::super::expression::to_df_value_expr(expression);
+
+        match pattern {
+            Pattern::Prefix(prefix) if !prefix.is_empty() => {
+                let lower = column.clone().gt_eq(lit(prefix.to_string()));
+                Some(match prefix_upper_bound(prefix) {
+                    Some(upper) => lower.and(column.lt(lit(upper))),
+                    // Only strings of maximal chars remain, all of which are >= prefix
+                    None => lower,
+                })
+            }
+            Pattern::Substring(substring) if !substring.is_empty() => {
+                let ngram_indexed = ctx
+                    .analysis
+                    .var_to_label
+                    .get(&property.variable)
+                    .and_then(|label| self.config.resolve_view_chain(label).ok())
+                    .and_then(|(base, _)| self.config.get_node_mapping(&base))
+                    .is_some_and(|m| m.has_ngram_index(&property.property));
+                ngram_indexed.then(|| contains(column, lit(substring.to_string())))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Literal part of a pattern the pushdown understands
+enum Pattern<'a> {
+    /// `'prefix%'`
+    Prefix(&'a str),
+    /// `'%substring%'`
+    Substring(&'a str),
+}
+
+/// Classify a LIKE pattern whose literal part has no wildcards or escapes
+fn parse_like(pattern: &str) -> Option<Pattern<'_>> {
+    let literal = |s: &str| !s.contains(['%', '_', '\\']);
+    if let Some(inner) = pattern
+        .strip_prefix('%')
+        .and_then(|p| p.strip_suffix('%'))
+        .filter(|inner| literal(inner))
+    {
+        return Some(Pattern::Substring(inner));
+    }
+    pattern
+        .strip_suffix('%')
+        .filter(|prefix| literal(prefix))
+        .map(Pattern::Prefix)
+}
+
+/// Smallest string greater than every string starting with `prefix`
+///
+/// Increments the last character that has a successor, dropping the
+/// characters after it. `None` when every character is `char::MAX`.
+fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            char::MAX => continue,
+            // Skip the surrogate range, which has no chars
+            '\u{D7FF}' => '\u{E000}',
+            c => char::from_u32(c as u32 + 1)?,
+        };
+        chars.push(next);
+        return Some(chars.into_iter().collect());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prefix_upper_bound;
+    use crate::ast::{BooleanExpression, PropertyRef, ValueExpression};
+    use crate::config::{GraphConfig, NodeMapping};
+    use crate::datafusion_planner::test_fixtures::{make_catalog, person_scan};
+    use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+    use crate::logical_plan::LogicalOperator;
+
+    fn name() -> ValueExpression {
+        ValueExpression::Property(PropertyRef {
+            variable: "n".to_string(),
+            property: "name".to_string(),
+        })
+    }
+
+    fn plan_filter(mapping: NodeMapping, predicate: BooleanExpression) -> String {
+        let cfg = GraphConfig::builder()
+            .with_node_mapping(mapping)
+            .build()
+            .unwrap();
+        let plan = DataFusionPlanner::with_catalog(cfg, make_catalog())
+            .plan(&LogicalOperator::Filter {
+                input: Box::new(person_scan("n")),
+                predicate,
+            })
+            .unwrap();
+        format!("{:?}", plan)
+    }
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound("ab").as_deref(), Some("ac"));
+        assert_eq!(prefix_upper_bound("a\u{10FFFF}").as_deref(), Some("b"));
+        assert_eq!(prefix_upper_bound("\u{D7FF}").as_deref(), Some("\u{E000}"));
+        assert_eq!(prefix_upper_bound("\u{10FFFF}"), None);
+    }
+
+    #[test]
+    fn test_anchored_like_becomes_range_filter() {
+        let s = plan_filter(
+            NodeMapping::new("Person", "id"),
+            BooleanExpression::Like {
+                expression: name(),
+                pattern: "Al%".to_string(),
+            },
+        );
+        assert!(s.contains(">= Utf8(\"Al\")"), "expected lower bound: {}", s);
+        assert!(s.contains("< Utf8(\"Am\")"), "expected upper bound: {}", s);
+        assert!(!s.contains("LIKE"), "unexpected LIKE: {}", s);
+    }
+
+    #[test]
+    fn test_contains_uses_ngram_index_only_when_declared() {
+        let contains = || BooleanExpression::Contains {
+            expression: name(),
+            substring: "li".to_string(),
+        };
+        let indexed = plan_filter(
+            NodeMapping::new("Person", "id").with_ngram_properties(vec!["name".to_string()]),
+            contains(),
+        );
+        assert!(
+            indexed.contains("contains("),
+            "expected contains: {}",
+            indexed
+        );
+
+        let plain = plan_filter(NodeMapping::new("Person", "id"), contains());
+        assert!(plain.contains("LIKE"), "expected LIKE: {}", plain);
+    }
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Constant-false pruning: filters that column statistics prove keep no row
+//! planned as empty relations, see [`crate::pruning`]
+
+use crate::ast::BooleanExpression;
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::Result;
+use crate::logical_plan::*;
+use datafusion::logical_expr::{EmptyRelation, LogicalPlan};
+
+impl DataFusionPlanner {
+    /// Plan `Filter(input, predicate)` as an empty relation with the schema of
+    /// `input`, or `None` when the statistics do not rule the predicate out
+    ///
+    /// `input` is still planned for its schema, which reads no data.
+    pub(crate) fn try_build_pruned_filter(
+        &self,
+        ctx: &mut PlanningContext,
+        input: &LogicalOperator,
+        predicate: &BooleanExpression,
+    ) -> Result<Option<LogicalPlan>> {
+        let Some(statistics) = &self.statistics else {
+            return Ok(None);
+        };
+        let catalog = self.catalog.as_deref();
+        if crate::pruning::contradiction(input, predicate, statistics, catalog).is_none() {
+            return Ok(None);
+        }
+        let input_plan = self.build_operator(ctx, input)?;
+        Ok(Some(LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: input_plan.schema().clone(),
+        })))
+    }
+}
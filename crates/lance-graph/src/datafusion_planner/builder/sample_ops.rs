@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Random sampling of matched rows (SAMPLE clause)
+//!
+//! - `SAMPLE p PERCENT` keeps each row when `rand() < p / 100`.
+//! - `SAMPLE n ROWS` orders by `rand()` with a fetch of `n`, which DataFusion
+//!   runs as a top-k that holds at most `n` rows, like a reservoir.
+//! - `SAMPLE n ROWS PER expr` numbers the rows of every `expr` group in
+//!   `rand()` order and keeps the first `n` of each group.
+//!
+//! The `rand()` calls are seeded by the query's seed when one is set, so a
+//! sample can be drawn again over the same data.
+
+use crate::ast::SampleMethod;
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::udf::{self, RandomKind};
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::Result;
+use crate::logical_plan::LogicalOperator;
+use datafusion::functions_window::expr_fn::row_number;
+use datafusion::logical_expr::{col, lit, Expr, ExprFunctionExt, LogicalPlan, LogicalPlanBuilder};
+
+/// Per-group rank of a row in a stratified sample, dropped after filtering
+const SAMPLE_RANK_COLUMN: &str = "__sample_rank";
+
+impl DataFusionPlanner {
+    pub(crate) fn build_sample(
+        &self,
+        ctx: &mut PlanningContext,
+        input: &LogicalOperator,
+        method: &SampleMethod,
+    ) -> Result<LogicalPlan> {
+        let input_plan = self.build_operator(ctx, input)?;
+        let random = || udf::create_random_udf(RandomKind::Float, None).call(vec![]);
+        let builder = match method {
+            SampleMethod::Percent(percent) => LogicalPlanBuilder::from(input_plan)
+                .filter(random().lt(lit(percent / 100.0)))
+                .map_err(|e| self.plan_error("Failed to build percentage sample", e))?,
+            SampleMethod::Rows(count) => LogicalPlanBuilder::from(input_plan)
+                .sort(vec![random().sort(true, false)])
+                .and_then(|b| b.limit(0, Some(*count as usize)))
+                .map_err(|e| self.plan_error("Failed to build row sample", e))?,
+            SampleMethod::RowsPer { count, by } => {
+                let columns: Vec<Expr> = input_plan
+                    .schema()
+                    .columns()
+                    .into_iter()
+                    .map(Expr::Column)
+                    .collect();
+                let rank = row_number()
+                    .partition_by(vec![super::super::expression::to_df_value_expr(by)])
+                    .order_by(vec![random().sort(true, false)])
+                    .build()
+                    .map_err(|e| self.plan_error("Failed to build sample rank", e))?
+                    .alias(SAMPLE_RANK_COLUMN);
+                LogicalPlanBuilder::from(input_plan)
+                    .window(vec![rank])
+                    .and_then(|b| b.filter(col(SAMPLE_RANK_COLUMN).lt_eq(lit(*count))))
+                    .and_then(|b| b.project(columns))
+                    .map_err(|e| self.plan_error("Failed to build stratified sample", e))?
+            }
+        };
+        builder
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{PropertyRef, SampleMethod, ValueExpression};
+    use crate::datafusion_planner::test_fixtures::{make_catalog, person_config, person_scan};
+    use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+    use crate::logical_plan::LogicalOperator;
+
+    fn plan_sample(method: SampleMethod) -> String {
+        let planner = DataFusionPlanner::with_catalog(person_config(), make_catalog());
+        let plan = planner
+            .plan(&LogicalOperator::Sample {
+                input: Box::new(person_scan("n")),
+                method,
+            })
+            .unwrap();
+        format!("{}", plan.display_indent())
+    }
+
+    #[test]
+    fn test_percent_sample_filters_on_rand() {
+        let s = plan_sample(SampleMethod::Percent(25.0));
+        assert!(s.contains("rand() < Float64(0.25)"), "{}", s);
+    }
+
+    #[test]
+    fn test_row_sample_is_random_top_k() {
+        let s = plan_sample(SampleMethod::Rows(10));
+        assert!(s.contains("Limit: skip=0, fetch=10"), "{}", s);
+        assert!(s.contains("Sort: rand()"), "{}", s);
+    }
+
+    #[test]
+    fn test_stratified_sample_ranks_per_group() {
+        let s = plan_sample(SampleMethod::RowsPer {
+            count: 2,
+            by: ValueExpression::Property(PropertyRef {
+                variable: "n".to_string(),
+                property: "name".to_string(),
+            }),
+        });
+        assert!(s.contains("row_number() PARTITION BY"), "{}", s);
+        assert!(s.contains("n__name"), "{}", s);
+        assert!(s.contains("__sample_rank <= UInt64(2)"), "{}", s);
+    }
+}
@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Statistics fast path: aggregates answered from metadata
+//!
+//! `MATCH (n:User) RETURN count(n)` needs no rows at all: a Lance manifest
+//! records the physical row count of every fragment and the size of its
+//! deletion vector. When a projection only counts the nodes of an unfiltered
+//! Lance-backed label, the planner sums those counts and returns a constant
+//! row instead of scanning the dataset.
+//!
+//! Lance keeps no column-level statistics in the manifest, but the
+//! `graph.summary()` histograms loaded with
+//! [`DataFusionPlanner::with_statistics`] start and end at the exact minimum
+//! and maximum of each numeric and date property. `min(n.age)` and
+//! `max(n.age)` are answered from them when they were collected at the
+//! dataset's current version and the bound converts back to the property's
+//! type without loss; other aggregates (e.g. `max(n.created_at)` of a
+//! timestamp, whose bounds are truncated to milliseconds) are still planned
+//! as aggregates, and the optimizer prunes their scan to the aggregated
+//! column.
+
+use crate::ast::ValueExpression;
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::Result;
+use crate::logical_plan::*;
+use arrow_schema::{DataType, Schema as ArrowSchema};
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::{lit, LogicalPlan, LogicalPlanBuilder};
+use datafusion::scalar::ScalarValue;
+use lance::datafusion::LanceTableProvider;
+use lance::dataset::Dataset;
+use std::sync::Arc;
+
+/// Largest magnitude up to which every integer is exactly an `f64`
+const EXACT_INTEGER_BOUND: f64 = 9_007_199_254_740_992.0;
+
+/// Milliseconds per day, the unit of `Date32` histogram bounds
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// An aggregate of the scanned variable the fast path can answer
+enum MetadataAggregate<'a> {
+    /// `count(*)` or `count(n)`
+    Count,
+    /// `min(n.property)` (`max: false`) or `max(n.property)`
+    Bound { property: &'a str, max: bool },
+}
+
+impl DataFusionPlanner {
+    /// Plan `Project(ScanByLabel, count(n), min(n.p), max(n.p), ...)` as a
+    /// constant row computed from dataset metadata and statistics, or `None`
+    /// when the fast path does not apply
+    ///
+    /// Applies when every projection is a non-distinct `count(*)` or
+    /// `count(n)` of the scanned variable, or a `min` or `max` of one of its
+    /// properties, the label has no inline property filters, view filters,
+    /// shared-table discriminator or soft-delete column, and its source is a
+    /// Lance dataset whose manifest knows every fragment's row count and, for
+    /// `min` and `max`, whose current version the statistics describe.
+    pub(crate) fn try_build_statistics_aggregate(
+        &self,
+        input: &LogicalOperator,
+        projections: &[ProjectionItem],
+    ) -> Result<Option<LogicalPlan>> {
+        let LogicalOperator::ScanByLabel {
+            variable,
+            label,
+            properties,
+        } = input
+        else {
+            return Ok(None);
+        };
+        if !properties.is_empty() || projections.is_empty() {
+            return Ok(None);
+        }
+        let Some(aggregates) = projections
+            .iter()
+            .map(|p| metadata_aggregate(&p.expression, variable))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(None);
+        };
+        let Some(dataset) = self.unfiltered_lance_dataset(label)? else {
+            return Ok(None);
+        };
+
+        let mut exprs = Vec::with_capacity(projections.len());
+        for (projection, aggregate) in projections.iter().zip(aggregates) {
+            let value = match aggregate {
+                MetadataAggregate::Count => metadata_row_count(&dataset).map(ScalarValue::from),
+                MetadataAggregate::Bound { property, max } => {
+                    self.histogram_bound(label, &dataset, property, max)
+                }
+            };
+            let Some(value) = value else {
+                return Ok(None);
+            };
+            let alias = match &projection.alias {
+                Some(alias) => alias.clone(),
+                None => super::super::expression::to_cypher_column_name(&projection.expression),
+            };
+            exprs.push(lit(value).alias(alias));
+        }
+        let plan = LogicalPlanBuilder::empty(true)
+            .project(exprs)
+            .map_err(|e| self.plan_error("Failed to project metadata aggregates", e))?
+            .build()
+            .map_err(|e| self.plan_error("Failed to build plan", e))?;
+        Ok(Some(plan))
+    }
+
+    /// The Lance dataset backing `label`, if the label reads all of it
+    fn unfiltered_lance_dataset(&self, label: &str) -> Result<Option<Arc<Dataset>>> {
+        let Some(catalog) = &self.catalog else {
+            return Ok(None);
+        };
+        let resolved = self.resolve_node_source(label)?;
+        if resolved.filter.is_some() || resolved.columns.is_some() {
+            return Ok(None);
+        }
+        let Some(source) = catalog.node_source(&resolved.source_name) else {
+            return Ok(None);
+        };
+        let Ok(provider) = source_as_provider(&source) else {
+            return Ok(None);
+        };
+        Ok(provider
+            .as_any()
+            .downcast_ref::<LanceTableProvider>()
+            .map(LanceTableProvider::dataset))
+    }
+
+    /// Smallest (or with `max`, largest) value of `property` of `label`, from
+    /// the outer bound of its histogram, if the statistics describe the
+    /// current version of `dataset` and the bound is exact
+    fn histogram_bound(
+        &self,
+        label: &str,
+        dataset: &Dataset,
+        property: &str,
+        max: bool,
+    ) -> Option<ScalarValue> {
+        let statistics = self.statistics.as_ref()?;
+        // Rows written since the statistics were collected may lie outside
+        // their bounds
+        if statistics.node(label)?.dataset_version != Some(dataset.version().version) {
+            return None;
+        }
+        let (min_value, max_value) = statistics.node_property(label, property)?.bounds()?;
+        let schema = ArrowSchema::from(dataset.schema());
+        let field = schema
+            .fields()
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(property))?;
+        exact_value(if max { max_value } else { min_value }, field.data_type())
+    }
+}
+
+/// Live row count of `dataset`, if its manifest knows every fragment's
+fn metadata_row_count(dataset: &Dataset) -> Option<i64> {
+    // Fragments written by old Lance versions may lack counts
+    let mut total = 0i64;
+    for fragment in dataset.fragments().iter() {
+        total += fragment.num_rows()? as i64;
+    }
+    Some(total)
+}
+
+/// `value`, a histogram bound, as a value of `data_type`, if it converts
+/// without loss
+fn exact_value(value: f64, data_type: &DataType) -> Option<ScalarValue> {
+    // Integers beyond 2^53 may have been rounded on their way to a bound
+    let integral = value.fract() == 0.0 && value.abs() <= EXACT_INTEGER_BOUND;
+    if data_type.is_integer() {
+        return integral
+            .then(|| {
+                ScalarValue::Int64(Some(value as i64))
+                    .cast_to(data_type)
+                    .ok()
+            })
+            .flatten();
+    }
+    match data_type {
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            ScalarValue::Float64(Some(value)).cast_to(data_type).ok()
+        }
+        DataType::Date32 => {
+            let days = value / MILLIS_PER_DAY;
+            (integral && days.fract() == 0.0).then(|| ScalarValue::Date32(Some(days as i32)))
+        }
+        DataType::Date64 => integral.then(|| ScalarValue::Date64(Some(value as i64))),
+        // Timestamps were truncated to milliseconds
+        _ => None,
+    }
+}
+
+/// The aggregate `expr` computes, if the fast path can answer it
+fn metadata_aggregate<'a>(
+    expr: &'a ValueExpression,
+    variable: &str,
+) -> Option<MetadataAggregate<'a>> {
+    let ValueExpression::AggregateFunction {
+        name,
+        args,
+        distinct,
+    } = expr
+    else {
+        return None;
+    };
+    match args.as_slice() {
+        [ValueExpression::Variable(v)]
+            if name.eq_ignore_ascii_case("count") && !distinct && (v == "*" || v == variable) =>
+        {
+            Some(MetadataAggregate::Count)
+        }
+        [ValueExpression::Property(property)] if property.variable == variable => {
+            let max = if name.eq_ignore_ascii_case("max") {
+                true
+            } else if name.eq_ignore_ascii_case("min") {
+                false
+            } else {
+                return None;
+            };
+            Some(MetadataAggregate::Bound {
+                property: &property.property,
+                max,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{PropertyRef, ValueExpression};
+    use crate::cost::{ElementStatistics, GraphStatistics, Histogram, PropertyStatistics};
+    use crate::datafusion_planner::test_fixtures::{person_config, person_scan};
+    use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+    use crate::logical_plan::{LogicalOperator, ProjectionItem};
+    use arrow_array::{ArrayRef, Int64Array, RecordBatch, RecordBatchIterator, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::datasource::DefaultTableSource;
+    use lance::datafusion::LanceTableProvider;
+    use lance::dataset::Dataset;
+    use lance_graph_catalog::InMemoryCatalog;
+    use std::sync::Arc;
+
+    async fn lance_person_planner() -> (DataFusionPlanner, tempfile::TempDir) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "David"])) as ArrayRef,
+            ],
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let uri = dir.path().join("Person.lance");
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let mut dataset = Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+        dataset.delete("id = 2").await.unwrap();
+
+        let provider = LanceTableProvider::new(Arc::new(dataset), false, false);
+        let catalog = InMemoryCatalog::new().with_node_source(
+            "Person",
+            Arc::new(DefaultTableSource::new(Arc::new(provider))),
+        );
+        (
+            DataFusionPlanner::with_catalog(person_config(), Arc::new(catalog)),
+            dir,
+        )
+    }
+
+    fn project_people(name: &str, arg: ValueExpression) -> LogicalOperator {
+        LogicalOperator::Project {
+            input: Box::new(person_scan("n")),
+            projections: vec![ProjectionItem {
+                expression: ValueExpression::AggregateFunction {
+                    name: name.to_string(),
+                    args: vec![arg],
+                    distinct: false,
+                },
+                alias: Some("total".to_string()),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_answered_from_metadata() {
+        let (planner, _dir) = lance_person_planner().await;
+        let count = project_people("count", ValueExpression::Variable("n".to_string()));
+        let s = format!("{:?}", planner.plan(&count).unwrap());
+        assert!(!s.contains("TableScan"), "unexpected scan: {}", s);
+        // Four rows written, one deleted
+        assert!(
+            s.contains("Int64(3) AS total"),
+            "expected live count: {}",
+            s
+        );
+    }
+
+    #[tokio::test]
+    async fn test_other_aggregates_still_scan() {
+        let (planner, _dir) = lance_person_planner().await;
+        let max = project_people(
+            "max",
+            ValueExpression::Property(PropertyRef {
+                variable: "n".to_string(),
+                property: "name".to_string(),
+            }),
+        );
+        let s = format!("{:?}", planner.plan(&max).unwrap());
+        assert!(s.contains("TableScan"), "expected scan: {}", s);
+    }
+
+    /// Statistics of the Person dataset at `version`, with the histogram of
+    /// its ids
+    fn id_statistics(version: u64) -> GraphStatistics {
+        let ids = PropertyStatistics {
+            histogram: Some(Histogram {
+                bounds: vec![1.0, 1.5, 3.0, 3.5, 4.0],
+            }),
+            ..Default::default()
+        };
+        GraphStatistics::new()
+            .with_node(
+                "Person",
+                ElementStatistics::new(3).with_dataset_version(version),
+            )
+            .with_node_property("Person", "id", ids)
+    }
+
+    fn id_bounds() -> LogicalOperator {
+        let bound = |name: &str| ProjectionItem {
+            expression: ValueExpression::AggregateFunction {
+                name: name.to_string(),
+                args: vec![ValueExpression::Property(PropertyRef {
+                    variable: "n".to_string(),
+                    property: "id".to_string(),
+                })],
+                distinct: false,
+            },
+            alias: Some(format!("{}_id", name)),
+        };
+        LogicalOperator::Project {
+            input: Box::new(person_scan("n")),
+            projections: vec![bound("min"), bound("max")],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_min_and_max_answered_from_histograms() {
+        let (planner, _dir) = lance_person_planner().await;
+        // Written at version 1, the delete commits version 2
+        let planner = planner.with_statistics(id_statistics(2));
+        let s = format!("{:?}", planner.plan(&id_bounds()).unwrap());
+        assert!(!s.contains("TableScan"), "unexpected scan: {}", s);
+        assert!(s.contains("Int64(1) AS min_id"), "expected minimum: {}", s);
+        assert!(s.contains("Int64(4) AS max_id"), "expected maximum: {}", s);
+    }
+
+    #[tokio::test]
+    async fn test_stale_or_inexact_histograms_still_scan() {
+        let (planner, _dir) = lance_person_planner().await;
+        let stale = planner.with_statistics(id_statistics(1));
+        let s = format!("{:?}", stale.plan(&id_bounds()).unwrap());
+        assert!(s.contains("TableScan"), "expected scan: {}", s);
+
+        assert_eq!(
+            super::exact_value(2.5, &DataType::Int64),
+            None,
+            "a fractional bound is no integer"
+        );
+        assert_eq!(
+            super::exact_value(9.007_199_254_740_994e15, &DataType::Int64),
+            None
+        );
+        assert_eq!(
+            super::exact_value(2.0 * 86_400_000.0, &DataType::Date32),
+            Some(datafusion::scalar::ScalarValue::Date32(Some(2)))
+        );
+    }
+}
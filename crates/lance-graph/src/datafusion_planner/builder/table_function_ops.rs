@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Table functions: `CALL name(args) YIELD ...` planned as a scan of the
+//! function's rows, see [`crate::table_function`]
+
+use crate::ast::TableFunctionCall;
+use crate::datafusion_planner::analysis::PlanningContext;
+use crate::datafusion_planner::DataFusionPlanner;
+use crate::error::{GraphError, Result};
+use crate::logical_plan::*;
+use datafusion::common::{Column, TableReference};
+use datafusion::datasource::provider_as_source;
+use datafusion::logical_expr::{Expr, LogicalPlan, LogicalPlanBuilder};
+
+impl DataFusionPlanner {
+    /// Scan the rows of `call`, keeping its yielded columns under their
+    /// variable names, cross joined with `input` when there is one
+    pub(crate) fn build_table_function(
+        &self,
+        ctx: &mut PlanningContext,
+        input: &Option<Box<LogicalOperator>>,
+        call: &TableFunctionCall,
+    ) -> Result<LogicalPlan> {
+        let name = call.name.to_lowercase();
+        let function = self
+            .table_functions
+            .get(&name)
+            .ok_or_else(|| GraphError::PlanError {
+                message: format!("Unknown table function: {}", call.name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let schema = function.schema();
+        for item in &call.yields {
+            if schema.field_with_name(&item.column).is_err() {
+                let available: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+                return Err(GraphError::PlanError {
+                    message: format!(
+                        "{} yields no column {}; available columns: {}",
+                        call.name,
+                        item.column,
+                        available.join(", ")
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+
+        let arguments = crate::table_function::evaluate_arguments(
+            &call.name,
+            &call.arguments,
+            &self.parameters,
+        )?;
+        let provider = crate::table_function::call_provider(function.clone(), arguments)?;
+        let table = TableReference::bare(name);
+        let yielded: Vec<Expr> = call
+            .yields
+            .iter()
+            .map(|item| {
+                Expr::Column(Column::new(Some(table.clone()), &item.column))
+                    .alias(item.variable().to_lowercase())
+            })
+            .collect();
+        let yielded_plan =
+            LogicalPlanBuilder::scan(table.clone(), provider_as_source(provider), None)
+                .map_err(|e| self.plan_error("Failed to scan table function", e))?
+                .project(yielded)
+                .map_err(|e| self.plan_error("Failed to project yielded columns", e))?;
+
+        let builder = match input {
+            Some(input_op) => {
+                let input_plan = self.build_operator(ctx, input_op)?;
+                LogicalPlanBuilder::from(input_plan)
+                    .cross_join(
+                        yielded_plan
+                            .build()
+                            .map_err(|e| self.plan_error("Failed to build plan", e))?,
+                    )
+                    .map_err(|e| self.plan_error("Failed to join yielded rows", e))?
+            }
+            None => yielded_plan,
+        };
+        builder
+            .build()
+            .map_err(|e| self.plan_error("Failed to build table function plan", e))
+    }
+}
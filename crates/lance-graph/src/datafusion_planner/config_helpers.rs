@@ -10,9 +10,29 @@ use super::analysis::PlanningContext;
 use super::DataFusionPlanner;
 use crate::config::{NodeMapping, RelationshipMapping};
 use crate::error::Result;
+use datafusion::logical_expr::{col, lit, Expr};
 use lance_graph_catalog::GraphSourceCatalog;
 use std::sync::Arc;
 
+/// Physical scan information for a node label
+pub(crate) struct ResolvedNodeSource {
+    /// Catalog name of the node source to scan
+    pub source_name: String,
+    /// Filter to apply before projecting (view filters, label discriminator)
+    pub filter: Option<Expr>,
+    /// Columns exposed for the label; `None` exposes every source column
+    pub columns: Option<Vec<String>>,
+}
+
+impl ResolvedNodeSource {
+    /// Whether a source column is exposed for this label
+    pub(crate) fn exposes(&self, column: &str) -> bool {
+        self.columns
+            .as_ref()
+            .is_none_or(|cols| cols.iter().any(|c| c.eq_ignore_ascii_case(column)))
+    }
+}
+
 impl DataFusionPlanner {
     /// Get relationship mapping from config (case-insensitive)
     pub(crate) fn get_relationship_mapping(&self, rel_type: &str) -> Result<&RelationshipMapping> {
@@ -88,6 +108,83 @@ impl DataFusionPlanner {
         Ok((target_label, node_map))
     }
 
+    /// Resolve the catalog source backing `label`, plus filters and columns to apply
+    ///
+    /// View labels (see [`crate::config::GraphConfigBuilder::with_view_label`])
+    /// scan their base label's source with every view filter in the chain
+    /// combined with AND. Labels stored in a shared table (see
+    /// [`NodeMapping::with_label_column`]) scan that table filtered on the
+    /// discriminator column, exposing only their key and property columns.
+    /// Soft-deleted rows are dropped unless the planner includes deleted rows.
+    pub(crate) fn resolve_node_source(&self, label: &str) -> Result<ResolvedNodeSource> {
+        let (base_label, filters) = self.config.resolve_view_chain(label)?;
+
+        let mut combined: Option<Expr> = None;
+        for filter in &filters {
+            let parsed = crate::parser::parse_filter_expression(filter).map_err(|e| {
+                crate::error::GraphError::ConfigError {
+                    message: format!("Invalid filter for view label '{}': {}", label, e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                }
+            })?;
+            let expr = super::expression::to_df_boolean_expr(&parsed);
+            combined = Some(match combined {
+                Some(acc) => acc.and(expr),
+                None => expr,
+            });
+        }
+
+        let Some(base_map) = self.config.get_node_mapping(&base_label) else {
+            return Ok(ResolvedNodeSource {
+                source_name: base_label,
+                filter: combined,
+                columns: None,
+            });
+        };
+
+        if let Some(live) = self.soft_delete_filter(base_map.soft_delete_column.as_deref()) {
+            combined = Some(match combined {
+                Some(acc) => live.and(acc),
+                None => live,
+            });
+        }
+
+        let mut columns = None;
+        if let Some(label_column) = &base_map.label_column {
+            let discriminator = col(label_column.to_lowercase()).eq(lit(base_map.label.clone()));
+            combined = Some(match combined {
+                Some(acc) => discriminator.and(acc),
+                None => discriminator,
+            });
+
+            if !base_map.property_fields.is_empty() {
+                columns = Some(
+                    base_map
+                        .key_columns()
+                        .into_iter()
+                        .map(str::to_string)
+                        .chain(base_map.property_fields.iter().cloned())
+                        .map(|c| c.to_lowercase())
+                        .collect(),
+                );
+            }
+        }
+
+        Ok(ResolvedNodeSource {
+            source_name: base_map.table_name().to_string(),
+            filter: combined,
+            columns,
+        })
+    }
+
+    /// Filter keeping rows not marked deleted in `soft_delete_column`, if any
+    pub(crate) fn soft_delete_filter(&self, soft_delete_column: Option<&str>) -> Option<Expr> {
+        if self.include_deleted {
+            return None;
+        }
+        soft_delete_column.map(|column| col(column.to_lowercase()).is_null())
+    }
+
     /// Get catalog reference
     pub(crate) fn get_catalog(&self) -> Result<&Arc<dyn GraphSourceCatalog>> {
         self.catalog
@@ -8,17 +8,32 @@
 use crate::ast::{BooleanExpression, PropertyValue, ValueExpression};
 use crate::case_insensitive::qualify_column;
 use crate::datafusion_planner::udf;
-use datafusion::functions::string::lower;
-use datafusion::functions::string::upper;
-use datafusion::logical_expr::{col, lit, BinaryExpr, Expr, Operator};
+use arrow::datatypes::DataType;
+use datafusion::functions::core::expr_fn::{coalesce, named_struct, nullif};
+use datafusion::functions::datetime::expr_fn::now;
+use datafusion::functions::string::{btrim, lower, ltrim, replace, rtrim, upper};
+use datafusion::functions::unicode::{left, right, substr};
+use datafusion::functions_nested::expr_fn::{gen_series, make_array};
+use datafusion::functions_nested::string::string_to_array_udf;
+use datafusion::logical_expr::{cast, col, lit, BinaryExpr, Expr, ExprFunctionExt, Operator};
 use datafusion_functions_aggregate::array_agg::array_agg;
 use datafusion_functions_aggregate::average::avg;
 use datafusion_functions_aggregate::count::count;
 use datafusion_functions_aggregate::count::count_distinct;
 use datafusion_functions_aggregate::min_max::max;
 use datafusion_functions_aggregate::min_max::min;
+use datafusion_functions_aggregate::stddev::{stddev, stddev_pop};
 use datafusion_functions_aggregate::sum::sum;
 
+/// Property under which a variable-length expansion records the hop count of
+/// the path variable bound to it (`length(p)` reads `p__length`)
+pub(crate) const PATH_LENGTH_PROPERTY: &str = "length";
+
+/// Compile the pattern of `=~`, which must match the whole string
+pub(crate) fn compile_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::Regex::new(&format!("^(?:{})$", pattern))
+}
+
 /// Helper function to create LIKE expressions with consistent settings
 fn create_like_expr(expression: &ValueExpression, pattern: &str, case_insensitive: bool) -> Expr {
     Expr::Like(datafusion::logical_expr::Like {
@@ -103,6 +118,34 @@ pub(crate) fn to_df_boolean_expr(expr: &BooleanExpression) -> Expr {
             let pattern = format!("%{}", suffix);
             create_like_expr(expression, &pattern, false)
         }
+        // Compiled once here and shared by every batch the filter sees;
+        // semantic analysis has already rejected invalid patterns
+        BE::RegexMatch {
+            expression,
+            pattern,
+        } => match compile_regex(pattern) {
+            Ok(regex) => {
+                udf::create_regex_match_udf(regex).call(vec![to_df_value_expr(expression)])
+            }
+            Err(_) => lit(false),
+        },
+        // Path predicates are pushed into VariableLengthExpand by the logical planner
+        // and evaluated per hop, so nothing is left to check on the joined rows
+        BE::AllInPath { .. } => lit(true),
+        // The predicate's value for every element, folded by the quantifier
+        BE::ListPredicate {
+            quantifier,
+            variable,
+            list,
+            predicate,
+        } => {
+            let matches =
+                list_comprehension(variable, list, None, Some(to_df_boolean_expr(predicate)));
+            udf::create_list_predicate_udf(*quantifier).call(vec![matches])
+        }
+        // EXISTS subqueries are planned as semi- and anti-joins by the logical
+        // planner and never reach a Filter
+        BE::ExistsSubquery { .. } => lit(true),
     }
 }
 
@@ -147,6 +190,116 @@ pub(crate) fn to_df_value_expr(expr: &ValueExpression) -> Expr {
                         Expr::Literal(datafusion::scalar::ScalarValue::Null, None)
                     }
                 }
+                "rand" => {
+                    // rand(seed) takes an integer literal; rand() is seeded per query or randomly
+                    let seed = match args.as_slice() {
+                        [VE::Literal(PV::Integer(seed))] => Some(*seed as u64),
+                        _ => None,
+                    };
+                    udf::create_random_udf(udf::RandomKind::Float, seed).call(vec![])
+                }
+                "randomuuid" => udf::create_random_udf(udf::RandomKind::Uuid, None).call(vec![]),
+                "timestamp" => {
+                    // Milliseconds since the epoch, fixed for the whole query like now()
+                    cast(now(), DataType::Int64) / lit(1_000_000i64)
+                }
+                // Hop count the variable-length expansion bound to the path
+                // records, or the length of a string in characters
+                "length" => match args.as_slice() {
+                    [VE::Variable(path)] => col(qualify_column(path, PATH_LENGTH_PROPERTY)),
+                    [text] => udf::create_text_udf(udf::TextKind::Length)
+                        .call(vec![to_df_value_expr(text)]),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                "split" => match args.as_slice() {
+                    [text, delimiter] => string_to_array_udf()
+                        .call(vec![to_df_value_expr(text), to_df_value_expr(delimiter)]),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                "replace" => match args.as_slice() {
+                    [text, search, replacement] => replace().call(vec![
+                        to_df_value_expr(text),
+                        to_df_value_expr(search),
+                        to_df_value_expr(replacement),
+                    ]),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                // Cypher counts characters from 0, SQL's substr from 1
+                "substring" => match args.as_slice() {
+                    [text, start] => substr().call(vec![
+                        to_df_value_expr(text),
+                        to_df_value_expr(start) + lit(1i64),
+                    ]),
+                    [text, start, length] => substr().call(vec![
+                        to_df_value_expr(text),
+                        to_df_value_expr(start) + lit(1i64),
+                        to_df_value_expr(length),
+                    ]),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                "left" | "right" => match args.as_slice() {
+                    [text, length] => {
+                        let function = if name.eq_ignore_ascii_case("left") {
+                            left()
+                        } else {
+                            right()
+                        };
+                        function.call(vec![to_df_value_expr(text), to_df_value_expr(length)])
+                    }
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                "trim" | "ltrim" | "rtrim" => match args.as_slice() {
+                    [text] => {
+                        let function = match name.to_lowercase().as_str() {
+                            "trim" => btrim(),
+                            "ltrim" => ltrim(),
+                            _ => rtrim(),
+                        };
+                        function.call(vec![to_df_value_expr(text)])
+                    }
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                // Cypher ranges include their end, like generate_series
+                "range" => match args.as_slice() {
+                    [start, end] => {
+                        gen_series(to_df_value_expr(start), to_df_value_expr(end), lit(1i64))
+                    }
+                    [start, end, step] => gen_series(
+                        to_df_value_expr(start),
+                        to_df_value_expr(end),
+                        to_df_value_expr(step),
+                    ),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                "size" | "head" | "last" | "tail" | "reverse" => match args.as_slice() {
+                    [list] => {
+                        let kind = match name.to_lowercase().as_str() {
+                            "size" => udf::ListKind::Size,
+                            "head" => udf::ListKind::Head,
+                            "last" => udf::ListKind::Last,
+                            "tail" => udf::ListKind::Tail,
+                            _ => udf::ListKind::Reverse,
+                        };
+                        udf::create_list_udf(kind).call(vec![to_df_value_expr(list)])
+                    }
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                // The first argument that is not NULL
+                "coalesce" if !args.is_empty() => {
+                    coalesce(args.iter().map(to_df_value_expr).collect())
+                }
+                // NULL when both arguments are equal; a NULL second argument
+                // equals nothing
+                "nullif" => match args.as_slice() {
+                    [value, other] => nullif(to_df_value_expr(value), to_df_value_expr(other)),
+                    _ => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+                },
+                function if crate::temporal::is_function(function) => {
+                    crate::temporal::to_df_expr(function, args)
+                }
+                crate::introspection::LIST_FUNCTION => {
+                    make_array(args.iter().map(to_df_value_expr).collect())
+                }
                 _ => {
                     // Unknown scalar function - return NULL
                     Expr::Literal(datafusion::scalar::ScalarValue::Null, None)
@@ -229,9 +382,39 @@ pub(crate) fn to_df_value_expr(expr: &ValueExpression) -> Expr {
                     }
                 }
                 "collect" => {
+                    if args.len() == 1 {
+                        collect_non_null(to_df_value_expr(&args[0]), *distinct)
+                    } else {
+                        lit(0)
+                    }
+                }
+                "stdev" | "stdevp" => {
                     if args.len() == 1 {
                         let arg_expr = to_df_value_expr(&args[0]);
-                        array_agg(arg_expr)
+                        let deviation = if name.eq_ignore_ascii_case("stdev") {
+                            stddev(arg_expr)
+                        } else {
+                            stddev_pop(arg_expr)
+                        };
+                        // Cypher's standard deviation of fewer than two values is 0.0
+                        coalesce(vec![deviation, lit(0.0)])
+                    } else {
+                        lit(0)
+                    }
+                }
+                "percentilecont" | "percentiledisc" => {
+                    if args.len() == 2 {
+                        // The values are collected and the percentile read
+                        // from the sorted list
+                        let kind = if name.eq_ignore_ascii_case("percentilecont") {
+                            udf::PercentileKind::Continuous
+                        } else {
+                            udf::PercentileKind::Discrete
+                        };
+                        udf::create_percentile_udf(kind).call(vec![
+                            collect_non_null(to_df_value_expr(&args[0]), *distinct),
+                            cast(to_df_value_expr(&args[1]), DataType::Float64),
+                        ])
                     } else {
                         lit(0)
                     }
@@ -316,22 +499,113 @@ pub(crate) fn to_df_value_expr(expr: &ValueExpression) -> Expr {
             lit(scalar)
         }
         VE::Parameter(name) => {
-            // TODO: Implement proper parameter resolution
-            // Parameters ($param) should be resolved to literal values from the query's
-            // parameter map (CypherQuery::parameters()) before or during planning.
-            //
-            // Current limitation: This creates a column reference as a placeholder,
-            // which will fail at execution if the column doesn't exist.
-            //
-            // Proper fix requires one of:
-            // 1. Resolve parameters during semantic analysis (substitute before planning)
-            // 2. Pass parameter map to to_df_value_expr and resolve here
-            // 3. Use DataFusion's parameter binding mechanism
+            // Scalar parameters are bound into the AST before planning
+            // (`template::bind_value_parameters`). One that reaches here had no
+            // scalar value, and fails as an unknown column.
             col(format!("${}", name))
         }
+        VE::Case { branches, default } => {
+            use datafusion::logical_expr::expr::Case;
+            let when_then = branches
+                .iter()
+                .map(|branch| {
+                    (
+                        Box::new(to_df_boolean_expr(&branch.condition)),
+                        Box::new(to_df_value_expr(&branch.value)),
+                    )
+                })
+                .collect();
+            let default = default.as_deref().map(|d| Box::new(to_df_value_expr(d)));
+            Expr::Case(Case::new(None, when_then, default))
+        }
+        VE::ListComprehension {
+            variable,
+            list,
+            predicate,
+            projection,
+        } => list_comprehension(
+            variable,
+            list,
+            predicate.as_deref().map(to_df_boolean_expr),
+            projection.as_deref().map(to_df_value_expr),
+        ),
+        VE::Reduce {
+            accumulator,
+            initial,
+            variable,
+            list,
+            expression,
+        } => {
+            let accumulator = accumulator.to_lowercase();
+            let variable = variable.to_lowercase();
+            let expression = to_df_value_expr(expression);
+            // Columns of the row the list belongs to are passed along with it
+            let mut outer: Vec<String> = expression
+                .column_refs()
+                .into_iter()
+                .map(|c| c.name.clone())
+                .filter(|name| *name != accumulator && *name != variable)
+                .collect();
+            outer.sort();
+            outer.dedup();
+            let args = [to_df_value_expr(list), to_df_value_expr(initial)]
+                .into_iter()
+                .chain(outer.iter().map(col))
+                .collect();
+            udf::create_reduce_udf(accumulator, variable, expression, outer).call(args)
+        }
+        // Pattern comprehensions and COUNT subqueries run as correlated
+        // subqueries before planning and never reach the planner
+        VE::PatternComprehension { .. } | VE::CountSubquery { .. } => {
+            Expr::Literal(datafusion::scalar::ScalarValue::Null, None)
+        }
+        // Maps are structs with one field per key
+        VE::MapProjection { items, .. } => named_struct(
+            items
+                .iter()
+                .flat_map(|item| [lit(item.key.clone()), to_df_value_expr(&item.value)])
+                .collect(),
+        ),
     }
 }
 
+/// The list comprehension `[variable IN list WHERE predicate | projection]`
+fn list_comprehension(
+    variable: &str,
+    list: &ValueExpression,
+    predicate: Option<Expr>,
+    projection: Option<Expr>,
+) -> Expr {
+    let variable = variable.to_lowercase();
+    // Columns of the row the list belongs to are passed along with it
+    let mut outer: Vec<String> = predicate
+        .iter()
+        .chain(&projection)
+        .flat_map(|e| e.column_refs())
+        .map(|c| c.name.clone())
+        .filter(|name| *name != variable)
+        .collect();
+    outer.sort();
+    outer.dedup();
+    let args = std::iter::once(to_df_value_expr(list))
+        .chain(outer.iter().map(col))
+        .collect();
+    udf::create_list_comprehension_udf(variable, predicate, projection, outer).call(args)
+}
+
+/// `array_agg` of the non-null values of `arg`, as Cypher's collect() skips NULLs
+fn collect_non_null(arg: Expr, distinct: bool) -> Expr {
+    let collect = array_agg(arg.clone()).filter(arg.is_not_null());
+    let collect = if distinct {
+        collect.distinct()
+    } else {
+        collect
+    };
+    collect
+        .build()
+        .unwrap_or(Expr::Literal(datafusion::scalar::ScalarValue::Null, None))
+}
+
 /// Check if a ValueExpression contains an aggregate function
 pub(crate) fn contains_aggregate(expr: &ValueExpression) -> bool {
     use crate::ast::ValueExpression as VE;
@@ -345,10 +619,48 @@ pub(crate) fn contains_aggregate(expr: &ValueExpression) -> bool {
         VE::VectorSimilarity { left, right, .. } => {
             contains_aggregate(left) || contains_aggregate(right)
         }
+        VE::Case { branches, default } => {
+            branches.iter().any(|branch| {
+                condition_contains_aggregate(&branch.condition) || contains_aggregate(&branch.value)
+            }) || default.as_deref().is_some_and(contains_aggregate)
+        }
+        // Semantic analysis keeps aggregates out of the predicate and projection
+        VE::ListComprehension { list, .. } => contains_aggregate(list),
+        VE::Reduce { initial, list, .. } => contains_aggregate(initial) || contains_aggregate(list),
+        VE::MapProjection { items, .. } => items.iter().any(|item| contains_aggregate(&item.value)),
         _ => false,
     }
 }
 
+/// Check if the operands of a condition contain an aggregate function
+pub(crate) fn condition_contains_aggregate(expr: &BooleanExpression) -> bool {
+    use crate::ast::BooleanExpression as BE;
+    match expr {
+        BE::Comparison { left, right, .. } => contains_aggregate(left) || contains_aggregate(right),
+        BE::And(left, right) | BE::Or(left, right) => {
+            condition_contains_aggregate(left) || condition_contains_aggregate(right)
+        }
+        BE::Not(inner) => condition_contains_aggregate(inner),
+        BE::In { expression, list } => {
+            contains_aggregate(expression) || list.iter().any(contains_aggregate)
+        }
+        BE::Like { expression, .. }
+        | BE::ILike { expression, .. }
+        | BE::Contains { expression, .. }
+        | BE::StartsWith { expression, .. }
+        | BE::EndsWith { expression, .. }
+        | BE::RegexMatch { expression, .. }
+        | BE::IsNull(expression)
+        | BE::IsNotNull(expression) => contains_aggregate(expression),
+        BE::Exists(_) | BE::AllInPath { .. } => false,
+        // Semantic analysis keeps aggregates out of the predicate
+        BE::ListPredicate { list, .. } => contains_aggregate(list),
+        BE::ExistsSubquery { where_clause, .. } => where_clause
+            .as_deref()
+            .is_some_and(condition_contains_aggregate),
+    }
+}
+
 /// Convert a ValueExpression to Cypher dot notation for column naming
 ///
 /// This generates user-friendly column names following Cypher conventions:
@@ -369,6 +681,15 @@ pub(crate) fn to_cypher_column_name(expr: &ValueExpression) -> String {
             // Handle nested property references
             format!("{}.{}", prop.variable, prop.property)
         }
+        // `at.year` / `e.at.year`
+        VE::ScalarFunction { name, args } if name == crate::temporal::COMPONENT_FUNCTION => {
+            match args.as_slice() {
+                [value, VE::Literal(PropertyValue::String(component))] => {
+                    format!("{}.{}", to_cypher_column_name(value), component)
+                }
+                _ => name.clone(),
+            }
+        }
         VE::ScalarFunction { name, args } | VE::AggregateFunction { name, args, .. } => {
             let distinct_str = if let VE::AggregateFunction { distinct: true, .. } = expr {
                 "DISTINCT "
@@ -390,6 +711,22 @@ pub(crate) fn to_cypher_column_name(expr: &ValueExpression) -> String {
                 name.to_lowercase()
             }
         }
+        VE::MapProjection { variable, items } => {
+            // `n {.name, other, key: value}`
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match &item.value {
+                    VE::Property(prop)
+                        if prop.variable == *variable && prop.property == item.key =>
+                    {
+                        format!(".{}", item.key)
+                    }
+                    VE::Variable(v) if *v == item.key => item.key.clone(),
+                    value => format!("{}: {}", item.key, to_cypher_column_name(value)),
+                })
+                .collect();
+            format!("{} {{{}}}", variable, items.join(", "))
+        }
         _ => {
             // For other expressions (literals, arithmetic), use a generic name
             // In practice, these should always have explicit aliases
@@ -1356,6 +1693,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_case_expression() {
+        let age = || ValueExpression::Property(PropertyRef::new("p", "age"));
+        let expr = ValueExpression::Case {
+            branches: vec![crate::ast::CaseBranch {
+                condition: BooleanExpression::Comparison {
+                    left: age(),
+                    operator: crate::ast::ComparisonOperator::LessThan,
+                    right: ValueExpression::Literal(PropertyValue::Integer(30)),
+                },
+                value: ValueExpression::Literal(PropertyValue::String("young".into())),
+            }],
+            default: None,
+        };
+        match to_df_value_expr(&expr) {
+            Expr::Case(case) => {
+                assert!(case.expr.is_none());
+                assert_eq!(case.when_then_expr.len(), 1);
+                assert!(case.else_expr.is_none());
+            }
+            other => panic!("Expected CASE, got {:?}", other),
+        }
+        assert!(!contains_aggregate(&expr));
+
+        // An aggregate in a THEN value makes the whole CASE an aggregate
+        let expr = ValueExpression::Case {
+            branches: vec![crate::ast::CaseBranch {
+                condition: BooleanExpression::IsNotNull(age()),
+                value: ValueExpression::AggregateFunction {
+                    name: "max".to_string(),
+                    args: vec![age()],
+                    distinct: false,
+                },
+            }],
+            default: Some(Box::new(ValueExpression::Literal(PropertyValue::Null))),
+        };
+        assert!(contains_aggregate(&expr));
+    }
+
     // ========================================================================
     // Unit tests for to_cypher_column_name()
     // ========================================================================
@@ -9,9 +9,10 @@
 //! higher-level planner code can remain focused on traversal semantics.
 
 use super::analysis::{PlanningContext, RelationshipInstance};
+use super::config_helpers::ResolvedNodeSource;
 use super::DataFusionPlanner;
 use crate::ast::{PropertyValue, RelationshipDirection};
-use crate::case_insensitive::qualify_column;
+use crate::case_insensitive::{qualify_column, qualify_key_pairs};
 use crate::config::{NodeMapping, RelationshipMapping};
 use crate::error::Result;
 use datafusion::logical_expr::{
@@ -25,7 +26,7 @@ use std::sync::Arc;
 pub(crate) struct SourceJoinParams<'a> {
     pub source_variable: &'a str,
     pub rel_qualifier: &'a str,
-    pub node_id_field: &'a str,
+    pub node_map: &'a NodeMapping,
     pub rel_map: &'a RelationshipMapping,
     pub direction: &'a RelationshipDirection,
 }
@@ -48,14 +49,23 @@ impl DataFusionPlanner {
         target_label: &str,
         target_variable: &str,
         target_properties: &HashMap<String, PropertyValue>,
+        resolved: &ResolvedNodeSource,
     ) -> Result<LogicalPlan> {
         let target_schema = target_source.schema();
+        let provenance =
+            self.provenance_exprs(target_variable, &target_source, |c| resolved.exposes(c));
         let normalized_target_label = target_label.to_lowercase();
         let mut target_builder =
             LogicalPlanBuilder::scan(&normalized_target_label, target_source, None).map_err(
                 |e| self.plan_error(&format!("Failed to scan target node '{}'", target_label), e),
             )?;
 
+        if let Some(label_filter) = resolved.filter.clone() {
+            target_builder = target_builder
+                .filter(label_filter)
+                .map_err(|e| self.plan_error("Failed to apply label filter", e))?;
+        }
+
         // Apply target property filters (e.g., (b {age: 30}))
         for (k, v) in target_properties.iter() {
             let lit_expr = super::expression::to_df_value_expr(
@@ -71,14 +81,16 @@ impl DataFusionPlanner {
             })?;
         }
 
-        let target_qualified_exprs: Vec<Expr> = target_schema
+        let mut target_qualified_exprs: Vec<Expr> = target_schema
             .fields()
             .iter()
+            .filter(|field| resolved.exposes(field.name()))
             .map(|field| {
                 let qualified_name = qualify_column(target_variable, field.name());
                 col(field.name()).alias(&qualified_name)
             })
             .collect();
+        target_qualified_exprs.extend(provenance);
 
         target_builder
             .project(target_qualified_exprs)
@@ -92,23 +104,28 @@ impl DataFusionPlanner {
         &self,
         mut builder: LogicalPlanBuilder,
         params: &TargetJoinParams,
-        qualified_target_key: String,
     ) -> Result<LogicalPlan> {
         // Determine the relationship target key based on direction
-        let target_key = match params.direction {
-            RelationshipDirection::Outgoing => &params.rel_map.target_id_field,
-            RelationshipDirection::Incoming => &params.rel_map.source_id_field,
-            RelationshipDirection::Undirected => &params.rel_map.target_id_field,
-        };
-
-        let qualified_rel_target_key = qualify_column(params.rel_qualifier, target_key);
+        let (rel_target_keys, target_keys) = qualify_key_pairs(
+            params.rel_qualifier,
+            &Self::get_target_join_keys(params.direction, params.rel_map),
+            params.target_variable,
+            &params.node_map.key_columns(),
+        )?;
 
-        // Create a filter expression: rel_target_key = target_key
-        let join_condition = Expr::BinaryExpr(BinaryExpr {
-            left: Box::new(col(&qualified_rel_target_key)),
-            op: Operator::Eq,
-            right: Box::new(col(&qualified_target_key)),
-        });
+        // Create a filter expression: rel_target_key = target_key (per key column)
+        let join_condition = rel_target_keys
+            .iter()
+            .zip(target_keys.iter())
+            .map(|(rel_key, node_key)| {
+                Expr::BinaryExpr(BinaryExpr {
+                    left: Box::new(col(rel_key)),
+                    op: Operator::Eq,
+                    right: Box::new(col(node_key)),
+                })
+            })
+            .reduce(Expr::and)
+            .unwrap(); // key_columns() always yields at least one column
 
         // Apply filter instead of join
         builder = builder
@@ -140,22 +157,15 @@ impl DataFusionPlanner {
         params: &SourceJoinParams,
     ) -> Result<LogicalPlanBuilder> {
         // Determine join keys based on direction
-        let right_key = match params.direction {
-            RelationshipDirection::Outgoing => &params.rel_map.source_id_field,
-            RelationshipDirection::Incoming => &params.rel_map.target_id_field,
-            RelationshipDirection::Undirected => &params.rel_map.source_id_field,
-        };
-
-        let qualified_left_key = qualify_column(params.source_variable, params.node_id_field);
-        let qualified_right_key = qualify_column(params.rel_qualifier, right_key);
+        let (left_keys, right_keys) = qualify_key_pairs(
+            params.source_variable,
+            &params.node_map.key_columns(),
+            params.rel_qualifier,
+            &Self::get_source_join_keys(params.direction, params.rel_map),
+        )?;
 
         LogicalPlanBuilder::from(left_plan)
-            .join(
-                rel_scan,
-                JoinType::Inner,
-                (vec![qualified_left_key], vec![qualified_right_key]),
-                None,
-            )
+            .join(rel_scan, JoinType::Inner, (left_keys, right_keys), None)
             .map_err(|e| self.plan_error("Failed to join source to relationship", e))
     }
 
@@ -179,7 +189,8 @@ impl DataFusionPlanner {
                 .map_err(|e| self.plan_error("Failed to build plan (no target label)", e));
         };
 
-        let Some(target_source) = cat.node_source(&target_label) else {
+        let resolved = self.resolve_node_source(&target_label)?;
+        let Some(target_source) = cat.node_source(&resolved.source_name) else {
             return builder
                 .build()
                 .map_err(|e| self.plan_error("Failed to build plan (no target source)", e));
@@ -203,7 +214,7 @@ impl DataFusionPlanner {
         if target_exists {
             // Variable reuse: target node columns already in schema
             // Skip creating new scan, just add filter constraint
-            return self.handle_variable_reuse_filter(builder, params, qualified_target_key);
+            return self.handle_variable_reuse_filter(builder, params);
         }
 
         // Normal case: target variable doesn't exist yet
@@ -213,22 +224,22 @@ impl DataFusionPlanner {
             &target_label,
             params.target_variable,
             params.target_properties,
+            &resolved,
         )?;
 
         // Determine target join keys
-        let target_key = match params.direction {
-            RelationshipDirection::Outgoing => &params.rel_map.target_id_field,
-            RelationshipDirection::Incoming => &params.rel_map.source_id_field,
-            RelationshipDirection::Undirected => &params.rel_map.target_id_field,
-        };
-
-        let qualified_rel_target_key = qualify_column(params.rel_qualifier, target_key);
+        let (rel_target_keys, target_keys) = qualify_key_pairs(
+            params.rel_qualifier,
+            &Self::get_target_join_keys(params.direction, params.rel_map),
+            params.target_variable,
+            &params.node_map.key_columns(),
+        )?;
 
         builder = builder
             .join(
                 target_scan,
                 JoinType::Inner,
-                (vec![qualified_rel_target_key], vec![qualified_target_key]),
+                (rel_target_keys, target_keys),
                 None,
             )
             .map_err(|e| self.plan_error("Failed to join relationship to target", e))?;
@@ -238,27 +249,27 @@ impl DataFusionPlanner {
             .map_err(|e| self.plan_error("Failed to build final join plan", e))
     }
 
-    /// Get relationship join key based on direction (source side)
-    pub(crate) fn get_source_join_key<'a>(
+    /// Get relationship join key columns based on direction (source side)
+    pub(crate) fn get_source_join_keys<'a>(
         direction: &RelationshipDirection,
         rel_map: &'a RelationshipMapping,
-    ) -> &'a str {
+    ) -> Vec<&'a str> {
         match direction {
-            RelationshipDirection::Outgoing => &rel_map.source_id_field,
-            RelationshipDirection::Incoming => &rel_map.target_id_field,
-            RelationshipDirection::Undirected => &rel_map.source_id_field,
+            RelationshipDirection::Outgoing => rel_map.source_key_columns(),
+            RelationshipDirection::Incoming => rel_map.target_key_columns(),
+            RelationshipDirection::Undirected => rel_map.source_key_columns(),
         }
     }
 
-    /// Get relationship join key based on direction (target side)
-    pub(crate) fn get_target_join_key<'a>(
+    /// Get relationship join key columns based on direction (target side)
+    pub(crate) fn get_target_join_keys<'a>(
         direction: &RelationshipDirection,
         rel_map: &'a RelationshipMapping,
-    ) -> &'a str {
+    ) -> Vec<&'a str> {
         match direction {
-            RelationshipDirection::Outgoing => &rel_map.target_id_field,
-            RelationshipDirection::Incoming => &rel_map.source_id_field,
-            RelationshipDirection::Undirected => &rel_map.target_id_field,
+            RelationshipDirection::Outgoing => rel_map.target_key_columns(),
+            RelationshipDirection::Incoming => rel_map.source_key_columns(),
+            RelationshipDirection::Undirected => rel_map.target_key_columns(),
         }
     }
 
@@ -274,15 +285,18 @@ impl DataFusionPlanner {
         node_map: &NodeMapping,
         direction: &RelationshipDirection,
     ) -> Result<LogicalPlanBuilder> {
-        let source_key = Self::get_source_join_key(direction, rel_map);
-        let qualified_source_key = qualify_column(source_variable, &node_map.id_field);
-        let qualified_rel_source_key = qualify_column(&rel_instance.alias, source_key);
+        let (source_keys, rel_source_keys) = qualify_key_pairs(
+            source_variable,
+            &node_map.key_columns(),
+            &rel_instance.alias,
+            &Self::get_source_join_keys(direction, rel_map),
+        )?;
 
         LogicalPlanBuilder::from(input_plan)
             .join(
                 rel_scan,
                 JoinType::Inner,
-                (vec![qualified_source_key], vec![qualified_rel_source_key]),
+                (source_keys, rel_source_keys),
                 None,
             )
             .map_err(|e| crate::error::GraphError::PlanError {
@@ -303,15 +317,18 @@ impl DataFusionPlanner {
         node_map: &NodeMapping,
         direction: &RelationshipDirection,
     ) -> Result<LogicalPlanBuilder> {
-        let target_key = Self::get_target_join_key(direction, rel_map);
-        let qualified_rel_target_key = qualify_column(&rel_instance.alias, target_key);
-        let qualified_target_key = qualify_column(target_variable, &node_map.id_field);
+        let (rel_target_keys, target_keys) = qualify_key_pairs(
+            &rel_instance.alias,
+            &Self::get_target_join_keys(direction, rel_map),
+            target_variable,
+            &node_map.key_columns(),
+        )?;
 
         builder
             .join(
                 target_scan,
                 JoinType::Inner,
-                (vec![qualified_rel_target_key], vec![qualified_target_key]),
+                (rel_target_keys, target_keys),
                 None,
             )
             .map_err(|e| crate::error::GraphError::PlanError {
@@ -613,4 +630,99 @@ mod tests {
             s
         );
     }
+
+    fn tenant_catalog() -> std::sync::Arc<dyn lance_graph_catalog::GraphSourceCatalog> {
+        use arrow_schema::{DataType, Field, Schema};
+        use lance_graph_catalog::{InMemoryCatalog, SimpleTableSource};
+        use std::sync::Arc;
+
+        let user_schema = Arc::new(Schema::new(vec![
+            Field::new("tenant_id", DataType::Int64, false),
+            Field::new("user_id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let follows_schema = Arc::new(Schema::new(vec![
+            Field::new("src_tenant", DataType::Int64, false),
+            Field::new("src_id", DataType::Int64, false),
+            Field::new("dst_tenant", DataType::Int64, false),
+            Field::new("dst_id", DataType::Int64, false),
+        ]));
+        Arc::new(
+            InMemoryCatalog::new()
+                .with_node_source("User", Arc::new(SimpleTableSource::new(user_schema)))
+                .with_relationship_source(
+                    "FOLLOWS",
+                    Arc::new(SimpleTableSource::new(follows_schema)),
+                ),
+        )
+    }
+
+    fn tenant_config(node_key: Vec<String>) -> crate::config::GraphConfig {
+        crate::config::GraphConfig::builder()
+            .with_node_mapping(
+                crate::config::NodeMapping::new("User", "user_id").with_composite_key(node_key),
+            )
+            .with_relationship_mapping(
+                crate::config::RelationshipMapping::new("FOLLOWS", "src_id", "dst_id")
+                    .with_composite_keys(
+                        vec!["src_tenant".to_string(), "src_id".to_string()],
+                        vec!["dst_tenant".to_string(), "dst_id".to_string()],
+                    ),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn tenant_expand() -> LogicalOperator {
+        // MATCH (a:User)-[:FOLLOWS]->(b:User)
+        LogicalOperator::Expand {
+            input: Box::new(LogicalOperator::ScanByLabel {
+                variable: "a".to_string(),
+                label: "User".to_string(),
+                properties: Default::default(),
+            }),
+            source_variable: "a".to_string(),
+            target_variable: "b".to_string(),
+            target_label: "User".to_string(),
+            relationship_types: vec!["FOLLOWS".to_string()],
+            direction: crate::ast::RelationshipDirection::Outgoing,
+            relationship_variable: None,
+            properties: Default::default(),
+            target_properties: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_expand_joins_on_composite_keys() {
+        let cfg = tenant_config(vec!["tenant_id".to_string(), "user_id".to_string()]);
+        let planner = DataFusionPlanner::with_catalog(cfg, tenant_catalog());
+        let df_plan = planner.plan(&tenant_expand()).unwrap();
+        let s = format!("{:?}", df_plan);
+
+        for key in [
+            "a__tenant_id",
+            "a__user_id",
+            "follows_1__src_tenant",
+            "follows_1__src_id",
+            "follows_1__dst_tenant",
+            "follows_1__dst_id",
+            "b__tenant_id",
+            "b__user_id",
+        ] {
+            assert!(s.contains(key), "missing composite join key {}: {}", key, s);
+        }
+    }
+
+    #[test]
+    fn test_expand_rejects_composite_key_arity_mismatch() {
+        // Node keyed by a single column, relationship by two
+        let cfg = tenant_config(vec!["user_id".to_string()]);
+        let planner = DataFusionPlanner::with_catalog(cfg, tenant_catalog());
+        let err = planner.plan(&tenant_expand()).unwrap_err();
+        assert!(
+            err.to_string().contains("Composite key mismatch"),
+            "unexpected error: {}",
+            err
+        );
+    }
 }
@@ -17,7 +17,7 @@
 pub mod analysis;
 mod builder;
 mod config_helpers;
-mod expression;
+pub(crate) mod expression;
 mod join_ops;
 mod scan_ops;
 mod udf;
@@ -30,12 +30,37 @@ mod test_fixtures;
 pub use analysis::{PlanningContext, QueryAnalysis, RelationshipInstance};
 
 use crate::config::GraphConfig;
+use crate::cost::{GraphStatistics, ObservedPredicates};
 use crate::error::Result;
+use crate::expansion::{ExpansionLimits, TruncationFlags};
 use crate::logical_plan::LogicalOperator;
+use crate::table_function::TableFunction;
 use datafusion::logical_expr::LogicalPlan;
 use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Provenance columns added per matched entity when provenance is enabled
+///
+/// `_dataset` is the dataset URI, `_fragment` the fragment id, `_rowid` the
+/// stable row id and `_version` the dataset version that was scanned.
+pub const PROVENANCE_COLUMNS: [&str; 4] = ["_dataset", "_fragment", "_rowid", "_version"];
+
+/// What integer `+`, `-` and `*` do when the result does not fit the type
+///
+/// Cypher integers fail on overflow, which is the default. The other modes
+/// trade that for results that are always defined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverflowMode {
+    /// Wrap around in two's complement, as Arrow's default kernels do
+    Wrap,
+    /// Clamp to the smallest or largest value of the type
+    Saturate,
+    /// Fail the query
+    #[default]
+    Error,
+}
+
 /// Planner abstraction for graph-to-physical planning
 pub trait GraphPhysicalPlanner {
     fn plan(&self, logical_plan: &LogicalOperator) -> Result<LogicalPlan>;
@@ -45,6 +70,17 @@ pub trait GraphPhysicalPlanner {
 pub struct DataFusionPlanner {
     pub(crate) config: GraphConfig,
     pub(crate) catalog: Option<Arc<dyn GraphSourceCatalog>>,
+    pub(crate) include_deleted: bool,
+    pub(crate) include_provenance: bool,
+    pub(crate) parameters: HashMap<String, serde_json::Value>,
+    pub(crate) seed: Option<u64>,
+    pub(crate) expansion_limits: ExpansionLimits,
+    pub(crate) truncation: TruncationFlags,
+    pub(crate) observed_predicates: Option<ObservedPredicates>,
+    pub(crate) statistics: Option<GraphStatistics>,
+    pub(crate) overflow_mode: OverflowMode,
+    pub(crate) unicode_normalization: bool,
+    pub(crate) table_functions: HashMap<String, Arc<dyn TableFunction>>,
 }
 
 impl DataFusionPlanner {
@@ -52,6 +88,17 @@ impl DataFusionPlanner {
         Self {
             config,
             catalog: None,
+            include_deleted: false,
+            include_provenance: false,
+            parameters: HashMap::new(),
+            seed: None,
+            expansion_limits: ExpansionLimits::default(),
+            truncation: TruncationFlags::default(),
+            observed_predicates: None,
+            statistics: None,
+            overflow_mode: OverflowMode::default(),
+            unicode_normalization: false,
+            table_functions: HashMap::new(),
         }
     }
 
@@ -59,9 +106,101 @@ impl DataFusionPlanner {
         Self {
             config,
             catalog: Some(catalog),
+            include_deleted: false,
+            include_provenance: false,
+            parameters: HashMap::new(),
+            seed: None,
+            expansion_limits: ExpansionLimits::default(),
+            truncation: TruncationFlags::default(),
+            observed_predicates: None,
+            statistics: None,
+            overflow_mode: OverflowMode::default(),
+            unicode_normalization: false,
+            table_functions: HashMap::new(),
         }
     }
 
+    /// Keep soft-deleted rows in scans (the query's `INCLUDE DELETED` option)
+    pub fn with_include_deleted(mut self, include_deleted: bool) -> Self {
+        self.include_deleted = include_deleted;
+        self
+    }
+
+    /// Return [`PROVENANCE_COLUMNS`] for every matched entity read from a Lance dataset
+    pub fn with_provenance(mut self, include_provenance: bool) -> Self {
+        self.include_provenance = include_provenance;
+        self
+    }
+
+    /// Query parameters bound into `IN $param` lists
+    pub fn with_parameters(mut self, parameters: HashMap<String, serde_json::Value>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Seed for `rand()` and `randomUUID()` calls without an explicit seed
+    ///
+    /// Each call site draws from its own generator, seeded from `seed` and its
+    /// position in the plan, so re-running the query reproduces its results.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Cap the paths of variable-length expansions, raising `truncation`
+    /// while the plan runs if a cap drops any
+    pub(crate) fn with_expansion_limits(
+        mut self,
+        limits: ExpansionLimits,
+        truncation: TruncationFlags,
+    ) -> Self {
+        self.expansion_limits = limits;
+        self.truncation = truncation;
+        self
+    }
+
+    /// How integer arithmetic handles overflow
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
+    /// Compare, match and case-map strings in Unicode normalization form C,
+    /// so that composed and decomposed spellings of a character are equal
+    ///
+    /// Needs the `icu` feature; planning fails without it.
+    pub fn with_unicode_normalization(mut self, unicode_normalization: bool) -> Self {
+        self.unicode_normalization = unicode_normalization;
+        self
+    }
+
+    /// Count the rows each `WHERE` predicate sees and keeps into `observed`
+    ///
+    /// Observed predicates are evaluated as plain filters, without the
+    /// disjunctive scan and hashed `IN` list rewrites.
+    pub(crate) fn with_observed_predicates(mut self, observed: ObservedPredicates) -> Self {
+        self.observed_predicates = Some(observed);
+        self
+    }
+
+    /// Plan filters that `statistics` prove keep no row as empty relations
+    /// (see [`crate::pruning`]), and answer `min` and `max` of unfiltered
+    /// labels from their histograms
+    pub fn with_statistics(mut self, statistics: GraphStatistics) -> Self {
+        self.statistics = Some(statistics);
+        self
+    }
+
+    /// Table functions `CALL ... YIELD` clauses can call, keyed by lowercase
+    /// name; see [`crate::table_function`]
+    pub fn with_table_functions(
+        mut self,
+        table_functions: HashMap<String, Arc<dyn TableFunction>>,
+    ) -> Self {
+        self.table_functions = table_functions;
+        self
+    }
+
     /// Helper to convert DataFusion builder errors into GraphError::PlanError with context
     pub(crate) fn plan_error<E: std::fmt::Display>(
         &self,
@@ -82,8 +221,179 @@ impl GraphPhysicalPlanner for DataFusionPlanner {
 
         // Phase 2: Build execution plan with context
         let mut ctx = PlanningContext::new(&analysis);
-        self.build_operator(&mut ctx, logical_plan)
+        let plan = self.build_operator(&mut ctx, logical_plan)?;
+        let plan = match self.seed {
+            Some(seed) => self.seed_random_functions(plan, seed)?,
+            None => plan,
+        };
+        let plan = match self.overflow_mode {
+            OverflowMode::Wrap => plan,
+            mode => self.check_integer_arithmetic(plan, mode)?,
+        };
+        if self.unicode_normalization {
+            return self.normalize_text(plan);
+        }
+        Ok(plan)
+    }
+}
+
+impl DataFusionPlanner {
+    /// Replace unseeded random UDFs with ones seeded from `seed`
+    fn seed_random_functions(&self, plan: LogicalPlan, seed: u64) -> Result<LogicalPlan> {
+        use datafusion::common::tree_node::{Transformed, TreeNode};
+        use datafusion::logical_expr::Expr;
+
+        let mut call_site = 0u64;
+        plan.transform_up(|node| {
+            node.map_expressions(|expr| {
+                expr.transform_up(|expr| {
+                    let kind = match &expr {
+                        Expr::ScalarFunction(f) => udf::unseeded_random_kind(&f.func),
+                        _ => None,
+                    };
+                    let Some(kind) = kind else {
+                        return Ok(Transformed::no(expr));
+                    };
+                    call_site += 1;
+                    let site_seed =
+                        seed.wrapping_add(call_site.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                    Ok(Transformed::yes(
+                        udf::create_random_udf(kind, Some(site_seed)).call(vec![]),
+                    ))
+                })
+            })
+        })
+        .map(|t| t.data)
+        .map_err(|e| self.plan_error("Failed to seed random functions", e))
+    }
+
+    /// Replace integer `+`, `-` and `*`, which wrap on overflow, with UDFs
+    /// handling overflow as `mode` says
+    fn check_integer_arithmetic(
+        &self,
+        plan: LogicalPlan,
+        mode: OverflowMode,
+    ) -> Result<LogicalPlan> {
+        use datafusion::common::tree_node::Transformed;
+        use datafusion::logical_expr::{cast, BinaryExpr, Expr, ExprSchemable, Operator};
+
+        rewrite_expressions(plan, |expr, schema| {
+            let Expr::BinaryExpr(BinaryExpr { left, op, right }) = &expr else {
+                return Ok(Transformed::no(expr));
+            };
+            if !matches!(op, Operator::Plus | Operator::Minus | Operator::Multiply) {
+                return Ok(Transformed::no(expr));
+            }
+            let integer = |e: &Expr| {
+                e.get_type(schema)
+                    .is_ok_and(|data_type| data_type.is_integer())
+            };
+            if !integer(left) || !integer(right) {
+                return Ok(Transformed::no(expr));
+            }
+            let data_type = expr.get_type(schema)?;
+            let udf = udf::create_checked_arithmetic_udf(*op, mode, data_type.clone());
+            let args = vec![
+                cast(left.as_ref().clone(), data_type.clone()),
+                cast(right.as_ref().clone(), data_type),
+            ];
+            Ok(Transformed::yes(udf.call(args)))
+        })
+        .map_err(|e| self.plan_error("Failed to check integer arithmetic", e))
     }
+
+    /// Normalize both sides of string comparisons, `LIKE` and `IN` lists, and
+    /// the results of `toUpper` and `toLower`, to NFC
+    #[cfg(feature = "icu")]
+    fn normalize_text(&self, plan: LogicalPlan) -> Result<LogicalPlan> {
+        use arrow::datatypes::DataType;
+        use datafusion::common::tree_node::Transformed;
+        use datafusion::logical_expr::expr::InList;
+        use datafusion::logical_expr::{BinaryExpr, Expr, ExprSchemable, Like};
+
+        let nfc = |expr: Expr| match &expr {
+            Expr::ScalarFunction(f) if f.name() == "nfc" => expr,
+            _ => udf::create_text_udf(udf::TextKind::Nfc).call(vec![expr]),
+        };
+        rewrite_expressions(plan, |expr, schema| {
+            let text = |e: &Expr| {
+                e.get_type(schema).is_ok_and(|data_type| {
+                    matches!(
+                        data_type,
+                        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+                    )
+                })
+            };
+            Ok(match expr {
+                Expr::BinaryExpr(BinaryExpr { left, op, right })
+                    if op.is_comparison_operator() && text(&left) && text(&right) =>
+                {
+                    Transformed::yes(Expr::BinaryExpr(BinaryExpr::new(
+                        Box::new(nfc(*left)),
+                        op,
+                        Box::new(nfc(*right)),
+                    )))
+                }
+                Expr::Like(like) if text(&like.expr) => Transformed::yes(Expr::Like(Like {
+                    expr: Box::new(nfc(*like.expr)),
+                    pattern: Box::new(nfc(*like.pattern)),
+                    ..like
+                })),
+                Expr::InList(in_list) if text(&in_list.expr) => {
+                    Transformed::yes(Expr::InList(InList {
+                        expr: Box::new(nfc(*in_list.expr)),
+                        list: in_list.list.into_iter().map(nfc).collect(),
+                        negated: in_list.negated,
+                    }))
+                }
+                Expr::ScalarFunction(f) if matches!(f.name(), "upper" | "lower") => {
+                    Transformed::yes(nfc(Expr::ScalarFunction(f)))
+                }
+                expr => Transformed::no(expr),
+            })
+        })
+        .map_err(|e| self.plan_error("Failed to normalize text", e))
+    }
+
+    #[cfg(not(feature = "icu"))]
+    fn normalize_text(&self, _plan: LogicalPlan) -> Result<LogicalPlan> {
+        Err(crate::error::GraphError::UnsupportedFeature {
+            feature: "Unicode normalization without the `icu` feature".to_string(),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+}
+
+/// Rewrite the expressions of every node of `plan` bottom-up; `rewrite` also
+/// gets the schema of the columns the expressions read
+fn rewrite_expressions(
+    plan: LogicalPlan,
+    mut rewrite: impl FnMut(
+        datafusion::logical_expr::Expr,
+        &datafusion::common::DFSchema,
+    ) -> datafusion::error::Result<
+        datafusion::common::tree_node::Transformed<datafusion::logical_expr::Expr>,
+    >,
+) -> datafusion::error::Result<LogicalPlan> {
+    use datafusion::common::tree_node::{Transformed, TreeNode};
+
+    plan.transform_up(|node| {
+        // Expressions read the columns of the node's inputs
+        let schema = match node.inputs().as_slice() {
+            _ if node.expressions().is_empty() => None,
+            [input] => Some(input.schema().as_ref().clone()),
+            [left, right] => left.schema().join(right.schema()).ok(),
+            _ => None,
+        };
+        let Some(schema) = schema else {
+            return node.recompute_schema().map(Transformed::no);
+        };
+        let rewritten =
+            node.map_expressions(|expr| expr.transform_up(|expr| rewrite(expr, &schema)))?;
+        // Types and nullability of the rewritten expressions may differ
+        rewritten.map_data(|plan| plan.recompute_schema())
+    })
+    .map(|t| t.data)
 }
 
 #[cfg(test)]
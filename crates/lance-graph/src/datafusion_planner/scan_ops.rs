@@ -9,7 +9,11 @@ use super::analysis::{PlanningContext, RelationshipInstance};
 use crate::ast::PropertyValue;
 use crate::case_insensitive::qualify_column;
 use crate::error::Result;
-use datafusion::logical_expr::{col, BinaryExpr, Expr, LogicalPlan, LogicalPlanBuilder, Operator};
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::{
+    col, lit, BinaryExpr, Expr, LogicalPlan, LogicalPlanBuilder, Operator, TableSource,
+};
+use lance::datafusion::LanceTableProvider;
 use lance_graph_catalog::GraphSourceCatalog;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -28,10 +32,14 @@ impl DataFusionPlanner {
     ) -> Result<LogicalPlan> {
         // Try to use catalog if available
         if let Some(cat) = &self.catalog {
+            // View and shared-table labels scan their backing source with filters inlined
+            let resolved = self.resolve_node_source(label)?;
+
             // Catalog exists - check if label is registered
-            if let Some(source) = cat.node_source(label) {
+            if let Some(source) = cat.node_source(&resolved.source_name) {
                 // Get schema before moving source
                 let schema = source.schema();
+                let provenance_source = source.clone();
                 // Normalize label for table scan (case-insensitive)
                 let normalized_label = label.to_lowercase();
                 let mut builder = LogicalPlanBuilder::scan(&normalized_label, source, None)
@@ -39,6 +47,12 @@ impl DataFusionPlanner {
                         self.plan_error(&format!("Failed to scan node source '{}'", label), e)
                     })?;
 
+                if let Some(label_filter) = resolved.filter.clone() {
+                    builder = builder
+                        .filter(label_filter)
+                        .map_err(|e| self.plan_error("Failed to apply label filter", e))?;
+                }
+
                 // Combine property filters into single predicate for efficiency
                 if !properties.is_empty() {
                     let filter_exprs: Vec<Expr> = properties
@@ -74,14 +88,18 @@ impl DataFusionPlanner {
 
                 // Create qualified column aliases: variable__property
                 // Normalize both variable and field names for case-insensitive behavior
-                let qualified_exprs: Vec<Expr> = schema
+                let mut qualified_exprs: Vec<Expr> = schema
                     .fields()
                     .iter()
+                    .filter(|field| resolved.exposes(field.name()))
                     .map(|field| {
                         let qualified_name = qualify_column(variable, field.name());
                         col(field.name()).alias(&qualified_name)
                     })
                     .collect();
+                qualified_exprs.extend(
+                    self.provenance_exprs(variable, &provenance_source, |c| resolved.exposes(c)),
+                );
 
                 // Add projection with qualified aliases
                 builder = builder
@@ -127,6 +145,7 @@ impl DataFusionPlanner {
         relationship_properties: &HashMap<String, PropertyValue>,
     ) -> Result<LogicalPlan> {
         let rel_schema = rel_source.schema();
+        let provenance = self.provenance_exprs(&rel_instance.alias, &rel_source, |_| true);
         let normalized_rel_type = rel_instance.rel_type.to_lowercase();
         let mut rel_builder = LogicalPlanBuilder::scan(&normalized_rel_type, rel_source, None)
             .map_err(|e| {
@@ -136,6 +155,17 @@ impl DataFusionPlanner {
                 )
             })?;
 
+        // Hide soft-deleted edges unless the query includes deleted rows
+        let soft_delete_column = self
+            .config
+            .get_relationship_mapping(&rel_instance.rel_type)
+            .and_then(|m| m.soft_delete_column.as_deref());
+        if let Some(live) = self.soft_delete_filter(soft_delete_column) {
+            rel_builder = rel_builder
+                .filter(live)
+                .map_err(|e| self.plan_error("Failed to filter soft-deleted edges", e))?;
+        }
+
         // Apply relationship property filters (e.g., -[r {since: 2020}]->)
         for (k, v) in relationship_properties.iter() {
             let lit_expr = super::expression::to_df_value_expr(
@@ -155,7 +185,7 @@ impl DataFusionPlanner {
         }
 
         // Use unique alias from rel_instance to avoid column conflicts
-        let rel_qualified_exprs: Vec<Expr> = rel_schema
+        let mut rel_qualified_exprs: Vec<Expr> = rel_schema
             .fields()
             .iter()
             .map(|field| {
@@ -163,6 +193,7 @@ impl DataFusionPlanner {
                 col(field.name()).alias(&qualified_name)
             })
             .collect();
+        rel_qualified_exprs.extend(provenance);
 
         rel_builder
             .project(rel_qualified_exprs)
@@ -190,8 +221,12 @@ impl DataFusionPlanner {
 
         // Get source node label and schema
         if let Some(source_label) = ctx.analysis.var_to_label.get(source_variable) {
-            if let Some(source) = cat.node_source(source_label) {
+            let resolved = self.resolve_node_source(source_label)?;
+            if let Some(source) = cat.node_source(&resolved.source_name) {
                 for field in source.schema().fields() {
+                    if !resolved.exposes(field.name()) {
+                        continue;
+                    }
                     expected.insert(qualify_column(source_variable, field.name()));
                 }
             }
@@ -199,8 +234,12 @@ impl DataFusionPlanner {
 
         // Get target node label and schema
         if let Some(target_label) = ctx.analysis.var_to_label.get(target_variable) {
-            if let Some(target) = cat.node_source(target_label) {
+            let resolved = self.resolve_node_source(target_label)?;
+            if let Some(target) = cat.node_source(&resolved.source_name) {
                 for field in target.schema().fields() {
+                    if !resolved.exposes(field.name()) {
+                        continue;
+                    }
                     expected.insert(qualify_column(target_variable, field.name()));
                 }
             }
@@ -226,15 +265,26 @@ impl DataFusionPlanner {
             })?;
 
         let rel_schema = rel_source.schema();
+        let rel_alias_lower = rel_instance.alias.to_lowercase();
+        let provenance = self.provenance_exprs(&rel_alias_lower, &rel_source, |_| true);
         let normalized_rel_type = rel_instance.rel_type.to_lowercase();
-        let rel_builder = LogicalPlanBuilder::scan(&normalized_rel_type, rel_source, None)
+        let mut rel_builder = LogicalPlanBuilder::scan(&normalized_rel_type, rel_source, None)
             .map_err(|e| crate::error::GraphError::PlanError {
                 message: format!("Failed to scan relationship: {}", e),
                 location: snafu::Location::new(file!(), line!(), column!()),
             })?;
 
-        let rel_alias_lower = rel_instance.alias.to_lowercase();
-        let rel_qualified_exprs: Vec<Expr> = rel_schema
+        let soft_delete_column = self
+            .config
+            .get_relationship_mapping(&rel_instance.rel_type)
+            .and_then(|m| m.soft_delete_column.as_deref());
+        if let Some(live) = self.soft_delete_filter(soft_delete_column) {
+            rel_builder = rel_builder
+                .filter(live)
+                .map_err(|e| self.plan_error("Failed to filter soft-deleted edges", e))?;
+        }
+
+        let mut rel_qualified_exprs: Vec<Expr> = rel_schema
             .fields()
             .iter()
             .map(|field| {
@@ -242,6 +292,7 @@ impl DataFusionPlanner {
                 col(field.name()).alias(&qualified_name)
             })
             .collect();
+        rel_qualified_exprs.extend(provenance);
 
         rel_builder
             .project(rel_qualified_exprs)
@@ -264,14 +315,18 @@ impl DataFusionPlanner {
         target_variable: &str,
         target_properties: &HashMap<String, PropertyValue>,
     ) -> Result<LogicalPlan> {
-        let target_source = catalog.node_source(target_label).ok_or_else(|| {
+        let resolved = self.resolve_node_source(target_label)?;
+        let target_source = catalog.node_source(&resolved.source_name).ok_or_else(|| {
             crate::error::GraphError::ConfigError {
                 message: format!("No table source found for node label: {}", target_label),
                 location: snafu::Location::new(file!(), line!(), column!()),
             }
         })?;
 
+        let target_var_lower = target_variable.to_lowercase();
         let target_schema = target_source.schema();
+        let provenance =
+            self.provenance_exprs(&target_var_lower, &target_source, |c| resolved.exposes(c));
         let normalized_target_label = target_label.to_lowercase();
         let mut target_builder =
             LogicalPlanBuilder::scan(&normalized_target_label, target_source, None).map_err(
@@ -281,6 +336,15 @@ impl DataFusionPlanner {
                 },
             )?;
 
+        if let Some(label_filter) = resolved.filter.clone() {
+            target_builder = target_builder.filter(label_filter).map_err(|e| {
+                crate::error::GraphError::PlanError {
+                    message: format!("Failed to apply label filter: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                }
+            })?;
+        }
+
         // Apply target property filters
         for (k, v) in target_properties.iter() {
             let lit_expr = super::expression::to_df_value_expr(
@@ -299,15 +363,16 @@ impl DataFusionPlanner {
             })?;
         }
 
-        let target_var_lower = target_variable.to_lowercase();
-        let target_qualified_exprs: Vec<Expr> = target_schema
+        let mut target_qualified_exprs: Vec<Expr> = target_schema
             .fields()
             .iter()
+            .filter(|field| resolved.exposes(field.name()))
             .map(|field| {
                 let qualified_name = qualify_column(&target_var_lower, field.name());
                 col(field.name()).alias(&qualified_name)
             })
             .collect();
+        target_qualified_exprs.extend(provenance);
 
         target_builder
             .project(target_qualified_exprs)
@@ -321,6 +386,46 @@ impl DataFusionPlanner {
                 location: snafu::Location::new(file!(), line!(), column!()),
             })
     }
+
+    /// Provenance columns for a scan of `source` qualified by `qualifier`
+    ///
+    /// Empty unless provenance is enabled and the source is a Lance dataset.
+    /// `_rowid` is only added when the scan does not already expose it.
+    pub(crate) fn provenance_exprs(
+        &self,
+        qualifier: &str,
+        source: &Arc<dyn TableSource>,
+        exposes: impl Fn(&str) -> bool,
+    ) -> Vec<Expr> {
+        if !self.include_provenance {
+            return Vec::new();
+        }
+        let Ok(provider) = source_as_provider(source) else {
+            return Vec::new();
+        };
+        let Some(lance) = provider.as_any().downcast_ref::<LanceTableProvider>() else {
+            return Vec::new();
+        };
+        let dataset = lance.dataset();
+        let schema = source.schema();
+
+        let mut exprs =
+            vec![lit(dataset.uri().to_string()).alias(qualify_column(qualifier, "_dataset"))];
+        if schema.field_with_name("_rowaddr").is_ok() {
+            // Row addresses pack the fragment id into their upper 32 bits
+            let fragment = Expr::BinaryExpr(BinaryExpr {
+                left: Box::new(col("_rowaddr")),
+                op: Operator::BitwiseShiftRight,
+                right: Box::new(lit(32u64)),
+            });
+            exprs.push(fragment.alias(qualify_column(qualifier, "_fragment")));
+        }
+        if schema.field_with_name("_rowid").is_ok() && !exposes("_rowid") {
+            exprs.push(col("_rowid").alias(qualify_column(qualifier, "_rowid")));
+        }
+        exprs.push(lit(dataset.version().version).alias(qualify_column(qualifier, "_version")));
+        exprs
+    }
 }
 
 #[cfg(test)]
@@ -416,6 +521,7 @@ mod tests {
             max_length: Some(2),
             relationship_variable: None,
             target_properties: Default::default(),
+            path_filter: Default::default(),
         };
 
         let result = planner.plan(&var_expand);
@@ -531,4 +637,120 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_view_label_scans_base_source_with_filter() {
+        // MATCH (n:Adult) where Adult = Person WHERE age >= 18
+        let cfg = crate::config::GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_view_label("Adult", "Person", "age >= 18")
+            .build()
+            .unwrap();
+        let scan = LogicalOperator::ScanByLabel {
+            variable: "n".to_string(),
+            label: "Adult".to_string(),
+            properties: Default::default(),
+        };
+
+        let planner = DataFusionPlanner::with_catalog(cfg, make_catalog());
+        let df_plan = planner.plan(&scan).unwrap();
+
+        let s = format!("{:?}", df_plan);
+        assert!(s.contains("Filter"), "plan missing view Filter: {}", s);
+        assert!(s.contains("age"), "plan missing view predicate: {}", s);
+        assert!(
+            s.contains("n__name"),
+            "plan missing qualified columns: {}",
+            s
+        );
+    }
+
+    #[test]
+    fn test_shared_table_label_filters_discriminator_and_columns() {
+        use arrow_schema::{DataType, Field, Schema};
+        use lance_graph_catalog::{InMemoryCatalog, SimpleTableSource};
+        use std::sync::Arc;
+
+        // Person and Company rows both live in a single "nodes" table
+        let nodes_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("industry", DataType::Utf8, true),
+        ]));
+        let catalog = Arc::new(
+            InMemoryCatalog::new()
+                .with_node_source("nodes", Arc::new(SimpleTableSource::new(nodes_schema))),
+        );
+        let cfg = crate::config::GraphConfig::builder()
+            .with_node_mapping(
+                crate::config::NodeMapping::new("Person", "id")
+                    .with_label_column("nodes", "kind")
+                    .with_properties(vec!["name".to_string()]),
+            )
+            .with_node_mapping(
+                crate::config::NodeMapping::new("Company", "id")
+                    .with_label_column("nodes", "kind")
+                    .with_properties(vec!["name".to_string(), "industry".to_string()]),
+            )
+            .build()
+            .unwrap();
+
+        let planner = DataFusionPlanner::with_catalog(cfg, catalog);
+        let df_plan = planner.plan(&person_scan("p")).unwrap();
+
+        let s = format!("{:?}", df_plan);
+        assert!(
+            s.contains("kind"),
+            "plan missing discriminator filter: {}",
+            s
+        );
+        assert!(s.contains("Person"), "plan missing label value: {}", s);
+        assert!(s.contains("p__name"), "plan missing property column: {}", s);
+        assert!(
+            !s.contains("p__industry"),
+            "plan exposes another label's column: {}",
+            s
+        );
+    }
+
+    #[test]
+    fn test_soft_delete_column_filtered_unless_included() {
+        use arrow_schema::{DataType, Field, Schema};
+        use lance_graph_catalog::{InMemoryCatalog, SimpleTableSource};
+        use std::sync::Arc;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("deleted_at", DataType::Int64, true),
+        ]));
+        let catalog = Arc::new(
+            InMemoryCatalog::new()
+                .with_node_source("Person", Arc::new(SimpleTableSource::new(schema))),
+        );
+        let cfg = crate::config::GraphConfig::builder()
+            .with_node_mapping(
+                crate::config::NodeMapping::new("Person", "id")
+                    .with_soft_delete_column("deleted_at"),
+            )
+            .build()
+            .unwrap();
+
+        let planner = DataFusionPlanner::with_catalog(cfg.clone(), catalog.clone());
+        let s = format!("{:?}", planner.plan(&person_scan("p")).unwrap());
+        assert!(
+            s.contains("deleted_at IS NULL"),
+            "plan missing soft-delete filter: {}",
+            s
+        );
+
+        let planner = DataFusionPlanner::with_catalog(cfg, catalog).with_include_deleted(true);
+        let s = format!("{:?}", planner.plan(&person_scan("p")).unwrap());
+        assert!(
+            !s.contains("IS NULL"),
+            "INCLUDE DELETED plan still filters: {}",
+            s
+        );
+    }
 }
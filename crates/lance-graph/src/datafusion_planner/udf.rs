@@ -3,15 +3,28 @@
 
 //! User-Defined Functions (UDFs) for DataFusion
 //!
-//! This module contains UDF implementations for vector operations used in graph queries.
-
-use crate::ast::DistanceMetric;
-use crate::datafusion_planner::vector_ops;
-use arrow::array::ArrayRef;
-use arrow::datatypes::DataType;
-use datafusion::logical_expr::{ScalarUDF, Signature, Volatility};
+//! This module contains UDF implementations for vector operations used in graph queries,
+//! for the `rand()` / `randomUUID()` functions, for variable-length path limits,
+//! for counting the rows `WHERE` predicates keep, for integer arithmetic that
+//! detects overflow, for Unicode-aware text functions, for list functions,
+//! for `=~` regular expression matching, for list comprehensions and list
+//! predicates, for `reduce()` and for the percentiles of collected values.
+
+use crate::ast::{DistanceMetric, ListQuantifier};
+use crate::cost::PredicateCounters;
+use crate::datafusion_planner::{vector_ops, OverflowMode};
+use arrow::array::{
+    Array, ArrayRef, ArrowNativeTypeOp, AsArray, BooleanArray, Float64Array, PrimitiveArray,
+    StringArray,
+};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Field, Int16Type, Int32Type, Int64Type, Int8Type, Schema,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use datafusion::logical_expr::{Operator, ScalarUDF, Signature, Volatility};
 use datafusion::physical_plan::ColumnarValue;
-use std::sync::{Arc, LazyLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
 
 /// Type alias for UDF function closures
 type UdfFunc =
@@ -452,6 +465,1363 @@ impl std::hash::Hash for VectorSimilarityUDF {
     }
 }
 
+/// Values produced by [`RandomUDF`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RandomKind {
+    /// `rand()`: Float64 uniformly distributed in `[0, 1)`
+    Float,
+    /// `randomUUID()`: version 4 UUID string
+    Uuid,
+}
+
+/// UDF implementation for `rand()` and `randomUUID()`
+///
+/// Draws from a SplitMix64 generator. A seeded instance yields the same
+/// sequence for the same input rows in the same order, so results are
+/// reproducible as long as the data and its partitioning do not change.
+struct RandomUDF {
+    kind: RandomKind,
+    seed: Option<u64>,
+    state: Mutex<u64>,
+    signature: Signature,
+}
+
+impl RandomUDF {
+    fn next_u64(state: &mut u64) -> u64 {
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn uuid(state: &mut u64) -> String {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&Self::next_u64(state).to_be_bytes());
+        bytes[8..].copy_from_slice(&Self::next_u64(state).to_be_bytes());
+        // Version 4, RFC 4122 variant
+        bytes[6] = (bytes[6] & 0x0F) | 0x40;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+impl std::fmt::Debug for RandomUDF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RandomUDF")
+            .field("kind", &self.kind)
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for RandomUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            RandomKind::Float => "rand",
+            RandomKind::Uuid => "randomuuid",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(match self.kind {
+            RandomKind::Float => DataType::Float64,
+            RandomKind::Uuid => DataType::Utf8,
+        })
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let array: ArrayRef = match self.kind {
+            // 53 random bits scaled into [0, 1)
+            RandomKind::Float => Arc::new(Float64Array::from_iter_values(
+                (0..args.number_rows)
+                    .map(|_| (Self::next_u64(&mut state) >> 11) as f64 / (1u64 << 53) as f64),
+            )),
+            RandomKind::Uuid => Arc::new(StringArray::from_iter_values(
+                (0..args.number_rows).map(|_| Self::uuid(&mut state)),
+            )),
+        };
+        Ok(ColumnarValue::Array(array))
+    }
+}
+
+impl PartialEq for RandomUDF {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.seed == other.seed
+    }
+}
+
+impl Eq for RandomUDF {}
+
+impl std::hash::Hash for RandomUDF {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.seed.hash(state);
+    }
+}
+
+/// Create a `rand()` / `randomUUID()` UDF, seeded or seeded from the OS
+pub(crate) fn create_random_udf(kind: RandomKind, seed: Option<u64>) -> Arc<ScalarUDF> {
+    use std::hash::{BuildHasher, Hasher};
+    let state = seed.unwrap_or_else(|| {
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    });
+    Arc::new(ScalarUDF::new_from_impl(RandomUDF {
+        kind,
+        seed,
+        state: Mutex::new(state),
+        signature: Signature::nullary(Volatility::Volatile),
+    }))
+}
+
+/// Kind of an unseeded random UDF, which a per-query seed replaces
+pub(crate) fn unseeded_random_kind(udf: &ScalarUDF) -> Option<RandomKind> {
+    udf.inner()
+        .as_any()
+        .downcast_ref::<RandomUDF>()
+        .filter(|r| r.seed.is_none())
+        .map(|r| r.kind)
+}
+
+/// UDF implementation of a path limit: `rank <= limit`
+///
+/// Raises `truncated` when any row is over the limit, which is how a capped
+/// expansion tells the caller that it dropped paths. Volatile so the
+/// optimizer keeps it as a separate filter above the ranking window.
+struct PathLimitUDF {
+    truncated: Arc<AtomicBool>,
+    signature: Signature,
+}
+
+impl std::fmt::Debug for PathLimitUDF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PathLimitUDF").finish()
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for PathLimitUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "path_limit"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(&args.args)?;
+        let ranks = arrays[0].as_primitive::<UInt64Type>();
+        let limits = arrays[1].as_primitive::<UInt64Type>();
+        let within: BooleanArray = ranks
+            .iter()
+            .zip(limits.iter())
+            .map(|(rank, limit)| Some(rank.zip(limit).is_none_or(|(r, l)| r <= l)))
+            .collect();
+        if within.false_count() > 0 {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        Ok(ColumnarValue::Array(Arc::new(within)))
+    }
+}
+
+impl PartialEq for PathLimitUDF {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.truncated, &other.truncated)
+    }
+}
+
+impl Eq for PathLimitUDF {}
+
+impl std::hash::Hash for PathLimitUDF {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.truncated).hash(state);
+    }
+}
+
+/// Create a `path_limit(rank, limit)` UDF that raises `truncated` on overflow
+pub(crate) fn create_path_limit_udf(truncated: Arc<AtomicBool>) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(PathLimitUDF {
+        truncated,
+        signature: Signature::exact(
+            vec![DataType::UInt64, DataType::UInt64],
+            Volatility::Volatile,
+        ),
+    }))
+}
+
+/// UDF implementation of an observed predicate: returns its boolean argument
+///
+/// Counts the rows it sees, and how many are true and null, into `counters`.
+/// Volatile so the optimizer neither pushes it into scans nor folds it away.
+struct ObservedPredicateUDF {
+    counters: Arc<PredicateCounters>,
+    signature: Signature,
+}
+
+impl std::fmt::Debug for ObservedPredicateUDF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObservedPredicateUDF").finish()
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for ObservedPredicateUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "observe_predicate"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let values = args.args[0].to_array(args.number_rows)?;
+        self.counters.add(values.as_boolean());
+        Ok(ColumnarValue::Array(values))
+    }
+}
+
+impl PartialEq for ObservedPredicateUDF {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.counters, &other.counters)
+    }
+}
+
+impl Eq for ObservedPredicateUDF {}
+
+impl std::hash::Hash for ObservedPredicateUDF {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.counters).hash(state);
+    }
+}
+
+/// Create an `observe_predicate(condition)` UDF counting into `counters`
+pub(crate) fn create_observed_predicate_udf(counters: Arc<PredicateCounters>) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(ObservedPredicateUDF {
+        counters,
+        signature: Signature::exact(vec![DataType::Boolean], Volatility::Volatile),
+    }))
+}
+
+/// UDF implementation of integer `+`, `-` or `*` that does not wrap on
+/// overflow, but fails or saturates as `mode` says
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct CheckedArithmeticUDF {
+    op: Operator,
+    mode: OverflowMode,
+    name: String,
+    signature: Signature,
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for CheckedArithmeticUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(arg_types[0].clone())
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(&args.args)?;
+        Ok(ColumnarValue::Array(self.evaluate(&arrays[0], &arrays[1])?))
+    }
+}
+
+impl CheckedArithmeticUDF {
+    fn evaluate(&self, left: &ArrayRef, right: &ArrayRef) -> datafusion::error::Result<ArrayRef> {
+        use arrow::compute::kernels::numeric;
+
+        Ok(match self.mode {
+            OverflowMode::Error => match self.op {
+                Operator::Plus => numeric::add(left, right),
+                Operator::Minus => numeric::sub(left, right),
+                _ => numeric::mul(left, right),
+            }
+            .map_err(|e| {
+                datafusion::error::DataFusionError::Execution(format!(
+                    "Integer overflow evaluating '{}': {}",
+                    self.op, e
+                ))
+            })?,
+            OverflowMode::Wrap => match self.op {
+                Operator::Plus => numeric::add_wrapping(left, right)?,
+                Operator::Minus => numeric::sub_wrapping(left, right)?,
+                _ => numeric::mul_wrapping(left, right)?,
+            },
+            OverflowMode::Saturate => {
+                macro_rules! saturate {
+                    ($t:ty) => {
+                        Arc::new(saturating::<$t>(
+                            self.op,
+                            left.as_primitive::<$t>(),
+                            right.as_primitive::<$t>(),
+                        )?) as ArrayRef
+                    };
+                }
+                match left.data_type() {
+                    DataType::Int8 => saturate!(Int8Type),
+                    DataType::Int16 => saturate!(Int16Type),
+                    DataType::Int32 => saturate!(Int32Type),
+                    DataType::Int64 => saturate!(Int64Type),
+                    DataType::UInt8 => saturate!(UInt8Type),
+                    DataType::UInt16 => saturate!(UInt16Type),
+                    DataType::UInt32 => saturate!(UInt32Type),
+                    DataType::UInt64 => saturate!(UInt64Type),
+                    other => {
+                        return Err(datafusion::error::DataFusionError::Execution(format!(
+                            "Saturating arithmetic on {} values",
+                            other
+                        )))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// `left op right`, clamped to the range of `T` instead of overflowing
+fn saturating<T: ArrowPrimitiveType>(
+    op: Operator,
+    left: &PrimitiveArray<T>,
+    right: &PrimitiveArray<T>,
+) -> std::result::Result<PrimitiveArray<T>, arrow::error::ArrowError> {
+    let zero = T::Native::ZERO;
+    arrow::compute::binary(left, right, |a, b| {
+        // On overflow the exact result lies past the bound on this side
+        let (result, above) = match op {
+            Operator::Plus => (a.add_checked(b), b.is_gt(zero)),
+            Operator::Minus => (a.sub_checked(b), b.is_lt(zero)),
+            _ => (a.mul_checked(b), a.is_lt(zero) == b.is_lt(zero)),
+        };
+        result.unwrap_or(if above {
+            T::Native::MAX_TOTAL_ORDER
+        } else {
+            T::Native::MIN_TOTAL_ORDER
+        })
+    })
+}
+
+/// Create a UDF computing `op` (`+`, `-` or `*`) on two `data_type` integers
+/// with overflow handled by `mode`
+pub(crate) fn create_checked_arithmetic_udf(
+    op: Operator,
+    mode: OverflowMode,
+    data_type: DataType,
+) -> Arc<ScalarUDF> {
+    let name = match op {
+        Operator::Plus => "checked_add",
+        Operator::Minus => "checked_sub",
+        _ => "checked_mul",
+    };
+    Arc::new(ScalarUDF::new_from_impl(CheckedArithmeticUDF {
+        op,
+        mode,
+        name: name.to_string(),
+        signature: Signature::exact(vec![data_type.clone(), data_type], Volatility::Immutable),
+    }))
+}
+
+/// Text function with Unicode semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TextKind {
+    /// Length in grapheme clusters with the `icu` feature, in characters
+    /// (code points) otherwise; never in bytes
+    Length,
+    /// Unicode normalization form C
+    #[cfg(feature = "icu")]
+    Nfc,
+}
+
+/// UDF implementation of a [`TextKind`] function of one string
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TextUDF {
+    kind: TextKind,
+    signature: Signature,
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for TextUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            TextKind::Length => "text_length",
+            #[cfg(feature = "icu")]
+            TextKind::Nfc => "nfc",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(match self.kind {
+            TextKind::Length => DataType::Int64,
+            #[cfg(feature = "icu")]
+            TextKind::Nfc => DataType::Utf8,
+        })
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let values = args.args[0].to_array(args.number_rows)?;
+        let strings = values.as_string::<i32>();
+        let result: ArrayRef = match self.kind {
+            TextKind::Length => Arc::new(
+                strings
+                    .iter()
+                    .map(|s| s.map(text_length))
+                    .collect::<arrow::array::Int64Array>(),
+            ),
+            #[cfg(feature = "icu")]
+            TextKind::Nfc => {
+                use unicode_normalization::UnicodeNormalization;
+                Arc::new(
+                    strings
+                        .iter()
+                        .map(|s| s.map(|s| s.nfc().collect::<String>()))
+                        .collect::<StringArray>(),
+                )
+            }
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+/// Length of `s` in grapheme clusters (`icu` feature) or characters
+fn text_length(s: &str) -> i64 {
+    #[cfg(feature = "icu")]
+    let length = unicode_segmentation::UnicodeSegmentation::graphemes(s, true).count();
+    #[cfg(not(feature = "icu"))]
+    let length = s.chars().count();
+    length as i64
+}
+
+/// Create the `kind` text function of one string
+pub(crate) fn create_text_udf(kind: TextKind) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(TextUDF {
+        kind,
+        signature: Signature::uniform(1, vec![DataType::Utf8], Volatility::Immutable),
+    }))
+}
+
+/// Function of one list; `size` and `reverse` also take a string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ListKind {
+    /// Number of items, or of characters like `length`
+    Size,
+    /// First item, NULL for an empty list
+    Head,
+    /// Last item, NULL for an empty list
+    Last,
+    /// All items but the first
+    Tail,
+    /// Items, or characters, in reverse order
+    Reverse,
+}
+
+/// UDF implementation of a [`ListKind`] function
+///
+/// Lists of every kind come back as `List`; values that are neither lists
+/// nor strings give NULL.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ListUDF {
+    kind: ListKind,
+    signature: Signature,
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for ListUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            ListKind::Size => "list_size",
+            ListKind::Head => "list_head",
+            ListKind::Last => "list_last",
+            ListKind::Tail => "list_tail",
+            ListKind::Reverse => "list_reverse",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(match (self.kind, &arg_types[0]) {
+            (ListKind::Size, _) => DataType::Int64,
+            (
+                ListKind::Head | ListKind::Last,
+                DataType::List(field)
+                | DataType::LargeList(field)
+                | DataType::FixedSizeList(field, _),
+            ) => field.data_type().clone(),
+            (
+                ListKind::Tail | ListKind::Reverse,
+                DataType::List(field)
+                | DataType::LargeList(field)
+                | DataType::FixedSizeList(field, _),
+            ) => DataType::List(field.clone()),
+            (ListKind::Reverse, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View) => {
+                DataType::Utf8
+            }
+            _ => DataType::Null,
+        })
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let values = args.args[0].to_array(args.number_rows)?;
+        let result: ArrayRef = match (self.kind, values.data_type()) {
+            (
+                ListKind::Size | ListKind::Reverse,
+                DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View,
+            ) => {
+                let strings = arrow::compute::cast(&values, &DataType::Utf8)?;
+                let strings = strings.as_string::<i32>();
+                if self.kind == ListKind::Size {
+                    Arc::new(
+                        strings
+                            .iter()
+                            .map(|s| s.map(text_length))
+                            .collect::<arrow::array::Int64Array>(),
+                    )
+                } else {
+                    Arc::new(
+                        strings
+                            .iter()
+                            .map(|s| s.map(|s| s.chars().rev().collect::<String>()))
+                            .collect::<StringArray>(),
+                    )
+                }
+            }
+            (
+                _,
+                DataType::List(field)
+                | DataType::LargeList(field)
+                | DataType::FixedSizeList(field, _),
+            ) => {
+                let lists = arrow::compute::cast(&values, &DataType::List(field.clone()))?;
+                list_function(self.kind, field.clone(), lists.as_list::<i32>())?
+            }
+            (_, data_type) => {
+                arrow::array::new_null_array(&self.return_type(&[data_type.clone()])?, values.len())
+            }
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+/// Apply `kind` to each list, picking items out of the child values by index
+fn list_function(
+    kind: ListKind,
+    field: arrow::datatypes::FieldRef,
+    lists: &arrow::array::ListArray,
+) -> datafusion::error::Result<ArrayRef> {
+    use arrow::array::{Int64Array, ListArray, UInt64Array};
+    let offsets = lists.value_offsets();
+    let items = |row: usize| offsets[row] as u64..offsets[row + 1] as u64;
+    Ok(match kind {
+        ListKind::Size => Arc::new(
+            (0..lists.len())
+                .map(|row| lists.is_valid(row).then(|| lists.value_length(row) as i64))
+                .collect::<Int64Array>(),
+        ),
+        ListKind::Head | ListKind::Last => {
+            let indices: UInt64Array = (0..lists.len())
+                .map(|row| {
+                    let mut items = items(row).filter(|_| lists.is_valid(row));
+                    if kind == ListKind::Head {
+                        items.next()
+                    } else {
+                        items.next_back()
+                    }
+                })
+                .collect();
+            arrow::compute::take(lists.values(), &indices, None)?
+        }
+        ListKind::Tail | ListKind::Reverse => {
+            let mut indices = Vec::new();
+            let mut new_offsets = vec![0i32];
+            for row in 0..lists.len() {
+                if lists.is_valid(row) {
+                    if kind == ListKind::Tail {
+                        indices.extend(items(row).skip(1));
+                    } else {
+                        indices.extend(items(row).rev());
+                    }
+                }
+                new_offsets.push(indices.len() as i32);
+            }
+            let values = arrow::compute::take(lists.values(), &UInt64Array::from(indices), None)?;
+            Arc::new(ListArray::try_new(
+                field,
+                arrow::buffer::OffsetBuffer::new(new_offsets.into()),
+                values,
+                lists.nulls().cloned(),
+            )?)
+        }
+    })
+}
+
+/// Create the `kind` list function
+pub(crate) fn create_list_udf(kind: ListKind) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(ListUDF {
+        kind,
+        signature: Signature::any(1, Volatility::Immutable),
+    }))
+}
+
+/// UDF implementation of `=~`: whether each string matches a regular
+/// expression compiled once when the query is planned
+///
+/// Values that are not strings match nothing and give NULL, like NULLs.
+struct RegexMatchUDF {
+    regex: regex::Regex,
+    signature: Signature,
+}
+
+impl std::fmt::Debug for RegexMatchUDF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegexMatchUDF")
+            .field("regex", &self.regex.as_str())
+            .finish()
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for RegexMatchUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "regex_match"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let values = args.args[0].to_array(args.number_rows)?;
+        let is_match = |s: Option<&str>| s.map(|s| self.regex.is_match(s));
+        let result: BooleanArray = match values.data_type() {
+            DataType::Utf8 => values.as_string::<i32>().iter().map(is_match).collect(),
+            DataType::LargeUtf8 => values.as_string::<i64>().iter().map(is_match).collect(),
+            DataType::Utf8View => values.as_string_view().iter().map(is_match).collect(),
+            _ => BooleanArray::new_null(values.len()),
+        };
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+}
+
+impl PartialEq for RegexMatchUDF {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Eq for RegexMatchUDF {}
+
+impl std::hash::Hash for RegexMatchUDF {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.regex.as_str().hash(state);
+    }
+}
+
+/// Create the `=~` matcher of `regex`
+pub(crate) fn create_regex_match_udf(regex: regex::Regex) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(RegexMatchUDF {
+        regex,
+        signature: Signature::any(1, Volatility::Immutable),
+    }))
+}
+
+/// UDF implementation of a list comprehension,
+/// `[variable IN list WHERE predicate | projection]`
+///
+/// The predicate and projection are expressions over a column named after the
+/// element variable and the `outer` columns of the row each list belongs to,
+/// which follow the list as arguments. A call flattens its lists into one
+/// batch of elements, evaluates both expressions over it and regroups the
+/// projections of the kept elements by row.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ListComprehensionUDF {
+    variable: String,
+    predicate: Option<datafusion::logical_expr::Expr>,
+    projection: Option<datafusion::logical_expr::Expr>,
+    outer: Vec<String>,
+    signature: Signature,
+}
+
+/// Physical predicate and projection of a [`ListComprehensionUDF`]
+type CompiledComprehension = (
+    Option<Arc<dyn datafusion::physical_expr::PhysicalExpr>>,
+    Option<Arc<dyn datafusion::physical_expr::PhysicalExpr>>,
+);
+
+impl ListComprehensionUDF {
+    /// Schema of the element batches for the argument types
+    fn element_schema(&self, arg_types: &[DataType]) -> datafusion::error::Result<Schema> {
+        let element = match &arg_types[0] {
+            DataType::List(field)
+            | DataType::LargeList(field)
+            | DataType::FixedSizeList(field, _) => field.data_type().clone(),
+            DataType::Null => DataType::Null,
+            other => {
+                return Err(datafusion::error::DataFusionError::Plan(format!(
+                    "List comprehension over '{}' needs a list, got {}",
+                    self.variable, other
+                )))
+            }
+        };
+        let fields: Vec<Field> = std::iter::once(Field::new(&self.variable, element, true))
+            .chain(
+                self.outer
+                    .iter()
+                    .zip(&arg_types[1..])
+                    .map(|(name, data_type)| Field::new(name, data_type.clone(), true)),
+            )
+            .collect();
+        Ok(Schema::new(fields))
+    }
+
+    /// Type-check and plan the predicate and projection over `schema`
+    fn compile(&self, schema: &Schema) -> datafusion::error::Result<CompiledComprehension> {
+        let df_schema = datafusion::common::DFSchema::try_from(schema.clone())?;
+        let ctx = datafusion::prelude::SessionContext::new();
+        let compile = |expr: &Option<datafusion::logical_expr::Expr>| {
+            expr.clone()
+                .map(|expr| ctx.create_physical_expr(expr, &df_schema))
+                .transpose()
+        };
+        Ok((compile(&self.predicate)?, compile(&self.projection)?))
+    }
+
+    /// Type of the list elements the comprehension produces
+    fn item_type(&self, schema: &Schema) -> datafusion::error::Result<DataType> {
+        match self.compile(schema)?.1 {
+            Some(projection) => projection.data_type(schema),
+            None => Ok(schema.field(0).data_type().clone()),
+        }
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for ListComprehensionUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "list_comprehension"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        let item = self.item_type(&self.element_schema(arg_types)?)?;
+        Ok(DataType::List(Arc::new(Field::new_list_field(item, true))))
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let arrays = args
+            .args
+            .iter()
+            .map(|arg| arg.to_array(args.number_rows))
+            .collect::<datafusion::error::Result<Vec<_>>>()?;
+        let arg_types: Vec<DataType> = arrays.iter().map(|a| a.data_type().clone()).collect();
+        let schema = Arc::new(self.element_schema(&arg_types)?);
+        let item = self.item_type(&schema)?;
+        if arrays[0].data_type() == &DataType::Null {
+            let field = Arc::new(Field::new_list_field(item, true));
+            return Ok(ColumnarValue::Array(arrow::array::new_null_array(
+                &DataType::List(field),
+                args.number_rows,
+            )));
+        }
+
+        // Every list as a List of the same elements
+        let element_type = schema.field(0).data_type().clone();
+        let lists = arrow::compute::cast(
+            &arrays[0],
+            &DataType::List(Arc::new(Field::new_list_field(element_type, true))),
+        )?;
+        let lists = lists.as_list::<i32>();
+
+        // One row per element, with the row of its list
+        let mut elements = Vec::new();
+        let mut rows = Vec::new();
+        for (row, window) in lists.offsets().windows(2).enumerate() {
+            if lists.is_valid(row) {
+                for element in window[0]..window[1] {
+                    elements.push(element as u32);
+                    rows.push(row as u32);
+                }
+            }
+        }
+        let elements = arrow::array::UInt32Array::from(elements);
+        let rows = arrow::array::UInt32Array::from(rows);
+        let mut columns = vec![arrow::compute::take(lists.values(), &elements, None)?];
+        for outer in &arrays[1..] {
+            columns.push(arrow::compute::take(outer, &rows, None)?);
+        }
+        let batch = arrow::record_batch::RecordBatch::try_new(schema.clone(), columns)?;
+
+        let (predicate, projection) = self.compile(&schema)?;
+        let values = match projection {
+            Some(projection) => projection.evaluate(&batch)?.into_array(batch.num_rows())?,
+            None => batch.column(0).clone(),
+        };
+        let (values, rows) = match predicate {
+            Some(predicate) => {
+                let keep = predicate.evaluate(&batch)?.into_array(batch.num_rows())?;
+                let keep = keep.as_boolean();
+                (
+                    arrow::compute::filter(&values, keep)?,
+                    arrow::compute::filter(&rows, keep)?,
+                )
+            }
+            None => (values, Arc::new(rows) as ArrayRef),
+        };
+
+        // Kept elements are in row order, so counting them per row gives the offsets
+        let mut lengths = vec![0usize; lists.len()];
+        for row in rows.as_primitive::<UInt32Type>().values() {
+            lengths[*row as usize] += 1;
+        }
+        let list = arrow::array::ListArray::try_new(
+            Arc::new(Field::new_list_field(item, true)),
+            arrow::buffer::OffsetBuffer::from_lengths(lengths),
+            values,
+            lists.nulls().cloned(),
+        )?;
+        Ok(ColumnarValue::Array(Arc::new(list)))
+    }
+}
+
+/// Create the UDF of a list comprehension over `variable`, called with the
+/// list followed by the `outer` columns its predicate and projection read
+pub(crate) fn create_list_comprehension_udf(
+    variable: String,
+    predicate: Option<datafusion::logical_expr::Expr>,
+    projection: Option<datafusion::logical_expr::Expr>,
+    outer: Vec<String>,
+) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(ListComprehensionUDF {
+        variable,
+        predicate,
+        projection,
+        outer,
+        signature: Signature::variadic_any(Volatility::Immutable),
+    }))
+}
+
+/// UDF implementation of the list predicates `all`, `any`, `none` and
+/// `single`, folding the list of the predicate's value for every element
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ListPredicateUDF {
+    quantifier: ListQuantifier,
+    signature: Signature,
+}
+
+impl ListPredicateUDF {
+    /// Outcome for a list with `trues` elements the predicate holds for,
+    /// `nulls` it is null for, out of `len`
+    fn fold(&self, trues: usize, nulls: usize, len: usize) -> Option<bool> {
+        // Null elements could turn either way, so only decide without them
+        // when they cannot change the outcome
+        match self.quantifier {
+            ListQuantifier::Any if trues > 0 => Some(true),
+            ListQuantifier::All if trues + nulls < len => Some(false),
+            ListQuantifier::None if trues > 0 => Some(false),
+            ListQuantifier::Single if trues > 1 => Some(false),
+            _ if nulls > 0 => None,
+            ListQuantifier::Any => Some(false),
+            ListQuantifier::All | ListQuantifier::None => Some(true),
+            ListQuantifier::Single => Some(trues == 1),
+        }
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for ListPredicateUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.quantifier {
+            ListQuantifier::All => "all",
+            ListQuantifier::Any => "any",
+            ListQuantifier::None => "none",
+            ListQuantifier::Single => "single",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let matches = args.args[0].to_array(args.number_rows)?;
+        if matches.data_type() == &DataType::Null {
+            return Ok(ColumnarValue::Array(arrow::array::new_null_array(
+                &DataType::Boolean,
+                args.number_rows,
+            )));
+        }
+        let matches = arrow::compute::cast(
+            &matches,
+            &DataType::List(Arc::new(Field::new_list_field(DataType::Boolean, true))),
+        )?;
+        let matches = matches.as_list::<i32>();
+        let values = matches.values().as_boolean();
+        let result: BooleanArray = matches
+            .offsets()
+            .windows(2)
+            .enumerate()
+            .map(|(row, window)| {
+                if matches.is_null(row) {
+                    return None;
+                }
+                let (start, end) = (window[0] as usize, window[1] as usize);
+                let nulls = (start..end).filter(|&i| values.is_null(i)).count();
+                let trues = (start..end)
+                    .filter(|&i| values.is_valid(i) && values.value(i))
+                    .count();
+                self.fold(trues, nulls, end - start)
+            })
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+}
+
+/// Create the UDF folding per-element predicate values by `quantifier`
+pub(crate) fn create_list_predicate_udf(quantifier: ListQuantifier) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(ListPredicateUDF {
+        quantifier,
+        signature: Signature::any(1, Volatility::Immutable),
+    }))
+}
+
+/// UDF implementation of a reduction,
+/// `reduce(accumulator = initial, variable IN list | expression)`
+///
+/// Called with the list, the initial value and the `outer` columns of the
+/// row each list belongs to. Each step evaluates the expression over one
+/// batch holding the accumulator, the next element and the outer columns of
+/// every row whose list has one more element, and its results replace those
+/// rows' accumulators.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ReduceUDF {
+    accumulator: String,
+    variable: String,
+    expression: datafusion::logical_expr::Expr,
+    outer: Vec<String>,
+    signature: Signature,
+}
+
+impl ReduceUDF {
+    /// Schema of the step batches for an accumulator of type `accumulator`
+    fn step_schema(
+        &self,
+        accumulator: DataType,
+        arg_types: &[DataType],
+    ) -> datafusion::error::Result<Schema> {
+        let element = match &arg_types[0] {
+            DataType::List(field)
+            | DataType::LargeList(field)
+            | DataType::FixedSizeList(field, _) => field.data_type().clone(),
+            DataType::Null => DataType::Null,
+            other => {
+                return Err(datafusion::error::DataFusionError::Plan(format!(
+                    "reduce() over '{}' needs a list, got {}",
+                    self.variable, other
+                )))
+            }
+        };
+        let fields: Vec<Field> = [
+            Field::new(&self.accumulator, accumulator, true),
+            Field::new(&self.variable, element, true),
+        ]
+        .into_iter()
+        .chain(
+            self.outer
+                .iter()
+                .zip(&arg_types[2..])
+                .map(|(name, data_type)| Field::new(name, data_type.clone(), true)),
+        )
+        .collect();
+        Ok(Schema::new(fields))
+    }
+
+    /// Type-check and plan the expression over `schema`
+    fn compile(
+        &self,
+        schema: &Schema,
+    ) -> datafusion::error::Result<Arc<dyn datafusion::physical_expr::PhysicalExpr>> {
+        let df_schema = datafusion::common::DFSchema::try_from(schema.clone())?;
+        let ctx = datafusion::prelude::SessionContext::new();
+        ctx.create_physical_expr(self.expression.clone(), &df_schema)
+    }
+
+    /// Type of the accumulator: the initial value's, widened to the
+    /// expression's when they differ (e.g. `acc = 0` summing floats)
+    fn accumulator_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        let mut accumulator = arg_types[1].clone();
+        for _ in 0..3 {
+            let schema = self.step_schema(accumulator.clone(), arg_types)?;
+            let folded = self.compile(&schema)?.data_type(&schema)?;
+            if folded == accumulator {
+                return Ok(accumulator);
+            }
+            accumulator = folded;
+        }
+        Err(datafusion::error::DataFusionError::Plan(format!(
+            "reduce() over '{}' changes the type of '{}' on every step",
+            self.variable, self.accumulator
+        )))
+    }
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for ReduceUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "reduce"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        self.accumulator_type(arg_types)
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let arrays = args
+            .args
+            .iter()
+            .map(|arg| arg.to_array(args.number_rows))
+            .collect::<datafusion::error::Result<Vec<_>>>()?;
+        let arg_types: Vec<DataType> = arrays.iter().map(|a| a.data_type().clone()).collect();
+        let accumulator_type = self.accumulator_type(&arg_types)?;
+        if arrays[0].data_type() == &DataType::Null {
+            return Ok(ColumnarValue::Array(arrow::array::new_null_array(
+                &accumulator_type,
+                args.number_rows,
+            )));
+        }
+        let schema = Arc::new(self.step_schema(accumulator_type.clone(), &arg_types)?);
+        let expression = self.compile(&schema)?;
+
+        // Every list as a List of the same elements
+        let element_type = schema.field(1).data_type().clone();
+        let lists = arrow::compute::cast(
+            &arrays[0],
+            &DataType::List(Arc::new(Field::new_list_field(element_type, true))),
+        )?;
+        let lists = lists.as_list::<i32>();
+
+        let mut accumulators = arrow::compute::cast(&arrays[1], &accumulator_type)?;
+        for position in 0.. {
+            // The rows whose lists have an element at `position`, and those elements
+            let mut rows = Vec::new();
+            let mut elements = Vec::new();
+            for (row, window) in lists.offsets().windows(2).enumerate() {
+                if lists.is_valid(row) && window[0] + position < window[1] {
+                    rows.push(row as u32);
+                    elements.push((window[0] + position) as u32);
+                }
+            }
+            if rows.is_empty() {
+                break;
+            }
+            let rows = arrow::array::UInt32Array::from(rows);
+            let elements = arrow::array::UInt32Array::from(elements);
+            let mut columns = vec![
+                arrow::compute::take(&accumulators, &rows, None)?,
+                arrow::compute::take(lists.values(), &elements, None)?,
+            ];
+            for outer in &arrays[2..] {
+                columns.push(arrow::compute::take(outer, &rows, None)?);
+            }
+            let batch = arrow::record_batch::RecordBatch::try_new(schema.clone(), columns)?;
+            let folded = expression.evaluate(&batch)?.into_array(batch.num_rows())?;
+            let folded = arrow::compute::cast(&folded, &accumulator_type)?;
+
+            // Stepped rows take their folded value, the others keep theirs
+            let mut indices: Vec<(usize, usize)> =
+                (0..accumulators.len()).map(|row| (0, row)).collect();
+            for (index, row) in rows.values().iter().enumerate() {
+                indices[*row as usize] = (1, index);
+            }
+            accumulators = arrow::compute::kernels::interleave::interleave(
+                &[accumulators.as_ref(), folded.as_ref()],
+                &indices,
+            )?;
+        }
+
+        // Null lists reduce to null
+        let null_lists = arrow::compute::kernels::boolean::is_null(&arrays[0])?;
+        let reduced = arrow::compute::kernels::nullif::nullif(&accumulators, &null_lists)?;
+        Ok(ColumnarValue::Array(reduced))
+    }
+}
+
+/// Create the UDF of a reduction over `variable` into `accumulator`, called
+/// with the list and the initial value followed by the `outer` columns its
+/// expression reads
+pub(crate) fn create_reduce_udf(
+    accumulator: String,
+    variable: String,
+    expression: datafusion::logical_expr::Expr,
+    outer: Vec<String>,
+) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(ReduceUDF {
+        accumulator,
+        variable,
+        expression,
+        outer,
+        signature: Signature::variadic_any(Volatility::Immutable),
+    }))
+}
+
+/// How `percentileCont` / `percentileDisc` pick the value at a percentile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PercentileKind {
+    /// Interpolated linearly between the two closest values, as Float64
+    Continuous,
+    /// The closest value at or above the percentile (nearest rank), in the
+    /// type of the values
+    Discrete,
+}
+
+/// UDF implementation of a [`PercentileKind`] percentile, called with a list
+/// of collected numbers and the percentile between 0.0 and 1.0
+///
+/// Empty and null lists give NULL.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct PercentileUDF {
+    kind: PercentileKind,
+    signature: Signature,
+}
+
+impl datafusion::logical_expr::ScalarUDFImpl for PercentileUDF {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            PercentileKind::Continuous => "percentile_cont",
+            PercentileKind::Discrete => "percentile_disc",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        let element = match &arg_types[0] {
+            DataType::List(field) => field.data_type().clone(),
+            DataType::Null => DataType::Null,
+            other => {
+                return Err(datafusion::error::DataFusionError::Plan(format!(
+                    "{} needs a list of numbers, got {}",
+                    self.name(),
+                    other
+                )))
+            }
+        };
+        if !element.is_numeric() && element != DataType::Null {
+            return Err(datafusion::error::DataFusionError::Plan(format!(
+                "{} needs numbers, got {}",
+                self.name(),
+                element
+            )));
+        }
+        Ok(match self.kind {
+            PercentileKind::Continuous => DataType::Float64,
+            PercentileKind::Discrete => element,
+        })
+    }
+
+    fn invoke_with_args(
+        &self,
+        args: datafusion::logical_expr::ScalarFunctionArgs,
+    ) -> datafusion::error::Result<ColumnarValue> {
+        let return_type = args.return_field.data_type().clone();
+        let lists = args.args[0].to_array(args.number_rows)?;
+        if lists.data_type() == &DataType::Null {
+            return Ok(ColumnarValue::Array(arrow::array::new_null_array(
+                &return_type,
+                args.number_rows,
+            )));
+        }
+        let lists = lists.as_list_opt::<i32>().ok_or_else(|| {
+            datafusion::error::DataFusionError::Execution(format!(
+                "{} needs a list of numbers, got {}",
+                self.name(),
+                lists.data_type()
+            ))
+        })?;
+        let percentiles = arrow::compute::cast(
+            &args.args[1].to_array(args.number_rows)?,
+            &DataType::Float64,
+        )?;
+        let percentiles = percentiles.as_primitive::<arrow::datatypes::Float64Type>();
+        let numbers = arrow::compute::cast(lists.values(), &DataType::Float64)?;
+        let numbers = numbers.as_primitive::<arrow::datatypes::Float64Type>();
+
+        // Per row, the positions in `lists.values()` of its non-null values
+        // in ascending order
+        let sorted = |row: usize| -> datafusion::error::Result<Vec<u32>> {
+            let (start, end) = (
+                lists.value_offsets()[row] as usize,
+                lists.value_offsets()[row + 1] as usize,
+            );
+            let values = numbers.slice(start, end - start);
+            let order = arrow::compute::sort_to_indices(&values, None, None)?;
+            Ok(order
+                .values()
+                .iter()
+                .filter(|&&i| values.is_valid(i as usize))
+                .map(|&i| start as u32 + i)
+                .collect())
+        };
+
+        let result: ArrayRef = match self.kind {
+            PercentileKind::Continuous => {
+                let mut result = arrow::array::Float64Builder::with_capacity(lists.len());
+                for row in 0..lists.len() {
+                    let order = sorted(row)?;
+                    if lists.is_null(row) || percentiles.is_null(row) || order.is_empty() {
+                        result.append_null();
+                        continue;
+                    }
+                    let position = percentiles.value(row) * (order.len() - 1) as f64;
+                    let (lower, upper) = (position.floor(), position.ceil());
+                    let low = numbers.value(order[lower as usize] as usize);
+                    let high = numbers.value(order[upper as usize] as usize);
+                    result.append_value(low + (high - low) * (position - lower));
+                }
+                Arc::new(result.finish())
+            }
+            PercentileKind::Discrete => {
+                let mut picks = arrow::array::UInt32Builder::with_capacity(lists.len());
+                for row in 0..lists.len() {
+                    let order = sorted(row)?;
+                    if lists.is_null(row) || percentiles.is_null(row) || order.is_empty() {
+                        picks.append_null();
+                        continue;
+                    }
+                    let rank = (percentiles.value(row) * order.len() as f64).ceil() as usize;
+                    picks.append_value(order[rank.clamp(1, order.len()) - 1]);
+                }
+                arrow::compute::take(lists.values(), &picks.finish(), None)?
+            }
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+/// Create the `kind` percentile of a list of numbers
+pub(crate) fn create_percentile_udf(kind: PercentileKind) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(PercentileUDF {
+        kind,
+        signature: Signature::any(2, Volatility::Immutable),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -737,4 +2107,86 @@ mod tests {
             panic!("Expected array result");
         }
     }
+
+    #[test]
+    fn test_random_udf_is_reproducible_per_seed() {
+        let draw = |seed: u64| {
+            let mut state = seed;
+            (0..4)
+                .map(|_| RandomUDF::next_u64(&mut state))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+
+        let mut state = 7;
+        let uuid = RandomUDF::uuid(&mut state);
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4", "version nibble: {}", uuid);
+        assert!("89ab".contains(&uuid[19..20]), "variant nibble: {}", uuid);
+    }
+
+    #[test]
+    fn test_unseeded_random_kind() {
+        let unseeded = create_random_udf(RandomKind::Uuid, None);
+        let seeded = create_random_udf(RandomKind::Float, Some(1));
+        assert_eq!(unseeded_random_kind(&unseeded), Some(RandomKind::Uuid));
+        assert_eq!(unseeded_random_kind(&seeded), None);
+    }
+
+    #[test]
+    fn test_checked_arithmetic_overflow_modes() {
+        use arrow::array::Int64Array;
+
+        let evaluate = |op: Operator, mode: OverflowMode, left: i64, right: i64| {
+            let udf = CheckedArithmeticUDF {
+                op,
+                mode,
+                name: "checked".to_string(),
+                signature: Signature::any(2, Volatility::Immutable),
+            };
+            let left: ArrayRef = Arc::new(Int64Array::from(vec![left]));
+            let right: ArrayRef = Arc::new(Int64Array::from(vec![right]));
+            udf.evaluate(&left, &right)
+                .map(|array| array.as_primitive::<Int64Type>().value(0))
+        };
+
+        assert_eq!(
+            evaluate(Operator::Plus, OverflowMode::Error, 2, 3).unwrap(),
+            5
+        );
+        let err = evaluate(Operator::Plus, OverflowMode::Error, i64::MAX, 1).unwrap_err();
+        assert!(err.to_string().contains("Integer overflow"), "{}", err);
+        assert!(evaluate(Operator::Multiply, OverflowMode::Error, i64::MIN, -1).is_err());
+
+        let saturate = OverflowMode::Saturate;
+        assert_eq!(
+            evaluate(Operator::Plus, saturate, i64::MAX, 1).unwrap(),
+            i64::MAX
+        );
+        assert_eq!(
+            evaluate(Operator::Minus, saturate, i64::MIN, 1).unwrap(),
+            i64::MIN
+        );
+        assert_eq!(
+            evaluate(Operator::Minus, saturate, 0, i64::MIN).unwrap(),
+            i64::MAX
+        );
+        assert_eq!(
+            evaluate(Operator::Multiply, saturate, i64::MAX, -2).unwrap(),
+            i64::MIN
+        );
+        assert_eq!(evaluate(Operator::Multiply, saturate, -4, 5).unwrap(), -20);
+    }
+
+    #[test]
+    fn test_text_length_counts_characters_not_bytes() {
+        assert_eq!(text_length("Zo\u{eb}"), 3);
+        assert_eq!(text_length("東京"), 2);
+        // 'e' and a combining diaeresis are one grapheme but two characters
+        #[cfg(feature = "icu")]
+        assert_eq!(text_length("Zoe\u{308}"), 3);
+        #[cfg(not(feature = "icu"))]
+        assert_eq!(text_length("Zoe\u{308}"), 4);
+    }
 }
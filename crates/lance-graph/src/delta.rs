@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Created rows of a write transaction that are not committed yet
+//!
+//! The statements of an atomic script (see
+//! [`CypherScript::execute_atomically`]) write to staging copies of the
+//! datasets of the graph. Rows that CREATE and MERGE append are not
+//! committed to the copies statement by statement: they are kept in a
+//! [`DeltaStore`], and the scans of later statements read them after the
+//! rows of the Lance dataset. A MATCH after a MERGE therefore returns the
+//! merged nodes, and merging the same pattern again matches them instead of
+//! creating them twice. A dataset the transaction creates is read from the
+//! store alone until it is committed.
+//!
+//! Statements that change rows in place (SET, REMOVE and DELETE) and
+//! procedure calls commit the staged rows to the copies first, and so does
+//! publishing the transaction; updated rows are read back from the copies.
+//! Relationship types without parallel edges merge rather than append new
+//! rows, so their rows are committed right away.
+//!
+//! [`CypherScript::execute_atomically`]: crate::script::CypherScript::execute_atomically
+
+use crate::error::Result;
+use arrow_array::{new_null_array, RecordBatch};
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion::catalog::Session;
+use datafusion::datasource::{MemTable, TableProvider, TableType};
+use datafusion::error::Result as DFResult;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Rows staged for one dataset
+#[derive(Debug)]
+pub(crate) struct StagedRows {
+    /// Name of the dataset as the namespace knows it
+    pub(crate) table: String,
+    /// Whether the dataset did not exist when its first rows were staged
+    pub(crate) new: bool,
+    pub(crate) batches: Vec<RecordBatch>,
+}
+
+/// Created rows per dataset, keyed by lowercase dataset name
+#[derive(Debug, Default)]
+pub(crate) struct DeltaStore {
+    tables: Mutex<HashMap<String, StagedRows>>,
+}
+
+impl DeltaStore {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stage `batches` of `table`, whose dataset does not exist if `new`
+    pub(crate) fn append(&self, table: &str, new: bool, batches: Vec<RecordBatch>) {
+        let batches: Vec<RecordBatch> = batches
+            .into_iter()
+            .filter(|batch| batch.num_rows() > 0)
+            .collect();
+        if batches.is_empty() {
+            return;
+        }
+        self.tables
+            .lock()
+            .unwrap()
+            .entry(table.to_lowercase())
+            .or_insert_with(|| StagedRows {
+                table: table.to_string(),
+                new,
+                batches: Vec::new(),
+            })
+            .batches
+            .extend(batches);
+    }
+
+    /// The rows staged for `table`
+    pub(crate) fn batches(&self, table: &str) -> Vec<RecordBatch> {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(&table.to_lowercase())
+            .map(|rows| rows.batches.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the dataset of `table` only exists in the store
+    pub(crate) fn is_new(&self, table: &str) -> bool {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(&table.to_lowercase())
+            .is_some_and(|rows| rows.new)
+    }
+
+    /// Remove the staged rows of every dataset, in dataset name order
+    pub(crate) fn take(&self) -> Vec<StagedRows> {
+        let mut staged: Vec<StagedRows> = self
+            .tables
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, rows)| rows)
+            .collect();
+        staged.sort_by(|a, b| a.table.cmp(&b.table));
+        staged
+    }
+
+    /// `providers` of `tables`, keyed by lowercase table name, with the rows
+    /// staged for them appended; datasets that only exist in the store are
+    /// added
+    pub(crate) fn overlay(
+        &self,
+        tables: &HashSet<String>,
+        mut providers: HashMap<String, Arc<dyn TableProvider>>,
+    ) -> Result<HashMap<String, Arc<dyn TableProvider>>> {
+        let staged = self.tables.lock().unwrap();
+        for table in tables {
+            let name = table.to_lowercase();
+            let Some(rows) = staged.get(&name) else {
+                continue;
+            };
+            let provider: Arc<dyn TableProvider> = match providers.remove(&name) {
+                Some(base) => {
+                    let schema = base.schema();
+                    let batches = rows
+                        .batches
+                        .iter()
+                        .map(|batch| align(batch, &schema))
+                        .collect::<Result<Vec<_>>>()?;
+                    Arc::new(OverlayTable {
+                        base,
+                        staged: Arc::new(MemTable::try_new(schema, vec![batches])?),
+                    })
+                }
+                None => Arc::new(MemTable::try_new(
+                    rows.batches[0].schema(),
+                    vec![rows.batches.clone()],
+                )?),
+            };
+            providers.insert(name, provider);
+        }
+        Ok(providers)
+    }
+}
+
+/// `batch` with the columns of `schema`; columns the dataset adds on scans,
+/// such as row ids, are null
+fn align(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) => column.clone(),
+            None => new_null_array(field.data_type(), batch.num_rows()),
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// A dataset whose scans return the rows staged for it after its own
+#[derive(Debug)]
+struct OverlayTable {
+    base: Arc<dyn TableProvider>,
+    staged: Arc<MemTable>,
+}
+
+#[async_trait]
+impl TableProvider for OverlayTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.base.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.base.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let base = self.base.scan(state, projection, filters, limit).await?;
+        let staged = self.staged.scan(state, projection, filters, limit).await?;
+        Ok(Arc::new(UnionExec::new(vec![base, staged])))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        // The staged rows are not filtered by the scan
+        Ok(self
+            .base
+            .supports_filters_pushdown(filters)?
+            .into_iter()
+            .map(|pushdown| match pushdown {
+                TableProviderFilterPushDown::Exact => TableProviderFilterPushDown::Inexact,
+                other => other,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Int64Array, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::prelude::SessionContext;
+
+    fn people(ids: Vec<i64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(ids))]).unwrap()
+    }
+
+    async fn ids(provider: Arc<dyn TableProvider>, sql: &str) -> Vec<i64> {
+        let ctx = SessionContext::new();
+        ctx.register_table("person", provider).unwrap();
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        let mut ids: Vec<i64> = batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                column.unwrap().values().to_vec()
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_overlay_appends_staged_rows_to_scans() {
+        // The dataset exposes a row id column the staged rows lack
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("_rowid", DataType::UInt64, true),
+        ]));
+        let base = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(UInt64Array::from(vec![0, 1])),
+            ],
+        )
+        .unwrap();
+        let base: Arc<dyn TableProvider> =
+            Arc::new(MemTable::try_new(schema, vec![vec![base]]).unwrap());
+
+        let delta = DeltaStore::new();
+        delta.append("Person", false, vec![people(vec![3]), people(vec![])]);
+        assert_eq!(delta.batches("person").len(), 1);
+        let tables = HashSet::from(["Person".to_string()]);
+        let providers = delta
+            .overlay(&tables, HashMap::from([("person".to_string(), base)]))
+            .unwrap();
+        let person = providers["person"].clone();
+        assert_eq!(
+            ids(person.clone(), "SELECT id FROM person").await,
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            ids(person.clone(), "SELECT id FROM person WHERE id > 1").await,
+            vec![2, 3]
+        );
+        assert_eq!(
+            ids(person, "SELECT id FROM person WHERE _rowid IS NULL").await,
+            vec![3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_datasets_are_read_from_the_store() {
+        let delta = DeltaStore::new();
+        delta.append("Person", true, vec![people(vec![1])]);
+        delta.append("Person", false, vec![people(vec![2])]);
+        assert!(delta.is_new("person"));
+
+        let tables = HashSet::from(["Person".to_string()]);
+        let providers = delta.overlay(&tables, HashMap::new()).unwrap();
+        assert_eq!(
+            ids(providers["person"].clone(), "SELECT id FROM person").await,
+            vec![1, 2]
+        );
+
+        let staged = delta.take();
+        assert_eq!(staged.len(), 1);
+        assert_eq!((staged[0].table.as_str(), staged[0].new), ("Person", true));
+        assert!(delta.batches("Person").is_empty());
+        assert!(!delta.is_new("Person"));
+    }
+}
@@ -0,0 +1,616 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Result schema of a query without planning or executing it
+//!
+//! [`CypherQuery::describe`] types each RETURN item from the AST, the semantic
+//! analysis (which label every variable is bound to) and the Arrow schemas of
+//! the datasets, following the same rules the DataFusion planner uses for
+//! column names and expression types. Clients can use it to create sinks or
+//! lay out result tables before running an expensive query.
+
+use crate::ast::{ArithmeticOperator, PropertyValue, ReturnItem, ValueExpression};
+use crate::config::GraphConfig;
+use crate::datafusion_planner::expression::{contains_aggregate, to_cypher_column_name};
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use crate::semantic::{ScopeType, SemanticAnalyzer, SemanticResult, VariableInfo, VariableType};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl CypherQuery {
+    /// Arrow schema (names, types, nullability) of the query's result
+    ///
+    /// Computed from semantic analysis only: no plan is built and no data is
+    /// read. `schemas` maps dataset names (as passed to [`CypherQuery::execute`])
+    /// to their Arrow schemas, which supply the types of returned properties.
+    ///
+    /// Provenance columns requested with [`CypherQuery::with_provenance`] are
+    /// not included.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let query = CypherQuery::new("MATCH (p:Person) RETURN p.name, count(*) AS n")?
+    ///     .with_config(config);
+    /// let schema = query.describe(&HashMap::from([(
+    ///     "Person".to_string(),
+    ///     person_batch.schema(),
+    /// )]))?;
+    /// assert_eq!(schema.field(1).data_type(), &DataType::Int64);
+    /// ```
+    pub fn describe(&self, schemas: &HashMap<String, SchemaRef>) -> Result<SchemaRef> {
+        let query = self.bind_label_parameters()?;
+        let config = query.require_config()?;
+        let schema_of = |name: &str, _: bool| {
+            schemas
+                .iter()
+                .find(|(dataset, _)| dataset.eq_ignore_ascii_case(name))
+                .map(|(_, schema)| schema.clone())
+        };
+        let mut resolved = query.ast().clone();
+        crate::introspection::resolve(&mut resolved, config, &schema_of)?;
+        let ast = &resolved;
+
+        let mut analyzer = SemanticAnalyzer::new(config.clone())
+            .with_compatibility_mode(self.compatibility_mode());
+        let semantic = analyzer.analyze(ast)?;
+        if !semantic.errors.is_empty() {
+            return Err(GraphError::PlanError {
+                message: format!("Semantic analysis failed:\n{}", semantic.errors.join("\n")),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        if let Some(call) = &ast.procedure {
+            if crate::query_monitor::is_monitor_call(call) {
+                return Ok(crate::query_monitor::procedure_schema(call));
+            }
+            return Ok(crate::summary::summary_schema());
+        }
+
+        let schemas: HashMap<String, &SchemaRef> = schemas
+            .iter()
+            .map(|(name, schema)| (name.to_lowercase(), schema))
+            .collect();
+        let mut typer = ResultTyper {
+            config,
+            semantic: &semantic,
+            schemas,
+            parameters: self.parameters(),
+            projected: HashMap::new(),
+        };
+
+        // WITH items are referenced by name after the WITH
+        if let Some(with_clause) = &ast.with_clause {
+            let fields = typer.fields(&with_clause.items)?;
+            typer.projected = fields
+                .into_iter()
+                .map(|field| (field.name().to_lowercase(), field))
+                .collect();
+        }
+        let fields = typer.fields(&ast.return_clause.items)?;
+        Ok(Arc::new(Schema::new(fields)))
+    }
+}
+
+/// Types value expressions of a query
+struct ResultTyper<'a> {
+    config: &'a GraphConfig,
+    semantic: &'a SemanticResult,
+    /// Dataset schemas by lowercase dataset name
+    schemas: HashMap<String, &'a SchemaRef>,
+    parameters: &'a HashMap<String, serde_json::Value>,
+    /// Fields projected by a preceding WITH, by lowercase name
+    projected: HashMap<String, Field>,
+}
+
+impl ResultTyper<'_> {
+    /// Output fields of a projection, named as the planner names them
+    fn fields(&self, items: &[ReturnItem]) -> Result<Vec<Field>> {
+        // Aliases are lowercased by plain projections but kept by aggregations
+        let aggregates = items.iter().any(|i| contains_aggregate(&i.expression));
+        items
+            .iter()
+            .map(|item| {
+                let name = match &item.alias {
+                    Some(alias) if aggregates => alias.clone(),
+                    Some(alias) => alias.to_lowercase(),
+                    None => to_cypher_column_name(&item.expression),
+                };
+                let (data_type, nullable) = self.value_type(&item.expression)?;
+                Ok(Field::new(name, data_type, nullable))
+            })
+            .collect()
+    }
+
+    /// Whether any of `args` may be NULL
+    fn any_nullable(&self, args: &[ValueExpression]) -> Result<bool> {
+        for arg in args {
+            if self.value_type(arg)?.1 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Arrow type and nullability of `expr`
+    fn value_type(&self, expr: &ValueExpression) -> Result<(DataType, bool)> {
+        use ValueExpression as VE;
+        Ok(match expr {
+            VE::Property(property) => {
+                let field = self.property_field(&property.variable, &property.property)?;
+                (field.data_type().clone(), field.is_nullable())
+            }
+            VE::Variable(variable) => match self.projected.get(&variable.to_lowercase()) {
+                Some(field) => (field.data_type().clone(), field.is_nullable()),
+                None => {
+                    return Err(unsupported(format!(
+                        "describing whole entity '{}'; return its properties instead",
+                        variable
+                    )))
+                }
+            },
+            VE::Literal(PropertyValue::Property(property)) => {
+                let field = self.property_field(&property.variable, &property.property)?;
+                (field.data_type().clone(), field.is_nullable())
+            }
+            VE::Literal(value) => literal_type(value),
+            VE::Parameter(name) => match self.parameters.get(name) {
+                Some(value) => match PropertyValue::from_json(value) {
+                    Some(value) => literal_type(&value),
+                    None => (DataType::Null, true),
+                },
+                None => (DataType::Null, true),
+            },
+            VE::ScalarFunction { name, args } => match name.to_lowercase().as_str() {
+                "tolower" | "lower" | "toupper" | "upper" if args.len() == 1 => {
+                    (DataType::Utf8, self.value_type(&args[0])?.1)
+                }
+                // NULL when any argument is
+                "replace" | "substring" | "left" | "right" | "trim" | "ltrim" | "rtrim" => {
+                    (DataType::Utf8, self.any_nullable(args)?)
+                }
+                "range" => (
+                    DataType::List(Arc::new(Field::new_list_field(DataType::Int64, true))),
+                    self.any_nullable(args)?,
+                ),
+                "size" => (DataType::Int64, self.any_nullable(args)?),
+                // Empty lists have no first or last item
+                "head" | "last" => match args.as_slice() {
+                    [list] => match self.value_type(list)?.0 {
+                        DataType::List(field)
+                        | DataType::LargeList(field)
+                        | DataType::FixedSizeList(field, _) => (field.data_type().clone(), true),
+                        _ => (DataType::Null, true),
+                    },
+                    _ => (DataType::Null, true),
+                },
+                "tail" | "reverse" => match args.as_slice() {
+                    [list] => match self.value_type(list)? {
+                        (
+                            DataType::List(field)
+                            | DataType::LargeList(field)
+                            | DataType::FixedSizeList(field, _),
+                            nullable,
+                        ) => (DataType::List(field), nullable),
+                        (_, nullable) => (DataType::Utf8, nullable),
+                    },
+                    _ => (DataType::Null, true),
+                },
+                "split" => (
+                    DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+                    self.any_nullable(args)?,
+                ),
+                // Typed by the first argument that is not a NULL literal, and
+                // NULL only when every argument can be
+                "coalesce" => {
+                    let mut data_type = DataType::Null;
+                    let mut nullable = true;
+                    for arg in args {
+                        let (arg_type, arg_nullable) = self.value_type(arg)?;
+                        if data_type == DataType::Null {
+                            data_type = arg_type;
+                        }
+                        nullable &= arg_nullable;
+                    }
+                    (data_type, nullable)
+                }
+                "nullif" => match args.first() {
+                    Some(value) => (self.value_type(value)?.0, true),
+                    None => (DataType::Null, true),
+                },
+                "rand" => (DataType::Float64, false),
+                "randomuuid" => (DataType::Utf8, false),
+                "length" => match args.as_slice() {
+                    [arg @ (VE::Property(_) | VE::ScalarFunction { .. })] => {
+                        (DataType::Int64, self.value_type(arg)?.1)
+                    }
+                    _ => (DataType::Int64, false),
+                },
+                "timestamp" => (DataType::Int64, false),
+                function if crate::temporal::is_function(function) => {
+                    crate::temporal::result_type(function, self.any_nullable(args)?)
+                }
+                crate::introspection::LIST_FUNCTION => (
+                    DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+                    false,
+                ),
+                // Planned as NULL
+                _ => (DataType::Null, true),
+            },
+            VE::AggregateFunction { name, args, .. } => {
+                let arg = match args.as_slice() {
+                    [VE::Variable(v)] if v == "*" || !self.is_projected(v) => None,
+                    // percentileCont / percentileDisc take the percentile second
+                    [arg] | [arg, _] => Some(self.value_type(arg)?),
+                    _ => return Ok((DataType::Int32, false)),
+                };
+                match (name.to_lowercase().as_str(), arg) {
+                    ("count", _) => (DataType::Int64, false),
+                    ("avg" | "percentilecont", _) => (DataType::Float64, true),
+                    ("stdev" | "stdevp", _) => (DataType::Float64, false),
+                    ("percentiledisc", Some((data_type, _))) => (data_type, true),
+                    ("sum", Some((data_type, _))) if data_type.is_floating() => {
+                        (DataType::Float64, true)
+                    }
+                    ("sum", Some((data_type, _))) if data_type.is_unsigned_integer() => {
+                        (DataType::UInt64, true)
+                    }
+                    ("sum", _) => (DataType::Int64, true),
+                    ("min" | "max", Some((data_type, _))) => (data_type, true),
+                    ("collect", Some((data_type, _))) => (
+                        DataType::List(Arc::new(Field::new_list_field(data_type, true))),
+                        true,
+                    ),
+                    _ => (DataType::Null, true),
+                }
+            }
+            VE::Arithmetic {
+                left,
+                operator,
+                right,
+            } => {
+                let (left_type, left_null) = self.value_type(left)?;
+                let (right_type, right_null) = self.value_type(right)?;
+                let data_type = if left_type.is_floating() || right_type.is_floating() {
+                    DataType::Float64
+                } else if left_type.is_integer() && right_type.is_integer() {
+                    DataType::Int64
+                } else {
+                    left_type
+                };
+                // Integer division by zero yields NULL
+                let nullable = left_null
+                    || right_null
+                    || matches!(
+                        operator,
+                        ArithmeticOperator::Divide | ArithmeticOperator::Modulo
+                    );
+                (data_type, nullable)
+            }
+            VE::VectorDistance { .. } | VE::VectorSimilarity { .. } => (DataType::Float32, true),
+            VE::Case { branches, default } => {
+                // Rows no branch matches are null without ELSE
+                let mut nullable = default.is_none();
+                let mut data_type = DataType::Null;
+                let values = branches.iter().map(|b| &b.value).chain(default.as_deref());
+                for value in values {
+                    let (value_type, value_null) = self.value_type(value)?;
+                    nullable |= value_null;
+                    data_type = match (data_type, value_type) {
+                        (DataType::Null, other) | (other, DataType::Null) => other,
+                        (left, right) if left == right => left,
+                        (left, right)
+                            if left.is_numeric()
+                                && right.is_numeric()
+                                && (left.is_floating() || right.is_floating()) =>
+                        {
+                            DataType::Float64
+                        }
+                        (left, right) if left.is_integer() && right.is_integer() => DataType::Int64,
+                        (left, _) => left,
+                    };
+                }
+                (data_type, nullable)
+            }
+            VE::ListComprehension {
+                variable,
+                list,
+                projection,
+                ..
+            } => {
+                let (list_type, nullable) = self.value_type(list)?;
+                let name = variable.to_lowercase();
+                let element = match &list_type {
+                    DataType::List(field)
+                    | DataType::LargeList(field)
+                    | DataType::FixedSizeList(field, _) => field.as_ref().clone().with_name(&name),
+                    _ => Field::new(&name, DataType::Null, true),
+                };
+                let item = match projection {
+                    Some(projection) => {
+                        // The element variable is in scope like a projected value
+                        let mut projected = self.projected.clone();
+                        projected.insert(name, element);
+                        let scoped = ResultTyper {
+                            config: self.config,
+                            semantic: self.semantic,
+                            schemas: self.schemas.clone(),
+                            parameters: self.parameters,
+                            projected,
+                        };
+                        scoped.value_type(projection)?.0
+                    }
+                    None => element.data_type().clone(),
+                };
+                (
+                    DataType::List(Arc::new(Field::new_list_field(item, true))),
+                    nullable,
+                )
+            }
+            VE::Reduce {
+                accumulator,
+                initial,
+                variable,
+                list,
+                expression,
+            } => {
+                let (list_type, _) = self.value_type(list)?;
+                let name = variable.to_lowercase();
+                let element = match &list_type {
+                    DataType::List(field)
+                    | DataType::LargeList(field)
+                    | DataType::FixedSizeList(field, _) => field.as_ref().clone().with_name(&name),
+                    _ => Field::new(&name, DataType::Null, true),
+                };
+                // The accumulator takes the type of the expression, which may
+                // widen the initial value's (e.g. `acc = 0` summing floats)
+                let mut data_type = self.value_type(initial)?.0;
+                for _ in 0..3 {
+                    let mut projected = self.projected.clone();
+                    projected.insert(name.clone(), element.clone());
+                    projected.insert(
+                        accumulator.to_lowercase(),
+                        Field::new(accumulator.to_lowercase(), data_type.clone(), true),
+                    );
+                    let scoped = ResultTyper {
+                        config: self.config,
+                        semantic: self.semantic,
+                        schemas: self.schemas.clone(),
+                        parameters: self.parameters,
+                        projected,
+                    };
+                    let folded = scoped.value_type(expression)?.0;
+                    if folded == data_type {
+                        break;
+                    }
+                    data_type = folded;
+                }
+                (data_type, true)
+            }
+            VE::PatternComprehension {
+                pattern,
+                projection,
+                ..
+            } => {
+                // The pattern's variables are in scope like matched ones
+                let mut semantic = self.semantic.clone();
+                let nodes = std::iter::once(&pattern.start_node)
+                    .chain(pattern.segments.iter().map(|segment| &segment.end_node));
+                for node in nodes {
+                    if let Some(variable) = &node.variable {
+                        semantic
+                            .variables
+                            .entry(variable.to_lowercase())
+                            .or_insert_with(|| VariableInfo {
+                                name: variable.clone(),
+                                variable_type: VariableType::Node,
+                                labels: node.labels.clone(),
+                                properties: Default::default(),
+                                defined_in: ScopeType::Match,
+                            });
+                    }
+                }
+                for relationship in pattern.segments.iter().map(|s| &s.relationship) {
+                    if let Some(variable) = &relationship.variable {
+                        semantic
+                            .variables
+                            .entry(variable.to_lowercase())
+                            .or_insert_with(|| VariableInfo {
+                                name: variable.clone(),
+                                variable_type: VariableType::Relationship,
+                                labels: relationship.types.clone(),
+                                properties: Default::default(),
+                                defined_in: ScopeType::Match,
+                            });
+                    }
+                }
+                let scoped = ResultTyper {
+                    config: self.config,
+                    semantic: &semantic,
+                    schemas: self.schemas.clone(),
+                    parameters: self.parameters,
+                    projected: self.projected.clone(),
+                };
+                let item = scoped.value_type(projection)?.0;
+                (
+                    DataType::List(Arc::new(Field::new_list_field(item, true))),
+                    false,
+                )
+            }
+            VE::CountSubquery { .. } => (DataType::Int64, false),
+            VE::MapProjection { items, .. } => {
+                let fields = items
+                    .iter()
+                    .map(|item| {
+                        let (data_type, nullable) = self.value_type(&item.value)?;
+                        Ok(Field::new(&item.key, data_type, nullable))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (DataType::Struct(fields.into()), false)
+            }
+            VE::VectorLiteral(values) => (
+                DataType::FixedSizeList(
+                    Arc::new(Field::new("item", DataType::Float32, true)),
+                    values.len() as i32,
+                ),
+                false,
+            ),
+        })
+    }
+
+    fn is_projected(&self, variable: &str) -> bool {
+        self.projected.contains_key(&variable.to_lowercase())
+    }
+
+    /// Dataset field of `variable.property`
+    fn property_field(&self, variable: &str, property: &str) -> Result<&Field> {
+        let info = self.semantic.variables.get(variable).ok_or_else(|| {
+            unsupported(format!(
+                "describing property '{}' of unbound variable '{}'",
+                property, variable
+            ))
+        })?;
+        let Some(label) = info.labels.first() else {
+            return Err(unsupported(format!(
+                "describing property '{}' of unlabeled variable '{}'",
+                property, variable
+            )));
+        };
+        let table = match info.variable_type {
+            VariableType::Node => {
+                let (base, _) = self.config.resolve_view_chain(label)?;
+                self.config
+                    .get_node_mapping(&base)
+                    .map(|m| m.table_name().to_string())
+            }
+            VariableType::Relationship => Some(label.clone()),
+            _ => None,
+        };
+        let schema = table
+            .and_then(|table| self.schemas.get(&table.to_lowercase()))
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!("No schema provided for label '{}'", label),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        schema
+            .fields()
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(property))
+            .map(|f| f.as_ref())
+            .ok_or_else(|| GraphError::PlanError {
+                message: format!("Label '{}' has no property '{}'", label, property),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+    }
+}
+
+fn literal_type(value: &PropertyValue) -> (DataType, bool) {
+    match value {
+        PropertyValue::String(_) => (DataType::Utf8, false),
+        PropertyValue::Integer(_) => (DataType::Int64, false),
+        PropertyValue::Float(_) => (DataType::Float64, false),
+        PropertyValue::Boolean(_) => (DataType::Boolean, false),
+        // Placeholder parameters are planned as `0`
+        PropertyValue::Parameter(_) => (DataType::Int32, false),
+        PropertyValue::Null | PropertyValue::Property(_) => (DataType::Null, true),
+    }
+}
+
+fn unsupported(feature: String) -> GraphError {
+    GraphError::UnsupportedFeature {
+        feature,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn describe(query: &str) -> Result<SchemaRef> {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        let person = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("age", DataType::Int32, true),
+        ]));
+        let knows = Arc::new(Schema::new(vec![
+            Field::new("src_id", DataType::Int64, false),
+            Field::new("dst_id", DataType::Int64, false),
+            Field::new("since", DataType::Date32, false),
+        ]));
+        CypherQuery::new(query)?
+            .with_config(config)
+            .describe(&HashMap::from([
+                ("Person".to_string(), person),
+                ("KNOWS".to_string(), knows),
+            ]))
+    }
+
+    #[test]
+    fn test_describe_properties_and_aggregates() {
+        let schema = describe(
+            "MATCH (p:Person)-[k:KNOWS]->(f:Person) \
+             RETURN p.name, k.since, count(f) AS friends, avg(f.age) AS Mean",
+        )
+        .unwrap();
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone(), f.is_nullable()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("p.name", DataType::Utf8, true),
+                ("k.since", DataType::Date32, false),
+                ("friends", DataType::Int64, false),
+                ("Mean", DataType::Float64, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_with_aliases_and_expressions() {
+        let schema = describe(
+            "MATCH (p:Person) WITH p.age + 1 AS next, toUpper(p.name) AS shout \
+             RETURN next, shout AS Loud",
+        )
+        .unwrap();
+        assert_eq!(schema.field(0).name(), "next");
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(1).name(), "loud");
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+    }
+
+    #[test]
+    fn test_describe_unknown_property() {
+        let err = describe("MATCH (p:Person) RETURN p.salary").unwrap_err();
+        assert!(err.to_string().contains("no property 'salary'"), "{}", err);
+    }
+
+    #[test]
+    fn test_describe_map_projection() {
+        let schema =
+            describe("MATCH (p:Person) RETURN p {.id, .name, next: p.age + 1} AS person").unwrap();
+        assert_eq!(schema.field(0).name(), "person");
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(
+            schema.field(0).data_type(),
+            &DataType::Struct(
+                vec![
+                    Field::new("id", DataType::Int64, false),
+                    Field::new("name", DataType::Utf8, true),
+                    Field::new("next", DataType::Int64, true),
+                ]
+                .into()
+            )
+        );
+    }
+}
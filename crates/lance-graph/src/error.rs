@@ -39,6 +39,10 @@ pub enum GraphError {
     #[snafu(display("Invalid graph pattern: {message}"))]
     InvalidPattern { message: String, location: Location },
 
+    /// A concurrent writer committed to a dataset this write was based on
+    #[snafu(display("Write conflict: {message}"))]
+    WriteConflict { message: String, location: Location },
+
     /// DataFusion integration error
     #[snafu(display("DataFusion error: {source}"))]
     DataFusion {
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Limits on the paths produced by variable-length expansion
+//!
+//! A variable-length pattern such as `(a)-[:KNOWS*1..6]->(b)` can match a
+//! number of paths that grows exponentially with the hop count on dense or
+//! cyclic graphs. [`ExpansionLimits`] caps the paths kept for each source node
+//! and across the whole expansion. Shorter paths are kept first, so a capped
+//! result still holds the nearest reachable nodes.
+//!
+//! When a cap drops paths the result is marked as truncated. The marker is
+//! stored in the schema metadata of the returned batch and read back with
+//! [`PathTruncation::from_schema`]:
+//!
+//! ```ignore
+//! use lance_graph::expansion::{ExpansionLimits, PathTruncation};
+//!
+//! let query = CypherQuery::new("MATCH (a:Person)-[:KNOWS*1..6]->(b:Person) RETURN a.name, b.name")?
+//!     .with_config(config)
+//!     .with_expansion_limits(ExpansionLimits::new().with_max_paths_per_source(100));
+//! let batch = query.execute(datasets, None).await?;
+//! if PathTruncation::from_schema(&batch.schema()).is_truncated() {
+//!     // Some sources reached more than 100 paths
+//! }
+//! ```
+
+use crate::error::{GraphError, Result};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Schema metadata key set to `"true"` when a per-source cap dropped paths
+pub const TRUNCATED_PER_SOURCE_KEY: &str = "lance_graph.paths_truncated_per_source";
+
+/// Schema metadata key set to `"true"` when the global path cap dropped paths
+pub const TRUNCATED_MAX_PATHS_KEY: &str = "lance_graph.paths_truncated";
+
+/// Caps on the paths a variable-length expansion may produce
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ExpansionLimits {
+    max_paths_per_source: Option<usize>,
+    max_paths: Option<usize>,
+}
+
+impl ExpansionLimits {
+    /// No limits; every matching path is produced
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `limit` paths for each source node
+    pub fn with_max_paths_per_source(mut self, limit: usize) -> Self {
+        self.max_paths_per_source = Some(limit);
+        self
+    }
+
+    /// Keep at most `limit` paths for each expansion in total
+    pub fn with_max_paths(mut self, limit: usize) -> Self {
+        self.max_paths = Some(limit);
+        self
+    }
+
+    /// Cap on paths kept for each source node
+    pub fn max_paths_per_source(&self) -> Option<usize> {
+        self.max_paths_per_source
+    }
+
+    /// Cap on paths kept for each expansion
+    pub fn max_paths(&self) -> Option<usize> {
+        self.max_paths
+    }
+
+    /// Whether no cap is set
+    pub fn is_unlimited(&self) -> bool {
+        self.max_paths_per_source.is_none() && self.max_paths.is_none()
+    }
+}
+
+/// Which expansion limits dropped paths from a result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathTruncation {
+    /// Some source node had more paths than the per-source cap
+    pub per_source: bool,
+    /// An expansion had more paths than the global cap
+    pub max_paths: bool,
+}
+
+impl PathTruncation {
+    /// Whether any limit dropped paths
+    pub fn is_truncated(&self) -> bool {
+        self.per_source || self.max_paths
+    }
+
+    /// Read the truncation markers from a result schema
+    pub fn from_schema(schema: &Schema) -> Self {
+        let flag = |key: &str| schema.metadata().get(key).is_some_and(|v| v == "true");
+        Self {
+            per_source: flag(TRUNCATED_PER_SOURCE_KEY),
+            max_paths: flag(TRUNCATED_MAX_PATHS_KEY),
+        }
+    }
+
+    /// `batch` with the truncation markers added to its schema metadata
+    pub(crate) fn annotate(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        if !self.is_truncated() {
+            return Ok(batch);
+        }
+        let mut metadata: HashMap<String, String> = batch.schema().metadata().clone();
+        for (key, truncated) in [
+            (TRUNCATED_PER_SOURCE_KEY, self.per_source),
+            (TRUNCATED_MAX_PATHS_KEY, self.max_paths),
+        ] {
+            if truncated {
+                metadata.insert(key.to_string(), "true".to_string());
+            }
+        }
+        let schema = Arc::new(batch.schema().as_ref().clone().with_metadata(metadata));
+        batch
+            .with_schema(schema)
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to mark truncated result: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+    }
+}
+
+/// Flags the path limit filters raise while a plan executes
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TruncationFlags {
+    pub(crate) per_source: Arc<AtomicBool>,
+    pub(crate) max_paths: Arc<AtomicBool>,
+}
+
+impl TruncationFlags {
+    /// Truncation observed so far
+    pub(crate) fn snapshot(&self) -> PathTruncation {
+        PathTruncation {
+            per_source: self.per_source.load(Ordering::Relaxed),
+            max_paths: self.max_paths.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field};
+
+    #[test]
+    fn test_truncation_round_trips_through_schema_metadata() {
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2]))]).unwrap();
+
+        let untouched = PathTruncation::default().annotate(batch.clone()).unwrap();
+        assert!(!PathTruncation::from_schema(&untouched.schema()).is_truncated());
+
+        let truncation = PathTruncation {
+            per_source: true,
+            max_paths: false,
+        };
+        let annotated = truncation.annotate(batch).unwrap();
+        assert_eq!(PathTruncation::from_schema(&annotated.schema()), truncation);
+        assert_eq!(annotated.num_rows(), 2);
+    }
+
+    #[test]
+    fn test_limits_builder() {
+        assert!(ExpansionLimits::new().is_unlimited());
+        let limits = ExpansionLimits::new()
+            .with_max_paths_per_source(10)
+            .with_max_paths(100);
+        assert_eq!(limits.max_paths_per_source(), Some(10));
+        assert_eq!(limits.max_paths(), Some(100));
+        assert!(!limits.is_unlimited());
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Graph discovery from a directory of Lance datasets
+//!
+//! [`GraphCatalog::discover`] lists the `*.lance` datasets directly under a
+//! local directory or object store prefix and derives a [`GraphConfig`] from
+//! their schemas, so a folder laid out like [`DirNamespace`] expects can be
+//! queried without writing a configuration first:
+//!
+//! - A dataset with a source/target column pair (`src_id`/`dst_id`,
+//!   `source_id`/`target_id`, `src`/`dst`, `source`/`target`,
+//!   `from_id`/`to_id` or `start_id`/`end_id`) becomes the relationship type
+//!   named after the dataset.
+//! - Any other dataset becomes the node label named after it, keyed by `id`,
+//!   `<label>_id` or else its first `*_id` column.
+//! - Datasets matching neither rule are skipped and reported in
+//!   [`GraphCatalog::skipped`].
+//!
+//! [`GraphCatalog::discover_with_options`] lists and opens the datasets with
+//! object store options such as credentials or an SSE-KMS key, and
+//! [`GraphCatalog::with_table_storage_options`] gives a single label or
+//! relationship type its own options when the engine opens its dataset.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::GraphCatalog;
+//!
+//! let catalog = GraphCatalog::discover("s3://bucket/graph").await?;
+//! let result = catalog
+//!     .execute("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name")
+//!     .await?;
+//! ```
+
+use crate::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use crate::error::Result;
+use crate::query::CypherQuery;
+use arrow_array::RecordBatch;
+use arrow_schema::{Schema as ArrowSchema, SchemaRef};
+use lance::dataset::builder::DatasetBuilder;
+use lance::io::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use lance_graph_catalog::{DirNamespace, StorageOptions};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Column pairs recognized as relationship endpoints, in order of preference
+const ENDPOINT_COLUMNS: &[(&str, &str)] = &[
+    ("src_id", "dst_id"),
+    ("source_id", "target_id"),
+    ("src", "dst"),
+    ("source", "target"),
+    ("from_id", "to_id"),
+    ("start_id", "end_id"),
+];
+
+/// What a discovered dataset was registered as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableRole {
+    Node {
+        id_field: String,
+    },
+    Relationship {
+        source_field: String,
+        target_field: String,
+    },
+}
+
+/// A Lance dataset found by [`GraphCatalog::discover`]
+#[derive(Debug, Clone)]
+pub struct DiscoveredTable {
+    /// Label or relationship type, the dataset name without `.lance`
+    pub name: String,
+    pub uri: String,
+    pub schema: SchemaRef,
+    pub role: TableRole,
+}
+
+/// A graph assembled from the Lance datasets of one directory
+#[derive(Debug, Clone)]
+pub struct GraphCatalog {
+    base_uri: String,
+    config: GraphConfig,
+    tables: Vec<DiscoveredTable>,
+    skipped: Vec<String>,
+    storage_options: StorageOptions,
+    table_storage_options: HashMap<String, StorageOptions>,
+}
+
+impl GraphCatalog {
+    /// Scan `dir` (a local path or object store URI) for Lance datasets and
+    /// register each as a node label or relationship type
+    pub async fn discover(dir: &str) -> Result<Self> {
+        Self::discover_with_options(dir, StorageOptions::default()).await
+    }
+
+    /// Like [`GraphCatalog::discover`], reaching the object store with
+    /// `options`, which also apply to every dataset the engine opens
+    pub async fn discover_with_options(
+        dir: &str,
+        options: impl Into<StorageOptions>,
+    ) -> Result<Self> {
+        let storage_options: StorageOptions = options.into();
+        let base_uri = dir.trim_end_matches('/').to_string();
+        let params = ObjectStoreParams {
+            storage_options: Some(storage_options.as_map().clone()),
+            ..Default::default()
+        };
+        let (store, path) = ObjectStore::from_uri_and_params(
+            Arc::new(ObjectStoreRegistry::default()),
+            &base_uri,
+            &params,
+        )
+        .await?;
+        let mut names: Vec<String> = store
+            .read_dir(path)
+            .await?
+            .into_iter()
+            .filter_map(|entry| entry.strip_suffix(".lance").map(str::to_string))
+            .collect();
+        names.sort();
+
+        let mut builder = GraphConfig::builder();
+        let mut tables = Vec::new();
+        let mut skipped = Vec::new();
+        for name in names {
+            let uri = format!("{}/{}.lance", base_uri, name);
+            let dataset = DatasetBuilder::from_uri(&uri)
+                .with_storage_options(storage_options.as_map().clone())
+                .load()
+                .await?;
+            let schema: SchemaRef = Arc::new(ArrowSchema::from(dataset.schema()));
+            let Some(role) = infer_role(&name, &schema) else {
+                skipped.push(name);
+                continue;
+            };
+            builder = match &role {
+                TableRole::Node { id_field } => {
+                    builder.with_node_mapping(NodeMapping::new(name.clone(), id_field.clone()))
+                }
+                TableRole::Relationship {
+                    source_field,
+                    target_field,
+                } => builder.with_relationship_mapping(RelationshipMapping::new(
+                    name.clone(),
+                    source_field.clone(),
+                    target_field.clone(),
+                )),
+            };
+            tables.push(DiscoveredTable {
+                name,
+                uri,
+                schema,
+                role,
+            });
+        }
+
+        Ok(Self {
+            base_uri,
+            config: builder.build()?,
+            tables,
+            skipped,
+            storage_options,
+            table_storage_options: HashMap::new(),
+        })
+    }
+
+    /// Open the dataset of label or relationship type `name` with `options`
+    /// on top of the catalog-wide options
+    pub fn with_table_storage_options(
+        mut self,
+        name: &str,
+        options: impl Into<StorageOptions>,
+    ) -> Self {
+        self.table_storage_options
+            .insert(name.to_string(), options.into());
+        self
+    }
+
+    /// Configuration covering every discovered label and relationship type
+    pub fn config(&self) -> &GraphConfig {
+        &self.config
+    }
+
+    /// Registered datasets, sorted by name
+    pub fn tables(&self) -> &[DiscoveredTable] {
+        &self.tables
+    }
+
+    /// Names of datasets that were neither node nor relationship tables
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// Namespace resolving the discovered datasets
+    pub fn namespace(&self) -> DirNamespace {
+        self.table_storage_options.iter().fold(
+            DirNamespace::new(self.base_uri.clone())
+                .with_storage_options(self.storage_options.clone()),
+            |namespace, (name, options)| {
+                namespace.with_table_storage_options(name, options.clone())
+            },
+        )
+    }
+
+    /// Parse `query` against the discovered graph
+    pub fn query(&self, query: &str) -> Result<CypherQuery> {
+        Ok(CypherQuery::new(query)?.with_config(self.config.clone()))
+    }
+
+    /// Parse and execute `query` against the discovered datasets
+    pub async fn execute(&self, query: &str) -> Result<RecordBatch> {
+        self.query(query)?
+            .execute_with_namespace(self.namespace(), None)
+            .await
+    }
+}
+
+/// Decide whether the dataset `name` holds nodes or relationships
+fn infer_role(name: &str, schema: &ArrowSchema) -> Option<TableRole> {
+    let column = |wanted: &str| {
+        schema
+            .fields()
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(wanted))
+            .map(|f| f.name().clone())
+    };
+
+    for (source, target) in ENDPOINT_COLUMNS {
+        if let (Some(source_field), Some(target_field)) = (column(source), column(target)) {
+            return Some(TableRole::Relationship {
+                source_field,
+                target_field,
+            });
+        }
+    }
+
+    column("id")
+        .or_else(|| column(&format!("{}_id", name)))
+        .or_else(|| {
+            schema
+                .fields()
+                .iter()
+                .map(|f| f.name())
+                .find(|n| n.to_lowercase().ends_with("_id"))
+                .cloned()
+        })
+        .map(|id_field| TableRole::Node { id_field })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Int64Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field};
+    use lance::dataset::Dataset;
+
+    async fn write(dir: &std::path::Path, name: &str, columns: Vec<(&str, Vec<i64>)>) {
+        let mut fields = Vec::new();
+        let mut arrays: Vec<arrow_array::ArrayRef> = Vec::new();
+        for (column, values) in columns {
+            fields.push(Field::new(column, DataType::Int64, false));
+            arrays.push(Arc::new(Int64Array::from(values)));
+        }
+        let batch = RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), arrays).unwrap();
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let uri = dir.join(format!("{}.lance", name));
+        Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_infer_role() {
+        let schema = |names: &[&str]| {
+            ArrowSchema::new(
+                names
+                    .iter()
+                    .map(|n| Field::new(*n, DataType::Int64, false))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            infer_role("KNOWS", &schema(&["SRC_ID", "DST_ID", "since"])),
+            Some(TableRole::Relationship {
+                source_field: "SRC_ID".to_string(),
+                target_field: "DST_ID".to_string(),
+            })
+        );
+        assert_eq!(
+            infer_role("Person", &schema(&["age", "person_id"])),
+            Some(TableRole::Node {
+                id_field: "person_id".to_string()
+            })
+        );
+        assert_eq!(
+            infer_role("Company", &schema(&["owner_id", "id"])),
+            Some(TableRole::Node {
+                id_field: "id".to_string()
+            })
+        );
+        assert_eq!(infer_role("notes", &schema(&["text"])), None);
+    }
+
+    #[tokio::test]
+    async fn test_discover_and_query_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "Person",
+            vec![("person_id", vec![1, 2, 3]), ("age", vec![30, 40, 50])],
+        )
+        .await;
+        write(
+            dir.path(),
+            "KNOWS",
+            vec![("src_id", vec![1, 2]), ("dst_id", vec![2, 3])],
+        )
+        .await;
+        write(dir.path(), "scratch", vec![("value", vec![1])]).await;
+        std::fs::write(dir.path().join("README.txt"), "not a dataset").unwrap();
+
+        let catalog = GraphCatalog::discover(dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let names: Vec<&str> = catalog.tables().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["KNOWS", "Person"]);
+        assert_eq!(catalog.skipped(), &["scratch".to_string()]);
+        assert!(catalog.config().get_node_mapping("Person").is_some());
+
+        let result = catalog
+            .execute("MATCH (a:Person)-[:KNOWS]->(b:Person) WHERE a.age > 35 RETURN b.age")
+            .await
+            .unwrap();
+        let ages = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ages.values().to_vec(), vec![50]);
+
+        let namespace = catalog
+            .with_table_storage_options("Person", StorageOptions::new().with_sse_kms("pii-key"))
+            .namespace();
+        assert_eq!(
+            namespace
+                .storage_options_for("Person")
+                .get("aws_sse_kms_key_id"),
+            Some("pii-key")
+        );
+        assert!(namespace.storage_options_for("KNOWS").is_empty());
+    }
+}
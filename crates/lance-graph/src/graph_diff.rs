@@ -0,0 +1,650 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Differences between two versions of a graph
+//!
+//! [`graph_diff`] compares every label and relationship type of a
+//! configuration between two versions of a namespace's datasets and streams
+//! the rows that were added, removed or changed, e.g. to audit a pipeline
+//! that rebuilds the graph nightly.
+//!
+//! Rows are matched by key: the key columns of a label, and the source and
+//! target key columns of a relationship type that forbids parallel edges.
+//! Relationships of other types are matched by all their columns, so an
+//! edited relationship shows up as removed and added.
+//!
+//! Lance version history keeps the comparison cheap: a fragment present in
+//! both versions holds the same rows in both, so only the rows of fragments
+//! written, rewritten or deleted from in between are compared. Diffing an
+//! append costs a scan of the appended fragments.
+//!
+//! `CALL graph.diff(from, to)` returns the changes between two dataset
+//! versions as one table; see [`diff_schema`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use futures::TryStreamExt;
+//! use lance_graph::graph_diff::{graph_diff, GraphVersion};
+//!
+//! for element in graph_diff(&config, &namespace, GraphVersion::Version(7), GraphVersion::Latest).await? {
+//!     let changes: Vec<RecordBatch> = element.changes.try_collect().await?;
+//!     println!("{} {}: {} changed rows", element.kind, element.name, changes.len());
+//! }
+//!
+//! let audit = CypherQuery::new("CALL graph.diff(7, 8)")?
+//!     .with_config(config)
+//!     .execute_with_namespace(namespace, None)
+//!     .await?;
+//! ```
+
+use crate::ast::{ProcedureCall, PropertyValue, ValueExpression};
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use arrow::compute::concat_batches;
+use arrow_array::{RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::MemTable;
+use datafusion::execution::context::SessionContext;
+use datafusion::logical_expr::{binary_expr, cast, lit, Expr, JoinType, Operator};
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::prelude::ident;
+use datafusion::scalar::ScalarValue;
+use futures::TryStreamExt;
+use lance::dataset::Dataset;
+use lance_graph_catalog::DirNamespace;
+use lance_namespace::models::DescribeTableRequest;
+use lance_namespace::LanceNamespace;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the diff procedure, matched case-insensitively
+pub const DIFF_PROCEDURE: &str = "graph.diff";
+
+/// Column of the changed rows holding the [`ChangeKind`]
+pub const CHANGE_COLUMN: &str = "change";
+
+/// Prefix of the columns of the other version while joining the two
+const OTHER_PREFIX: &str = "__diff_other_";
+
+/// Version of a graph, resolved per dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphVersion {
+    /// Version `n` of every dataset; version 0 is before its creation
+    Version(u64),
+    /// The latest version of every dataset committed at or before this time
+    AsOf(SystemTime),
+    /// The latest version of every dataset
+    Latest,
+}
+
+/// How a row differs between the two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Only in the newer version
+    Added,
+    /// Only in the older version; the row carries its old values
+    Removed,
+    /// In both versions with different values; the row carries its new values
+    Changed,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+/// Changed rows of one label or relationship type
+pub struct ElementDiff {
+    /// `node` or `relationship`
+    pub kind: &'static str,
+    /// Label or relationship type
+    pub name: String,
+    /// Dataset version compared on the older side; `None` if it did not exist
+    pub from_version: Option<u64>,
+    /// Dataset version compared on the newer side; `None` if it did not exist
+    pub to_version: Option<u64>,
+    /// Columns rows are matched by
+    pub key_columns: Vec<String>,
+    /// The [`CHANGE_COLUMN`] followed by the dataset's columns
+    pub changes: SendableRecordBatchStream,
+}
+
+/// Changes of every label and relationship type of `config` between the
+/// `from` and `to` versions of the datasets of `namespace`
+///
+/// Labels come first, then relationship types, each sorted by name. View
+/// labels are skipped; they are filters over their base label.
+pub async fn graph_diff(
+    config: &GraphConfig,
+    namespace: &DirNamespace,
+    from: GraphVersion,
+    to: GraphVersion,
+) -> Result<Vec<ElementDiff>> {
+    let ctx = SessionContext::new();
+    let mut latest: HashMap<String, Option<Dataset>> = HashMap::new();
+    let mut diffs = Vec::new();
+
+    let mut elements = Vec::new();
+    let mut nodes: Vec<_> = config
+        .node_mappings
+        .values()
+        .filter(|m| m.view_of.is_none())
+        .collect();
+    nodes.sort_by(|a, b| a.label.cmp(&b.label));
+    for mapping in nodes {
+        let filter = mapping
+            .label_column
+            .as_ref()
+            .map(|column| ident(column).eq(lit(mapping.label.as_str())));
+        let keys = mapping
+            .key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        elements.push(Element {
+            kind: "node",
+            name: mapping.label.clone(),
+            table: mapping.table_name().to_string(),
+            filter,
+            keys: Some(keys),
+        });
+    }
+    let mut relationships: Vec<_> = config.relationship_mappings.values().collect();
+    relationships.sort_by(|a, b| a.relationship_type.cmp(&b.relationship_type));
+    for mapping in relationships {
+        let keys = (!mapping.allow_parallel_edges).then(|| {
+            mapping
+                .edge_key_columns()
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        });
+        elements.push(Element {
+            kind: "relationship",
+            name: mapping.relationship_type.clone(),
+            table: mapping.relationship_type.clone(),
+            filter: None,
+            keys,
+        });
+    }
+
+    for Element {
+        kind,
+        name,
+        table,
+        filter,
+        keys,
+    } in elements
+    {
+        let dataset = match latest.get(&table.to_lowercase()) {
+            Some(dataset) => dataset.clone(),
+            None => {
+                let dataset = open_latest(namespace, &table).await?;
+                latest.insert(table.to_lowercase(), dataset.clone());
+                dataset
+            }
+        };
+        let (old, new) = match &dataset {
+            Some(dataset) => (
+                checkout(dataset, &table, from).await?,
+                checkout(dataset, &table, to).await?,
+            ),
+            None => (None, None),
+        };
+        let Some(schema) = new.as_ref().or(old.as_ref()).map(arrow_schema) else {
+            continue;
+        };
+        let key_columns =
+            keys.unwrap_or_else(|| schema.fields().iter().map(|f| f.name().clone()).collect());
+        let changes = changes(
+            &ctx,
+            old.as_ref(),
+            new.as_ref(),
+            &schema,
+            filter,
+            &key_columns,
+        )
+        .await?
+        .execute_stream()
+        .await?;
+        diffs.push(ElementDiff {
+            kind,
+            name,
+            from_version: old.map(|d| d.version().version),
+            to_version: new.map(|d| d.version().version),
+            key_columns,
+            changes,
+        });
+    }
+    Ok(diffs)
+}
+
+/// A label or relationship type to compare
+struct Element {
+    kind: &'static str,
+    name: String,
+    /// Dataset backing the element
+    table: String,
+    /// Keeps the element's rows of a shared dataset
+    filter: Option<Expr>,
+    /// Columns rows are matched by; all columns when `None`
+    keys: Option<Vec<String>>,
+}
+
+/// Latest version of the dataset backing `table`, if it exists
+pub(crate) async fn open_latest(namespace: &DirNamespace, table: &str) -> Result<Option<Dataset>> {
+    let mut request = DescribeTableRequest::new();
+    request.id = Some(vec![table.to_string()]);
+    let Ok(response) = namespace.describe_table(request).await else {
+        return Ok(None);
+    };
+    let Some(location) = response.location else {
+        return Ok(None);
+    };
+    let mut builder = lance::dataset::builder::DatasetBuilder::from_uri(&location);
+    if let Some(options) = response.storage_options.filter(|o| !o.is_empty()) {
+        builder = builder.with_storage_options(options);
+    }
+    Ok(builder.load().await.ok())
+}
+
+/// `version` of `latest`, or `None` if the dataset did not exist yet
+async fn checkout(latest: &Dataset, table: &str, version: GraphVersion) -> Result<Option<Dataset>> {
+    let latest_number = latest.version().version;
+    let number = match version {
+        GraphVersion::Latest => latest_number,
+        GraphVersion::Version(0) => return Ok(None),
+        GraphVersion::Version(n) if n > latest_number => {
+            return Err(GraphError::PlanError {
+                message: format!(
+                    "'{}' has no version {}; its latest version is {}",
+                    table, n, latest_number
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+        }
+        GraphVersion::Version(n) => n,
+        GraphVersion::AsOf(time) => {
+            let millis = time
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as i64);
+            let committed = latest
+                .versions()
+                .await?
+                .into_iter()
+                .filter(|v| v.timestamp.timestamp_millis() <= millis)
+                .map(|v| v.version)
+                .max();
+            match committed {
+                Some(n) => n,
+                None => return Ok(None),
+            }
+        }
+    };
+    if number == latest_number {
+        return Ok(Some(latest.clone()));
+    }
+    let dataset =
+        latest
+            .checkout_version(number)
+            .await
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!(
+                    "Failed to check out version {} of '{}': {}",
+                    number, table, e
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+    Ok(Some(dataset))
+}
+
+fn arrow_schema(dataset: &Dataset) -> SchemaRef {
+    Arc::new(ArrowSchema::from(dataset.schema()))
+}
+
+/// Rows of the fragments of `dataset` that `other` does not share
+async fn unshared_rows(dataset: &Dataset, other: Option<&Dataset>) -> Result<Vec<RecordBatch>> {
+    let fragments: Vec<_> = dataset
+        .fragments()
+        .iter()
+        .filter(|fragment| other.is_none_or(|o| !o.fragments().contains(fragment)))
+        .cloned()
+        .collect();
+    if fragments.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut scanner = dataset.scan();
+    scanner.with_fragments(fragments);
+    Ok(scanner.try_into_stream().await?.try_collect().await?)
+}
+
+/// Added, removed and changed rows between `old` and `new`, with the
+/// columns of `schema` (the newer one)
+async fn changes(
+    ctx: &SessionContext,
+    old: Option<&Dataset>,
+    new: Option<&Dataset>,
+    schema: &SchemaRef,
+    filter: Option<Expr>,
+    keys: &[String],
+) -> Result<DataFrame> {
+    let side = |dataset: Option<&Dataset>, other: Option<&Dataset>| async move {
+        let Some(dataset) = dataset else {
+            return Ok::<_, GraphError>(
+                ctx.read_table(Arc::new(MemTable::try_new(schema.clone(), vec![vec![]])?))?,
+            );
+        };
+        let rows = unshared_rows(dataset, other).await?;
+        let table = MemTable::try_new(arrow_schema(dataset), vec![rows])?;
+        Ok(ctx.read_table(Arc::new(table))?)
+    };
+    let mut old_rows = aligned(side(old, new).await?, schema)?;
+    let mut new_rows = aligned(side(new, old).await?, schema)?;
+    if let Some(filter) = filter {
+        old_rows = old_rows.filter(filter.clone())?;
+        new_rows = new_rows.filter(filter)?;
+    }
+
+    let columns: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    let output = |df: DataFrame, kind: ChangeKind| {
+        let mut exprs = vec![lit(kind.as_str()).alias(CHANGE_COLUMN)];
+        exprs.extend(columns.iter().map(|c| ident(*c)));
+        df.select(exprs)
+    };
+
+    let added = output(
+        anti_join(new_rows.clone(), old_rows.clone(), &columns, keys)?,
+        ChangeKind::Added,
+    )?;
+    let removed = output(
+        anti_join(old_rows.clone(), new_rows.clone(), &columns, keys)?,
+        ChangeKind::Removed,
+    )?;
+    let mut diff = added.union(removed)?;
+
+    let differs = columns
+        .iter()
+        .filter(|c| !keys.iter().any(|k| k == *c))
+        .map(|c| {
+            binary_expr(
+                ident(*c),
+                Operator::IsDistinctFrom,
+                ident(format!("{}{}", OTHER_PREFIX, c)),
+            )
+        })
+        .reduce(Expr::or);
+    if let Some(differs) = differs {
+        let changed = new_rows
+            .join_on(
+                renamed(old_rows, &columns)?,
+                JoinType::Inner,
+                key_equality(keys),
+            )?
+            .filter(differs)?;
+        diff = diff.union(output(changed, ChangeKind::Changed)?)?;
+    }
+    Ok(diff)
+}
+
+/// `df` with the columns of `schema`, cast to its types; missing columns are null
+fn aligned(df: DataFrame, schema: &SchemaRef) -> Result<DataFrame> {
+    let exprs = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let expr = match df.schema().field_with_unqualified_name(field.name()) {
+                Ok(existing) if existing.data_type() == field.data_type() => ident(field.name()),
+                Ok(_) => cast(ident(field.name()), field.data_type().clone()),
+                Err(_) => lit(ScalarValue::try_from(field.data_type())?),
+            };
+            Ok(expr.alias(field.name()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(df.select(exprs)?)
+}
+
+/// `df` with every column prefixed by [`OTHER_PREFIX`]
+fn renamed(df: DataFrame, columns: &[&str]) -> Result<DataFrame> {
+    Ok(df.select(
+        columns
+            .iter()
+            .map(|c| ident(*c).alias(format!("{}{}", OTHER_PREFIX, c))),
+    )?)
+}
+
+fn key_equality(keys: &[String]) -> Vec<Expr> {
+    keys.iter()
+        .map(|k| {
+            binary_expr(
+                ident(k),
+                Operator::IsNotDistinctFrom,
+                ident(format!("{}{}", OTHER_PREFIX, k)),
+            )
+        })
+        .collect()
+}
+
+/// Rows of `left` whose key has no match in `right`
+fn anti_join(
+    left: DataFrame,
+    right: DataFrame,
+    columns: &[&str],
+    keys: &[String],
+) -> Result<DataFrame> {
+    Ok(left.join_on(
+        renamed(right, columns)?,
+        JoinType::LeftAnti,
+        key_equality(keys),
+    )?)
+}
+
+/// Fail unless `call` passes two dataset versions to `graph.diff`
+pub(crate) fn check_diff_call(call: &ProcedureCall) -> Result<()> {
+    let versions = call.arguments.len() == 2
+        && call.arguments.iter().all(|arg| {
+            matches!(
+                arg,
+                ValueExpression::Literal(PropertyValue::Integer(n)) if *n >= 0
+            ) || matches!(arg, ValueExpression::Parameter(_))
+        });
+    if !versions {
+        return Err(GraphError::PlanError {
+            message: format!(
+                "{}() takes two dataset versions, e.g. {}(1, 2)",
+                DIFF_PROCEDURE, DIFF_PROCEDURE
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(())
+}
+
+/// Whether `call` is `graph.diff(...)`
+pub(crate) fn is_diff_call(call: &ProcedureCall) -> bool {
+    call.name.eq_ignore_ascii_case(DIFF_PROCEDURE)
+}
+
+/// Fail if `call` is `graph.diff`, which reads the version history of a
+/// namespace's datasets
+pub(crate) fn check_not_diff_call(call: &ProcedureCall) -> Result<()> {
+    if is_diff_call(call) {
+        return Err(GraphError::UnsupportedFeature {
+            feature: format!(
+                "{}() without a namespace; use execute_with_namespace",
+                DIFF_PROCEDURE
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(())
+}
+
+/// Versions passed to `graph.diff`, with parameters bound
+fn call_versions(
+    call: &ProcedureCall,
+    parameters: &HashMap<String, serde_json::Value>,
+) -> Result<(GraphVersion, GraphVersion)> {
+    check_diff_call(call)?;
+    let version = |arg: &ValueExpression| match arg {
+        ValueExpression::Literal(PropertyValue::Integer(n)) => Ok(GraphVersion::Version(*n as u64)),
+        ValueExpression::Parameter(name) => parameters
+            .get(name)
+            .and_then(serde_json::Value::as_u64)
+            .map(GraphVersion::Version)
+            .ok_or_else(|| GraphError::PlanError {
+                message: format!(
+                    "Parameter ${} of {}() must be a dataset version",
+                    name, DIFF_PROCEDURE
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+        _ => unreachable!("checked by check_diff_call"),
+    };
+    Ok((version(&call.arguments[0])?, version(&call.arguments[1])?))
+}
+
+/// Schema of the rows returned by `CALL graph.diff(from, to)`
+///
+/// `key` and `properties` are JSON objects of the row's key columns and of
+/// all its non-null columns.
+pub fn diff_schema() -> SchemaRef {
+    Arc::new(ArrowSchema::new(vec![
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new(CHANGE_COLUMN, DataType::Utf8, false),
+        Field::new("key", DataType::Utf8, false),
+        Field::new("properties", DataType::Utf8, false),
+    ]))
+}
+
+/// Run `CALL graph.diff(from, to)` against the datasets of `namespace`
+pub(crate) async fn diff_procedure(
+    query: &CypherQuery,
+    namespace: &DirNamespace,
+) -> Result<RecordBatch> {
+    let Some(call) = &query.ast().procedure else {
+        return Err(GraphError::PlanError {
+            message: format!("Query is not a {}() call", DIFF_PROCEDURE),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    };
+    let (from, to) = call_versions(call, query.parameters())?;
+
+    let mut columns: [Vec<String>; 5] = Default::default();
+    for element in graph_diff(query.require_config()?, namespace, from, to).await? {
+        let schema = element.changes.schema();
+        let batches: Vec<RecordBatch> = element.changes.try_collect().await?;
+        let batch = concat_batches(&schema, &batches)?;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        let changes = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| GraphError::ExecutionError {
+                message: format!("Column '{}' is not a string column", CHANGE_COLUMN),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let indices: Vec<usize> = (1..batch.num_columns()).collect();
+        for (row, properties) in json_rows(&batch.project(&indices)?)?
+            .into_iter()
+            .enumerate()
+        {
+            let key: serde_json::Map<_, _> = element
+                .key_columns
+                .iter()
+                .filter_map(|k| properties.get(k).map(|v| (k.clone(), v.clone())))
+                .collect();
+            columns[0].push(element.kind.to_string());
+            columns[1].push(element.name.clone());
+            columns[2].push(changes.value(row).to_string());
+            columns[3].push(serde_json::Value::Object(key).to_string());
+            columns[4].push(serde_json::Value::Object(properties).to_string());
+        }
+    }
+    Ok(RecordBatch::try_new(
+        diff_schema(),
+        columns
+            .into_iter()
+            .map(|values| Arc::new(StringArray::from(values)) as _)
+            .collect(),
+    )?)
+}
+
+/// Rows of `batch` as JSON objects of their non-null columns
+fn json_rows(batch: &RecordBatch) -> Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    writer.write(batch)?;
+    writer.finish()?;
+    serde_json::from_slice(&writer.into_inner()).map_err(|e| GraphError::ExecutionError {
+        message: format!("Failed to convert changed rows to JSON: {}", e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_cypher_query;
+
+    fn call(cypher: &str) -> ProcedureCall {
+        parse_cypher_query(cypher).unwrap().procedure.unwrap()
+    }
+
+    #[test]
+    fn test_call_versions() {
+        let parameters = HashMap::from([("from".to_string(), serde_json::json!(3))]);
+        assert_eq!(
+            call_versions(&call("CALL graph.diff(1, 2)"), &parameters).unwrap(),
+            (GraphVersion::Version(1), GraphVersion::Version(2))
+        );
+        assert_eq!(
+            call_versions(&call("CALL GRAPH.DIFF($from, 4)"), &parameters).unwrap(),
+            (GraphVersion::Version(3), GraphVersion::Version(4))
+        );
+
+        assert!(call_versions(&call("CALL graph.diff($to, 4)"), &parameters).is_err());
+        for invalid in ["CALL graph.diff(1)", "CALL graph.diff(1, 'x')"] {
+            let err = check_diff_call(&call(invalid)).unwrap_err();
+            assert!(
+                err.to_string().contains("two dataset versions"),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_rows_skip_nulls() {
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(arrow_array::Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("Alice"), None])),
+            ],
+        )
+        .unwrap();
+        let rows = json_rows(&batch).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(rows[0].clone()),
+            serde_json::json!({"id": 1, "name": "Alice"})
+        );
+        assert_eq!(
+            serde_json::Value::Object(rows[1].clone()),
+            serde_json::json!({"id": 2})
+        );
+    }
+}
@@ -0,0 +1,775 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Graph Projection for Algorithms
+//!
+//! Materializes node and relationship RecordBatches into a compact adjacency
+//! structure (CSR) that graph algorithms iterate over. One projection fixes how
+//! edge weights are derived and normalized and which direction edges are
+//! followed, so every algorithm run on it sees the same weighted graph.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::graph_projection::{
+//!     EdgeWeight, GraphProjection, ProjectionDirection, ProjectionOptions, WeightNormalization,
+//! };
+//!
+//! let options = ProjectionOptions::new("Person", "KNOWS")
+//!     .weight(EdgeWeight::Property("strength".to_string()))
+//!     .normalization(WeightNormalization::MinMax)
+//!     .direction(ProjectionDirection::Undirected);
+//! let projection = GraphProjection::from_batches(&config, &options, &people, &knows)?;
+//! for (neighbor, weight) in projection.neighbors(0) {
+//!     // ...
+//! }
+//! ```
+//!
+//! Relationships an algorithm derives from a projection (e.g. node similarity
+//! or a kNN graph) are collected as [`ComputedRelationships`] and written back
+//! with [`crate::graph_writer::BufferedGraphWriter::write_relationships`].
+
+use crate::ast::DistanceMetric;
+use crate::config::{GraphConfig, RelationshipMapping};
+use crate::datafusion_planner::vector_ops;
+use crate::error::{GraphError, Result};
+use arrow::array::{Array, ArrayRef, Float64Array, Int64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Source of edge weights in a projection
+#[derive(Debug, Clone, PartialEq)]
+pub enum EdgeWeight {
+    /// Every edge has the same weight
+    Constant(f64),
+    /// Read the weight from a numeric relationship property
+    Property(String),
+    /// Derive the weight from the similarity of the endpoints' embeddings
+    Similarity {
+        /// Vector column on the node dataset
+        column: String,
+        /// Metric used to compare the two endpoint vectors
+        metric: DistanceMetric,
+    },
+}
+
+impl Default for EdgeWeight {
+    fn default() -> Self {
+        Self::Constant(1.0)
+    }
+}
+
+/// Normalization applied to edge weights after they are derived
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WeightNormalization {
+    /// Keep weights as derived
+    #[default]
+    None,
+    /// `ln(1 + w)`, dampening heavy-tailed weights; requires `w > -1`
+    Log,
+    /// Rescale into `[0, 1]` using the smallest and largest weight
+    MinMax,
+}
+
+/// Which direction relationships are followed in the projection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProjectionDirection {
+    /// Source → target, as stored
+    #[default]
+    Natural,
+    /// Target → source
+    Reverse,
+    /// Both directions
+    Undirected,
+}
+
+/// Options describing which graph to project and how
+#[derive(Debug, Clone)]
+pub struct ProjectionOptions {
+    node_label: String,
+    relationship_type: String,
+    weight: EdgeWeight,
+    normalization: WeightNormalization,
+    direction: ProjectionDirection,
+}
+
+impl ProjectionOptions {
+    /// Project nodes of `node_label` connected by `relationship_type`
+    pub fn new(node_label: &str, relationship_type: &str) -> Self {
+        Self {
+            node_label: node_label.to_string(),
+            relationship_type: relationship_type.to_string(),
+            weight: EdgeWeight::default(),
+            normalization: WeightNormalization::default(),
+            direction: ProjectionDirection::default(),
+        }
+    }
+
+    /// Set the edge weight source (default: constant 1.0)
+    pub fn weight(mut self, weight: EdgeWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Set the weight normalization (default: none)
+    pub fn normalization(mut self, normalization: WeightNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Set the direction edges are followed (default: natural)
+    pub fn direction(mut self, direction: ProjectionDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Get the projected node label
+    pub fn node_label(&self) -> &str {
+        &self.node_label
+    }
+
+    /// Get the projected relationship type
+    pub fn relationship_type(&self) -> &str {
+        &self.relationship_type
+    }
+
+    /// Get the edge weight source
+    pub fn get_weight(&self) -> &EdgeWeight {
+        &self.weight
+    }
+
+    /// Get the weight normalization
+    pub fn get_normalization(&self) -> WeightNormalization {
+        self.normalization
+    }
+
+    /// Get the projection direction
+    pub fn get_direction(&self) -> ProjectionDirection {
+        self.direction
+    }
+}
+
+/// Weighted adjacency of a projected graph in compressed sparse row form
+///
+/// Nodes are addressed by dense indices `0..node_count()`; use
+/// [`GraphProjection::node_id`] and [`GraphProjection::node_index`] to map
+/// between indices and the node ids stored in the dataset.
+#[derive(Debug, Clone)]
+pub struct GraphProjection {
+    node_ids: Vec<i64>,
+    index: HashMap<i64, usize>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<f64>,
+}
+
+impl GraphProjection {
+    /// Build a projection from a node batch and a relationship batch
+    ///
+    /// Id columns come from the label and relationship mappings in `config`.
+    /// Relationships whose endpoints are null or missing from `nodes` are skipped.
+    pub fn from_batches(
+        config: &GraphConfig,
+        options: &ProjectionOptions,
+        nodes: &RecordBatch,
+        relationships: &RecordBatch,
+    ) -> Result<Self> {
+        let node_map = config
+            .get_node_mapping(&options.node_label)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!("No mapping found for node label: {}", options.node_label),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let rel_map = config
+            .get_relationship_mapping(&options.relationship_type)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!(
+                    "No mapping found for relationship type: {}",
+                    options.relationship_type
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let node_id_array = int64_column(nodes, &node_map.id_field)?;
+        let mut node_ids = Vec::with_capacity(node_id_array.len());
+        let mut index = HashMap::with_capacity(node_id_array.len());
+        for i in 0..node_id_array.len() {
+            if node_id_array.is_null(i) {
+                continue;
+            }
+            let id = node_id_array.value(i);
+            index.entry(id).or_insert_with(|| {
+                node_ids.push(id);
+                node_ids.len() - 1
+            });
+        }
+
+        let sources = int64_column(relationships, &rel_map.source_id_field)?;
+        let targets = int64_column(relationships, &rel_map.target_id_field)?;
+        let raw_weights = Self::edge_weights(
+            options,
+            nodes,
+            relationships,
+            &node_id_array,
+            (&sources, &targets),
+        )?;
+
+        // Resolve endpoints to dense indices, dropping dangling relationships
+        let mut edges: Vec<(usize, usize, f64)> = Vec::with_capacity(relationships.num_rows());
+        for (row, weight) in raw_weights.into_iter().enumerate() {
+            if sources.is_null(row) || targets.is_null(row) {
+                continue;
+            }
+            let (Some(&src), Some(&dst)) = (
+                index.get(&sources.value(row)),
+                index.get(&targets.value(row)),
+            ) else {
+                continue;
+            };
+            match options.direction {
+                ProjectionDirection::Natural => edges.push((src, dst, weight)),
+                ProjectionDirection::Reverse => edges.push((dst, src, weight)),
+                ProjectionDirection::Undirected => {
+                    edges.push((src, dst, weight));
+                    if src != dst {
+                        edges.push((dst, src, weight));
+                    }
+                }
+            }
+        }
+
+        normalize_weights(&mut edges, options.normalization)?;
+
+        // Counting sort into CSR layout, preserving input order within a node
+        let mut offsets = vec![0usize; node_ids.len() + 1];
+        for (src, _, _) in &edges {
+            offsets[src + 1] += 1;
+        }
+        let mut running = 0;
+        for offset in offsets.iter_mut() {
+            running += *offset;
+            *offset = running;
+        }
+        let mut cursor = offsets.clone();
+        let mut csr_targets = vec![0usize; edges.len()];
+        let mut csr_weights = vec![0f64; edges.len()];
+        for (src, dst, weight) in edges {
+            let slot = cursor[src];
+            csr_targets[slot] = dst;
+            csr_weights[slot] = weight;
+            cursor[src] += 1;
+        }
+
+        Ok(Self {
+            node_ids,
+            index,
+            offsets,
+            targets: csr_targets,
+            weights: csr_weights,
+        })
+    }
+
+    /// Number of projected nodes
+    pub fn node_count(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    /// Number of projected (directed) edges
+    ///
+    /// Undirected projections store each relationship once per direction.
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Node id stored in the dataset for a dense node index
+    pub fn node_id(&self, node: usize) -> Option<i64> {
+        self.node_ids.get(node).copied()
+    }
+
+    /// Dense node index for a node id stored in the dataset
+    pub fn node_index(&self, id: i64) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
+    /// Number of outgoing edges of a node in the projected direction
+    pub fn degree(&self, node: usize) -> usize {
+        self.offsets[node + 1] - self.offsets[node]
+    }
+
+    /// Neighbors of a node with the corresponding edge weights
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.offsets[node]..self.offsets[node + 1];
+        self.targets[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.weights[range].iter().copied())
+    }
+
+    /// Derive one raw weight per relationship row
+    fn edge_weights(
+        options: &ProjectionOptions,
+        nodes: &RecordBatch,
+        relationships: &RecordBatch,
+        node_id_array: &Int64Array,
+        (sources, targets): (&Int64Array, &Int64Array),
+    ) -> Result<Vec<f64>> {
+        let rows = relationships.num_rows();
+        match &options.weight {
+            EdgeWeight::Constant(value) => Ok(vec![*value; rows]),
+            EdgeWeight::Property(property) => {
+                let array = column(relationships, property)?;
+                let values =
+                    cast(&array, &DataType::Float64).map_err(|e| GraphError::ExecutionError {
+                        message: format!("Weight property '{}' is not numeric: {}", property, e),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })?;
+                let values = values
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .expect("cast to Float64 yields Float64Array");
+                if values.null_count() > 0 {
+                    return Err(GraphError::ExecutionError {
+                        message: format!("Weight property '{}' contains nulls", property),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                Ok(values.values().to_vec())
+            }
+            EdgeWeight::Similarity {
+                column: vector_column,
+                metric,
+            } => {
+                let vectors = vector_ops::extract_vectors(&column(nodes, vector_column)?)?;
+                let mut by_id: HashMap<i64, &Vec<f32>> = HashMap::new();
+                for (i, vector) in vectors.iter().enumerate() {
+                    if !node_id_array.is_null(i) {
+                        by_id.insert(node_id_array.value(i), vector);
+                    }
+                }
+
+                let endpoint = |ids: &Int64Array, row: usize| {
+                    (!ids.is_null(row))
+                        .then(|| by_id.get(&ids.value(row)))
+                        .flatten()
+                };
+                Ok((0..rows)
+                    .map(
+                        |row| match (endpoint(sources, row), endpoint(targets, row)) {
+                            (Some(a), Some(b)) => vector_ops::compute_vector_similarities(
+                                std::slice::from_ref(*a),
+                                b,
+                                metric,
+                            )[0] as f64,
+                            // Dangling relationships are dropped when building adjacency
+                            _ => 0.0,
+                        },
+                    )
+                    .collect())
+            }
+        }
+    }
+}
+
+/// How [`crate::graph_writer::BufferedGraphWriter::write_relationships`] treats
+/// an existing relationship dataset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RelationshipWriteMode {
+    /// Add the relationships to the dataset, creating it if needed
+    #[default]
+    Append,
+    /// Replace the dataset's contents with the relationships
+    Overwrite,
+}
+
+/// Where and how relationships computed by an algorithm are stored
+#[derive(Debug, Clone)]
+pub struct RelationshipOutput {
+    relationship_type: String,
+    source_field: String,
+    target_field: String,
+    weight_property: String,
+    mode: RelationshipWriteMode,
+}
+
+impl RelationshipOutput {
+    /// Store relationships of `relationship_type` in `src_id` / `dst_id` /
+    /// `weight` columns, appending to existing ones
+    pub fn new(relationship_type: &str) -> Self {
+        Self {
+            relationship_type: relationship_type.to_string(),
+            source_field: "src_id".to_string(),
+            target_field: "dst_id".to_string(),
+            weight_property: "weight".to_string(),
+            mode: RelationshipWriteMode::default(),
+        }
+    }
+
+    /// Set the columns holding the source and target node ids
+    pub fn endpoint_fields(mut self, source_field: &str, target_field: &str) -> Self {
+        self.source_field = source_field.to_string();
+        self.target_field = target_field.to_string();
+        self
+    }
+
+    /// Set the relationship property holding the weight (default: `weight`)
+    pub fn weight_property(mut self, property: &str) -> Self {
+        self.weight_property = property.to_string();
+        self
+    }
+
+    /// Set how an existing dataset is treated (default: append)
+    pub fn mode(mut self, mode: RelationshipWriteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Get the relationship type
+    pub fn relationship_type(&self) -> &str {
+        &self.relationship_type
+    }
+
+    /// Get the source node id column
+    pub fn source_field(&self) -> &str {
+        &self.source_field
+    }
+
+    /// Get the target node id column
+    pub fn target_field(&self) -> &str {
+        &self.target_field
+    }
+
+    /// Get the weight property
+    pub fn get_weight_property(&self) -> &str {
+        &self.weight_property
+    }
+
+    /// Get the write mode
+    pub fn get_mode(&self) -> RelationshipWriteMode {
+        self.mode
+    }
+
+    /// Mapping registering the written relationships in a graph configuration
+    pub fn mapping(&self) -> RelationshipMapping {
+        RelationshipMapping::new(
+            self.relationship_type.as_str(),
+            self.source_field.as_str(),
+            self.target_field.as_str(),
+        )
+        .with_properties(vec![self.weight_property.clone()])
+    }
+}
+
+/// Weighted relationships produced by an algorithm, e.g. node similarity or
+/// kNN graph construction, addressed by node id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComputedRelationships {
+    pub sources: Vec<i64>,
+    pub targets: Vec<i64>,
+    pub weights: Vec<f64>,
+}
+
+impl ComputedRelationships {
+    /// Translate `(source, target, weight)` triples of dense node indices of
+    /// `projection` into node ids
+    pub fn from_indices(
+        projection: &GraphProjection,
+        edges: impl IntoIterator<Item = (usize, usize, f64)>,
+    ) -> Result<Self> {
+        let mut computed = Self::default();
+        for (src, dst, weight) in edges {
+            let (Some(source), Some(target)) = (projection.node_id(src), projection.node_id(dst))
+            else {
+                return Err(GraphError::ExecutionError {
+                    message: format!(
+                        "Computed relationship ({}, {}) references a node outside the projection",
+                        src, dst
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            };
+            computed.push(source, target, weight);
+        }
+        Ok(computed)
+    }
+
+    /// Add a relationship between two node ids
+    pub fn push(&mut self, source: i64, target: i64, weight: f64) {
+        self.sources.push(source);
+        self.targets.push(target);
+        self.weights.push(weight);
+    }
+
+    /// Number of relationships
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Relationship batch with the columns named by `output`
+    pub fn to_record_batch(&self, output: &RelationshipOutput) -> Result<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(&output.source_field, DataType::Int64, false),
+            Field::new(&output.target_field, DataType::Int64, false),
+            Field::new(&output.weight_property, DataType::Float64, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(self.sources.clone())),
+                Arc::new(Int64Array::from(self.targets.clone())),
+                Arc::new(Float64Array::from(self.weights.clone())),
+            ],
+        )
+        .map_err(|e| GraphError::ExecutionError {
+            message: format!(
+                "Invalid computed relationships for '{}': {}",
+                output.relationship_type, e
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+}
+
+/// Apply the configured normalization to projected edge weights in place
+fn normalize_weights(
+    edges: &mut [(usize, usize, f64)],
+    normalization: WeightNormalization,
+) -> Result<()> {
+    match normalization {
+        WeightNormalization::None => {}
+        WeightNormalization::Log => {
+            for (_, _, weight) in edges.iter_mut() {
+                if *weight <= -1.0 {
+                    return Err(GraphError::ExecutionError {
+                        message: format!(
+                            "Log normalization requires weights greater than -1, got {}",
+                            weight
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                *weight = weight.ln_1p();
+            }
+        }
+        WeightNormalization::MinMax => {
+            let (min, max) = edges
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, _, w)| {
+                    (lo.min(*w), hi.max(*w))
+                });
+            let range = max - min;
+            for (_, _, weight) in edges.iter_mut() {
+                // All-equal weights collapse to 1.0 rather than dividing by zero
+                *weight = if range > 0.0 {
+                    (*weight - min) / range
+                } else {
+                    1.0
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Look up a column case-insensitively
+fn column(batch: &RecordBatch, name: &str) -> Result<ArrayRef> {
+    let schema = batch.schema();
+    let (idx, _) = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .find(|(_, f)| f.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| GraphError::ExecutionError {
+            message: format!("Column '{}' not found in projection input", name),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    Ok(batch.column(idx).clone())
+}
+
+/// Read an id column as Int64
+fn int64_column(batch: &RecordBatch, name: &str) -> Result<Int64Array> {
+    let array =
+        cast(&column(batch, name)?, &DataType::Int64).map_err(|e| GraphError::ExecutionError {
+            message: format!("Id column '{}' cannot be read as Int64: {}", name, e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    Ok(array
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .expect("cast to Int64 yields Int64Array")
+        .clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{FixedSizeListArray, Float32Array};
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src", "dst")
+            .build()
+            .unwrap()
+    }
+
+    fn nodes() -> RecordBatch {
+        let values = Float32Array::from(vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        let embedding = FixedSizeListArray::try_new(
+            Arc::new(Field::new("item", DataType::Float32, true)),
+            2,
+            Arc::new(values),
+            None,
+        )
+        .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("embedding", embedding.data_type().clone(), false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+                Arc::new(embedding),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn knows() -> RecordBatch {
+        // 10 -> 20 (w=1), 10 -> 30 (w=3), 20 -> 30 (w=5), 30 -> 99 (dangling)
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("src", DataType::Int64, false),
+            Field::new("dst", DataType::Int64, false),
+            Field::new("strength", DataType::Int32, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![10, 10, 20, 30])),
+                Arc::new(Int64Array::from(vec![20, 30, 30, 99])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 3, 5, 7])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn neighbor_ids(projection: &GraphProjection, id: i64) -> Vec<(i64, f64)> {
+        let node = projection.node_index(id).unwrap();
+        projection
+            .neighbors(node)
+            .map(|(n, w)| (projection.node_id(n).unwrap(), w))
+            .collect()
+    }
+
+    #[test]
+    fn test_natural_projection_skips_dangling_edges() {
+        let options = ProjectionOptions::new("Person", "KNOWS");
+        let projection =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap();
+
+        assert_eq!(projection.node_count(), 3);
+        assert_eq!(projection.edge_count(), 3);
+        assert_eq!(neighbor_ids(&projection, 10), vec![(20, 1.0), (30, 1.0)]);
+        assert_eq!(projection.degree(projection.node_index(30).unwrap()), 0);
+    }
+
+    #[test]
+    fn test_reverse_and_undirected_projection() {
+        let reverse =
+            ProjectionOptions::new("Person", "KNOWS").direction(ProjectionDirection::Reverse);
+        let projection =
+            GraphProjection::from_batches(&config(), &reverse, &nodes(), &knows()).unwrap();
+        assert_eq!(neighbor_ids(&projection, 30), vec![(10, 1.0), (20, 1.0)]);
+        assert!(neighbor_ids(&projection, 10).is_empty());
+
+        let undirected =
+            ProjectionOptions::new("Person", "KNOWS").direction(ProjectionDirection::Undirected);
+        let projection =
+            GraphProjection::from_batches(&config(), &undirected, &nodes(), &knows()).unwrap();
+        assert_eq!(projection.edge_count(), 6);
+        assert_eq!(neighbor_ids(&projection, 20), vec![(10, 1.0), (30, 1.0)]);
+    }
+
+    #[test]
+    fn test_property_weights_with_min_max_normalization() {
+        let options = ProjectionOptions::new("Person", "KNOWS")
+            .weight(EdgeWeight::Property("strength".to_string()))
+            .normalization(WeightNormalization::MinMax);
+        let projection =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap();
+
+        assert_eq!(neighbor_ids(&projection, 10), vec![(20, 0.0), (30, 0.5)]);
+        assert_eq!(neighbor_ids(&projection, 20), vec![(30, 1.0)]);
+    }
+
+    #[test]
+    fn test_log_normalization() {
+        let options = ProjectionOptions::new("Person", "KNOWS")
+            .weight(EdgeWeight::Property("strength".to_string()))
+            .normalization(WeightNormalization::Log);
+        let projection =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap();
+
+        let weights = neighbor_ids(&projection, 10);
+        assert!((weights[0].1 - 2f64.ln()).abs() < 1e-9);
+        assert!((weights[1].1 - 4f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_similarity_weights() {
+        let options = ProjectionOptions::new("Person", "KNOWS").weight(EdgeWeight::Similarity {
+            column: "embedding".to_string(),
+            metric: DistanceMetric::Cosine,
+        });
+        let projection =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap();
+
+        // 10 = [1, 0], 20 = [0, 1], 30 = [1, 0]
+        assert_eq!(neighbor_ids(&projection, 10), vec![(20, 0.0), (30, 1.0)]);
+    }
+
+    #[test]
+    fn test_missing_weight_property() {
+        let options = ProjectionOptions::new("Person", "KNOWS")
+            .weight(EdgeWeight::Property("missing".to_string()));
+        let err =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_computed_relationships_batch() {
+        let options = ProjectionOptions::new("Person", "KNOWS");
+        let projection =
+            GraphProjection::from_batches(&config(), &options, &nodes(), &knows()).unwrap();
+        let (a, b) = (
+            projection.node_index(10).unwrap(),
+            projection.node_index(30).unwrap(),
+        );
+        let computed = ComputedRelationships::from_indices(&projection, vec![(a, b, 0.9)]).unwrap();
+        assert_eq!(computed.sources, vec![10]);
+        assert_eq!(computed.targets, vec![30]);
+
+        let output = RelationshipOutput::new("SIMILAR")
+            .endpoint_fields("src", "dst")
+            .weight_property("score");
+        let batch = computed.to_record_batch(&output).unwrap();
+        let names: Vec<&str> = batch
+            .schema_ref()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(names, vec!["src", "dst", "score"]);
+        assert_eq!(output.mapping().property_fields, vec!["score".to_string()]);
+
+        assert!(ComputedRelationships::from_indices(&projection, vec![(a, 7, 1.0)]).is_err());
+    }
+}
@@ -0,0 +1,1663 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Buffered graph writes
+//!
+//! Every Lance commit creates a new dataset version, so committing created
+//! nodes and relationships one row at a time is prohibitively slow. The
+//! [`BufferedGraphWriter`] accumulates created rows per dataset and commits
+//! them in micro-batches once a size or age threshold is reached.
+//!
+//! Datasets are laid out like [`crate::DirNamespace`] expects them:
+//! `<base_uri>/<table>.lance`, where `<table>` is the node label (or its
+//! shared source table) or the relationship type.
+//! [`BufferedGraphWriter::with_namespace`] writes to the locations a
+//! namespace resolves the tables to instead, opening and writing every
+//! dataset with the namespace's storage options for its table.
+//!
+//! # Durability
+//!
+//! Writes are at-least-once: rows are only dropped from the buffer after the
+//! Lance commit holding them succeeded, and a failed flush keeps them for the
+//! next attempt. A commit that succeeds but is not acknowledged (e.g. the
+//! process dies right after) can be retried by the caller, duplicating rows.
+//! Buffered rows are lost if the writer is dropped without [`BufferedGraphWriter::flush`].
+//! An append that loses a race with another writer's commit is retried on
+//! the new latest version; other commits rejected as conflicting fail with
+//! [`GraphError::WriteConflict`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::graph_writer::{BufferedGraphWriter, WriterOptions};
+//! use std::time::Duration;
+//!
+//! let options = WriterOptions::default()
+//!     .with_max_buffered_rows(50_000)
+//!     .with_max_buffer_age(Duration::from_secs(5));
+//! let mut writer = BufferedGraphWriter::new(config, "s3://bucket/graph", options);
+//! writer.create_nodes("Person", people).await?;
+//! writer.create_relationships("KNOWS", knows).await?;
+//! writer.flush().await?;
+//!
+//! // Refresh embeddings in place: update matched nodes, insert new ones
+//! let stats = writer.upsert_nodes("Person", "id", embeddings).await?;
+//!
+//! // Move edges pointing at missing people aside
+//! let validation = EdgeValidation::new("Person", "Person").with_quarantine("KNOWS_orphans");
+//! let report = writer.validate_edges("KNOWS", &validation).await?;
+//!
+//! // Persist a kNN graph computed on a projection as SIMILAR relationships
+//! let output = RelationshipOutput::new("SIMILAR").weight_property("score");
+//! writer.write_relationships(&knn, &output).await?;
+//! ```
+
+use crate::config::{GraphConfig, NodeMapping};
+use crate::conflict;
+use crate::error::{GraphError, Result};
+use crate::graph_projection::{ComputedRelationships, RelationshipOutput, RelationshipWriteMode};
+use arrow::compute::{concat_batches, take_record_batch};
+use arrow::row::{RowConverter, SortField};
+use arrow_array::{RecordBatch, RecordBatchIterator, UInt32Array};
+use arrow_schema::{ArrowError, DataType, Schema as ArrowSchema, SchemaRef};
+use datafusion::execution::context::SessionContext;
+use datafusion::functions_aggregate::expr_fn::{count, first_value, last_value, max, min, sum};
+use datafusion::logical_expr::{cast, lit, Expr, JoinType};
+use datafusion::prelude::ident;
+use datafusion::scalar::ScalarValue;
+use lance::datafusion::LanceTableProvider;
+use lance::dataset::builder::DatasetBuilder;
+use lance::dataset::{
+    Dataset, DeleteBuilder, MergeInsertBuilder, WhenMatched, WhenNotMatched, WriteMode,
+    WriteParams, ROW_ID,
+};
+use lance::io::ObjectStoreParams;
+use lance_graph_catalog::DirNamespace;
+use lance_index::scalar::{BuiltinIndexType, ScalarIndexParams};
+use lance_index::{DatasetIndexExt, IndexType};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Flush thresholds for [`BufferedGraphWriter`]
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    /// Flush a dataset once this many rows are buffered for it
+    pub max_buffered_rows: usize,
+    /// Flush a dataset once its oldest buffered row is this old
+    pub max_buffer_age: Duration,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            max_buffered_rows: 10_000,
+            max_buffer_age: Duration::from_secs(1),
+        }
+    }
+}
+
+impl WriterOptions {
+    pub fn with_max_buffered_rows(mut self, rows: usize) -> Self {
+        self.max_buffered_rows = rows;
+        self
+    }
+
+    pub fn with_max_buffer_age(mut self, age: Duration) -> Self {
+        self.max_buffer_age = age;
+        self
+    }
+}
+
+/// Row counts of an [`BufferedGraphWriter::upsert_nodes`] call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UpsertStats {
+    /// Rows whose key was not present yet
+    pub inserted: u64,
+    /// Existing rows replaced by a row with the same key
+    pub updated: u64,
+}
+
+/// How [`BufferedGraphWriter::deduplicate_edges`] combines a property of parallel edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeAggregation {
+    /// Value of the earliest written edge
+    #[default]
+    First,
+    /// Value of the latest written edge
+    Last,
+    Sum,
+    Min,
+    Max,
+}
+
+/// Row counts of a [`BufferedGraphWriter::deduplicate_edges`] call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Edges before deduplication
+    pub before: u64,
+    /// Edges after deduplication, one per node pair
+    pub after: u64,
+}
+
+/// Endpoint labels and options of a [`BufferedGraphWriter::validate_edges`] run
+#[derive(Debug, Clone)]
+pub struct EdgeValidation {
+    source_label: String,
+    target_label: String,
+    sample_size: usize,
+    quarantine: Option<String>,
+}
+
+impl EdgeValidation {
+    /// Check that sources are `source_label` nodes and targets `target_label` nodes
+    pub fn new(source_label: &str, target_label: &str) -> Self {
+        Self {
+            source_label: source_label.to_string(),
+            target_label: target_label.to_string(),
+            sample_size: 10,
+            quarantine: None,
+        }
+    }
+
+    /// Return at most this many orphaned edges in the report (default: 10)
+    pub fn with_sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_size = sample_size;
+        self
+    }
+
+    /// Move orphaned edges out of the relationship dataset into `table`
+    pub fn with_quarantine(mut self, table: &str) -> Self {
+        self.quarantine = Some(table.to_string());
+        self
+    }
+}
+
+/// Result of a [`BufferedGraphWriter::validate_edges`] run
+#[derive(Debug, Clone)]
+pub struct EdgeValidationReport {
+    /// Edges checked
+    pub edges: u64,
+    /// Edges whose source key matches no source node
+    pub missing_source: u64,
+    /// Edges whose target key matches no target node
+    pub missing_target: u64,
+    /// Edges with at least one dangling endpoint
+    pub orphaned: u64,
+    /// Some of the orphaned edges, with all their columns
+    pub samples: RecordBatch,
+    /// Edges moved to the quarantine dataset
+    pub quarantined: u64,
+}
+
+/// Rows waiting to be committed to one dataset
+struct TableBuffer {
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    rows: usize,
+    oldest: Instant,
+}
+
+/// Accumulates created nodes and relationships and commits them in micro-batches
+pub struct BufferedGraphWriter {
+    config: GraphConfig,
+    namespace: DirNamespace,
+    options: WriterOptions,
+    buffers: HashMap<String, TableBuffer>,
+}
+
+impl BufferedGraphWriter {
+    pub fn new(config: GraphConfig, base_uri: impl Into<String>, options: WriterOptions) -> Self {
+        Self::with_namespace(config, DirNamespace::new(base_uri), options)
+    }
+
+    /// Write to the datasets `namespace` resolves the tables to
+    pub fn with_namespace(
+        config: GraphConfig,
+        namespace: DirNamespace,
+        options: WriterOptions,
+    ) -> Self {
+        Self {
+            config,
+            namespace,
+            options,
+            buffers: HashMap::new(),
+        }
+    }
+
+    /// Buffer created nodes of `label`, flushing the dataset if a threshold is hit
+    pub async fn create_nodes(&mut self, label: &str, batch: RecordBatch) -> Result<()> {
+        let table = self.writable_node_table(label, &batch)?;
+        self.buffer(table, batch).await
+    }
+
+    /// Buffer created relationships of `rel_type`, flushing the dataset if a threshold is hit
+    pub async fn create_relationships(&mut self, rel_type: &str, batch: RecordBatch) -> Result<()> {
+        let table = self.writable_relationship_table(rel_type, &batch)?;
+        self.buffer(table, batch).await
+    }
+
+    /// Insert the nodes of `batch` whose `keys` match no row of the dataset
+    /// of `label`; returns the number of nodes inserted
+    pub(crate) async fn merge_nodes(
+        &mut self,
+        label: &str,
+        keys: Vec<String>,
+        batch: RecordBatch,
+    ) -> Result<u64> {
+        let table = self.writable_node_table(label, &batch)?;
+        self.insert_missing(table, keys, batch).await
+    }
+
+    /// Insert the relationships of `batch` whose `keys` match no row of the
+    /// dataset of `rel_type`; returns the number of relationships inserted
+    pub(crate) async fn merge_relationships(
+        &mut self,
+        rel_type: &str,
+        keys: Vec<String>,
+        batch: RecordBatch,
+    ) -> Result<u64> {
+        let table = self.writable_relationship_table(rel_type, &batch)?;
+        self.insert_missing(table, keys, batch).await
+    }
+
+    /// Merge `batch` into the node dataset of `label` by `key_column`
+    ///
+    /// Rows whose key matches an existing node replace it; the others are
+    /// inserted. Uses Lance merge-insert, so refreshing e.g. embeddings costs
+    /// one commit regardless of how many nodes change. Rows of the dataset
+    /// still buffered by this writer are committed first.
+    pub async fn upsert_nodes(
+        &mut self,
+        label: &str,
+        key_column: &str,
+        batch: RecordBatch,
+    ) -> Result<UpsertStats> {
+        let table = self.writable_node_table(label, &batch)?;
+        require_columns(&batch, &[key_column], label)?;
+        self.flush_tables(vec![table.clone()]).await?;
+
+        self.merge_insert(&table, vec![key_column.to_string()], batch)
+            .await
+    }
+
+    /// Collapse parallel edges of `rel_type` into one edge per node pair
+    ///
+    /// Maintenance routine for relationship datasets written before parallel
+    /// edges were disallowed (or by other writers). Property columns are
+    /// combined with the aggregation given for them in `aggregations`,
+    /// defaulting to [`EdgeAggregation::First`]. The dataset is only rewritten
+    /// when duplicates exist.
+    pub async fn deduplicate_edges(
+        &mut self,
+        rel_type: &str,
+        aggregations: &HashMap<String, EdgeAggregation>,
+    ) -> Result<DedupStats> {
+        let mapping = self
+            .config
+            .get_relationship_mapping(rel_type)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!(
+                    "Cannot deduplicate unknown relationship type '{}'",
+                    rel_type
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let table = mapping.relationship_type.clone();
+        let keys: Vec<String> = mapping
+            .edge_key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        self.flush_tables(vec![table.clone()]).await?;
+
+        let dataset = Arc::new(self.open(&table).await?);
+        let before = dataset.count_rows(None).await? as u64;
+        let schema = ArrowSchema::from(dataset.schema());
+        let is_key = |name: &str| keys.iter().any(|k| k.eq_ignore_ascii_case(name));
+        let row_order = || vec![ident(ROW_ID).sort(true, false)];
+
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "edges",
+            Arc::new(LanceTableProvider::new(dataset.clone(), true, false)),
+        )?;
+        let group_by: Vec<Expr> = schema
+            .fields()
+            .iter()
+            .filter(|f| is_key(f.name()))
+            .map(|f| ident(f.name()))
+            .collect();
+        let aggregates: Vec<Expr> = schema
+            .fields()
+            .iter()
+            .filter(|f| !is_key(f.name()))
+            .map(|f| {
+                let column = ident(f.name());
+                let aggregate = match aggregations.get(f.name()).copied().unwrap_or_default() {
+                    EdgeAggregation::First => first_value(column, row_order()),
+                    EdgeAggregation::Last => last_value(column, row_order()),
+                    EdgeAggregation::Sum => sum(column),
+                    EdgeAggregation::Min => min(column),
+                    EdgeAggregation::Max => max(column),
+                };
+                // Keep the column type (e.g. SUM widens integers)
+                cast(aggregate, f.data_type().clone()).alias(f.name())
+            })
+            .collect();
+        let columns: Vec<Expr> = schema.fields().iter().map(|f| ident(f.name())).collect();
+        let batches = ctx
+            .table("edges")
+            .await?
+            .aggregate(group_by, aggregates)?
+            .select(columns)?
+            .collect()
+            .await?;
+
+        let after: u64 = batches.iter().map(|b| b.num_rows() as u64).sum();
+        if after < before {
+            let schema = batches[0].schema();
+            let reader =
+                RecordBatchIterator::new(batches.into_iter().map(Ok::<_, ArrowError>), schema);
+            Dataset::write(
+                reader,
+                self.table_uri(&table).as_str(),
+                Some(self.write_params(&table, WriteMode::Overwrite)),
+            )
+            .await?;
+        }
+        Ok(DedupStats { before, after })
+    }
+
+    /// Find edges of `rel_type` whose endpoints reference no existing node
+    ///
+    /// An edge is orphaned when its source key matches no node of the
+    /// validation's source label, or its target key none of the target label;
+    /// null keys and nodes marked in the label's soft-delete column never
+    /// match. With a quarantine table, orphaned edges are appended to that
+    /// dataset and then deleted from the relationship dataset by their keys,
+    /// in a commit based on the version that was validated. If another
+    /// writer changed the same rows in between, the delete fails with
+    /// [`GraphError::WriteConflict`] and the quarantined copies stay behind.
+    /// Rows of the involved datasets still buffered by this writer are
+    /// committed first.
+    pub async fn validate_edges(
+        &mut self,
+        rel_type: &str,
+        validation: &EdgeValidation,
+    ) -> Result<EdgeValidationReport> {
+        let mapping = self
+            .config
+            .get_relationship_mapping(rel_type)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!("Cannot validate unknown relationship type '{}'", rel_type),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?
+            .clone();
+        let source =
+            self.endpoint_mapping(&validation.source_label, &mapping.source_key_columns())?;
+        let target =
+            self.endpoint_mapping(&validation.target_label, &mapping.target_key_columns())?;
+        let table = mapping.relationship_type.clone();
+        let mut tables = vec![
+            table.clone(),
+            source.table_name().to_string(),
+            target.table_name().to_string(),
+        ];
+        tables.sort();
+        tables.dedup();
+        self.flush_tables(tables).await?;
+
+        let ctx = SessionContext::new();
+        let dataset = Arc::new(self.open(&table).await?);
+        ctx.register_table(
+            "edges",
+            Arc::new(LanceTableProvider::new(dataset.clone(), false, false)),
+        )?;
+        let edges = ctx.table("edges").await?;
+        let columns: Vec<Expr> = edges
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| ident(f.name()))
+            .collect();
+
+        // Mark every edge with whether each endpoint resolved
+        let mut annotated = edges;
+        for (side, node_mapping, edge_keys) in [
+            ("source", &source, mapping.source_key_columns()),
+            ("target", &target, mapping.target_key_columns()),
+        ] {
+            let nodes_table = format!("{}_nodes", side);
+            let dataset = self.open(node_mapping.table_name()).await?;
+            ctx.register_table(
+                nodes_table.as_str(),
+                Arc::new(LanceTableProvider::new(Arc::new(dataset), false, false)),
+            )?;
+            let mut nodes = ctx.table(nodes_table.as_str()).await?;
+            if let Some(column) = &node_mapping.label_column {
+                nodes = nodes.filter(ident(column).eq(lit(node_mapping.label.as_str())))?;
+            }
+            // The column only exists once something was deleted
+            if let Some(column) = node_mapping
+                .soft_delete_column
+                .as_deref()
+                .filter(|column| nodes.schema().field_with_unqualified_name(column).is_ok())
+            {
+                nodes = nodes.filter(ident(column).is_null())?;
+            }
+            let aliases: Vec<String> = (0..edge_keys.len())
+                .map(|i| format!("__{}_key_{}", side, i))
+                .collect();
+            let mut keys: Vec<Expr> = node_mapping
+                .key_columns()
+                .into_iter()
+                .zip(&aliases)
+                .map(|(column, alias)| ident(column).alias(alias))
+                .collect();
+            keys.push(lit(true).alias(format!("__{}_found", side)));
+            let nodes = nodes.select(keys)?.distinct()?;
+            let aliases: Vec<&str> = aliases.iter().map(String::as_str).collect();
+            annotated = annotated.join(nodes, JoinType::Left, &edge_keys, &aliases, None)?;
+        }
+
+        let source_missing = ident("__source_found").is_null();
+        let target_missing = ident("__target_found").is_null();
+        let orphaned = source_missing.clone().or(target_missing.clone());
+        let orphans = annotated
+            .clone()
+            .filter(orphaned.clone())?
+            .select(columns)?;
+
+        // All four counts in one pass over the edges
+        let flag = |missing: Expr| sum(cast(missing, DataType::Int64));
+        let counts = annotated
+            .aggregate(
+                vec![],
+                vec![
+                    count(lit(1)).alias("edges"),
+                    flag(source_missing).alias("missing_source"),
+                    flag(target_missing).alias("missing_target"),
+                    flag(orphaned).alias("orphaned"),
+                ],
+            )?
+            .collect()
+            .await?;
+        let counts = concat_batches(&counts[0].schema(), &counts)?;
+        let count_of = |column: usize| -> Result<u64> {
+            let value = ScalarValue::try_from_array(counts.column(column), 0)?;
+            Ok(match value.cast_to(&DataType::UInt64)? {
+                ScalarValue::UInt64(Some(count)) => count,
+                _ => 0,
+            })
+        };
+        let edges = count_of(0)?;
+        let missing_source = count_of(1)?;
+        let missing_target = count_of(2)?;
+        let orphaned = count_of(3)?;
+
+        let schema: SchemaRef = Arc::new(orphans.schema().as_arrow().clone());
+        let samples = orphans
+            .clone()
+            .limit(0, Some(validation.sample_size))?
+            .collect()
+            .await?;
+        let samples = concat_batches(&schema, &samples)?;
+
+        let mut quarantined = 0;
+        if let Some(quarantine) = validation.quarantine.as_ref().filter(|_| orphaned > 0) {
+            let mut edge_keys = mapping.source_key_columns();
+            edge_keys.extend(mapping.target_key_columns());
+            let keys = orphans
+                .clone()
+                .select(edge_keys.iter().map(|column| ident(*column)).collect())?
+                .distinct()?
+                .collect()
+                .await?;
+            let keys = keys
+                .iter()
+                .flat_map(|batch| {
+                    (0..batch.num_rows()).map(move |row| {
+                        batch
+                            .columns()
+                            .iter()
+                            .map(|column| ScalarValue::try_from_array(column, row))
+                            .collect::<std::result::Result<Vec<_>, _>>()
+                    })
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let orphans = orphans.collect().await?;
+
+            // Keep the orphans before dropping them from the relationship dataset
+            let mode = match self.open(quarantine).await {
+                Ok(_) => WriteMode::Append,
+                Err(lance::Error::DatasetNotFound { .. } | lance::Error::NotFound { .. }) => {
+                    WriteMode::Create
+                }
+                Err(e) => return Err(e.into()),
+            };
+            self.write_batches(quarantine, schema, orphans, mode)
+                .await?;
+
+            let edge_keys: Vec<String> =
+                edge_keys.iter().map(|column| column.to_string()).collect();
+            let filter = crate::writing::lance_sql(&crate::writing::key_filter(
+                &ArrowSchema::from(dataset.schema()),
+                &table,
+                &edge_keys,
+                &keys,
+            )?)?;
+            DeleteBuilder::new(dataset, filter)
+                .conflict_retries(0)
+                .execute()
+                .await
+                .map_err(|e| conflict::commit_error(&table, e))?;
+            quarantined = orphaned;
+        }
+
+        Ok(EdgeValidationReport {
+            edges,
+            missing_source,
+            missing_target,
+            orphaned,
+            samples,
+            quarantined,
+        })
+    }
+
+    /// Build an NGram index on the string `property` of `label`
+    ///
+    /// Lets `CONTAINS` and `LIKE '%x%'` filters on the property use the index
+    /// once it is declared with [`crate::config::NodeMapping::with_ngram_properties`].
+    /// Rows of the dataset still buffered by this writer are committed first;
+    /// an existing index on the column is replaced.
+    pub async fn create_ngram_index(&mut self, label: &str, property: &str) -> Result<()> {
+        let mapping =
+            self.config
+                .get_node_mapping(label)
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Cannot index unknown label '{}'", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        let table = mapping.table_name().to_string();
+        self.flush_tables(vec![table.clone()]).await?;
+
+        let mut dataset = self.open(&table).await?;
+        dataset
+            .create_index(
+                &[property],
+                IndexType::NGram,
+                None,
+                &ScalarIndexParams::for_builtin(BuiltinIndexType::NGram),
+                true,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Write relationships computed by an algorithm to the dataset of `output`'s type
+    ///
+    /// An unknown relationship type is added to this writer's configuration
+    /// only, not to any catalog or to the configuration queries were built
+    /// with, and its dataset is created under the base URI. To query it, plan
+    /// with [`BufferedGraphWriter::get_config`]. A known type must use the
+    /// same endpoint columns; the weight property is added to its mapping if
+    /// missing. Appends go through the regular commit
+    /// path, so types without parallel edges keep the latest weight per pair.
+    /// Overwriting discards rows of the type still buffered by this writer.
+    /// Returns the number of relationships written.
+    pub async fn write_relationships(
+        &mut self,
+        computed: &ComputedRelationships,
+        output: &RelationshipOutput,
+    ) -> Result<usize> {
+        let table = self.register_relationship_output(output)?;
+        let batch = computed.to_record_batch(output)?;
+        let rows = batch.num_rows();
+
+        match output.get_mode() {
+            RelationshipWriteMode::Append => {
+                self.flush_tables(vec![table.clone()]).await?;
+                if rows > 0 {
+                    let buffer = TableBuffer {
+                        schema: batch.schema(),
+                        batches: vec![batch],
+                        rows,
+                        oldest: Instant::now(),
+                    };
+                    self.commit(&table, &buffer).await?;
+                }
+            }
+            RelationshipWriteMode::Overwrite => {
+                self.buffers.remove(&table);
+                let uri = self.table_uri(&table);
+                let schema = batch.schema();
+                let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+                Dataset::write(
+                    reader,
+                    uri.as_str(),
+                    Some(self.write_params(&table, WriteMode::Overwrite)),
+                )
+                .await
+                .map_err(|e| GraphError::ExecutionError {
+                    message: format!("Failed to overwrite '{}': {}", uri, e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Graph configuration of the writer, including the relationship types
+    /// added by [`BufferedGraphWriter::write_relationships`]
+    pub fn get_config(&self) -> &GraphConfig {
+        &self.config
+    }
+
+    /// Number of rows buffered and not yet committed
+    pub fn pending_rows(&self) -> usize {
+        self.buffers.values().map(|b| b.rows).sum()
+    }
+
+    /// Commit every dataset whose buffer exceeded the age threshold
+    ///
+    /// Call this periodically when writes are sparse; otherwise age is only
+    /// checked as new rows arrive. Returns the number of rows committed.
+    pub async fn flush_expired(&mut self) -> Result<usize> {
+        let mut expired: Vec<String> = self
+            .buffers
+            .iter()
+            .filter(|(_, buffer)| buffer.oldest.elapsed() >= self.options.max_buffer_age)
+            .map(|(table, _)| table.clone())
+            .collect();
+        expired.sort();
+        self.flush_tables(expired).await
+    }
+
+    /// Commit every buffered row; returns the number of rows committed
+    pub async fn flush(&mut self) -> Result<usize> {
+        let mut tables: Vec<String> = self.buffers.keys().cloned().collect();
+        tables.sort();
+        self.flush_tables(tables).await
+    }
+
+    /// Remove the buffered rows of every dataset whose commit appends them,
+    /// in dataset name order, without committing them
+    ///
+    /// Rows of relationship types without parallel edges stay buffered:
+    /// committing them merges into the dataset.
+    pub(crate) fn take_appends(&mut self) -> Vec<(String, Vec<RecordBatch>)> {
+        let mut tables: Vec<String> = self
+            .buffers
+            .keys()
+            .filter(|table| {
+                self.config
+                    .get_relationship_mapping(table)
+                    .is_none_or(|mapping| mapping.allow_parallel_edges)
+            })
+            .cloned()
+            .collect();
+        tables.sort();
+        tables
+            .into_iter()
+            .filter_map(|table| {
+                let buffer = self.buffers.remove(&table)?;
+                Some((table, buffer.batches))
+            })
+            .collect()
+    }
+
+    /// Validate a node batch for `label` and resolve the table it is written to
+    fn writable_node_table(&self, label: &str, batch: &RecordBatch) -> Result<String> {
+        let mapping =
+            self.config
+                .get_node_mapping(label)
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Cannot write nodes of unknown label '{}'", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        if let Some(base) = &mapping.view_of {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Cannot write nodes of view label '{}'; write to '{}' instead",
+                    label, base
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let mut required = mapping.key_columns();
+        required.extend(mapping.label_column.as_deref());
+        require_columns(batch, &required, label)?;
+        Ok(mapping.table_name().to_string())
+    }
+
+    /// Validate a relationship batch for `rel_type` and resolve the table it is written to
+    fn writable_relationship_table(&self, rel_type: &str, batch: &RecordBatch) -> Result<String> {
+        let mapping = self
+            .config
+            .get_relationship_mapping(rel_type)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!("Cannot write relationships of unknown type '{}'", rel_type),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let mut required = mapping.source_key_columns();
+        required.extend(mapping.target_key_columns());
+        require_columns(batch, &required, rel_type)?;
+        Ok(mapping.relationship_type.clone())
+    }
+
+    /// Add `output`'s relationship type to the writer's configuration, or
+    /// check its existing mapping; returns its table
+    fn register_relationship_output(&mut self, output: &RelationshipOutput) -> Result<String> {
+        let Some(mapping) = self
+            .config
+            .relationship_mappings
+            .get_mut(&output.relationship_type().to_lowercase())
+        else {
+            let mapping = output.mapping();
+            let table = mapping.relationship_type.clone();
+            self.config
+                .relationship_mappings
+                .insert(table.to_lowercase(), mapping);
+            return Ok(table);
+        };
+
+        if !mapping
+            .source_id_field
+            .eq_ignore_ascii_case(output.source_field())
+            || !mapping
+                .target_id_field
+                .eq_ignore_ascii_case(output.target_field())
+        {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Relationship type '{}' connects '{}' -> '{}', not '{}' -> '{}'",
+                    mapping.relationship_type,
+                    mapping.source_id_field,
+                    mapping.target_id_field,
+                    output.source_field(),
+                    output.target_field()
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let weight = output.get_weight_property();
+        if !mapping
+            .property_fields
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(weight))
+        {
+            mapping.property_fields.push(weight.to_string());
+        }
+        Ok(mapping.relationship_type.clone())
+    }
+
+    /// Node mapping of an edge endpoint label, checked against the edge's key columns
+    fn endpoint_mapping(&self, label: &str, edge_keys: &[&str]) -> Result<NodeMapping> {
+        let mapping =
+            self.config
+                .get_node_mapping(label)
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Cannot validate edges against unknown label '{}'", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        if let Some(base) = &mapping.view_of {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Cannot validate edges against view label '{}'; use '{}' instead",
+                    label, base
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        if mapping.key_columns().len() != edge_keys.len() {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Label '{}' is keyed by {} column(s), but edges reference it by {}",
+                    label,
+                    mapping.key_columns().len(),
+                    edge_keys.len()
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        Ok(mapping.clone())
+    }
+
+    async fn buffer(&mut self, table: String, batch: RecordBatch) -> Result<()> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        let buffer = self
+            .buffers
+            .entry(table.clone())
+            .or_insert_with(|| TableBuffer {
+                schema: batch.schema(),
+                batches: Vec::new(),
+                rows: 0,
+                oldest: Instant::now(),
+            });
+        if buffer.schema != batch.schema() {
+            return Err(GraphError::ExecutionError {
+                message: format!(
+                    "Batch schema for '{}' does not match the rows already buffered",
+                    table
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        buffer.rows += batch.num_rows();
+        buffer.batches.push(batch);
+
+        if buffer.rows >= self.options.max_buffered_rows
+            || buffer.oldest.elapsed() >= self.options.max_buffer_age
+        {
+            self.flush_tables(vec![table]).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert the rows of `batch` whose `keys` match no row of the dataset
+    /// of `table` in one merge-insert commit; returns the number of rows
+    /// inserted
+    ///
+    /// Key columns the dataset lacks are left out of the match. Rows of the
+    /// dataset still buffered by this writer are committed first.
+    async fn insert_missing(
+        &mut self,
+        table: String,
+        keys: Vec<String>,
+        batch: RecordBatch,
+    ) -> Result<u64> {
+        self.flush_tables(vec![table.clone()]).await?;
+        if batch.num_rows() == 0 {
+            return Ok(0);
+        }
+        let uri = self.table_uri(&table);
+        let rows = batch.num_rows() as u64;
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+        let dataset = match self.open(&table).await {
+            Ok(dataset) => dataset,
+            Err(lance::Error::DatasetNotFound { .. } | lance::Error::NotFound { .. }) => {
+                let params = self.write_params(&table, WriteMode::Create);
+                Dataset::write(reader, uri.as_str(), Some(params))
+                    .await
+                    .map_err(|e| upsert_error(&uri, e))?;
+                return Ok(rows);
+            }
+            Err(e) => return Err(upsert_error(&uri, e)),
+        };
+
+        let columns = ArrowSchema::from(dataset.schema());
+        let keys: Vec<String> = keys
+            .iter()
+            .filter_map(|key| {
+                columns
+                    .fields()
+                    .iter()
+                    .find(|f| f.name().eq_ignore_ascii_case(key))
+                    .map(|f| f.name().clone())
+            })
+            .collect();
+        let mut builder = MergeInsertBuilder::try_new(Arc::new(dataset), keys)
+            .map_err(|e| upsert_error(&uri, e))?;
+        let job = builder
+            .when_matched(WhenMatched::DoNothing)
+            .when_not_matched(WhenNotMatched::InsertAll)
+            .try_build()
+            .map_err(|e| upsert_error(&uri, e))?;
+        let (_, stats) = job
+            .execute_reader(reader)
+            .await
+            .map_err(|e| upsert_error(&uri, e))?;
+        Ok(stats.num_inserted_rows)
+    }
+
+    async fn flush_tables(&mut self, tables: Vec<String>) -> Result<usize> {
+        let mut committed = 0;
+        for table in tables {
+            let Some(buffer) = self.buffers.get(&table) else {
+                continue;
+            };
+            let rows = buffer.rows;
+            // Keep the rows buffered until the commit succeeded
+            self.commit(&table, buffer).await?;
+            self.buffers.remove(&table);
+            committed += rows;
+        }
+        Ok(committed)
+    }
+
+    async fn commit(&self, table: &str, buffer: &TableBuffer) -> Result<()> {
+        let uri = self.table_uri(table);
+        if let Some(mapping) = self
+            .config
+            .get_relationship_mapping(table)
+            .filter(|m| !m.allow_parallel_edges)
+        {
+            // One edge per node pair: collapse the buffer, then merge into the dataset
+            let keys = mapping.edge_key_columns();
+            let batch = concat_batches(&buffer.schema, &buffer.batches)?;
+            let batch = keep_last_per_key(&batch, &keys)?;
+            let keys = keys.into_iter().map(str::to_string).collect();
+            self.merge_insert(table, keys, batch).await?;
+            return Ok(());
+        }
+
+        // Appends commute with other commits: one that lost a race (including
+        // two writers creating the dataset) is retried on the new latest
+        // version
+        let mut retries = 0;
+        loop {
+            let mode = match self.open_dataset(table).await? {
+                Some(_) => WriteMode::Append,
+                None => WriteMode::Create,
+            };
+            let reader = RecordBatchIterator::new(
+                buffer
+                    .batches
+                    .clone()
+                    .into_iter()
+                    .map(Ok::<RecordBatch, ArrowError>),
+                buffer.schema.clone(),
+            );
+            let written =
+                Dataset::write(reader, uri.as_str(), Some(self.write_params(table, mode))).await;
+            let e = match written {
+                Ok(_) => return Ok(()),
+                Err(e) => e,
+            };
+            let raced = conflict::is_commit_conflict(&e)
+                || matches!(e, lance::Error::DatasetAlreadyExists { .. });
+            if raced && retries < conflict::APPEND_RETRIES {
+                retries += 1;
+                continue;
+            }
+            if conflict::is_commit_conflict(&e) {
+                return Err(conflict::commit_error(table, e));
+            }
+            return Err(GraphError::ExecutionError {
+                message: format!("Failed to commit {} rows to '{}': {}", buffer.rows, uri, e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+    }
+
+    /// Update rows of the dataset of `table` matching `batch` on `keys`, insert the rest
+    async fn merge_insert(
+        &self,
+        table: &str,
+        keys: Vec<String>,
+        batch: RecordBatch,
+    ) -> Result<UpsertStats> {
+        let uri = self.table_uri(table);
+        let rows = batch.num_rows() as u64;
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+        let dataset = match self.open(table).await {
+            Ok(dataset) => dataset,
+            Err(lance::Error::DatasetNotFound { .. } | lance::Error::NotFound { .. }) => {
+                // Nothing to merge into yet: every row is new
+                let params = self.write_params(table, WriteMode::Create);
+                Dataset::write(reader, uri.as_str(), Some(params))
+                    .await
+                    .map_err(|e| upsert_error(&uri, e))?;
+                return Ok(UpsertStats {
+                    inserted: rows,
+                    updated: 0,
+                });
+            }
+            Err(e) => return Err(upsert_error(&uri, e)),
+        };
+
+        let mut builder = MergeInsertBuilder::try_new(Arc::new(dataset), keys)
+            .map_err(|e| upsert_error(&uri, e))?;
+        let job = builder
+            .when_matched(WhenMatched::UpdateAll)
+            .when_not_matched(WhenNotMatched::InsertAll)
+            .try_build()
+            .map_err(|e| upsert_error(&uri, e))?;
+        let (_, stats) = job
+            .execute_reader(reader)
+            .await
+            .map_err(|e| upsert_error(&uri, e))?;
+        Ok(UpsertStats {
+            inserted: stats.num_inserted_rows,
+            updated: stats.num_updated_rows,
+        })
+    }
+
+    /// Write `batches` to the dataset of `table`
+    async fn write_batches(
+        &self,
+        table: &str,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+        mode: WriteMode,
+    ) -> Result<()> {
+        let uri = self.table_uri(table);
+        let reader = RecordBatchIterator::new(batches.into_iter().map(Ok::<_, ArrowError>), schema);
+        Dataset::write(reader, uri.as_str(), Some(self.write_params(table, mode)))
+            .await
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to write '{}': {}", uri, e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        Ok(())
+    }
+
+    /// Latest version of the dataset backing `table`, opened with the
+    /// namespace's storage options for it
+    async fn open(&self, table: &str) -> lance::Result<Dataset> {
+        DatasetBuilder::from_uri(self.table_uri(table))
+            .with_storage_options(self.namespace.storage_options_for(table).into())
+            .load()
+            .await
+    }
+
+    /// Latest version of the dataset backing `table`, or `None` if there is
+    /// none yet
+    pub(crate) async fn open_dataset(&self, table: &str) -> Result<Option<Dataset>> {
+        match self.open(table).await {
+            Ok(dataset) => Ok(Some(dataset)),
+            Err(lance::Error::DatasetNotFound { .. } | lance::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parameters writing the dataset of `table` in `mode` with the
+    /// namespace's storage options for it
+    fn write_params(&self, table: &str, mode: WriteMode) -> WriteParams {
+        WriteParams {
+            mode,
+            store_params: Some(store_params(&self.namespace, table)),
+            ..Default::default()
+        }
+    }
+
+    /// Location of the dataset backing `table`
+    pub fn table_uri(&self, table: &str) -> String {
+        self.namespace.table_uri(table)
+    }
+}
+
+/// Object store parameters carrying the storage options `namespace` gives
+/// `table`
+pub(crate) fn store_params(namespace: &DirNamespace, table: &str) -> ObjectStoreParams {
+    ObjectStoreParams {
+        storage_options: Some(namespace.storage_options_for(table).as_map().clone()),
+        ..Default::default()
+    }
+}
+
+/// Drop all but the last row of every distinct key
+fn keep_last_per_key(batch: &RecordBatch, keys: &[&str]) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let columns = keys
+        .iter()
+        .map(|key| {
+            schema
+                .fields()
+                .iter()
+                .position(|f| f.name().eq_ignore_ascii_case(key))
+                .map(|idx| batch.column(idx).clone())
+                .ok_or_else(|| GraphError::ExecutionError {
+                    message: format!("Edge batch is missing key column '{}'", key),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let converter = RowConverter::new(
+        columns
+            .iter()
+            .map(|c| SortField::new(c.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = converter.convert_columns(&columns)?;
+
+    let mut last = HashMap::new();
+    for (idx, row) in rows.iter().enumerate() {
+        last.insert(row, idx as u32);
+    }
+    if last.len() == batch.num_rows() {
+        return Ok(batch.clone());
+    }
+    let mut indices: Vec<u32> = last.into_values().collect();
+    indices.sort_unstable();
+    Ok(take_record_batch(batch, &UInt32Array::from(indices))?)
+}
+
+fn upsert_error(uri: &str, e: lance::Error) -> GraphError {
+    if conflict::is_commit_conflict(&e) {
+        return conflict::commit_error(uri, e);
+    }
+    GraphError::ExecutionError {
+        message: format!("Failed to upsert into '{}': {}", uri, e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+fn require_columns(batch: &RecordBatch, columns: &[&str], name: &str) -> Result<()> {
+    let schema = batch.schema();
+    for column in columns {
+        if !schema
+            .fields()
+            .iter()
+            .any(|f| f.name().eq_ignore_ascii_case(column))
+        {
+            return Err(GraphError::ExecutionError {
+                message: format!("Batch for '{}' is missing key column '{}'", name, column),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RelationshipMapping;
+    use arrow_array::{BooleanArray, Float64Array, Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .with_view_label("Adult", "Person", "age >= 18")
+            .with_relationship_mapping(
+                RelationshipMapping::new("FOLLOWS", "src_id", "dst_id").with_parallel_edges(false),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn people(ids: Vec<i64>) -> RecordBatch {
+        let names: Vec<String> = ids.iter().map(|i| format!("p{}", i)).collect();
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn knows(pairs: Vec<(i64, i64)>) -> RecordBatch {
+        let (src, dst): (Vec<i64>, Vec<i64>) = pairs.into_iter().unzip();
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("src_id", DataType::Int64, false),
+                Field::new("dst_id", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(src)),
+                Arc::new(Int64Array::from(dst)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn weighted(edges: Vec<(i64, i64, f64)>) -> RecordBatch {
+        let src: Vec<i64> = edges.iter().map(|e| e.0).collect();
+        let dst: Vec<i64> = edges.iter().map(|e| e.1).collect();
+        let weight: Vec<f64> = edges.iter().map(|e| e.2).collect();
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("src_id", DataType::Int64, false),
+                Field::new("dst_id", DataType::Int64, false),
+                Field::new("weight", DataType::Float64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(src)),
+                Arc::new(Int64Array::from(dst)),
+                Arc::new(Float64Array::from(weight)),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn matching_rows(uri: &str, filter: &str) -> usize {
+        Dataset::open(uri)
+            .await
+            .unwrap()
+            .count_rows(Some(filter.to_string()))
+            .await
+            .unwrap()
+    }
+
+    async fn row_count(uri: &str) -> usize {
+        Dataset::open(uri)
+            .await
+            .unwrap()
+            .count_rows(None)
+            .await
+            .unwrap()
+    }
+
+    fn long_lived() -> WriterOptions {
+        WriterOptions::default().with_max_buffer_age(Duration::from_secs(3600))
+    }
+
+    #[tokio::test]
+    async fn test_writes_use_the_namespace_storage_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let namespace = DirNamespace::new(dir.path().to_str().unwrap()).with_table_storage_options(
+            "Person",
+            lance_graph_catalog::StorageOptions::new().with_option("region", "eu-west-1"),
+        );
+        let mut writer = BufferedGraphWriter::with_namespace(config(), namespace, long_lived());
+        let params = writer.write_params("Person", WriteMode::Append);
+        let options = params.store_params.unwrap().storage_options.unwrap();
+        assert_eq!(options.get("region").map(String::as_str), Some("eu-west-1"));
+
+        writer
+            .create_nodes("Person", people(vec![1, 2]))
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+        let dataset = writer.open_dataset("Person").await.unwrap().unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 2);
+        assert!(writer.open_dataset("KNOWS").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rows_stay_buffered_below_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = BufferedGraphWriter::new(
+            config(),
+            dir.path().to_str().unwrap(),
+            long_lived().with_max_buffered_rows(10),
+        );
+        writer
+            .create_nodes("Person", people(vec![1, 2, 3]))
+            .await
+            .unwrap();
+
+        assert_eq!(writer.pending_rows(), 3);
+        assert!(Dataset::open(&writer.table_uri("Person")).await.is_err());
+
+        assert_eq!(writer.flush().await.unwrap(), 3);
+        assert_eq!(writer.pending_rows(), 0);
+        assert_eq!(row_count(&writer.table_uri("Person")).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_size_threshold_flushes_and_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = BufferedGraphWriter::new(
+            config(),
+            dir.path().to_str().unwrap(),
+            long_lived().with_max_buffered_rows(4),
+        );
+        writer
+            .create_nodes("Person", people(vec![1, 2]))
+            .await
+            .unwrap();
+        writer
+            .create_nodes("Person", people(vec![3, 4]))
+            .await
+            .unwrap();
+        assert_eq!(writer.pending_rows(), 0);
+        assert_eq!(row_count(&writer.table_uri("Person")).await, 4);
+
+        writer
+            .create_nodes("Person", people(vec![5, 6, 7, 8]))
+            .await
+            .unwrap();
+        assert_eq!(row_count(&writer.table_uri("Person")).await, 8);
+    }
+
+    #[tokio::test]
+    async fn test_age_threshold_flushes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = BufferedGraphWriter::new(
+            config(),
+            dir.path().to_str().unwrap(),
+            WriterOptions::default().with_max_buffer_age(Duration::ZERO),
+        );
+        writer
+            .create_relationships("KNOWS", knows(vec![(1, 2), (2, 3)]))
+            .await
+            .unwrap();
+        assert_eq!(writer.pending_rows(), 0);
+        assert_eq!(row_count(&writer.table_uri("KNOWS")).await, 2);
+        assert_eq!(writer.flush_expired().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_dataset_is_not_recreated() {
+        let dir = tempfile::tempdir().unwrap();
+        let versions = dir.path().join("Person.lance").join("_versions");
+        std::fs::create_dir_all(&versions).unwrap();
+        std::fs::write(versions.join("1.manifest"), b"not a manifest").unwrap();
+
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        writer
+            .create_nodes("Person", people(vec![1]))
+            .await
+            .unwrap();
+        assert!(writer.flush().await.is_err());
+        // The failed flush keeps its rows buffered, so upsert with a new writer
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        assert!(writer
+            .upsert_nodes("Person", "id", people(vec![2]))
+            .await
+            .is_err());
+        assert_eq!(
+            std::fs::read(versions.join("1.manifest")).unwrap(),
+            b"not a manifest"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upsert_nodes_updates_and_inserts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+
+        // Creates the dataset when it does not exist yet
+        let stats = writer
+            .upsert_nodes("Person", "id", people(vec![1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            UpsertStats {
+                inserted: 2,
+                updated: 0
+            }
+        );
+
+        // Buffered creates land before the merge
+        writer
+            .create_nodes("Person", people(vec![3]))
+            .await
+            .unwrap();
+        let renamed = RecordBatch::try_new(
+            people(vec![]).schema(),
+            vec![
+                Arc::new(Int64Array::from(vec![2, 3, 4])),
+                Arc::new(StringArray::from(vec!["bob", "carol", "dave"])),
+            ],
+        )
+        .unwrap();
+        let stats = writer.upsert_nodes("Person", "id", renamed).await.unwrap();
+        assert_eq!(
+            stats,
+            UpsertStats {
+                inserted: 1,
+                updated: 2
+            }
+        );
+        assert_eq!(writer.pending_rows(), 0);
+
+        let uri = writer.table_uri("Person");
+        assert_eq!(row_count(&uri).await, 4);
+        let dataset = Dataset::open(&uri).await.unwrap();
+        let matched = dataset
+            .count_rows(Some("name = 'carol'".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(matched, 1);
+
+        let err = writer
+            .upsert_nodes("Person", "email", people(vec![5]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing key column 'email'"));
+    }
+
+    #[tokio::test]
+    async fn test_unique_edges_keep_latest_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        writer
+            .create_relationships(
+                "FOLLOWS",
+                weighted(vec![(1, 2, 1.0), (1, 2, 2.0), (2, 3, 1.0)]),
+            )
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+        let uri = writer.table_uri("FOLLOWS");
+        assert_eq!(row_count(&uri).await, 2);
+        assert_eq!(matching_rows(&uri, "src_id = 1 AND weight = 2.0").await, 1);
+
+        // A later write of an existing pair replaces the stored edge
+        writer
+            .create_relationships("FOLLOWS", weighted(vec![(1, 2, 5.0)]))
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+        assert_eq!(row_count(&uri).await, 2);
+        assert_eq!(matching_rows(&uri, "src_id = 1 AND weight = 5.0").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_validate_edges_reports_and_quarantines_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        writer
+            .create_nodes("Person", people(vec![1, 2]))
+            .await
+            .unwrap();
+        writer
+            .create_relationships("KNOWS", knows(vec![(1, 2), (2, 3), (4, 1)]))
+            .await
+            .unwrap();
+
+        let validation = EdgeValidation::new("Person", "Person").with_sample_size(1);
+        let report = writer.validate_edges("KNOWS", &validation).await.unwrap();
+        assert_eq!(
+            (
+                report.edges,
+                report.missing_source,
+                report.missing_target,
+                report.orphaned
+            ),
+            (3, 1, 1, 2)
+        );
+        assert_eq!(report.samples.num_rows(), 1);
+        assert_eq!(report.quarantined, 0);
+        assert_eq!(row_count(&writer.table_uri("KNOWS")).await, 3);
+
+        let report = writer
+            .validate_edges("KNOWS", &validation.with_quarantine("KNOWS_orphans"))
+            .await
+            .unwrap();
+        assert_eq!(report.quarantined, 2);
+        assert_eq!(row_count(&writer.table_uri("KNOWS")).await, 1);
+        assert_eq!(row_count(&writer.table_uri("KNOWS_orphans")).await, 2);
+
+        let report = writer
+            .validate_edges("KNOWS", &EdgeValidation::new("Person", "Person"))
+            .await
+            .unwrap();
+        assert_eq!(report.orphaned, 0);
+        assert_eq!(report.samples.num_rows(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_validate_edges_skips_soft_deleted_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_soft_delete_column("deleted"))
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config, dir.path().to_str().unwrap(), long_lived());
+        let people = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("deleted", DataType::Boolean, true),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(BooleanArray::from(vec![None, Some(true)])),
+            ],
+        )
+        .unwrap();
+        writer.create_nodes("Person", people).await.unwrap();
+        writer
+            .create_relationships("KNOWS", knows(vec![(1, 2), (2, 1), (1, 1)]))
+            .await
+            .unwrap();
+
+        let validation = EdgeValidation::new("Person", "Person").with_quarantine("KNOWS_orphans");
+        let report = writer.validate_edges("KNOWS", &validation).await.unwrap();
+        assert_eq!(
+            (
+                report.edges,
+                report.missing_source,
+                report.missing_target,
+                report.orphaned
+            ),
+            (3, 1, 1, 2)
+        );
+        assert_eq!(report.quarantined, 2);
+        assert_eq!(
+            matching_rows(&writer.table_uri("KNOWS"), "src_id = 1 AND dst_id = 1").await,
+            1
+        );
+        assert_eq!(row_count(&writer.table_uri("KNOWS")).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_deduplicate_edges_aggregates_properties() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        writer
+            .create_relationships(
+                "KNOWS",
+                weighted(vec![(1, 2, 1.0), (2, 3, 4.0), (1, 2, 3.0)]),
+            )
+            .await
+            .unwrap();
+
+        let aggregations = HashMap::from([("weight".to_string(), EdgeAggregation::Sum)]);
+        let stats = writer
+            .deduplicate_edges("KNOWS", &aggregations)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            DedupStats {
+                before: 3,
+                after: 2
+            }
+        );
+
+        let uri = writer.table_uri("KNOWS");
+        assert_eq!(row_count(&uri).await, 2);
+        assert_eq!(matching_rows(&uri, "weight = 4.0").await, 2);
+
+        // Already unique: nothing to rewrite
+        let version = Dataset::open(&uri).await.unwrap().version().version;
+        let stats = writer
+            .deduplicate_edges("KNOWS", &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            DedupStats {
+                before: 2,
+                after: 2
+            }
+        );
+        assert_eq!(
+            Dataset::open(&uri).await.unwrap().version().version,
+            version
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+
+        let err = writer
+            .create_nodes("Robot", people(vec![1]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown label 'Robot'"));
+
+        let err = writer
+            .create_nodes("Adult", people(vec![1]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("view label 'Adult'"));
+
+        let err = writer
+            .create_relationships("KNOWS", people(vec![1]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing key column 'src_id'"));
+
+        writer
+            .create_nodes("Person", people(vec![1]))
+            .await
+            .unwrap();
+        let err = writer
+            .create_nodes("Person", knows(vec![(1, 2)]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing key column 'id'"));
+        assert_eq!(writer.pending_rows(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_computed_relationships() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer =
+            BufferedGraphWriter::new(config(), dir.path().to_str().unwrap(), long_lived());
+        let mut knn = ComputedRelationships::default();
+        knn.push(1, 2, 0.9);
+        knn.push(2, 1, 0.9);
+
+        // Unknown types are registered and their dataset created
+        let output = RelationshipOutput::new("SIMILAR").weight_property("score");
+        assert_eq!(writer.write_relationships(&knn, &output).await.unwrap(), 2);
+        let mapping = writer
+            .get_config()
+            .get_relationship_mapping("SIMILAR")
+            .unwrap();
+        assert_eq!(mapping.property_fields, vec!["score".to_string()]);
+        assert_eq!(row_count(&writer.table_uri("SIMILAR")).await, 2);
+
+        writer.write_relationships(&knn, &output).await.unwrap();
+        assert_eq!(row_count(&writer.table_uri("SIMILAR")).await, 4);
+
+        let mut top = ComputedRelationships::default();
+        top.push(1, 3, 0.5);
+        let overwrite = output.clone().mode(RelationshipWriteMode::Overwrite);
+        writer.write_relationships(&top, &overwrite).await.unwrap();
+        assert_eq!(row_count(&writer.table_uri("SIMILAR")).await, 1);
+
+        // Existing types must keep their endpoint columns
+        let err = writer
+            .write_relationships(
+                &knn,
+                &RelationshipOutput::new("KNOWS").endpoint_fields("a", "b"),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("src_id"), "{}", err);
+    }
+}
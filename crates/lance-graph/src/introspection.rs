@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Graph introspection functions
+//!
+//! | Function        | Result                                                |
+//! |-----------------|-------------------------------------------------------|
+//! | `labels(n)`     | List of the labels of node `n`                        |
+//! | `type(r)`       | Type of relationship `r`                              |
+//! | `keys(x)`       | List of the property names of a node or relationship  |
+//! | `properties(x)` | Map (struct) of the properties of a node or relationship |
+//! | `id(n)`         | Identifier of node `n`: its id column                 |
+//!
+//! Labels and types come from the MATCH pattern binding the variable, and
+//! property names from the dataset schema of its label or type, so generic
+//! tooling can inspect matched elements without knowing the schema:
+//!
+//! ```text
+//! MATCH (a:Person)-[r:KNOWS]->(b)
+//! RETURN labels(a), type(r), properties(r), id(b)
+//! ```
+//!
+//! The calls are resolved before semantic analysis: `labels(n)` becomes a
+//! list of the pattern's labels, `properties(n)` the map projection
+//! `n {.name, .age, ...}`, and `id(n)` the property holding the node's id.
+//! Without dataset schemas (as for cost estimation) property names are the
+//! id and property fields of the configuration. Columns the storage adds
+//! (row ids and addresses), label, type, endpoint and soft-delete columns
+//! are not properties. Elements bound by OPTIONAL MATCH that did not match
+//! give NULL.
+
+use crate::ast::{
+    BooleanExpression, CaseBranch, CypherQuery as CypherAST, GraphPattern, MapProjectionItem,
+    PropertyRef, PropertyValue, ReadingClause, ValueExpression,
+};
+use crate::config::GraphConfig;
+use crate::datafusion_planner::expression::to_cypher_column_name;
+use crate::datafusion_planner::PROVENANCE_COLUMNS;
+use crate::error::{GraphError, Result};
+use crate::temporal::{for_each_value_mut, visit_value};
+use arrow_schema::SchemaRef;
+use std::collections::{HashMap, HashSet};
+
+/// Name under which the string lists of `labels()` and `keys()` are
+/// planned, as `graph.list('a', 'b')`
+pub(crate) const LIST_FUNCTION: &str = "graph.list";
+
+/// The introspection functions, lowercase
+const FUNCTIONS: &[&str] = &["labels", "type", "keys", "properties", "id"];
+
+/// Arrow schema of the dataset of a node table (`true`) or relationship
+/// type (`false`), when known
+pub(crate) type SchemaOf<'f> = dyn Fn(&str, bool) -> Option<SchemaRef> + 'f;
+
+/// Whether `name` (lowercase) is an introspection function
+pub(crate) fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+/// What a MATCH pattern binds a variable to
+#[derive(Clone)]
+enum Element {
+    Node(Vec<String>),
+    Relationship {
+        types: Vec<String>,
+        variable_length: bool,
+    },
+}
+
+struct Binding {
+    element: Element,
+    /// Bound by an OPTIONAL MATCH, so NULL for rows without a match
+    optional: bool,
+}
+
+/// Rewrite the introspection calls of `ast` into the expressions computing
+/// them
+///
+/// Unaliased RETURN items keep the column name of the call (`labels(n)`).
+pub(crate) fn resolve(
+    ast: &mut CypherAST,
+    config: &GraphConfig,
+    schema_of: &SchemaOf<'_>,
+) -> Result<()> {
+    let bindings = bindings(ast);
+    let mut rewrite = |expr: &mut ValueExpression| -> Result<()> {
+        let ValueExpression::ScalarFunction { name, args } = expr else {
+            return Ok(());
+        };
+        if !is_function(&name.to_lowercase()) {
+            return Ok(());
+        }
+        *expr = resolve_call(name, args, &bindings, config, schema_of)?;
+        Ok(())
+    };
+
+    for item in &mut ast.return_clause.items {
+        if item.alias.is_some() {
+            continue;
+        }
+        let mut resolved = item.expression.clone();
+        visit_value(&mut resolved, &mut rewrite)?;
+        if resolved != item.expression {
+            item.alias = Some(to_cypher_column_name(&item.expression));
+            item.expression = resolved;
+        }
+    }
+    for_each_value_mut(ast, &mut rewrite)
+}
+
+/// Elements bound by the MATCH patterns of `ast` (and renamed by WITH), by
+/// lowercase variable
+fn bindings(ast: &CypherAST) -> HashMap<String, Binding> {
+    let mut bindings: HashMap<String, Binding> = HashMap::new();
+    let mut bind = |variable: &Option<String>, element: Element, optional: bool| {
+        let Some(variable) = variable else {
+            return;
+        };
+        let binding = bindings.entry(variable.to_lowercase()).or_insert(Binding {
+            element: Element::Node(vec![]),
+            optional,
+        });
+        // A later pattern may name the labels an earlier one left out
+        let unlabeled = matches!(&binding.element, Element::Node(labels) if labels.is_empty());
+        if unlabeled {
+            binding.element = element;
+        }
+    };
+    for clause in ast
+        .reading_clauses
+        .iter()
+        .chain(&ast.post_with_reading_clauses)
+    {
+        let ReadingClause::Match(match_clause) = clause else {
+            continue;
+        };
+        let optional = match_clause.optional;
+        for pattern in &match_clause.patterns {
+            let (start, segments) = match pattern {
+                GraphPattern::Node(node) => (node, &[][..]),
+                GraphPattern::Path(path) => (&path.start_node, path.segments.as_slice()),
+            };
+            bind(
+                &start.variable,
+                Element::Node(start.labels.clone()),
+                optional,
+            );
+            for segment in segments {
+                let relationship = &segment.relationship;
+                let element = Element::Relationship {
+                    types: relationship.types.clone(),
+                    variable_length: relationship.length.is_some(),
+                };
+                bind(&relationship.variable, element, optional);
+                let end = &segment.end_node;
+                bind(&end.variable, Element::Node(end.labels.clone()), optional);
+            }
+        }
+    }
+
+    // `WITH n AS m` binds m to the element of n
+    for item in ast.with_clause.iter().flat_map(|with| &with.items) {
+        let (ValueExpression::Variable(variable), Some(alias)) = (&item.expression, &item.alias)
+        else {
+            continue;
+        };
+        let Some(binding) = bindings.get(&variable.to_lowercase()) else {
+            continue;
+        };
+        let element = binding.element.clone();
+        let optional = binding.optional;
+        bindings.insert(alias.to_lowercase(), Binding { element, optional });
+    }
+    bindings
+}
+
+/// The expression computing the introspection call `name(args)`
+fn resolve_call(
+    name: &str,
+    args: &[ValueExpression],
+    bindings: &HashMap<String, Binding>,
+    config: &GraphConfig,
+    schema_of: &SchemaOf<'_>,
+) -> Result<ValueExpression> {
+    let function = name.to_lowercase();
+    let [ValueExpression::Variable(variable)] = args else {
+        return Err(plan_error(format!(
+            "{}() takes a single node or relationship variable",
+            name
+        )));
+    };
+    let Some(binding) = bindings.get(&variable.to_lowercase()) else {
+        return Err(plan_error(format!(
+            "{}() takes a variable bound by a MATCH pattern, got '{}'",
+            name, variable
+        )));
+    };
+
+    // The column that is NULL exactly when an optional element did not match
+    let (value, present) = match &binding.element {
+        Element::Node(labels) => {
+            let Some(label) = labels.first() else {
+                return Err(plan_error(format!(
+                    "{}({}) needs the label of '{}' in its MATCH pattern",
+                    name, variable, variable
+                )));
+            };
+            let mapping = config
+                .get_node_mapping(label)
+                .ok_or_else(|| plan_error(format!("No node mapping for label '{}'", label)))?;
+            let keys = mapping.key_columns();
+            let value = match function.as_str() {
+                "labels" => list(labels),
+                "id" => match keys.as_slice() {
+                    [id] => property(variable, id),
+                    _ => {
+                        return Err(GraphError::UnsupportedFeature {
+                            feature: format!(
+                                "id() of '{}', whose label '{}' has a composite key",
+                                variable, label
+                            ),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        })
+                    }
+                },
+                "keys" | "properties" => {
+                    let (base, _) = config.resolve_view_chain(label)?;
+                    let table = config
+                        .get_node_mapping(&base)
+                        .map_or(base.as_str(), |base| base.table_name());
+                    let mut internal = vec![];
+                    internal.extend(mapping.label_column.as_deref());
+                    internal.extend(mapping.soft_delete_column.as_deref());
+                    let declared = keys
+                        .iter()
+                        .copied()
+                        .chain(mapping.property_fields.iter().map(String::as_str));
+                    let names = property_names(schema_of(table, true), declared, &internal);
+                    properties_value(&function, variable, &names)
+                }
+                _ => {
+                    return Err(plan_error(format!(
+                        "{}() takes a relationship variable, but '{}' is a node",
+                        name, variable
+                    )))
+                }
+            };
+            (value, keys[0].to_string())
+        }
+        Element::Relationship {
+            types,
+            variable_length,
+        } => {
+            if *variable_length {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: format!(
+                        "{}() of the variable-length relationship '{}'",
+                        name, variable
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            let rel_type = match types.as_slice() {
+                [rel_type] => rel_type,
+                _ => {
+                    return Err(GraphError::UnsupportedFeature {
+                        feature: format!(
+                            "{}({}) unless '{}' has exactly one type in its MATCH pattern",
+                            name, variable, variable
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                }
+            };
+            let mapping = config.get_relationship_mapping(rel_type).ok_or_else(|| {
+                plan_error(format!("No relationship mapping for type '{}'", rel_type))
+            })?;
+            let value = match function.as_str() {
+                "type" => ValueExpression::Literal(PropertyValue::String(rel_type.clone())),
+                "keys" | "properties" => {
+                    let mut internal = mapping.source_key_columns();
+                    internal.extend(mapping.target_key_columns());
+                    internal.extend(mapping.type_field.as_deref());
+                    internal.extend(mapping.soft_delete_column.as_deref());
+                    let declared = mapping.property_fields.iter().map(String::as_str);
+                    let names = property_names(
+                        schema_of(&mapping.relationship_type, false),
+                        declared,
+                        &internal,
+                    );
+                    properties_value(&function, variable, &names)
+                }
+                "id" => {
+                    return Err(GraphError::UnsupportedFeature {
+                        feature: format!(
+                            "id() of the relationship '{}': relationships have no id column",
+                            variable
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                }
+                _ => {
+                    return Err(plan_error(format!(
+                        "{}() takes a node variable, but '{}' is a relationship",
+                        name, variable
+                    )))
+                }
+            };
+            (value, mapping.source_key_columns()[0].to_string())
+        }
+    };
+
+    if !binding.optional || function == "id" {
+        return Ok(value);
+    }
+    Ok(ValueExpression::Case {
+        branches: vec![CaseBranch {
+            condition: BooleanExpression::IsNull(property(variable, &present)),
+            value: ValueExpression::Literal(PropertyValue::Null),
+        }],
+        default: Some(Box::new(value)),
+    })
+}
+
+/// Property names of an element: the columns of its dataset, or the
+/// `declared` id and property fields when the schema is unknown
+fn property_names<'a>(
+    schema: Option<SchemaRef>,
+    declared: impl Iterator<Item = &'a str>,
+    internal: &[&str],
+) -> Vec<String> {
+    let is_property = |name: &str| {
+        !PROVENANCE_COLUMNS.contains(&name)
+            && name != "_rowaddr"
+            && !internal
+                .iter()
+                .any(|column| column.eq_ignore_ascii_case(name))
+    };
+    let mut names: Vec<String> = match schema {
+        Some(schema) => schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect(),
+        None => declared.map(str::to_string).collect(),
+    };
+    names.retain(|name| is_property(name));
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.to_lowercase()));
+    names
+}
+
+/// `keys(variable)` or `properties(variable)` given its property names
+fn properties_value(function: &str, variable: &str, names: &[String]) -> ValueExpression {
+    if function == "keys" {
+        return list(names);
+    }
+    // Maps without entries have no Arrow struct to plan them as
+    if names.is_empty() {
+        return ValueExpression::Literal(PropertyValue::Null);
+    }
+    ValueExpression::MapProjection {
+        variable: variable.to_string(),
+        items: names
+            .iter()
+            .map(|name| MapProjectionItem {
+                key: name.clone(),
+                value: property(variable, name),
+            })
+            .collect(),
+    }
+}
+
+/// The list of `values`, as a call of [`LIST_FUNCTION`]
+fn list(values: &[String]) -> ValueExpression {
+    ValueExpression::ScalarFunction {
+        name: LIST_FUNCTION.to_string(),
+        args: values
+            .iter()
+            .map(|value| ValueExpression::Literal(PropertyValue::String(value.clone())))
+            .collect(),
+    }
+}
+
+fn property(variable: &str, name: &str) -> ValueExpression {
+    ValueExpression::Property(PropertyRef {
+        variable: variable.to_string(),
+        property: name.to_string(),
+    })
+}
+
+fn plan_error(message: String) -> GraphError {
+    GraphError::PlanError {
+        message,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Background query jobs
+//!
+//! Long analytics queries triggered from a web UI should not hold a request
+//! open until they finish. A [`JobManager`] runs submitted queries on the
+//! current Tokio runtime and returns a [`JobId`] at once; callers poll
+//! [`JobManager::status`] and read the result with [`JobManager::results`]
+//! once the job succeeded.
+//!
+//! Results are persisted as the Lance dataset `<results_uri>/job_<id>.lance`,
+//! so they outlive the process that computed them. Job ids are unique per
+//! manager; a new manager over the same `results_uri` overwrites the results
+//! of an earlier one as it reuses ids.
+//!
+//! At most [`JobManager::with_max_concurrent_jobs`] jobs run at a time; the
+//! others stay [`JobStatus::Queued`] until a slot frees up. With
+//! [`JobManager::with_runtimes`] jobs run on the runtime dedicated to
+//! [`WorkloadClass::Analytic`] instead, away from interactive queries.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::jobs::{JobManager, JobStatus};
+//!
+//! let jobs = JobManager::new("s3://bucket/graph-jobs").with_max_concurrent_jobs(2);
+//! let id = jobs.submit_with_namespace(query, namespace);
+//!
+//! // Later, e.g. from a polling endpoint
+//! if let Some(JobStatus::Succeeded { rows }) = jobs.status(id) {
+//!     let batch = jobs.results(id).await?;
+//! }
+//! ```
+
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use crate::runtime::{QueryRuntimes, WorkloadClass};
+use arrow::compute::concat_batches;
+use arrow_array::{RecordBatch, RecordBatchIterator};
+use arrow_schema::{ArrowError, Schema};
+use futures::TryStreamExt;
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance_graph_catalog::DirNamespace;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+
+/// Identifier of a submitted job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job_{}", self.0)
+    }
+}
+
+/// Progress of a submitted job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a free execution slot
+    Queued,
+    Running,
+    /// The result, with this many rows, is persisted
+    Succeeded {
+        rows: usize,
+    },
+    /// Execution or persisting the result failed
+    Failed {
+        message: String,
+    },
+}
+
+impl JobStatus {
+    /// Whether the job will not change status anymore
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded { .. } | Self::Failed { .. })
+    }
+}
+
+/// Where a job reads its graph from
+enum JobSource {
+    Datasets(HashMap<String, RecordBatch>),
+    Namespace(DirNamespace),
+}
+
+/// Runs queries in the background and tracks their status
+pub struct JobManager {
+    results_uri: String,
+    slots: Arc<Semaphore>,
+    next_id: AtomicU64,
+    statuses: Arc<Mutex<HashMap<JobId, JobStatus>>>,
+    runtimes: Option<Arc<QueryRuntimes>>,
+}
+
+impl JobManager {
+    /// Manager persisting job results under `results_uri`
+    pub fn new(results_uri: impl Into<String>) -> Self {
+        Self {
+            results_uri: results_uri.into().trim_end_matches('/').to_string(),
+            slots: Arc::new(Semaphore::new(4)),
+            next_id: AtomicU64::new(1),
+            statuses: Arc::new(Mutex::new(HashMap::new())),
+            runtimes: None,
+        }
+    }
+
+    /// Run jobs on the runtime `runtimes` dedicates to
+    /// [`WorkloadClass::Analytic`], if any
+    pub fn with_runtimes(mut self, runtimes: Arc<QueryRuntimes>) -> Self {
+        self.runtimes = Some(runtimes);
+        self
+    }
+
+    /// Run at most `jobs` jobs at a time (default 4)
+    pub fn with_max_concurrent_jobs(mut self, jobs: usize) -> Self {
+        self.slots = Arc::new(Semaphore::new(jobs.max(1)));
+        self
+    }
+
+    /// Run `query` against in-memory `datasets` in the background
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn submit(&self, query: CypherQuery, datasets: HashMap<String, RecordBatch>) -> JobId {
+        self.spawn(query, JobSource::Datasets(datasets))
+    }
+
+    /// Run `query` against the datasets of `namespace` in the background
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn submit_with_namespace(&self, query: CypherQuery, namespace: DirNamespace) -> JobId {
+        self.spawn(query, JobSource::Namespace(namespace))
+    }
+
+    /// Status of job `id`, or `None` if this manager never submitted it
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.statuses.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Every job submitted to this manager with its status, oldest first
+    pub fn jobs(&self) -> Vec<(JobId, JobStatus)> {
+        let mut jobs: Vec<_> = self
+            .statuses
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, status)| (*id, status.clone()))
+            .collect();
+        jobs.sort_by_key(|(id, _)| *id);
+        jobs
+    }
+
+    /// Location of the dataset holding the result of job `id`
+    pub fn result_uri(&self, id: JobId) -> String {
+        format!("{}/{}.lance", self.results_uri, id)
+    }
+
+    /// Read back the persisted result of a succeeded job
+    pub async fn results(&self, id: JobId) -> Result<RecordBatch> {
+        match self.status(id) {
+            Some(JobStatus::Succeeded { .. }) => {}
+            status => {
+                return Err(GraphError::ExecutionError {
+                    message: format!("{} has no result; status is {:?}", id, status),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        }
+        let dataset = Dataset::open(&self.result_uri(id)).await?;
+        let schema = Arc::new(Schema::from(dataset.schema()));
+        let batches: Vec<RecordBatch> = dataset
+            .scan()
+            .try_into_stream()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    fn spawn(&self, query: CypherQuery, source: JobSource) -> JobId {
+        let id = JobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.statuses.lock().unwrap().insert(id, JobStatus::Queued);
+
+        let slots = self.slots.clone();
+        let statuses = self.statuses.clone();
+        let uri = self.result_uri(id);
+        let job = async move {
+            // The semaphore is never closed
+            let _slot = slots.acquire_owned().await.ok();
+            let set = |status: JobStatus| {
+                statuses.lock().unwrap().insert(id, status);
+            };
+            set(JobStatus::Running);
+            match run(query, source, &uri).await {
+                Ok(rows) => set(JobStatus::Succeeded { rows }),
+                Err(e) => set(JobStatus::Failed {
+                    message: e.to_string(),
+                }),
+            }
+        };
+        match self
+            .runtimes
+            .as_ref()
+            .and_then(|runtimes| runtimes.handle(WorkloadClass::Analytic))
+        {
+            Some(handle) => handle.spawn(job),
+            None => tokio::spawn(job),
+        };
+        id
+    }
+}
+
+/// Execute `query` and write its result to `uri`, returning the row count
+async fn run(query: CypherQuery, source: JobSource, uri: &str) -> Result<usize> {
+    let batch = match source {
+        JobSource::Datasets(datasets) => query.execute(datasets, None).await?,
+        JobSource::Namespace(namespace) => query.execute_with_namespace(namespace, None).await?,
+    };
+    let rows = batch.num_rows();
+    let schema = batch.schema();
+    let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+    Dataset::write(
+        reader,
+        uri,
+        Some(WriteParams {
+            mode: WriteMode::Overwrite,
+            ..Default::default()
+        }),
+    )
+    .await
+    .map_err(|e| GraphError::ExecutionError {
+        message: format!("Failed to persist job result to '{}': {}", uri, e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GraphConfig;
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field};
+
+    fn datasets() -> HashMap<String, RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let person = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            ],
+        )
+        .unwrap();
+        HashMap::from([("Person".to_string(), person)])
+    }
+
+    fn query(cypher: &str) -> CypherQuery {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        CypherQuery::new(cypher).unwrap().with_config(config)
+    }
+
+    async fn wait(jobs: &JobManager, id: JobId) -> JobStatus {
+        loop {
+            let status = jobs.status(id).unwrap();
+            if status.is_finished() {
+                return status;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_submitted_job_persists_its_result() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::new(tmp_dir.path().to_str().unwrap());
+
+        let id = jobs.submit(query("MATCH (p:Person) RETURN p.name"), datasets());
+        assert!(jobs.status(id).is_some());
+        assert_eq!(wait(&jobs, id).await, JobStatus::Succeeded { rows: 3 });
+
+        let result = jobs.results(id).await.unwrap();
+        assert_eq!(result.num_rows(), 3);
+        assert!(std::path::Path::new(&jobs.result_uri(id)).exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_job_reports_its_error() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::new(tmp_dir.path().to_str().unwrap()).with_max_concurrent_jobs(1);
+
+        let failing = jobs.submit(query("MATCH (c:Company) RETURN c.name"), datasets());
+        let ok = jobs.submit(query("MATCH (p:Person) RETURN p.id"), datasets());
+
+        assert!(matches!(
+            wait(&jobs, failing).await,
+            JobStatus::Failed { .. }
+        ));
+        assert!(jobs.results(failing).await.is_err());
+        assert_eq!(wait(&jobs, ok).await, JobStatus::Succeeded { rows: 3 });
+        assert_eq!(
+            jobs.jobs().iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![failing, ok]
+        );
+        assert_eq!(jobs.status(JobId(99)), None);
+    }
+
+    #[test]
+    fn test_jobs_run_on_the_analytic_runtime() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let analytic = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let runtimes =
+            QueryRuntimes::new().with_runtime(WorkloadClass::Analytic, analytic.handle().clone());
+        let jobs =
+            JobManager::new(tmp_dir.path().to_str().unwrap()).with_runtimes(Arc::new(runtimes));
+
+        // Submitting outside of any runtime only works on the dedicated one
+        let id = jobs.submit(query("MATCH (p:Person) RETURN p.name"), datasets());
+        assert_eq!(
+            analytic.block_on(wait(&jobs, id)),
+            JobStatus::Succeeded { rows: 3 }
+        );
+    }
+}
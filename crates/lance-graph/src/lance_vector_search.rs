@@ -34,12 +34,58 @@
 use crate::ast::DistanceMetric;
 use crate::datafusion_planner::vector_ops;
 use crate::error::{GraphError, Result};
-use arrow::array::{Array, ArrayRef, Float32Array, UInt32Array};
+use arrow::array::{Array, ArrayRef, Float32Array, StringArray, UInt32Array, UInt64Array};
 use arrow::compute::take;
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+/// Diagnostic column naming the stage that produced each row's distance
+///
+/// `index` for approximate distances from the vector index, `refine` for exact
+/// distances re-ranked after the index, and `flat` for brute-force distances
+/// over data the index does not cover.
+pub const SEARCH_STAGE_COLUMN: &str = "_search_stage";
+
+/// Diagnostic column holding the number of IVF partitions probed
+///
+/// Null for rows found by flat search, or when nprobes was left for Lance to
+/// choose adaptively.
+pub const NPROBES_COLUMN: &str = "_nprobes";
+
+/// Distinguishes the in-memory copies built for temporary indices
+static TEMPORARY_INDEX_ID: AtomicU64 = AtomicU64::new(0);
+
+/// What [`VectorSearch::search_lance`] does when the searched column has no
+/// vector index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingIndexPolicy {
+    /// Compare the query against every vector, silently (default)
+    #[default]
+    BruteForce,
+    /// Compare against every vector and report a warning in
+    /// [`VectorSearchResult::warnings`]
+    WarnAndBruteForce,
+    /// Fail the search
+    Error,
+    /// Copy the dataset into memory and search it through a flat IVF index
+    /// built for this search only
+    TemporaryIndex,
+}
+
+impl MissingIndexPolicy {
+    /// Name shown in [`VectorSearch::explain_lance`]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BruteForce => "brute_force",
+            Self::WarnAndBruteForce => "warn_and_brute_force",
+            Self::Error => "error",
+            Self::TemporaryIndex => "temporary_index",
+        }
+    }
+}
+
 /// Builder for vector similarity search operations
 ///
 /// Supports both brute-force search on RecordBatches and ANN search on Lance datasets.
@@ -57,6 +103,16 @@ pub struct VectorSearch {
     include_distance: bool,
     /// Name for the distance column (default: "_distance")
     distance_column_name: String,
+    /// Number of IVF partitions to probe (Lance chooses adaptively if unset)
+    nprobes: Option<usize>,
+    /// Refine factor for re-ranking index candidates with exact distances
+    refine_factor: Option<u32>,
+    /// Whether to include per-row search diagnostics in ANN results
+    include_diagnostics: bool,
+    /// Behavior of ANN search over a column without vector index
+    missing_index_policy: MissingIndexPolicy,
+    /// Whether ANN search also scans rows the vector index does not cover
+    include_unindexed: bool,
 }
 
 impl VectorSearch {
@@ -77,6 +133,11 @@ impl VectorSearch {
             top_k: 10,
             include_distance: true,
             distance_column_name: "_distance".to_string(),
+            nprobes: None,
+            refine_factor: None,
+            include_diagnostics: false,
+            missing_index_policy: MissingIndexPolicy::default(),
+            include_unindexed: true,
         }
     }
 
@@ -125,6 +186,61 @@ impl VectorSearch {
         self
     }
 
+    /// Set the number of IVF partitions to probe in ANN search
+    ///
+    /// # Arguments
+    /// * `n` - Partitions to probe; more partitions improve recall but cost latency
+    pub fn nprobes(mut self, n: usize) -> Self {
+        self.nprobes = Some(n);
+        self
+    }
+
+    /// Re-rank ANN candidates using exact distances
+    ///
+    /// # Arguments
+    /// * `factor` - Read `factor * k` candidates from the index before re-ranking
+    pub fn refine_factor(mut self, factor: u32) -> Self {
+        self.refine_factor = Some(factor);
+        self
+    }
+
+    /// Whether to include per-row diagnostics in ANN search results
+    ///
+    /// Adds the distance, [`SEARCH_STAGE_COLUMN`] and [`NPROBES_COLUMN`] to the
+    /// output of [`Self::search_lance`] so recall issues can be diagnosed from
+    /// the results themselves.
+    ///
+    /// # Arguments
+    /// * `include` - If true, adds diagnostic columns to results
+    pub fn include_diagnostics(mut self, include: bool) -> Self {
+        self.include_diagnostics = include;
+        self
+    }
+
+    /// Set what ANN search does when the column has no vector index
+    ///
+    /// # Arguments
+    /// * `policy` - Brute force (default), warn and brute force, error, or
+    ///   search through a temporary index
+    pub fn missing_index_policy(mut self, policy: MissingIndexPolicy) -> Self {
+        self.missing_index_policy = policy;
+        self
+    }
+
+    /// Whether ANN search also covers rows appended after the index was built
+    ///
+    /// By default the fragments a vector index does not cover are searched by
+    /// brute force and merged into the index's top-k, so fresh rows are never
+    /// missed. Disabling this searches the index alone, trading fresh rows for
+    /// latency.
+    ///
+    /// # Arguments
+    /// * `include` - If false, only rows covered by the index are returned
+    pub fn include_unindexed(mut self, include: bool) -> Self {
+        self.include_unindexed = include;
+        self
+    }
+
     // Getters for accessing internal state (used by Python bindings)
 
     /// Get the column name
@@ -147,6 +263,26 @@ impl VectorSearch {
         self.top_k
     }
 
+    /// Get the nprobes value if set
+    pub fn get_nprobes(&self) -> Option<usize> {
+        self.nprobes
+    }
+
+    /// Get the refine factor if set
+    pub fn get_refine_factor(&self) -> Option<u32> {
+        self.refine_factor
+    }
+
+    /// Get the missing index policy
+    pub fn get_missing_index_policy(&self) -> MissingIndexPolicy {
+        self.missing_index_policy
+    }
+
+    /// Get whether rows not covered by the index are searched
+    pub fn get_include_unindexed(&self) -> bool {
+        self.include_unindexed
+    }
+
     /// Perform brute-force vector search on a RecordBatch
     ///
     /// This method computes distances for all vectors in the batch and returns
@@ -220,36 +356,187 @@ impl VectorSearch {
     ///     .await?;
     /// ```
     pub async fn search_lance(&self, dataset: &lance::Dataset) -> Result<RecordBatch> {
-        use arrow::compute::concat_batches;
-        use futures::TryStreamExt;
+        Ok(self.search_lance_result(dataset).await?.data)
+    }
 
-        let query_vector = self
-            .query_vector
-            .as_ref()
+    /// Perform ANN vector search on a Lance dataset, reporting how it was served
+    ///
+    /// Like [`Self::search_lance`], and also tells whether a vector index was
+    /// used and carries the warnings of [`MissingIndexPolicy::WarnAndBruteForce`].
+    pub async fn search_lance_result(
+        &self,
+        dataset: &lance::Dataset,
+    ) -> Result<VectorSearchResult> {
+        let query_vector = self.require_query_vector()?;
+
+        let mut warnings = Vec::new();
+        let mut unindexed_rows = 0;
+        let temporary;
+        let (target, used_ann_index) = if let Some(index) = self.vector_index_name(dataset).await? {
+            unindexed_rows = Self::unindexed_rows(dataset, &index).await?;
+            (dataset, true)
+        } else {
+            match self.missing_index_policy {
+                MissingIndexPolicy::BruteForce => (dataset, false),
+                MissingIndexPolicy::WarnAndBruteForce => {
+                    warnings.push(format!(
+                        "No vector index on column '{}'; searched all vectors",
+                        self.column
+                    ));
+                    (dataset, false)
+                }
+                MissingIndexPolicy::Error => {
+                    return Err(GraphError::ExecutionError {
+                        message: format!(
+                            "No vector index on column '{}'; create one or choose another missing index policy",
+                            self.column
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                }
+                MissingIndexPolicy::TemporaryIndex => {
+                    match self.build_temporary_index(dataset).await? {
+                        Some(indexed) => {
+                            temporary = indexed;
+                            (&temporary, true)
+                        }
+                        None => (dataset, false),
+                    }
+                }
+            }
+        };
+
+        let data = self.nearest(target, query_vector).await?;
+        let vectors_scanned = if used_ann_index {
+            0
+        } else {
+            target.count_rows(None).await?
+        };
+        Ok(VectorSearchResult {
+            data,
+            used_ann_index,
+            vectors_scanned,
+            unindexed_rows,
+            warnings,
+        })
+    }
+
+    /// Describe how [`Self::search_lance`] runs on `dataset`
+    ///
+    /// The first line names the vector index searched, or the missing index
+    /// policy applied when the column has none; Lance's scan plan follows.
+    pub async fn explain_lance(&self, dataset: &lance::Dataset) -> Result<String> {
+        let query_vector = self.require_query_vector()?;
+        let access = match self.vector_index_name(dataset).await? {
+            Some(name) => format!(
+                "index={}, unindexed_rows={} ({})",
+                name,
+                Self::unindexed_rows(dataset, &name).await?,
+                if self.include_unindexed {
+                    "brute force merged"
+                } else {
+                    "skipped"
+                }
+            ),
+            None => {
+                let action = match self.missing_index_policy {
+                    MissingIndexPolicy::BruteForce | MissingIndexPolicy::WarnAndBruteForce => {
+                        "brute force"
+                    }
+                    MissingIndexPolicy::Error => "fail",
+                    MissingIndexPolicy::TemporaryIndex => "build temporary IVF_FLAT index",
+                };
+                format!(
+                    "index=none, missing_index_policy={} ({})",
+                    self.missing_index_policy.as_str(),
+                    action
+                )
+            }
+        };
+        let plan = self
+            .nearest_scanner(dataset, query_vector)?
+            .explain_plan(true)
+            .await?;
+        Ok(format!(
+            "VectorSearch: column={}, k={}, {}\n{}",
+            self.column, self.top_k, access, plan
+        ))
+    }
+
+    fn require_query_vector(&self) -> Result<&[f32]> {
+        self.query_vector
+            .as_deref()
             .ok_or_else(|| GraphError::ConfigError {
                 message: "Query vector is required for search".to_string(),
                 location: snafu::Location::new(file!(), line!(), column!()),
-            })?;
+            })
+    }
 
-        // Convert metric to Lance's DistanceType
-        let lance_metric = match self.metric {
-            DistanceMetric::L2 => lance_linalg::distance::DistanceType::L2,
-            DistanceMetric::Cosine => lance_linalg::distance::DistanceType::Cosine,
-            DistanceMetric::Dot => lance_linalg::distance::DistanceType::Dot,
-        };
+    /// Name of a vector index on the searched column, if any
+    pub(crate) async fn vector_index_name(
+        &self,
+        dataset: &lance::Dataset,
+    ) -> Result<Option<String>> {
+        use lance_index::DatasetIndexExt;
+
+        let field_id = dataset.schema().field(&self.column).map(|f| f.id);
+        let indices = dataset.load_indices().await?;
+        Ok(indices
+            .iter()
+            .find(|index| field_id.is_some_and(|id| index.fields.contains(&id)))
+            .map(|index| index.name.clone()))
+    }
 
-        // Create query array
-        let query_array = Float32Array::from(query_vector.clone());
+    /// Rows of `dataset` in fragments the index `name` does not cover
+    async fn unindexed_rows(dataset: &lance::Dataset, name: &str) -> Result<usize> {
+        use lance::index::DatasetIndexInternalExt;
 
-        // Build scanner with ANN search
-        let mut scanner = dataset.scan();
-        scanner
-            .nearest(&self.column, &query_array as &dyn Array, self.top_k)
-            .map_err(|e| GraphError::ExecutionError {
-                message: format!("Failed to configure nearest neighbor search: {}", e),
-                location: snafu::Location::new(file!(), line!(), column!()),
-            })?
-            .distance_metric(lance_metric);
+        let fragments = dataset.unindexed_fragments(name).await?;
+        Ok(fragments.iter().filter_map(|f| f.num_rows()).sum())
+    }
+
+    /// In-memory copy of `dataset` with a flat IVF index on the searched column
+    ///
+    /// Returns `None` for an empty dataset, which has nothing to train on.
+    async fn build_temporary_index(
+        &self,
+        dataset: &lance::Dataset,
+    ) -> Result<Option<lance::Dataset>> {
+        use arrow::array::RecordBatchIterator;
+        use lance::index::vector::VectorIndexParams;
+        use lance_index::{DatasetIndexExt, IndexType};
+
+        let batch = dataset.scan().try_into_batch().await?;
+        if batch.num_rows() == 0 {
+            return Ok(None);
+        }
+        let uri = format!(
+            "memory://lance-graph-temporary-index-{}",
+            TEMPORARY_INDEX_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let mut copy = lance::Dataset::write(reader, &uri, None).await?;
+        copy.create_index(
+            &[self.column.as_str()],
+            IndexType::Vector,
+            None,
+            &VectorIndexParams::ivf_flat(1, self.distance_type()),
+            true,
+        )
+        .await?;
+        Ok(Some(copy))
+    }
+
+    /// Run the configured nearest-neighbor search on `dataset`
+    async fn nearest(&self, dataset: &lance::Dataset, query_vector: &[f32]) -> Result<RecordBatch> {
+        use arrow::compute::concat_batches;
+        use futures::TryStreamExt;
+
+        let mut scanner = self.nearest_scanner(dataset, query_vector)?;
+        if self.include_diagnostics {
+            scanner.with_row_address();
+        }
 
         // Execute scan and collect results
         let stream = scanner
@@ -278,9 +565,220 @@ impl VectorSearch {
 
         // Concatenate batches
         let schema = batches[0].schema();
-        concat_batches(&schema, &batches).map_err(|e| GraphError::ExecutionError {
-            message: format!("Failed to concatenate result batches: {}", e),
-            location: snafu::Location::new(file!(), line!(), column!()),
+        let mut batch =
+            concat_batches(&schema, &batches).map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to concatenate result batches: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        // Index-only search adds row ids the caller did not ask for
+        if !self.include_unindexed {
+            if let Ok(idx) = schema.index_of(lance::dataset::ROW_ID) {
+                batch.remove_column(idx);
+            }
+        }
+
+        if self.include_diagnostics {
+            self.add_diagnostics(dataset, batch).await
+        } else {
+            Ok(batch)
+        }
+    }
+
+    /// Lance's DistanceType for the configured metric
+    fn distance_type(&self) -> lance_linalg::distance::DistanceType {
+        match self.metric {
+            DistanceMetric::L2 => lance_linalg::distance::DistanceType::L2,
+            DistanceMetric::Cosine => lance_linalg::distance::DistanceType::Cosine,
+            DistanceMetric::Dot => lance_linalg::distance::DistanceType::Dot,
+        }
+    }
+
+    /// Build a scanner for the configured nearest-neighbor search over `query_vector`
+    fn nearest_scanner(
+        &self,
+        dataset: &lance::Dataset,
+        query_vector: &[f32],
+    ) -> Result<lance::dataset::scanner::Scanner> {
+        let lance_metric = self.distance_type();
+
+        // Create query array
+        let query_array = Float32Array::from(query_vector.to_vec());
+
+        // Build scanner with ANN search
+        let mut scanner = dataset.scan();
+        scanner
+            .nearest(&self.column, &query_array as &dyn Array, self.top_k)
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to configure nearest neighbor search: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?
+            .distance_metric(lance_metric);
+        if let Some(nprobes) = self.nprobes {
+            scanner.nprobes(nprobes);
+        }
+        if let Some(factor) = self.refine_factor {
+            scanner.refine(factor);
+        }
+        if !self.include_unindexed {
+            scanner.fast_search();
+        }
+        Ok(scanner)
+    }
+
+    /// Measure recall@k of the ANN search against exact search
+    ///
+    /// Re-executes each sampled query vector twice, once with the configured
+    /// index settings (nprobes, refine factor) and once as an exact flat search,
+    /// and reports the fraction of the exact top-k found by the indexed search.
+    /// Use it to tune `nprobes` / `refine_factor` without external scripts.
+    ///
+    /// # Arguments
+    /// * `dataset` - Lance dataset with vector column
+    /// * `sample` - Query vectors to evaluate; empty uses the configured query vector
+    ///
+    /// # Example
+    /// ```ignore
+    /// let report = VectorSearch::new("embedding")
+    ///     .top_k(10)
+    ///     .nprobes(20)
+    ///     .evaluate_recall(&dataset, &sample_vectors)
+    ///     .await?;
+    /// println!("recall@{}: {:.3}", report.k, report.mean_recall());
+    /// ```
+    pub async fn evaluate_recall(
+        &self,
+        dataset: &lance::Dataset,
+        sample: &[Vec<f32>],
+    ) -> Result<RecallReport> {
+        let queries: Vec<&[f32]> = if sample.is_empty() {
+            let query_vector =
+                self.query_vector
+                    .as_deref()
+                    .ok_or_else(|| GraphError::ConfigError {
+                        message: "Query vector or sample is required for recall evaluation"
+                            .to_string(),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })?;
+            vec![query_vector]
+        } else {
+            sample.iter().map(Vec::as_slice).collect()
+        };
+
+        let mut recalls = Vec::with_capacity(queries.len());
+        for query_vector in queries {
+            let indexed = self.nearest_row_ids(dataset, query_vector, true).await?;
+            let exact = self.nearest_row_ids(dataset, query_vector, false).await?;
+            recalls.push(if exact.is_empty() {
+                1.0
+            } else {
+                exact.intersection(&indexed).count() as f64 / exact.len() as f64
+            });
+        }
+
+        Ok(RecallReport {
+            k: self.top_k,
+            recalls,
+        })
+    }
+
+    /// Row ids of the nearest neighbors, with or without the vector index
+    async fn nearest_row_ids(
+        &self,
+        dataset: &lance::Dataset,
+        query_vector: &[f32],
+        use_index: bool,
+    ) -> Result<std::collections::HashSet<u64>> {
+        use futures::TryStreamExt;
+
+        let mut scanner = self.nearest_scanner(dataset, query_vector)?;
+        scanner.use_index(use_index).with_row_id();
+        let batches: Vec<RecordBatch> = scanner.try_into_stream().await?.try_collect().await?;
+
+        let mut row_ids = std::collections::HashSet::new();
+        for batch in &batches {
+            let column = batch
+                .column_by_name(lance::dataset::ROW_ID)
+                .and_then(|c| c.as_any().downcast_ref::<UInt64Array>())
+                .ok_or_else(|| GraphError::ExecutionError {
+                    message: "Nearest neighbor results are missing row ids".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+            row_ids.extend(column.values().iter().copied());
+        }
+        Ok(row_ids)
+    }
+
+    /// Replace the row address column with per-row search diagnostics
+    async fn add_diagnostics(
+        &self,
+        dataset: &lance::Dataset,
+        batch: RecordBatch,
+    ) -> Result<RecordBatch> {
+        use lance_index::DatasetIndexExt;
+
+        // Fragments covered by a vector index on the searched column
+        let field_id = dataset.schema().field(&self.column).map(|f| f.id);
+        let indices = dataset.load_indices().await?;
+        let indexed: Vec<_> = indices
+            .iter()
+            .filter(|index| field_id.is_some_and(|id| index.fields.contains(&id)))
+            .filter_map(|index| index.fragment_bitmap.as_ref())
+            .collect();
+
+        let schema = batch.schema();
+        let addr_idx = schema
+            .index_of("_rowaddr")
+            .map_err(|_| GraphError::ExecutionError {
+                message: "ANN search results are missing row addresses".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let addresses = batch
+            .column(addr_idx)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| GraphError::ExecutionError {
+                message: "Row address column is not UInt64".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let mut stages = Vec::with_capacity(batch.num_rows());
+        let mut nprobes = Vec::with_capacity(batch.num_rows());
+        for address in addresses.values().iter() {
+            // Row addresses pack the fragment id into their upper 32 bits
+            let fragment = (address >> 32) as u32;
+            if indexed.iter().any(|bitmap| bitmap.contains(fragment)) {
+                stages.push(if self.refine_factor.is_some() {
+                    "refine"
+                } else {
+                    "index"
+                });
+                nprobes.push(self.nprobes.map(|n| n as u32));
+            } else {
+                stages.push("flat");
+                nprobes.push(None);
+            }
+        }
+
+        let mut fields: Vec<Field> = Vec::with_capacity(batch.num_columns() + 1);
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns() + 1);
+        for (i, field) in schema.fields().iter().enumerate() {
+            if i == addr_idx {
+                continue;
+            }
+            fields.push(field.as_ref().clone());
+            columns.push(batch.column(i).clone());
+        }
+        fields.push(Field::new(SEARCH_STAGE_COLUMN, DataType::Utf8, false));
+        columns.push(Arc::new(StringArray::from(stages)) as ArrayRef);
+        fields.push(Field::new(NPROBES_COLUMN, DataType::UInt32, true));
+        columns.push(Arc::new(UInt32Array::from(nprobes)) as ArrayRef);
+
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(|e| {
+            GraphError::ExecutionError {
+                message: format!("Failed to attach search diagnostics: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }
         })
     }
 
@@ -355,12 +853,41 @@ pub struct VectorSearchResult {
     pub used_ann_index: bool,
     /// Number of vectors scanned (for brute-force)
     pub vectors_scanned: usize,
+    /// Rows appended after the vector index was built, brute-forced into the
+    /// top-k unless [`VectorSearch::include_unindexed`] is disabled
+    pub unindexed_rows: usize,
+    /// Issues worth reporting, e.g. a missing vector index
+    pub warnings: Vec<String>,
+}
+
+/// Recall@k of ANN search relative to exact search, per sampled query
+#[derive(Debug, Clone)]
+pub struct RecallReport {
+    /// Number of neighbors compared per query
+    pub k: usize,
+    /// Recall of each sampled query, in sample order
+    pub recalls: Vec<f64>,
+}
+
+impl RecallReport {
+    /// Mean recall across sampled queries
+    pub fn mean_recall(&self) -> f64 {
+        if self.recalls.is_empty() {
+            return 1.0;
+        }
+        self.recalls.iter().sum::<f64>() / self.recalls.len() as f64
+    }
+
+    /// Lowest recall of any sampled query
+    pub fn min_recall(&self) -> f64 {
+        self.recalls.iter().copied().fold(1.0, f64::min)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::array::{FixedSizeListArray, Int64Array, StringArray};
+    use arrow::array::{FixedSizeListArray, Int64Array};
     use arrow::datatypes::FieldRef;
 
     fn create_test_batch() -> RecordBatch {
@@ -551,4 +1078,237 @@ mod tests {
         // Should return all 5 rows
         assert_eq!(results.num_rows(), 5);
     }
+
+    #[tokio::test]
+    async fn test_search_lance_diagnostics_without_index() {
+        use arrow::array::RecordBatchIterator;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri = tmp_dir.path().join("people.lance");
+        let batch = create_test_batch();
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = lance::Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+
+        let results = VectorSearch::new("embedding")
+            .query_vector(vec![1.0, 0.0, 0.0])
+            .top_k(2)
+            .nprobes(4)
+            .include_diagnostics(true)
+            .search_lance(&dataset)
+            .await
+            .unwrap();
+
+        assert_eq!(results.num_rows(), 2);
+        let schema = results.schema();
+        assert!(schema.field_with_name("_distance").is_ok());
+        assert!(schema.field_with_name("_rowaddr").is_err());
+
+        // Without a vector index every row comes from flat search
+        let stages = results
+            .column_by_name(SEARCH_STAGE_COLUMN)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert!(stages.iter().all(|stage| stage == Some("flat")));
+        let nprobes = results.column_by_name(NPROBES_COLUMN).unwrap();
+        assert_eq!(nprobes.null_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_index_policies() {
+        use arrow::array::RecordBatchIterator;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri = tmp_dir.path().join("people.lance");
+        let batch = create_test_batch();
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = lance::Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+        let search = |policy| {
+            VectorSearch::new("embedding")
+                .query_vector(vec![1.0, 0.0, 0.0])
+                .top_k(2)
+                .missing_index_policy(policy)
+        };
+        let names = |batch: &RecordBatch| -> Vec<String> {
+            let names = batch
+                .column_by_name("name")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            names.iter().map(|n| n.unwrap().to_string()).collect()
+        };
+
+        // Today's behavior is the default: silent brute force
+        let brute = search(MissingIndexPolicy::default())
+            .search_lance_result(&dataset)
+            .await
+            .unwrap();
+        assert!(!brute.used_ann_index);
+        assert!(brute.warnings.is_empty());
+        assert_eq!(brute.vectors_scanned, 5);
+
+        let warned = search(MissingIndexPolicy::WarnAndBruteForce)
+            .search_lance_result(&dataset)
+            .await
+            .unwrap();
+        assert_eq!(warned.warnings.len(), 1);
+        assert!(warned.warnings[0].contains("embedding"));
+
+        let err = search(MissingIndexPolicy::Error)
+            .search_lance(&dataset)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No vector index"), "{err}");
+
+        let temporary = search(MissingIndexPolicy::TemporaryIndex)
+            .search_lance_result(&dataset)
+            .await
+            .unwrap();
+        assert!(temporary.used_ann_index);
+        assert_eq!(names(&temporary.data), names(&brute.data));
+
+        let explain = search(MissingIndexPolicy::TemporaryIndex)
+            .explain_lance(&dataset)
+            .await
+            .unwrap();
+        assert!(
+            explain.contains("index=none, missing_index_policy=temporary_index"),
+            "{explain}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_lance_merges_rows_appended_after_index() {
+        use arrow::array::RecordBatchIterator;
+        use lance::dataset::{WriteMode, WriteParams};
+        use lance::index::vector::VectorIndexParams;
+        use lance_index::{DatasetIndexExt, IndexType};
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri = tmp_dir.path().join("people.lance");
+        let batch = create_test_batch();
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema.clone());
+        let mut dataset = lance::Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+        dataset
+            .create_index(
+                &["embedding"],
+                IndexType::Vector,
+                None,
+                &VectorIndexParams::ivf_flat(1, lance_linalg::distance::DistanceType::L2),
+                true,
+            )
+            .await
+            .unwrap();
+
+        // Append a row nearer to the query than any indexed row
+        let field = Arc::new(Field::new("item", DataType::Float32, true)) as FieldRef;
+        let values = Arc::new(Float32Array::from(vec![0.0, 0.0, -1.0]));
+        let fresh = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![6])),
+                Arc::new(StringArray::from(vec!["Zed"])),
+                Arc::new(FixedSizeListArray::try_new(field, 3, values, None).unwrap()),
+            ],
+        )
+        .unwrap();
+        let params = WriteParams {
+            mode: WriteMode::Append,
+            ..Default::default()
+        };
+        let reader = RecordBatchIterator::new(vec![Ok(fresh)], schema);
+        let dataset = lance::Dataset::write(reader, uri.to_str().unwrap(), Some(params))
+            .await
+            .unwrap();
+
+        let search = VectorSearch::new("embedding")
+            .query_vector(vec![0.0, 0.0, -1.0])
+            .top_k(1);
+        let nearest = |batch: &RecordBatch| {
+            batch
+                .column_by_name("name")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .value(0)
+                .to_string()
+        };
+
+        let merged = search
+            .clone()
+            .include_diagnostics(true)
+            .search_lance_result(&dataset)
+            .await
+            .unwrap();
+        assert!(merged.used_ann_index);
+        assert_eq!(merged.unindexed_rows, 1);
+        assert_eq!(nearest(&merged.data), "Zed");
+        let stages = merged
+            .data
+            .column_by_name(SEARCH_STAGE_COLUMN)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(stages.value(0), "flat");
+
+        let indexed_only = search
+            .clone()
+            .include_unindexed(false)
+            .search_lance(&dataset)
+            .await
+            .unwrap();
+        assert_ne!(nearest(&indexed_only), "Zed");
+        assert!(indexed_only.column_by_name("_rowid").is_none());
+
+        let explain = search.explain_lance(&dataset).await.unwrap();
+        assert!(
+            explain.contains("unindexed_rows=1 (brute force merged)"),
+            "{explain}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_recall_matches_exact_without_index() {
+        use arrow::array::RecordBatchIterator;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let uri = tmp_dir.path().join("people.lance");
+        let batch = create_test_batch();
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = lance::Dataset::write(reader, uri.to_str().unwrap(), None)
+            .await
+            .unwrap();
+
+        let sample = vec![vec![1.0, 0.0, 0.0], vec![0.0, 0.9, 0.1]];
+        let report = VectorSearch::new("embedding")
+            .top_k(2)
+            .evaluate_recall(&dataset, &sample)
+            .await
+            .unwrap();
+
+        assert_eq!(report.k, 2);
+        assert_eq!(report.recalls.len(), 2);
+        assert_eq!(report.mean_recall(), 1.0);
+        assert_eq!(report.min_recall(), 1.0);
+
+        // Without a sample, the configured query vector is required
+        let err = VectorSearch::new("embedding")
+            .evaluate_recall(&dataset, &[])
+            .await;
+        assert!(err.is_err());
+    }
 }
@@ -35,26 +35,72 @@
 //! # }
 //! ```
 
+pub mod algorithms;
 pub mod ast;
+mod batch;
+mod call_subquery;
 pub mod case_insensitive;
+pub mod checkpoint;
+pub mod clustering;
+pub mod coalesce;
 pub mod config;
+mod conflict;
+pub mod cost;
+pub mod credentials;
 pub mod datafusion_planner;
+mod delta;
+mod describe;
 pub mod error;
+pub mod expansion;
+pub mod graph_catalog;
+pub mod graph_diff;
+pub mod graph_projection;
+pub mod graph_writer;
+mod introspection;
+pub mod jobs;
 pub mod lance_native_planner;
 pub mod lance_vector_search;
+pub mod lint;
 pub mod logical_plan;
+pub mod lookup;
+pub mod output;
 pub mod parser;
+mod pattern_comprehension;
+pub mod plan_snapshot;
+pub mod pruning;
 pub mod query;
+pub mod query_monitor;
+#[cfg(feature = "redis")]
+pub mod redis_cache;
+pub mod result_cache;
+pub mod runtime;
+pub mod script;
 pub mod semantic;
+pub mod session;
+mod shortest_paths;
 pub mod simple_executor;
+pub mod subscription;
+pub mod summary;
+pub mod table_function;
+pub mod template;
+mod temporal;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
+mod transaction;
+mod vector_candidates;
+pub mod writing;
 
 /// Maximum allowed hops for variable-length relationship expansion (e.g., *1..N)
 pub const MAX_VARIABLE_LENGTH_HOPS: u32 = 20;
 
 pub use config::{GraphConfig, NodeMapping, RelationshipMapping};
+pub use datafusion_planner::OverflowMode;
 pub use error::{GraphError, Result};
+pub use graph_catalog::GraphCatalog;
 pub use lance_graph_catalog::{
-    DirNamespace, GraphSourceCatalog, InMemoryCatalog, SimpleTableSource,
+    DirNamespace, GraphSourceCatalog, InMemoryCatalog, SimpleTableSource, StorageOptions,
+    TenantNamespace,
 };
 pub use lance_vector_search::VectorSearch;
 pub use query::{CypherQuery, ExecutionStrategy};
+pub use semantic::{CompatibilityMode, ExperimentalFeature};
@@ -0,0 +1,771 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Query linting
+//!
+//! Flags queries that are valid but likely to be slow or hard to reuse:
+//! cartesian products, unbounded variable-length paths, unfiltered label
+//! scans, unused variables and inline literals. Findings are structured so
+//! tools (the language server, notebooks, CI checks) can surface them.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::lint::lint;
+//!
+//! for warning in lint("MATCH (a:Person), (b:Person) RETURN a, b", &config)? {
+//!     println!("{}", warning);
+//! }
+//! ```
+
+use crate::ast::*;
+use crate::config::GraphConfig;
+use crate::error::Result;
+use crate::parser::parse_cypher_query;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Category of a lint finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LintKind {
+    /// MATCH patterns that share no variable or join predicate
+    CartesianProduct,
+    /// Variable-length relationship without an upper bound (`*`, `*2..`)
+    UnboundedVariableLength,
+    /// Pattern that reads a whole label with no property filter
+    FullLabelScan,
+    /// Variable bound in a pattern but never referenced
+    UnusedVariable,
+    /// Literal value where a query parameter would allow reuse
+    NonParameterizedLiteral,
+}
+
+impl LintKind {
+    /// Stable kebab-case identifier, suitable for diagnostics codes
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintKind::CartesianProduct => "cartesian-product",
+            LintKind::UnboundedVariableLength => "unbounded-variable-length",
+            LintKind::FullLabelScan => "full-label-scan",
+            LintKind::UnusedVariable => "unused-variable",
+            LintKind::NonParameterizedLiteral => "non-parameterized-literal",
+        }
+    }
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    pub message: String,
+    /// Query variable the finding is about, when there is one
+    pub variable: Option<String>,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.code(), self.message)
+    }
+}
+
+/// Parse and lint a Cypher query against a graph schema
+pub fn lint(query: &str, config: &GraphConfig) -> Result<Vec<LintWarning>> {
+    let query = parse_cypher_query(query)?;
+    Ok(lint_query(&query, config))
+}
+
+/// Lint an already parsed query
+pub fn lint_query(query: &CypherQuery, config: &GraphConfig) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    // Variables projected by WITH are already bound (and usually filtered)
+    // when the post-WITH MATCH runs
+    let carried: HashSet<String> = query
+        .with_clause
+        .iter()
+        .flat_map(|with| &with.items)
+        .flat_map(|item| {
+            let mut vars = Vec::new();
+            collect_value_variables(&item.expression, &mut vars);
+            vars.extend(item.alias.as_ref().map(|a| a.to_lowercase()));
+            vars
+        })
+        .collect();
+
+    lint_stage(
+        &query.reading_clauses,
+        query.where_clause.as_ref(),
+        &HashSet::new(),
+        config,
+        &mut warnings,
+    );
+    lint_stage(
+        &query.post_with_reading_clauses,
+        query.post_with_where_clause.as_ref(),
+        &carried,
+        config,
+        &mut warnings,
+    );
+    lint_unused_variables(query, &mut warnings);
+    warnings
+}
+
+/// Variables bound by one pattern, plus whether it carries inline properties
+struct PatternInfo<'a> {
+    variables: HashSet<String>,
+    labels: Vec<&'a str>,
+    has_inline_filter: bool,
+}
+
+/// Lint the patterns and predicate of one MATCH ... WHERE stage
+fn lint_stage(
+    clauses: &[ReadingClause],
+    where_clause: Option<&WhereClause>,
+    carried: &HashSet<String>,
+    config: &GraphConfig,
+    warnings: &mut Vec<LintWarning>,
+) {
+    let mut patterns = Vec::new();
+    for clause in clauses {
+        if let ReadingClause::Match(match_clause) = clause {
+            for pattern in &match_clause.patterns {
+                patterns.push(pattern_info(pattern, warnings));
+            }
+            if let Some(optional_where) = &match_clause.where_clause {
+                lint_literals(&optional_where.expression, warnings);
+            }
+        }
+    }
+
+    if let Some(where_clause) = where_clause {
+        lint_literals(&where_clause.expression, warnings);
+    }
+
+    // Group patterns connected through shared variables or join predicates
+    let mut components: Vec<(HashSet<String>, Vec<usize>)> = Vec::new();
+    for (idx, pattern) in patterns.iter().enumerate() {
+        merge_component(&mut components, &pattern.variables, Some(idx));
+    }
+    let mut filtered: HashSet<String> = carried.clone();
+    if let Some(where_clause) = where_clause {
+        for predicate in predicate_leaves(&where_clause.expression) {
+            let mut vars = Vec::new();
+            collect_boolean_variables(predicate, &mut vars);
+            let vars: HashSet<String> = vars.into_iter().collect();
+            merge_component(&mut components, &vars, None);
+            filtered.extend(vars);
+        }
+    }
+    // Carried variables link every pattern that touches them to the prior rows
+    merge_component(&mut components, carried, None);
+
+    if components.len() > 1 {
+        let groups: Vec<String> = components
+            .iter()
+            .map(|(vars, _)| describe_variables(vars))
+            .collect();
+        warnings.push(LintWarning {
+            kind: LintKind::CartesianProduct,
+            message: format!(
+                "MATCH patterns {} are not connected; the result is their cartesian product",
+                groups.join(" and ")
+            ),
+            variable: None,
+        });
+    }
+
+    for (vars, members) in &components {
+        let is_filtered = vars.iter().any(|v| filtered.contains(v))
+            || members.iter().any(|&i| patterns[i].has_inline_filter);
+        if is_filtered {
+            continue;
+        }
+        let label = members
+            .iter()
+            .flat_map(|&i| patterns[i].labels.iter())
+            .find(|label| config.get_node_mapping(label).is_some());
+        if let Some(label) = label {
+            warnings.push(LintWarning {
+                kind: LintKind::FullLabelScan,
+                message: format!(
+                    "Pattern {} has no filter; every `{}` node is scanned",
+                    describe_variables(vars),
+                    label
+                ),
+                variable: None,
+            });
+        }
+    }
+}
+
+fn pattern_info<'a>(pattern: &'a GraphPattern, warnings: &mut Vec<LintWarning>) -> PatternInfo<'a> {
+    let mut info = PatternInfo {
+        variables: HashSet::new(),
+        labels: Vec::new(),
+        has_inline_filter: false,
+    };
+    match pattern {
+        GraphPattern::Node(node) => visit_node(node, &mut info, warnings),
+        GraphPattern::Path(path) => {
+            info.variables
+                .extend(path.variable.as_ref().map(|v| v.to_lowercase()));
+            visit_node(&path.start_node, &mut info, warnings);
+            for segment in &path.segments {
+                let rel = &segment.relationship;
+                info.variables
+                    .extend(rel.variable.as_ref().map(|v| v.to_lowercase()));
+                info.has_inline_filter |= !rel.properties.is_empty();
+                lint_inline_properties(rel.variable.as_deref(), &rel.properties, warnings);
+                if let Some(LengthRange { max: None, .. }) = rel.length {
+                    let name = rel
+                        .variable
+                        .clone()
+                        .or_else(|| rel.types.first().cloned())
+                        .unwrap_or_else(|| "relationship".to_string());
+                    warnings.push(LintWarning {
+                        kind: LintKind::UnboundedVariableLength,
+                        message: format!(
+                            "Variable-length relationship `{}` has no upper bound; expansion is capped at {} hops",
+                            name,
+                            crate::MAX_VARIABLE_LENGTH_HOPS
+                        ),
+                        variable: rel.variable.clone(),
+                    });
+                }
+                visit_node(&segment.end_node, &mut info, warnings);
+            }
+        }
+    }
+    info
+}
+
+fn visit_node<'a>(
+    node: &'a NodePattern,
+    info: &mut PatternInfo<'a>,
+    warnings: &mut Vec<LintWarning>,
+) {
+    info.variables
+        .extend(node.variable.as_ref().map(|v| v.to_lowercase()));
+    info.labels.extend(node.labels.iter().map(String::as_str));
+    info.has_inline_filter |= !node.properties.is_empty();
+    lint_inline_properties(node.variable.as_deref(), &node.properties, warnings);
+}
+
+/// Merge every component sharing a variable with `vars`
+///
+/// With `pattern` set, `vars` always forms (or joins) a component; otherwise
+/// `vars` only links components that already exist.
+fn merge_component(
+    components: &mut Vec<(HashSet<String>, Vec<usize>)>,
+    vars: &HashSet<String>,
+    pattern: Option<usize>,
+) {
+    let (touching, rest): (Vec<_>, Vec<_>) = std::mem::take(components)
+        .into_iter()
+        .partition(|(existing, _)| !existing.is_disjoint(vars));
+    *components = rest;
+    if touching.is_empty() && pattern.is_none() {
+        return;
+    }
+    let mut merged_vars = vars.clone();
+    let mut merged_members: Vec<usize> = pattern.into_iter().collect();
+    for (existing, members) in touching {
+        merged_vars.extend(existing);
+        merged_members.extend(members);
+    }
+    merged_members.sort_unstable();
+    // Keep components in query order
+    let position = components
+        .iter()
+        .position(|(_, members)| members.first() > merged_members.first())
+        .unwrap_or(components.len());
+    components.insert(position, (merged_vars, merged_members));
+}
+
+fn describe_variables(vars: &HashSet<String>) -> String {
+    if vars.is_empty() {
+        return "(anonymous)".to_string();
+    }
+    let mut vars: Vec<&str> = vars.iter().map(String::as_str).collect();
+    vars.sort_unstable();
+    format!("({})", vars.join(", "))
+}
+
+/// Split a predicate into its AND/OR/NOT-free leaves
+fn predicate_leaves(expr: &BooleanExpression) -> Vec<&BooleanExpression> {
+    match expr {
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            let mut leaves = predicate_leaves(left);
+            leaves.extend(predicate_leaves(right));
+            leaves
+        }
+        BooleanExpression::Not(inner) => predicate_leaves(inner),
+        other => vec![other],
+    }
+}
+
+fn lint_literals(expr: &BooleanExpression, warnings: &mut Vec<LintWarning>) {
+    for leaf in predicate_leaves(expr) {
+        let (subject, literals): (&ValueExpression, Vec<&ValueExpression>) = match leaf {
+            BooleanExpression::Comparison { left, right, .. } => match (left, right) {
+                (ValueExpression::Literal(_), subject) => (subject, vec![left]),
+                (subject, literal) => (subject, vec![literal]),
+            },
+            BooleanExpression::In { expression, list } => (expression, list.iter().collect()),
+            _ => continue,
+        };
+        for literal in literals {
+            if let ValueExpression::Literal(value) = literal {
+                push_literal_warning(subject_name(subject), value, warnings);
+            }
+        }
+    }
+}
+
+fn lint_inline_properties(
+    variable: Option<&str>,
+    properties: &HashMap<String, PropertyValue>,
+    warnings: &mut Vec<LintWarning>,
+) {
+    let mut keys: Vec<&String> = properties.keys().collect();
+    keys.sort();
+    for key in keys {
+        let subject = (variable.unwrap_or_default().to_string(), key.clone());
+        push_literal_warning(Some(subject), &properties[key], warnings);
+    }
+}
+
+/// `(variable, property)` a literal is compared against, when known
+fn subject_name(expr: &ValueExpression) -> Option<(String, String)> {
+    match expr {
+        ValueExpression::Property(prop) => Some((prop.variable.clone(), prop.property.clone())),
+        _ => None,
+    }
+}
+
+fn push_literal_warning(
+    subject: Option<(String, String)>,
+    value: &PropertyValue,
+    warnings: &mut Vec<LintWarning>,
+) {
+    let literal = match value {
+        PropertyValue::String(s) => format!("'{}'", s),
+        PropertyValue::Integer(i) => i.to_string(),
+        PropertyValue::Float(f) => f.to_string(),
+        PropertyValue::Boolean(b) => b.to_string(),
+        PropertyValue::Null | PropertyValue::Parameter(_) | PropertyValue::Property(_) => return,
+    };
+    let (target, suggestion, variable) = match subject {
+        Some((var, property)) if !var.is_empty() => (
+            format!(" for `{}.{}`", var, property),
+            format!("${}", property),
+            Some(var),
+        ),
+        Some((_, property)) => (
+            format!(" for `{}`", property),
+            format!("${}", property),
+            None,
+        ),
+        None => (String::new(), "a parameter".to_string(), None),
+    };
+    warnings.push(LintWarning {
+        kind: LintKind::NonParameterizedLiteral,
+        message: format!(
+            "Literal {}{} is inlined in the query; use {} so the query can be reused",
+            literal, target, suggestion
+        ),
+        variable,
+    });
+}
+
+fn lint_unused_variables(query: &CypherQuery, warnings: &mut Vec<LintWarning>) {
+    // Bindings in pattern order, with how many times each name is bound
+    let mut bound: Vec<String> = Vec::new();
+    let mut bind_counts: HashMap<String, usize> = HashMap::new();
+    let mut referenced: Vec<String> = Vec::new();
+
+    let mut bind = |var: &Option<String>| {
+        if let Some(var) = var {
+            let key = var.to_lowercase();
+            let count = bind_counts.entry(key).or_insert(0);
+            if *count == 0 {
+                bound.push(var.clone());
+            }
+            *count += 1;
+        }
+    };
+
+    for clause in query
+        .reading_clauses
+        .iter()
+        .chain(&query.post_with_reading_clauses)
+    {
+        match clause {
+            ReadingClause::Match(match_clause) => {
+                for pattern in &match_clause.patterns {
+                    match pattern {
+                        GraphPattern::Node(node) => {
+                            bind(&node.variable);
+                            reference_properties(&node.properties, &mut referenced);
+                        }
+                        GraphPattern::Path(path) => {
+                            bind(&path.variable);
+                            bind(&path.start_node.variable);
+                            reference_properties(&path.start_node.properties, &mut referenced);
+                            for segment in &path.segments {
+                                bind(&segment.relationship.variable);
+                                reference_properties(
+                                    &segment.relationship.properties,
+                                    &mut referenced,
+                                );
+                                bind(&segment.end_node.variable);
+                                reference_properties(&segment.end_node.properties, &mut referenced);
+                            }
+                        }
+                    }
+                }
+                if let Some(where_clause) = &match_clause.where_clause {
+                    collect_boolean_variables(&where_clause.expression, &mut referenced);
+                }
+            }
+            ReadingClause::Unwind(unwind) => {
+                collect_value_variables(&unwind.expression, &mut referenced);
+            }
+            ReadingClause::TableFunction(call) => {
+                for argument in &call.arguments {
+                    collect_value_variables(argument, &mut referenced);
+                }
+            }
+        }
+    }
+
+    for where_clause in [&query.where_clause, &query.post_with_where_clause]
+        .into_iter()
+        .flatten()
+    {
+        collect_boolean_variables(&where_clause.expression, &mut referenced);
+    }
+    if let Some(with) = &query.with_clause {
+        for item in &with.items {
+            collect_value_variables(&item.expression, &mut referenced);
+        }
+        for item in with.order_by.iter().flat_map(|o| &o.items) {
+            collect_value_variables(&item.expression, &mut referenced);
+        }
+    }
+    for item in &query.return_clause.items {
+        collect_value_variables(&item.expression, &mut referenced);
+    }
+    for item in query.order_by.iter().flat_map(|o| &o.items) {
+        collect_value_variables(&item.expression, &mut referenced);
+    }
+
+    let referenced: HashSet<String> = referenced.into_iter().collect();
+    for var in bound {
+        let key = var.to_lowercase();
+        if bind_counts[&key] == 1 && !referenced.contains(&key) {
+            warnings.push(LintWarning {
+                kind: LintKind::UnusedVariable,
+                message: format!(
+                    "Variable `{}` is bound but never used; leave the pattern element anonymous",
+                    var
+                ),
+                variable: Some(var),
+            });
+        }
+    }
+}
+
+fn reference_properties(properties: &HashMap<String, PropertyValue>, referenced: &mut Vec<String>) {
+    for value in properties.values() {
+        if let PropertyValue::Property(prop) = value {
+            referenced.push(prop.variable.to_lowercase());
+        }
+    }
+}
+
+fn collect_boolean_variables(expr: &BooleanExpression, vars: &mut Vec<String>) {
+    match expr {
+        BooleanExpression::Comparison { left, right, .. } => {
+            collect_value_variables(left, vars);
+            collect_value_variables(right, vars);
+        }
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            collect_boolean_variables(left, vars);
+            collect_boolean_variables(right, vars);
+        }
+        BooleanExpression::Not(inner) => collect_boolean_variables(inner, vars),
+        BooleanExpression::Exists(prop) => vars.push(prop.variable.to_lowercase()),
+        BooleanExpression::In { expression, list } => {
+            collect_value_variables(expression, vars);
+            for item in list {
+                collect_value_variables(item, vars);
+            }
+        }
+        BooleanExpression::Like { expression, .. }
+        | BooleanExpression::ILike { expression, .. }
+        | BooleanExpression::Contains { expression, .. }
+        | BooleanExpression::StartsWith { expression, .. }
+        | BooleanExpression::EndsWith { expression, .. }
+        | BooleanExpression::RegexMatch { expression, .. }
+        | BooleanExpression::IsNull(expression)
+        | BooleanExpression::IsNotNull(expression) => collect_value_variables(expression, vars),
+        BooleanExpression::AllInPath {
+            variable,
+            path,
+            predicate,
+            ..
+        } => {
+            // The element variable is local to the predicate
+            vars.push(path.to_lowercase());
+            let mut inner = Vec::new();
+            collect_boolean_variables(predicate, &mut inner);
+            let element = variable.to_lowercase();
+            vars.extend(inner.into_iter().filter(|v| *v != element));
+        }
+        BooleanExpression::ListPredicate {
+            variable,
+            list,
+            predicate,
+            ..
+        } => {
+            // The element variable is local to the predicate
+            collect_value_variables(list, vars);
+            let mut inner = Vec::new();
+            collect_boolean_variables(predicate, &mut inner);
+            let element = variable.to_lowercase();
+            vars.extend(inner.into_iter().filter(|v| *v != element));
+        }
+        BooleanExpression::ExistsSubquery {
+            patterns,
+            where_clause,
+        } => collect_subquery_variables(patterns, where_clause.as_deref(), vars),
+    }
+}
+
+/// Variables an `EXISTS { ... }` or `COUNT { ... }` subquery uses
+fn collect_subquery_variables(
+    patterns: &[GraphPattern],
+    where_clause: Option<&BooleanExpression>,
+    vars: &mut Vec<String>,
+) {
+    // Node variables bound by the query are used by the subquery
+    for pattern in patterns {
+        match pattern {
+            GraphPattern::Node(node) => {
+                vars.extend(node.variable.as_ref().map(|v| v.to_lowercase()))
+            }
+            GraphPattern::Path(path) => {
+                let nodes = std::iter::once(&path.start_node)
+                    .chain(path.segments.iter().map(|segment| &segment.end_node));
+                vars.extend(
+                    nodes.filter_map(|node| node.variable.as_ref().map(|v| v.to_lowercase())),
+                );
+            }
+        }
+    }
+    if let Some(where_clause) = where_clause {
+        collect_boolean_variables(where_clause, vars);
+    }
+}
+
+fn collect_value_variables(expr: &ValueExpression, vars: &mut Vec<String>) {
+    match expr {
+        ValueExpression::Variable(var) => vars.push(var.to_lowercase()),
+        ValueExpression::Property(prop) => vars.push(prop.variable.to_lowercase()),
+        ValueExpression::ScalarFunction { args, .. }
+        | ValueExpression::AggregateFunction { args, .. } => {
+            for arg in args {
+                collect_value_variables(arg, vars);
+            }
+        }
+        ValueExpression::Arithmetic { left, right, .. }
+        | ValueExpression::VectorDistance { left, right, .. }
+        | ValueExpression::VectorSimilarity { left, right, .. } => {
+            collect_value_variables(left, vars);
+            collect_value_variables(right, vars);
+        }
+        ValueExpression::Case { branches, default } => {
+            for branch in branches {
+                collect_boolean_variables(&branch.condition, vars);
+                collect_value_variables(&branch.value, vars);
+            }
+            if let Some(default) = default {
+                collect_value_variables(default, vars);
+            }
+        }
+        ValueExpression::ListComprehension {
+            variable,
+            list,
+            predicate,
+            projection,
+        } => {
+            // The element variable is local to the comprehension
+            collect_value_variables(list, vars);
+            let mut inner = Vec::new();
+            if let Some(predicate) = predicate {
+                collect_boolean_variables(predicate, &mut inner);
+            }
+            if let Some(projection) = projection {
+                collect_value_variables(projection, &mut inner);
+            }
+            let element = variable.to_lowercase();
+            vars.extend(inner.into_iter().filter(|v| *v != element));
+        }
+        ValueExpression::Reduce {
+            accumulator,
+            initial,
+            variable,
+            list,
+            expression,
+        } => {
+            // The accumulator and element variables are local to the reduction
+            collect_value_variables(initial, vars);
+            collect_value_variables(list, vars);
+            let mut inner = Vec::new();
+            collect_value_variables(expression, &mut inner);
+            let locals = [accumulator.to_lowercase(), variable.to_lowercase()];
+            vars.extend(inner.into_iter().filter(|v| !locals.contains(v)));
+        }
+        ValueExpression::PatternComprehension {
+            pattern,
+            predicate,
+            projection,
+        } => {
+            // Pattern variables bound by the query are used by the comprehension
+            let nodes = std::iter::once(&pattern.start_node)
+                .chain(pattern.segments.iter().map(|segment| &segment.end_node));
+            vars.extend(nodes.filter_map(|node| node.variable.as_ref().map(|v| v.to_lowercase())));
+            if let Some(predicate) = predicate {
+                collect_boolean_variables(predicate, vars);
+            }
+            collect_value_variables(projection, vars);
+        }
+        ValueExpression::MapProjection { variable, items } => {
+            vars.push(variable.to_lowercase());
+            for item in items {
+                collect_value_variables(&item.value, vars);
+            }
+        }
+        ValueExpression::CountSubquery {
+            patterns,
+            where_clause,
+        } => collect_subquery_variables(patterns, where_clause.as_deref(), vars),
+        ValueExpression::Literal(_)
+        | ValueExpression::Parameter(_)
+        | ValueExpression::VectorLiteral(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{NodeMapping, RelationshipMapping};
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("Person", "id")
+                    .with_properties(vec!["name".to_string(), "age".to_string()]),
+            )
+            .with_relationship_mapping(RelationshipMapping::new("KNOWS", "src_id", "dst_id"))
+            .build()
+            .unwrap()
+    }
+
+    fn kinds(query: &str) -> Vec<LintKind> {
+        lint(query, &config())
+            .unwrap()
+            .into_iter()
+            .map(|w| w.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_query_has_no_warnings() {
+        let warnings = lint(
+            "MATCH (a:Person)-[:KNOWS*1..3]->(b:Person) WHERE a.name = $name RETURN b.name",
+            &config(),
+        )
+        .unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_cartesian_product() {
+        let warnings = lint(
+            "MATCH (a:Person), (b:Person) WHERE a.age > $min AND b.age > $min RETURN a.name, b.name",
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0].kind, LintKind::CartesianProduct);
+        assert!(warnings[0].message.contains("(a) and (b)"));
+    }
+
+    #[test]
+    fn test_join_predicate_connects_patterns() {
+        let kinds = kinds("MATCH (a:Person), (b:Person) WHERE a.age = b.age RETURN a.name, b.name");
+        assert!(!kinds.contains(&LintKind::CartesianProduct));
+        assert!(!kinds.contains(&LintKind::FullLabelScan));
+    }
+
+    #[test]
+    fn test_unbounded_variable_length() {
+        let kinds =
+            kinds("MATCH (a:Person {name: $name})-[:KNOWS*2..]->(b:Person) RETURN a.name, b.name");
+        assert_eq!(kinds, vec![LintKind::UnboundedVariableLength]);
+    }
+
+    #[test]
+    fn test_full_label_scan() {
+        let warnings = lint("MATCH (n:Person) RETURN n.name", &config()).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, LintKind::FullLabelScan);
+        assert!(warnings[0].message.contains("`Person`"));
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let warnings = lint(
+            "MATCH (a:Person)-[r:KNOWS]->(b:Person) WHERE a.name = $name RETURN a.name",
+            &config(),
+        )
+        .unwrap();
+        let unused: Vec<_> = warnings
+            .iter()
+            .filter(|w| w.kind == LintKind::UnusedVariable)
+            .filter_map(|w| w.variable.as_deref())
+            .collect();
+        assert_eq!(unused, vec!["r", "b"]);
+    }
+
+    #[test]
+    fn test_non_parameterized_literals() {
+        let warnings = lint(
+            "MATCH (a:Person {name: 'Alice'}) WHERE a.age > 30 RETURN a.name",
+            &config(),
+        )
+        .unwrap();
+        let literals: Vec<_> = warnings
+            .iter()
+            .filter(|w| w.kind == LintKind::NonParameterizedLiteral)
+            .collect();
+        assert_eq!(literals.len(), 2);
+        assert!(literals[0].message.contains("'Alice' for `a.name`"));
+        assert!(literals[1].message.contains("30 for `a.age`"));
+        assert_eq!(
+            literals[1].to_string(),
+            "non-parameterized-literal: Literal 30 for `a.age` is inlined in the query; use $age so the query can be reused"
+        );
+    }
+
+    #[test]
+    fn test_carried_variables_are_not_rescanned() {
+        let kinds = kinds(
+            "MATCH (a:Person) WHERE a.name = $name WITH a MATCH (a)-[:KNOWS]->(b:Person) RETURN b.name",
+        );
+        assert!(kinds.is_empty(), "{:?}", kinds);
+    }
+}
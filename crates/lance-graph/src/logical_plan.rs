@@ -34,12 +34,25 @@ pub enum LogicalOperator {
         alias: String,
     },
 
+    /// Call a registered table function and bind its yielded columns
+    TableFunction {
+        /// Rows the yielded rows are combined with, if any
+        input: Option<Box<LogicalOperator>>,
+        call: TableFunctionCall,
+    },
+
     /// Apply a filter predicate (WHERE clause)
     Filter {
         input: Box<LogicalOperator>,
         predicate: BooleanExpression,
     },
 
+    /// Keep a random subset of the rows (SAMPLE clause)
+    Sample {
+        input: Box<LogicalOperator>,
+        method: SampleMethod,
+    },
+
     /// Traverse relationships (the core graph operation)
     ///
     /// Represents a single-hop relationship traversal: (source)-[rel]->(target)
@@ -88,6 +101,8 @@ pub enum LogicalOperator {
         max_length: Option<u32>,
         /// Property filters to apply on target nodes
         target_properties: HashMap<String, PropertyValue>,
+        /// Relationship and node predicates checked at every hop
+        path_filter: PathFilter,
     },
 
     /// Project specific columns (RETURN clause)
@@ -106,6 +121,12 @@ pub enum LogicalOperator {
     /// Apply DISTINCT
     Distinct { input: Box<LogicalOperator> },
 
+    /// Keep one row per distinct value of `keys`
+    DistinctOn {
+        input: Box<LogicalOperator>,
+        keys: Vec<ValueExpression>,
+    },
+
     /// Apply ORDER BY
     Sort {
         input: Box<LogicalOperator>,
@@ -140,6 +161,28 @@ pub enum JoinType {
     Right,
     Full,
     Cross,
+    /// Keep the left rows with at least one match on the right (`EXISTS { ... }`)
+    Semi,
+    /// Keep the left rows without any match on the right (`NOT EXISTS { ... }`)
+    Anti,
+}
+
+/// Predicates a variable-length expansion checks while extending the frontier,
+/// so paths through a rejected relationship or node are never materialized
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PathFilter {
+    /// Path variable bound to the pattern (`p = (a)-[*1..3]->(b)`)
+    pub path_variable: Option<String>,
+    /// Inline property map on the relationship (`-[:KNOWS*1..3 {active: true}]-`)
+    pub relationship_properties: HashMap<String, PropertyValue>,
+    /// `all(x IN nodes(p) WHERE ...)` predicates as (element variable, predicate)
+    pub node_predicates: Vec<(String, BooleanExpression)>,
+    /// `all(r IN relationships(p) WHERE ...)` predicates as (element variable, predicate)
+    pub relationship_predicates: Vec<(String, BooleanExpression)>,
+    /// Keep only the shortest paths between each pair of end nodes
+    /// (`shortestPath(...)` / `allShortestPaths(...)`)
+    #[serde(default)]
+    pub shortest: Option<ShortestPathKind>,
 }
 
 /// Sort specification for ORDER BY
@@ -153,6 +196,8 @@ pub struct SortItem {
 pub struct LogicalPlanner<'a> {
     /// Track variables in scope
     variables: HashMap<String, String>, // variable -> label
+    /// Number of anonymous nodes named so far
+    anonymous_nodes: usize,
     config: &'a GraphConfig,
 }
 
@@ -160,21 +205,32 @@ impl<'a> LogicalPlanner<'a> {
     pub fn new(config: &'a GraphConfig) -> Self {
         Self {
             variables: HashMap::new(),
+            anonymous_nodes: 0,
             config,
         }
     }
 
+    /// A fresh variable name for an anonymous node pattern
+    fn anonymous_node(&mut self) -> String {
+        self.anonymous_nodes += 1;
+        format!("_node_{}", self.anonymous_nodes - 1)
+    }
+
     /// Convert a Cypher AST to a logical plan
     pub fn plan(&mut self, query: &CypherQuery) -> Result<LogicalOperator> {
+        if let Some(call) = &query.procedure {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!("planning CALL {}(); execute the query instead", call.name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
         // Plan main MATCH clauses
         let mut plan = self.plan_reading_clauses(None, &query.reading_clauses)?;
 
         // Apply WHERE clause if present (before WITH)
         if let Some(where_clause) = &query.where_clause {
-            plan = LogicalOperator::Filter {
-                input: Box::new(plan),
-                predicate: where_clause.expression.clone(),
-            };
+            plan = self.plan_where(plan, &where_clause.expression)?;
         }
 
         // Apply WITH clause if present (intermediate projection/aggregation)
@@ -189,9 +245,14 @@ impl<'a> LogicalPlanner<'a> {
 
         // Apply post-WITH WHERE clause if present
         if let Some(post_where) = &query.post_with_where_clause {
-            plan = LogicalOperator::Filter {
+            plan = self.plan_where(plan, &post_where.expression)?;
+        }
+
+        // Apply SAMPLE clause if present
+        if let Some(method) = &query.sample {
+            plan = LogicalOperator::Sample {
                 input: Box::new(plan),
-                predicate: post_where.expression.clone(),
+                method: method.clone(),
             };
         }
 
@@ -269,6 +330,16 @@ impl<'a> LogicalPlanner<'a> {
             ReadingClause::Unwind(unwind_clause) => {
                 self.plan_unwind_clause_with_base(base, unwind_clause)
             }
+            ReadingClause::TableFunction(call) => {
+                for item in &call.yields {
+                    self.variables
+                        .insert(item.variable().to_string(), "Yielded".to_string());
+                }
+                Ok(LogicalOperator::TableFunction {
+                    input: base.map(Box::new),
+                    call: call.clone(),
+                })
+            }
         }
     }
 
@@ -302,6 +373,10 @@ impl<'a> LogicalPlanner<'a> {
             });
         }
 
+        if match_clause.optional {
+            return self.plan_optional_match(base, match_clause);
+        }
+
         let mut plan = base;
         for pattern in &match_clause.patterns {
             match pattern {
@@ -334,12 +409,159 @@ impl<'a> LogicalPlanner<'a> {
         })
     }
 
+    /// Plan an `OPTIONAL MATCH` as a left join of the rows so far with its patterns
+    ///
+    /// The patterns (and the clause's own WHERE) are planned as an independent
+    /// subplan joined on the variables it shares with `base`, so rows without
+    /// a match are kept with nulls for the newly introduced variables.
+    fn plan_optional_match(
+        &mut self,
+        base: Option<LogicalOperator>,
+        match_clause: &MatchClause,
+    ) -> Result<LogicalOperator> {
+        let Some(base) = base else {
+            return Err(GraphError::PlanError {
+                message: "OPTIONAL MATCH must follow a MATCH or UNWIND clause".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+
+        let mut right = self.plan_independent_patterns(&match_clause.patterns)?;
+        if let Some(where_clause) = &match_clause.where_clause {
+            right = self.plan_where(right, &where_clause.expression)?;
+        }
+
+        Ok(LogicalOperator::Join {
+            left: Box::new(base),
+            right: Box::new(right),
+            join_type: JoinType::Left,
+        })
+    }
+
+    /// Plan `patterns` on their own, without a base plan, as the right side
+    /// of a join with the rows so far
+    fn plan_independent_patterns(&mut self, patterns: &[GraphPattern]) -> Result<LogicalOperator> {
+        let mut right: Option<LogicalOperator> = None;
+        for pattern in patterns {
+            let pattern_plan = match pattern {
+                GraphPattern::Node(node) => self.plan_node_scan(node)?,
+                GraphPattern::Path(path) => self.plan_path(None, path)?,
+            };
+            right = Some(match right {
+                None => pattern_plan,
+                Some(plan) => LogicalOperator::Join {
+                    left: Box::new(plan),
+                    right: Box::new(pattern_plan),
+                    join_type: JoinType::Cross,
+                },
+            });
+        }
+        right.ok_or_else(|| GraphError::PlanError {
+            message: "Failed to plan the patterns of a subquery".to_string(),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+
+    /// Apply a WHERE predicate to `plan`
+    ///
+    /// Path predicates move into their expansions, and top-level
+    /// `EXISTS { ... }` and `NOT EXISTS { ... }` conjuncts become semi- and
+    /// anti-joins with the subquery's patterns. The rest is a Filter, applied
+    /// before the joins so they only probe rows that pass it.
+    fn plan_where(
+        &mut self,
+        mut plan: LogicalOperator,
+        predicate: &BooleanExpression,
+    ) -> Result<LogicalOperator> {
+        let Some(predicate) = attach_path_predicates(&mut plan, predicate)? else {
+            return Ok(plan);
+        };
+        let mut conjuncts = Vec::new();
+        split_conjuncts(&predicate, &mut conjuncts);
+
+        let mut remaining: Option<BooleanExpression> = None;
+        let mut subqueries = Vec::new();
+        for conjunct in conjuncts {
+            let subquery = match conjunct {
+                BooleanExpression::ExistsSubquery {
+                    patterns,
+                    where_clause,
+                } => (patterns, where_clause, JoinType::Semi),
+                BooleanExpression::Not(inner) => match inner.as_ref() {
+                    BooleanExpression::ExistsSubquery {
+                        patterns,
+                        where_clause,
+                    } => (patterns, where_clause, JoinType::Anti),
+                    _ => {
+                        remaining = Some(and_predicate(remaining, conjunct)?);
+                        continue;
+                    }
+                },
+                _ => {
+                    remaining = Some(and_predicate(remaining, conjunct)?);
+                    continue;
+                }
+            };
+            subqueries.push(subquery);
+        }
+
+        if let Some(predicate) = remaining {
+            plan = LogicalOperator::Filter {
+                input: Box::new(plan),
+                predicate,
+            };
+        }
+        for (patterns, where_clause, join_type) in subqueries {
+            let right = self.plan_exists_subquery(patterns, where_clause.as_deref())?;
+            plan = LogicalOperator::Join {
+                left: Box::new(plan),
+                right: Box::new(right),
+                join_type,
+            };
+        }
+        Ok(plan)
+    }
+
+    /// Plan the patterns and predicate of an `EXISTS { ... }` subquery as the
+    /// right side of a semi- or anti-join
+    ///
+    /// Like an OPTIONAL MATCH, the patterns are planned independently and
+    /// joined on the variables they share with the outer rows. The variables
+    /// they introduce go out of scope again afterwards.
+    fn plan_exists_subquery(
+        &mut self,
+        patterns: &[GraphPattern],
+        predicate: Option<&BooleanExpression>,
+    ) -> Result<LogicalOperator> {
+        let shares_variable = patterns
+            .iter()
+            .flat_map(pattern_variables)
+            .any(|variable| self.variables.contains_key(variable));
+        if !shares_variable {
+            return Err(GraphError::PlanError {
+                message: "EXISTS subquery must share a variable with the enclosing MATCH"
+                    .to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let outer = self.variables.clone();
+        let result = self
+            .plan_independent_patterns(patterns)
+            .and_then(|right| match predicate {
+                Some(predicate) => self.plan_where(right, predicate),
+                None => Ok(right),
+            });
+        self.variables = outer;
+        result
+    }
+
     /// Plan a node scan (ScanByLabel)
     fn plan_node_scan(&mut self, node: &NodePattern) -> Result<LogicalOperator> {
         let variable = node
             .variable
             .clone()
-            .unwrap_or_else(|| format!("_node_{}", self.variables.len()));
+            .unwrap_or_else(|| self.anonymous_node());
 
         // Validate label consistency if variable already exists
         self.validate_variable_label(&variable, &node.labels)?;
@@ -413,7 +635,7 @@ impl<'a> LogicalPlanner<'a> {
                 .end_node
                 .variable
                 .clone()
-                .unwrap_or_else(|| format!("_node_{}", self.variables.len()));
+                .unwrap_or_else(|| self.anonymous_node());
 
             // Validate label consistency for already-registered variables
             self.validate_variable_label(&target_variable, &segment.end_node.labels)?;
@@ -432,7 +654,10 @@ impl<'a> LogicalPlanner<'a> {
             // Optimize fixed-length var-length expansions (*1 or *1..1)
             let next_plan = match segment.relationship.length.as_ref() {
                 Some(length_range)
-                    if length_range.min == Some(1) && length_range.max == Some(1) =>
+                    if length_range.min == Some(1)
+                        && length_range.max == Some(1)
+                        && path.variable.is_none()
+                        && path.shortest.is_none() =>
                 {
                     LogicalOperator::Expand {
                         input: Box::new(plan),
@@ -456,6 +681,14 @@ impl<'a> LogicalPlanner<'a> {
                     min_length: length_range.min,
                     max_length: length_range.max,
                     target_properties: segment.end_node.properties.clone(),
+                    path_filter: PathFilter {
+                        // nodes(p)/relationships(p) only line up with the
+                        // expansion when it is the whole path
+                        path_variable: path.variable.clone().filter(|_| path.segments.len() == 1),
+                        relationship_properties: segment.relationship.properties.clone(),
+                        shortest: path.shortest,
+                        ..Default::default()
+                    },
                 },
                 None => LogicalOperator::Expand {
                     input: Box::new(plan),
@@ -483,6 +716,14 @@ impl<'a> LogicalPlanner<'a> {
         match plan {
             LogicalOperator::ScanByLabel { variable, .. } => Ok(variable.clone()),
             LogicalOperator::Unwind { alias, .. } => Ok(alias.clone()),
+            LogicalOperator::TableFunction { call, .. } => call
+                .yields
+                .last()
+                .map(|item| item.variable().to_string())
+                .ok_or_else(|| GraphError::PlanError {
+                    message: format!("CALL {} yields no columns", call.name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                }),
             LogicalOperator::Expand {
                 target_variable, ..
             } => Ok(target_variable.clone()),
@@ -490,8 +731,10 @@ impl<'a> LogicalPlanner<'a> {
                 target_variable, ..
             } => Ok(target_variable.clone()),
             LogicalOperator::Filter { input, .. } => self.extract_variable_from_plan(input),
+            LogicalOperator::Sample { input, .. } => self.extract_variable_from_plan(input),
             LogicalOperator::Project { input, .. } => self.extract_variable_from_plan(input),
             LogicalOperator::Distinct { input } => self.extract_variable_from_plan(input),
+            LogicalOperator::DistinctOn { input, .. } => self.extract_variable_from_plan(input),
             LogicalOperator::Sort { input, .. } => self.extract_variable_from_plan(input),
             LogicalOperator::Offset { input, .. } => self.extract_variable_from_plan(input),
             LogicalOperator::Limit { input, .. } => self.extract_variable_from_plan(input),
@@ -509,6 +752,14 @@ impl<'a> LogicalPlanner<'a> {
         return_clause: &ReturnClause,
         input: LogicalOperator,
     ) -> Result<LogicalOperator> {
+        let input = if return_clause.distinct_on.is_empty() {
+            input
+        } else {
+            LogicalOperator::DistinctOn {
+                input: Box::new(input),
+                keys: self.distinct_on_keys(&return_clause.distinct_on)?,
+            }
+        };
         let mut projections: Vec<ProjectionItem> = Vec::new();
 
         for item in &return_clause.items {
@@ -577,6 +828,39 @@ impl<'a> LogicalPlanner<'a> {
         Ok(plan)
     }
 
+    /// Keys of DISTINCT ON, with node variables replaced by their key columns
+    fn distinct_on_keys(&self, keys: &[ValueExpression]) -> Result<Vec<ValueExpression>> {
+        let mut resolved = Vec::new();
+        for key in keys {
+            let node = match key {
+                ValueExpression::Variable(var) => self
+                    .variables
+                    .get(var)
+                    .filter(|label| label.as_str() != "Unwound")
+                    .map(|label| (var, label)),
+                _ => None,
+            };
+            let Some((var, label)) = node else {
+                resolved.push(key.clone());
+                continue;
+            };
+            let mapping =
+                self.config
+                    .get_node_mapping(label)
+                    .ok_or_else(|| GraphError::PlanError {
+                        message: format!("Node label '{}' doesn't exist", label),
+                        location: Location::new(file!(), line!(), column!()),
+                    })?;
+            resolved.extend(mapping.key_columns().into_iter().map(|column| {
+                ValueExpression::Property(PropertyRef {
+                    variable: var.clone(),
+                    property: column.to_string(),
+                })
+            }));
+        }
+        Ok(resolved)
+    }
+
     /// Plan WITH clause - intermediate projection/aggregation with optional ORDER BY and LIMIT
     fn plan_with_clause(
         &self,
@@ -598,6 +882,14 @@ impl<'a> LogicalPlanner<'a> {
             projections,
         };
 
+        // WITH DISTINCT deduplicates the projected rows before ORDER BY and
+        // LIMIT, like RETURN DISTINCT
+        if with_clause.distinct {
+            plan = LogicalOperator::Distinct {
+                input: Box::new(plan),
+            };
+        }
+
         // Apply ORDER BY within WITH if present
         if let Some(order_by) = &with_clause.order_by {
             plan = LogicalOperator::Sort {
@@ -625,6 +917,164 @@ impl<'a> LogicalPlanner<'a> {
     }
 }
 
+/// Move `all(x IN nodes(p) WHERE ...)` conjuncts of a WHERE predicate into the
+/// PathFilter of the variable-length expansion bound to `p`, returning what is
+/// left for the regular Filter (None when nothing remains)
+fn attach_path_predicates(
+    plan: &mut LogicalOperator,
+    predicate: &BooleanExpression,
+) -> Result<Option<BooleanExpression>> {
+    let mut conjuncts = Vec::new();
+    split_conjuncts(predicate, &mut conjuncts);
+
+    let mut remaining: Option<BooleanExpression> = None;
+    for conjunct in conjuncts {
+        if let BooleanExpression::AllInPath {
+            variable,
+            path,
+            elements,
+            predicate,
+        } = conjunct
+        {
+            let filter = path_filter_mut(plan, path).ok_or_else(|| {
+                GraphError::UnsupportedFeature {
+                    feature: format!(
+                        "path predicates on '{}' (only a single variable-length relationship can be bound to a path variable)",
+                        path
+                    ),
+                    location: Location::new(file!(), line!(), column!()),
+                }
+            })?;
+            let entry = (variable.clone(), predicate.as_ref().clone());
+            match elements {
+                PathElements::Nodes => filter.node_predicates.push(entry),
+                PathElements::Relationships => filter.relationship_predicates.push(entry),
+            }
+            continue;
+        }
+        if contains_path_predicate(conjunct) {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "path predicates under OR or NOT".to_string(),
+                location: Location::new(file!(), line!(), column!()),
+            });
+        }
+        remaining = Some(match remaining {
+            Some(acc) => BooleanExpression::And(Box::new(acc), Box::new(conjunct.clone())),
+            None => conjunct.clone(),
+        });
+    }
+    Ok(remaining)
+}
+
+/// Node and relationship variables named in `pattern`
+fn pattern_variables(pattern: &GraphPattern) -> Vec<&str> {
+    match pattern {
+        GraphPattern::Node(node) => node.variable.as_deref().into_iter().collect(),
+        GraphPattern::Path(path) => std::iter::once(path.start_node.variable.as_deref())
+            .chain(path.segments.iter().flat_map(|segment| {
+                [
+                    segment.relationship.variable.as_deref(),
+                    segment.end_node.variable.as_deref(),
+                ]
+            }))
+            .flatten()
+            .collect(),
+    }
+}
+
+/// `conjunct` added to the predicate `acc` with AND
+///
+/// EXISTS subqueries are only supported as (negated) conjuncts of a WHERE
+/// predicate, so a conjunct containing one is rejected.
+fn and_predicate(
+    acc: Option<BooleanExpression>,
+    conjunct: &BooleanExpression,
+) -> Result<BooleanExpression> {
+    if contains_exists_subquery(conjunct) {
+        return Err(GraphError::UnsupportedFeature {
+            feature: "EXISTS subqueries under OR or NOT other than NOT EXISTS { ... }".to_string(),
+            location: Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(match acc {
+        Some(acc) => BooleanExpression::And(Box::new(acc), Box::new(conjunct.clone())),
+        None => conjunct.clone(),
+    })
+}
+
+/// Whether `expr` contains an `EXISTS { ... }` subquery
+pub(crate) fn contains_exists_subquery(expr: &BooleanExpression) -> bool {
+    match expr {
+        BooleanExpression::ExistsSubquery { .. } => true,
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            contains_exists_subquery(left) || contains_exists_subquery(right)
+        }
+        BooleanExpression::Not(inner) => contains_exists_subquery(inner),
+        BooleanExpression::AllInPath { predicate, .. } => contains_exists_subquery(predicate),
+        _ => false,
+    }
+}
+
+pub(crate) fn split_conjuncts<'e>(
+    expr: &'e BooleanExpression,
+    out: &mut Vec<&'e BooleanExpression>,
+) {
+    match expr {
+        BooleanExpression::And(left, right) => {
+            split_conjuncts(left, out);
+            split_conjuncts(right, out);
+        }
+        other => out.push(other),
+    }
+}
+
+fn contains_path_predicate(expr: &BooleanExpression) -> bool {
+    match expr {
+        BooleanExpression::AllInPath { .. } => true,
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            contains_path_predicate(left) || contains_path_predicate(right)
+        }
+        BooleanExpression::Not(inner) => contains_path_predicate(inner),
+        _ => false,
+    }
+}
+
+/// Find the PathFilter of the expansion bound to `path`, without looking past
+/// a projection (path variables do not survive WITH)
+fn path_filter_mut<'p>(plan: &'p mut LogicalOperator, path: &str) -> Option<&'p mut PathFilter> {
+    match plan {
+        LogicalOperator::VariableLengthExpand {
+            input, path_filter, ..
+        } => {
+            if path_filter
+                .path_variable
+                .as_deref()
+                .is_some_and(|v| v.eq_ignore_ascii_case(path))
+            {
+                Some(path_filter)
+            } else {
+                path_filter_mut(input, path)
+            }
+        }
+        LogicalOperator::Filter { input, .. }
+        | LogicalOperator::Sample { input, .. }
+        | LogicalOperator::Expand { input, .. } => path_filter_mut(input, path),
+        LogicalOperator::Unwind {
+            input: Some(input), ..
+        }
+        | LogicalOperator::TableFunction {
+            input: Some(input), ..
+        } => path_filter_mut(input, path),
+        LogicalOperator::Join { left, right, .. } => {
+            if let Some(filter) = path_filter_mut(left, path) {
+                return Some(filter);
+            }
+            path_filter_mut(right, path)
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -841,6 +1291,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_optional_match_logical_plan() {
+        let query_text = "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) \
+                          WHERE b.age > 30 RETURN a.name, b.name";
+
+        let ast = parse_cypher_query(query_text).unwrap();
+        let config = GraphConfig::default();
+        let mut planner = LogicalPlanner::new(&config);
+        let logical_plan = planner.plan(&ast).unwrap();
+
+        // Should be: Project { input: Join(Left) { ScanByLabel, Filter { Expand } } }
+        let LogicalOperator::Project { input, .. } = &logical_plan else {
+            panic!("Expected Project");
+        };
+        let LogicalOperator::Join {
+            left,
+            right,
+            join_type,
+        } = input.as_ref()
+        else {
+            panic!("Expected Join");
+        };
+        assert_eq!(*join_type, JoinType::Left);
+        assert!(
+            matches!(left.as_ref(), LogicalOperator::ScanByLabel { variable, .. } if variable == "a")
+        );
+        let LogicalOperator::Filter { input, .. } = right.as_ref() else {
+            panic!("Expected the optional WHERE on the right side");
+        };
+        assert!(matches!(
+            input.as_ref(),
+            LogicalOperator::Expand { target_variable, .. } if target_variable == "b"
+        ));
+    }
+
+    #[test]
+    fn test_optional_match_requires_preceding_clause() {
+        let ast = parse_cypher_query("OPTIONAL MATCH (a:Person) RETURN a.name").unwrap();
+        let config = GraphConfig::default();
+        let mut planner = LogicalPlanner::new(&config);
+        assert!(planner.plan(&ast).is_err());
+    }
+
+    #[test]
+    fn test_exists_subquery_logical_plan() {
+        let query_text = "MATCH (a:Person) \
+                          WHERE a.age > 30 AND NOT EXISTS { (a)-[:KNOWS]->(b:Person) WHERE b.age < 20 } \
+                          RETURN a.name";
+
+        let ast = parse_cypher_query(query_text).unwrap();
+        let config = GraphConfig::default();
+        let mut planner = LogicalPlanner::new(&config);
+        let logical_plan = planner.plan(&ast).unwrap();
+
+        // Should be: Project { input: Join(Anti) { Filter { ScanByLabel }, Filter { Expand } } }
+        let LogicalOperator::Project { input, .. } = &logical_plan else {
+            panic!("Expected Project");
+        };
+        let LogicalOperator::Join {
+            left,
+            right,
+            join_type,
+        } = input.as_ref()
+        else {
+            panic!("Expected Join");
+        };
+        assert_eq!(*join_type, JoinType::Anti);
+        let LogicalOperator::Filter { input, .. } = left.as_ref() else {
+            panic!("Expected the plain conjunct to filter the outer rows");
+        };
+        assert!(matches!(
+            input.as_ref(),
+            LogicalOperator::ScanByLabel { .. }
+        ));
+        let LogicalOperator::Filter { input, .. } = right.as_ref() else {
+            panic!("Expected the subquery WHERE on the right side");
+        };
+        assert!(matches!(
+            input.as_ref(),
+            LogicalOperator::Expand { target_variable, .. } if target_variable == "b"
+        ));
+        // The subquery's variables are out of scope again
+        assert!(!planner.variables.contains_key("b"));
+    }
+
+    #[test]
+    fn test_exists_subquery_restrictions() {
+        let config = GraphConfig::default();
+        let plan = |query: &str| {
+            let ast = parse_cypher_query(query).unwrap();
+            LogicalPlanner::new(&config).plan(&ast)
+        };
+        assert!(plan(
+            "MATCH (a:Person) WHERE a.age > 30 OR EXISTS { (a)-[:KNOWS]->(:Person) } RETURN a"
+        )
+        .is_err());
+        assert!(
+            plan("MATCH (a:Person) WHERE EXISTS { (b:Person)-[:KNOWS]->(:Person) } RETURN a")
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_where_clause_logical_plan() {
         // Note: Current parser only supports simple comparisons, not AND/OR
@@ -936,6 +1488,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_path_predicates_are_pushed_into_variable_length_expand() {
+        let query_text = "MATCH p = (a:Person)-[:KNOWS*1..3 {active: true}]->(b:Person) \
+                          WHERE all(x IN nodes(p) WHERE x.type <> 'bot') AND b.age > 30 \
+                          RETURN b.name";
+
+        let ast = parse_cypher_query(query_text).unwrap();
+        let config = GraphConfig::default();
+        let mut planner = LogicalPlanner::new(&config);
+        let logical_plan = planner.plan(&ast).unwrap();
+
+        // Project { Filter(b.age > 30) { VariableLengthExpand } }
+        let LogicalOperator::Project { input, .. } = &logical_plan else {
+            panic!("Expected Project");
+        };
+        let LogicalOperator::Filter { input, predicate } = input.as_ref() else {
+            panic!("Expected Filter");
+        };
+        assert!(matches!(
+            predicate,
+            BooleanExpression::Comparison {
+                operator: ComparisonOperator::GreaterThan,
+                ..
+            }
+        ));
+        let LogicalOperator::VariableLengthExpand { path_filter, .. } = input.as_ref() else {
+            panic!("Expected VariableLengthExpand");
+        };
+        assert_eq!(path_filter.path_variable.as_deref(), Some("p"));
+        assert_eq!(
+            path_filter.relationship_properties.get("active"),
+            Some(&PropertyValue::Boolean(true))
+        );
+        assert_eq!(path_filter.node_predicates.len(), 1);
+        assert_eq!(path_filter.node_predicates[0].0, "x");
+        assert!(path_filter.relationship_predicates.is_empty());
+
+        // A path predicate with no variable-length expansion to attach to
+        let ast = parse_cypher_query(
+            "MATCH p = (a:Person)-[:KNOWS]->(b:Person)-[:KNOWS*1..2]->(c:Person) \
+             WHERE all(x IN nodes(p) WHERE x.age > 1) RETURN c.name",
+        )
+        .unwrap();
+        let mut planner = LogicalPlanner::new(&config);
+        assert!(matches!(
+            planner.plan(&ast),
+            Err(GraphError::UnsupportedFeature { .. })
+        ));
+    }
+
     #[test]
     fn test_shared_variable_chained_paths_in_match() {
         let query_text =
@@ -1026,6 +1628,29 @@ mod tests {
             _ => panic!("Expected Distinct at top level"),
         }
 
+        // DISTINCT ON a node deduplicates by its id below the projection
+        let q = "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN DISTINCT ON (b) b.name, a.name";
+        let ast = parse_cypher_query(q).unwrap();
+        let config_with_ids = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("KNOWS", "src", "dst")
+            .build()
+            .unwrap();
+        let mut planner = LogicalPlanner::new(&config_with_ids);
+        match planner.plan(&ast).unwrap() {
+            LogicalOperator::Project { input, .. } => match *input {
+                LogicalOperator::DistinctOn { keys, .. } => assert_eq!(
+                    keys,
+                    vec![ValueExpression::Property(PropertyRef {
+                        variable: "b".to_string(),
+                        property: "person_id".to_string(),
+                    })]
+                ),
+                other => panic!("Expected DistinctOn under Project, got {:?}", other),
+            },
+            other => panic!("Expected Project at top level, got {:?}", other),
+        }
+
         // ORDER BY + LIMIT should be Limit(Sort(Project(..)))
         let q2 = "MATCH (n:Person) RETURN n.name ORDER BY n.name LIMIT 10";
         let ast2 = parse_cypher_query(q2).unwrap();
@@ -1332,6 +1957,15 @@ mod tests {
                 id_field: "id".to_string(),
                 property_fields: vec!["name".to_string(), "age".to_string()],
                 filter_conditions: None,
+                view_of: None,
+                key_fields: Vec::new(),
+                source_table: None,
+                label_column: None,
+                soft_delete_column: None,
+                indexed_properties: Vec::new(),
+                ngram_properties: Vec::new(),
+                vector_properties: Vec::new(),
+                sorted_by: Vec::new(),
             })
             .build()
             .unwrap();
@@ -0,0 +1,403 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Lookup joins against external key-value stores
+//!
+//! Properties that live outside Lance (a feature store, Redis, a service)
+//! can be joined into query results without copying them into a dataset. A
+//! [`LookupSource`] returns the values stored under a batch of keys; a
+//! [`LookupJoin`] attached with [`crate::CypherQuery::with_lookup_join`]
+//! names the result column holding the keys and appends the source's columns
+//! to every result row:
+//!
+//! ```ignore
+//! use lance_graph::lookup::{LookupJoin, LookupSource};
+//!
+//! #[derive(Debug)]
+//! struct Features { client: FeatureStoreClient }
+//!
+//! #[async_trait::async_trait]
+//! impl LookupSource for Features {
+//!     fn schema(&self) -> SchemaRef {
+//!         features_schema() // e.g. `score: Float64`
+//!     }
+//!
+//!     async fn get(&self, keys: ArrayRef) -> Result<RecordBatch> {
+//!         self.client.multi_get(keys).await
+//!     }
+//! }
+//!
+//! let result = CypherQuery::new("MATCH (p:Person) RETURN p.id, p.name")?
+//!     .with_config(config)
+//!     .with_lookup_join(LookupJoin::new("p.id", Arc::new(features)).with_prefix("p."))
+//!     .execute(datasets, None)
+//!     .await?; // columns p.id, p.name, p.score
+//! ```
+//!
+//! The join runs as the last operator of the plan, batch by batch as results
+//! are produced: each batch's distinct non-null keys are fetched in calls of
+//! at most [`LookupSource::max_keys`] keys. It is a left join, so rows whose
+//! key is null or missing from the source get nulls. Looked up values are
+//! part of the result only; `WHERE` and `ORDER BY` cannot refer to them.
+
+use crate::error::{GraphError, Result};
+use arrow::compute::{concat_batches, take};
+use arrow::row::{RowConverter, SortField};
+use arrow_array::{new_null_array, Array, ArrayRef, RecordBatch, UInt32Array};
+use arrow_schema::{Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::execution::TaskContext;
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties, SendableRecordBatchStream,
+};
+use futures::TryStreamExt;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Keys used when a source does not say how many it accepts per call
+pub const DEFAULT_LOOKUP_KEYS: usize = 1024;
+
+/// A store returning the values kept under a batch of keys
+#[async_trait]
+pub trait LookupSource: Send + Sync + fmt::Debug {
+    /// Schema of the values stored under each key
+    fn schema(&self) -> SchemaRef;
+
+    /// Values stored under `keys`, one row per key in the same order, with
+    /// nulls for keys the store does not hold
+    ///
+    /// `keys` are distinct and non-null.
+    async fn get(&self, keys: ArrayRef) -> Result<RecordBatch>;
+
+    /// Most keys passed to one [`Self::get`] call
+    fn max_keys(&self) -> usize {
+        DEFAULT_LOOKUP_KEYS
+    }
+}
+
+/// Values of a [`LookupSource`] appended to the results, keyed by a result
+/// column
+#[derive(Debug, Clone)]
+pub struct LookupJoin {
+    /// Result column holding the keys, e.g. `p.id`
+    pub key_column: String,
+    pub source: Arc<dyn LookupSource>,
+    /// Prepended to the names of the source's columns in the result
+    pub prefix: String,
+}
+
+impl LookupJoin {
+    pub fn new(key_column: impl Into<String>, source: Arc<dyn LookupSource>) -> Self {
+        Self {
+            key_column: key_column.into(),
+            source,
+            prefix: String::new(),
+        }
+    }
+
+    /// Name the appended columns `prefix` followed by the source's column
+    /// names, e.g. `p.` for `p.score`
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+/// `input` followed by the lookup joins `joins`, or `input` itself without
+/// any
+pub(crate) fn with_lookup_joins(
+    input: Arc<dyn ExecutionPlan>,
+    joins: &[LookupJoin],
+) -> Result<Arc<dyn ExecutionPlan>> {
+    if joins.is_empty() {
+        return Ok(input);
+    }
+    Ok(Arc::new(LookupJoinExec::try_new(input, joins.to_vec())?))
+}
+
+/// Appends the values of lookup sources to every batch of its input
+#[derive(Debug)]
+struct LookupJoinExec {
+    input: Arc<dyn ExecutionPlan>,
+    joins: Vec<LookupJoin>,
+    /// Index of the key column of each join in the input
+    key_indices: Vec<usize>,
+    properties: PlanProperties,
+}
+
+impl LookupJoinExec {
+    fn try_new(input: Arc<dyn ExecutionPlan>, joins: Vec<LookupJoin>) -> Result<Self> {
+        let (schema, key_indices) = joined_schema(&input.schema(), &joins)?;
+        let input_properties = input.properties();
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema),
+            input_properties.partitioning.clone(),
+            input_properties.emission_type,
+            input_properties.boundedness,
+        );
+        Ok(Self {
+            input,
+            joins,
+            key_indices,
+            properties,
+        })
+    }
+}
+
+/// `batch` with the columns of `joins` appended, for results not produced
+/// by a plan (e.g. nested subqueries)
+pub(crate) async fn join_batch(batch: RecordBatch, joins: &[LookupJoin]) -> Result<RecordBatch> {
+    if joins.is_empty() {
+        return Ok(batch);
+    }
+    let (schema, key_indices) = joined_schema(&batch.schema(), joins)?;
+    let joins: Vec<(usize, LookupJoin)> =
+        key_indices.into_iter().zip(joins.iter().cloned()).collect();
+    Ok(append(batch, &joins, schema).await?)
+}
+
+/// Schema of `input` followed by the columns of `joins`, and the index of
+/// the key column of each join in `input`
+fn joined_schema(input: &SchemaRef, joins: &[LookupJoin]) -> Result<(SchemaRef, Vec<usize>)> {
+    let mut fields: Vec<Arc<Field>> = input.fields().iter().cloned().collect();
+    let mut key_indices = Vec::with_capacity(joins.len());
+    for join in joins {
+        let key_index = input
+            .index_of(&join.key_column)
+            .ok()
+            .or_else(|| {
+                input
+                    .fields()
+                    .iter()
+                    .position(|field| field.name().eq_ignore_ascii_case(&join.key_column))
+            })
+            .ok_or_else(|| GraphError::PlanError {
+                message: format!(
+                    "Lookup key column {} is not a result column",
+                    join.key_column
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        key_indices.push(key_index);
+        for field in join.source.schema().fields() {
+            let name = format!("{}{}", join.prefix, field.name());
+            if fields.iter().any(|f| f.name().eq_ignore_ascii_case(&name)) {
+                return Err(GraphError::PlanError {
+                    message: format!("Lookup column {} is already a result column", name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            // Missing keys give nulls
+            fields.push(Arc::new(
+                field.as_ref().clone().with_name(name).with_nullable(true),
+            ));
+        }
+    }
+    let schema = Schema::new_with_metadata(fields, input.metadata().clone());
+    Ok((Arc::new(schema), key_indices))
+}
+
+/// `batch` with the looked up columns of `joins`, each paired with the index
+/// of its key column
+async fn append(
+    batch: RecordBatch,
+    joins: &[(usize, LookupJoin)],
+    schema: SchemaRef,
+) -> DFResult<RecordBatch> {
+    let mut columns = batch.columns().to_vec();
+    for (key_index, join) in joins {
+        columns.extend(looked_up(batch.column(*key_index), join).await?);
+    }
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+impl DisplayAs for LookupJoinExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        let keys: Vec<&str> = self.joins.iter().map(|j| j.key_column.as_str()).collect();
+        write!(f, "LookupJoinExec: keys=[{}]", keys.join(", "))
+    }
+}
+
+impl ExecutionPlan for LookupJoinExec {
+    fn name(&self) -> &str {
+        "LookupJoinExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let plan = Self::try_new(children.swap_remove(0), self.joins.clone())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(Arc::new(plan))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let schema = self.schema();
+        let joins: Arc<[(usize, LookupJoin)]> = self
+            .key_indices
+            .iter()
+            .copied()
+            .zip(self.joins.iter().cloned())
+            .collect();
+        let output_schema = schema.clone();
+        let batches = self
+            .input
+            .execute(partition, context)?
+            .and_then(move |batch| {
+                let joins = joins.clone();
+                let schema = output_schema.clone();
+                async move { append(batch, &joins, schema).await }
+            });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, batches)))
+    }
+}
+
+/// The columns of `join`'s source for every key of `keys`
+async fn looked_up(keys: &ArrayRef, join: &LookupJoin) -> DFResult<Vec<ArrayRef>> {
+    let schema = join.source.schema();
+
+    // Position of each row's key among the distinct keys, null for null keys
+    let converter = RowConverter::new(vec![SortField::new(keys.data_type().clone())])?;
+    let rows = converter.convert_columns(&[keys.clone()])?;
+    let mut distinct = HashMap::new();
+    let mut first_rows: Vec<u32> = Vec::new();
+    let positions: UInt32Array = (0..keys.len())
+        .map(|i| {
+            if keys.is_null(i) {
+                return None;
+            }
+            let next = first_rows.len() as u32;
+            Some(*distinct.entry(rows.row(i)).or_insert_with(|| {
+                first_rows.push(i as u32);
+                next
+            }))
+        })
+        .collect();
+    if first_rows.is_empty() {
+        return Ok(schema
+            .fields()
+            .iter()
+            .map(|field| new_null_array(field.data_type(), keys.len()))
+            .collect());
+    }
+
+    let mut fetched = Vec::new();
+    for chunk in first_rows.chunks(join.source.max_keys().max(1)) {
+        let chunk_keys = take(keys.as_ref(), &UInt32Array::from(chunk.to_vec()), None)?;
+        let values = join
+            .source
+            .get(chunk_keys)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        if values.num_rows() != chunk.len() {
+            return Err(DataFusionError::Execution(format!(
+                "Lookup source {:?} returned {} rows for {} keys",
+                join.source,
+                values.num_rows(),
+                chunk.len()
+            )));
+        }
+        fetched.push(values);
+    }
+    let values = concat_batches(&schema, &fetched)?;
+    Ok(values
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &positions, None))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Float64Array, Int64Array, StringArray};
+    use arrow_schema::DataType;
+    use std::sync::Mutex;
+
+    /// Scores for even keys, recording the keys of every call
+    #[derive(Debug, Default)]
+    struct EvenScores {
+        calls: Mutex<Vec<Vec<i64>>>,
+    }
+
+    #[async_trait]
+    impl LookupSource for EvenScores {
+        fn schema(&self) -> SchemaRef {
+            Arc::new(Schema::new(vec![Field::new(
+                "score",
+                DataType::Float64,
+                true,
+            )]))
+        }
+
+        async fn get(&self, keys: ArrayRef) -> Result<RecordBatch> {
+            let keys = keys.as_any().downcast_ref::<Int64Array>().unwrap();
+            self.calls.lock().unwrap().push(keys.values().to_vec());
+            let scores: Float64Array = keys
+                .values()
+                .iter()
+                .map(|k| (k % 2 == 0).then(|| *k as f64 / 10.0))
+                .collect();
+            Ok(RecordBatch::try_new(self.schema(), vec![Arc::new(scores)])?)
+        }
+
+        fn max_keys(&self) -> usize {
+            2
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookups_fetch_distinct_keys_in_chunks() {
+        let source = Arc::new(EvenScores::default());
+        let join = LookupJoin::new("p.id", source.clone());
+        let keys: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(2),
+            Some(3),
+            None,
+            Some(2),
+            Some(4),
+            Some(6),
+        ]));
+
+        let columns = looked_up(&keys, &join).await.unwrap();
+        let scores = columns[0].as_any().downcast_ref::<Float64Array>().unwrap();
+        let scores: Vec<Option<f64>> = scores.iter().collect();
+        assert_eq!(
+            scores,
+            vec![Some(0.2), None, None, Some(0.2), Some(0.4), Some(0.6)]
+        );
+        assert_eq!(*source.calls.lock().unwrap(), vec![vec![2, 3], vec![4, 6]]);
+
+        // Only null keys call nothing
+        let keys: ArrayRef = Arc::new(StringArray::from(vec![None::<&str>]));
+        let columns = looked_up(&keys, &join).await.unwrap();
+        assert_eq!(columns[0].null_count(), 1);
+        assert_eq!(source.calls.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,659 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Result values for drivers
+//!
+//! Query results are Arrow record batches, but the protocols drivers speak
+//! have smaller type systems: JSON has no dates and rounds integers beyond
+//! 2^53, Bolt has no unsigned integers, decimals or vectors. [`OutputCoercion`]
+//! converts result rows to [`OutputValue`]s, a value model both protocols map
+//! onto directly, so protocol servers share one conversion and every lossy
+//! choice is made by the policy rather than by accident:
+//!
+//! | Arrow                                    | [`OutputValue`]                                   |
+//! |------------------------------------------|---------------------------------------------------|
+//! | integers                                 | `Integer`; beyond the largest integer, per [`Unrepresentable`] |
+//! | decimals                                 | per [`Unrepresentable`]                           |
+//! | floats                                   | `Float`; `Float32` by its shortest decimal form   |
+//! | strings, binary                          | `String`, `Bytes`                                 |
+//! | dates, times, timestamps, durations, intervals | temporal values, or ISO 8601 strings with [`TemporalOutput::IsoString`] |
+//! | lists, vectors (fixed-size lists)        | `List`                                            |
+//! | structs, maps                            | `Map`                                             |
+//!
+//! [`OutputCoercion::json`] and [`OutputCoercion::bolt`] are the policies of
+//! the two protocols:
+//!
+//! ```ignore
+//! use lance_graph::output::OutputCoercion;
+//!
+//! let result = query.execute(datasets, None).await?;
+//! let rows = OutputCoercion::json().json_rows(&result)?;
+//! ```
+
+use crate::error::{GraphError, Result};
+use arrow::array::timezone::Tz;
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::*;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, FixedOffset, NaiveTime, Offset, TimeZone};
+
+/// Largest integer a JSON number holds exactly in JavaScript, 2^53 - 1
+pub const MAX_SAFE_JSON_INTEGER: u64 = (1 << 53) - 1;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A result value in the type system shared by the driver protocols
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    List(Vec<OutputValue>),
+    /// Entries in column or key order
+    Map(Vec<(String, OutputValue)>),
+    /// Days since the Unix epoch
+    Date(i64),
+    /// Nanoseconds since midnight
+    LocalTime(i64),
+    /// Wall-clock date and time without a time zone
+    LocalDateTime {
+        seconds: i64,
+        nanoseconds: u32,
+    },
+    /// An instant, with the UTC offset it is shown in
+    DateTime {
+        seconds: i64,
+        nanoseconds: u32,
+        offset_seconds: i32,
+    },
+    Duration {
+        months: i64,
+        days: i64,
+        seconds: i64,
+        nanoseconds: i32,
+    },
+}
+
+impl OutputValue {
+    /// The value as JSON
+    ///
+    /// Temporal values become ISO 8601 strings, bytes arrays of numbers, and
+    /// non-finite floats the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+        match self {
+            Self::Null => Value::Null,
+            Self::Boolean(b) => Value::Bool(*b),
+            Self::Integer(i) => Value::from(*i),
+            Self::Float(f) => match serde_json::Number::from_f64(*f) {
+                Some(number) => Value::Number(number),
+                None if f.is_nan() => Value::from("NaN"),
+                None if *f > 0.0 => Value::from("Infinity"),
+                None => Value::from("-Infinity"),
+            },
+            Self::String(s) => Value::from(s.as_str()),
+            Self::Bytes(bytes) => Value::from(bytes.clone()),
+            Self::List(items) => Value::Array(items.iter().map(Self::to_json).collect()),
+            Self::Map(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            temporal => Value::from(temporal.iso_string().unwrap_or_default()),
+        }
+    }
+
+    /// The ISO 8601 form of a temporal value
+    pub fn iso_string(&self) -> Option<String> {
+        match *self {
+            Self::Date(days) => {
+                let date = DateTime::from_timestamp(days.checked_mul(SECONDS_PER_DAY)?, 0)?;
+                Some(date.date_naive().to_string())
+            }
+            Self::LocalTime(nanos) => {
+                let seconds = u32::try_from(nanos.div_euclid(NANOS_PER_SECOND as i64)).ok()?;
+                let nanos = nanos.rem_euclid(NANOS_PER_SECOND as i64) as u32;
+                let time = NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)?;
+                Some(time.format("%H:%M:%S%.f").to_string())
+            }
+            Self::LocalDateTime {
+                seconds,
+                nanoseconds,
+            } => {
+                let datetime = DateTime::from_timestamp(seconds, nanoseconds)?.naive_utc();
+                Some(datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            }
+            Self::DateTime {
+                seconds,
+                nanoseconds,
+                offset_seconds,
+            } => {
+                let offset = FixedOffset::east_opt(offset_seconds)?;
+                let datetime = offset.timestamp_opt(seconds, nanoseconds).single()?;
+                Some(datetime.to_rfc3339())
+            }
+            Self::Duration {
+                months,
+                days,
+                seconds,
+                nanoseconds,
+            } => Some(iso_duration(months, days, seconds, nanoseconds)),
+            _ => None,
+        }
+    }
+}
+
+/// `P1Y2M3DT4H5M6.5S`, leaving out zero components
+fn iso_duration(months: i64, days: i64, seconds: i64, nanoseconds: i32) -> String {
+    let mut iso = String::from("P");
+    if months / 12 != 0 {
+        iso.push_str(&format!("{}Y", months / 12));
+    }
+    if months % 12 != 0 {
+        iso.push_str(&format!("{}M", months % 12));
+    }
+    if days != 0 {
+        iso.push_str(&format!("{}D", days));
+    }
+    let nanos = seconds as i128 * NANOS_PER_SECOND + nanoseconds as i128;
+    if nanos == 0 && iso.len() > 1 {
+        return iso;
+    }
+    iso.push('T');
+    let hours = nanos / (3_600 * NANOS_PER_SECOND);
+    let minutes = nanos / (60 * NANOS_PER_SECOND) % 60;
+    let rest = nanos % (60 * NANOS_PER_SECOND);
+    if hours != 0 {
+        iso.push_str(&format!("{}H", hours));
+    }
+    if minutes != 0 {
+        iso.push_str(&format!("{}M", minutes));
+    }
+    if rest != 0 || (hours == 0 && minutes == 0) {
+        let sign = if rest < 0 { "-" } else { "" };
+        let (whole, fraction) = (rest.abs() / NANOS_PER_SECOND, rest.abs() % NANOS_PER_SECOND);
+        let fraction = format!("{:09}", fraction);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            iso.push_str(&format!("{}{}S", sign, whole));
+        } else {
+            iso.push_str(&format!("{}{}.{}S", sign, whole, fraction));
+        }
+    }
+    iso
+}
+
+/// How temporal values are output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TemporalOutput {
+    /// As the temporal [`OutputValue`]s
+    #[default]
+    Native,
+    /// As ISO 8601 strings
+    IsoString,
+}
+
+/// How numbers without an exact protocol type are output: integers beyond
+/// the policy's largest integer, and decimals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Unrepresentable {
+    /// As their decimal string, exactly
+    #[default]
+    String,
+    /// As the nearest float, losing precision
+    Float,
+    /// Fail the conversion
+    Error,
+}
+
+/// Policy converting result values for a driver protocol
+///
+/// The default is lossless: temporal values stay temporal, integers up to
+/// `i64::MAX` stay integers, and other numbers become strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutputCoercion {
+    temporal: TemporalOutput,
+    max_integer: u64,
+    unrepresentable: Unrepresentable,
+}
+
+impl Default for OutputCoercion {
+    fn default() -> Self {
+        Self {
+            temporal: TemporalOutput::Native,
+            max_integer: i64::MAX as u64,
+            unrepresentable: Unrepresentable::String,
+        }
+    }
+}
+
+impl OutputCoercion {
+    /// The lossless default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy for JSON: ISO 8601 strings for temporal values, and strings
+    /// for integers JavaScript cannot hold exactly
+    pub fn json() -> Self {
+        Self::new()
+            .with_temporal(TemporalOutput::IsoString)
+            .with_max_integer(MAX_SAFE_JSON_INTEGER)
+    }
+
+    /// Policy for Bolt, whose integers are 64-bit and which has temporal
+    /// types of its own
+    pub fn bolt() -> Self {
+        Self::new()
+    }
+
+    /// Output temporal values as `temporal`
+    pub fn with_temporal(mut self, temporal: TemporalOutput) -> Self {
+        self.temporal = temporal;
+        self
+    }
+
+    /// Output integers of magnitude up to `max_integer` (at most `i64::MAX`)
+    /// as integers
+    pub fn with_max_integer(mut self, max_integer: u64) -> Self {
+        self.max_integer = max_integer.min(i64::MAX as u64);
+        self
+    }
+
+    /// Output numbers without an exact protocol type as `unrepresentable`
+    pub fn with_unrepresentable(mut self, unrepresentable: Unrepresentable) -> Self {
+        self.unrepresentable = unrepresentable;
+        self
+    }
+
+    pub fn temporal(&self) -> TemporalOutput {
+        self.temporal
+    }
+
+    pub fn max_integer(&self) -> u64 {
+        self.max_integer
+    }
+
+    pub fn unrepresentable(&self) -> Unrepresentable {
+        self.unrepresentable
+    }
+
+    /// The rows of `batch`, one value per column
+    pub fn rows(&self, batch: &RecordBatch) -> Result<Vec<Vec<OutputValue>>> {
+        (0..batch.num_rows())
+            .map(|row| {
+                batch
+                    .columns()
+                    .iter()
+                    .map(|column| self.value(column.as_ref(), row))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The rows of `batch` as JSON objects keyed by column name
+    pub fn json_rows(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+        let schema = batch.schema();
+        Ok(self
+            .rows(batch)?
+            .into_iter()
+            .map(|row| {
+                schema
+                    .fields()
+                    .iter()
+                    .zip(row)
+                    .map(|(field, value)| (field.name().clone(), value.to_json()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// The value of `array` at `row`
+    pub fn value(&self, array: &dyn Array, row: usize) -> Result<OutputValue> {
+        use DataType as DT;
+        if array.is_null(row) {
+            return Ok(OutputValue::Null);
+        }
+        Ok(match array.data_type() {
+            DT::Null => OutputValue::Null,
+            DT::Boolean => OutputValue::Boolean(array.as_boolean().value(row)),
+            DT::Int8 => self.integer(array.as_primitive::<Int8Type>().value(row).into())?,
+            DT::Int16 => self.integer(array.as_primitive::<Int16Type>().value(row).into())?,
+            DT::Int32 => self.integer(array.as_primitive::<Int32Type>().value(row).into())?,
+            DT::Int64 => self.integer(array.as_primitive::<Int64Type>().value(row).into())?,
+            DT::UInt8 => self.integer(array.as_primitive::<UInt8Type>().value(row).into())?,
+            DT::UInt16 => self.integer(array.as_primitive::<UInt16Type>().value(row).into())?,
+            DT::UInt32 => self.integer(array.as_primitive::<UInt32Type>().value(row).into())?,
+            DT::UInt64 => self.integer(array.as_primitive::<UInt64Type>().value(row).into())?,
+            DT::Float16 => {
+                OutputValue::Float(array.as_primitive::<Float16Type>().value(row).to_f64())
+            }
+            DT::Float32 => {
+                // The shortest decimal form, so 0.1f32 is 0.1 rather than
+                // 0.10000000149011612
+                let value = array.as_primitive::<Float32Type>().value(row);
+                OutputValue::Float(value.to_string().parse().unwrap_or(value as f64))
+            }
+            DT::Float64 => OutputValue::Float(array.as_primitive::<Float64Type>().value(row)),
+            DT::Decimal128(_, _) | DT::Decimal256(_, _) => self.number(formatted(array, row)?)?,
+            DT::Utf8 => OutputValue::String(array.as_string::<i32>().value(row).to_string()),
+            DT::LargeUtf8 => OutputValue::String(array.as_string::<i64>().value(row).to_string()),
+            DT::Utf8View => OutputValue::String(array.as_string_view().value(row).to_string()),
+            DT::Binary => OutputValue::Bytes(array.as_binary::<i32>().value(row).to_vec()),
+            DT::LargeBinary => OutputValue::Bytes(array.as_binary::<i64>().value(row).to_vec()),
+            DT::BinaryView => OutputValue::Bytes(array.as_binary_view().value(row).to_vec()),
+            DT::FixedSizeBinary(_) => {
+                OutputValue::Bytes(array.as_fixed_size_binary().value(row).to_vec())
+            }
+            DT::Date32 => self.temporal_value(OutputValue::Date(
+                array.as_primitive::<Date32Type>().value(row).into(),
+            )),
+            DT::Date64 => {
+                let millis = array.as_primitive::<Date64Type>().value(row);
+                self.temporal_value(OutputValue::Date(
+                    millis.div_euclid(SECONDS_PER_DAY * 1_000),
+                ))
+            }
+            DT::Time32(_) | DT::Time64(_) => {
+                let nanos = i64::try_from(time_nanos(array, row)).map_err(|_| {
+                    output_error(format!(
+                        "Time out of range: {}",
+                        formatted_lossy(array, row)
+                    ))
+                })?;
+                self.temporal_value(OutputValue::LocalTime(nanos))
+            }
+            DT::Timestamp(_, timezone) => {
+                let nanos = time_nanos(array, row);
+                let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).map_err(|_| {
+                    output_error(format!(
+                        "Timestamp out of range: {}",
+                        formatted_lossy(array, row)
+                    ))
+                })?;
+                let nanoseconds = nanos.rem_euclid(NANOS_PER_SECOND) as u32;
+                self.temporal_value(match timezone {
+                    None => OutputValue::LocalDateTime {
+                        seconds,
+                        nanoseconds,
+                    },
+                    Some(timezone) => OutputValue::DateTime {
+                        seconds,
+                        nanoseconds,
+                        offset_seconds: utc_offset(timezone, seconds)?,
+                    },
+                })
+            }
+            DT::Duration(_) => {
+                let nanos = time_nanos(array, row);
+                self.temporal_value(OutputValue::Duration {
+                    months: 0,
+                    days: 0,
+                    seconds: (nanos / NANOS_PER_SECOND) as i64,
+                    nanoseconds: (nanos % NANOS_PER_SECOND) as i32,
+                })
+            }
+            DT::Interval(IntervalUnit::YearMonth) => self.temporal_value(OutputValue::Duration {
+                months: array
+                    .as_primitive::<IntervalYearMonthType>()
+                    .value(row)
+                    .into(),
+                days: 0,
+                seconds: 0,
+                nanoseconds: 0,
+            }),
+            DT::Interval(IntervalUnit::DayTime) => {
+                let interval = array.as_primitive::<IntervalDayTimeType>().value(row);
+                self.temporal_value(OutputValue::Duration {
+                    months: 0,
+                    days: interval.days.into(),
+                    seconds: (interval.milliseconds / 1_000).into(),
+                    nanoseconds: interval.milliseconds % 1_000 * 1_000_000,
+                })
+            }
+            DT::Interval(IntervalUnit::MonthDayNano) => {
+                let interval = array.as_primitive::<IntervalMonthDayNanoType>().value(row);
+                self.temporal_value(OutputValue::Duration {
+                    months: interval.months.into(),
+                    days: interval.days.into(),
+                    seconds: interval.nanoseconds / NANOS_PER_SECOND as i64,
+                    nanoseconds: (interval.nanoseconds % NANOS_PER_SECOND as i64) as i32,
+                })
+            }
+            DT::List(_) => self.list(array.as_list::<i32>().value(row).as_ref())?,
+            DT::LargeList(_) => self.list(array.as_list::<i64>().value(row).as_ref())?,
+            DT::FixedSizeList(_, _) => self.list(array.as_fixed_size_list().value(row).as_ref())?,
+            DT::Struct(fields) => {
+                let columns = array.as_struct().columns();
+                OutputValue::Map(
+                    fields
+                        .iter()
+                        .zip(columns)
+                        .map(|(field, column)| {
+                            Ok((field.name().clone(), self.value(column.as_ref(), row)?))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            DT::Map(_, _) => {
+                let entries = array.as_map().value(row);
+                let (keys, values) = (entries.column(0), entries.column(1));
+                OutputValue::Map(
+                    (0..entries.len())
+                        .map(|entry| {
+                            let key = match self.value(keys.as_ref(), entry)? {
+                                OutputValue::String(key) => key,
+                                key => key.to_json().to_string(),
+                            };
+                            Ok((key, self.value(values.as_ref(), entry)?))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            DT::Dictionary(_, value_type) => {
+                let value = arrow::compute::cast(&array.slice(row, 1), value_type)?;
+                self.value(value.as_ref(), 0)?
+            }
+            other => {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: format!("output of {} values", other),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        })
+    }
+
+    fn integer(&self, value: i128) -> Result<OutputValue> {
+        if value.unsigned_abs() <= self.max_integer as u128 {
+            return Ok(OutputValue::Integer(value as i64));
+        }
+        self.number(value.to_string())
+    }
+
+    /// A number without an exact protocol type, given as its decimal string
+    fn number(&self, decimal: String) -> Result<OutputValue> {
+        match self.unrepresentable {
+            Unrepresentable::String => Ok(OutputValue::String(decimal)),
+            Unrepresentable::Float => decimal
+                .parse()
+                .map(OutputValue::Float)
+                .map_err(|_| output_error(format!("{} is not a number", decimal))),
+            Unrepresentable::Error => Err(output_error(format!(
+                "{} has no exact representation in the output protocol",
+                decimal
+            ))),
+        }
+    }
+
+    fn temporal_value(&self, value: OutputValue) -> OutputValue {
+        match self.temporal {
+            TemporalOutput::Native => value,
+            TemporalOutput::IsoString => match value.iso_string() {
+                Some(iso) => OutputValue::String(iso),
+                None => value,
+            },
+        }
+    }
+
+    fn list(&self, items: &dyn Array) -> Result<OutputValue> {
+        Ok(OutputValue::List(
+            (0..items.len())
+                .map(|item| self.value(items, item))
+                .collect::<Result<_>>()?,
+        ))
+    }
+}
+
+/// Nanoseconds of a time, timestamp or duration value
+fn time_nanos(array: &dyn Array, row: usize) -> i128 {
+    use DataType as DT;
+    let (ticks, unit): (i128, &TimeUnit) = match array.data_type() {
+        DT::Time32(unit @ TimeUnit::Second) => (
+            array.as_primitive::<Time32SecondType>().value(row).into(),
+            unit,
+        ),
+        DT::Time32(unit) => (
+            array
+                .as_primitive::<Time32MillisecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Time64(unit @ TimeUnit::Microsecond) => (
+            array
+                .as_primitive::<Time64MicrosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Time64(unit) => (
+            array
+                .as_primitive::<Time64NanosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Timestamp(unit @ TimeUnit::Second, _) => (
+            array
+                .as_primitive::<TimestampSecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Timestamp(unit @ TimeUnit::Millisecond, _) => (
+            array
+                .as_primitive::<TimestampMillisecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Timestamp(unit @ TimeUnit::Microsecond, _) => (
+            array
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Timestamp(unit, _) => (
+            array
+                .as_primitive::<TimestampNanosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Duration(unit @ TimeUnit::Second) => (
+            array.as_primitive::<DurationSecondType>().value(row).into(),
+            unit,
+        ),
+        DT::Duration(unit @ TimeUnit::Millisecond) => (
+            array
+                .as_primitive::<DurationMillisecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Duration(unit @ TimeUnit::Microsecond) => (
+            array
+                .as_primitive::<DurationMicrosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        DT::Duration(unit) => (
+            array
+                .as_primitive::<DurationNanosecondType>()
+                .value(row)
+                .into(),
+            unit,
+        ),
+        _ => return 0,
+    };
+    let nanos_per_tick: i128 = match unit {
+        TimeUnit::Second => NANOS_PER_SECOND,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    };
+    ticks * nanos_per_tick
+}
+
+/// Offset from UTC of `timezone` at `seconds` since the epoch
+fn utc_offset(timezone: &str, seconds: i64) -> Result<i32> {
+    let tz: Tz = timezone
+        .parse()
+        .map_err(|_| output_error(format!("Unsupported time zone {}", timezone)))?;
+    let instant = DateTime::from_timestamp(seconds, 0)
+        .ok_or_else(|| output_error(format!("Timestamp out of range: {}s", seconds)))?;
+    Ok(tz
+        .offset_from_utc_datetime(&instant.naive_utc())
+        .fix()
+        .local_minus_utc())
+}
+
+/// The display form of the value of `array` at `row`
+fn formatted(array: &dyn Array, row: usize) -> Result<String> {
+    Ok(ArrayFormatter::try_new(array, &FormatOptions::default())?
+        .value(row)
+        .to_string())
+}
+
+fn formatted_lossy(array: &dyn Array, row: usize) -> String {
+    formatted(array, row).unwrap_or_else(|_| "?".to_string())
+}
+
+fn output_error(message: String) -> GraphError {
+    GraphError::ExecutionError {
+        message,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_durations() {
+        let cases = [
+            (
+                (14, 3, 4 * 3_600 + 5 * 60 + 6, 500_000_000),
+                "P1Y2M3DT4H5M6.5S",
+            ),
+            ((0, 0, 0, 0), "PT0S"),
+            ((0, 7, 0, 0), "P7D"),
+            ((0, 0, 90, 0), "PT1M30S"),
+            ((0, 0, 0, -250_000_000), "PT-0.25S"),
+        ];
+        for ((months, days, seconds, nanoseconds), expected) in cases {
+            assert_eq!(iso_duration(months, days, seconds, nanoseconds), expected);
+        }
+    }
+}
@@ -12,16 +12,22 @@ use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while1},
     character::complete::{char, digit0, digit1, multispace0, multispace1, one_of},
-    combinator::{map, map_res, opt, peek, recognize},
-    multi::{many0, separated_list0, separated_list1},
-    sequence::{delimited, pair, preceded, tuple},
+    combinator::{map, map_res, not, opt, peek, recognize},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Parse a complete Cypher query
+///
+/// `//` line comments and `/* */` block comments may appear wherever
+/// whitespace may.
 pub fn parse_cypher_query(input: &str) -> Result<CypherQuery> {
-    let (remaining, query) = cypher_query(input).map_err(|e| GraphError::ParseError {
+    let stripped = strip_comments(input)?;
+    let input = stripped.as_ref();
+    let (remaining, mut query) = cypher_query(input).map_err(|e| GraphError::ParseError {
         message: format!("Failed to parse Cypher query: {}", e),
         position: 0,
         location: snafu::Location::new(file!(), line!(), column!()),
@@ -35,17 +41,490 @@ pub fn parse_cypher_query(input: &str) -> Result<CypherQuery> {
         });
     }
 
+    crate::temporal::resolve_accessors(&mut query);
     Ok(query)
 }
 
+/// Parse a standalone boolean expression such as a view label filter
+/// (e.g. `active = true AND age >= 18`)
+pub fn parse_filter_expression(input: &str) -> Result<BooleanExpression> {
+    let (remaining, expr) =
+        preceded(multispace0, boolean_expression)(input).map_err(|e| GraphError::ParseError {
+            message: format!("Failed to parse filter expression: {}", e),
+            position: 0,
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+
+    if !remaining.trim().is_empty() {
+        return Err(GraphError::ParseError {
+            message: format!("Unexpected input after filter expression: {}", remaining),
+            position: input.len() - remaining.len(),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+
+    Ok(expr)
+}
+
+/// `input` with its `//` and `/* */` comments blanked out
+///
+/// Comment characters become spaces and newlines are kept, so positions in
+/// the result are positions in `input`. Comment markers inside string
+/// literals are left alone.
+pub fn strip_comments(input: &str) -> Result<Cow<'_, str>> {
+    blank_comments(input).map_err(|start| GraphError::ParseError {
+        message: "Unterminated block comment".to_string(),
+        position: start,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Split a script into its statements, separated by semicolons
+///
+/// Returns the byte offset and text of every statement, comments included;
+/// statements holding nothing but whitespace and comments are skipped.
+/// Semicolons inside string literals and comments do not separate
+/// statements.
+pub fn split_statements(input: &str) -> Result<Vec<(usize, &str)>> {
+    let stripped = strip_comments(input)?;
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut quote: Option<u8> = None;
+    for (i, b) in stripped.bytes().enumerate() {
+        match (quote, b) {
+            (Some(q), _) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'\'' | b'"') => quote = Some(b),
+            (None, b';') => {
+                if !stripped[start..i].trim().is_empty() {
+                    statements.push((start, &input[start..i]));
+                }
+                start = i + 1;
+            }
+            (None, _) => {}
+        }
+    }
+    if !stripped[start..].trim().is_empty() {
+        statements.push((start, &input[start..]));
+    }
+    Ok(statements)
+}
+
+/// `input` with its comments replaced by spaces, or the byte offset of an
+/// unterminated block comment
+fn blank_comments(input: &str) -> std::result::Result<Cow<'_, str>, usize> {
+    if !input.contains("//") && !input.contains("/*") {
+        return Ok(Cow::Borrowed(input));
+    }
+    let bytes = input.as_bytes();
+    let mut blanked = bytes.to_vec();
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if let Some(q) = quote {
+            if b == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        let end = match (b, bytes.get(i + 1)) {
+            (b'\'' | b'"', _) => {
+                quote = Some(b);
+                i += 1;
+                continue;
+            }
+            (b'/', Some(b'/')) => input[i..].find('\n').map_or(input.len(), |n| i + n),
+            (b'/', Some(b'*')) => match input[i + 2..].find("*/") {
+                Some(n) => i + 2 + n + 2,
+                None => return Err(i),
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        // Whole characters are blanked, so the result stays valid UTF-8
+        for byte in &mut blanked[i..end] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+        i = end;
+    }
+    Ok(Cow::Owned(
+        String::from_utf8(blanked).expect("comments are blanked as whole characters"),
+    ))
+}
+
+/// A syntax error located in the query text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    /// Human-readable description of the error
+    pub message: String,
+    /// Byte range of the offending text in the query
+    pub span: std::ops::Range<usize>,
+    /// 1-based line of the start of `span`
+    pub line: usize,
+    /// 1-based column (in characters) of the start of `span`
+    pub column: usize,
+}
+
+impl ParseDiagnostic {
+    fn new(input: &str, message: impl Into<String>, span: std::ops::Range<usize>) -> Self {
+        let prefix = &input[..span.start];
+        let line = prefix.matches('\n').count() + 1;
+        let column = prefix
+            .rsplit('\n')
+            .next()
+            .map(|l| l.chars().count())
+            .unwrap_or(0)
+            + 1;
+        Self {
+            message: message.into(),
+            span,
+            line,
+            column,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Parse a Cypher query, reporting every syntax error found instead of only the first
+///
+/// On failure the query is split into top-level clauses (MATCH, UNWIND, WHERE,
+/// WITH, RETURN, ORDER BY, SKIP, LIMIT and the writing clauses CREATE, MERGE,
+/// SET, REMOVE and [DETACH] DELETE) which are checked independently, so an
+/// error in one clause does not hide errors in the following ones. Unbalanced
+/// brackets and unterminated strings are reported as well.
+pub fn parse_cypher_query_with_diagnostics(
+    input: &str,
+) -> std::result::Result<CypherQuery, Vec<ParseDiagnostic>> {
+    let first_error = match parse_cypher_query(input) {
+        Ok(query) => return Ok(query),
+        Err(e) => e,
+    };
+
+    // Comments are blanked out, which keeps every position in `input`
+    let stripped = match blank_comments(input) {
+        Ok(stripped) => stripped,
+        Err(start) => {
+            return Err(vec![ParseDiagnostic::new(
+                input,
+                "Unterminated block comment",
+                start..input.len(),
+            )])
+        }
+    };
+    let input = stripped.as_ref();
+
+    let mut diagnostics = lexical_diagnostics(input);
+    let segments = clause_segments(input);
+
+    match segments.first() {
+        None => diagnostics.push(ParseDiagnostic::new(
+            input,
+            "Expected a MATCH, UNWIND, WITH, RETURN or writing clause",
+            0..input.len(),
+        )),
+        Some((_, start)) => {
+            let leading = &input[..*start];
+            if !leading.trim().is_empty() {
+                let offset = leading.len() - leading.trim_start().len();
+                diagnostics.push(ParseDiagnostic::new(
+                    input,
+                    format!("Unexpected input before first clause: {}", leading.trim()),
+                    offset..leading.trim_end().len(),
+                ));
+            }
+        }
+    }
+
+    for (i, &(keyword, start)) in segments.iter().enumerate() {
+        let end = segments.get(i + 1).map(|(_, s)| *s).unwrap_or(input.len());
+        let text = &input[start..end];
+        if let Err((offset, message)) = check_clause(keyword, text) {
+            let clause_end = start + text.trim_end().len();
+            let error_start = (start + offset).min(clause_end);
+            let display = match keyword {
+                "ORDER" => "ORDER BY",
+                "DETACH" => "DETACH DELETE",
+                _ => keyword,
+            };
+            diagnostics.push(ParseDiagnostic::new(
+                input,
+                format!("Invalid {} clause: {}", display, message),
+                error_start..clause_end,
+            ));
+        }
+    }
+
+    // Writing queries need not return anything
+    let writes = segments.iter().any(|(k, _)| WRITE_KEYWORDS.contains(k));
+    if !writes && !segments.iter().any(|(k, _)| *k == "RETURN") {
+        diagnostics.push(ParseDiagnostic::new(
+            input,
+            "Query must end with a RETURN clause",
+            input.len()..input.len(),
+        ));
+    }
+
+    if diagnostics.is_empty() {
+        // Every clause is valid on its own: the problem is how they are combined
+        let position = match &first_error {
+            GraphError::ParseError { position, .. } => (*position).min(input.len()),
+            _ => 0,
+        };
+        diagnostics.push(ParseDiagnostic::new(
+            input,
+            format!("Clauses are not in a valid order: {}", first_error),
+            position..input.len(),
+        ));
+    }
+
+    diagnostics.sort_by_key(|d| d.span.start);
+    Err(diagnostics)
+}
+
+/// Top-level clause keywords recognized during error recovery
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "CALL", "MATCH", "UNWIND", "WHERE", "WITH", "SAMPLE", "RETURN", "ORDER", "SKIP", "LIMIT",
+    "CREATE", "MERGE", "SET", "REMOVE", "DETACH", "DELETE",
+];
+
+/// Keywords of the writing clauses among [`CLAUSE_KEYWORDS`]
+const WRITE_KEYWORDS: &[&str] = &["CREATE", "MERGE", "SET", "REMOVE", "DETACH", "DELETE"];
+
+/// Find the start offset of every clause keyword
+///
+/// Keywords inside strings, property accesses (`n.limit`), map keys
+/// (`{skip: 1}`), the `WITH` of `STARTS WITH` / `ENDS WITH` and the `DELETE`
+/// of `DETACH DELETE` are ignored.
+/// Keywords inside unclosed brackets still start a clause so that one missing
+/// `)` does not hide errors in the rest of the query.
+fn clause_segments(input: &str) -> Vec<(&'static str, usize)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut segments = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut previous_word = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            quote = Some(c);
+            continue;
+        }
+        if !is_word_char(c) || input[..i].chars().next_back().is_some_and(is_word_char) {
+            continue;
+        }
+
+        let word_len = input[i..]
+            .find(|ch: char| !is_word_char(ch))
+            .unwrap_or(input.len() - i);
+        let word = input[i..i + word_len].to_uppercase();
+        let after_dot = input[..i].trim_end().ends_with('.');
+        let before_colon = input[i + word_len..].trim_start().starts_with(':');
+        let is_string_operator_with =
+            word == "WITH" && (previous_word == "STARTS" || previous_word == "ENDS");
+        let is_detach_delete = word == "DELETE" && previous_word == "DETACH";
+        if !after_dot && !before_colon && !is_string_operator_with && !is_detach_delete {
+            if let Some(keyword) = CLAUSE_KEYWORDS.iter().find(|k| **k == word) {
+                segments.push((*keyword, i));
+            }
+        }
+        previous_word = word;
+    }
+
+    segments
+}
+
+/// Parse a single clause in isolation, returning the error offset and message on failure
+fn check_clause(keyword: &str, text: &str) -> std::result::Result<(), (usize, String)> {
+    let result: IResult<&str, ()> = match keyword {
+        // Only the opening of a CALL subquery falls into its segment
+        "CALL" => alt((
+            map(standalone_call, |_| ()),
+            map(table_function_call, |_| ()),
+            map(tuple((tag_no_case("CALL"), multispace0, char('{'))), |_| ()),
+        ))(text),
+        "MATCH" => map(match_clause, |_| ())(text),
+        "UNWIND" => map(unwind_clause, |_| ())(text),
+        "WHERE" => map(where_clause, |_| ())(text),
+        "WITH" => map(with_clause, |_| ())(text),
+        "SAMPLE" => map(sample_clause, |_| ())(text),
+        "RETURN" => map(return_clause, |_| ())(text),
+        "ORDER" => map(order_by_clause, |_| ())(text),
+        "SKIP" => map(skip_clause, |_| ())(text),
+        "LIMIT" => map(limit_clause, |_| ())(text),
+        "CREATE" => map(create_clause, |_| ())(text),
+        "MERGE" => map(merge_clause, |_| ())(text),
+        "SET" => map(set_clause, |_| ())(text),
+        "REMOVE" => map(remove_clause, |_| ())(text),
+        "DETACH" | "DELETE" => map(delete_clause, |_| ())(text),
+        _ => Ok((text, ())),
+    };
+
+    match result {
+        Ok((remaining, _)) if remaining.trim().is_empty() => Ok(()),
+        Ok((remaining, _)) => {
+            let remaining = remaining.trim_start();
+            Err((
+                text.len() - remaining.len(),
+                format!("unexpected input '{}'", remaining.trim_end()),
+            ))
+        }
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            let remaining = e.input.trim_start();
+            let offset = text.len() - remaining.len();
+            let message = if remaining.is_empty() {
+                "unexpected end of clause".to_string()
+            } else {
+                let snippet: String = remaining.chars().take(20).collect();
+                format!("unexpected input near '{}'", snippet.trim_end())
+            };
+            Err((offset, message))
+        }
+        Err(nom::Err::Incomplete(_)) => Err((text.len(), "incomplete input".to_string())),
+    }
+}
+
+/// Report unterminated strings and unbalanced brackets
+fn lexical_diagnostics(input: &str) -> Vec<ParseDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut quote: Option<(char, usize)> = None;
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some((q, _)) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some((c, i)),
+            '(' | '[' | '{' => open.push((c, i)),
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                match open.last() {
+                    Some((o, _)) if *o == expected => {
+                        open.pop();
+                    }
+                    _ => diagnostics.push(ParseDiagnostic::new(
+                        input,
+                        format!("Unmatched closing '{}'", c),
+                        i..i + 1,
+                    )),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((_, start)) = quote {
+        diagnostics.push(ParseDiagnostic::new(
+            input,
+            "Unterminated string literal",
+            start..input.len(),
+        ));
+    }
+    for (c, i) in open {
+        diagnostics.push(ParseDiagnostic::new(
+            input,
+            format!("Unclosed '{}'", c),
+            i..i + 1,
+        ));
+    }
+    diagnostics
+}
+
 // Top-level parser for a complete Cypher query
 fn cypher_query(input: &str) -> IResult<&str, CypherQuery> {
+    alt((standalone_call, clause_query))(input)
+}
+
+// Parse a standalone procedure call: `CALL graph.summary()`
+fn standalone_call(input: &str) -> IResult<&str, CypherQuery> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("CALL")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, name) = recognize(separated_list1(char('.'), identifier))(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, arguments) = separated_list0(comma_ws, value_expression)(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char(')')(input)?;
+    let (input, _) = multispace0(input)?;
+    // `CALL f() YIELD ...` reads from a table function instead
+    let (input, _) = not(tag_no_case("YIELD"))(input)?;
+
+    Ok((
+        input,
+        CypherQuery {
+            reading_clauses: vec![],
+            where_clause: None,
+            with_clause: None,
+            post_with_reading_clauses: vec![],
+            post_with_where_clause: None,
+            return_clause: ReturnClause {
+                distinct: false,
+                distinct_on: Vec::new(),
+                items: vec![],
+            },
+            limit: None,
+            order_by: None,
+            skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: Some(ProcedureCall {
+                name: name.to_string(),
+                arguments,
+            }),
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
+        },
+    ))
+}
+
+fn clause_query(input: &str) -> IResult<&str, CypherQuery> {
     let (input, _) = multispace0(input)?;
     let (input, reading_clauses) = many0(reading_clause)(input)?;
     let (input, pre_with_where) = opt(where_clause)(input)?;
 
+    // A CALL subquery is followed directly by RETURN
+    let (input, call) = opt(call_subquery)(input)?;
+
     // Optional WITH clause with optional post-WITH MATCH and WHERE
-    let (input, with_result) = opt(with_clause)(input)?;
+    let (input, with_result) = match call {
+        Some(_) => (input, None),
+        None => opt(with_clause)(input)?,
+    };
     // Only try to parse post-WITH clauses if we have a WITH clause
     let (input, post_with_reading_clauses, post_with_where) = match with_result {
         Some(_) => {
@@ -56,9 +535,68 @@ fn cypher_query(input: &str) -> IResult<&str, CypherQuery> {
         None => (input, vec![], None),
     };
 
+    // CREATE, MERGE, SET, REMOVE and DELETE write to the graph and end the
+    // query
+    if with_result.is_none() && call.is_none() {
+        let (rest, create) = opt(create_clause)(input)?;
+        let (rest, merge) = match create {
+            Some(_) => (rest, None),
+            None => opt(merge_clause)(rest)?,
+        };
+        let (rest, set) = match (&create, &merge) {
+            (None, None) => opt(set_clause)(rest)?,
+            _ => (rest, None),
+        };
+        let (rest, remove) = match (&create, &merge, &set) {
+            (None, None, None) => opt(remove_clause)(rest)?,
+            _ => (rest, None),
+        };
+        let (rest, delete) = match (&create, &merge, &set, &remove) {
+            (None, None, None, None) => opt(delete_clause)(rest)?,
+            _ => (rest, None),
+        };
+        if create.is_some()
+            || merge.is_some()
+            || set.is_some()
+            || remove.is_some()
+            || delete.is_some()
+        {
+            let (rest, _) = multispace0(rest)?;
+            return Ok((
+                rest,
+                CypherQuery {
+                    reading_clauses,
+                    where_clause: pre_with_where,
+                    with_clause: None,
+                    post_with_reading_clauses,
+                    post_with_where_clause: post_with_where,
+                    return_clause: ReturnClause {
+                        distinct: false,
+                        distinct_on: Vec::new(),
+                        items: vec![],
+                    },
+                    limit: None,
+                    order_by: None,
+                    skip: None,
+                    include_deleted: false,
+                    sample: None,
+                    procedure: None,
+                    create_clause: create,
+                    merge_clause: merge,
+                    set_clause: set,
+                    remove_clause: remove,
+                    delete_clause: delete,
+                    call_subquery: None,
+                },
+            ));
+        }
+    }
+
+    let (input, sample) = opt(sample_clause)(input)?;
     let (input, return_clause) = return_clause(input)?;
     let (input, order_by) = opt(order_by_clause)(input)?;
     let (input, (skip, limit)) = pagination_clauses(input)?;
+    let (input, include_deleted) = opt(include_deleted_option)(input)?;
     let (input, _) = multispace0(input)?;
 
     Ok((
@@ -73,26 +611,90 @@ fn cypher_query(input: &str) -> IResult<&str, CypherQuery> {
             limit,
             order_by,
             skip,
+            include_deleted: include_deleted.is_some(),
+            sample,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: call,
         },
     ))
 }
 
-// Parse a reading clause (MATCH or UNWIND)
+// Parse a CALL subquery: `CALL { WITH p MATCH (p)-[:KNOWS]->(f) RETURN f.name AS friend }`
+fn call_subquery(input: &str) -> IResult<&str, CallSubquery> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("CALL")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char('{')(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, imports) = opt(call_imports)(input)?;
+    let (input, query) = clause_query(input)?;
+    let (input, _) = char('}')(input)?;
+    let (input, _) = multispace0(input)?;
+
+    Ok((
+        input,
+        CallSubquery {
+            imports: imports.unwrap_or_default(),
+            query: Box::new(query),
+        },
+    ))
+}
+
+// Parse the importing `WITH p, q` that opens a correlated subquery; a WITH
+// projecting properties or aliases is an ordinary WITH clause
+fn call_imports(input: &str) -> IResult<&str, Vec<String>> {
+    let (input, _) = tag_no_case("WITH")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, imports) = separated_list1(comma_ws, identifier)(input)?;
+    let (input, _) = not(preceded(
+        multispace0,
+        alt((
+            tag("."),
+            tag("("),
+            terminated(tag_no_case("AS"), multispace1),
+        )),
+    ))(input)?;
+    let (input, _) = multispace1(input)?;
+    Ok((input, imports.into_iter().map(String::from).collect()))
+}
+
+// Parse a reading clause (MATCH, UNWIND or CALL ... YIELD)
 fn reading_clause(input: &str) -> IResult<&str, ReadingClause> {
     alt((
         map(match_clause, ReadingClause::Match),
         map(unwind_clause, ReadingClause::Unwind),
+        map(table_function_call, ReadingClause::TableFunction),
     ))(input)
 }
 
-// Parse a MATCH clause
+// Parse a MATCH or OPTIONAL MATCH clause
 fn match_clause(input: &str) -> IResult<&str, MatchClause> {
     let (input, _) = multispace0(input)?;
+    let (input, optional) = opt(tuple((tag_no_case("OPTIONAL"), multispace1)))(input)?;
     let (input, _) = tag_no_case("MATCH")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, patterns) = separated_list0(comma_ws, graph_pattern)(input)?;
 
-    Ok((input, MatchClause { patterns }))
+    // A WHERE right after OPTIONAL MATCH belongs to the optional pattern
+    let (input, where_clause) = if optional.is_some() {
+        opt(where_clause)(input)?
+    } else {
+        (input, None)
+    };
+
+    Ok((
+        input,
+        MatchClause {
+            patterns,
+            optional: optional.is_some(),
+            where_clause,
+        },
+    ))
 }
 
 // Parse an UNWIND clause
@@ -115,14 +717,191 @@ fn unwind_clause(input: &str) -> IResult<&str, UnwindClause> {
     ))
 }
 
+// Parse a table function call: `CALL weather.forecast('Berlin', 3) YIELD day, temperature AS t`
+fn table_function_call(input: &str) -> IResult<&str, TableFunctionCall> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("CALL")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, name) = recognize(separated_list1(char('.'), identifier))(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, arguments) = separated_list0(comma_ws, value_expression)(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char(')')(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, _) = tag_no_case("YIELD")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, yields) = separated_list1(comma_ws, yield_item)(input)?;
+
+    Ok((
+        input,
+        TableFunctionCall {
+            name: name.to_string(),
+            arguments,
+            yields,
+        },
+    ))
+}
+
+// Parse a YIELD item: `column` or `column AS alias`
+fn yield_item(input: &str) -> IResult<&str, YieldItem> {
+    let (input, column) = identifier(input)?;
+    let (input, alias) = opt(preceded(
+        tuple((multispace1, tag_no_case("AS"), multispace1)),
+        identifier,
+    ))(input)?;
+    Ok((
+        input,
+        YieldItem {
+            column: column.to_string(),
+            alias: alias.map(str::to_string),
+        },
+    ))
+}
+
+// Parse a CREATE clause
+fn create_clause(input: &str) -> IResult<&str, CreateClause> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("CREATE")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, patterns) = separated_list1(comma_ws, graph_pattern)(input)?;
+
+    Ok((input, CreateClause { patterns }))
+}
+
+// Parse a MERGE clause
+fn merge_clause(input: &str) -> IResult<&str, MergeClause> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("MERGE")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, pattern) = graph_pattern(input)?;
+
+    Ok((input, MergeClause { pattern }))
+}
+
+// Parse a SET clause
+fn set_clause(input: &str) -> IResult<&str, SetClause> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("SET")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, items) = separated_list1(comma_ws, set_item)(input)?;
+
+    Ok((input, SetClause { items }))
+}
+
+// Parse a REMOVE clause
+fn remove_clause(input: &str) -> IResult<&str, RemoveClause> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("REMOVE")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, items) = separated_list1(comma_ws, remove_item)(input)?;
+
+    Ok((input, RemoveClause { items }))
+}
+
+// Parse a DELETE or DETACH DELETE clause
+fn delete_clause(input: &str) -> IResult<&str, DeleteClause> {
+    let (input, _) = multispace0(input)?;
+    let (input, detach) = opt(terminated(tag_no_case("DETACH"), multispace1))(input)?;
+    let (input, _) = tag_no_case("DELETE")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, variables) = separated_list1(comma_ws, identifier)(input)?;
+
+    Ok((
+        input,
+        DeleteClause {
+            detach: detach.is_some(),
+            variables: variables.into_iter().map(str::to_string).collect(),
+        },
+    ))
+}
+
+// Parse a SET item: n.prop = value, n += {..} / $map, or n:Label
+fn set_item(input: &str) -> IResult<&str, SetItem> {
+    alt((
+        map(
+            tuple((
+                property_reference,
+                multispace0,
+                char('='),
+                multispace0,
+                property_value,
+            )),
+            |(target, _, _, _, value)| SetItem::Property { target, value },
+        ),
+        map(
+            tuple((
+                identifier,
+                multispace0,
+                tag("+="),
+                multispace0,
+                alt((
+                    map(property_map, PropertyMap::Literal),
+                    map(parameter, PropertyMap::Parameter),
+                )),
+            )),
+            |(variable, _, _, _, properties)| SetItem::Properties {
+                variable: variable.to_string(),
+                properties,
+            },
+        ),
+        map(
+            pair(identifier, many1(preceded(char(':'), name_or_parameter))),
+            |(variable, labels)| SetItem::Labels {
+                variable: variable.to_string(),
+                labels: labels.into_iter().map(str::to_string).collect(),
+            },
+        ),
+    ))(input)
+}
+
+// Parse a REMOVE item: n.prop or n:Label
+fn remove_item(input: &str) -> IResult<&str, RemoveItem> {
+    alt((
+        map(property_reference, |target| RemoveItem::Property { target }),
+        map(
+            pair(identifier, many1(preceded(char(':'), name_or_parameter))),
+            |(variable, labels)| RemoveItem::Labels {
+                variable: variable.to_string(),
+                labels: labels.into_iter().map(str::to_string).collect(),
+            },
+        ),
+    ))(input)
+}
+
 // Parse a graph pattern (node or path)
 fn graph_pattern(input: &str) -> IResult<&str, GraphPattern> {
     alt((
+        map(named_path_pattern, GraphPattern::Path),
+        map(shortest_path_pattern, GraphPattern::Path),
         map(path_pattern, GraphPattern::Path),
         map(node_pattern, GraphPattern::Node),
     ))(input)
 }
 
+// Parse a path pattern bound to a path variable: `p = (a)-[*1..3]->(b)`
+fn named_path_pattern(input: &str) -> IResult<&str, PathPattern> {
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace0, char('='), multispace0))(input)?;
+    let (input, mut path) = alt((shortest_path_pattern, path_pattern))(input)?;
+    path.variable = Some(variable.to_string());
+    Ok((input, path))
+}
+
+// Parse `shortestPath(<path>)` or `allShortestPaths(<path>)`
+fn shortest_path_pattern(input: &str) -> IResult<&str, PathPattern> {
+    let (input, kind) = alt((
+        map(tag_no_case("allShortestPaths"), |_| ShortestPathKind::All),
+        map(tag_no_case("shortestPath"), |_| ShortestPathKind::Single),
+    ))(input)?;
+    let (input, _) = tuple((multispace0, char('('), multispace0))(input)?;
+    let (input, mut path) = path_pattern(input)?;
+    let (input, _) = tuple((multispace0, char(')')))(input)?;
+    path.shortest = Some(kind);
+    Ok((input, path))
+}
+
 // Parse a path pattern (only if there are segments)
 fn path_pattern(input: &str) -> IResult<&str, PathPattern> {
     let (input, start_node) = node_pattern(input)?;
@@ -139,8 +918,10 @@ fn path_pattern(input: &str) -> IResult<&str, PathPattern> {
     Ok((
         input,
         PathPattern {
+            variable: None,
             start_node,
             segments,
+            shortest: None,
         },
     ))
 }
@@ -165,7 +946,7 @@ fn node_pattern(input: &str) -> IResult<&str, NodePattern> {
     let (input, _) = char('(')(input)?;
     let (input, _) = multispace0(input)?;
     let (input, variable) = opt(identifier)(input)?;
-    let (input, labels) = many0(preceded(char(':'), identifier))(input)?;
+    let (input, labels) = many0(preceded(char(':'), name_or_parameter))(input)?;
     let (input, _) = multispace0(input)?;
     let (input, properties) = opt(property_map)(input)?;
     let (input, _) = multispace0(input)?;
@@ -242,7 +1023,7 @@ type RelationshipContentResult<'a> = (
 fn relationship_content(input: &str) -> IResult<&str, RelationshipContentResult<'_>> {
     let (input, _) = multispace0(input)?;
     let (input, variable) = opt(identifier)(input)?;
-    let (input, types) = many0(preceded(char(':'), identifier))(input)?;
+    let (input, types) = many0(preceded(char(':'), name_or_parameter))(input)?;
     let (input, _) = multispace0(input)?;
     let (input, length) = opt(length_range)(input)?;
     let (input, _) = multispace0(input)?;
@@ -252,6 +1033,12 @@ fn relationship_content(input: &str) -> IResult<&str, RelationshipContentResult<
     Ok((input, (variable, types, properties, length)))
 }
 
+// Parse a label, relationship type or property name, or a `$param` standing
+// for one that is bound before planning
+fn name_or_parameter(input: &str) -> IResult<&str, &str> {
+    alt((identifier, recognize(preceded(char('$'), identifier))))(input)
+}
+
 // Parse a property map: {key: value, key2: value2}
 fn property_map(input: &str) -> IResult<&str, HashMap<String, PropertyValue>> {
     let (input, _) = multispace0(input)?;
@@ -352,18 +1139,137 @@ fn boolean_primary_expression(input: &str) -> IResult<&str, BooleanExpression> {
             ),
             |expr| expr,
         ),
+        exists_subquery,
+        all_in_path_function,
+        list_predicate_function,
+        exists_function,
         comparison_expression,
     ))(input)
 }
 
+// Parse an `EXISTS { [MATCH] pattern, ... [WHERE predicate] }` subquery
+fn exists_subquery(input: &str) -> IResult<&str, BooleanExpression> {
+    let (input, _) = tag_no_case("EXISTS")(input)?;
+    let (input, (patterns, where_clause)) = subquery_body(input)?;
+    Ok((
+        input,
+        BooleanExpression::ExistsSubquery {
+            patterns,
+            where_clause,
+        },
+    ))
+}
+
+// Parse `COUNT { [MATCH] pattern, ... [WHERE ...] }`
+fn count_subquery(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, _) = tag_no_case("COUNT")(input)?;
+    let (input, (patterns, where_clause)) = subquery_body(input)?;
+    Ok((
+        input,
+        ValueExpression::CountSubquery {
+            patterns,
+            where_clause,
+        },
+    ))
+}
+
+// Parse the braced patterns and optional WHERE of an EXISTS or COUNT subquery
+fn subquery_body(
+    input: &str,
+) -> IResult<&str, (Vec<GraphPattern>, Option<Box<BooleanExpression>>)> {
+    let (input, _) = tuple((multispace0, char('{'), multispace0))(input)?;
+    let (input, _) = opt(tuple((keyword("MATCH"), multispace0)))(input)?;
+    let (input, patterns) = separated_list1(comma_ws, graph_pattern)(input)?;
+    let (input, where_clause) = opt(where_clause)(input)?;
+    let (input, _) = tuple((multispace0, char('}')))(input)?;
+    Ok((
+        input,
+        (patterns, where_clause.map(|w| Box::new(w.expression))),
+    ))
+}
+
+// Parse the list predicate `all(x IN nodes(p) WHERE ...)` over a path variable
+fn all_in_path_function(input: &str) -> IResult<&str, BooleanExpression> {
+    let (input, _) = tag_no_case("all")(input)?;
+    let (input, _) = tuple((multispace0, char('('), multispace0))(input)?;
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace1, tag_no_case("IN"), multispace1))(input)?;
+    let (input, elements) = alt((
+        map(tag_no_case("nodes"), |_| PathElements::Nodes),
+        map(tag_no_case("relationships"), |_| {
+            PathElements::Relationships
+        }),
+    ))(input)?;
+    let (input, path) = delimited(
+        tuple((multispace0, char('('), multispace0)),
+        identifier,
+        tuple((multispace0, char(')'))),
+    )(input)?;
+    let (input, _) = tuple((multispace1, tag_no_case("WHERE"), multispace1))(input)?;
+    let (input, predicate) = boolean_expression(input)?;
+    let (input, _) = tuple((multispace0, char(')')))(input)?;
+    Ok((
+        input,
+        BooleanExpression::AllInPath {
+            variable: variable.to_string(),
+            path: path.to_string(),
+            elements,
+            predicate: Box::new(predicate),
+        },
+    ))
+}
+
+// Parse `any(x IN list WHERE predicate)`, likewise `all`, `none` and `single`
+fn list_predicate_function(input: &str) -> IResult<&str, BooleanExpression> {
+    let (input, quantifier) = alt((
+        map(keyword("all"), |_| ListQuantifier::All),
+        map(keyword("any"), |_| ListQuantifier::Any),
+        map(keyword("none"), |_| ListQuantifier::None),
+        map(keyword("single"), |_| ListQuantifier::Single),
+    ))(input)?;
+    let (input, _) = tuple((multispace0, char('('), multispace0))(input)?;
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace1, keyword("IN"), multispace1))(input)?;
+    let (input, list) = value_expression(input)?;
+    let (input, _) = tuple((multispace0, keyword("WHERE"), multispace0))(input)?;
+    let (input, predicate) = boolean_expression(input)?;
+    let (input, _) = tuple((multispace0, char(')')))(input)?;
+    Ok((
+        input,
+        BooleanExpression::ListPredicate {
+            quantifier,
+            variable: variable.to_string(),
+            list,
+            predicate: Box::new(predicate),
+        },
+    ))
+}
+
+// Parse the Neo4j `exists(n.prop)` property-existence function
+fn exists_function(input: &str) -> IResult<&str, BooleanExpression> {
+    let (input, _) = tag_no_case("exists")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, prop) = delimited(
+        tuple((char('('), multispace0)),
+        property_reference,
+        tuple((multispace0, char(')'))),
+    )(input)?;
+    Ok((input, BooleanExpression::Exists(prop)))
+}
+
 fn comparison_expression(input: &str) -> IResult<&str, BooleanExpression> {
     let (input, _) = multispace0(input)?;
     let (input, left) = value_expression(input)?;
     let (input, _) = multispace0(input)?;
     let left_clone = left.clone();
 
+    // `IN $param` binds a list parameter, kept as a single-parameter list
+    let in_list = alt((
+        value_expression_list,
+        map(parameter, |name| vec![ValueExpression::Parameter(name)]),
+    ));
     if let Ok((input_after_in, (_, _, list))) =
-        tuple((tag_no_case("IN"), multispace0, value_expression_list))(input)
+        tuple((tag_no_case("IN"), multispace0, in_list))(input)
     {
         return Ok((
             input_after_in,
@@ -443,6 +1349,18 @@ fn comparison_expression(input: &str) -> IResult<&str, BooleanExpression> {
             },
         ));
     }
+    // Match =~ regular expression
+    if let Ok((input_after_regex, (_, _, pattern))) =
+        tuple((tag("=~"), multispace0, string_literal))(input)
+    {
+        return Ok((
+            input_after_regex,
+            BooleanExpression::RegexMatch {
+                expression: left,
+                pattern,
+            },
+        ));
+    }
     // Match is null
     if let Ok((rest, ())) = is_null_comparison(input) {
         return Ok((rest, BooleanExpression::IsNull(left_clone)));
@@ -482,18 +1400,66 @@ fn comparison_operator(input: &str) -> IResult<&str, ComparisonOperator> {
 // Parse a basic value expression (without vector functions to avoid circular dependency)
 fn basic_value_expression(input: &str) -> IResult<&str, ValueExpression> {
     alt((
-        parse_vector_literal, // Try vector literal first [0.1, 0.2]
-        parse_parameter,      // Try $parameter
-        function_call,        // Regular function calls
+        pattern_comprehension,                         // [(a)-->(b) WHERE ... | ...]
+        list_comprehension,                            // [x IN list WHERE ... | ...]
+        count_subquery,                                // COUNT { (a)-->(b) WHERE ... }
+        reduce_expression,                             // reduce(acc = 0, x IN list | ...)
+        map_projection,                                // n {.name, key: value}
+        parse_vector_literal,                          // Try vector literal first [0.1, 0.2]
+        parse_parameter,                               // Try $parameter
+        function_call,                                 // Regular function calls
         map(property_value, ValueExpression::Literal), // Try literals BEFORE property references
+        component_accessor,                            // e.at.year
         map(property_reference, ValueExpression::Property),
         map(identifier, |id| ValueExpression::Variable(id.to_string())),
     ))(input)
 }
 
-// Parse a value expression
-// Optimization: Use peek to avoid expensive backtracking for non-vector queries
+// Parse a value expression: operands joined by arithmetic operators, with
+// `*`, `/` and `%` binding tighter than `+` and `-`
 fn value_expression(input: &str) -> IResult<&str, ValueExpression> {
+    let additive = alt((
+        map(char('+'), |_| ArithmeticOperator::Add),
+        map(char('-'), |_| ArithmeticOperator::Subtract),
+    ));
+    arithmetic_chain(multiplicative_expression, additive)(input)
+}
+
+fn multiplicative_expression(input: &str) -> IResult<&str, ValueExpression> {
+    let multiplicative = alt((
+        map(char('*'), |_| ArithmeticOperator::Multiply),
+        map(char('/'), |_| ArithmeticOperator::Divide),
+        map(char('%'), |_| ArithmeticOperator::Modulo),
+    ));
+    arithmetic_chain(operand_expression, multiplicative)(input)
+}
+
+// Left-associative chain of `operand`s separated by `operator`
+fn arithmetic_chain<'a>(
+    operand: fn(&'a str) -> IResult<&'a str, ValueExpression>,
+    operator: impl FnMut(&'a str) -> IResult<&'a str, ArithmeticOperator>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, ValueExpression> {
+    let mut rest = many0(tuple((
+        delimited(multispace0, operator, multispace0),
+        operand,
+    )));
+    move |input| {
+        let (input, first) = operand(input)?;
+        let (input, rest) = rest(input)?;
+        let expression = rest.into_iter().fold(first, |left, (operator, right)| {
+            ValueExpression::Arithmetic {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            }
+        });
+        Ok((input, expression))
+    }
+}
+
+// Parse a single operand of a value expression
+// Optimization: Use peek to avoid expensive backtracking for non-vector queries
+fn operand_expression(input: &str) -> IResult<&str, ValueExpression> {
     // Peek at first identifier to dispatch to correct parser
     // This eliminates failed parser attempts for every non-vector expression
     if let Ok((_, first_ident)) = peek(identifier)(input) {
@@ -502,6 +1468,7 @@ fn value_expression(input: &str) -> IResult<&str, ValueExpression> {
         match ident_lower.as_str() {
             "vector_distance" => return parse_vector_distance(input),
             "vector_similarity" => return parse_vector_similarity(input),
+            "case" => return case_expression(input),
             _ => {} // Not a vector function, continue to basic expressions
         }
     }
@@ -510,6 +1477,179 @@ fn value_expression(input: &str) -> IResult<&str, ValueExpression> {
     basic_value_expression(input)
 }
 
+// Parse `CASE [operand] WHEN ... THEN ... [ELSE ...] END`; the simple form,
+// with an operand, compares it to each WHEN value
+fn case_expression(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, _) = keyword("CASE")(input)?;
+    let (input, operand) = opt(preceded(
+        tuple((multispace0, not(keyword("WHEN")))),
+        value_expression,
+    ))(input)?;
+    let when = || tuple((multispace0, keyword("WHEN"), multispace0));
+    let then = || tuple((multispace0, keyword("THEN"), multispace0));
+    let (input, branches) = match operand {
+        Some(operand) => many1(map(
+            pair(
+                preceded(when(), value_expression),
+                preceded(then(), value_expression),
+            ),
+            |(expected, value)| CaseBranch {
+                condition: BooleanExpression::Comparison {
+                    left: operand.clone(),
+                    operator: ComparisonOperator::Equal,
+                    right: expected,
+                },
+                value,
+            },
+        ))(input)?,
+        None => many1(map(
+            pair(
+                preceded(when(), boolean_expression),
+                preceded(then(), value_expression),
+            ),
+            |(condition, value)| CaseBranch { condition, value },
+        ))(input)?,
+    };
+    let (input, default) = opt(preceded(
+        tuple((multispace0, keyword("ELSE"), multispace0)),
+        value_expression,
+    ))(input)?;
+    let (input, _) = tuple((multispace0, keyword("END")))(input)?;
+    Ok((
+        input,
+        ValueExpression::Case {
+            branches,
+            default: default.map(Box::new),
+        },
+    ))
+}
+
+// Parse `[x IN list WHERE predicate | projection]`; the WHERE part and the
+// projection are optional
+fn list_comprehension(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, _) = tuple((char('['), multispace0))(input)?;
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace1, keyword("IN"), multispace1))(input)?;
+    let (input, list) = value_expression(input)?;
+    let (input, predicate) = opt(preceded(
+        tuple((multispace0, keyword("WHERE"), multispace0)),
+        boolean_expression,
+    ))(input)?;
+    let (input, projection) = opt(preceded(
+        tuple((multispace0, char('|'), multispace0)),
+        value_expression,
+    ))(input)?;
+    let (input, _) = tuple((multispace0, char(']')))(input)?;
+    Ok((
+        input,
+        ValueExpression::ListComprehension {
+            variable: variable.to_string(),
+            list: Box::new(list),
+            predicate: predicate.map(Box::new),
+            projection: projection.map(Box::new),
+        },
+    ))
+}
+
+// Parse `reduce(accumulator = initial, x IN list | expression)`
+fn reduce_expression(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, _) = tuple((keyword("reduce"), multispace0, char('('), multispace0))(input)?;
+    let (input, accumulator) = identifier(input)?;
+    let (input, _) = tuple((multispace0, char('='), multispace0))(input)?;
+    let (input, initial) = value_expression(input)?;
+    let (input, _) = comma_ws(input)?;
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace1, keyword("IN"), multispace1))(input)?;
+    let (input, list) = value_expression(input)?;
+    let (input, _) = tuple((multispace0, char('|'), multispace0))(input)?;
+    let (input, expression) = value_expression(input)?;
+    let (input, _) = tuple((multispace0, char(')')))(input)?;
+    Ok((
+        input,
+        ValueExpression::Reduce {
+            accumulator: accumulator.to_string(),
+            initial: Box::new(initial),
+            variable: variable.to_string(),
+            list: Box::new(list),
+            expression: Box::new(expression),
+        },
+    ))
+}
+
+// Parse `[(a)-[:REL]->(b) WHERE predicate | projection]`; the WHERE part is
+// optional and the pattern needs at least one relationship
+fn pattern_comprehension(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, _) = tuple((char('['), multispace0))(input)?;
+    let (input, pattern) = alt((named_path_pattern, path_pattern))(input)?;
+    let (input, predicate) = opt(preceded(
+        tuple((multispace0, keyword("WHERE"), multispace0)),
+        boolean_expression,
+    ))(input)?;
+    let (input, _) = tuple((multispace0, char('|'), multispace0))(input)?;
+    let (input, projection) = value_expression(input)?;
+    let (input, _) = tuple((multispace0, char(']')))(input)?;
+    Ok((
+        input,
+        ValueExpression::PatternComprehension {
+            pattern,
+            predicate: predicate.map(Box::new),
+            projection: Box::new(projection),
+        },
+    ))
+}
+
+// Parse a map projection `n {.name, key: expression, other}`
+fn map_projection(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, variable) = identifier(input)?;
+    let (input, _) = tuple((multispace0, char('{'), multispace0))(input)?;
+    let (input, items) =
+        separated_list1(comma_ws, |input| map_projection_item(variable, input))(input)?;
+    let (input, _) = tuple((multispace0, char('}')))(input)?;
+    Ok((
+        input,
+        ValueExpression::MapProjection {
+            variable: variable.to_string(),
+            items,
+        },
+    ))
+}
+
+// Parse one item of a map projection of `variable`: `.property`,
+// `key: expression` or `other`
+fn map_projection_item<'a>(variable: &str, input: &'a str) -> IResult<&'a str, MapProjectionItem> {
+    alt((
+        map(preceded(char('.'), identifier), |property| {
+            MapProjectionItem {
+                key: property.to_string(),
+                value: ValueExpression::Property(PropertyRef::new(variable, property)),
+            }
+        }),
+        map(
+            tuple((
+                identifier,
+                tuple((multispace0, char(':'), multispace0)),
+                value_expression,
+            )),
+            |(key, _, value)| MapProjectionItem {
+                key: key.to_string(),
+                value,
+            },
+        ),
+        map(identifier, |name| MapProjectionItem {
+            key: name.to_string(),
+            value: ValueExpression::Variable(name.to_string()),
+        }),
+    ))(input)
+}
+
+// Match `word` case-insensitively, not followed by more identifier characters
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        tag_no_case(word),
+        not(take_while1(|c: char| c.is_alphanumeric() || c == '_')),
+    )
+}
+
 // Parse distance metric: cosine, l2, dot
 fn parse_distance_metric(input: &str) -> IResult<&str, DistanceMetric> {
     alt((
@@ -519,7 +1659,7 @@ fn parse_distance_metric(input: &str) -> IResult<&str, DistanceMetric> {
     ))(input)
 }
 
-// Parse vector_distance(expr, expr, metric)
+// Parse vector_distance(expr, expr[, metric])
 fn parse_vector_distance(input: &str) -> IResult<&str, ValueExpression> {
     let (input, _) = tag_no_case("vector_distance")(input)?;
     let (input, _) = multispace0(input)?;
@@ -535,12 +1675,14 @@ fn parse_vector_distance(input: &str) -> IResult<&str, ValueExpression> {
     // Parse right expression - use basic_value_expression to avoid circular dependency
     let (input, right) = basic_value_expression(input)?;
     let (input, _) = multispace0(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, _) = multispace0(input)?;
 
-    // Parse metric
-    let (input, metric) = parse_distance_metric(input)?;
-    let (input, _) = multispace0(input)?;
+    // Parse metric, cosine when omitted
+    let (input, metric) = opt(delimited(
+        tuple((char(','), multispace0)),
+        parse_distance_metric,
+        multispace0,
+    ))(input)?;
+    let metric = metric.unwrap_or_default();
     let (input, _) = char(')')(input)?;
 
     Ok((
@@ -553,7 +1695,7 @@ fn parse_vector_distance(input: &str) -> IResult<&str, ValueExpression> {
     ))
 }
 
-// Parse vector_similarity(expr, expr, metric)
+// Parse vector_similarity(expr, expr[, metric])
 fn parse_vector_similarity(input: &str) -> IResult<&str, ValueExpression> {
     let (input, _) = tag_no_case("vector_similarity")(input)?;
     let (input, _) = multispace0(input)?;
@@ -569,12 +1711,14 @@ fn parse_vector_similarity(input: &str) -> IResult<&str, ValueExpression> {
     // Parse right expression - use basic_value_expression to avoid circular dependency
     let (input, right) = basic_value_expression(input)?;
     let (input, _) = multispace0(input)?;
-    let (input, _) = char(',')(input)?;
-    let (input, _) = multispace0(input)?;
 
-    // Parse metric
-    let (input, metric) = parse_distance_metric(input)?;
-    let (input, _) = multispace0(input)?;
+    // Parse metric, cosine when omitted
+    let (input, metric) = opt(delimited(
+        tuple((char(','), multispace0)),
+        parse_distance_metric,
+        multispace0,
+    ))(input)?;
+    let metric = metric.unwrap_or_default();
     let (input, _) = char(')')(input)?;
 
     Ok((
@@ -594,9 +1738,9 @@ fn parse_parameter(input: &str) -> IResult<&str, ValueExpression> {
     Ok((input, ValueExpression::Parameter(name.to_string())))
 }
 
-// Parse a function call: function_name(args)
+// Parse a function call: function_name(args), or namespace.function_name(args)
 fn function_call(input: &str) -> IResult<&str, ValueExpression> {
-    let (input, name) = identifier(input)?;
+    let (input, name) = recognize(separated_list1(char('.'), identifier))(input)?;
     let (input, _) = multispace0(input)?;
     let (input, _) = char('(')(input)?;
     let (input, _) = multispace0(input)?;
@@ -738,7 +1882,7 @@ fn parse_vector_literal(input: &str) -> IResult<&str, ValueExpression> {
 fn property_reference(input: &str) -> IResult<&str, PropertyRef> {
     let (input, variable) = identifier(input)?;
     let (input, _) = char('.')(input)?;
-    let (input, property) = identifier(input)?;
+    let (input, property) = name_or_parameter(input)?;
 
     Ok((
         input,
@@ -749,11 +1893,23 @@ fn property_reference(input: &str) -> IResult<&str, PropertyRef> {
     ))
 }
 
+// Parse a component of a temporal property: variable.property.component
+fn component_accessor(input: &str) -> IResult<&str, ValueExpression> {
+    let (input, property) = property_reference(input)?;
+    let (input, _) = char('.')(input)?;
+    let (input, component) = identifier(input)?;
+    Ok((
+        input,
+        crate::temporal::accessor(ValueExpression::Property(property), component),
+    ))
+}
+
 // Parse a WITH clause (intermediate projection/aggregation)
 fn with_clause(input: &str) -> IResult<&str, WithClause> {
     let (input, _) = multispace0(input)?;
     let (input, _) = tag_no_case("WITH")(input)?;
     let (input, _) = multispace1(input)?;
+    let (input, distinct) = opt(terminated(tag_no_case("DISTINCT"), multispace1))(input)?;
     let (input, items) = separated_list0(comma_ws, return_item)(input)?;
     let (input, order_by) = opt(order_by_clause)(input)?;
     let (input, limit) = opt(limit_clause)(input)?;
@@ -761,6 +1917,7 @@ fn with_clause(input: &str) -> IResult<&str, WithClause> {
     Ok((
         input,
         WithClause {
+            distinct: distinct.is_some(),
             items,
             order_by,
             limit,
@@ -774,6 +1931,11 @@ fn return_clause(input: &str) -> IResult<&str, ReturnClause> {
     let (input, _) = tag_no_case("RETURN")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, distinct) = opt(tag_no_case("DISTINCT"))(input)?;
+    let (input, distinct_on) = if distinct.is_some() {
+        opt(distinct_on_keys)(input)?
+    } else {
+        (input, None)
+    };
     let (input, _) = if distinct.is_some() {
         multispace1(input)?
     } else {
@@ -784,12 +1946,25 @@ fn return_clause(input: &str) -> IResult<&str, ReturnClause> {
     Ok((
         input,
         ReturnClause {
-            distinct: distinct.is_some(),
+            distinct: distinct.is_some() && distinct_on.is_none(),
+            distinct_on: distinct_on.unwrap_or_default(),
             items,
         },
     ))
 }
 
+// Parse the keys of DISTINCT ON: ` ON (expr, ...)`
+fn distinct_on_keys(input: &str) -> IResult<&str, Vec<ValueExpression>> {
+    let (input, _) = multispace1(input)?;
+    let (input, _) = tag_no_case("ON")(input)?;
+    let (input, _) = multispace0(input)?;
+    delimited(
+        tuple((char('('), multispace0)),
+        separated_list1(comma_ws, value_expression),
+        tuple((multispace0, char(')'))),
+    )(input)
+}
+
 // Parse a return item
 fn return_item(input: &str) -> IResult<&str, ReturnItem> {
     let (input, expression) = value_expression(input)?;
@@ -872,6 +2047,45 @@ fn limit_clause(input: &str) -> IResult<&str, u64> {
     Ok((input, limit as u64))
 }
 
+// Parse the trailing INCLUDE DELETED query option
+fn include_deleted_option(input: &str) -> IResult<&str, ()> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("INCLUDE")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, _) = tag_no_case("DELETED")(input)?;
+    Ok((input, ()))
+}
+
+// Parse a SAMPLE clause: `SAMPLE 10 PERCENT`, `SAMPLE 100 ROWS [PER <expr>]`
+fn sample_clause(input: &str) -> IResult<&str, SampleMethod> {
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag_no_case("SAMPLE")(input)?;
+    let (input, _) = multispace1(input)?;
+    alt((sample_percent, sample_rows))(input)
+}
+
+fn sample_percent(input: &str) -> IResult<&str, SampleMethod> {
+    let (input, percent) = alt((float_literal, map(integer_literal, |i| i as f64)))(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, _) = tag_no_case("PERCENT")(input)?;
+    Ok((input, SampleMethod::Percent(percent)))
+}
+
+fn sample_rows(input: &str) -> IResult<&str, SampleMethod> {
+    let (input, count) = map_res(integer_literal, u64::try_from)(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, _) = tag_no_case("ROWS")(input)?;
+    let (input, by) = opt(preceded(
+        tuple((multispace1, tag_no_case("PER"), multispace1)),
+        value_expression,
+    ))(input)?;
+    let method = match by {
+        Some(by) => SampleMethod::RowsPer { count, by },
+        None => SampleMethod::Rows(count),
+    };
+    Ok((input, method))
+}
+
 // Parse a SKIP clause
 fn skip_clause(input: &str) -> IResult<&str, u64> {
     let (input, _) = multispace0(input)?;
@@ -1158,11 +2372,170 @@ mod tests {
                     }
                     _ => panic!("Expected comparison for destination name filter"),
                 }
-            }
-            other => panic!("Expected AND expression, got {:?}", other),
+            }
+            other => panic!("Expected AND expression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_in_list_parameter() {
+        let query = "MATCH (n:Person) WHERE n.id IN $ids RETURN n.name";
+        let result = parse_cypher_query(query).unwrap();
+
+        match result
+            .where_clause
+            .expect("Expected WHERE clause")
+            .expression
+        {
+            BooleanExpression::In { list, .. } => {
+                assert_eq!(list, vec![ValueExpression::Parameter("ids".to_string())]);
+            }
+            other => panic!("Expected IN expression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_label_parameters() {
+        let query = "MATCH (a:$label)-[:$relType]->(b:Person) RETURN a.name";
+        let result = parse_cypher_query(query).unwrap();
+
+        match &result.reading_clauses[0] {
+            ReadingClause::Match(m) => match &m.patterns[0] {
+                GraphPattern::Path(path) => {
+                    assert_eq!(path.start_node.labels, vec!["$label".to_string()]);
+                    assert_eq!(
+                        path.segments[0].relationship.types,
+                        vec!["$relType".to_string()]
+                    );
+                    assert_eq!(path.segments[0].end_node.labels, vec!["Person".to_string()]);
+                }
+                other => panic!("Expected path pattern, got {:?}", other),
+            },
+            other => panic!("Expected MATCH clause, got {:?}", other),
         }
     }
 
+    #[test]
+    fn test_parse_sample_clause() {
+        let sample = |query: &str| parse_cypher_query(query).unwrap().sample;
+        assert_eq!(
+            sample("MATCH (n:Person) WHERE n.age > 30 SAMPLE 12.5 PERCENT RETURN n.name"),
+            Some(SampleMethod::Percent(12.5))
+        );
+        assert_eq!(
+            sample("MATCH (n:Person) sample 100 rows RETURN n.name"),
+            Some(SampleMethod::Rows(100))
+        );
+        assert_eq!(
+            sample("MATCH (n:Person) SAMPLE 5 ROWS PER n.city RETURN n.name"),
+            Some(SampleMethod::RowsPer {
+                count: 5,
+                by: ValueExpression::Property(PropertyRef {
+                    variable: "n".to_string(),
+                    property: "city".to_string(),
+                }),
+            })
+        );
+        assert_eq!(sample("MATCH (n:Person) RETURN n.name"), None);
+        assert!(parse_cypher_query("MATCH (n:Person) SAMPLE -1 ROWS RETURN n.name").is_err());
+    }
+
+    #[test]
+    fn test_parse_standalone_call() {
+        let query = parse_cypher_query("  call graph.summary( ) ").unwrap();
+        assert_eq!(
+            query.procedure,
+            Some(ProcedureCall {
+                name: "graph.summary".to_string(),
+                arguments: vec![],
+            })
+        );
+        assert!(query.reading_clauses.is_empty());
+        assert!(parse_cypher_query("MATCH (n:Person) RETURN n.name")
+            .unwrap()
+            .procedure
+            .is_none());
+        assert!(parse_cypher_query("CALL graph.summary() RETURN 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_table_function_call() {
+        let query = parse_cypher_query(
+            "CALL weather.forecast('Berlin', $days) YIELD day, temperature AS t \
+             MATCH (e:Event) WHERE e.day = day RETURN e.name, t",
+        )
+        .unwrap();
+        assert!(query.procedure.is_none());
+        assert_eq!(query.reading_clauses.len(), 2);
+        let ReadingClause::TableFunction(call) = &query.reading_clauses[0] else {
+            panic!("Expected table function call");
+        };
+        assert_eq!(call.name, "weather.forecast");
+        assert_eq!(
+            call.arguments,
+            vec![
+                ValueExpression::Literal(PropertyValue::String("Berlin".to_string())),
+                ValueExpression::Parameter("days".to_string()),
+            ]
+        );
+        let variables: Vec<_> = call.yields.iter().map(YieldItem::variable).collect();
+        assert_eq!(variables, vec!["day", "t"]);
+        assert_eq!(call.yields[1].column, "temperature");
+
+        // A call after MATCH, and one without YIELD items
+        let query = parse_cypher_query(
+            "MATCH (n:Person) CALL gen.range(3) YIELD value RETURN n.name, value",
+        )
+        .unwrap();
+        assert!(matches!(
+            query.reading_clauses[1],
+            ReadingClause::TableFunction(_)
+        ));
+        assert!(parse_cypher_query("CALL gen.range(3) YIELD RETURN 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_temporal_functions_and_accessors() {
+        let query = parse_cypher_query(
+            "MATCH (e:Event) WITH e, e.at AS at \
+             RETURN date.truncate('month', e.at), e.at.year, at.hour, e.day",
+        )
+        .unwrap();
+        let items: Vec<_> = query
+            .return_clause
+            .items
+            .iter()
+            .map(|item| &item.expression)
+            .collect();
+        assert_eq!(
+            items[0],
+            &ValueExpression::ScalarFunction {
+                name: "date.truncate".to_string(),
+                args: vec![
+                    ValueExpression::Literal(PropertyValue::String("month".to_string())),
+                    ValueExpression::Property(PropertyRef::new("e", "at")),
+                ],
+            }
+        );
+        assert_eq!(
+            items[1],
+            &crate::temporal::accessor(
+                ValueExpression::Property(PropertyRef::new("e", "at")),
+                "year"
+            )
+        );
+        // `at` is a value, so `at.hour` is a component; `e` a node, so
+        // `e.day` stays a property
+        assert_eq!(
+            items[2],
+            &crate::temporal::accessor(ValueExpression::Variable("at".to_string()), "hour")
+        );
+        assert_eq!(
+            items[3],
+            &ValueExpression::Property(PropertyRef::new("e", "day"))
+        );
+    }
+
     #[test]
     fn test_parse_query_with_in_clause() {
         let query = "MATCH (src:Entity)-[rel:RELATIONSHIP]->(dst:Entity) WHERE rel.relationship_type IN ['WORKS_FOR', 'PART_OF'] RETURN src.name";
@@ -1279,6 +2652,17 @@ mod tests {
         assert!(result.order_by.is_some());
     }
 
+    #[test]
+    fn test_parse_query_include_deleted() {
+        let result = parse_cypher_query("MATCH (n:Person) RETURN n.name").unwrap();
+        assert!(!result.include_deleted);
+
+        let query = "MATCH (n:Person) RETURN n.name LIMIT 10 include deleted";
+        let result = parse_cypher_query(query).unwrap();
+        assert!(result.include_deleted);
+        assert_eq!(result.limit, Some(10));
+    }
+
     #[test]
     fn test_parse_count_star() {
         let query = "MATCH (n:Person) RETURN count(*) AS total";
@@ -1520,6 +2904,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_regex_match() {
+        let query = parse_cypher_query(
+            "MATCH (n:Person) WHERE n.name =~ 'A.*' AND n.age = 3 RETURN n.name",
+        )
+        .unwrap();
+        let BooleanExpression::And(left, right) = &query.where_clause.unwrap().expression else {
+            panic!("Expected AND expression");
+        };
+        assert_eq!(
+            **left,
+            BooleanExpression::RegexMatch {
+                expression: ValueExpression::Property(PropertyRef::new("n", "name")),
+                pattern: "A.*".to_string(),
+            }
+        );
+        assert!(matches!(**right, BooleanExpression::Comparison { .. }));
+    }
+
     #[test]
     fn test_parse_contains_case_insensitive_keyword() {
         let query = "MATCH (n:Person) WHERE n.name contains 'test' RETURN n.name";
@@ -1748,6 +3151,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_with_distinct() {
+        let ast = parse_cypher_query(
+            "MATCH (a:Person)-[:KNOWS]->(b:Person) WITH DISTINCT b.city AS city RETURN city",
+        )
+        .unwrap();
+        let with_clause = ast.with_clause.unwrap();
+        assert!(with_clause.distinct);
+        assert_eq!(with_clause.items[0].alias.as_deref(), Some("city"));
+
+        // A projected variable starting with "distinct" is not DISTINCT
+        let ast = parse_cypher_query(
+            "MATCH (n:Person) WITH n.name AS distinct_name RETURN distinct_name",
+        )
+        .unwrap();
+        assert!(!ast.with_clause.unwrap().distinct);
+        let ast = parse_cypher_query(
+            "MATCH (distinctive:Person) WITH distinctive.name AS name RETURN name",
+        )
+        .unwrap();
+        let with_clause = ast.with_clause.unwrap();
+        assert!(!with_clause.distinct);
+        assert_eq!(
+            with_clause.items[0].expression,
+            ValueExpression::Property(PropertyRef::new("distinctive", "name"))
+        );
+    }
+
+    #[test]
+    fn test_parse_return_distinct_on() {
+        let ast = parse_cypher_query(
+            "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN DISTINCT ON (b, a.city) b.name",
+        )
+        .unwrap();
+        assert!(!ast.return_clause.distinct);
+        assert_eq!(
+            ast.return_clause.distinct_on,
+            vec![
+                ValueExpression::Variable("b".to_string()),
+                ValueExpression::Property(PropertyRef {
+                    variable: "a".to_string(),
+                    property: "city".to_string(),
+                }),
+            ]
+        );
+
+        // A returned variable starting with "on" is not DISTINCT ON
+        let ast = parse_cypher_query("MATCH (one:Person) RETURN DISTINCT one.name").unwrap();
+        assert!(ast.return_clause.distinct);
+        assert!(ast.return_clause.distinct_on.is_empty());
+    }
+
+    #[test]
+    fn test_weighted_vector_functions_in_order_by() {
+        let query = "MATCH (n:Doc) RETURN n.title \
+                     ORDER BY 0.7 * vector_similarity(n.title_vec, $q) \
+                     + 0.3 * vector_similarity(n.body_vec, $q) DESC LIMIT 10";
+        let ast = parse_cypher_query(query).unwrap();
+        let order_by = ast.order_by.expect("Expected ORDER BY clause");
+
+        // Multiplication binds tighter than addition
+        let ValueExpression::Arithmetic {
+            left,
+            operator: ArithmeticOperator::Add,
+            right,
+        } = &order_by.items[0].expression
+        else {
+            panic!("Expected a sum, got {:?}", order_by.items[0].expression);
+        };
+        for (side, column) in [(left, "title_vec"), (right, "body_vec")] {
+            match side.as_ref() {
+                ValueExpression::Arithmetic {
+                    left,
+                    operator: ArithmeticOperator::Multiply,
+                    right,
+                } => {
+                    assert!(matches!(
+                        left.as_ref(),
+                        ValueExpression::Literal(PropertyValue::Float(_))
+                    ));
+                    match right.as_ref() {
+                        ValueExpression::VectorSimilarity {
+                            left: property,
+                            metric,
+                            ..
+                        } => {
+                            // The metric defaults to cosine when omitted
+                            assert_eq!(*metric, DistanceMetric::Cosine);
+                            assert!(matches!(
+                                property.as_ref(),
+                                ValueExpression::Property(p) if p.property == column
+                            ));
+                        }
+                        other => panic!("Expected VectorSimilarity, got {:?}", other),
+                    }
+                }
+                other => panic!("Expected a product, got {:?}", other),
+            }
+        }
+        assert_eq!(order_by.items[0].direction, SortDirection::Descending);
+    }
+
+    #[test]
+    fn test_arithmetic_is_left_associative() {
+        let ast = parse_cypher_query("MATCH (n:Person) RETURN n.age - 1 - 2 AS x").unwrap();
+        let ValueExpression::Arithmetic {
+            left,
+            operator: ArithmeticOperator::Subtract,
+            right,
+        } = &ast.return_clause.items[0].expression
+        else {
+            panic!("Expected a difference");
+        };
+        assert!(matches!(
+            left.as_ref(),
+            ValueExpression::Arithmetic {
+                operator: ArithmeticOperator::Subtract,
+                ..
+            }
+        ));
+        assert_eq!(**right, ValueExpression::Literal(PropertyValue::Integer(2)));
+    }
+
     #[test]
     fn test_hybrid_query_with_vector_and_property_filters() {
         let query = "MATCH (p:Person) WHERE p.age > 25 AND vector_similarity(p.embedding, $query_vec, cosine) > 0.7 RETURN p.name";
@@ -1878,4 +3404,721 @@ mod tests {
         let ast = parse_cypher_query(query);
         assert!(ast.is_ok(), "Failed to parse UNWIND after MATCH");
     }
+
+    #[test]
+    fn test_parse_filter_expression() {
+        let expr = parse_filter_expression("active = true AND age >= 18").unwrap();
+        assert!(matches!(expr, BooleanExpression::And(_, _)));
+
+        assert!(parse_filter_expression("active = true RETURN x").is_err());
+    }
+
+    #[test]
+    fn test_parse_exists_function() {
+        let query = "MATCH (n:Person) WHERE exists(n.email) RETURN n.name";
+        let result = parse_cypher_query(query).unwrap();
+        match result.where_clause.unwrap().expression {
+            BooleanExpression::Exists(prop) => {
+                assert_eq!(prop.variable, "n");
+                assert_eq!(prop.property, "email");
+            }
+            other => panic!("Expected Exists expression, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_exists_subquery() {
+        let query = "MATCH (p:Person) \
+                     WHERE NOT EXISTS { MATCH (p)-[:KNOWS]->(f:Person) WHERE f.age > $min } \
+                     AND exists { (p)-[:LIVES_IN]->(:City), (p)-[:WORKS_AT]->(:Company) } \
+                     RETURN p.name";
+        let result = parse_cypher_query(query).unwrap();
+        let BooleanExpression::And(left, right) = result.where_clause.unwrap().expression else {
+            panic!("Expected a conjunction");
+        };
+        let BooleanExpression::Not(inner) = *left else {
+            panic!("Expected NOT EXISTS");
+        };
+        match *inner {
+            BooleanExpression::ExistsSubquery {
+                patterns,
+                where_clause,
+            } => {
+                assert_eq!(patterns.len(), 1);
+                assert!(matches!(
+                    where_clause.as_deref(),
+                    Some(BooleanExpression::Comparison {
+                        right: ValueExpression::Parameter(name),
+                        ..
+                    }) if name == "min"
+                ));
+            }
+            other => panic!("Expected EXISTS subquery, got {:?}", other),
+        }
+        match *right {
+            BooleanExpression::ExistsSubquery {
+                patterns,
+                where_clause,
+            } => {
+                assert_eq!(patterns.len(), 2);
+                assert!(where_clause.is_none());
+            }
+            other => panic!("Expected EXISTS subquery, got {:?}", other),
+        }
+
+        // The property-existence function is unaffected
+        let result = parse_cypher_query("MATCH (n) WHERE exists(n.email) RETURN n").unwrap();
+        assert!(matches!(
+            result.where_clause.unwrap().expression,
+            BooleanExpression::Exists(_)
+        ));
+        assert!(parse_cypher_query("MATCH (n) WHERE EXISTS { } RETURN n").is_err());
+    }
+
+    #[test]
+    fn test_parse_count_subquery() {
+        let query = "MATCH (n:Person) \
+                     WHERE COUNT { (n)-[:FOLLOWS]->() } > 5 \
+                     RETURN n.name, count { MATCH (n)-[:KNOWS]->(f) WHERE f.age > 30 } AS friends";
+        let result = parse_cypher_query(query).unwrap();
+        let BooleanExpression::Comparison { left, right, .. } =
+            result.where_clause.unwrap().expression
+        else {
+            panic!("Expected a comparison");
+        };
+        assert!(matches!(
+            left,
+            ValueExpression::CountSubquery { ref patterns, where_clause: None } if patterns.len() == 1
+        ));
+        assert_eq!(right, ValueExpression::Literal(PropertyValue::Integer(5)));
+        assert!(matches!(
+            &result.return_clause.items[1].expression,
+            ValueExpression::CountSubquery {
+                where_clause: Some(_),
+                ..
+            }
+        ));
+
+        // The aggregate function is unaffected
+        let result = parse_cypher_query("MATCH (n) RETURN count(n)").unwrap();
+        assert!(matches!(
+            result.return_clause.items[0].expression,
+            ValueExpression::AggregateFunction { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_shortest_path_patterns() {
+        let shortest = |query: &str| {
+            let result = parse_cypher_query(query).unwrap();
+            let ReadingClause::Match(match_clause) = &result.reading_clauses[0] else {
+                panic!("Expected match clause");
+            };
+            let GraphPattern::Path(path) = &match_clause.patterns[0] else {
+                panic!("Expected path pattern");
+            };
+            (path.variable.clone(), path.shortest, path.segments.len())
+        };
+
+        assert_eq!(
+            shortest("MATCH p = shortestPath((a:Person)-[:KNOWS*]-(b:Person)) RETURN p"),
+            (Some("p".to_string()), Some(ShortestPathKind::Single), 1)
+        );
+        assert_eq!(
+            shortest("MATCH p = allShortestPaths( (a)-[:KNOWS*..5]->(b) ) RETURN length(p)"),
+            (Some("p".to_string()), Some(ShortestPathKind::All), 1)
+        );
+        assert_eq!(
+            shortest("MATCH SHORTESTPATH((a)-[:KNOWS*]->(b)) RETURN b"),
+            (None, Some(ShortestPathKind::Single), 1)
+        );
+        assert_eq!(
+            shortest("MATCH p = (a)-[:KNOWS*]->(b) RETURN b"),
+            (Some("p".to_string()), None, 1)
+        );
+        assert!(parse_cypher_query("MATCH p = shortestPath((a)-[:KNOWS*]->(b) RETURN p").is_err());
+    }
+
+    #[test]
+    fn test_parse_create_clause() {
+        let result =
+            parse_cypher_query("CREATE (n:Person {name: $name})-[:KNOWS]->(m:Person {id: 2})")
+                .unwrap();
+        assert!(result.reading_clauses.is_empty());
+        assert!(result.return_clause.items.is_empty());
+        let create = result.create_clause.unwrap();
+        assert_eq!(create.patterns.len(), 1);
+        let GraphPattern::Path(path) = &create.patterns[0] else {
+            panic!("Expected path pattern");
+        };
+        assert_eq!(
+            path.start_node.properties.get("name"),
+            Some(&PropertyValue::Parameter("name".to_string()))
+        );
+        assert_eq!(path.segments[0].relationship.types, vec!["KNOWS"]);
+
+        let result = parse_cypher_query(
+            "MATCH (m:Person) WHERE m.name = 'Bob' CREATE (n:Person {id: 3}), (n)-[:KNOWS]->(m)",
+        )
+        .unwrap();
+        assert_eq!(result.reading_clauses.len(), 1);
+        assert!(result.where_clause.is_some());
+        assert_eq!(result.create_clause.unwrap().patterns.len(), 2);
+
+        assert!(parse_cypher_query("CREATE (n:Person {id: 1}) RETURN n.id").is_err());
+    }
+
+    #[test]
+    fn test_parse_merge_clause() {
+        let result = parse_cypher_query(
+            "MATCH (a:Person {id: 1}), (b:Person {id: 2}) MERGE (a)-[:KNOWS]->(b)",
+        )
+        .unwrap();
+        assert_eq!(result.reading_clauses.len(), 1);
+        assert!(result.create_clause.is_none());
+        let GraphPattern::Path(path) = &result.merge_clause.unwrap().pattern else {
+            panic!("Expected path pattern");
+        };
+        assert_eq!(path.start_node.variable.as_deref(), Some("a"));
+        assert_eq!(path.segments[0].end_node.variable.as_deref(), Some("b"));
+
+        let result = parse_cypher_query("merge (n:Person {id: $id})").unwrap();
+        assert!(matches!(
+            result.merge_clause.unwrap().pattern,
+            GraphPattern::Node(_)
+        ));
+
+        // MERGE takes a single pattern
+        assert!(parse_cypher_query("MERGE (n:Person {id: 1}), (m:Person {id: 2})").is_err());
+    }
+
+    #[test]
+    fn test_parse_set_clause() {
+        let result = parse_cypher_query(
+            "MATCH (n:Person) WHERE n.id = 1 SET n.age = 30, n += $changes, n:Employee, m += {name: 'x'}",
+        )
+        .unwrap();
+        assert!(result.where_clause.is_some());
+        let items = result.set_clause.unwrap().items;
+        assert_eq!(
+            items[0],
+            SetItem::Property {
+                target: PropertyRef {
+                    variable: "n".to_string(),
+                    property: "age".to_string(),
+                },
+                value: PropertyValue::Integer(30),
+            }
+        );
+        assert_eq!(
+            items[1],
+            SetItem::Properties {
+                variable: "n".to_string(),
+                properties: PropertyMap::Parameter("changes".to_string()),
+            }
+        );
+        assert_eq!(
+            items[2],
+            SetItem::Labels {
+                variable: "n".to_string(),
+                labels: vec!["Employee".to_string()],
+            }
+        );
+        assert_eq!(items[3].variable(), "m");
+
+        assert!(parse_cypher_query("MATCH (n:Person) SET n.age = 30 RETURN n").is_err());
+    }
+
+    #[test]
+    fn test_parse_delete_clause() {
+        let result = parse_cypher_query("MATCH (a:Person)-[r:KNOWS]->(b) DELETE r, b").unwrap();
+        assert_eq!(
+            result.delete_clause,
+            Some(DeleteClause {
+                detach: false,
+                variables: vec!["r".to_string(), "b".to_string()],
+            })
+        );
+
+        let result = parse_cypher_query("MATCH (n:Person) WHERE n.id = 1 detach delete n").unwrap();
+        assert!(result.where_clause.is_some());
+        assert!(result.delete_clause.unwrap().detach);
+
+        assert!(parse_cypher_query("MATCH (n:Person) DELETE n RETURN n").is_err());
+    }
+
+    #[test]
+    fn test_parse_remove_clause() {
+        let result = parse_cypher_query("MATCH (n:Person) REMOVE n.age, n:Person").unwrap();
+        assert_eq!(
+            result.remove_clause.unwrap().items,
+            vec![
+                RemoveItem::Property {
+                    target: PropertyRef::new("n", "age"),
+                },
+                RemoveItem::Labels {
+                    variable: "n".to_string(),
+                    labels: vec!["Person".to_string()],
+                },
+            ]
+        );
+        assert!(result.return_clause.items.is_empty());
+
+        assert!(parse_cypher_query("MATCH (n:Person) REMOVE n RETURN n").is_err());
+    }
+
+    #[test]
+    fn test_parse_call_subquery() {
+        let query = "MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) \
+                     RETURN f.name AS friend ORDER BY f.age DESC LIMIT 3 } \
+                     RETURN p.name, friend";
+        let result = parse_cypher_query(query).unwrap();
+        assert_eq!(result.reading_clauses.len(), 1);
+        assert_eq!(result.return_clause.items.len(), 2);
+        let call = result.call_subquery.unwrap();
+        assert_eq!(call.imports, vec!["p".to_string()]);
+        assert_eq!(call.query.reading_clauses.len(), 1);
+        assert_eq!(
+            call.query.return_clause.items[0].alias.as_deref(),
+            Some("friend")
+        );
+        assert_eq!(call.query.limit, Some(3));
+
+        // Without imports the subquery is uncorrelated; a projecting WITH is
+        // part of the subquery
+        let result = parse_cypher_query(
+            "CALL { WITH 1 AS one MATCH (n:Person) RETURN count(n) AS total } RETURN total",
+        )
+        .unwrap();
+        let call = result.call_subquery.unwrap();
+        assert!(call.imports.is_empty());
+        assert!(call.query.with_clause.is_some());
+
+        assert!(parse_cypher_query("MATCH (p:Person) CALL { WITH p RETURN p.name AS n }").is_err());
+    }
+
+    #[test]
+    fn test_parse_case_expressions() {
+        let query = "MATCH (p:Person) \
+                     RETURN CASE WHEN p.age < 30 THEN 'young' WHEN p.age < 60 THEN 'adult' ELSE 'senior' END AS bucket, \
+                     case p.city when 'Paris' then 1 end AS parisian";
+        let result = parse_cypher_query(query).unwrap();
+        let items = &result.return_clause.items;
+        assert_eq!(items.len(), 2);
+
+        let ValueExpression::Case { branches, default } = &items[0].expression else {
+            panic!("Expected CASE, got {:?}", items[0].expression);
+        };
+        assert_eq!(branches.len(), 2);
+        assert!(matches!(
+            branches[0].condition,
+            BooleanExpression::Comparison {
+                operator: ComparisonOperator::LessThan,
+                ..
+            }
+        ));
+        assert_eq!(
+            default.as_deref(),
+            Some(&ValueExpression::Literal(PropertyValue::String(
+                "senior".to_string()
+            )))
+        );
+        assert_eq!(items[0].alias.as_deref(), Some("bucket"));
+
+        // The simple form compares the operand to each WHEN value
+        let ValueExpression::Case { branches, default } = &items[1].expression else {
+            panic!("Expected CASE, got {:?}", items[1].expression);
+        };
+        assert_eq!(
+            branches[0].condition,
+            BooleanExpression::Comparison {
+                left: ValueExpression::Property(PropertyRef::new("p", "city")),
+                operator: ComparisonOperator::Equal,
+                right: ValueExpression::Literal(PropertyValue::String("Paris".to_string())),
+            }
+        );
+        assert!(default.is_none());
+
+        // CASE in WHERE and ORDER BY
+        let result = parse_cypher_query(
+            "MATCH (p:Person) WHERE CASE WHEN p.age IS NULL THEN 0 ELSE p.age END > 18 \
+             RETURN p.name ORDER BY CASE p.name WHEN 'Bob' THEN 0 ELSE 1 END, p.name",
+        )
+        .unwrap();
+        assert!(result.where_clause.is_some());
+        assert_eq!(result.order_by.unwrap().items.len(), 2);
+
+        assert!(parse_cypher_query("MATCH (p:Person) RETURN CASE WHEN p.age < 30 THEN 1").is_err());
+        assert!(parse_cypher_query("MATCH (p:Person) RETURN CASE ELSE 1 END").is_err());
+    }
+
+    #[test]
+    fn test_parse_list_comprehension() {
+        let result = parse_cypher_query(
+            "MATCH (p:Person) WITH p.city AS city, collect(p.age) AS ages \
+             RETURN city, [a IN ages WHERE a > 30 | a * 2] AS doubled, [a IN ages] AS copied",
+        )
+        .unwrap();
+        let items = &result.return_clause.items;
+        assert_eq!(
+            items[1].expression,
+            ValueExpression::ListComprehension {
+                variable: "a".to_string(),
+                list: Box::new(ValueExpression::Variable("ages".to_string())),
+                predicate: Some(Box::new(BooleanExpression::Comparison {
+                    left: ValueExpression::Variable("a".to_string()),
+                    operator: ComparisonOperator::GreaterThan,
+                    right: ValueExpression::Literal(PropertyValue::Integer(30)),
+                })),
+                projection: Some(Box::new(ValueExpression::Arithmetic {
+                    left: Box::new(ValueExpression::Variable("a".to_string())),
+                    operator: ArithmeticOperator::Multiply,
+                    right: Box::new(ValueExpression::Literal(PropertyValue::Integer(2))),
+                })),
+            }
+        );
+        let ValueExpression::ListComprehension {
+            predicate,
+            projection,
+            ..
+        } = &items[2].expression
+        else {
+            panic!("Expected list comprehension, got {:?}", items[2].expression);
+        };
+        assert!(predicate.is_none() && projection.is_none());
+
+        // Vector literals are unaffected
+        let result = parse_cypher_query("UNWIND [x IN [1, 2, 3] | x * 10] AS y RETURN y").unwrap();
+        let ReadingClause::Unwind(unwind) = &result.reading_clauses[0] else {
+            panic!("Expected UNWIND clause");
+        };
+        let ValueExpression::ListComprehension { list, .. } = &unwind.expression else {
+            panic!("Expected list comprehension, got {:?}", unwind.expression);
+        };
+        assert_eq!(**list, ValueExpression::VectorLiteral(vec![1.0, 2.0, 3.0]));
+
+        assert!(parse_cypher_query("MATCH (p:Person) RETURN [a IN p.tags WHERE | a]").is_err());
+    }
+
+    #[test]
+    fn test_parse_reduce() {
+        let result = parse_cypher_query(
+            "MATCH (p:Person) RETURN REDUCE(total = 0, s IN p.scores | total + s)",
+        )
+        .unwrap();
+        assert_eq!(
+            result.return_clause.items[0].expression,
+            ValueExpression::Reduce {
+                accumulator: "total".to_string(),
+                initial: Box::new(ValueExpression::Literal(PropertyValue::Integer(0))),
+                variable: "s".to_string(),
+                list: Box::new(ValueExpression::Property(PropertyRef::new("p", "scores"))),
+                expression: Box::new(ValueExpression::Arithmetic {
+                    left: Box::new(ValueExpression::Variable("total".to_string())),
+                    operator: ArithmeticOperator::Add,
+                    right: Box::new(ValueExpression::Variable("s".to_string())),
+                }),
+            }
+        );
+
+        assert!(
+            parse_cypher_query("MATCH (p:Person) RETURN reduce(total, s IN p.scores | s)").is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_comprehension() {
+        let result = parse_cypher_query(
+            "MATCH (p:Person) \
+             RETURN p.name, [(p)-[:KNOWS]->(f:Person) WHERE f.age > 30 | f.name] AS friends",
+        )
+        .unwrap();
+        let ValueExpression::PatternComprehension {
+            pattern,
+            predicate,
+            projection,
+        } = &result.return_clause.items[1].expression
+        else {
+            panic!(
+                "Expected pattern comprehension, got {:?}",
+                result.return_clause.items[1].expression
+            );
+        };
+        assert_eq!(pattern.start_node.variable.as_deref(), Some("p"));
+        assert_eq!(pattern.segments.len(), 1);
+        assert_eq!(pattern.segments[0].relationship.types, vec!["KNOWS"]);
+        assert_eq!(pattern.segments[0].end_node.labels, vec!["Person"]);
+        assert!(predicate.is_some());
+        assert_eq!(
+            **projection,
+            ValueExpression::Property(PropertyRef::new("f", "name"))
+        );
+
+        // The projection is required, and a lone node is not a pattern
+        assert!(parse_cypher_query("MATCH (p:Person) RETURN [(p)-[:KNOWS]->(f)] AS x").is_err());
+        assert!(parse_cypher_query("MATCH (p:Person) RETURN [(p) | p.name] AS x").is_err());
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let query = parse_cypher_query(
+            "// People over 30\n\
+             MATCH (p:Person) /* adults\n only */ WHERE p.age > 30 // inline\n\
+             RETURN p.name, 'a // b' AS text, \"/* c */\" AS other",
+        )
+        .unwrap();
+        assert_eq!(query.return_clause.items.len(), 3);
+        assert_eq!(
+            query.return_clause.items[1].expression,
+            ValueExpression::Literal(PropertyValue::String("a // b".to_string()))
+        );
+        assert_eq!(
+            query.return_clause.items[2].expression,
+            ValueExpression::Literal(PropertyValue::String("/* c */".to_string()))
+        );
+
+        // Positions of errors are positions in the text with its comments
+        let err = parse_cypher_query("MATCH (n) /* open RETURN n").unwrap_err();
+        assert!(
+            matches!(err, GraphError::ParseError { position: 10, .. }),
+            "{}",
+            err
+        );
+        let diagnostics =
+            parse_cypher_query_with_diagnostics("/* é */ MATCH (n) RETRUN n").unwrap_err();
+        assert_eq!(diagnostics[0].line, 1);
+    }
+
+    #[test]
+    fn test_parse_map_projection() {
+        let query =
+            parse_cypher_query("MATCH (n:Person) RETURN n {.name, .age, score: n.age * 2, n} AS m")
+                .unwrap();
+        let ValueExpression::MapProjection { variable, items } =
+            &query.return_clause.items[0].expression
+        else {
+            panic!("expected a map projection");
+        };
+        assert_eq!(variable, "n");
+        let keys: Vec<&str> = items.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, vec!["name", "age", "score", "n"]);
+        assert_eq!(
+            items[0].value,
+            ValueExpression::Property(PropertyRef::new("n", "name"))
+        );
+        assert!(matches!(items[2].value, ValueExpression::Arithmetic { .. }));
+        assert_eq!(items[3].value, ValueExpression::Variable("n".to_string()));
+        assert_eq!(query.return_clause.items[0].alias.as_deref(), Some("m"));
+
+        // A map projection needs at least one item
+        assert!(parse_cypher_query("MATCH (n:Person) RETURN n {}").is_err());
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let script = "CREATE (a:Person {name: 'x;y'});\n\
+                      // only a comment;\n\
+                      ;\n\
+                      MATCH (n) /* ; */ RETURN n";
+        let statements = split_statements(script).unwrap();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], (0, "CREATE (a:Person {name: 'x;y'})"));
+        assert!(statements[1]
+            .1
+            .trim_start()
+            .starts_with("MATCH (n) /* ; */"));
+        assert_eq!(&script[statements[1].0..], statements[1].1);
+
+        assert!(split_statements("   ;  // nothing").unwrap().is_empty());
+        assert!(split_statements("MATCH (n) RETURN n; /* open").is_err());
+    }
+
+    #[test]
+    fn test_parse_list_predicates() {
+        let result =
+            parse_cypher_query("MATCH (n:Person) WHERE any(x IN n.tags WHERE x = 'rust') RETURN n")
+                .unwrap();
+        assert_eq!(
+            result.where_clause.unwrap().expression,
+            BooleanExpression::ListPredicate {
+                quantifier: ListQuantifier::Any,
+                variable: "x".to_string(),
+                list: ValueExpression::Property(PropertyRef::new("n", "tags")),
+                predicate: Box::new(BooleanExpression::Comparison {
+                    left: ValueExpression::Variable("x".to_string()),
+                    operator: ComparisonOperator::Equal,
+                    right: ValueExpression::Literal(PropertyValue::String("rust".to_string())),
+                }),
+            }
+        );
+
+        for (function, quantifier) in [
+            ("ALL", ListQuantifier::All),
+            ("none", ListQuantifier::None),
+            ("single", ListQuantifier::Single),
+        ] {
+            let query = format!(
+                "MATCH (n:Person) WHERE {}(s IN n.scores WHERE s > 3) RETURN n",
+                function
+            );
+            let result = parse_cypher_query(&query).unwrap();
+            let BooleanExpression::ListPredicate { quantifier: q, .. } =
+                result.where_clause.unwrap().expression
+            else {
+                panic!("Expected list predicate for {}", function);
+            };
+            assert_eq!(q, quantifier);
+        }
+    }
+
+    #[test]
+    fn test_parse_path_variable_and_all_predicate() {
+        let query = "MATCH p = (a:Person)-[:KNOWS*1..3 {active: true}]->(b:Person) \
+                     WHERE all(x IN nodes(p) WHERE x.type <> 'bot') RETURN b.name";
+        let result = parse_cypher_query(query).unwrap();
+
+        let ReadingClause::Match(match_clause) = &result.reading_clauses[0] else {
+            panic!("Expected match clause");
+        };
+        let GraphPattern::Path(path) = &match_clause.patterns[0] else {
+            panic!("Expected path pattern");
+        };
+        assert_eq!(path.variable.as_deref(), Some("p"));
+        assert_eq!(
+            path.segments[0].relationship.properties.get("active"),
+            Some(&PropertyValue::Boolean(true))
+        );
+
+        match result.where_clause.unwrap().expression {
+            BooleanExpression::AllInPath {
+                variable,
+                path,
+                elements,
+                predicate,
+            } => {
+                assert_eq!(variable, "x");
+                assert_eq!(path, "p");
+                assert_eq!(elements, PathElements::Nodes);
+                assert!(matches!(
+                    *predicate,
+                    BooleanExpression::Comparison {
+                        operator: ComparisonOperator::NotEqual,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("Expected AllInPath expression, got {:?}", other),
+        }
+
+        let result = parse_cypher_query(
+            "MATCH p = (a)-[:KNOWS*1..2]->(b) WHERE all(r IN relationships(p) WHERE r.weight > 1) RETURN b",
+        )
+        .unwrap();
+        assert!(matches!(
+            result.where_clause.unwrap().expression,
+            BooleanExpression::AllInPath {
+                elements: PathElements::Relationships,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_optional_match_with_where() {
+        let query = "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) WHERE b.age > 30 \
+                     RETURN a.name, b.name";
+        let result = parse_cypher_query(query).unwrap();
+
+        assert_eq!(result.reading_clauses.len(), 2);
+        let ReadingClause::Match(first) = &result.reading_clauses[0] else {
+            panic!("Expected match clause");
+        };
+        assert!(!first.optional);
+        let ReadingClause::Match(optional) = &result.reading_clauses[1] else {
+            panic!("Expected match clause");
+        };
+        assert!(optional.optional);
+        assert!(matches!(
+            optional.where_clause.as_ref().unwrap().expression,
+            BooleanExpression::Comparison {
+                operator: ComparisonOperator::GreaterThan,
+                ..
+            }
+        ));
+        // The WHERE belongs to the OPTIONAL MATCH, not to the query
+        assert!(result.where_clause.is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_valid_query() {
+        let query = parse_cypher_query_with_diagnostics(
+            "MATCH (n:Person) WHERE n.name STARTS WITH 'A' RETURN n.name",
+        )
+        .unwrap();
+        assert!(query.where_clause.is_some());
+    }
+
+    #[test]
+    fn test_diagnostics_report_multiple_errors() {
+        let input = "MATCH (n:Person WHERE n.age > RETURN n.name LIMIT abc";
+        let diagnostics = parse_cypher_query_with_diagnostics(input).unwrap_err();
+
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert!(
+            diagnostics.len() >= 3,
+            "expected several diagnostics: {:?}",
+            messages
+        );
+        assert!(messages.iter().any(|m| m.contains("Unclosed '('")));
+        assert!(messages.iter().any(|m| m.contains("Invalid LIMIT clause")));
+        // Diagnostics are ordered by position
+        assert!(diagnostics
+            .windows(2)
+            .all(|w| w[0].span.start <= w[1].span.start));
+    }
+
+    #[test]
+    fn test_diagnostics_line_and_column() {
+        let input = "MATCH (n:Person)\nWHERE n.age >\nRETURN n.name";
+        let diagnostics = parse_cypher_query_with_diagnostics(input).unwrap_err();
+        let where_error = diagnostics
+            .iter()
+            .find(|d| d.message.contains("WHERE"))
+            .expect("WHERE diagnostic");
+        assert_eq!(where_error.line, 2);
+    }
+
+    #[test]
+    fn test_diagnostics_check_writing_clauses() {
+        let input = "MATCH (n:Person) WHERE n.id = 1 SET n.age = CREATE (m:Person {id: })";
+        let diagnostics = parse_cypher_query_with_diagnostics(input).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.contains("Invalid SET clause")));
+        assert!(messages.iter().any(|m| m.contains("Invalid CREATE clause")));
+        // Writing queries end without RETURN
+        assert!(
+            !messages.iter().any(|m| m.contains("RETURN")),
+            "{:?}",
+            messages
+        );
+
+        let input = "MATCH (n:Person) DETACH DELETE n, RETURN n";
+        let diagnostics = parse_cypher_query_with_diagnostics(input).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("Invalid DETACH DELETE clause")),
+            "{:?}",
+            messages
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_missing_return_and_unterminated_string() {
+        let input = "MATCH (n:Person) WHERE n.name = 'Alice";
+        let diagnostics = parse_cypher_query_with_diagnostics(input).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.contains("Unterminated string")));
+    }
 }
@@ -0,0 +1,561 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Nested execution of pattern comprehensions and COUNT subqueries
+//!
+//! `MATCH (p:Person) RETURN p.name, [(p)-[:KNOWS]->(f:Person) WHERE f.age > 30 | f.name]
+//! AS friends` lists the names of every person's friends older than 30
+//! without a second MATCH and `collect()`. Each comprehension becomes a
+//! correlated subquery collecting its projection,
+//!
+//! ```text
+//! MATCH (p)-[:KNOWS]->(f:Person) WHERE f.age > 30 RETURN collect(f.name) AS patterncomprehension0
+//! ```
+//!
+//! which imports the nodes its pattern shares with the outer MATCH and runs
+//! like a `CALL { ... }` subquery (see [`crate::call_subquery`]): once per
+//! distinct combination of imported nodes, with RETURN planned over the outer
+//! rows and the collected lists. A row whose pattern matches nothing gets an
+//! empty list.
+//!
+//! A `COUNT { (p)-[:FOLLOWS]->() WHERE ... }` subquery runs the same way,
+//! returning `count(*)` instead of a list, and is 0 for a row without any
+//! match. COUNT subqueries can also filter: the conjuncts of WHERE that read
+//! one are applied to the outer rows joined with the counts, the others
+//! before the subqueries run.
+//!
+//! Both are evaluated in WHERE, RETURN and ORDER BY of queries without WITH
+//! or CALL. Their predicates read the variables of their patterns.
+//!
+//! ```ignore
+//! let query = CypherQuery::new(
+//!     "MATCH (p:Person) WHERE COUNT { (p)-[:FOLLOWS]->() } > 5 \
+//!      RETURN p.name, [(p)-[:KNOWS]->(f:Person) | f.name] AS friends",
+//! )?
+//! .with_config(config);
+//! let friends = query.execute(datasets, None).await?;
+//! ```
+
+use crate::ast::{
+    BooleanExpression, CypherQuery as CypherAST, GraphPattern, MatchClause, ReadingClause,
+    ReturnClause, ReturnItem, ValueExpression, WhereClause,
+};
+use crate::call_subquery::Correlated;
+use crate::datafusion_planner::expression::to_cypher_column_name;
+use crate::error::{GraphError, Result};
+use crate::logical_plan::split_conjuncts;
+
+/// Prefixes of the values the subqueries of pattern comprehensions and COUNT
+/// subqueries return
+const VALUE_PREFIX: &str = "patterncomprehension";
+const COUNT_PREFIX: &str = "countsubquery";
+
+/// A query with its pattern comprehensions and COUNT subqueries replaced by
+/// the values of correlated subqueries
+#[derive(Debug)]
+pub(crate) struct Rewritten {
+    pub ast: CypherAST,
+    pub subqueries: Vec<Correlated>,
+    /// Conjuncts of WHERE applied once the subqueries' values are known
+    pub filter: Option<BooleanExpression>,
+}
+
+/// Error for a query with pattern comprehensions or COUNT subqueries planned
+/// as a single plan (EXPLAIN, SQL, streaming, the simple executor)
+pub(crate) fn check_none(ast: &CypherAST) -> Result<()> {
+    if !contains_nested(ast) {
+        return Ok(());
+    }
+    Err(GraphError::UnsupportedFeature {
+        feature: "pattern comprehensions and COUNT subqueries in a single plan; they run once \
+                  per outer row through execute()"
+            .to_string(),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Whether `ast` holds a pattern comprehension or COUNT subquery
+pub(crate) fn contains_nested(ast: &CypherAST) -> bool {
+    let match_conditions = ast
+        .reading_clauses
+        .iter()
+        .chain(&ast.post_with_reading_clauses)
+        .filter_map(|clause| match clause {
+            ReadingClause::Match(match_clause) => match_clause.where_clause.as_ref(),
+            ReadingClause::Unwind(_) | ReadingClause::TableFunction(_) => None,
+        });
+    let values_contain = ast
+        .return_clause
+        .items
+        .iter()
+        .map(|item| &item.expression)
+        .chain(&ast.return_clause.distinct_on)
+        .chain(
+            ast.order_by
+                .iter()
+                .flat_map(|order_by| order_by.items.iter().map(|item| &item.expression)),
+        )
+        .any(value_contains);
+    values_contain
+        || [&ast.where_clause, &ast.post_with_where_clause]
+            .into_iter()
+            .flatten()
+            .chain(match_conditions)
+            .any(|where_clause| condition_contains(&where_clause.expression))
+}
+
+fn value_contains(expr: &ValueExpression) -> bool {
+    match expr {
+        ValueExpression::PatternComprehension { .. } | ValueExpression::CountSubquery { .. } => {
+            true
+        }
+        ValueExpression::ScalarFunction { args, .. }
+        | ValueExpression::AggregateFunction { args, .. } => args.iter().any(value_contains),
+        ValueExpression::Arithmetic { left, right, .. }
+        | ValueExpression::VectorDistance { left, right, .. }
+        | ValueExpression::VectorSimilarity { left, right, .. } => {
+            value_contains(left) || value_contains(right)
+        }
+        ValueExpression::Case { branches, default } => {
+            branches.iter().any(|branch| {
+                condition_contains(&branch.condition) || value_contains(&branch.value)
+            }) || default.as_deref().is_some_and(value_contains)
+        }
+        ValueExpression::ListComprehension {
+            list,
+            predicate,
+            projection,
+            ..
+        } => {
+            value_contains(list)
+                || predicate.as_deref().is_some_and(condition_contains)
+                || projection.as_deref().is_some_and(value_contains)
+        }
+        ValueExpression::Reduce {
+            initial,
+            list,
+            expression,
+            ..
+        } => value_contains(initial) || value_contains(list) || value_contains(expression),
+        ValueExpression::MapProjection { items, .. } => {
+            items.iter().any(|item| value_contains(&item.value))
+        }
+        ValueExpression::Variable(_)
+        | ValueExpression::Property(_)
+        | ValueExpression::Literal(_)
+        | ValueExpression::Parameter(_)
+        | ValueExpression::VectorLiteral(_) => false,
+    }
+}
+
+fn condition_contains(expr: &BooleanExpression) -> bool {
+    match expr {
+        BooleanExpression::Comparison { left, right, .. } => {
+            value_contains(left) || value_contains(right)
+        }
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            condition_contains(left) || condition_contains(right)
+        }
+        BooleanExpression::Not(inner) => condition_contains(inner),
+        BooleanExpression::In { expression, list } => {
+            value_contains(expression) || list.iter().any(value_contains)
+        }
+        BooleanExpression::Like { expression, .. }
+        | BooleanExpression::ILike { expression, .. }
+        | BooleanExpression::Contains { expression, .. }
+        | BooleanExpression::StartsWith { expression, .. }
+        | BooleanExpression::EndsWith { expression, .. }
+        | BooleanExpression::RegexMatch { expression, .. }
+        | BooleanExpression::IsNull(expression)
+        | BooleanExpression::IsNotNull(expression) => value_contains(expression),
+        BooleanExpression::AllInPath { predicate, .. } => condition_contains(predicate),
+        BooleanExpression::ListPredicate {
+            list, predicate, ..
+        } => value_contains(list) || condition_contains(predicate),
+        BooleanExpression::ExistsSubquery { where_clause, .. } => {
+            where_clause.as_deref().is_some_and(condition_contains)
+        }
+        BooleanExpression::Exists(_) => false,
+    }
+}
+
+/// `ast` with its pattern comprehensions and COUNT subqueries replaced by
+/// the values of the correlated subqueries returned alongside, or `None`
+/// without any
+pub(crate) fn rewrite(ast: &CypherAST) -> Result<Option<Rewritten>> {
+    if !contains_nested(ast) {
+        return Ok(None);
+    }
+    if ast.with_clause.is_some() || ast.call_subquery.is_some() {
+        return Err(GraphError::UnsupportedFeature {
+            feature: "pattern comprehensions and COUNT subqueries in queries with WITH or CALL"
+                .to_string(),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+
+    let mut rewritten = ast.clone();
+    let mut extractor = Extractor {
+        outer: ast,
+        subqueries: Vec::new(),
+    };
+    let mut filter = None;
+    if let Some(where_clause) = rewritten.where_clause.take() {
+        // Conjuncts reading a subquery's value wait for it, the rest still
+        // narrows the outer rows the subqueries run for
+        let mut conjuncts = Vec::new();
+        split_conjuncts(&where_clause.expression, &mut conjuncts);
+        let mut remaining = None;
+        for conjunct in conjuncts {
+            if condition_contains(conjunct) {
+                let mut conjunct = conjunct.clone();
+                extractor.extract_condition(&mut conjunct)?;
+                filter = Some(and(filter, conjunct));
+            } else {
+                remaining = Some(and(remaining, conjunct.clone()));
+            }
+        }
+        rewritten.where_clause = remaining.map(|expression| WhereClause { expression });
+    }
+    for item in &mut rewritten.return_clause.items {
+        if item.alias.is_none() && value_contains(&item.expression) {
+            // Keep the column name the expression would have had
+            item.alias = Some(to_cypher_column_name(&item.expression));
+        }
+        extractor.extract(&mut item.expression)?;
+    }
+    for key in &mut rewritten.return_clause.distinct_on {
+        extractor.extract(key)?;
+    }
+    if let Some(order_by) = &mut rewritten.order_by {
+        for item in &mut order_by.items {
+            extractor.extract(&mut item.expression)?;
+        }
+    }
+    if contains_nested(&rewritten) || filter.as_ref().is_some_and(condition_contains) {
+        return Err(GraphError::UnsupportedFeature {
+            feature: "pattern comprehensions and COUNT subqueries inside list comprehensions, \
+                      path predicates, EXISTS subqueries or OPTIONAL MATCH"
+                .to_string(),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(Some(Rewritten {
+        ast: rewritten,
+        subqueries: extractor.subqueries,
+        filter,
+    }))
+}
+
+fn and(left: Option<BooleanExpression>, right: BooleanExpression) -> BooleanExpression {
+    match left {
+        Some(left) => BooleanExpression::And(Box::new(left), Box::new(right)),
+        None => right,
+    }
+}
+
+/// Replaces pattern comprehensions and COUNT subqueries by the values of
+/// their subqueries
+struct Extractor<'a> {
+    outer: &'a CypherAST,
+    subqueries: Vec<Correlated>,
+}
+
+impl Extractor<'_> {
+    fn extract(&mut self, expr: &mut ValueExpression) -> Result<()> {
+        match expr {
+            ValueExpression::PatternComprehension {
+                pattern,
+                predicate,
+                projection,
+            } => {
+                let name = format!("{}{}", VALUE_PREFIX, self.subqueries.len());
+                let collect = ValueExpression::AggregateFunction {
+                    name: "collect".to_string(),
+                    args: vec![projection.as_ref().clone()],
+                    distinct: false,
+                };
+                let patterns = [GraphPattern::Path(pattern.clone())];
+                let subquery = self.subquery(&patterns, predicate.as_deref(), collect, &name)?;
+                self.subqueries.push(subquery);
+                *expr = ValueExpression::Variable(name);
+            }
+            ValueExpression::CountSubquery {
+                patterns,
+                where_clause,
+            } => {
+                let name = format!("{}{}", COUNT_PREFIX, self.subqueries.len());
+                let count = ValueExpression::AggregateFunction {
+                    name: "count".to_string(),
+                    args: vec![ValueExpression::Variable("*".to_string())],
+                    distinct: false,
+                };
+                let subquery = self.subquery(patterns, where_clause.as_deref(), count, &name)?;
+                self.subqueries.push(subquery);
+                *expr = ValueExpression::Variable(name);
+            }
+            ValueExpression::ScalarFunction { args, .. }
+            | ValueExpression::AggregateFunction { args, .. } => {
+                for arg in args {
+                    self.extract(arg)?;
+                }
+            }
+            ValueExpression::Arithmetic { left, right, .. }
+            | ValueExpression::VectorDistance { left, right, .. }
+            | ValueExpression::VectorSimilarity { left, right, .. } => {
+                self.extract(left)?;
+                self.extract(right)?;
+            }
+            ValueExpression::Case { branches, default } => {
+                for branch in branches {
+                    self.extract_condition(&mut branch.condition)?;
+                    self.extract(&mut branch.value)?;
+                }
+                if let Some(default) = default {
+                    self.extract(default)?;
+                }
+            }
+            ValueExpression::ListComprehension { list, .. } => self.extract(list)?,
+            ValueExpression::Reduce { initial, list, .. } => {
+                self.extract(initial)?;
+                self.extract(list)?;
+            }
+            ValueExpression::MapProjection { items, .. } => {
+                for item in items {
+                    self.extract(&mut item.value)?;
+                }
+            }
+            ValueExpression::Variable(_)
+            | ValueExpression::Property(_)
+            | ValueExpression::Literal(_)
+            | ValueExpression::Parameter(_)
+            | ValueExpression::VectorLiteral(_) => {}
+        }
+        Ok(())
+    }
+
+    fn extract_condition(&mut self, expr: &mut BooleanExpression) -> Result<()> {
+        match expr {
+            BooleanExpression::Comparison { left, right, .. } => {
+                self.extract(left)?;
+                self.extract(right)?;
+            }
+            BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+                self.extract_condition(left)?;
+                self.extract_condition(right)?;
+            }
+            BooleanExpression::Not(inner) => self.extract_condition(inner)?,
+            BooleanExpression::In { expression, list } => {
+                self.extract(expression)?;
+                for item in list {
+                    self.extract(item)?;
+                }
+            }
+            BooleanExpression::Like { expression, .. }
+            | BooleanExpression::ILike { expression, .. }
+            | BooleanExpression::Contains { expression, .. }
+            | BooleanExpression::StartsWith { expression, .. }
+            | BooleanExpression::EndsWith { expression, .. }
+            | BooleanExpression::RegexMatch { expression, .. }
+            | BooleanExpression::IsNull(expression)
+            | BooleanExpression::IsNotNull(expression) => self.extract(expression)?,
+            BooleanExpression::ListPredicate { list, .. } => self.extract(list)?,
+            BooleanExpression::Exists(_)
+            | BooleanExpression::AllInPath { .. }
+            | BooleanExpression::ExistsSubquery { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The subquery returning `value` over the matches of `patterns`
+    fn subquery(
+        &self,
+        patterns: &[GraphPattern],
+        predicate: Option<&BooleanExpression>,
+        value: ValueExpression,
+        name: &str,
+    ) -> Result<Correlated> {
+        let (outer_nodes, outer_relationships) = outer_variables(self.outer);
+        let paths = patterns.iter().filter_map(|pattern| match pattern {
+            GraphPattern::Path(path) => Some(path),
+            GraphPattern::Node(_) => None,
+        });
+        if let Some(variable) = paths
+            .flat_map(|path| &path.segments)
+            .filter_map(|segment| segment.relationship.variable.as_deref())
+            .find(|v| {
+                outer_relationships
+                    .iter()
+                    .any(|r| r.eq_ignore_ascii_case(v))
+            })
+        {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!(
+                    "relationship variable '{}' of the query inside a pattern comprehension or \
+                     COUNT subquery",
+                    variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let nodes = patterns.iter().flat_map(|pattern| match pattern {
+            GraphPattern::Node(node) => vec![node],
+            GraphPattern::Path(path) => std::iter::once(&path.start_node)
+                .chain(path.segments.iter().map(|segment| &segment.end_node))
+                .collect(),
+        });
+        let mut imports: Vec<String> = Vec::new();
+        for variable in nodes.filter_map(|node| node.variable.as_deref()) {
+            let bound = outer_nodes.iter().any(|v| v.eq_ignore_ascii_case(variable));
+            if bound && !imports.iter().any(|v| v.eq_ignore_ascii_case(variable)) {
+                imports.push(variable.to_string());
+            }
+        }
+
+        let mut query = self.outer.clone();
+        query.reading_clauses = vec![ReadingClause::Match(MatchClause {
+            patterns: patterns.to_vec(),
+            optional: false,
+            where_clause: None,
+        })];
+        query.where_clause = predicate.map(|expression| WhereClause {
+            expression: expression.clone(),
+        });
+        query.post_with_reading_clauses = Vec::new();
+        query.post_with_where_clause = None;
+        query.return_clause = ReturnClause {
+            distinct: false,
+            distinct_on: Vec::new(),
+            items: vec![ReturnItem {
+                expression: value,
+                alias: Some(name.to_string()),
+            }],
+        };
+        query.order_by = None;
+        query.skip = None;
+        query.limit = None;
+        query.sample = None;
+        Ok(Correlated { query, imports })
+    }
+}
+
+/// Node and relationship variables bound by the MATCH clauses of `ast`
+fn outer_variables(ast: &CypherAST) -> (Vec<String>, Vec<String>) {
+    let mut nodes = Vec::new();
+    let mut relationships = Vec::new();
+    let patterns = ast
+        .reading_clauses
+        .iter()
+        .filter_map(|clause| match clause {
+            ReadingClause::Match(match_clause) => Some(match_clause),
+            ReadingClause::Unwind(_) | ReadingClause::TableFunction(_) => None,
+        })
+        .flat_map(|match_clause| &match_clause.patterns);
+    for pattern in patterns {
+        match pattern {
+            GraphPattern::Node(node) => nodes.extend(node.variable.clone()),
+            GraphPattern::Path(path) => {
+                nodes.extend(path.start_node.variable.clone());
+                for segment in &path.segments {
+                    relationships.extend(segment.relationship.variable.clone());
+                    nodes.extend(segment.end_node.variable.clone());
+                }
+            }
+        }
+    }
+    (nodes, relationships)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::PropertyRef;
+    use crate::parser::parse_cypher_query;
+
+    #[test]
+    fn test_rewrite_imports_outer_nodes_and_collects_the_projection() {
+        let ast = parse_cypher_query(
+            "MATCH (p:Person) \
+             RETURN p.name, [(p)-[:KNOWS]->(f:Person) WHERE f.age > 30 | f.name] AS friends \
+             ORDER BY size([(p)-[:KNOWS]->(g:Person) | g.name])",
+        )
+        .unwrap();
+        let Rewritten {
+            ast: rewritten,
+            subqueries,
+            filter,
+        } = rewrite(&ast).unwrap().unwrap();
+
+        assert!(filter.is_none());
+        assert_eq!(subqueries.len(), 2);
+        assert_eq!(subqueries[0].imports, vec!["p".to_string()]);
+        assert!(subqueries[0].query.where_clause.is_some());
+        assert_eq!(
+            subqueries[0].query.return_clause.items[0],
+            ReturnItem {
+                expression: ValueExpression::AggregateFunction {
+                    name: "collect".to_string(),
+                    args: vec![ValueExpression::Property(PropertyRef::new("f", "name"))],
+                    distinct: false,
+                },
+                alias: Some("patterncomprehension0".to_string()),
+            }
+        );
+        assert_eq!(
+            rewritten.return_clause.items[1].expression,
+            ValueExpression::Variable("patterncomprehension0".to_string())
+        );
+        assert_eq!(
+            rewritten.return_clause.items[1].alias.as_deref(),
+            Some("friends")
+        );
+        assert!(!contains_nested(&rewritten));
+    }
+
+    #[test]
+    fn test_rewrite_splits_where_around_count_subqueries() {
+        let ast = parse_cypher_query(
+            "MATCH (p:Person) \
+             WHERE p.age > 30 AND COUNT { (p)-[:KNOWS]->(:Person) } >= 2 \
+             RETURN p.name",
+        )
+        .unwrap();
+        let rewritten = rewrite(&ast).unwrap().unwrap();
+
+        // The plain conjunct stays with the outer MATCH
+        assert!(matches!(
+            rewritten.ast.where_clause.as_ref().map(|w| &w.expression),
+            Some(BooleanExpression::Comparison {
+                left: ValueExpression::Property(_),
+                ..
+            })
+        ));
+        assert_eq!(rewritten.subqueries.len(), 1);
+        assert_eq!(rewritten.subqueries[0].imports, vec!["p".to_string()]);
+        assert!(matches!(
+            &rewritten.subqueries[0].query.return_clause.items[0].expression,
+            ValueExpression::AggregateFunction { name, .. } if name == "count"
+        ));
+        assert!(matches!(
+            rewritten.filter,
+            Some(BooleanExpression::Comparison {
+                left: ValueExpression::Variable(ref name),
+                ..
+            }) if name == "countsubquery0"
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_leaves_other_queries_alone() {
+        let ast = parse_cypher_query("MATCH (p:Person) RETURN p.name").unwrap();
+        assert!(rewrite(&ast).unwrap().is_none());
+        assert!(check_none(&ast).is_ok());
+
+        let ast = parse_cypher_query(
+            "MATCH (p:Person) WITH p RETURN [(p)-[:KNOWS]->(f:Person) | f.name] AS friends",
+        )
+        .unwrap();
+        assert!(check_none(&ast).is_err());
+        assert!(rewrite(&ast).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Plan snapshot testing
+//!
+//! Records the optimized logical plan of every query in a corpus as a text
+//! file and, on later runs, reports any query whose plan changed. Running a
+//! workload's corpus in CI turns planner or DataFusion upgrades that alter
+//! plans into explicit, reviewable snapshot updates.
+//!
+//! Snapshots are written to `<snapshot_dir>/<name>.plan`. A missing snapshot
+//! counts as a mismatch unless updating is enabled, either with
+//! [`PlanSnapshotter::with_update`] or by setting the
+//! `LANCE_GRAPH_UPDATE_SNAPSHOTS` environment variable.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::plan_snapshot::{PlanCorpus, PlanSnapshotter};
+//!
+//! let corpus = PlanCorpus::from_dir("tests/queries")?;
+//! let report = PlanSnapshotter::new(config, datasets, "tests/plans")
+//!     .check(&corpus)
+//!     .await?;
+//! report.assert_unchanged();
+//! ```
+
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that switches snapshot checks to update mode
+pub const UPDATE_SNAPSHOTS_ENV: &str = "LANCE_GRAPH_UPDATE_SNAPSHOTS";
+
+/// File extension of query files read by [`PlanCorpus::from_dir`]
+const QUERY_EXTENSION: &str = "cypher";
+
+/// File extension of snapshot files
+const SNAPSHOT_EXTENSION: &str = "plan";
+
+/// A named set of queries whose plans are snapshotted
+#[derive(Debug, Clone, Default)]
+pub struct PlanCorpus {
+    queries: Vec<(String, String)>,
+}
+
+impl PlanCorpus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a query; `name` becomes the snapshot file name
+    pub fn with_query(mut self, name: impl Into<String>, query: impl Into<String>) -> Self {
+        self.queries.push((name.into(), query.into()));
+        self
+    }
+
+    /// Load every `*.cypher` file in a directory, named after the file stem
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| io_error(dir, e))?;
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(dir, e))?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(QUERY_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut corpus = Self::new();
+        for path in paths {
+            let query = std::fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+            corpus = corpus.with_query(name, query);
+        }
+        Ok(corpus)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Queries as `(name, query)` pairs, in insertion order
+    pub fn queries(&self) -> &[(String, String)] {
+        &self.queries
+    }
+}
+
+/// Compares the plans of a corpus against stored snapshots
+pub struct PlanSnapshotter {
+    config: GraphConfig,
+    datasets: HashMap<String, RecordBatch>,
+    snapshot_dir: PathBuf,
+    update: bool,
+}
+
+impl PlanSnapshotter {
+    /// Create a snapshotter
+    ///
+    /// Only the schemas of `datasets` are used for planning, so empty batches
+    /// with the production schemas are enough.
+    pub fn new(
+        config: GraphConfig,
+        datasets: HashMap<String, RecordBatch>,
+        snapshot_dir: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            config,
+            datasets,
+            snapshot_dir: snapshot_dir.into(),
+            update: std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some(),
+        }
+    }
+
+    /// Write new or changed plans instead of reporting them as mismatches
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Normalized plan text for a single query
+    pub async fn plan_text(&self, query: &str) -> Result<String> {
+        let plan = CypherQuery::new(query)?
+            .with_config(self.config.clone())
+            .optimized_logical_plan(self.datasets.clone())
+            .await?;
+        Ok(normalize_plan(&plan.display_indent().to_string()))
+    }
+
+    /// Plan every query in the corpus and compare against the snapshots
+    pub async fn check(&self, corpus: &PlanCorpus) -> Result<SnapshotReport> {
+        let mut report = SnapshotReport::default();
+        for (name, query) in corpus.queries() {
+            let actual = self
+                .plan_text(query)
+                .await
+                .map_err(|e| GraphError::PlanError {
+                    message: format!("Failed to plan corpus query '{}': {}", name, e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+            let path = self.snapshot_path(name);
+            let expected = match std::fs::read_to_string(&path) {
+                Ok(text) => Some(normalize_plan(&text)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(io_error(&path, e)),
+            };
+
+            if expected.as_deref() == Some(actual.as_str()) {
+                report.unchanged.push(name.clone());
+            } else if self.update {
+                std::fs::create_dir_all(&self.snapshot_dir)
+                    .map_err(|e| io_error(&self.snapshot_dir, e))?;
+                std::fs::write(&path, format!("{}\n", actual)).map_err(|e| io_error(&path, e))?;
+                report.written.push(name.clone());
+            } else {
+                report.mismatches.push(PlanMismatch {
+                    name: name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    fn snapshot_path(&self, name: &str) -> PathBuf {
+        self.snapshot_dir
+            .join(format!("{}.{}", name, SNAPSHOT_EXTENSION))
+    }
+}
+
+/// A query whose plan differs from its snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanMismatch {
+    pub name: String,
+    /// Stored plan, or `None` when no snapshot exists yet
+    pub expected: Option<String>,
+    pub actual: String,
+}
+
+impl fmt::Display for PlanMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.expected {
+            None => writeln!(f, "{}: no snapshot recorded", self.name)?,
+            Some(expected) => {
+                writeln!(f, "{}: plan changed", self.name)?;
+                for line in expected.lines() {
+                    writeln!(f, "-{}", line)?;
+                }
+            }
+        }
+        for line in self.actual.lines() {
+            writeln!(f, "+{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of [`PlanSnapshotter::check`]
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotReport {
+    /// Queries whose plan matched the snapshot
+    pub unchanged: Vec<String>,
+    /// Queries whose snapshot was created or rewritten (update mode)
+    pub written: Vec<String>,
+    /// Queries whose plan differs from, or lacks, a snapshot
+    pub mismatches: Vec<PlanMismatch>,
+}
+
+impl SnapshotReport {
+    pub fn is_unchanged(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Panic with a diff of every mismatched plan
+    pub fn assert_unchanged(&self) {
+        if self.is_unchanged() {
+            return;
+        }
+        let details: Vec<String> = self.mismatches.iter().map(|m| m.to_string()).collect();
+        panic!(
+            "{} plan snapshot(s) differ; rerun with {}=1 to accept the new plans\n\n{}",
+            self.mismatches.len(),
+            UPDATE_SNAPSHOTS_ENV,
+            details.join("\n")
+        );
+    }
+}
+
+/// Normalize plan text so snapshots only differ on plan changes
+///
+/// Trims trailing whitespace and blank lines and unifies line endings.
+pub fn normalize_plan(plan: &str) -> String {
+    plan.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> GraphError {
+    GraphError::ExecutionError {
+        message: format!("Plan snapshot I/O error on {}: {}", path.display(), e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_plan() {
+        let plan =
+            "Projection: p.name  \r\n  Filter: p.age > Int64(30)\r\n\n    TableScan: person\n";
+        assert_eq!(
+            normalize_plan(plan),
+            "Projection: p.name\n  Filter: p.age > Int64(30)\n    TableScan: person"
+        );
+    }
+
+    #[test]
+    fn test_mismatch_display() {
+        let mismatch = PlanMismatch {
+            name: "adults".to_string(),
+            expected: Some("Filter: a\n  TableScan: person".to_string()),
+            actual: "TableScan: person".to_string(),
+        };
+        assert_eq!(
+            mismatch.to_string(),
+            "adults: plan changed\n-Filter: a\n-  TableScan: person\n+TableScan: person\n"
+        );
+    }
+
+    #[test]
+    fn test_corpus_from_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.cypher"), "MATCH (n) RETURN n").unwrap();
+        std::fs::write(dir.path().join("a.cypher"), "MATCH (m) RETURN m").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let corpus = PlanCorpus::from_dir(dir.path()).unwrap();
+        let names: Vec<&str> = corpus.queries().iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,378 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Constant-false pruning from column statistics
+//!
+//! A `WHERE` predicate comparing a property to a literal outside the
+//! property's global minimum and maximum keeps no row: `n.year > 3000` when
+//! no year is later than 2024. With statistics attached
+//! ([`CypherQuery::with_statistics`]), the planner replaces such a filter and
+//! everything below it with an empty relation of the same schema, so the
+//! query returns an empty, correctly typed result without reading the
+//! datasets. EXPLAIN lists every pruned filter with the bounds that ruled it
+//! out.
+//!
+//! The bounds are the outer bounds of the property's histogram, so only
+//! numeric and temporal properties prune. An `AND` prunes when either side
+//! does and an `OR` when both sides do; `=`, `<`, `<=`, `>` and `>=` against
+//! integer, float and ISO 8601 literals are checked. Filters on variables
+//! bound by `WITH` or `UNWIND` are left alone.
+//!
+//! Statistics of a Lance-backed label or type only prune while its dataset is
+//! at the version they were collected from (their `dataset_version`, which
+//! `CALL graph.summary()` reports). After a write, or for statistics that
+//! record no version, the filter runs as usual until the statistics are
+//! refreshed. In-memory tables carry no version; their statistics are
+//! trusted to describe the batches passed in.
+//!
+//! [`CypherQuery::with_statistics`]: crate::query::CypherQuery::with_statistics
+
+use crate::ast::{BooleanExpression, ComparisonOperator, PropertyValue, ValueExpression};
+use crate::cost::{flipped, histogram_value, GraphStatistics};
+use crate::logical_plan::LogicalOperator;
+use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A filter the statistics prove keeps no row
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrunedFilter {
+    /// The comparison that can never hold, e.g. `n.year > 3000`
+    pub comparison: String,
+    /// Label or relationship type of the compared property
+    pub element: String,
+    pub property: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl fmt::Display for PrunedFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is always false: {}.{} is within [{}, {}]",
+            self.comparison, self.element, self.property, self.min, self.max
+        )
+    }
+}
+
+/// Every filter of `plan` that `statistics` prove keeps no row, outermost
+/// first
+///
+/// With a `catalog`, statistics of Lance-backed elements are only used at
+/// the dataset version they were collected from.
+pub fn pruned_filters(
+    plan: &LogicalOperator,
+    statistics: &GraphStatistics,
+    catalog: Option<&dyn GraphSourceCatalog>,
+) -> Vec<PrunedFilter> {
+    let mut pruned = Vec::new();
+    collect_pruned(plan, statistics, catalog, &mut pruned);
+    pruned
+}
+
+fn collect_pruned(
+    plan: &LogicalOperator,
+    statistics: &GraphStatistics,
+    catalog: Option<&dyn GraphSourceCatalog>,
+    pruned: &mut Vec<PrunedFilter>,
+) {
+    if let LogicalOperator::Filter { input, predicate } = plan {
+        if let Some(filter) = contradiction(input, predicate, statistics, catalog) {
+            // Nothing below a pruned filter is planned
+            pruned.push(filter);
+            return;
+        }
+    }
+    match plan {
+        LogicalOperator::ScanByLabel { .. } => {}
+        LogicalOperator::Unwind { input, .. } | LogicalOperator::TableFunction { input, .. } => {
+            if let Some(input) = input {
+                collect_pruned(input, statistics, catalog, pruned);
+            }
+        }
+        LogicalOperator::Join { left, right, .. } => {
+            collect_pruned(left, statistics, catalog, pruned);
+            collect_pruned(right, statistics, catalog, pruned);
+        }
+        LogicalOperator::Filter { input, .. }
+        | LogicalOperator::Sample { input, .. }
+        | LogicalOperator::Expand { input, .. }
+        | LogicalOperator::VariableLengthExpand { input, .. }
+        | LogicalOperator::Project { input, .. }
+        | LogicalOperator::Distinct { input }
+        | LogicalOperator::DistinctOn { input, .. }
+        | LogicalOperator::Sort { input, .. }
+        | LogicalOperator::Offset { input, .. }
+        | LogicalOperator::Limit { input, .. } => {
+            collect_pruned(input, statistics, catalog, pruned)
+        }
+    }
+}
+
+/// The comparison of `predicate` that `statistics` prove false for every row
+/// of `input`, if any
+pub(crate) fn contradiction(
+    input: &LogicalOperator,
+    predicate: &BooleanExpression,
+    statistics: &GraphStatistics,
+    catalog: Option<&dyn GraphSourceCatalog>,
+) -> Option<PrunedFilter> {
+    let mut elements = HashMap::new();
+    bound_elements(input, &mut elements);
+    contradicting(predicate, &elements, statistics, catalog)
+}
+
+/// Whether the statistics of `element` describe the dataset `catalog` reads
+/// it from: always for in-memory tables, and for Lance datasets only at the
+/// version the statistics were collected from
+fn current(
+    element: &Element<'_>,
+    statistics: &GraphStatistics,
+    catalog: Option<&dyn GraphSourceCatalog>,
+) -> bool {
+    let Some(catalog) = catalog else {
+        return true;
+    };
+    let (source, recorded) = match element {
+        Element::Node(label) => (catalog.node_source(label), statistics.node(label)),
+        Element::Relationship(rel_type) => (
+            catalog.relationship_source(rel_type),
+            statistics.relationship(rel_type),
+        ),
+    };
+    match source.and_then(|source| crate::result_cache::lance_version(&source)) {
+        Some((_, version)) => recorded.and_then(|s| s.dataset_version) == Some(version),
+        None => true,
+    }
+}
+
+/// A label or relationship type, to look property statistics up by
+enum Element<'a> {
+    Node(&'a str),
+    Relationship(&'a str),
+}
+
+/// Node and single-type relationship variables matched by `plan`
+///
+/// Stops at projections, `UNWIND` and `CALL ... YIELD`, which may rebind
+/// names.
+fn bound_elements<'a>(plan: &'a LogicalOperator, elements: &mut HashMap<&'a str, Element<'a>>) {
+    match plan {
+        LogicalOperator::ScanByLabel {
+            variable, label, ..
+        } => {
+            elements.insert(variable, Element::Node(label));
+        }
+        LogicalOperator::Expand {
+            input,
+            target_variable,
+            target_label,
+            relationship_types,
+            relationship_variable,
+            ..
+        } => {
+            bound_elements(input, elements);
+            if !target_label.is_empty() {
+                elements.insert(target_variable, Element::Node(target_label));
+            }
+            if let (Some(variable), [rel_type]) =
+                (relationship_variable, relationship_types.as_slice())
+            {
+                elements.insert(variable, Element::Relationship(rel_type));
+            }
+        }
+        LogicalOperator::Join { left, right, .. } => {
+            bound_elements(left, elements);
+            bound_elements(right, elements);
+        }
+        LogicalOperator::Filter { input, .. }
+        | LogicalOperator::Sample { input, .. }
+        | LogicalOperator::VariableLengthExpand { input, .. }
+        | LogicalOperator::Distinct { input }
+        | LogicalOperator::Sort { input, .. }
+        | LogicalOperator::Offset { input, .. }
+        | LogicalOperator::Limit { input, .. } => bound_elements(input, elements),
+        LogicalOperator::Unwind { .. }
+        | LogicalOperator::TableFunction { .. }
+        | LogicalOperator::Project { .. }
+        | LogicalOperator::DistinctOn { .. } => {}
+    }
+}
+
+fn contradicting(
+    predicate: &BooleanExpression,
+    elements: &HashMap<&str, Element<'_>>,
+    statistics: &GraphStatistics,
+    catalog: Option<&dyn GraphSourceCatalog>,
+) -> Option<PrunedFilter> {
+    match predicate {
+        BooleanExpression::And(left, right) => contradicting(left, elements, statistics, catalog)
+            .or_else(|| contradicting(right, elements, statistics, catalog)),
+        // Either side suffices to explain the pruning
+        BooleanExpression::Or(left, right) => contradicting(left, elements, statistics, catalog)
+            .filter(|_| contradicting(right, elements, statistics, catalog).is_some()),
+        BooleanExpression::Comparison {
+            left,
+            operator,
+            right,
+        } => {
+            let (property, operator, value) = match (left, right) {
+                (ValueExpression::Property(p), ValueExpression::Literal(v)) => {
+                    (p, operator.clone(), v)
+                }
+                (ValueExpression::Literal(v), ValueExpression::Property(p)) => {
+                    (p, flipped(operator), v)
+                }
+                _ => return None,
+            };
+            let element = elements.get(property.variable.as_str())?;
+            if !current(element, statistics, catalog) {
+                return None;
+            }
+            let (element, stats) = match element {
+                Element::Node(label) => {
+                    (label, statistics.node_property(label, &property.property))
+                }
+                Element::Relationship(rel_type) => (
+                    rel_type,
+                    statistics.relationship_property(rel_type, &property.property),
+                ),
+            };
+            let (min, max) = stats?.bounds()?;
+            let literal = histogram_value(value)?;
+            let never = match operator {
+                ComparisonOperator::Equal => literal < min || literal > max,
+                ComparisonOperator::LessThan => literal <= min,
+                ComparisonOperator::LessThanOrEqual => literal < min,
+                ComparisonOperator::GreaterThan => literal >= max,
+                ComparisonOperator::GreaterThanOrEqual => literal > max,
+                ComparisonOperator::NotEqual => false,
+            };
+            never.then(|| PrunedFilter {
+                comparison: format!(
+                    "{}.{} {} {}",
+                    property.variable,
+                    property.property,
+                    operator_symbol(&operator),
+                    literal_text(value)
+                ),
+                element: element.to_string(),
+                property: property.property.clone(),
+                min,
+                max,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn operator_symbol(operator: &ComparisonOperator) -> &'static str {
+    match operator {
+        ComparisonOperator::Equal => "=",
+        ComparisonOperator::NotEqual => "<>",
+        ComparisonOperator::LessThan => "<",
+        ComparisonOperator::LessThanOrEqual => "<=",
+        ComparisonOperator::GreaterThan => ">",
+        ComparisonOperator::GreaterThanOrEqual => ">=",
+    }
+}
+
+fn literal_text(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::Integer(i) => i.to_string(),
+        PropertyValue::Float(f) => f.to_string(),
+        PropertyValue::String(s) => format!("'{}'", s),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::PropertyRef;
+    use crate::cost::{Histogram, PropertyStatistics};
+
+    fn statistics() -> GraphStatistics {
+        GraphStatistics::new().with_node_property(
+            "Book",
+            "year",
+            PropertyStatistics {
+                histogram: Some(Histogram {
+                    bounds: vec![1990.0, 2005.0, 2024.0],
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn scan() -> LogicalOperator {
+        LogicalOperator::ScanByLabel {
+            variable: "b".to_string(),
+            label: "Book".to_string(),
+            properties: HashMap::new(),
+        }
+    }
+
+    fn year(operator: ComparisonOperator, value: i64) -> BooleanExpression {
+        BooleanExpression::Comparison {
+            left: ValueExpression::Property(PropertyRef::new("b", "year")),
+            operator,
+            right: ValueExpression::Literal(PropertyValue::Integer(value)),
+        }
+    }
+
+    #[test]
+    fn test_comparisons_outside_the_bounds_are_contradictions() {
+        let statistics = statistics();
+        let pruned = |predicate: BooleanExpression| {
+            contradiction(&scan(), &predicate, &statistics, None).map(|p| p.to_string())
+        };
+        assert_eq!(
+            pruned(year(ComparisonOperator::GreaterThan, 3000)).as_deref(),
+            Some("b.year > 3000 is always false: Book.year is within [1990, 2024]")
+        );
+        assert!(pruned(year(ComparisonOperator::GreaterThan, 2024)).is_some());
+        assert!(pruned(year(ComparisonOperator::GreaterThanOrEqual, 2024)).is_none());
+        assert!(pruned(year(ComparisonOperator::LessThan, 1990)).is_some());
+        assert!(pruned(year(ComparisonOperator::Equal, 1989)).is_some());
+        assert!(pruned(year(ComparisonOperator::Equal, 2000)).is_none());
+        assert!(pruned(year(ComparisonOperator::NotEqual, 1000)).is_none());
+
+        // Literal on the left
+        let flipped = BooleanExpression::Comparison {
+            left: ValueExpression::Literal(PropertyValue::Integer(3000)),
+            operator: ComparisonOperator::LessThan,
+            right: ValueExpression::Property(PropertyRef::new("b", "year")),
+        };
+        assert!(pruned(flipped).is_some());
+
+        let and = BooleanExpression::And(
+            Box::new(year(ComparisonOperator::GreaterThan, 2000)),
+            Box::new(year(ComparisonOperator::LessThan, 1000)),
+        );
+        assert!(pruned(and).is_some());
+        let or = BooleanExpression::Or(
+            Box::new(year(ComparisonOperator::GreaterThan, 2000)),
+            Box::new(year(ComparisonOperator::LessThan, 1000)),
+        );
+        assert!(pruned(or).is_none());
+    }
+
+    #[test]
+    fn test_projected_variables_are_not_pruned() {
+        let statistics = statistics();
+        let predicate = year(ComparisonOperator::GreaterThan, 3000);
+        let filter = |input: LogicalOperator| LogicalOperator::Filter {
+            input: Box::new(input),
+            predicate: predicate.clone(),
+        };
+        assert_eq!(pruned_filters(&filter(scan()), &statistics, None).len(), 1);
+
+        let projected = LogicalOperator::Project {
+            input: Box::new(scan()),
+            projections: vec![],
+        };
+        assert!(pruned_filters(&filter(projected), &statistics, None).is_empty());
+    }
+}
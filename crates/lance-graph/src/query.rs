@@ -4,19 +4,34 @@
 //! High-level Cypher query interface for Lance datasets
 
 use crate::ast::CypherQuery as CypherAST;
-use crate::ast::ReadingClause;
+use crate::ast::{ReadingClause, SampleMethod};
+use crate::coalesce::{coalesce_stream, BatchCoalescing};
 use crate::config::GraphConfig;
+use crate::cost::{CostEstimate, GraphStatistics, ObservedPredicates, SelectivityFeedback};
+use crate::credentials::{CredentialsProvider, DatasetCredentials};
+use crate::datafusion_planner::OverflowMode;
+use crate::delta::DeltaStore;
 use crate::error::{GraphError, Result};
+use crate::expansion::{ExpansionLimits, TruncationFlags};
 use crate::logical_plan::LogicalPlanner;
+use crate::lookup::{self, LookupJoin};
 use crate::parser::parse_cypher_query;
+use crate::query_monitor::{QueryMonitor, QueryProgress};
+use crate::result_cache::{self, ResultCache, ResultKey};
+use crate::runtime::{QueryRuntimes, WorkloadClass};
+use crate::semantic::{require_feature, CompatibilityMode, ExperimentalFeature};
+use crate::shortest_paths;
 use crate::simple_executor::{
     to_df_boolean_expr_simple, to_df_order_by_expr_simple, to_df_value_expr_simple, PathExecutor,
 };
+use crate::table_function::TableFunction;
+use crate::vector_candidates;
 use arrow_array::RecordBatch;
 use arrow_schema::{Field, Schema, SchemaRef};
-use lance_graph_catalog::DirNamespace;
+use lance_graph_catalog::{DirNamespace, TenantNamespace};
 use lance_namespace::models::DescribeTableRequest;
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 /// Normalize an Arrow schema to have lowercase field names.
@@ -75,6 +90,46 @@ pub struct CypherQuery {
     config: Option<GraphConfig>,
     /// Query parameters
     parameters: HashMap<String, serde_json::Value>,
+    /// Accepted Cypher syntax extensions
+    compatibility_mode: CompatibilityMode,
+    /// Experimental syntax the query opted in to
+    experimental_features: BTreeSet<ExperimentalFeature>,
+    /// Whether results carry provenance columns for each matched entity
+    include_provenance: bool,
+    /// Seed for `rand()` / `randomUUID()` calls without an explicit seed
+    seed: Option<u64>,
+    /// Memoized results shared with other queries
+    result_cache: Option<Arc<ResultCache>>,
+    /// Attributes of the calling session, e.g. the tenant
+    session_attributes: HashMap<String, String>,
+    /// Source of refreshable credentials for remote datasets
+    credentials_provider: Option<Arc<dyn CredentialsProvider>>,
+    /// Caps on the paths of variable-length expansions
+    expansion_limits: ExpansionLimits,
+    /// Runtimes dedicated to workload classes
+    runtimes: Option<Arc<QueryRuntimes>>,
+    /// Workload class selecting the runtime executions run on
+    workload_class: WorkloadClass,
+    /// Store of the predicate selectivities executions observe
+    selectivity_feedback: Option<Arc<SelectivityFeedback>>,
+    /// Property bounds that prune filters no row can pass
+    statistics: Option<GraphStatistics>,
+    /// What integer arithmetic does on overflow
+    overflow_mode: OverflowMode,
+    /// Whether strings are compared in Unicode normalization form C
+    unicode_normalization: bool,
+    /// Target size of streamed result batches
+    batch_coalescing: BatchCoalescing,
+    /// Table functions callable with `CALL ... YIELD`, keyed by lowercase name
+    table_functions: HashMap<String, Arc<dyn TableFunction>>,
+    /// External values appended to the results
+    lookup_joins: Vec<LookupJoin>,
+    /// Registry the query's executions are listed in while they run
+    query_monitor: Option<Arc<QueryMonitor>>,
+    /// Progress the current execution reports to `query_monitor`
+    progress: Option<Arc<QueryProgress>>,
+    /// Uncommitted rows of the write transaction the query runs in
+    delta: Option<Arc<DeltaStore>>,
 }
 impl CypherQuery {
     /// Create a new Cypher query from a query string
@@ -86,6 +141,26 @@ impl CypherQuery {
             ast,
             config: None,
             parameters: HashMap::new(),
+            compatibility_mode: CompatibilityMode::default(),
+            experimental_features: BTreeSet::new(),
+            include_provenance: false,
+            seed: None,
+            result_cache: None,
+            session_attributes: HashMap::new(),
+            credentials_provider: None,
+            expansion_limits: ExpansionLimits::default(),
+            runtimes: None,
+            workload_class: WorkloadClass::default(),
+            selectivity_feedback: None,
+            statistics: None,
+            overflow_mode: OverflowMode::default(),
+            unicode_normalization: false,
+            batch_coalescing: BatchCoalescing::default(),
+            table_functions: HashMap::new(),
+            lookup_joins: Vec::new(),
+            query_monitor: None,
+            progress: None,
+            delta: None,
         })
     }
 
@@ -111,6 +186,203 @@ impl CypherQuery {
         self
     }
 
+    /// Choose whether Neo4j syntax extensions are accepted (default) or
+    /// rejected in favour of strict openCypher
+    pub fn with_compatibility_mode(mut self, mode: CompatibilityMode) -> Self {
+        self.compatibility_mode = mode;
+        self
+    }
+
+    /// Accept the experimental syntax of `feature`
+    ///
+    /// Queries using experimental syntax fail to execute unless they opted
+    /// in to its feature. Features can also be named, e.g.
+    /// `"call_subquery".parse::<ExperimentalFeature>()?`.
+    pub fn with_experimental_feature(mut self, feature: ExperimentalFeature) -> Self {
+        self.experimental_features.insert(feature);
+        self
+    }
+
+    /// Return provenance columns (`_dataset`, `_fragment`, `_rowid`, `_version`)
+    /// for every matched entity, e.g. `n._rowid`
+    ///
+    /// Lets debugging and downstream writes locate the exact source rows. Only
+    /// entities read from Lance datasets (namespace execution) carry provenance.
+    pub fn with_provenance(mut self, include_provenance: bool) -> Self {
+        self.include_provenance = include_provenance;
+        self
+    }
+
+    /// Seed `rand()` and `randomUUID()` so that re-running the query over the
+    /// same data reproduces its random values (e.g. a sample drawn with
+    /// `WHERE rand() < 0.1`)
+    ///
+    /// Calls with an explicit seed, `rand(seed)`, keep their own seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Serve repeated executions from `cache` while the graph's datasets are
+    /// unchanged
+    ///
+    /// See [`crate::result_cache`] for which results are cached.
+    pub fn with_result_cache(mut self, cache: Arc<ResultCache>) -> Self {
+        self.result_cache = Some(cache);
+        self
+    }
+
+    /// Set an attribute of the calling session
+    ///
+    /// Attributes select per-session resources such as the tenant whose
+    /// datasets [`CypherQuery::execute_with_tenant_namespace`] reads.
+    pub fn with_session_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.session_attributes.insert(key.into(), value.into());
+        self
+    }
+
+    /// Open namespace datasets with credentials from `provider`
+    ///
+    /// Expiring credentials are refreshed through the provider while the
+    /// datasets are read; see [`crate::credentials`].
+    pub fn with_credentials_provider(mut self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.credentials_provider = Some(provider);
+        self
+    }
+
+    /// Make the table function `function` callable as `CALL name(...) YIELD ...`
+    ///
+    /// Names are dotted and case-insensitive; see [`crate::table_function`].
+    pub fn with_table_function(
+        mut self,
+        name: impl Into<String>,
+        function: Arc<dyn TableFunction>,
+    ) -> Self {
+        self.table_functions
+            .insert(name.into().to_lowercase(), function);
+        self
+    }
+
+    /// Append the values `join` looks up for each result row
+    ///
+    /// Joins are applied in the order they are added; see [`crate::lookup`].
+    pub fn with_lookup_join(mut self, join: LookupJoin) -> Self {
+        self.lookup_joins.push(join);
+        self
+    }
+
+    /// List executions in `monitor` while they run, where
+    /// `CALL dbms.listQueries()` shows them and `CALL dbms.killQuery(id)`
+    /// stops them
+    ///
+    /// See [`crate::query_monitor`].
+    pub fn with_query_monitor(mut self, monitor: Arc<QueryMonitor>) -> Self {
+        self.query_monitor = Some(monitor);
+        self
+    }
+
+    /// Execute on the runtime `runtimes` dedicates to the query's workload
+    /// class, if any
+    ///
+    /// See [`crate::runtime`].
+    pub fn with_runtimes(mut self, runtimes: Arc<QueryRuntimes>) -> Self {
+        self.runtimes = Some(runtimes);
+        self
+    }
+
+    /// Set the workload class of the query (default
+    /// [`WorkloadClass::Interactive`])
+    pub fn with_workload_class(mut self, class: WorkloadClass) -> Self {
+        self.workload_class = class;
+        self
+    }
+
+    /// Sample the matched rows before RETURN, as the `SAMPLE` clause does
+    ///
+    /// Replaces a `SAMPLE` clause written in the query. Combine with
+    /// [`CypherQuery::with_seed`] to draw the same sample again.
+    pub fn with_sample(mut self, method: SampleMethod) -> Self {
+        self.ast.sample = Some(method);
+        self
+    }
+
+    /// Cap the paths each variable-length expansion produces
+    ///
+    /// Results that lost paths to a cap are marked in their schema metadata;
+    /// see [`crate::expansion`].
+    pub fn with_expansion_limits(mut self, limits: ExpansionLimits) -> Self {
+        self.expansion_limits = limits;
+        self
+    }
+
+    /// Choose what integer `+`, `-` and `*` do on overflow
+    ///
+    /// Defaults to [`OverflowMode::Error`], failing the query like Cypher
+    /// does, instead of silently wrapping around.
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
+    /// Compare strings, match `LIKE` patterns and case-map in Unicode
+    /// normalization form C
+    ///
+    /// Property data often mixes composed and decomposed spellings (`é` as
+    /// one character or as `e` and a combining accent), which differ byte for
+    /// byte. Needs the `icu` feature; queries fail to plan without it.
+    pub fn with_unicode_normalization(mut self, unicode_normalization: bool) -> Self {
+        self.unicode_normalization = unicode_normalization;
+        self
+    }
+
+    /// Merge small streamed result batches into batches of a target size
+    ///
+    /// Applies to [`CypherQuery::execute_into`] and
+    /// [`CypherQuery::execute_with_callback`]; see [`crate::coalesce`].
+    pub fn with_batch_coalescing(mut self, coalescing: BatchCoalescing) -> Self {
+        self.batch_coalescing = coalescing;
+        self
+    }
+
+    /// Record the selectivity and null count each `WHERE` predicate shows
+    /// while this query runs into `feedback`
+    ///
+    /// Statistics carrying the same store estimate this query with what it
+    /// observed; see [`crate::cost`]. Observed predicates are evaluated as
+    /// plain filters, so leave this off where every run counts.
+    pub fn with_selectivity_feedback(mut self, feedback: Arc<SelectivityFeedback>) -> Self {
+        self.selectivity_feedback = Some(feedback);
+        self
+    }
+
+    /// Prune filters that `statistics` prove keep no row, returning an empty
+    /// result without scanning the datasets
+    ///
+    /// Pruning trusts the statistics, so refresh them after writes; see
+    /// [`crate::pruning`].
+    pub fn with_statistics(mut self, statistics: GraphStatistics) -> Self {
+        self.statistics = Some(statistics);
+        self
+    }
+
+    /// Hash identifying this query in a [`SelectivityFeedback`] store
+    ///
+    /// Derived from the parsed query alone, so runs with other parameters or
+    /// options share it, and stable across processes and builds.
+    pub fn fingerprint(&self) -> Result<String> {
+        let ast = serde_json::to_value(&self.ast).map_err(|e| GraphError::ExecutionError {
+            message: format!("Failed to serialize query AST: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+        Ok(result_cache::stable_hash(&[&result_cache::canonical_json(
+            &ast,
+        )]))
+    }
+
     /// Get the original query text
     pub fn query_text(&self) -> &str {
         &self.query_text
@@ -121,6 +393,11 @@ impl CypherQuery {
         &self.ast
     }
 
+    /// Mutable AST, for rewrites applied before planning (e.g. graph-qualified labels)
+    pub(crate) fn ast_mut(&mut self) -> &mut CypherAST {
+        &mut self.ast
+    }
+
     /// Get the graph configuration
     pub fn config(&self) -> Option<&GraphConfig> {
         self.config.as_ref()
@@ -131,8 +408,41 @@ impl CypherQuery {
         &self.parameters
     }
 
+    /// Get the syntax compatibility mode
+    pub fn compatibility_mode(&self) -> CompatibilityMode {
+        self.compatibility_mode
+    }
+
+    /// Get the experimental features the query opted in to
+    pub fn experimental_features(&self) -> &BTreeSet<ExperimentalFeature> {
+        &self.experimental_features
+    }
+
+    /// Whether results carry provenance columns
+    pub fn includes_provenance(&self) -> bool {
+        self.include_provenance
+    }
+
+    pub fn query_monitor(&self) -> Option<&Arc<QueryMonitor>> {
+        self.query_monitor.as_ref()
+    }
+
+    /// Run the query inside a write transaction whose uncommitted rows are
+    /// kept in `delta`
+    ///
+    /// See [`crate::delta`].
+    pub(crate) fn with_delta(mut self, delta: Arc<DeltaStore>) -> Self {
+        self.delta = Some(delta);
+        self
+    }
+
+    /// Uncommitted rows of the write transaction the query runs in
+    pub(crate) fn delta(&self) -> Option<&Arc<DeltaStore>> {
+        self.delta.as_ref()
+    }
+
     /// Get the required config, returning an error if not set
-    fn require_config(&self) -> Result<&GraphConfig> {
+    pub(crate) fn require_config(&self) -> Result<&GraphConfig> {
         self.config.as_ref().ok_or_else(|| GraphError::ConfigError {
             message: "Graph configuration is required for query execution".to_string(),
             location: snafu::Location::new(file!(), line!(), column!()),
@@ -178,6 +488,22 @@ impl CypherQuery {
         &self,
         datasets: HashMap<String, arrow::record_batch::RecordBatch>,
         strategy: Option<ExecutionStrategy>,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        if let Some(runtimes) = &self.runtimes {
+            let query = self.clone();
+            return runtimes
+                .run(self.workload_class, async move {
+                    query.execute_on_current_runtime(datasets, strategy).await
+                })
+                .await;
+        }
+        self.execute_on_current_runtime(datasets, strategy).await
+    }
+
+    async fn execute_on_current_runtime(
+        &self,
+        datasets: HashMap<String, arrow::record_batch::RecordBatch>,
+        strategy: Option<ExecutionStrategy>,
     ) -> Result<arrow::record_batch::RecordBatch> {
         let strategy = strategy.unwrap_or_default();
         match strategy {
@@ -202,12 +528,105 @@ impl CypherQuery {
             .await
     }
 
+    /// Execute the query against the datasets of the session's tenant
+    ///
+    /// The tenant is read from the session attribute named by `namespace`
+    /// (see [`CypherQuery::with_session_attribute`]), so one configuration
+    /// serves every tenant.
+    pub async fn execute_with_tenant_namespace(
+        &self,
+        namespace: &TenantNamespace,
+        strategy: Option<ExecutionStrategy>,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        let resolved =
+            namespace
+                .resolve(&self.session_attributes)
+                .map_err(|e| GraphError::ConfigError {
+                    message: format!("Failed to resolve tenant datasets: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        self.execute_with_namespace(resolved, strategy).await
+    }
+
     /// Execute the query using a shared namespace instance.
     pub async fn execute_with_namespace_arc(
         &self,
         namespace: std::sync::Arc<DirNamespace>,
         strategy: Option<ExecutionStrategy>,
     ) -> Result<arrow::record_batch::RecordBatch> {
+        if let Some(runtimes) = &self.runtimes {
+            let query = self.clone();
+            return runtimes
+                .run(self.workload_class, async move {
+                    query
+                        .execute_with_namespace_on_current_runtime(namespace, strategy)
+                        .await
+                })
+                .await;
+        }
+        self.execute_with_namespace_on_current_runtime(namespace, strategy)
+            .await
+    }
+
+    /// `namespace` with the credentials of the query's provider added to the
+    /// storage options of every dataset the query writes
+    ///
+    /// Writes are short-lived, so the credentials are fetched once and not
+    /// refreshed.
+    async fn with_write_credentials(
+        &self,
+        namespace: std::sync::Arc<DirNamespace>,
+    ) -> Result<std::sync::Arc<DirNamespace>> {
+        let Some(provider) = &self.credentials_provider else {
+            return Ok(namespace);
+        };
+        let mut credentialed = (*namespace).clone();
+        for table in crate::writing::written_tables(self)? {
+            let credentials = provider
+                .credentials(&table, &namespace.table_uri(&table))
+                .await?;
+            let options = namespace
+                .storage_options_for(&table)
+                .merged(&credentials.to_storage_options().into());
+            credentialed = credentialed.with_table_storage_options(&table, options);
+        }
+        Ok(std::sync::Arc::new(credentialed))
+    }
+
+    async fn execute_with_namespace_on_current_runtime(
+        &self,
+        namespace: std::sync::Arc<DirNamespace>,
+        strategy: Option<ExecutionStrategy>,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        // CREATE, MERGE, SET, REMOVE and DELETE write to the namespace's
+        // datasets instead of returning rows
+        let writes = self.ast.set_clause.is_some()
+            || self.ast.remove_clause.is_some()
+            || self.ast.delete_clause.is_some()
+            || self.ast.create_clause.is_some()
+            || self.ast.merge_clause.is_some();
+        if writes && strategy.unwrap_or_default() == ExecutionStrategy::DataFusion {
+            let namespace = self.with_write_credentials(namespace).await?;
+            if self.ast.set_clause.is_some() || self.ast.remove_clause.is_some() {
+                return crate::writing::execute_set(self, namespace).await;
+            }
+            if self.ast.delete_clause.is_some() {
+                return crate::writing::execute_delete(self, namespace).await;
+            }
+            if self.ast.create_clause.is_some() || self.ast.merge_clause.is_some() {
+                return crate::writing::execute_writes(self, namespace).await;
+            }
+        }
+        // graph.diff() reads the version history of the datasets
+        if let Some(call) = self
+            .ast
+            .procedure
+            .as_ref()
+            .filter(|c| crate::graph_diff::is_diff_call(c))
+        {
+            crate::summary::check_procedure(call)?;
+            return crate::graph_diff::diff_procedure(self, &namespace).await;
+        }
         let namespace_trait: std::sync::Arc<dyn lance_namespace::LanceNamespace + Send + Sync> =
             namespace;
         self.execute_with_namespace_internal(namespace_trait, strategy)
@@ -312,6 +731,36 @@ impl CypherQuery {
         datasets: HashMap<String, arrow::record_batch::RecordBatch>,
     ) -> Result<String> {
         use datafusion_sql::unparser::plan_to_sql;
+
+        // Optimizing simplifies the plan (e.g., merging projections) to produce cleaner SQL.
+        // Arithmetic is left to the SQL operators, whose overflow is up to the target engine.
+        let optimized_plan = self
+            .clone()
+            .with_overflow_mode(OverflowMode::Wrap)
+            .optimized_logical_plan(datasets)
+            .await?;
+
+        // Unparse to SQL
+        let sql_ast = plan_to_sql(&optimized_plan).map_err(|e| GraphError::PlanError {
+            message: format!("Failed to unparse plan to SQL: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+
+        Ok(sql_ast.to_string())
+    }
+
+    /// Build the DataFusion logical plan and optimize it with the default rules
+    ///
+    /// Unlike the physical plan, the optimized logical plan does not depend on
+    /// the machine it is built on (e.g. the number of partitions), which makes
+    /// it suitable for comparing plans across runs. See [`crate::plan_snapshot`].
+    ///
+    /// # Arguments
+    /// * `datasets` - HashMap of table name to RecordBatch; only the schemas are used
+    pub async fn optimized_logical_plan(
+        &self,
+        datasets: HashMap<String, arrow::record_batch::RecordBatch>,
+    ) -> Result<datafusion::logical_expr::LogicalPlan> {
         use std::sync::Arc;
 
         let _config = self.require_config()?;
@@ -322,25 +771,43 @@ impl CypherQuery {
             .await?;
 
         // Generate Logical Plan
-        let (_, df_plan) = self.create_logical_plans(Arc::new(catalog))?;
+        let (_, df_plan) =
+            self.create_logical_plans(Arc::new(catalog), &TruncationFlags::default(), None)?;
 
         // Optimize the plan using DataFusion's default optimizer rules
-        // This helps simplify the plan (e.g., merging projections) to produce cleaner SQL
-        let optimized_plan = ctx
-            .state()
+        ctx.state()
             .optimize(&df_plan)
             .map_err(|e| GraphError::PlanError {
                 message: format!("Failed to optimize plan: {}", e),
                 location: snafu::Location::new(file!(), line!(), column!()),
-            })?;
-
-        // Unparse to SQL
-        let sql_ast = plan_to_sql(&optimized_plan).map_err(|e| GraphError::PlanError {
-            message: format!("Failed to unparse plan to SQL: {}", e),
-            location: snafu::Location::new(file!(), line!(), column!()),
-        })?;
+            })
+    }
 
-        Ok(sql_ast.to_string())
+    /// Predict rows returned, bytes scanned and peak memory without executing
+    ///
+    /// Only the query and `statistics` are used; no dataset is opened. See
+    /// [`crate::cost`] for how the estimate is derived.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let statistics = GraphStatistics::new()
+    ///     .with_node("Person", ElementStatistics::new(1_000_000).with_size_bytes(64_000_000))
+    ///     .with_relationship("KNOWS", ElementStatistics::new(50_000_000));
+    /// let estimate = query.estimate_cost(&statistics)?;
+    /// ```
+    pub fn estimate_cost(&self, statistics: &GraphStatistics) -> Result<CostEstimate> {
+        if let Some(call) = &self.ast.procedure {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!("cost estimation of procedure '{}'", call.name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let (_, logical_plan) = self.create_graph_logical_plan(None)?;
+        crate::cost::estimate_cost_with_fingerprint(
+            &logical_plan,
+            statistics,
+            Some(&self.fingerprint()?),
+        )
     }
 
     /// Execute query with a DataFusion SessionContext, automatically building the catalog
@@ -404,21 +871,24 @@ impl CypherQuery {
         // Build catalog by querying SessionContext for table providers
         let mut catalog = InMemoryCatalog::new();
 
-        // Register node sources
-        for label in config.node_mappings.keys() {
-            let table_provider =
-                ctx.table_provider(label)
-                    .await
-                    .map_err(|e| GraphError::ConfigError {
-                        message: format!(
-                            "Node label '{}' not found in SessionContext: {}",
-                            label, e
-                        ),
-                        location: snafu::Location::new(file!(), line!(), column!()),
-                    })?;
+        // Register node sources (view labels are planned against their base label)
+        for mapping in config.node_mappings.values() {
+            if mapping.view_of.is_some() {
+                continue;
+            }
+            let table_name = mapping.table_name().to_lowercase();
+            let table_provider = ctx.table_provider(table_name.as_str()).await.map_err(|e| {
+                GraphError::ConfigError {
+                    message: format!(
+                        "Node label '{}' not found in SessionContext: {}",
+                        mapping.label, e
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                }
+            })?;
 
             let table_source = Arc::new(DefaultTableSource::new(table_provider));
-            catalog = catalog.with_node_source(label, table_source);
+            catalog = catalog.with_node_source(&table_name, table_source);
         }
 
         // Register relationship sources
@@ -483,11 +953,101 @@ impl CypherQuery {
         &self,
         catalog: std::sync::Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
         ctx: datafusion::execution::context::SessionContext,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        if let Some(call) = self
+            .ast
+            .procedure
+            .as_ref()
+            .filter(|c| crate::query_monitor::is_monitor_call(c))
+        {
+            return crate::query_monitor::execute_procedure(self, call).await;
+        }
+        // Executions nested in a tracked one (which carry its progress) are
+        // not tracked again
+        match self
+            .query_monitor
+            .as_ref()
+            .filter(|_| self.progress.is_none())
+        {
+            Some(monitor) => {
+                monitor
+                    .track(&self.query_text, |progress| {
+                        let mut query = self.clone();
+                        query.progress = Some(progress);
+                        async move { query.execute_planned(catalog, ctx).await }
+                    })
+                    .await
+            }
+            None => self.execute_planned(catalog, ctx).await,
+        }
+    }
+
+    /// Plan and execute with `catalog` and `ctx`, reporting progress if the
+    /// execution is tracked
+    async fn execute_planned(
+        &self,
+        catalog: std::sync::Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
+        ctx: datafusion::execution::context::SessionContext,
     ) -> Result<arrow::record_batch::RecordBatch> {
         use arrow::compute::concat_batches;
+        use futures::TryStreamExt;
+
+        crate::writing::check_read_only(&self.ast)?;
+        // Procedures run against the registered tables instead of a plan
+        if let Some(call) = &self.ast.procedure {
+            crate::summary::check_procedure(call)?;
+            crate::graph_diff::check_not_diff_call(call)?;
+            let summary = crate::summary::graph_summary(self.require_config()?, &ctx).await?;
+            return lookup::join_batch(summary, &self.lookup_joins).await;
+        }
+        // CALL subqueries run nested, once per distinct imported row
+        if let Some(call) = &self.ast.call_subquery {
+            require_feature(
+                &self.experimental_features,
+                ExperimentalFeature::CallSubquery,
+            )?;
+            let result = crate::call_subquery::execute(self, call, catalog, &ctx).await?;
+            return lookup::join_batch(result, &self.lookup_joins).await;
+        }
+        // Pattern comprehensions and COUNT subqueries run nested the same way
+        if let Some(rewritten) = crate::pattern_comprehension::rewrite(&self.ast)? {
+            let result = crate::call_subquery::execute_nested(
+                self,
+                &rewritten.ast,
+                &rewritten.subqueries,
+                rewritten.filter.as_ref(),
+                true,
+                catalog,
+                &ctx,
+            )
+            .await?;
+            return lookup::join_batch(result, &self.lookup_joins).await;
+        }
+
+        let cached = match &self.result_cache {
+            Some(cache) => self
+                .result_key(catalog.as_ref())?
+                .map(|key| (cache.clone(), key)),
+            None => None,
+        };
+        if let Some((cache, key)) = &cached {
+            if let Some(batch) = cache.lookup(key).await {
+                return Ok(batch);
+            }
+        }
 
-        // Create logical plans (phases 1-3)
-        let (_logical_plan, df_logical_plan) = self.create_logical_plans(catalog)?;
+        // Narrow vector top-k queries to index candidates and bound shortest
+        // path searches, then plan (phases 1-3)
+        let query = self.with_vector_candidates(catalog.as_ref(), None).await?;
+        let query = query.with_shortest_path_bounds(&catalog, &ctx).await?;
+        let truncation = TruncationFlags::default();
+        let observed = self
+            .selectivity_feedback
+            .as_ref()
+            .map(|_| ObservedPredicates::default());
+        let (_logical_plan, df_logical_plan) =
+            query.create_logical_plans(catalog, &truncation, observed.as_ref())?;
+        let deterministic = self.seed.is_some() || !result_cache::is_volatile(&df_logical_plan);
 
         // Execute the DataFusion plan (phase 4)
         let df = ctx
@@ -498,62 +1058,337 @@ impl CypherQuery {
                 location: snafu::Location::new(file!(), line!(), column!()),
             })?;
 
+        let task_ctx = df.task_ctx();
+        let physical_plan =
+            df.create_physical_plan()
+                .await
+                .map_err(|e| GraphError::ExecutionError {
+                    message: format!("Failed to create physical plan: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        let physical_plan = lookup::with_lookup_joins(physical_plan, &self.lookup_joins)?;
+
         // Get schema before collecting (in case result is empty)
-        let result_schema = df.schema().inner().clone();
+        let result_schema = physical_plan.schema();
 
-        // Collect results
-        let batches = df.collect().await.map_err(|e| GraphError::ExecutionError {
+        // Collect results, counting the rows produced so far
+        let task_ctx = match &self.progress {
+            Some(progress) => progress.start_executing(task_ctx),
+            None => Arc::new(task_ctx),
+        };
+        let collect_error = |e: datafusion::error::DataFusionError| GraphError::ExecutionError {
             message: format!("Failed to collect query results: {}", e),
             location: snafu::Location::new(file!(), line!(), column!()),
-        })?;
+        };
+        let mut stream = datafusion::physical_plan::execute_stream(physical_plan, task_ctx)
+            .map_err(collect_error)?;
+        let mut batches = Vec::new();
+        while let Some(batch) = stream.try_next().await.map_err(collect_error)? {
+            if let Some(progress) = &self.progress {
+                progress.add_rows(batch.num_rows());
+            }
+            batches.push(batch);
+        }
 
-        if batches.is_empty() {
+        let result = if batches.is_empty() {
             // Return empty batch with the schema from the DataFrame
             // This preserves column structure even when there are no rows
-            return Ok(arrow::record_batch::RecordBatch::new_empty(result_schema));
+            arrow::record_batch::RecordBatch::new_empty(result_schema)
+        } else {
+            // Combine all batches
+            let schema = batches[0].schema();
+            concat_batches(&schema, &batches).map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to concatenate result batches: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?
+        };
+        let result = truncation.snapshot().annotate(result)?;
+        if let Some((feedback, observed)) = self.selectivity_feedback.as_ref().zip(observed) {
+            observed.record_into(feedback, &self.fingerprint()?);
         }
 
-        // Combine all batches
-        let schema = batches[0].schema();
-        concat_batches(&schema, &batches).map_err(|e| GraphError::ExecutionError {
-            message: format!("Failed to concatenate result batches: {}", e),
-            location: snafu::Location::new(file!(), line!(), column!()),
-        })
+        if let Some((cache, key)) = cached.filter(|_| deterministic) {
+            cache.store(key, result.clone()).await;
+        }
+        Ok(result)
     }
 
-    /// Execute using the DataFusion planner with in-memory datasets
-    ///
-    /// # Overview
-    /// This convenience method creates both a catalog and session context from the provided
-    /// in-memory RecordBatches. It's ideal for testing and small datasets that fit in memory.
-    ///
-    /// For production use with external data sources (CSV, Parquet, databases), use
-    /// `execute_with_context` instead, which automatically builds the catalog
-    /// from the SessionContext.
-    ///
-    /// # Arguments
-    /// * `datasets` - HashMap of table name to RecordBatch (nodes and relationships)
-    ///
-    /// # Returns
-    /// A single RecordBatch containing the query results
-    async fn execute_datafusion(
+    /// This query with its shortest path patterns capped at the longest
+    /// shortest path a breadth-first search finds; see
+    /// [`crate::shortest_paths`]
+    pub(crate) async fn with_shortest_path_bounds(
         &self,
-        datasets: HashMap<String, arrow::record_batch::RecordBatch>,
-    ) -> Result<arrow::record_batch::RecordBatch> {
-        use std::sync::Arc;
-
-        // Build catalog and context from datasets
-        let (catalog, ctx) = self
-            .build_catalog_and_context_from_datasets(datasets)
-            .await?;
-
-        // Delegate to common execution logic
-        self.execute_with_catalog_and_context(Arc::new(catalog), ctx)
-            .await
+        catalog: &Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
+        ctx: &datafusion::execution::context::SessionContext,
+    ) -> Result<Cow<'_, Self>> {
+        Ok(
+            match shortest_paths::bound_path_lengths(self, catalog, ctx).await? {
+                Some(ast) => {
+                    let mut query = self.clone();
+                    query.ast = ast;
+                    Cow::Owned(query)
+                }
+                None => Cow::Borrowed(self),
+            },
+        )
     }
 
-    /// Helper to build catalog and context from in-memory datasets
-    async fn build_catalog_and_context_from_datasets(
+    /// Plan `ast` with the configuration and options of this query and
+    /// collect its result
+    pub(crate) async fn collect_ast(
+        &self,
+        ast: CypherAST,
+        catalog: Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
+        ctx: &datafusion::execution::context::SessionContext,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut query = self.clone();
+        query.ast = ast;
+        let (_, plan) = query.create_logical_plans(catalog, &TruncationFlags::default(), None)?;
+        Ok(ctx.execute_logical_plan(plan).await?.collect().await?)
+    }
+
+    /// [`Self::collect_ast`] as a single batch, which has the columns of the
+    /// plan even without rows
+    pub(crate) async fn collect_ast_batch(
+        &self,
+        ast: CypherAST,
+        catalog: Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
+        ctx: &datafusion::execution::context::SessionContext,
+    ) -> Result<RecordBatch> {
+        let mut query = self.clone();
+        query.ast = ast;
+        let (_, plan) = query.create_logical_plans(catalog, &TruncationFlags::default(), None)?;
+        let plan_schema = plan.schema().inner().clone();
+        let batches = ctx.execute_logical_plan(plan).await?.collect().await?;
+        let schema = batches.first().map_or(plan_schema, |batch| batch.schema());
+        Ok(arrow::compute::concat_batches(&schema, &batches)?)
+    }
+
+    /// This query restricted to the candidates of its vector index, if it
+    /// ranks by an indexed embedding column; see [`crate::vector_candidates`]
+    pub(crate) async fn with_vector_candidates(
+        &self,
+        catalog: &dyn lance_graph_catalog::GraphSourceCatalog,
+        seeds: Option<&vector_candidates::SeedCache>,
+    ) -> Result<Cow<'_, Self>> {
+        let mut ast = self.ast.clone();
+        vector_candidates::bind_vector_parameters(&mut ast, &self.parameters);
+        let restricted =
+            vector_candidates::restrict_to_candidates(&ast, self.require_config()?, catalog, seeds)
+                .await?;
+        Ok(match restricted {
+            Some(ast) => {
+                let mut query = self.clone();
+                query.ast = ast;
+                Cow::Owned(query)
+            }
+            None => Cow::Borrowed(self),
+        })
+    }
+
+    /// Result cache key of this query over the datasets of `catalog`, or
+    /// `None` when they are not all versioned
+    fn result_key(
+        &self,
+        catalog: &dyn lance_graph_catalog::GraphSourceCatalog,
+    ) -> Result<Option<ResultKey>> {
+        // Table functions and lookups read from outside the graph, which has
+        // no version
+        if !self.lookup_joins.is_empty()
+            || self
+                .ast
+                .reading_clauses
+                .iter()
+                .any(|clause| matches!(clause, ReadingClause::TableFunction(_)))
+        {
+            return Ok(None);
+        }
+        let Some(versions) = result_cache::dataset_versions(self.require_config()?, catalog) else {
+            return Ok(None);
+        };
+        Ok(Some(ResultKey {
+            versions,
+            ..self.plan_key()?
+        }))
+    }
+
+    /// What the plan of this query depends on, besides the datasets
+    fn plan_key(&self) -> Result<ResultKey> {
+        let canonical = |value: serde_json::Result<serde_json::Value>, what: &str| {
+            value
+                .map(|value| result_cache::canonical_json(&value))
+                .map_err(|e| GraphError::ExecutionError {
+                    message: format!("Failed to serialize {}: {}", what, e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+        };
+        Ok(ResultKey {
+            query: format!(
+                "{}\nseed={:?} provenance={} limits={:?} overflow={:?} nfc={}",
+                canonical(serde_json::to_value(&self.ast), "query AST")?,
+                self.seed,
+                self.include_provenance,
+                self.expansion_limits,
+                self.overflow_mode,
+                self.unicode_normalization
+            ),
+            config: canonical(serde_json::to_value(self.require_config()?), "graph config")?,
+            parameters: canonical(serde_json::to_value(&self.parameters), "query parameters")?,
+            versions: Vec::new(),
+        })
+    }
+
+    /// Hash of what the query's plan depends on: its AST, parameters, graph
+    /// configuration and the options that change its result
+    ///
+    /// Unlike `std` hashes it is the same in every process and build, so engine
+    /// instances sharing a remote plan or result cache agree on it. Queries
+    /// that differ only in whitespace or the order of map entries hash alike.
+    pub fn plan_hash(&self) -> Result<String> {
+        Ok(self.plan_key()?.stable_hash())
+    }
+
+    /// Execute using the DataFusion planner with in-memory datasets
+    ///
+    /// # Overview
+    /// This convenience method creates both a catalog and session context from the provided
+    /// in-memory RecordBatches. It's ideal for testing and small datasets that fit in memory.
+    ///
+    /// For production use with external data sources (CSV, Parquet, databases), use
+    /// `execute_with_context` instead, which automatically builds the catalog
+    /// from the SessionContext.
+    ///
+    /// # Arguments
+    /// * `datasets` - HashMap of table name to RecordBatch (nodes and relationships)
+    ///
+    /// # Returns
+    /// A single RecordBatch containing the query results
+    async fn execute_datafusion(
+        &self,
+        datasets: HashMap<String, arrow::record_batch::RecordBatch>,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        use std::sync::Arc;
+
+        // Build catalog and context from datasets
+        let (catalog, ctx) = self
+            .build_catalog_and_context_from_datasets(datasets)
+            .await?;
+
+        // Delegate to common execution logic
+        self.execute_with_catalog_and_context(Arc::new(catalog), ctx)
+            .await
+    }
+
+    /// Execute with the DataFusion planner, sending each result batch to
+    /// `sender` as soon as it is produced
+    ///
+    /// The consumer (e.g. an actor's mailbox) works on early batches while the
+    /// query is still running. Sending waits for channel capacity, so a bounded
+    /// channel limits how far execution runs ahead of the consumer. Dropping
+    /// the receiver stops execution early without an error.
+    ///
+    /// Streamed results are not memoized and carry no truncation markers.
+    pub async fn execute_into(
+        &self,
+        datasets: HashMap<String, RecordBatch>,
+        mut sender: futures::channel::mpsc::Sender<RecordBatch>,
+    ) -> Result<()> {
+        use futures::{SinkExt, TryStreamExt};
+
+        let mut stream = self.execute_batch_stream(datasets).await?;
+        while let Some(batch) = stream.try_next().await? {
+            if sender.send(batch).await.is_err() {
+                // Receiver dropped: nobody wants the remaining batches
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute with the DataFusion planner, calling `callback` on each result
+    /// batch as soon as it is produced
+    ///
+    /// An error returned by `callback` stops execution and is returned.
+    /// Like [`CypherQuery::execute_into`], results are not memoized.
+    pub async fn execute_with_callback<F>(
+        &self,
+        datasets: HashMap<String, RecordBatch>,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(RecordBatch) -> Result<()>,
+    {
+        use futures::TryStreamExt;
+
+        let mut stream = self.execute_batch_stream(datasets).await?;
+        while let Some(batch) = stream.try_next().await? {
+            callback(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Plan this query over in-memory datasets and start executing it,
+    /// yielding result batches as DataFusion produces them
+    async fn execute_batch_stream(
+        &self,
+        datasets: HashMap<String, RecordBatch>,
+    ) -> Result<futures::stream::BoxStream<'static, Result<RecordBatch>>> {
+        use futures::{StreamExt, TryStreamExt};
+
+        let (catalog, ctx) = self
+            .build_catalog_and_context_from_datasets(datasets)
+            .await?;
+
+        crate::writing::check_read_only(&self.ast)?;
+        if let Some(call) = &self.ast.procedure {
+            crate::summary::check_procedure(call)?;
+            crate::graph_diff::check_not_diff_call(call)?;
+            let batch = if crate::query_monitor::is_monitor_call(call) {
+                crate::query_monitor::execute_procedure(self, call).await?
+            } else {
+                crate::summary::graph_summary(self.require_config()?, &ctx).await?
+            };
+            let batch = lookup::join_batch(batch, &self.lookup_joins).await?;
+            return Ok(futures::stream::once(async move { Ok(batch) }).boxed());
+        }
+
+        let catalog: Arc<dyn lance_graph_catalog::GraphSourceCatalog> = Arc::new(catalog);
+        let query = self.with_vector_candidates(catalog.as_ref(), None).await?;
+        let query = query.with_shortest_path_bounds(&catalog, &ctx).await?;
+        let (_, df_logical_plan) =
+            query.create_logical_plans(catalog, &TruncationFlags::default(), None)?;
+        let df = ctx
+            .execute_logical_plan(df_logical_plan)
+            .await
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to execute DataFusion plan: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let task_ctx = df.task_ctx();
+        let physical_plan =
+            df.create_physical_plan()
+                .await
+                .map_err(|e| GraphError::ExecutionError {
+                    message: format!("Failed to create physical plan: {}", e),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        let physical_plan = lookup::with_lookup_joins(physical_plan, &self.lookup_joins)?;
+        let stream = datafusion::physical_plan::execute_stream(physical_plan, Arc::new(task_ctx))
+            .map_err(|e| GraphError::ExecutionError {
+            message: format!("Failed to start query execution: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+        let stream = stream
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to produce query results: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+            .boxed();
+        Ok(coalesce_stream(stream, self.batch_coalescing))
+    }
+
+    /// Helper to build catalog and context from in-memory datasets
+    pub(crate) async fn build_catalog_and_context_from_datasets(
         &self,
         datasets: HashMap<String, arrow::record_batch::RecordBatch>,
     ) -> Result<(
@@ -561,7 +1396,6 @@ impl CypherQuery {
         datafusion::execution::context::SessionContext,
     )> {
         use datafusion::datasource::{DefaultTableSource, MemTable};
-        use datafusion::execution::context::SessionContext;
         use lance_graph_catalog::InMemoryCatalog;
         use std::sync::Arc;
 
@@ -573,7 +1407,7 @@ impl CypherQuery {
         }
 
         // Create session context and catalog
-        let ctx = SessionContext::new();
+        let ctx = crate::clustering::session_context(self.config.as_ref());
         let mut catalog = InMemoryCatalog::new();
 
         // Register all datasets as tables
@@ -591,6 +1425,8 @@ impl CypherQuery {
                     location: snafu::Location::new(file!(), line!(), column!()),
                 })?,
             );
+            let mem_table =
+                crate::clustering::with_declared_order(self.config.as_ref(), name, mem_table);
 
             // Normalize table name to lowercase
             let normalized_name = name.to_lowercase();
@@ -615,25 +1451,35 @@ impl CypherQuery {
     }
 
     /// Helper to build catalog and context using a namespace resolver
-    async fn build_catalog_and_context_from_namespace(
+    pub(crate) async fn build_catalog_and_context_from_namespace(
         &self,
         namespace: std::sync::Arc<dyn lance_namespace::LanceNamespace + Send + Sync>,
     ) -> Result<(
         lance_graph_catalog::InMemoryCatalog,
         datafusion::execution::context::SessionContext,
     )> {
-        use datafusion::datasource::{DefaultTableSource, TableProvider};
-        use datafusion::execution::context::SessionContext;
-        use lance::datafusion::LanceTableProvider;
-        use lance_graph_catalog::InMemoryCatalog;
-        use std::sync::Arc;
+        let providers = self
+            .open_namespace_tables(namespace, self.namespace_tables()?)
+            .await?;
+        self.catalog_and_context_from_providers(&providers)
+    }
 
+    /// Names of the datasets the configuration reads, as the namespace knows them
+    pub(crate) fn namespace_tables(&self) -> Result<HashSet<String>> {
         let config = self.require_config()?;
 
         let mut required_tables: HashSet<String> = HashSet::new();
         // Use original label/type names (not lowercase keys) for namespace resolution
         // The namespace needs the original casing to find files on disk
-        required_tables.extend(config.node_mappings.values().map(|m| m.label.clone()));
+        // View labels share their base label's dataset and need no table of their own;
+        // labels stored in a shared table resolve that table instead of their own name
+        required_tables.extend(
+            config
+                .node_mappings
+                .values()
+                .filter(|m| m.view_of.is_none())
+                .map(|m| m.table_name().to_string()),
+        );
         required_tables.extend(
             config
                 .relationship_mappings
@@ -649,12 +1495,58 @@ impl CypherQuery {
                 location: snafu::Location::new(file!(), line!(), column!()),
             });
         }
+        Ok(required_tables)
+    }
 
-        let ctx = SessionContext::new();
-        let mut catalog = InMemoryCatalog::new();
-        let mut providers: HashMap<String, Arc<dyn TableProvider>> = HashMap::new();
+    /// Open `tables` through the namespace, keyed by lowercase table name
+    ///
+    /// Inside a write transaction, scans also return the rows it staged.
+    pub(crate) async fn open_namespace_tables(
+        &self,
+        namespace: std::sync::Arc<dyn lance_namespace::LanceNamespace + Send + Sync>,
+        tables: HashSet<String>,
+    ) -> Result<HashMap<String, std::sync::Arc<dyn datafusion::datasource::TableProvider>>> {
+        use datafusion::datasource::TableProvider;
+        use lance::datafusion::LanceTableProvider;
+        use std::sync::Arc;
+
+        // Datasets the transaction creates only exist in its delta so far
+        let opened = match &self.delta {
+            Some(delta) => tables
+                .iter()
+                .filter(|table| !delta.is_new(table))
+                .cloned()
+                .collect(),
+            None => tables.clone(),
+        };
+        let providers: HashMap<String, Arc<dyn TableProvider>> = self
+            .open_namespace_datasets(namespace, opened)
+            .await?
+            .into_iter()
+            .map(|(name, dataset)| {
+                let provider: Arc<dyn TableProvider> =
+                    Arc::new(LanceTableProvider::new(dataset, true, true));
+                (name, provider)
+            })
+            .collect();
+        match &self.delta {
+            Some(delta) => delta.overlay(&tables, providers),
+            None => Ok(providers),
+        }
+    }
+
+    /// Open the latest version of `tables` through the namespace, keyed by
+    /// lowercase table name
+    pub(crate) async fn open_namespace_datasets(
+        &self,
+        namespace: std::sync::Arc<dyn lance_namespace::LanceNamespace + Send + Sync>,
+        tables: HashSet<String>,
+    ) -> Result<HashMap<String, std::sync::Arc<lance::dataset::Dataset>>> {
+        use std::sync::Arc;
+
+        let mut datasets = HashMap::new();
 
-        for table_name in required_tables {
+        for table_name in tables {
             let mut request = DescribeTableRequest::new();
             request.id = Some(vec![table_name.clone()]);
 
@@ -678,20 +1570,64 @@ impl CypherQuery {
                 location: snafu::Location::new(file!(), line!(), column!()),
             })?;
 
-            let dataset = lance::dataset::Dataset::open(&location)
-                .await
-                .map_err(|e| GraphError::ConfigError {
-                    message: format!("Failed to open dataset for table '{}': {}", table_name, e),
-                    location: snafu::Location::new(file!(), line!(), column!()),
-                })?;
+            let mut builder = lance::dataset::builder::DatasetBuilder::from_uri(&location);
+            let mut storage_options = response.storage_options.unwrap_or_default();
+            if let Some(provider) = &self.credentials_provider {
+                let credentials = provider.credentials(&table_name, &location).await?;
+                storage_options.extend(credentials.to_storage_options());
+                // Lance refreshes expiring credentials through the provider and
+                // requires the expiry of the initial ones to schedule it
+                if credentials.expires_at.is_some() {
+                    builder = builder.with_storage_options_provider(Arc::new(
+                        DatasetCredentials::new(provider.clone(), &table_name, &location),
+                    ));
+                }
+            }
+            if !storage_options.is_empty() {
+                builder = builder.with_storage_options(storage_options);
+            }
+            let dataset = builder.load().await.map_err(|e| GraphError::ConfigError {
+                message: format!("Failed to open dataset for table '{}': {}", table_name, e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
 
-            let dataset = Arc::new(dataset);
-            let provider: Arc<dyn TableProvider> =
-                Arc::new(LanceTableProvider::new(dataset.clone(), true, true));
+            // Store dataset with normalized (lowercase) key for consistent lookup
+            datasets.insert(table_name.to_lowercase(), Arc::new(dataset));
+        }
 
-            // Register with lowercase table name for case-insensitive behavior
+        Ok(datasets)
+    }
+
+    /// Catalog and session context of this query's configuration over
+    /// opened datasets
+    pub(crate) fn catalog_and_context_from_providers(
+        &self,
+        providers: &HashMap<String, std::sync::Arc<dyn datafusion::datasource::TableProvider>>,
+    ) -> Result<(
+        lance_graph_catalog::InMemoryCatalog,
+        datafusion::execution::context::SessionContext,
+    )> {
+        use datafusion::datasource::DefaultTableSource;
+        use lance_graph_catalog::InMemoryCatalog;
+        use std::sync::Arc;
+
+        let config = self.require_config()?;
+        let ctx = crate::clustering::session_context(Some(config));
+        let mut catalog = InMemoryCatalog::new();
+        // Providers reporting the sort order the configuration declares
+        let provider = |table: &str| {
+            providers.get(&table.to_lowercase()).map(|provider| {
+                crate::clustering::with_declared_order(Some(config), table, provider.clone())
+            })
+        };
+
+        for table_name in self.namespace_tables()? {
             let normalized_table_name = table_name.to_lowercase();
-            ctx.register_table(&normalized_table_name, provider.clone())
+            let Some(provider) = provider(&table_name) else {
+                continue;
+            };
+            // Register with lowercase table name for case-insensitive behavior
+            ctx.register_table(&normalized_table_name, provider)
                 .map_err(|e| GraphError::PlanError {
                     message: format!(
                         "Failed to register table '{}' in SessionContext: {}",
@@ -699,38 +1635,35 @@ impl CypherQuery {
                     ),
                     location: snafu::Location::new(file!(), line!(), column!()),
                 })?;
-
-            // Store provider with normalized (lowercase) key for consistent lookup
-            providers.insert(normalized_table_name.clone(), provider);
         }
 
-        for label in config.node_mappings.keys() {
-            let provider = providers
-                .get(label)
-                .ok_or_else(|| GraphError::ConfigError {
-                    message: format!(
-                        "Namespace did not resolve dataset for node label '{}'",
-                        label
-                    ),
-                    location: snafu::Location::new(file!(), line!(), column!()),
-                })?;
+        for mapping in config.node_mappings.values() {
+            if mapping.view_of.is_some() {
+                continue;
+            }
+            let table_name = mapping.table_name();
+            let provider = provider(table_name).ok_or_else(|| GraphError::ConfigError {
+                message: format!(
+                    "Namespace did not resolve dataset for node label '{}'",
+                    mapping.label
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
 
-            let table_source = Arc::new(DefaultTableSource::new(provider.clone()));
-            catalog = catalog.with_node_source(label, table_source);
+            let table_source = Arc::new(DefaultTableSource::new(provider));
+            catalog = catalog.with_node_source(table_name, table_source);
         }
 
         for rel_type in config.relationship_mappings.keys() {
-            let provider = providers
-                .get(rel_type)
-                .ok_or_else(|| GraphError::ConfigError {
-                    message: format!(
-                        "Namespace did not resolve dataset for relationship type '{}'",
-                        rel_type
-                    ),
-                    location: snafu::Location::new(file!(), line!(), column!()),
-                })?;
+            let provider = provider(rel_type).ok_or_else(|| GraphError::ConfigError {
+                message: format!(
+                    "Namespace did not resolve dataset for relationship type '{}'",
+                    rel_type
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
 
-            let table_source = Arc::new(DefaultTableSource::new(provider.clone()));
+            let table_source = Arc::new(DefaultTableSource::new(provider));
             catalog = catalog.with_relationship_source(rel_type, table_source);
         }
 
@@ -745,10 +1678,15 @@ impl CypherQuery {
     ) -> Result<String> {
         // Create all plans (phases 1-4)
         let (logical_plan, df_logical_plan, physical_plan) =
-            self.create_plans(catalog, &ctx).await?;
+            self.create_plans(catalog.clone(), &ctx).await?;
 
         // Format the explain output
-        self.format_explain_output(&logical_plan, &df_logical_plan, physical_plan.as_ref())
+        self.format_explain_output(
+            catalog.as_ref(),
+            &logical_plan,
+            &df_logical_plan,
+            physical_plan.as_ref(),
+        )
     }
 
     /// Helper to create logical plans (graph logical, DataFusion logical)
@@ -758,18 +1696,82 @@ impl CypherQuery {
     fn create_logical_plans(
         &self,
         catalog: std::sync::Arc<dyn lance_graph_catalog::GraphSourceCatalog>,
+        truncation: &TruncationFlags,
+        observed: Option<&ObservedPredicates>,
     ) -> Result<(
         crate::logical_plan::LogicalOperator,
         datafusion::logical_expr::LogicalPlan,
     )> {
         use crate::datafusion_planner::{DataFusionPlanner, GraphPhysicalPlanner};
+
+        let config = self.require_config()?;
+        let (query, logical_plan) = self.create_graph_logical_plan(Some(catalog.as_ref()))?;
+
+        // Phase 3: DataFusion Logical Plan
+        let df_planner = DataFusionPlanner::with_catalog(config.clone(), catalog)
+            .with_include_deleted(query.ast.include_deleted)
+            .with_provenance(self.include_provenance)
+            .with_parameters(self.parameters.clone())
+            .with_seed(self.seed)
+            .with_expansion_limits(self.expansion_limits, truncation.clone())
+            .with_overflow_mode(self.overflow_mode)
+            .with_unicode_normalization(self.unicode_normalization)
+            .with_table_functions(self.table_functions.clone());
+        let df_planner = match &self.statistics {
+            Some(statistics) => df_planner.with_statistics(statistics.clone()),
+            None => df_planner,
+        };
+        let df_planner = match observed {
+            Some(observed) => df_planner.with_observed_predicates(observed.clone()),
+            None => df_planner,
+        };
+        let df_logical_plan = df_planner.plan(&logical_plan)?;
+
+        Ok((logical_plan, df_logical_plan))
+    }
+
+    /// Phases 1-2 of query execution: bind parameters, run semantic analysis
+    /// and build the graph logical plan
+    ///
+    /// Returns the query with its parameters bound alongside the plan.
+    /// Introspection functions read property names from the schemas of the
+    /// `catalog` sources, or from the configuration without one.
+    fn create_graph_logical_plan(
+        &self,
+        catalog: Option<&dyn lance_graph_catalog::GraphSourceCatalog>,
+    ) -> Result<(Cow<'_, Self>, crate::logical_plan::LogicalOperator)> {
         use crate::semantic::SemanticAnalyzer;
+        use datafusion::logical_expr::TableSource;
 
         let config = self.require_config()?;
+        crate::call_subquery::check_no_subquery(&self.ast)?;
+        crate::pattern_comprehension::check_none(&self.ast)?;
+        let mut query = self.bind_label_parameters()?;
+        if !self.parameters.is_empty() {
+            vector_candidates::bind_vector_parameters(&mut query.to_mut().ast, &self.parameters);
+            crate::temporal::bind_parameters(&mut query.to_mut().ast, &self.parameters)?;
+            crate::template::bind_value_parameters(&mut query.to_mut().ast, &self.parameters)?;
+        }
+        let schema_of = |name: &str, is_label: bool| {
+            let catalog = catalog?;
+            let source = if is_label {
+                catalog.node_source(name)
+            } else {
+                catalog.relationship_source(name)
+            };
+            source.map(|source| source.schema())
+        };
+        let mut resolved = query.ast.clone();
+        crate::introspection::resolve(&mut resolved, config, &schema_of)?;
+        if resolved != query.ast {
+            query.to_mut().ast = resolved;
+        }
+        let ast = &query.ast;
 
         // Phase 1: Semantic Analysis
-        let mut analyzer = SemanticAnalyzer::new(config.clone());
-        let semantic = analyzer.analyze(&self.ast)?;
+        let mut analyzer =
+            SemanticAnalyzer::new(config.clone()).with_compatibility_mode(self.compatibility_mode);
+        let semantic = analyzer.analyze(ast)?;
         if !semantic.errors.is_empty() {
             return Err(GraphError::PlanError {
                 message: format!("Semantic analysis failed:\n{}", semantic.errors.join("\n")),
@@ -779,13 +1781,70 @@ impl CypherQuery {
 
         // Phase 2: Graph Logical Plan
         let mut logical_planner = LogicalPlanner::new(config);
-        let logical_plan = logical_planner.plan(&self.ast)?;
+        let logical_plan = logical_planner.plan(ast)?;
+        Ok((query, logical_plan))
+    }
 
-        // Phase 3: DataFusion Logical Plan
-        let df_planner = DataFusionPlanner::with_catalog(config.clone(), catalog);
-        let df_logical_plan = df_planner.plan(&logical_plan)?;
+    /// Substitute `$param` labels and relationship types from the parameters
+    ///
+    /// Each parameter must be a string naming a label (or relationship type)
+    /// of the configuration. It replaces the pattern entry as a whole, so a
+    /// parameter value can never change the structure of the query.
+    pub(crate) fn bind_label_parameters(&self) -> Result<Cow<'_, Self>> {
+        let config = self.require_config()?;
+        let mut ast = self.ast.clone();
+        let mut bound = false;
+        ast.try_for_each_pattern_names_mut(|names, is_label| {
+            let kind = if is_label {
+                "label"
+            } else {
+                "relationship type"
+            };
+            for name in names.iter_mut() {
+                let Some(param) = name.strip_prefix('$') else {
+                    continue;
+                };
+                let value = match self.parameters.get(param) {
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(other) => {
+                        return Err(GraphError::PlanError {
+                            message: format!(
+                                "Parameter ${} used as a {} must be a string, got {}",
+                                param, kind, other
+                            ),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        })
+                    }
+                    None => {
+                        return Err(GraphError::PlanError {
+                            message: format!("Missing value for parameter ${}", param),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        })
+                    }
+                };
+                let known = if is_label {
+                    config.get_node_mapping(&value).is_some()
+                } else {
+                    config.get_relationship_mapping(&value).is_some()
+                };
+                if !known {
+                    return Err(GraphError::PlanError {
+                        message: format!("Parameter ${} names unknown {} '{}'", param, kind, value),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                *name = value;
+                bound = true;
+            }
+            Ok(())
+        })?;
 
-        Ok((logical_plan, df_logical_plan))
+        if !bound {
+            return Ok(Cow::Borrowed(self));
+        }
+        let mut query = self.clone();
+        query.ast = ast;
+        Ok(Cow::Owned(query))
     }
 
     /// Helper to create all plans (graph logical, DataFusion logical, physical)
@@ -799,7 +1858,8 @@ impl CypherQuery {
         std::sync::Arc<dyn datafusion::physical_plan::ExecutionPlan>,
     )> {
         // Phases 1-3: Create logical plans
-        let (logical_plan, df_logical_plan) = self.create_logical_plans(catalog)?;
+        let (logical_plan, df_logical_plan) =
+            self.create_logical_plans(catalog, &TruncationFlags::default(), None)?;
 
         // Phase 4: DataFusion Physical Plan
         let df = ctx
@@ -817,6 +1877,7 @@ impl CypherQuery {
                     message: format!("Failed to create physical plan: {}", e),
                     location: snafu::Location::new(file!(), line!(), column!()),
                 })?;
+        let physical_plan = lookup::with_lookup_joins(physical_plan, &self.lookup_joins)?;
 
         Ok((logical_plan, df_logical_plan, physical_plan))
     }
@@ -824,6 +1885,7 @@ impl CypherQuery {
     /// Format explain output as a table
     fn format_explain_output(
         &self,
+        catalog: &dyn lance_graph_catalog::GraphSourceCatalog,
         logical_plan: &crate::logical_plan::LogicalOperator,
         df_logical_plan: &datafusion::logical_expr::LogicalPlan,
         physical_plan: &dyn datafusion::physical_plan::ExecutionPlan,
@@ -842,6 +1904,15 @@ impl CypherQuery {
         let graph_plan_str = format!("{:#?}", logical_plan);
         rows.push(("graph_logical_plan", graph_plan_str));
 
+        // Filters the statistics prove keep no row
+        if let Some(statistics) = &self.statistics {
+            let pruned = crate::pruning::pruned_filters(logical_plan, statistics, Some(catalog));
+            if !pruned.is_empty() {
+                let pruned: Vec<String> = pruned.iter().map(|p| p.to_string()).collect();
+                rows.push(("pruned_filters", pruned.join("\n")));
+            }
+        }
+
         // Row 2: DataFusion Logical Plan
         let df_logical_str = format!("{}", df_logical_plan.display_indent());
         rows.push(("logical_plan", df_logical_str));
@@ -931,6 +2002,18 @@ impl CypherQuery {
     pub async fn execute_simple(
         &self,
         datasets: HashMap<String, arrow::record_batch::RecordBatch>,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        let mut query = self.bind_label_parameters()?;
+        if !self.parameters.is_empty() {
+            crate::template::bind_value_parameters(&mut query.to_mut().ast, &self.parameters)?;
+        }
+        query.execute_simple_bound(datasets).await
+    }
+
+    /// [`Self::execute_simple`] once parameters are bound
+    async fn execute_simple_bound(
+        &self,
+        datasets: HashMap<String, arrow::record_batch::RecordBatch>,
     ) -> Result<arrow::record_batch::RecordBatch> {
         use crate::semantic::SemanticAnalyzer;
         use arrow::compute::concat_batches;
@@ -940,9 +2023,12 @@ impl CypherQuery {
 
         // Require a config for now, even if we don't fully exploit it yet
         let config = self.require_config()?.clone();
+        crate::call_subquery::check_no_subquery(&self.ast)?;
+        crate::pattern_comprehension::check_none(&self.ast)?;
 
         // Ensure we don't silently ignore unsupported features (e.g. scalar functions).
-        let mut analyzer = SemanticAnalyzer::new(config);
+        let mut analyzer =
+            SemanticAnalyzer::new(config).with_compatibility_mode(self.compatibility_mode);
         let semantic = analyzer.analyze(&self.ast)?;
         if !semantic.errors.is_empty() {
             return Err(GraphError::PlanError {
@@ -950,33 +2036,107 @@ impl CypherQuery {
                 location: snafu::Location::new(file!(), line!(), column!()),
             });
         }
-
-        if datasets.is_empty() {
-            return Err(GraphError::PlanError {
-                message: "No input datasets provided".to_string(),
+        if !self.ast.return_clause.distinct_on.is_empty() {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "DISTINCT ON with the simple execution strategy".to_string(),
                 location: snafu::Location::new(file!(), line!(), column!()),
             });
         }
-
-        // Create DataFusion context and register all provided tables
-        // Normalize schemas and table names for case-insensitive behavior
-        let ctx = SessionContext::new();
-        for (name, batch) in &datasets {
-            let normalized_batch = normalize_record_batch(batch)?;
-            let table = MemTable::try_new(
-                normalized_batch.schema(),
-                vec![vec![normalized_batch.clone()]],
-            )
-            .map_err(|e| GraphError::PlanError {
-                message: format!("Failed to create DataFusion table: {}", e),
+        let binds_path_variable = self.ast.reading_clauses.iter().any(|clause| {
+            matches!(clause, ReadingClause::Match(m) if m.patterns.iter().any(|pattern| {
+                matches!(pattern, crate::ast::GraphPattern::Path(path) if path.variable.is_some())
+            }))
+        });
+        if !self.expansion_limits.is_unlimited() {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "expansion limits with the simple execution strategy".to_string(),
                 location: snafu::Location::new(file!(), line!(), column!()),
-            })?;
-
-            // Register with lowercase table name
-            let normalized_name = name.to_lowercase();
-            ctx.register_table(&normalized_name, Arc::new(table))
-                .map_err(|e| GraphError::PlanError {
-                    message: format!("Failed to register table '{}': {}", name, e),
+            });
+        }
+        if binds_path_variable {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "path variables with the simple execution strategy".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let has_shortest_path = self
+            .ast
+            .reading_clauses
+            .iter()
+            .chain(&self.ast.post_with_reading_clauses)
+            .any(|clause| {
+                matches!(clause, ReadingClause::Match(m) if m.patterns.iter().any(|pattern| {
+                    matches!(pattern, crate::ast::GraphPattern::Path(path) if path.shortest.is_some())
+                }))
+            });
+        if has_shortest_path {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "shortestPath() with the simple execution strategy".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let has_optional_match = self
+            .ast
+            .reading_clauses
+            .iter()
+            .chain(&self.ast.post_with_reading_clauses)
+            .any(|clause| matches!(clause, ReadingClause::Match(m) if m.optional));
+        if has_optional_match {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "OPTIONAL MATCH with the simple execution strategy".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let has_exists_subquery = [&self.ast.where_clause, &self.ast.post_with_where_clause]
+            .into_iter()
+            .flatten()
+            .any(|w| crate::logical_plan::contains_exists_subquery(&w.expression));
+        if has_exists_subquery {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "EXISTS subqueries with the simple execution strategy".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        if self.ast.sample.is_some() {
+            return Err(GraphError::UnsupportedFeature {
+                feature: "SAMPLE with the simple execution strategy".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        crate::writing::check_read_only(&self.ast)?;
+        if let Some(call) = &self.ast.procedure {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!("CALL {}() with the simple execution strategy", call.name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        if datasets.is_empty() {
+            return Err(GraphError::PlanError {
+                message: "No input datasets provided".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        // Create DataFusion context and register all provided tables
+        // Normalize schemas and table names for case-insensitive behavior
+        let ctx = SessionContext::new();
+        for (name, batch) in &datasets {
+            let normalized_batch = normalize_record_batch(batch)?;
+            let table = MemTable::try_new(
+                normalized_batch.schema(),
+                vec![vec![normalized_batch.clone()]],
+            )
+            .map_err(|e| GraphError::PlanError {
+                message: format!("Failed to create DataFusion table: {}", e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+            // Register with lowercase table name
+            let normalized_name = name.to_lowercase();
+            ctx.register_table(&normalized_name, Arc::new(table))
+                .map_err(|e| GraphError::PlanError {
+                    message: format!("Failed to register table '{}': {}", name, e),
                     location: snafu::Location::new(file!(), line!(), column!()),
                 })?;
         }
@@ -1139,6 +2299,9 @@ impl CypherQuery {
                 ReadingClause::Unwind(unwind_clause) => {
                     variables.push(unwind_clause.alias.clone());
                 }
+                ReadingClause::TableFunction(call) => {
+                    variables.extend(call.yields.iter().map(|item| item.variable().to_string()));
+                }
             }
         }
 
@@ -1301,8 +2464,10 @@ impl CypherQuery {
                 for hops in min_len..=max_len {
                     // Build a fixed-length synthetic path by repeating the single segment
                     let mut synthetic = crate::ast::PathPattern {
+                        variable: None,
                         start_node: path.start_node.clone(),
                         segments: Vec::with_capacity(hops as usize),
+                        shortest: None,
                     };
 
                     for i in 0..hops {
@@ -1373,6 +2538,8 @@ impl CypherQueryBuilder {
 
         let match_clause = crate::ast::MatchClause {
             patterns: vec![crate::ast::GraphPattern::Node(node)],
+            optional: false,
+            where_clause: None,
         };
 
         self.match_clauses.push(match_clause);
@@ -1445,6 +2612,7 @@ impl CypherQueryBuilder {
             post_with_where_clause: None,
             return_clause: crate::ast::ReturnClause {
                 distinct: self.distinct,
+                distinct_on: Vec::new(),
                 items: self.return_items,
             },
             order_by: if self.order_by_items.is_empty() {
@@ -1456,6 +2624,15 @@ impl CypherQueryBuilder {
             },
             limit: self.limit,
             skip: self.skip,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         // Generate query text from AST (simplified)
@@ -1466,6 +2643,26 @@ impl CypherQueryBuilder {
             ast,
             config: self.config,
             parameters: self.parameters,
+            compatibility_mode: CompatibilityMode::default(),
+            experimental_features: BTreeSet::new(),
+            include_provenance: false,
+            seed: None,
+            result_cache: None,
+            session_attributes: HashMap::new(),
+            credentials_provider: None,
+            expansion_limits: ExpansionLimits::default(),
+            runtimes: None,
+            workload_class: WorkloadClass::default(),
+            selectivity_feedback: None,
+            statistics: None,
+            overflow_mode: OverflowMode::default(),
+            unicode_normalization: false,
+            batch_coalescing: BatchCoalescing::default(),
+            table_functions: HashMap::new(),
+            lookup_joins: Vec::new(),
+            query_monitor: None,
+            progress: None,
+            delta: None,
         };
 
         Ok(query)
@@ -2328,6 +3525,505 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn tenant_namespace_reads_the_session_tenant() {
+        use arrow_array::RecordBatch;
+        use tempfile::tempdir;
+
+        let tmp_dir = tempdir().unwrap();
+        let people = build_people_batch();
+        for (tenant, rows) in [("acme", 4), ("globex", 1)] {
+            let dir = tmp_dir.path().join(tenant);
+            write_lance_dataset(&dir.join("Person.lance"), people.slice(0, rows)).await;
+            write_lance_dataset(&dir.join("FRIEND_OF.lance"), build_friendship_batch()).await;
+        }
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let namespace = TenantNamespace::new(format!("{}/{{tenant}}", tmp_dir.path().display()));
+        let query = CypherQuery::new("MATCH (p:Person) RETURN p.name")
+            .unwrap()
+            .with_config(config);
+
+        let run = |tenant: &str| {
+            let query = query.clone().with_session_attribute("tenant", tenant);
+            let namespace = &namespace;
+            async move { query.execute_with_tenant_namespace(namespace, None).await }
+        };
+        let acme: RecordBatch = run("acme").await.unwrap();
+        let globex: RecordBatch = run("globex").await.unwrap();
+        assert_eq!((acme.num_rows(), globex.num_rows()), (4, 1));
+
+        assert!(run("../acme").await.is_err());
+        let err = query
+            .execute_with_tenant_namespace(&namespace, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tenant"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn credentials_provider_is_asked_for_every_dataset() {
+        use crate::credentials::{Credentials, CredentialsProvider};
+        use std::sync::Mutex;
+        use tempfile::tempdir;
+
+        #[derive(Debug, Default)]
+        struct Recording {
+            tables: Mutex<Vec<String>>,
+        }
+
+        #[async_trait::async_trait]
+        impl CredentialsProvider for Recording {
+            async fn credentials(&self, table: &str, uri: &str) -> Result<Credentials> {
+                assert!(uri.ends_with(&format!("{}.lance", table)));
+                self.tables.lock().unwrap().push(table.to_string());
+                Ok(Credentials::new())
+            }
+
+            fn provider_id(&self) -> String {
+                "Recording".to_string()
+            }
+        }
+
+        let tmp_dir = tempdir().unwrap();
+        write_lance_dataset(&tmp_dir.path().join("Person.lance"), build_people_batch()).await;
+        write_lance_dataset(
+            &tmp_dir.path().join("FRIEND_OF.lance"),
+            build_friendship_batch(),
+        )
+        .await;
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let provider = Arc::new(Recording::default());
+        let result = CypherQuery::new("MATCH (p:Person) RETURN p.name")
+            .unwrap()
+            .with_config(config)
+            .with_credentials_provider(provider.clone())
+            .execute_with_namespace(
+                DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned()),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.num_rows(), 4);
+
+        let mut tables = provider.tables.lock().unwrap().clone();
+        tables.sort();
+        assert_eq!(tables, vec!["FRIEND_OF", "Person"]);
+    }
+
+    #[tokio::test]
+    async fn vector_order_by_searches_the_named_embedding_index() {
+        use crate::config::NodeMapping;
+        use arrow_array::{FixedSizeListArray, Float32Array, Int64Array, StringArray};
+        use arrow_schema::DataType;
+        use lance::dataset::Dataset;
+        use lance::index::vector::VectorIndexParams;
+        use lance_index::{DatasetIndexExt, IndexType};
+        use tempfile::tempdir;
+
+        let embeddings = |values: Vec<f32>| {
+            let item = Arc::new(Field::new("item", DataType::Float32, true));
+            Arc::new(
+                FixedSizeListArray::try_new(item, 2, Arc::new(Float32Array::from(values)), None)
+                    .unwrap(),
+            )
+        };
+        let vector_field = |name: &str| {
+            Field::new(
+                name,
+                DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 2),
+                false,
+            )
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("title", DataType::Utf8, false),
+            vector_field("title_vec"),
+            vector_field("body_vec"),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d"])),
+                embeddings(vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 3.0, 0.0]),
+                embeddings(vec![3.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.0, 0.0]),
+            ],
+        )
+        .unwrap();
+
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("Doc.lance");
+        write_lance_dataset(&path, batch).await;
+        let mut dataset = Dataset::open(path.to_str().unwrap()).await.unwrap();
+        dataset
+            .create_index(
+                &["title_vec"],
+                IndexType::Vector,
+                None,
+                &VectorIndexParams::ivf_flat(1, lance_linalg::distance::DistanceType::L2),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let config = GraphConfig::builder()
+            .with_node_mapping(
+                NodeMapping::new("Doc", "id")
+                    .with_vector_properties(vec!["title_vec".into(), "body_vec".into()]),
+            )
+            .build()
+            .unwrap();
+        let run = |column: &str| {
+            let query = CypherQuery::new(&format!(
+                "MATCH (d:Doc) RETURN d.title \
+                 ORDER BY vector_distance(d.{}, $q, l2) ASC LIMIT 2",
+                column
+            ))
+            .unwrap()
+            .with_config(config.clone())
+            .with_parameter("q", serde_json::json!([2.9, 0.0]));
+            let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+            async move { query.execute_with_namespace(namespace, None).await }
+        };
+
+        let result = run("title_vec").await.unwrap();
+        let titles = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!((titles.value(0), titles.value(1)), ("d", "c"));
+
+        let err = run("body_vec").await.unwrap_err();
+        assert!(err.to_string().contains("no vector index"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn vector_order_by_on_relationship_uses_edge_index() {
+        use crate::config::RelationshipMapping;
+        use arrow_array::{FixedSizeListArray, Float32Array, Int64Array, StringArray};
+        use arrow_schema::DataType;
+        use lance::dataset::Dataset;
+        use lance::index::vector::VectorIndexParams;
+        use lance_index::{DatasetIndexExt, IndexType};
+        use tempfile::tempdir;
+
+        let item = Arc::new(Field::new("item", DataType::Float32, true));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("person1_id", DataType::Int64, false),
+            Field::new("person2_id", DataType::Int64, false),
+            Field::new("embedding", DataType::FixedSizeList(item.clone(), 2), false),
+        ]));
+        let values = Float32Array::from(vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 3.0, 0.0]);
+        let friendships = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 1, 2, 3])),
+                Arc::new(Int64Array::from(vec![2, 3, 4, 4])),
+                Arc::new(FixedSizeListArray::try_new(item, 2, Arc::new(values), None).unwrap()),
+            ],
+        )
+        .unwrap();
+
+        let tmp_dir = tempdir().unwrap();
+        write_lance_dataset(&tmp_dir.path().join("Person.lance"), build_people_batch()).await;
+        let path = tmp_dir.path().join("FRIEND_OF.lance");
+        write_lance_dataset(&path, friendships).await;
+        let mut dataset = Dataset::open(path.to_str().unwrap()).await.unwrap();
+        dataset
+            .create_index(
+                &["embedding"],
+                IndexType::Vector,
+                None,
+                &VectorIndexParams::ivf_flat(1, lance_linalg::distance::DistanceType::L2),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship_mapping(
+                RelationshipMapping::new("FRIEND_OF", "person1_id", "person2_id")
+                    .with_vector_properties(vec!["embedding".into()]),
+            )
+            .build()
+            .unwrap();
+        let result = CypherQuery::new(
+            "MATCH (a:Person)-[r:FRIEND_OF]->(b:Person) RETURN a.name, b.name \
+             ORDER BY vector_distance(r.embedding, $q, l2) LIMIT 2",
+        )
+        .unwrap()
+        .with_config(config)
+        .with_parameter("q", serde_json::json!([2.9, 0.0]))
+        .execute_with_namespace(
+            DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let names = |i: usize| {
+            result
+                .column(i)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .iter()
+                .map(|v| v.unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(0), vec!["Carol", "Bob"]);
+        assert_eq!(names(1), vec!["David", "David"]);
+    }
+
+    #[tokio::test]
+    async fn result_cache_serves_unchanged_datasets() {
+        use crate::result_cache::{ResultCache, ResultCacheOptions};
+        use arrow_array::RecordBatchIterator;
+        use lance::dataset::{Dataset, WriteMode, WriteParams};
+        use tempfile::tempdir;
+
+        let tmp_dir = tempdir().unwrap();
+        let person_path = tmp_dir.path().join("Person.lance");
+        write_lance_dataset(&person_path, build_people_batch()).await;
+        write_lance_dataset(
+            &tmp_dir.path().join("FRIEND_OF.lance"),
+            build_friendship_batch(),
+        )
+        .await;
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let cache = Arc::new(ResultCache::new(ResultCacheOptions::default()));
+        let run = |query: &str, min_age: i64| {
+            let query = CypherQuery::new(query)
+                .unwrap()
+                .with_config(config.clone())
+                .with_parameter("min", min_age)
+                .with_result_cache(cache.clone());
+            let namespace = namespace.clone();
+            async move { query.execute_with_namespace(namespace, None).await.unwrap() }
+        };
+        let adults = "MATCH (p:Person) WHERE p.age > $min RETURN p.name";
+
+        assert_eq!(run(adults, 30).await.num_rows(), 2);
+        assert_eq!(run(adults, 30).await.num_rows(), 2);
+        assert_eq!((cache.stats().hits, cache.stats().misses), (1, 1));
+
+        // Other parameters are a different entry
+        assert_eq!(run(adults, 40).await.num_rows(), 1);
+        assert_eq!(cache.stats().misses, 2);
+
+        // Writing a new dataset version invalidates the cached result
+        let batch = build_people_batch();
+        let reader = RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema());
+        Dataset::write(
+            reader,
+            person_path.to_str().unwrap(),
+            Some(WriteParams {
+                mode: WriteMode::Append,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(run(adults, 30).await.num_rows(), 4);
+        assert_eq!(cache.stats().misses, 3);
+
+        // Unseeded random results are not kept
+        let entries = cache.stats().entries;
+        run("MATCH (p:Person) WHERE rand() < 0.5 RETURN p.name", 0).await;
+        assert_eq!(cache.stats().entries, entries);
+    }
+
+    #[tokio::test]
+    async fn result_cache_backend_is_shared_between_engines() {
+        use crate::result_cache::{ResultCache, ResultCacheBackend, ResultCacheOptions};
+        use std::sync::Mutex;
+        use std::time::Duration;
+        use tempfile::tempdir;
+
+        #[derive(Debug, Default)]
+        struct SharedBackend(Mutex<HashMap<String, Vec<u8>>>);
+
+        #[async_trait::async_trait]
+        impl ResultCacheBackend for SharedBackend {
+            async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+                Ok(self.0.lock().unwrap().get(key).cloned())
+            }
+
+            async fn put(&self, key: &str, value: Vec<u8>, _ttl: Duration) -> Result<()> {
+                self.0.lock().unwrap().insert(key.to_string(), value);
+                Ok(())
+            }
+        }
+
+        let tmp_dir = tempdir().unwrap();
+        write_lance_dataset(&tmp_dir.path().join("Person.lance"), build_people_batch()).await;
+        write_lance_dataset(
+            &tmp_dir.path().join("FRIEND_OF.lance"),
+            build_friendship_batch(),
+        )
+        .await;
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let backend = Arc::new(SharedBackend::default());
+        // One cache per engine instance, all backed by the same store
+        let engine = || {
+            Arc::new(ResultCache::new(ResultCacheOptions::default()).with_backend(backend.clone()))
+        };
+        let query = |text: &str, cache: &Arc<ResultCache>| {
+            CypherQuery::new(text)
+                .unwrap()
+                .with_config(config.clone())
+                .with_parameter("min", 30)
+                .with_result_cache(cache.clone())
+        };
+
+        let first = engine();
+        let adults = query("MATCH (p:Person) WHERE p.age > $min RETURN p.name", &first);
+        assert_eq!(
+            adults
+                .execute_with_namespace(namespace.clone(), None)
+                .await
+                .unwrap()
+                .num_rows(),
+            2
+        );
+        assert_eq!(backend.0.lock().unwrap().len(), 1);
+
+        // Another instance plans the same query, spelled differently, alike
+        let second = engine();
+        let respelled = query(
+            "MATCH (p:Person)\n  WHERE p.age > $min\n  RETURN p.name",
+            &second,
+        );
+        assert_eq!(adults.plan_hash().unwrap(), respelled.plan_hash().unwrap());
+        let result = respelled
+            .execute_with_namespace(namespace, None)
+            .await
+            .unwrap();
+        assert_eq!(result.num_rows(), 2);
+        assert_eq!(second.stats().backend_hits, 1);
+
+        assert_ne!(
+            adults.plan_hash().unwrap(),
+            adults
+                .clone()
+                .with_parameter("min", 40)
+                .plan_hash()
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn provenance_columns_locate_source_rows() {
+        use arrow_array::{Array, UInt64Array};
+        use tempfile::tempdir;
+
+        let tmp_dir = tempdir().unwrap();
+        write_lance_dataset(&tmp_dir.path().join("Person.lance"), build_people_batch()).await;
+        write_lance_dataset(
+            &tmp_dir.path().join("FRIEND_OF.lance"),
+            build_friendship_batch(),
+        )
+        .await;
+
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+
+        let query = CypherQuery::new("MATCH (p:Person) WHERE p.name = 'Carol' RETURN p.name")
+            .unwrap()
+            .with_config(config.clone())
+            .with_provenance(true);
+        let result = query
+            .execute_with_namespace(namespace.clone(), None)
+            .await
+            .unwrap();
+
+        let schema = result.schema();
+        for column in crate::datafusion_planner::PROVENANCE_COLUMNS {
+            let name = format!("p.{}", column);
+            assert!(schema.index_of(&name).is_ok(), "missing {}", name);
+        }
+        let row_ids = result
+            .column_by_name("p._rowid")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(row_ids.len(), 1);
+        assert_eq!(row_ids.value(0), 2);
+
+        // Provenance is opt-in
+        let query = CypherQuery::new("MATCH (p:Person) RETURN p.name")
+            .unwrap()
+            .with_config(config);
+        let result = query.execute_with_namespace(namespace, None).await.unwrap();
+        assert_eq!(result.num_columns(), 1);
+    }
+
+    #[tokio::test]
+    async fn label_parameters_bind_to_configured_names() {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "person_id")
+            .with_relationship("FRIEND_OF", "person1_id", "person2_id")
+            .build()
+            .unwrap();
+        let datasets = || {
+            HashMap::from([
+                ("Person".to_string(), build_people_batch()),
+                ("FRIEND_OF".to_string(), build_friendship_batch()),
+            ])
+        };
+        let query = |label: serde_json::Value| {
+            CypherQuery::new("MATCH (a:$label)-[:$rel]->(b:Person) RETURN a.name, b.name")
+                .unwrap()
+                .with_config(config.clone())
+                .with_parameter("label", label)
+                .with_parameter("rel", "FRIEND_OF")
+        };
+
+        let result = query(serde_json::json!("Person"))
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+        assert_eq!(result.num_rows(), 4);
+
+        // Values are checked against the configuration, never spliced into the query
+        let err = query(serde_json::json!("Person) DETACH DELETE (x"))
+            .execute(datasets(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown label"), "{}", err);
+
+        let err = query(serde_json::json!(1))
+            .execute(datasets(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must be a string"), "{}", err);
+    }
+
     #[tokio::test]
     async fn test_execute_fails_on_semantic_error() {
         use arrow_array::RecordBatch;
@@ -2364,4 +4060,222 @@ mod tests {
             ),
         }
     }
+
+    #[test]
+    fn test_estimate_cost_without_datasets() {
+        use crate::cost::ElementStatistics;
+
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        let statistics = GraphStatistics::new()
+            .with_node(
+                "Person",
+                ElementStatistics::new(1_000).with_size_bytes(64_000),
+            )
+            .with_relationship("KNOWS", ElementStatistics::new(5_000));
+
+        let query = CypherQuery::new("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN b.name")
+            .unwrap()
+            .with_config(config.clone());
+        let estimate = query.estimate_cost(&statistics).unwrap();
+        assert_eq!(estimate.rows, 5_000);
+        assert_eq!(estimate.bytes_scanned, 64_000 + 5_000 * 64 + 64_000);
+
+        let limited = CypherQuery::new("MATCH (a:Person) RETURN a.name LIMIT 5")
+            .unwrap()
+            .with_config(config.clone());
+        assert_eq!(limited.estimate_cost(&statistics).unwrap().rows, 5);
+
+        let summary = CypherQuery::new("CALL graph.summary()")
+            .unwrap()
+            .with_config(config);
+        assert!(summary.estimate_cost(&statistics).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_selectivity_feedback_refines_estimates() {
+        use crate::cost::ElementStatistics;
+        use arrow_array::{Int64Array, StringArray};
+        use arrow_schema::DataType;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("age", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "David"])),
+                Arc::new(Int64Array::from(vec![Some(28), Some(34), None, Some(42)])),
+            ],
+        )
+        .unwrap();
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        let feedback = Arc::new(SelectivityFeedback::new());
+        let statistics = GraphStatistics::new()
+            .with_node("Person", ElementStatistics::new(1_000))
+            .with_feedback(feedback.clone());
+
+        let query = CypherQuery::new("MATCH (p:Person) WHERE p.age > $min RETURN p.name")
+            .unwrap()
+            .with_config(config)
+            .with_parameter("min", 30);
+        let guessed = query.estimate_cost(&statistics).unwrap().rows;
+        assert_eq!(guessed, 334);
+
+        let datasets = HashMap::from([("Person".to_string(), batch)]);
+        let result = query
+            .clone()
+            .with_selectivity_feedback(feedback.clone())
+            .execute(datasets, None)
+            .await
+            .unwrap();
+        assert_eq!(result.num_rows(), 2);
+
+        let observations = feedback.observations(&query.fingerprint().unwrap());
+        let [(_, observation)] = observations.try_into().unwrap();
+        assert_eq!(observation.rows, 4.0);
+        assert_eq!(observation.selectivity(), Some(0.5));
+        assert_eq!(observation.null_fraction(), Some(0.25));
+        assert_eq!(query.estimate_cost(&statistics).unwrap().rows, 500);
+    }
+
+    #[tokio::test]
+    async fn test_integer_overflow_modes() {
+        use arrow_array::Int64Array;
+        use arrow_schema::DataType;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("score", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(Int64Array::from(vec![i64::MAX - 1, 5])),
+            ],
+        )
+        .unwrap();
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        let run = |mode: Option<OverflowMode>| {
+            let mut query = CypherQuery::new("MATCH (p:Person) RETURN p.score + 2 AS s")
+                .unwrap()
+                .with_config(config.clone());
+            if let Some(mode) = mode {
+                query = query.with_overflow_mode(mode);
+            }
+            let datasets = HashMap::from([("Person".to_string(), batch.clone())]);
+            async move { query.execute(datasets, None).await }
+        };
+        let scores = |result: RecordBatch| {
+            let column = result.column(0);
+            let values = column.as_any().downcast_ref::<Int64Array>().unwrap();
+            let mut values = values.values().to_vec();
+            values.sort();
+            values
+        };
+
+        let err = run(None).await.unwrap_err();
+        assert!(err.to_string().contains("Integer overflow"), "{}", err);
+        assert_eq!(
+            scores(run(Some(OverflowMode::Saturate)).await.unwrap()),
+            vec![7, i64::MAX]
+        );
+        assert_eq!(
+            scores(run(Some(OverflowMode::Wrap)).await.unwrap()),
+            vec![i64::MIN, 7]
+        );
+    }
+
+    /// "Zoë" spelled with a precomposed ë and with e and a combining diaeresis
+    fn zoe_batch() -> RecordBatch {
+        use arrow_array::{Int64Array, StringArray};
+        use arrow_schema::DataType;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["Zo\u{eb}", "Zoe\u{308}"])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_string_length_counts_characters() {
+        use arrow_array::Int64Array;
+
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        let query = |cypher: &str| {
+            CypherQuery::new(cypher)
+                .unwrap()
+                .with_config(config.clone())
+        };
+        let datasets = || HashMap::from([("Person".to_string(), zoe_batch())]);
+
+        let result = query("MATCH (p:Person) RETURN p.id AS id, length(toUpper(p.name)) AS n")
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+        let lengths = result
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let mut lengths = lengths.values().to_vec();
+        lengths.sort();
+        // Graphemes with the icu feature, characters without; never bytes
+        if cfg!(feature = "icu") {
+            assert_eq!(lengths, vec![3, 3]);
+        } else {
+            assert_eq!(lengths, vec![3, 4]);
+        }
+
+        let precomposed = query("MATCH (p:Person) WHERE p.name = 'Zo\u{eb}' RETURN p.id");
+        let matched = precomposed.execute(datasets(), None).await.unwrap();
+        assert_eq!(
+            matched.num_rows(),
+            1,
+            "byte comparison tells the spellings apart"
+        );
+        let normalized = precomposed
+            .with_unicode_normalization(true)
+            .execute(datasets(), None)
+            .await;
+        if cfg!(feature = "icu") {
+            assert_eq!(normalized.unwrap().num_rows(), 2);
+        } else {
+            assert!(normalized.unwrap_err().to_string().contains("icu"));
+        }
+
+        #[cfg(feature = "icu")]
+        {
+            let like = query("MATCH (p:Person) WHERE p.name STARTS WITH 'Zo\u{eb}' RETURN p.id")
+                .with_unicode_normalization(true)
+                .execute(datasets(), None)
+                .await
+                .unwrap();
+            assert_eq!(like.num_rows(), 2);
+        }
+    }
 }
@@ -0,0 +1,452 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Introspection of running queries
+//!
+//! A [`QueryMonitor`] shared by the queries of a server (see
+//! [`CypherQuery::with_query_monitor`]) tracks every execution in flight: its
+//! text, how long it has been running, whether it is still being planned or
+//! already executing, and how many result rows it has produced. Queries can
+//! list them and stop one by its id:
+//!
+//! ```text
+//! CALL dbms.listQueries()
+//! CALL dbms.killQuery('query_7')
+//! ```
+//!
+//! A killed query stops at its next suspension point and fails with an
+//! execution error. [`QueryMonitor::kill`] (and `dbms.killQuery`) returns
+//! only once the query is torn down: its operators, including those
+//! DataFusion runs on spawned tasks, are dropped, which releases their memory
+//! reservations and deletes their spill files, or fails if that takes longer
+//! than the monitor's kill timeout. Results are cached only once
+//! complete, so a killed query leaves no partial result in a
+//! [`ResultCache`]. Executions that stream their results
+//! ([`CypherQuery::execute_into`], [`CypherQuery::execute_with_callback`]) and
+//! writes are not tracked.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::query_monitor::QueryMonitor;
+//!
+//! let monitor = Arc::new(QueryMonitor::new());
+//! let query = CypherQuery::new(cypher)?
+//!     .with_config(config)
+//!     .with_query_monitor(monitor.clone());
+//!
+//! // Elsewhere, e.g. in an admin endpoint
+//! for active in monitor.active_queries() {
+//!     if active.elapsed > Duration::from_secs(60) {
+//!         monitor.kill(active.id).await?;
+//!     }
+//! }
+//! ```
+//!
+//! [`ResultCache`]: crate::result_cache::ResultCache
+//! [`CypherQuery::with_query_monitor`]: crate::query::CypherQuery::with_query_monitor
+//! [`CypherQuery::execute_into`]: crate::query::CypherQuery::execute_into
+//! [`CypherQuery::execute_with_callback`]: crate::query::CypherQuery::execute_with_callback
+
+use crate::ast::{ProcedureCall, PropertyValue, ValueExpression};
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::execution::TaskContext;
+use futures::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Procedure listing the running queries
+pub const LIST_QUERIES_PROCEDURE: &str = "dbms.listQueries";
+
+/// Procedure stopping a running query by id
+pub const KILL_QUERY_PROCEDURE: &str = "dbms.killQuery";
+
+/// How long [`QueryMonitor::kill`] waits for a query to be torn down by
+/// default
+pub const DEFAULT_KILL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Identifier of a tracked execution, unique per monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QueryId(pub u64);
+
+impl fmt::Display for QueryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "query_{}", self.0)
+    }
+}
+
+impl std::str::FromStr for QueryId {
+    type Err = std::num::ParseIntError;
+
+    /// Parse `query_7`, or the bare number `7`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        s.strip_prefix("query_").unwrap_or(s).parse().map(QueryId)
+    }
+}
+
+/// Stage a tracked execution is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryPhase {
+    /// Building the logical and physical plans
+    Planning,
+    /// Producing result rows
+    Executing,
+}
+
+impl fmt::Display for QueryPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Planning => "planning",
+            Self::Executing => "executing",
+        })
+    }
+}
+
+/// A running query, as listed by [`QueryMonitor::active_queries`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveQuery {
+    pub id: QueryId,
+    /// The Cypher text of the query
+    pub query: String,
+    pub phase: QueryPhase,
+    /// Time since the execution started
+    pub elapsed: Duration,
+    /// Result rows produced so far
+    pub rows: u64,
+}
+
+/// Progress of one tracked execution, reported by the execution itself
+#[derive(Debug, Default)]
+pub(crate) struct QueryProgress {
+    executing: AtomicBool,
+    rows: AtomicU64,
+    /// Number of task contexts of the execution's physical plans still held
+    /// by their operators
+    live_contexts: watch::Sender<usize>,
+}
+
+impl QueryProgress {
+    /// The plans are built; rows are being produced by operators sharing the
+    /// returned task context
+    ///
+    /// The context carries a guard that reports it dropped, with the last
+    /// operator holding it.
+    pub(crate) fn start_executing(self: &Arc<Self>, task_ctx: TaskContext) -> Arc<TaskContext> {
+        self.executing.store(true, Ordering::Relaxed);
+        self.live_contexts.send_modify(|live| *live += 1);
+        let guard = Arc::new(LiveContext {
+            progress: self.clone(),
+        });
+        let config = task_ctx.session_config().clone().with_extension(guard);
+        Arc::new(task_ctx.with_session_config(config))
+    }
+
+    pub(crate) fn add_rows(&self, rows: usize) {
+        self.rows.fetch_add(rows as u64, Ordering::Relaxed);
+    }
+}
+
+/// Session config extension of an execution's task context, dropped with it
+#[derive(Debug)]
+struct LiveContext {
+    progress: Arc<QueryProgress>,
+}
+
+impl Drop for LiveContext {
+    fn drop(&mut self) {
+        self.progress.live_contexts.send_modify(|live| *live -= 1);
+    }
+}
+
+struct TrackedQuery {
+    query: String,
+    started: Instant,
+    progress: Arc<QueryProgress>,
+    abort: AbortHandle,
+    /// Never sent to; dropped with the entry once the execution is dropped
+    untracked: watch::Sender<()>,
+}
+
+/// Registry of the executions in flight of the queries sharing it
+pub struct QueryMonitor {
+    next_id: AtomicU64,
+    queries: Mutex<HashMap<QueryId, TrackedQuery>>,
+    kill_timeout: Duration,
+}
+
+impl Default for QueryMonitor {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::default(),
+            queries: Mutex::default(),
+            kill_timeout: DEFAULT_KILL_TIMEOUT,
+        }
+    }
+}
+
+impl fmt::Debug for QueryMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryMonitor")
+            .field("active", &self.queries.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl QueryMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait at most `timeout` in [`kill`](Self::kill) for a query to be torn
+    /// down
+    pub fn with_kill_timeout(mut self, timeout: Duration) -> Self {
+        self.kill_timeout = timeout;
+        self
+    }
+
+    /// The running queries, oldest first
+    pub fn active_queries(&self) -> Vec<ActiveQuery> {
+        let mut active: Vec<_> = self
+            .queries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, tracked)| ActiveQuery {
+                id: *id,
+                query: tracked.query.clone(),
+                phase: if tracked.progress.executing.load(Ordering::Relaxed) {
+                    QueryPhase::Executing
+                } else {
+                    QueryPhase::Planning
+                },
+                elapsed: tracked.started.elapsed(),
+                rows: tracked.progress.rows.load(Ordering::Relaxed),
+            })
+            .collect();
+        active.sort_by_key(|query| query.id);
+        active
+    }
+
+    /// Stop query `id`; `false` if it is not running
+    ///
+    /// The query stops at its next suspension point and its execution fails.
+    pub fn cancel(&self, id: QueryId) -> bool {
+        match self.queries.lock().unwrap().get(&id) {
+            Some(tracked) => {
+                tracked.abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop query `id` and wait until it is torn down; `false` if it is not
+    /// running
+    ///
+    /// Returns once the execution has been dropped, and with it every
+    /// operator holding the task context of its physical plans, including
+    /// those running on tasks DataFusion spawned. The execution must keep
+    /// being polled, or be dropped, for this to happen; if it is not torn
+    /// down within the monitor's kill timeout (see
+    /// [`with_kill_timeout`](Self::with_kill_timeout)), this fails, leaving
+    /// the query aborted.
+    pub async fn kill(&self, id: QueryId) -> Result<bool> {
+        let (mut untracked, mut live_contexts) = {
+            let queries = self.queries.lock().unwrap();
+            let Some(tracked) = queries.get(&id) else {
+                return Ok(false);
+            };
+            tracked.abort.abort();
+            (
+                tracked.untracked.subscribe(),
+                tracked.progress.live_contexts.subscribe(),
+            )
+        };
+        let torn_down = async {
+            // Resolves with an error when the sender is dropped
+            let _ = untracked.changed().await;
+            // Spawned tasks drop their operators once they observe their
+            // abort; the sender lives as long as the last task context
+            let _ = live_contexts.wait_for(|live| *live == 0).await;
+        };
+        match tokio::time::timeout(self.kill_timeout, torn_down).await {
+            Ok(()) => Ok(true),
+            Err(_) => Err(GraphError::ExecutionError {
+                message: format!(
+                    "{} was aborted but not torn down within {:?}",
+                    id, self.kill_timeout
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+        }
+    }
+
+    /// Run `execute` as the tracked execution of `query`, passing it the
+    /// progress to report
+    pub(crate) async fn track<F, Fut, T>(&self, query: &str, execute: F) -> Result<T>
+    where
+        F: FnOnce(Arc<QueryProgress>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let id = QueryId(self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let progress = Arc::new(QueryProgress::default());
+        let (abort, registration) = AbortHandle::new_pair();
+        self.queries.lock().unwrap().insert(
+            id,
+            TrackedQuery {
+                query: query.to_string(),
+                started: Instant::now(),
+                progress: progress.clone(),
+                abort,
+                untracked: watch::channel(()).0,
+            },
+        );
+        // Untracked however the execution ends, including by being dropped
+        let _tracked = Untrack { monitor: self, id };
+        match Abortable::new(execute(progress), registration).await {
+            Ok(result) => result,
+            Err(_) => Err(GraphError::ExecutionError {
+                message: format!("{} was killed", id),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+        }
+    }
+}
+
+/// Removes a tracked execution from its monitor when dropped
+struct Untrack<'a> {
+    monitor: &'a QueryMonitor,
+    id: QueryId,
+}
+
+impl Drop for Untrack<'_> {
+    fn drop(&mut self) {
+        self.monitor.queries.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Whether `call` is one of the monitor's procedures
+pub(crate) fn is_monitor_call(call: &ProcedureCall) -> bool {
+    call.name.eq_ignore_ascii_case(LIST_QUERIES_PROCEDURE)
+        || call.name.eq_ignore_ascii_case(KILL_QUERY_PROCEDURE)
+}
+
+/// Fail unless `call` passes a monitor procedure the arguments it takes
+pub(crate) fn check_call(call: &ProcedureCall) -> Result<()> {
+    let valid = if call.name.eq_ignore_ascii_case(LIST_QUERIES_PROCEDURE) {
+        call.arguments.is_empty()
+    } else {
+        matches!(
+            call.arguments.as_slice(),
+            [ValueExpression::Literal(
+                PropertyValue::String(_) | PropertyValue::Integer(_) | PropertyValue::Parameter(_)
+            ) | ValueExpression::Parameter(_)]
+        )
+    };
+    if !valid {
+        return Err(GraphError::PlanError {
+            message: format!(
+                "{}() takes no arguments and {}(id) a query id",
+                LIST_QUERIES_PROCEDURE, KILL_QUERY_PROCEDURE
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(())
+}
+
+/// Schema of the rows returned by the monitor procedure `call`
+pub(crate) fn procedure_schema(call: &ProcedureCall) -> SchemaRef {
+    if call.name.eq_ignore_ascii_case(LIST_QUERIES_PROCEDURE) {
+        Arc::new(Schema::new(vec![
+            Field::new("query_id", DataType::Utf8, false),
+            Field::new("query", DataType::Utf8, false),
+            Field::new("phase", DataType::Utf8, false),
+            Field::new("elapsed_ms", DataType::Int64, false),
+            Field::new("rows", DataType::Int64, false),
+        ]))
+    } else {
+        Arc::new(Schema::new(vec![
+            Field::new("query_id", DataType::Utf8, false),
+            Field::new("killed", DataType::Boolean, false),
+        ]))
+    }
+}
+
+/// Run the monitor procedure `call` of `query` against its monitor
+///
+/// `dbms.killQuery` returns once the query it stops is torn down, and fails
+/// if that takes longer than the monitor's kill timeout.
+pub(crate) async fn execute_procedure(
+    query: &CypherQuery,
+    call: &ProcedureCall,
+) -> Result<RecordBatch> {
+    check_call(call)?;
+    let monitor = query
+        .query_monitor()
+        .ok_or_else(|| GraphError::ConfigError {
+            message: format!(
+                "CALL {}() needs a query monitor; see CypherQuery::with_query_monitor",
+                call.name
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    let columns: Vec<ArrayRef> = if call.name.eq_ignore_ascii_case(LIST_QUERIES_PROCEDURE) {
+        let active = monitor.active_queries();
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                active.iter().map(|q| q.id.to_string()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                active.iter().map(|q| q.query.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                active.iter().map(|q| q.phase.to_string()),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                active.iter().map(|q| q.elapsed.as_millis() as i64),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                active.iter().map(|q| q.rows as i64),
+            )),
+        ]
+    } else {
+        let id = query_id_argument(query, &call.arguments[0])?;
+        vec![
+            Arc::new(StringArray::from(vec![id.to_string()])),
+            Arc::new(BooleanArray::from(vec![monitor.kill(id).await?])),
+        ]
+    };
+    Ok(RecordBatch::try_new(procedure_schema(call), columns)?)
+}
+
+/// The query id `dbms.killQuery` is given, with parameters bound
+fn query_id_argument(query: &CypherQuery, argument: &ValueExpression) -> Result<QueryId> {
+    let value = match argument {
+        ValueExpression::Literal(PropertyValue::Parameter(name))
+        | ValueExpression::Parameter(name) => query
+            .parameters()
+            .get(name)
+            .and_then(PropertyValue::from_json)
+            .unwrap_or(PropertyValue::Null),
+        ValueExpression::Literal(value) => value.clone(),
+        _ => PropertyValue::Null,
+    };
+    let id = match &value {
+        PropertyValue::String(id) => id.parse().ok(),
+        PropertyValue::Integer(id) => u64::try_from(*id).ok().map(QueryId),
+        _ => None,
+    };
+    id.ok_or_else(|| GraphError::PlanError {
+        message: format!("Invalid query id {:?}", value),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Redis backend for shared result caches
+//!
+//! Requires the `redis` feature. Results are stored with `SET ... EX`, so
+//! Redis expires them after the TTL of the [`ResultCache`] that wrote them;
+//! keys are the stable hashes of [`crate::result_cache`] under a configurable
+//! prefix, so several graphs can share one Redis.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::redis_cache::RedisResultCacheBackend;
+//! use lance_graph::result_cache::{ResultCache, ResultCacheOptions};
+//!
+//! let backend = RedisResultCacheBackend::connect("redis://cache:6379")
+//!     .await?
+//!     .with_prefix("social-graph:");
+//! let cache = Arc::new(ResultCache::new(ResultCacheOptions::default()).with_backend(Arc::new(backend)));
+//! ```
+//!
+//! [`ResultCache`]: crate::result_cache::ResultCache
+
+use crate::error::{GraphError, Result};
+use crate::result_cache::ResultCacheBackend;
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use std::fmt;
+use std::time::Duration;
+
+/// Prefix of the keys written when none is configured
+pub const DEFAULT_KEY_PREFIX: &str = "lance-graph:result:";
+
+/// [`ResultCacheBackend`] storing results in Redis
+#[derive(Clone)]
+pub struct RedisResultCacheBackend {
+    connection: MultiplexedConnection,
+    prefix: String,
+}
+
+impl fmt::Debug for RedisResultCacheBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisResultCacheBackend")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisResultCacheBackend {
+    /// Connect to the Redis server at `url`, e.g. `redis://host:6379/0`
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).map_err(|e| GraphError::ConfigError {
+            message: format!("Invalid Redis URL '{}': {}", url, e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| redis_error("connect to", e))?;
+        Ok(Self {
+            connection,
+            prefix: DEFAULT_KEY_PREFIX.to_string(),
+        })
+    }
+
+    /// Prefix keys with `prefix` instead of [`DEFAULT_KEY_PREFIX`]
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[async_trait]
+impl ResultCacheBackend for RedisResultCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut connection = self.connection.clone();
+        connection
+            .get::<_, Option<Vec<u8>>>(format!("{}{}", self.prefix, key))
+            .await
+            .map_err(|e| redis_error("read from", e))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .set_ex::<_, _, ()>(
+                format!("{}{}", self.prefix, key),
+                value,
+                ttl.as_secs().max(1),
+            )
+            .await
+            .map_err(|e| redis_error("write to", e))
+    }
+}
+
+fn redis_error(action: &str, e: redis::RedisError) -> GraphError {
+    GraphError::ExecutionError {
+        message: format!("Failed to {} the Redis result cache: {}", action, e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
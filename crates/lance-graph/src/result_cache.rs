@@ -0,0 +1,595 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Memoized query results
+//!
+//! Dashboards re-run the same queries with the same parameters far more often
+//! than the underlying datasets change. A [`ResultCache`] attached with
+//! [`crate::CypherQuery::with_result_cache`] keeps full results keyed by the
+//! query, its parameters and the version of every dataset of the graph, so an
+//! entry stops matching as soon as one of them is written to.
+//!
+//! - Only graphs backed entirely by Lance datasets are cached; in-memory
+//!   tables have no version to key on.
+//! - Results of queries calling volatile functions (`rand()`, `randomUUID()`,
+//!   `SAMPLE`) are only cached when the query is seeded.
+//! - Entries expire after the TTL, and the least recently used ones are
+//!   evicted to stay within the size budget.
+//!
+//! Engine instances behind a load balancer can share results through a
+//! [`ResultCacheBackend`], e.g. Redis with the `redis` feature (see the
+//! `redis_cache` module). Misses in memory are looked up in the backend and
+//! computed results are written to it as Arrow IPC streams. Backend entries
+//! are keyed by a hash of the query's AST, parameters, graph configuration
+//! and dataset versions that is stable across processes and builds (see
+//! [`crate::CypherQuery::plan_hash`]), so every instance agrees on it; query
+//! text that differs only in whitespace shares entries. Backend failures are
+//! counted and otherwise treated as misses.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::result_cache::{ResultCache, ResultCacheOptions};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let cache = Arc::new(ResultCache::new(
+//!     ResultCacheOptions::default()
+//!         .with_ttl(Duration::from_secs(30))
+//!         .with_max_bytes(256 << 20),
+//! ));
+//! let query = CypherQuery::new("MATCH (p:Person) RETURN count(p)")?
+//!     .with_config(config)
+//!     .with_result_cache(cache.clone());
+//! let result = query.execute_with_namespace(namespace, None).await?;
+//!
+//! // Shared by every instance pointing at the same Redis
+//! let shared = Arc::new(
+//!     ResultCache::new(ResultCacheOptions::default())
+//!         .with_backend(Arc::new(RedisResultCacheBackend::connect("redis://cache:6379").await?)),
+//! );
+//! ```
+
+use crate::config::GraphConfig;
+use crate::error::Result;
+use arrow::compute::concat_batches;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow_array::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::tree_node::TreeNode;
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::{LogicalPlan, TableSource};
+use lance::datafusion::LanceTableProvider;
+use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Expiry and size limits of a [`ResultCache`]
+#[derive(Debug, Clone)]
+pub struct ResultCacheOptions {
+    /// Drop entries this long after they were stored
+    pub ttl: Duration,
+    /// Upper bound on the in-memory size of all cached results
+    pub max_bytes: usize,
+}
+
+impl Default for ResultCacheOptions {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            max_bytes: 64 << 20,
+        }
+    }
+}
+
+impl ResultCacheOptions {
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+/// Counters of a [`ResultCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResultCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Results currently cached
+    pub entries: usize,
+    /// In-memory size of the cached results
+    pub bytes: usize,
+    /// Memory misses served by the backend
+    pub backend_hits: u64,
+    /// Failed backend reads and writes
+    pub backend_errors: u64,
+}
+
+/// Remote store shared by the result caches of several engine instances
+///
+/// Keys are stable hashes (see [`crate::CypherQuery::plan_hash`]); values are
+/// results encoded as Arrow IPC streams.
+#[async_trait]
+pub trait ResultCacheBackend: Send + Sync + fmt::Debug {
+    /// Value stored under `key`, if any
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `value` under `key` for `ttl`
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()>;
+}
+
+/// What a cached result depends on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ResultKey {
+    /// Query AST as canonical JSON and the options that change its result
+    pub query: String,
+    /// Graph configuration as canonical JSON
+    pub config: String,
+    /// Parameters as JSON with sorted keys
+    pub parameters: String,
+    /// (dataset uri, version) of every dataset of the graph, sorted
+    pub versions: Vec<(String, u64)>,
+}
+
+impl ResultKey {
+    /// Hex digest of the key, the same in every process and build
+    pub(crate) fn stable_hash(&self) -> String {
+        let mut hasher = StableHasher::default();
+        hasher.write_str(&self.query);
+        hasher.write_str(&self.config);
+        hasher.write_str(&self.parameters);
+        for (uri, version) in &self.versions {
+            hasher.write_str(uri);
+            hasher.write(&version.to_le_bytes());
+        }
+        format!("{:032x}", hasher.finish())
+    }
+}
+
+/// Hex digest of `parts`, the same in every process and build
+pub(crate) fn stable_hash(parts: &[&str]) -> String {
+    let mut hasher = StableHasher::default();
+    for part in parts {
+        hasher.write_str(part);
+    }
+    format!("{:032x}", hasher.finish())
+}
+
+/// 128-bit FNV-1a, which unlike `std`'s hashers is specified to never change
+struct StableHasher(u128);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+impl StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+
+    /// Length-prefixed, so that field boundaries are part of the hash
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    fn finish(&self) -> u128 {
+        self.0
+    }
+}
+
+/// `value` as JSON with the keys of every object sorted
+pub(crate) fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let entries: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    format!(
+                        "{}:{}",
+                        serde_json::Value::from(k.as_str()),
+                        canonical_json(v)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        serde_json::Value::Array(values) => {
+            let values: Vec<String> = values.iter().map(canonical_json).collect();
+            format!("[{}]", values.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    batch: RecordBatch,
+    bytes: usize,
+    created: Instant,
+    last_used: Instant,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<ResultKey, Entry>,
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+    backend_hits: u64,
+    backend_errors: u64,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &ResultKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.bytes;
+        }
+    }
+}
+
+/// Query results shared by the queries it is attached to
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    options: ResultCacheOptions,
+    state: Mutex<CacheState>,
+    backend: Option<Arc<dyn ResultCacheBackend>>,
+}
+
+impl ResultCache {
+    pub fn new(options: ResultCacheOptions) -> Self {
+        Self {
+            options,
+            state: Mutex::new(CacheState::default()),
+            backend: None,
+        }
+    }
+
+    /// Share results with other engine instances through `backend`
+    pub fn with_backend(mut self, backend: Arc<dyn ResultCacheBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Hit and miss counts and current usage
+    pub fn stats(&self) -> ResultCacheStats {
+        let state = self.state.lock().unwrap();
+        ResultCacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+            bytes: state.bytes,
+            backend_hits: state.backend_hits,
+            backend_errors: state.backend_errors,
+        }
+    }
+
+    /// Drop every cached result
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.bytes = 0;
+    }
+
+    /// Cached result for `key`, unless missing or expired
+    pub(crate) fn get(&self, key: &ResultKey) -> Option<RecordBatch> {
+        let mut state = self.state.lock().unwrap();
+        let expired = match state.entries.get_mut(key) {
+            Some(entry) if entry.created.elapsed() < self.options.ttl => {
+                entry.last_used = Instant::now();
+                let batch = entry.batch.clone();
+                state.hits += 1;
+                return Some(batch);
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            state.remove(key);
+        }
+        state.misses += 1;
+        None
+    }
+
+    /// Store `batch` for `key`, evicting least recently used results to fit
+    ///
+    /// Results larger than the whole budget are not stored.
+    pub(crate) fn insert(&self, key: ResultKey, batch: RecordBatch) {
+        let bytes = batch.get_array_memory_size();
+        if bytes > self.options.max_bytes {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.remove(&key);
+
+        let ttl = self.options.ttl;
+        let expired: Vec<ResultKey> = state
+            .entries
+            .iter()
+            .filter(|(_, e)| e.created.elapsed() >= ttl)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in &expired {
+            state.remove(key);
+        }
+        while state.bytes + bytes > self.options.max_bytes {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            state.remove(&oldest);
+        }
+
+        let now = Instant::now();
+        state.bytes += bytes;
+        state.entries.insert(
+            key,
+            Entry {
+                batch,
+                bytes,
+                created: now,
+                last_used: now,
+            },
+        );
+    }
+}
+
+impl ResultCache {
+    /// Cached result for `key`, from memory or else from the backend
+    pub(crate) async fn lookup(&self, key: &ResultKey) -> Option<RecordBatch> {
+        if let Some(batch) = self.get(key) {
+            return Some(batch);
+        }
+        let backend = self.backend.as_ref()?;
+        let fetched = match backend.get(&key.stable_hash()).await {
+            Ok(bytes) => bytes.map(|bytes| decode_ipc(&bytes)).transpose(),
+            Err(e) => Err(e),
+        };
+        match fetched {
+            Ok(Some(batch)) => {
+                self.state.lock().unwrap().backend_hits += 1;
+                self.insert(key.clone(), batch.clone());
+                Some(batch)
+            }
+            Ok(None) => None,
+            Err(_) => {
+                self.state.lock().unwrap().backend_errors += 1;
+                None
+            }
+        }
+    }
+
+    /// Store `batch` for `key` in memory and in the backend
+    pub(crate) async fn store(&self, key: ResultKey, batch: RecordBatch) {
+        let Some(backend) = &self.backend else {
+            self.insert(key, batch);
+            return;
+        };
+        let hash = key.stable_hash();
+        let encoded = encode_ipc(&batch);
+        self.insert(key, batch);
+        let stored = match encoded {
+            // Like in memory, results above the whole budget are not stored
+            Ok(bytes) if bytes.len() > self.options.max_bytes => Ok(()),
+            Ok(bytes) => backend.put(&hash, bytes, self.options.ttl).await,
+            Err(e) => Err(e),
+        };
+        if stored.is_err() {
+            self.state.lock().unwrap().backend_errors += 1;
+        }
+    }
+}
+
+fn encode_ipc(batch: &RecordBatch) -> Result<Vec<u8>> {
+    let mut writer = StreamWriter::try_new(Vec::new(), &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+fn decode_ipc(bytes: &[u8]) -> Result<RecordBatch> {
+    let reader = StreamReader::try_new(Cursor::new(bytes), None)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(concat_batches(&schema, &batches)?)
+}
+
+/// Version of every dataset of `config` in `catalog`, or `None` when one of
+/// them is not a Lance dataset
+pub(crate) fn dataset_versions(
+    config: &GraphConfig,
+    catalog: &dyn GraphSourceCatalog,
+) -> Option<Vec<(String, u64)>> {
+    let nodes = config
+        .node_mappings
+        .values()
+        .filter(|m| m.view_of.is_none())
+        .map(|m| catalog.node_source(m.table_name()));
+    let relationships = config
+        .relationship_mappings
+        .values()
+        .map(|m| catalog.relationship_source(&m.relationship_type));
+
+    let mut versions = nodes
+        .chain(relationships)
+        .map(|source| lance_version(&source?))
+        .collect::<Option<Vec<_>>>()?;
+    versions.sort();
+    versions.dedup();
+    Some(versions)
+}
+
+pub(crate) fn lance_version(source: &Arc<dyn TableSource>) -> Option<(String, u64)> {
+    let provider = source_as_provider(source).ok()?;
+    let lance = provider.as_any().downcast_ref::<LanceTableProvider>()?;
+    let dataset = lance.dataset();
+    Some((dataset.uri().to_string(), dataset.version().version))
+}
+
+/// Whether running `plan` twice may give different results
+pub(crate) fn is_volatile(plan: &LogicalPlan) -> bool {
+    plan.exists(|node| Ok(node.expressions().iter().any(|e| e.is_volatile())))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Int64Array;
+    use arrow_schema::{DataType, Field, Schema};
+
+    fn key(query: &str) -> ResultKey {
+        ResultKey {
+            query: query.to_string(),
+            config: "{}".to_string(),
+            parameters: "{}".to_string(),
+            versions: vec![("memory://Person.lance".to_string(), 1)],
+        }
+    }
+
+    fn batch(rows: i64) -> RecordBatch {
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("n", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from_iter_values(0..rows))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hit_miss_and_ttl() {
+        let cache = ResultCache::new(ResultCacheOptions::default());
+        assert!(cache.get(&key("a")).is_none());
+        cache.insert(key("a"), batch(3));
+        assert_eq!(cache.get(&key("a")).unwrap().num_rows(), 3);
+
+        let mut other_version = key("a");
+        other_version.versions[0].1 = 2;
+        assert!(cache.get(&other_version).is_none());
+        assert_eq!(
+            cache.stats(),
+            ResultCacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 1,
+                bytes: batch(3).get_array_memory_size(),
+                backend_hits: 0,
+                backend_errors: 0,
+            }
+        );
+
+        let expiring = ResultCache::new(ResultCacheOptions::default().with_ttl(Duration::ZERO));
+        expiring.insert(key("a"), batch(3));
+        assert!(expiring.get(&key("a")).is_none());
+        assert_eq!(expiring.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_size_budget_evicts_least_recently_used() {
+        let size = batch(100).get_array_memory_size();
+        let cache =
+            ResultCache::new(ResultCacheOptions::default().with_max_bytes(size * 2 + size / 2));
+        cache.insert(key("a"), batch(100));
+        cache.insert(key("b"), batch(100));
+        // Touch "a" so that "b" is the least recently used
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), batch(100));
+
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+        assert!(cache.stats().bytes <= size * 2 + size / 2);
+
+        // Results above the whole budget are not cached
+        cache.insert(key("big"), batch(1_000));
+        assert!(cache.get(&key("big")).is_none());
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    /// Backend keeping values in memory, shared by several caches
+    #[derive(Debug, Default)]
+    struct MemoryBackend {
+        values: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl ResultCacheBackend for MemoryBackend {
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.values.lock().unwrap().get(key).cloned())
+        }
+
+        async fn put(&self, key: &str, value: Vec<u8>, _ttl: Duration) -> Result<()> {
+            self.values.lock().unwrap().insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stable_hash_and_canonical_json() {
+        assert_eq!(key("a").stable_hash(), key("a").stable_hash());
+        assert_eq!(key("a").stable_hash().len(), 32);
+        assert_ne!(key("a").stable_hash(), key("b").stable_hash());
+        let mut other_version = key("a");
+        other_version.versions[0].1 = 2;
+        assert_ne!(key("a").stable_hash(), other_version.stable_hash());
+
+        // Field boundaries are part of the hash
+        let mut shifted = key("ab");
+        shifted.config = "}".to_string();
+        let mut unshifted = key("a");
+        unshifted.config = "b}".to_string();
+        assert_ne!(shifted.stable_hash(), unshifted.stable_hash());
+
+        let value = serde_json::json!({"b": [1, {"d": null, "c": "x"}], "a": true});
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":true,"b":[1,{"c":"x","d":null}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backend_shares_results_between_caches() {
+        let backend = Arc::new(MemoryBackend::default());
+        let first = ResultCache::new(ResultCacheOptions::default()).with_backend(backend.clone());
+        let second = ResultCache::new(ResultCacheOptions::default()).with_backend(backend.clone());
+
+        assert!(first.lookup(&key("a")).await.is_none());
+        first.store(key("a"), batch(3)).await;
+        assert_eq!(
+            backend.values.lock().unwrap().keys().collect::<Vec<_>>(),
+            vec![&key("a").stable_hash()]
+        );
+
+        assert_eq!(second.lookup(&key("a")).await.unwrap().num_rows(), 3);
+        assert_eq!((second.stats().misses, second.stats().backend_hits), (1, 1));
+        // Now also cached in memory
+        assert!(second.lookup(&key("a")).await.is_some());
+        assert_eq!((second.stats().hits, second.stats().backend_hits), (1, 1));
+
+        backend
+            .values
+            .lock()
+            .unwrap()
+            .insert(key("b").stable_hash(), b"not ipc".to_vec());
+        assert!(second.lookup(&key("b")).await.is_none());
+        assert_eq!(second.stats().backend_errors, 1);
+    }
+}
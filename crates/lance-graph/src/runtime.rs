@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Query resource isolation
+//!
+//! Queries run on the Tokio runtime of their caller, so in a process serving
+//! both interactive lookups and heavy analytic queries the analytic scans and
+//! joins take the worker threads the interactive path needs for its I/O.
+//! [`QueryRuntimes`] maps each [`WorkloadClass`] to a dedicated runtime:
+//! queries of that class (see [`CypherQuery::with_runtimes`]) are spawned
+//! onto it, and so are the tasks DataFusion spawns while executing them.
+//! Classes without a dedicated runtime run on the caller's runtime.
+//!
+//! The runtimes are owned by the application; [`QueryRuntimes`] only holds
+//! their handles, so it must not outlive them. Dropping the future of a
+//! query aborts the task running it on the dedicated runtime.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::runtime::{QueryRuntimes, WorkloadClass};
+//!
+//! let analytic = tokio::runtime::Builder::new_multi_thread()
+//!     .worker_threads(4)
+//!     .thread_name("graph-analytic")
+//!     .enable_all()
+//!     .build()?;
+//! let runtimes = Arc::new(
+//!     QueryRuntimes::new().with_runtime(WorkloadClass::Analytic, analytic.handle().clone()),
+//! );
+//!
+//! let report = CypherQuery::new("MATCH (a)-[:KNOWS*1..4]->(b) RETURN a.id, count(b)")?
+//!     .with_config(config)
+//!     .with_runtimes(runtimes.clone())
+//!     .with_workload_class(WorkloadClass::Analytic)
+//!     .execute_with_namespace(namespace, None)
+//!     .await?;
+//! ```
+//!
+//! [`CypherQuery::with_runtimes`]: crate::query::CypherQuery::with_runtimes
+
+use crate::error::{GraphError, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use tokio::runtime::Handle;
+use tokio::task::AbortHandle;
+
+/// Kind of workload a query belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WorkloadClass {
+    /// Latency-sensitive queries, e.g. lookups serving a request
+    #[default]
+    Interactive,
+    /// Long-running scans and aggregations, e.g. reports and background jobs
+    Analytic,
+}
+
+impl fmt::Display for WorkloadClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interactive => write!(f, "interactive"),
+            Self::Analytic => write!(f, "analytic"),
+        }
+    }
+}
+
+/// Runtime handles per workload class
+#[derive(Debug, Clone, Default)]
+pub struct QueryRuntimes {
+    handles: HashMap<WorkloadClass, Handle>,
+}
+
+impl QueryRuntimes {
+    /// Run every workload class on the caller's runtime
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run queries of `class` on the runtime of `handle`
+    pub fn with_runtime(mut self, class: WorkloadClass, handle: Handle) -> Self {
+        self.handles.insert(class, handle);
+        self
+    }
+
+    /// Handle of the runtime dedicated to `class`, if any
+    pub fn handle(&self, class: WorkloadClass) -> Option<&Handle> {
+        self.handles.get(&class)
+    }
+
+    /// Drive `future` on the runtime of `class`, or on the caller's runtime
+    /// when the class has none
+    pub async fn run<F, T>(&self, class: WorkloadClass, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let Some(handle) = self.handle(class) else {
+            return future.await;
+        };
+        let task = handle.spawn(future);
+        let _abort = AbortOnDrop(task.abort_handle());
+        task.await.map_err(|e| GraphError::ExecutionError {
+            message: format!("Query on the {} runtime did not complete: {}", class, e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?
+    }
+}
+
+/// Aborts a spawned query when the future waiting for it is dropped
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::ThreadId;
+
+    fn dedicated_runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_run_uses_the_runtime_of_the_class() {
+        let analytic = dedicated_runtime();
+        let analytic_thread: ThreadId = analytic
+            .block_on(analytic.spawn(async { std::thread::current().id() }))
+            .unwrap();
+        let runtimes =
+            QueryRuntimes::new().with_runtime(WorkloadClass::Analytic, analytic.handle().clone());
+
+        let caller = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        caller.block_on(async {
+            let thread = runtimes
+                .run(WorkloadClass::Analytic, async {
+                    Ok(std::thread::current().id())
+                })
+                .await
+                .unwrap();
+            assert_eq!(thread, analytic_thread);
+
+            // Interactive has no dedicated runtime and stays on the caller's
+            let thread = runtimes
+                .run(WorkloadClass::Interactive, async {
+                    Ok(std::thread::current().id())
+                })
+                .await
+                .unwrap();
+            assert_eq!(thread, std::thread::current().id());
+
+            let err = runtimes
+                .run(WorkloadClass::Analytic, async {
+                    Err::<(), _>(GraphError::ExecutionError {
+                        message: "boom".to_string(),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                })
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("boom"));
+        });
+    }
+}
@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Multi-statement Cypher scripts
+//!
+//! Migration and setup scripts hold several statements separated by
+//! semicolons, such as creating nodes and then the relationships between
+//! them. [`CypherScript`] splits a script into its statements (see
+//! [`split_statements`]) and runs them one after another with the same
+//! configuration, parameters and session attributes; against a namespace,
+//! every statement sees the writes of the statements before it.
+//!
+//! Execution returns one result per statement, in order. It stops at the
+//! first failing statement, whose number the error reports; the writes of
+//! the statements before it are kept. [`CypherScript::execute_atomically`]
+//! instead stages the writes of all statements and publishes them once the
+//! last one succeeded, or not at all if a statement fails or conflicts with
+//! another writer.
+//!
+//! ```ignore
+//! use lance_graph::script::CypherScript;
+//!
+//! let script = CypherScript::new(
+//!     "// People first, then who knows whom
+//!      CREATE (:Person {id: 1, name: 'Alice'}), (:Person {id: 2, name: 'Bob'});
+//!      MATCH (a:Person {id: 1}), (b:Person {id: 2}) CREATE (a)-[:KNOWS]->(b);
+//!      MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name;",
+//! )?
+//! .with_config(config);
+//! let results = script.execute_with_namespace(namespace).await?;
+//! assert_eq!(results.len(), 3);
+//! ```
+//!
+//! [`split_statements`]: crate::parser::split_statements
+
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use crate::parser::split_statements;
+use crate::query::CypherQuery;
+use crate::semantic::ExperimentalFeature;
+use crate::transaction::StagedGraph;
+use arrow_array::RecordBatch;
+use lance_graph_catalog::DirNamespace;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The statements of a Cypher script, executed in order
+#[derive(Debug, Clone)]
+pub struct CypherScript {
+    statements: Vec<CypherQuery>,
+    /// Byte offset of every statement in the script text
+    offsets: Vec<usize>,
+}
+
+impl CypherScript {
+    /// Split `script` into its statements and parse each of them
+    ///
+    /// Parse errors report the statement and their position in `script`.
+    pub fn new(script: &str) -> Result<Self> {
+        let mut statements = Vec::new();
+        let mut offsets = Vec::new();
+        for (index, (offset, text)) in split_statements(script)?.into_iter().enumerate() {
+            let statement =
+                CypherQuery::new(text).map_err(|e| statement_error(index, offset, e))?;
+            statements.push(statement);
+            offsets.push(offset);
+        }
+        Ok(Self {
+            statements,
+            offsets,
+        })
+    }
+
+    /// The parsed statements, in script order
+    pub fn statements(&self) -> &[CypherQuery] {
+        &self.statements
+    }
+
+    /// Number of statements in the script
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Whether the script holds no statement
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Set the graph configuration of every statement
+    pub fn with_config(self, config: GraphConfig) -> Self {
+        self.map_statements(|statement| statement.with_config(config.clone()))
+    }
+
+    /// Add a parameter that every statement can read
+    pub fn with_parameter<K, V>(self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<serde_json::Value>,
+    {
+        let (key, value) = (key.into(), value.into());
+        self.map_statements(|statement| statement.with_parameter(key.clone(), value.clone()))
+    }
+
+    /// Add parameters that every statement can read
+    pub fn with_parameters(self, params: HashMap<String, serde_json::Value>) -> Self {
+        self.map_statements(|statement| statement.with_parameters(params.clone()))
+    }
+
+    /// Set an attribute of the calling session for every statement
+    ///
+    /// See [`CypherQuery::with_session_attribute`].
+    pub fn with_session_attribute(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        self.map_statements(|statement| {
+            statement.with_session_attribute(key.clone(), value.clone())
+        })
+    }
+
+    /// Accept the experimental syntax of `feature` in every statement
+    ///
+    /// See [`CypherQuery::with_experimental_feature`].
+    pub fn with_experimental_feature(self, feature: ExperimentalFeature) -> Self {
+        self.map_statements(|statement| statement.with_experimental_feature(feature))
+    }
+
+    /// Execute the statements in order against in-memory `datasets`
+    ///
+    /// Returns one result per statement.
+    pub async fn execute(
+        &self,
+        datasets: HashMap<String, RecordBatch>,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut results = Vec::with_capacity(self.statements.len());
+        for (index, statement) in self.statements.iter().enumerate() {
+            let result = statement
+                .execute(datasets.clone(), None)
+                .await
+                .map_err(|e| self.error(index, e))?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Execute the statements in order against the datasets of `namespace`
+    ///
+    /// Every statement reads the datasets as the statements before it left
+    /// them. Returns one result per statement.
+    pub async fn execute_with_namespace(
+        &self,
+        namespace: DirNamespace,
+    ) -> Result<Vec<RecordBatch>> {
+        let namespace = Arc::new(namespace);
+        let mut results = Vec::with_capacity(self.statements.len());
+        for (index, statement) in self.statements.iter().enumerate() {
+            let result = statement
+                .execute_with_namespace_arc(namespace.clone(), None)
+                .await
+                .map_err(|e| self.error(index, e))?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Execute the statements in order against staged clones of the datasets
+    /// of `namespace` they write to, publishing their writes only if every
+    /// statement succeeds
+    ///
+    /// Every statement reads the writes of the statements before it: a MATCH
+    /// after a MERGE returns the merged nodes even though they are not
+    /// committed yet. Every changed dataset gets a single new version. If a
+    /// statement fails, the datasets are left as they were. Publishing keeps
+    /// compatible commits other writers made meanwhile; a conflicting one
+    /// fails with [`GraphError::WriteConflict`] before any dataset is
+    /// published. The datasets are then committed one after the other, so a
+    /// writer committing during that step can still leave part of the script
+    /// published. Existing datasets of relationship types with parallel edges
+    /// cannot be written to. Returns one result per statement.
+    pub async fn execute_atomically(&self, namespace: DirNamespace) -> Result<Vec<RecordBatch>> {
+        let Some(first) = self.statements.first() else {
+            return Ok(Vec::new());
+        };
+        let mut staged = StagedGraph::begin(namespace, first.require_config()?);
+        let mut results = Vec::with_capacity(self.statements.len());
+        for (index, statement) in self.statements.iter().enumerate() {
+            match staged.execute(statement).await {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    staged.discard().await;
+                    return Err(self.error(index, e));
+                }
+            }
+        }
+        staged.publish().await?;
+        Ok(results)
+    }
+
+    /// `error` of the statement at `index`, located in the script
+    pub(crate) fn error(&self, index: usize, error: GraphError) -> GraphError {
+        statement_error(index, self.offsets[index], error)
+    }
+
+    fn map_statements(mut self, f: impl Fn(CypherQuery) -> CypherQuery) -> Self {
+        self.statements = self.statements.into_iter().map(f).collect();
+        self
+    }
+}
+
+/// `error` of the statement at `index`, which starts at byte `offset` of the
+/// script
+fn statement_error(index: usize, offset: usize, error: GraphError) -> GraphError {
+    match error {
+        GraphError::ParseError {
+            message,
+            position,
+            location,
+        } => GraphError::ParseError {
+            message: format!("statement {}: {}", index + 1, message),
+            position: offset + position,
+            location,
+        },
+        other => GraphError::ExecutionError {
+            message: format!("Statement {} of the script failed: {}", index + 1, other),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_parses_every_statement() {
+        let script = CypherScript::new(
+            "/* setup */\n\
+             CREATE (:Person {id: 1, name: 'Alice'});\n\
+             // then read it back\n\
+             MATCH (p:Person) RETURN p.name;\n",
+        )
+        .unwrap();
+        assert_eq!(script.len(), 2);
+        assert!(script.statements()[0].ast().create_clause.is_some());
+        assert!(script.statements()[1].ast().create_clause.is_none());
+        assert!(CypherScript::new("  // nothing to run\n")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors_locate_the_statement() {
+        let script = "MATCH (p:Person) RETURN p.name;\nMATCH (p:Person) RETURN";
+        let err = CypherScript::new(script).unwrap_err();
+        match err {
+            GraphError::ParseError {
+                message, position, ..
+            } => {
+                assert!(message.starts_with("statement 2:"), "{}", message);
+                assert!(position >= script.find('\n').unwrap());
+            }
+            other => panic!("expected a parse error, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_settings_apply_to_every_statement() {
+        let script = CypherScript::new("MATCH (p:Person) RETURN p; MATCH (c:City) RETURN c")
+            .unwrap()
+            .with_parameter("min", 3)
+            .with_session_attribute("tenant", "acme");
+        for statement in script.statements() {
+            assert_eq!(statement.parameters()["min"], serde_json::json!(3));
+        }
+    }
+}
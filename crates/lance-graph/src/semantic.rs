@@ -11,14 +11,106 @@
 use crate::ast::*;
 use crate::case_insensitive::CaseInsensitiveLookup;
 use crate::config::GraphConfig;
+use crate::datafusion_planner::expression::{condition_contains_aggregate, contains_aggregate};
 use crate::error::{GraphError, Result};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Which Cypher syntax extensions a query may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompatibilityMode {
+    /// Accept Neo4j-specific extensions such as `exists(n.prop)` and `ILIKE`
+    #[default]
+    Neo4j,
+    /// Reject syntax outside openCypher (e.g. require `n.prop IS NOT NULL`)
+    OpenCypher,
+}
+
+/// Syntax that queries only accept once opted in to
+///
+/// New grammar ships behind a feature until it is considered stable, so it
+/// cannot change the behaviour of production queries that did not ask for
+/// it. Features are named in snake case, e.g. `"call_subquery"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExperimentalFeature {
+    /// `CALL { ... }` subqueries
+    CallSubquery,
+}
+
+impl ExperimentalFeature {
+    /// Every experimental feature
+    pub const ALL: &'static [ExperimentalFeature] = &[ExperimentalFeature::CallSubquery];
+
+    /// Name of the feature
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExperimentalFeature::CallSubquery => "call_subquery",
+        }
+    }
+
+    /// The syntax the feature enables, for error messages
+    fn syntax(&self) -> &'static str {
+        match self {
+            ExperimentalFeature::CallSubquery => "CALL { ... } subqueries",
+        }
+    }
+}
+
+impl std::fmt::Display for ExperimentalFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ExperimentalFeature {
+    type Err = GraphError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|feature| feature.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!(
+                    "Unknown experimental feature '{}'; known features: {}",
+                    name,
+                    Self::ALL
+                        .iter()
+                        .map(ExperimentalFeature::name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+    }
+}
+
+/// Fail unless `feature`, which the query uses, is one of `enabled`
+pub(crate) fn require_feature(
+    enabled: &BTreeSet<ExperimentalFeature>,
+    feature: ExperimentalFeature,
+) -> Result<()> {
+    if enabled.contains(&feature) {
+        return Ok(());
+    }
+    Err(GraphError::UnsupportedFeature {
+        feature: format!(
+            "{} are experimental; enable the '{}' experimental feature to use them",
+            feature.syntax(),
+            feature
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
 
 /// Semantic analyzer - validates and enriches the AST
 pub struct SemanticAnalyzer {
     config: GraphConfig,
     variables: HashMap<String, VariableInfo>,
     current_scope: ScopeType,
+    compatibility_mode: CompatibilityMode,
+    /// Path variables bound to a single variable-length relationship, whose
+    /// length the planner tracks
+    measured_paths: HashSet<String>,
 }
 
 /// Information about a variable in the query
@@ -65,15 +157,49 @@ impl SemanticAnalyzer {
             config,
             variables: HashMap::new(),
             current_scope: ScopeType::Match,
+            compatibility_mode: CompatibilityMode::default(),
+            measured_paths: HashSet::new(),
         }
     }
 
+    /// Set which syntax extensions are accepted (default: Neo4j)
+    pub fn with_compatibility_mode(mut self, mode: CompatibilityMode) -> Self {
+        self.compatibility_mode = mode;
+        self
+    }
+
+    /// Reject a non-openCypher construct when running in strict mode
+    fn check_extension(&self, construct: &str, alternative: &str) -> Result<()> {
+        if self.compatibility_mode == CompatibilityMode::OpenCypher {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!(
+                    "{} is not part of openCypher; use {} instead",
+                    construct, alternative
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        Ok(())
+    }
+
     /// Analyze a Cypher query AST
     pub fn analyze(&mut self, query: &CypherQuery) -> Result<SemanticResult> {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
 
-        // Phase 1: Variable discovery in READING clauses (MATCH/UNWIND)
+        // A standalone CALL has no clauses to analyze
+        if let Some(call) = &query.procedure {
+            if let Err(e) = crate::summary::check_procedure(call) {
+                errors.push(format!("CALL error: {}", e));
+            }
+            return Ok(SemanticResult {
+                variables: HashMap::new(),
+                errors,
+                warnings,
+            });
+        }
+
+        // Phase 1: Variable discovery in READING clauses (MATCH/UNWIND/CALL)
         self.current_scope = ScopeType::Match;
         for clause in &query.reading_clauses {
             match clause {
@@ -87,6 +213,11 @@ impl SemanticAnalyzer {
                         errors.push(format!("UNWIND clause error: {}", e));
                     }
                 }
+                ReadingClause::TableFunction(call) => {
+                    if let Err(e) = self.analyze_table_function_call(call) {
+                        errors.push(format!("CALL clause error: {}", e));
+                    }
+                }
             }
         }
 
@@ -120,6 +251,11 @@ impl SemanticAnalyzer {
                         errors.push(format!("Post-WITH UNWIND clause error: {}", e));
                     }
                 }
+                ReadingClause::TableFunction(call) => {
+                    if let Err(e) = self.analyze_table_function_call(call) {
+                        errors.push(format!("Post-WITH CALL clause error: {}", e));
+                    }
+                }
             }
         }
 
@@ -131,6 +267,13 @@ impl SemanticAnalyzer {
             }
         }
 
+        // Phase 4: Validate SAMPLE clause if present
+        if let Some(sample) = &query.sample {
+            if let Err(e) = self.analyze_sample(sample) {
+                errors.push(format!("SAMPLE clause error: {}", e));
+            }
+        }
+
         // Phase 5: Validate RETURN clause
         self.current_scope = ScopeType::Return;
         if let Err(e) = self.analyze_return_clause(&query.return_clause) {
@@ -163,6 +306,12 @@ impl SemanticAnalyzer {
         for pattern in &match_clause.patterns {
             self.analyze_graph_pattern(pattern)?;
         }
+        if let Some(where_clause) = &match_clause.where_clause {
+            let scope = std::mem::replace(&mut self.current_scope, ScopeType::Where);
+            let result = self.analyze_where_clause(where_clause);
+            self.current_scope = scope;
+            result?;
+        }
         Ok(())
     }
 
@@ -196,6 +345,49 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// Check the arguments of a table function call and register its yielded
+    /// columns as property variables
+    fn analyze_table_function_call(&mut self, call: &TableFunctionCall) -> Result<()> {
+        for argument in &call.arguments {
+            if !matches!(
+                argument,
+                ValueExpression::Literal(_) | ValueExpression::Parameter(_)
+            ) {
+                return Err(GraphError::PlanError {
+                    message: format!("Arguments of {} must be literals or parameters", call.name),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+
+        for item in &call.yields {
+            let var_name_lower = item.variable().to_lowercase();
+            // A yielded column becomes a new column of every row, so it
+            // cannot rebind a variable
+            if self.variables.contains_key(&var_name_lower) {
+                return Err(GraphError::PlanError {
+                    message: format!(
+                        "Variable '{}' yielded by {} is already bound",
+                        item.variable(),
+                        call.name
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            self.variables.insert(
+                var_name_lower,
+                VariableInfo {
+                    name: item.variable().to_string(),
+                    variable_type: VariableType::Property,
+                    labels: vec![],
+                    properties: HashSet::new(),
+                    defined_in: self.current_scope.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
     /// Analyze a graph pattern and register variables
     fn analyze_graph_pattern(&mut self, pattern: &GraphPattern) -> Result<()> {
         match pattern {
@@ -203,6 +395,18 @@ impl SemanticAnalyzer {
                 self.register_node_variable(node)?;
             }
             GraphPattern::Path(path) => {
+                if path.shortest.is_some() {
+                    Self::validate_shortest_path(path)?;
+                }
+                if let Some(path_var) = &path.variable {
+                    self.register_path_variable(path_var)?;
+                    if let [segment] = path.segments.as_slice() {
+                        if segment.relationship.length.is_some() {
+                            self.measured_paths.insert(path_var.to_lowercase());
+                        }
+                    }
+                }
+
                 // Register start node
                 self.register_node_variable(&path.start_node)?;
 
@@ -223,6 +427,33 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// `shortestPath(...)` and `allShortestPaths(...)` wrap a single
+    /// variable-length relationship starting at zero or one hop
+    fn validate_shortest_path(path: &PathPattern) -> Result<()> {
+        let min = match path.segments.as_slice() {
+            [segment] => segment
+                .relationship
+                .length
+                .as_ref()
+                .map(|length| length.min.unwrap_or(1)),
+            _ => None,
+        };
+        match min {
+            Some(0 | 1) => Ok(()),
+            Some(min) => Err(GraphError::InvalidPattern {
+                message: format!(
+                    "shortestPath() requires a minimum length of 0 or 1, got {}",
+                    min
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+            None => Err(GraphError::InvalidPattern {
+                message: "shortestPath() requires a single variable-length relationship, e.g. shortestPath((a)-[:KNOWS*]-(b))".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            }),
+        }
+    }
+
     /// Register a node variable
     fn register_node_variable(&mut self, node: &NodePattern) -> Result<()> {
         if let Some(var_name) = &node.variable {
@@ -293,11 +524,94 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// Register a path variable
+    fn register_path_variable(&mut self, var_name: &str) -> Result<()> {
+        let var_name_lower = var_name.to_lowercase();
+        if self.variables.contains_key(&var_name_lower) {
+            return Err(GraphError::PlanError {
+                message: format!("Path variable '{}' is already defined", var_name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let var_info = VariableInfo {
+            name: var_name.to_string(),
+            variable_type: VariableType::Path,
+            labels: vec![],
+            properties: HashSet::new(),
+            defined_in: self.current_scope.clone(),
+        };
+        self.variables.insert(var_name_lower, var_info);
+        Ok(())
+    }
+
+    /// Analyze an `all(x IN nodes(p) WHERE ...)` predicate with `x` bound to
+    /// the path elements for the duration of the predicate
+    fn analyze_path_predicate(
+        &mut self,
+        variable: &str,
+        path: &str,
+        elements: PathElements,
+        predicate: &BooleanExpression,
+    ) -> Result<()> {
+        let is_path = self
+            .variables
+            .get(&path.to_lowercase())
+            .is_some_and(|info| info.variable_type == VariableType::Path);
+        if !is_path {
+            return Err(GraphError::PlanError {
+                message: format!("'{}' is not a path variable", path),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let key = variable.to_lowercase();
+        let element = VariableInfo {
+            name: variable.to_string(),
+            variable_type: match elements {
+                PathElements::Nodes => VariableType::Node,
+                PathElements::Relationships => VariableType::Relationship,
+            },
+            labels: vec![],
+            properties: HashSet::new(),
+            defined_in: self.current_scope.clone(),
+        };
+        let shadowed = self.variables.insert(key.clone(), element);
+        let result = self.analyze_boolean_expression(predicate);
+        match shadowed {
+            Some(info) => self.variables.insert(key, info),
+            None => self.variables.remove(&key),
+        };
+        result
+    }
+
     /// Analyze WHERE clause
     fn analyze_where_clause(&mut self, where_clause: &WhereClause) -> Result<()> {
         self.analyze_boolean_expression(&where_clause.expression)
     }
 
+    /// Validate a SAMPLE clause
+    fn analyze_sample(&mut self, sample: &SampleMethod) -> Result<()> {
+        self.check_extension("SAMPLE", "WHERE rand() < fraction")?;
+        match sample {
+            SampleMethod::Percent(percent) if !(*percent > 0.0 && *percent <= 100.0) => {
+                Err(GraphError::PlanError {
+                    message: format!("SAMPLE percentage must be in (0, 100], got {}", percent),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+            SampleMethod::RowsPer { by, .. } => {
+                if matches!(by, ValueExpression::AggregateFunction { .. }) {
+                    return Err(GraphError::PlanError {
+                        message: "SAMPLE ... PER cannot use an aggregate".to_string(),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                self.analyze_value_expression(by)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Analyze boolean expression and check variable references
     fn analyze_boolean_expression(&mut self, expr: &BooleanExpression) -> Result<()> {
         match expr {
@@ -313,6 +627,10 @@ impl SemanticAnalyzer {
                 self.analyze_boolean_expression(inner)?;
             }
             BooleanExpression::Exists(prop_ref) => {
+                self.check_extension(
+                    &format!("exists({}.{})", prop_ref.variable, prop_ref.property),
+                    &format!("{}.{} IS NOT NULL", prop_ref.variable, prop_ref.property),
+                )?;
                 self.validate_property_reference(prop_ref)?;
             }
             BooleanExpression::In { expression, list } => {
@@ -325,6 +643,7 @@ impl SemanticAnalyzer {
                 self.analyze_value_expression(expression)?;
             }
             BooleanExpression::ILike { expression, .. } => {
+                self.check_extension("ILIKE", "toLower(...) with LIKE or STARTS WITH")?;
                 self.analyze_value_expression(expression)?;
             }
             BooleanExpression::Contains { expression, .. } => {
@@ -336,12 +655,47 @@ impl SemanticAnalyzer {
             BooleanExpression::EndsWith { expression, .. } => {
                 self.analyze_value_expression(expression)?;
             }
+            BooleanExpression::RegexMatch {
+                expression,
+                pattern,
+            } => {
+                self.analyze_value_expression(expression)?;
+                if let Err(e) = crate::datafusion_planner::expression::compile_regex(pattern) {
+                    return Err(GraphError::PlanError {
+                        message: format!("Invalid regular expression '{}': {}", pattern, e),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+            }
             BooleanExpression::IsNull(expression) => {
                 self.analyze_value_expression(expression)?;
             }
             BooleanExpression::IsNotNull(expression) => {
                 self.analyze_value_expression(expression)?;
             }
+            BooleanExpression::AllInPath {
+                variable,
+                path,
+                elements,
+                predicate,
+            } => {
+                self.analyze_path_predicate(variable, path, *elements, predicate)?;
+            }
+            BooleanExpression::ListPredicate {
+                variable,
+                list,
+                predicate,
+                ..
+            } => {
+                self.analyze_value_expression(list)?;
+                self.analyze_list_comprehension(variable, Some(predicate), None)?;
+            }
+            BooleanExpression::ExistsSubquery {
+                patterns,
+                where_clause,
+            } => {
+                self.analyze_subquery("EXISTS", patterns, where_clause.as_deref())?;
+            }
         }
         Ok(())
     }
@@ -368,7 +722,8 @@ impl SemanticAnalyzer {
                 let function_name = name.to_lowercase();
                 // Validate arity and known functions
                 match function_name.as_str() {
-                    "tolower" | "lower" | "toupper" | "upper" => {
+                    "tolower" | "lower" | "toupper" | "upper" | "trim" | "ltrim" | "rtrim"
+                    | "reverse" | "size" | "head" | "last" | "tail" => {
                         if args.len() != 1 {
                             return Err(GraphError::PlanError {
                                 message: format!(
@@ -380,11 +735,93 @@ impl SemanticAnalyzer {
                             });
                         }
                     }
+                    "split" | "left" | "right" | "replace" | "substring" | "range" | "nullif" => {
+                        let arity = match function_name.as_str() {
+                            "replace" => 3..=3,
+                            "substring" | "range" => 2..=3,
+                            _ => 2..=2,
+                        };
+                        if !arity.contains(&args.len()) {
+                            return Err(GraphError::PlanError {
+                                message: format!(
+                                    "{} requires {} arguments, got {}",
+                                    name.to_uppercase(),
+                                    if arity.start() == arity.end() {
+                                        arity.start().to_string()
+                                    } else {
+                                        format!("{} or {}", arity.start(), arity.end())
+                                    },
+                                    args.len()
+                                ),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+                    }
+                    "coalesce" => {
+                        if args.is_empty() {
+                            return Err(GraphError::PlanError {
+                                message: "COALESCE requires at least 1 argument".to_string(),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+                    }
+                    "rand" => {
+                        if !matches!(
+                            args.as_slice(),
+                            [] | [ValueExpression::Literal(PropertyValue::Integer(_))]
+                        ) {
+                            return Err(GraphError::PlanError {
+                                message: "RAND takes no argument or an integer literal seed"
+                                    .to_string(),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+                    }
+                    "length" => {
+                        // Strings are measured too; of variables only paths are
+                        let measured = match args.as_slice() {
+                            [ValueExpression::Variable(path)] => {
+                                self.measured_paths.contains(&path.to_lowercase())
+                            }
+                            [_] => true,
+                            _ => false,
+                        };
+                        if !measured {
+                            return Err(GraphError::UnsupportedFeature {
+                                feature: "LENGTH of anything but a string or a path variable bound to a single variable-length relationship, e.g. p = (a)-[:KNOWS*1..3]->(b)".to_string(),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+                    }
+                    function if crate::temporal::is_function(function) => {
+                        crate::temporal::check_call(function, args)?;
+                    }
+                    // Introspection calls are resolved before analysis by
+                    // the DataFusion strategy only
+                    function if crate::introspection::is_function(function) => {
+                        return Err(GraphError::UnsupportedFeature {
+                            feature: format!("{}() with the simple execution strategy", name),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        });
+                    }
+                    crate::introspection::LIST_FUNCTION => {}
+                    "randomuuid" | "timestamp" => {
+                        if !args.is_empty() {
+                            return Err(GraphError::PlanError {
+                                message: format!(
+                                    "{} takes no arguments, got {}",
+                                    name.to_uppercase(),
+                                    args.len()
+                                ),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+                    }
                     _ => {
                         // Unknown scalar function - reject early with helpful error
                         return Err(GraphError::UnsupportedFeature {
                             feature: format!(
-                                "Cypher function '{}' is not implemented. Supported scalar functions: toLower, lower, toUpper, upper. Supported aggregate functions: COUNT, SUM, AVG, MIN, MAX, COLLECT.",
+                                "Cypher function '{}' is not implemented. Supported scalar functions: toLower, lower, toUpper, upper, split, replace, substring, left, right, trim, ltrim, rtrim, reverse, range, size, head, last, tail, coalesce, nullIf, rand, randomUUID, timestamp, length, date, datetime, duration, duration.between, date.truncate, datetime.truncate, labels, type, keys, properties, id. Supported aggregate functions: COUNT, SUM, AVG, MIN, MAX, COLLECT, stDev, stDevP, percentileCont, percentileDisc.",
                                 name
                             ),
                             location: snafu::Location::new(file!(), line!(), column!()),
@@ -405,13 +842,14 @@ impl SemanticAnalyzer {
                 let function_name = name.to_lowercase();
                 // Validate known aggregate functions
                 match function_name.as_str() {
-                    "count" | "sum" | "avg" | "min" | "max" | "collect" => {
-                        // DISTINCT is only supported for COUNT
+                    "count" | "sum" | "avg" | "min" | "max" | "collect" | "stdev" | "stdevp"
+                    | "percentilecont" | "percentiledisc" => {
+                        // DISTINCT is only supported for COUNT and COLLECT
                         // Other aggregates silently ignore it in execution, so reject early
-                        if *distinct && function_name != "count" {
+                        if *distinct && !matches!(function_name.as_str(), "count" | "collect") {
                             return Err(GraphError::UnsupportedFeature {
                                 feature: format!(
-                                    "DISTINCT is only supported with COUNT, not {}",
+                                    "DISTINCT is only supported with COUNT and COLLECT, not {}",
                                     function_name.to_uppercase()
                                 ),
                                 location: snafu::Location::new(file!(), line!(), column!()),
@@ -433,8 +871,40 @@ impl SemanticAnalyzer {
                                 }
                             }
                         }
-                        // All aggregates require exactly 1 argument
-                        if args.len() != 1 {
+                        // percentileCont and percentileDisc take the percentile
+                        // as a number literal between 0.0 and 1.0
+                        let percentile =
+                            matches!(function_name.as_str(), "percentilecont" | "percentiledisc");
+                        if percentile && args.len() == 2 {
+                            let fraction = match &args[1] {
+                                ValueExpression::Literal(PropertyValue::Integer(i)) => {
+                                    Some(*i as f64)
+                                }
+                                ValueExpression::Literal(PropertyValue::Float(f)) => Some(*f),
+                                _ => None,
+                            };
+                            if !fraction.is_some_and(|f| (0.0..=1.0).contains(&f)) {
+                                return Err(GraphError::PlanError {
+                                    message: format!(
+                                        "{} requires a percentile between 0.0 and 1.0 as its second argument",
+                                        name
+                                    ),
+                                    location: snafu::Location::new(file!(), line!(), column!()),
+                                });
+                            }
+                        } else if percentile {
+                            return Err(GraphError::PlanError {
+                                message: format!(
+                                    "{} requires exactly 2 arguments, got {}",
+                                    name,
+                                    args.len()
+                                ),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            });
+                        }
+
+                        // All other aggregates require exactly 1 argument
+                        if !percentile && args.len() != 1 {
                             return Err(GraphError::PlanError {
                                 message: format!(
                                     "{} requires exactly 1 argument, got {}",
@@ -445,9 +915,9 @@ impl SemanticAnalyzer {
                             });
                         }
 
-                        // Additional validation for SUM, AVG, MIN, MAX: they require properties, not bare variables
+                        // Additional validation for the numeric aggregates: they require properties, not bare variables
                         // Only COUNT and COLLECT allow bare variables (COUNT(*), COUNT(p), COLLECT(p))
-                        if matches!(function_name.as_str(), "sum" | "avg" | "min" | "max") {
+                        if !matches!(function_name.as_str(), "count" | "collect") {
                             if let Some(ValueExpression::Variable(v)) = args.first() {
                                 return Err(GraphError::PlanError {
                                     message: format!(
@@ -463,7 +933,7 @@ impl SemanticAnalyzer {
                         // Unknown aggregate function - reject early
                         return Err(GraphError::UnsupportedFeature {
                             feature: format!(
-                                "Cypher aggregate function '{}' is not implemented. Supported aggregate functions: COUNT, SUM, AVG, MIN, MAX, COLLECT.",
+                                "Cypher aggregate function '{}' is not implemented. Supported aggregate functions: COUNT, SUM, AVG, MIN, MAX, COLLECT, stDev, stDevP, percentileCont, percentileDisc.",
                                 name
                             ),
                             location: snafu::Location::new(file!(), line!(), column!()),
@@ -550,10 +1020,252 @@ impl SemanticAnalyzer {
             ValueExpression::Parameter(_) => {
                 // Parameters are always valid (resolved at runtime)
             }
+            ValueExpression::Case { branches, default } => {
+                for branch in branches {
+                    self.analyze_boolean_expression(&branch.condition)?;
+                    self.analyze_value_expression(&branch.value)?;
+                }
+                if let Some(default) = default {
+                    self.analyze_value_expression(default)?;
+                }
+            }
+            ValueExpression::ListComprehension {
+                variable,
+                list,
+                predicate,
+                projection,
+            } => {
+                self.analyze_value_expression(list)?;
+                self.analyze_list_comprehension(
+                    variable,
+                    predicate.as_deref(),
+                    projection.as_deref(),
+                )?;
+            }
+            ValueExpression::Reduce {
+                accumulator,
+                initial,
+                variable,
+                list,
+                expression,
+            } => {
+                self.analyze_value_expression(initial)?;
+                self.analyze_value_expression(list)?;
+                self.analyze_reduce(accumulator, variable, expression)?;
+            }
+            ValueExpression::PatternComprehension {
+                pattern,
+                predicate,
+                projection,
+            } => {
+                self.analyze_pattern_comprehension(pattern, predicate.as_deref(), projection)?;
+            }
+            ValueExpression::MapProjection { variable, items } => {
+                self.analyze_map_projection(variable, items)?;
+            }
+            ValueExpression::CountSubquery {
+                patterns,
+                where_clause,
+            } => {
+                self.analyze_subquery("COUNT", patterns, where_clause.as_deref())?;
+            }
         }
         Ok(())
     }
 
+    /// Analyze the items of a map projection of `variable`
+    fn analyze_map_projection(
+        &mut self,
+        variable: &str,
+        items: &[MapProjectionItem],
+    ) -> Result<()> {
+        if !self.variables.contains_key_ci(variable) {
+            return Err(GraphError::PlanError {
+                message: format!("Undefined variable: '{}'", variable),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|other| other.key == item.key) {
+                return Err(GraphError::PlanError {
+                    message: format!(
+                        "Duplicate key '{}' in map projection of '{}'",
+                        item.key, variable
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            // Mixing aggregated and per-row values would need implicit grouping
+            if contains_aggregate(&item.value) {
+                return Err(GraphError::PlanError {
+                    message: "Aggregates are not allowed inside a map projection; aggregate in a \
+                              WITH clause first"
+                        .to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            self.analyze_value_expression(&item.value)?;
+        }
+        Ok(())
+    }
+
+    /// Analyze the pattern, predicate and projection of a pattern
+    /// comprehension with the pattern's variables in scope
+    fn analyze_pattern_comprehension(
+        &mut self,
+        pattern: &PathPattern,
+        predicate: Option<&BooleanExpression>,
+        projection: &ValueExpression,
+    ) -> Result<()> {
+        if contains_aggregate(projection) || predicate.is_some_and(condition_contains_aggregate) {
+            return Err(GraphError::PlanError {
+                message: "Aggregates are not allowed inside a pattern comprehension".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        // Variables the pattern introduces are local to the comprehension
+        let variables = self.variables.clone();
+        let measured_paths = self.measured_paths.clone();
+        let result = self
+            .analyze_graph_pattern(&GraphPattern::Path(pattern.clone()))
+            .and_then(|_| predicate.map_or(Ok(()), |p| self.analyze_boolean_expression(p)))
+            .and_then(|_| self.analyze_value_expression(projection));
+        self.variables = variables;
+        self.measured_paths = measured_paths;
+        result
+    }
+
+    /// Analyze the patterns and predicate of an `EXISTS { ... }` or
+    /// `COUNT { ... }` subquery, named by `kind`
+    ///
+    /// The subquery only receives the outer variables its patterns share
+    /// with the outer rows (as join keys, or pinned to the imported nodes),
+    /// so its predicate can only refer to the variables of its own patterns.
+    fn analyze_subquery(
+        &mut self,
+        kind: &str,
+        patterns: &[GraphPattern],
+        predicate: Option<&BooleanExpression>,
+    ) -> Result<()> {
+        if predicate.is_some_and(condition_contains_aggregate) {
+            return Err(GraphError::PlanError {
+                message: format!("Aggregates are not allowed inside {} subqueries", kind),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        // Variables the patterns introduce are local to the subquery
+        let variables = self.variables.clone();
+        let measured_paths = self.measured_paths.clone();
+        let result = patterns
+            .iter()
+            .try_for_each(|pattern| self.analyze_graph_pattern(pattern))
+            .and_then(|_| {
+                let Some(predicate) = predicate else {
+                    return Ok(());
+                };
+                let mut pattern_variables = HashSet::new();
+                for pattern in patterns {
+                    collect_pattern_variables(pattern, &mut pattern_variables);
+                }
+                self.variables
+                    .retain(|name, _| pattern_variables.contains(name));
+                self.analyze_boolean_expression(predicate)
+            });
+        self.variables = variables;
+        self.measured_paths = measured_paths;
+        result
+    }
+
+    /// Analyze the predicate and projection of a list comprehension, or the
+    /// predicate of a list predicate, with its element variable in scope
+    fn analyze_list_comprehension(
+        &mut self,
+        variable: &str,
+        predicate: Option<&BooleanExpression>,
+        projection: Option<&ValueExpression>,
+    ) -> Result<()> {
+        if projection.is_some_and(contains_aggregate)
+            || predicate.is_some_and(condition_contains_aggregate)
+        {
+            return Err(GraphError::PlanError {
+                message: format!(
+                    "Aggregates are not allowed inside a list comprehension or predicate over '{}'",
+                    variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let key = variable.to_lowercase();
+        let element = VariableInfo {
+            name: variable.to_string(),
+            variable_type: VariableType::Property,
+            labels: vec![],
+            properties: HashSet::new(),
+            defined_in: self.current_scope.clone(),
+        };
+        let shadowed = self.variables.insert(key.clone(), element);
+        let result = predicate
+            .map_or(Ok(()), |p| self.analyze_boolean_expression(p))
+            .and_then(|_| projection.map_or(Ok(()), |p| self.analyze_value_expression(p)));
+        match shadowed {
+            Some(info) => self.variables.insert(key, info),
+            None => self.variables.remove(&key),
+        };
+        result
+    }
+
+    /// Analyze the expression of a reduction with its accumulator and
+    /// element variables in scope
+    fn analyze_reduce(
+        &mut self,
+        accumulator: &str,
+        variable: &str,
+        expression: &ValueExpression,
+    ) -> Result<()> {
+        if accumulator.eq_ignore_ascii_case(variable) {
+            return Err(GraphError::PlanError {
+                message: format!(
+                    "reduce() needs distinct accumulator and element variables, got '{}' twice",
+                    variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        if contains_aggregate(expression) {
+            return Err(GraphError::PlanError {
+                message: format!(
+                    "Aggregates are not allowed inside a reduce() over '{}'",
+                    variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let mut shadowed = Vec::new();
+        for name in [accumulator, variable] {
+            let local = VariableInfo {
+                name: name.to_string(),
+                variable_type: VariableType::Property,
+                labels: vec![],
+                properties: HashSet::new(),
+                defined_in: self.current_scope.clone(),
+            };
+            let key = name.to_lowercase();
+            shadowed.push((key.clone(), self.variables.insert(key, local)));
+        }
+        let result = self.analyze_value_expression(expression);
+        for (key, info) in shadowed {
+            match info {
+                Some(info) => self.variables.insert(key, info),
+                None => self.variables.remove(&key),
+            };
+        }
+        result
+    }
+
     fn register_projection_alias(&mut self, alias: &str) {
         // Use case-insensitive lookup and store normalized key
         if self.variables.contains_key_ci(alias) {
@@ -584,6 +1296,9 @@ impl SemanticAnalyzer {
 
     /// Analyze RETURN clause
     fn analyze_return_clause(&mut self, return_clause: &ReturnClause) -> Result<()> {
+        for key in &return_clause.distinct_on {
+            self.analyze_value_expression(key)?;
+        }
         for item in &return_clause.items {
             self.analyze_value_expression(&item.expression)?;
             if let Some(alias) = &item.alias {
@@ -732,6 +1447,26 @@ impl SemanticAnalyzer {
     }
 }
 
+/// Add the (lowercased) variables `pattern` binds to `variables`
+fn collect_pattern_variables(pattern: &GraphPattern, variables: &mut HashSet<String>) {
+    let mut add = |variable: &Option<String>| {
+        if let Some(variable) = variable {
+            variables.insert(variable.to_lowercase());
+        }
+    };
+    match pattern {
+        GraphPattern::Node(node) => add(&node.variable),
+        GraphPattern::Path(path) => {
+            add(&path.variable);
+            add(&path.start_node.variable);
+            for segment in &path.segments {
+                add(&segment.relationship.variable);
+                add(&segment.end_node.variable);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -762,6 +1497,7 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![ReturnItem {
                     expression: expr,
                     alias: None,
@@ -770,6 +1506,15 @@ mod tests {
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
         let mut analyzer = SemanticAnalyzer::new(test_config());
         analyzer.analyze(&query)
@@ -785,6 +1530,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Node(node)],
+                optional: false,
+                where_clause: None,
             })],
             where_clause: None,
             with_clause: None,
@@ -792,6 +1539,7 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![ReturnItem {
                     expression: expr,
                     alias: None,
@@ -800,6 +1548,15 @@ mod tests {
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
         let mut analyzer = SemanticAnalyzer::new(test_config());
         analyzer.analyze(&query)
@@ -818,6 +1575,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Node(node1), GraphPattern::Node(node2)],
+                optional: false,
+                where_clause: None,
             })],
             where_clause: None,
             with_clause: None,
@@ -825,11 +1584,21 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -857,16 +1626,20 @@ mod tests {
         });
 
         let path = PathPattern {
+            variable: None,
             start_node: start,
             segments: vec![PathSegment {
                 relationship: rel,
                 end_node: end,
             }],
+            shortest: None,
         };
 
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Path(path)],
+                optional: false,
+                where_clause: None,
             })],
             where_clause: None,
             with_clause: None,
@@ -874,11 +1647,21 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -899,6 +1682,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Node(node)],
+                optional: false,
+                where_clause: None,
             })],
             where_clause: Some(where_clause),
             with_clause: None,
@@ -906,11 +1691,21 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -931,16 +1726,20 @@ mod tests {
             .with_type("KNOWS");
 
         let path = PathPattern {
+            variable: None,
             start_node: start,
             segments: vec![PathSegment {
                 relationship: rel,
                 end_node: end,
             }],
+            shortest: None,
         };
 
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Path(path)],
+                optional: false,
+                where_clause: None,
             })],
             where_clause: None,
             with_clause: None,
@@ -948,11 +1747,21 @@ mod tests {
             post_with_where_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -970,6 +1779,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Node(node)],
+                optional: false,
+                where_clause: None,
             })],
             post_with_reading_clauses: vec![],
             post_with_where_clause: None,
@@ -977,11 +1788,21 @@ mod tests {
             with_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -1010,6 +1831,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Node(node)],
+                optional: false,
+                where_clause: None,
             })],
             post_with_reading_clauses: vec![],
             post_with_where_clause: None,
@@ -1017,11 +1840,21 @@ mod tests {
             with_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(custom_config);
@@ -1045,16 +1878,20 @@ mod tests {
         });
 
         let path = PathPattern {
+            variable: None,
             start_node: start,
             segments: vec![PathSegment {
                 relationship: rel,
                 end_node: end,
             }],
+            shortest: None,
         };
 
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Path(path)],
+                optional: false,
+                where_clause: None,
             })],
             post_with_reading_clauses: vec![],
             post_with_where_clause: None,
@@ -1062,11 +1899,21 @@ mod tests {
             with_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(test_config());
@@ -1098,6 +1945,7 @@ mod tests {
         rel2.length = None;
 
         let path = PathPattern {
+            variable: None,
             start_node: start,
             segments: vec![
                 PathSegment {
@@ -1109,6 +1957,7 @@ mod tests {
                     end_node: end,
                 },
             ],
+            shortest: None,
         };
 
         // Custom config that knows both relationship types to avoid warnings muddying the assertion
@@ -1122,6 +1971,8 @@ mod tests {
         let query = CypherQuery {
             reading_clauses: vec![ReadingClause::Match(MatchClause {
                 patterns: vec![GraphPattern::Path(path)],
+                optional: false,
+                where_clause: None,
             })],
             post_with_reading_clauses: vec![],
             post_with_where_clause: None,
@@ -1129,11 +1980,21 @@ mod tests {
             with_clause: None,
             return_clause: ReturnClause {
                 distinct: false,
+                distinct_on: Vec::new(),
                 items: vec![],
             },
             limit: None,
             order_by: None,
             skip: None,
+            include_deleted: false,
+            sample: None,
+            procedure: None,
+            create_clause: None,
+            merge_clause: None,
+            set_clause: None,
+            remove_clause: None,
+            delete_clause: None,
+            call_subquery: None,
         };
 
         let mut analyzer = SemanticAnalyzer::new(custom_config);
@@ -1434,8 +2295,8 @@ mod tests {
     }
 
     #[test]
-    fn test_distinct_only_supported_on_count() {
-        // SUM(DISTINCT n.age) should fail - DISTINCT only supported for COUNT
+    fn test_distinct_only_supported_on_count_and_collect() {
+        // SUM(DISTINCT n.age) should fail - DISTINCT only supported for COUNT and COLLECT
         let expr = ValueExpression::AggregateFunction {
             name: "sum".to_string(),
             args: vec![ValueExpression::Property(PropertyRef::new("n", "age"))],
@@ -1450,6 +2311,43 @@ mod tests {
             "Expected error about DISTINCT only for COUNT, got: {:?}",
             result.errors
         );
+
+        let expr = ValueExpression::AggregateFunction {
+            name: "collect".to_string(),
+            args: vec![ValueExpression::Property(PropertyRef::new("n", "age"))],
+            distinct: true,
+        };
+        let result = analyze_return_with_match("n", "Person", expr).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_percentile_requires_fraction_literal() {
+        let percentile = |args: Vec<ValueExpression>| {
+            let expr = ValueExpression::AggregateFunction {
+                name: "percentileDisc".to_string(),
+                args,
+                distinct: false,
+            };
+            analyze_return_with_match("n", "Person", expr)
+                .unwrap()
+                .errors
+        };
+        let age = || ValueExpression::Property(PropertyRef::new("n", "age"));
+        assert!(percentile(vec![
+            age(),
+            ValueExpression::Literal(PropertyValue::Float(0.9))
+        ])
+        .is_empty());
+        assert!(percentile(vec![age()])
+            .iter()
+            .any(|e| e.contains("requires exactly 2 arguments")));
+        assert!(percentile(vec![
+            age(),
+            ValueExpression::Literal(PropertyValue::Integer(2))
+        ])
+        .iter()
+        .any(|e| e.contains("between 0.0 and 1.0")));
     }
 
     #[test]
@@ -1643,4 +2541,202 @@ mod tests {
             assert!(result.unwrap().errors.is_empty());
         }
     }
+
+    #[test]
+    fn test_compatibility_mode_exists_function() {
+        let query = crate::parser::parse_cypher_query(
+            "MATCH (n:Person) WHERE exists(n.name) RETURN n.name",
+        )
+        .unwrap();
+
+        let mut lenient = SemanticAnalyzer::new(test_config());
+        let result = lenient.analyze(&query).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let mut strict = SemanticAnalyzer::new(test_config())
+            .with_compatibility_mode(CompatibilityMode::OpenCypher);
+        let result = strict.analyze(&query).unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("n.name IS NOT NULL"));
+    }
+
+    #[test]
+    fn test_experimental_features_by_name() {
+        let feature: ExperimentalFeature = "CALL_SUBQUERY".parse().unwrap();
+        assert_eq!(feature, ExperimentalFeature::CallSubquery);
+        assert_eq!(feature.to_string(), "call_subquery");
+        let err = "quantified_path_patterns"
+            .parse::<ExperimentalFeature>()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("known features: call_subquery"),
+            "{}",
+            err
+        );
+
+        let mut enabled = BTreeSet::new();
+        assert!(require_feature(&enabled, feature).is_err());
+        enabled.insert(feature);
+        require_feature(&enabled, feature).unwrap();
+    }
+
+    #[test]
+    fn test_compatibility_mode_ilike() {
+        let query = crate::parser::parse_cypher_query(
+            "MATCH (n:Person) WHERE n.name ILIKE 'a%' RETURN n.name",
+        )
+        .unwrap();
+
+        let mut strict = SemanticAnalyzer::new(test_config())
+            .with_compatibility_mode(CompatibilityMode::OpenCypher);
+        let result = strict.analyze(&query).unwrap();
+        assert!(result.errors.iter().any(|e| e.contains("ILIKE")));
+
+        // Standard syntax is accepted in strict mode
+        let query = crate::parser::parse_cypher_query(
+            "MATCH (n:Person) WHERE n.name IS NOT NULL RETURN n.name",
+        )
+        .unwrap();
+        let mut strict = SemanticAnalyzer::new(test_config())
+            .with_compatibility_mode(CompatibilityMode::OpenCypher);
+        assert!(strict.analyze(&query).unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn test_shortest_path_requires_single_variable_length_relationship() {
+        let errors = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            SemanticAnalyzer::new(test_config())
+                .analyze(&query)
+                .unwrap()
+                .errors
+        };
+        assert!(errors(
+            "MATCH p = shortestPath((a:Person)-[:KNOWS*]-(b:Person)) RETURN p, length(p)"
+        )
+        .is_empty());
+        for cypher in [
+            "MATCH p = shortestPath((a:Person)-[:KNOWS]->(b:Person)) RETURN p",
+            "MATCH p = allShortestPaths((a:Person)-[:KNOWS*2..4]->(b:Person)) RETURN p",
+            "MATCH p = shortestPath((a:Person)-[:KNOWS*]->(b:Person)-[:KNOWS]->(c:Person)) RETURN p",
+        ] {
+            assert!(
+                errors(cypher).iter().any(|e| e.contains("shortestPath()")),
+                "{}",
+                cypher
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_length_requires_variable_length_path() {
+        let errors = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            SemanticAnalyzer::new(test_config())
+                .analyze(&query)
+                .unwrap()
+                .errors
+        };
+        assert!(
+            errors("MATCH p = (a:Person)-[:KNOWS*1..3]->(b:Person) RETURN b.name, length(p)")
+                .is_empty()
+        );
+        for cypher in [
+            "MATCH p = (a:Person)-[:KNOWS]->(b:Person) RETURN length(p)",
+            "MATCH (a:Person)-[:KNOWS*1..3]->(b:Person) RETURN length(a)",
+            "MATCH p = (a:Person)-[:KNOWS*1..2]->(b:Person)-[:KNOWS]->(c:Person) RETURN length(p)",
+        ] {
+            assert!(
+                errors(cypher).iter().any(|e| e.contains("LENGTH")),
+                "{}",
+                cypher
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_clause_validation() {
+        let errors = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            SemanticAnalyzer::new(test_config())
+                .analyze(&query)
+                .unwrap()
+                .errors
+        };
+        assert!(errors("MATCH (n:Person) SAMPLE 10 PERCENT RETURN n.name").is_empty());
+        assert!(errors("MATCH (n:Person) SAMPLE 150 PERCENT RETURN n.name")
+            .iter()
+            .any(|e| e.contains("SAMPLE percentage")));
+        assert!(
+            errors("MATCH (n:Person) SAMPLE 2 ROWS PER m.name RETURN n.name")
+                .iter()
+                .any(|e| e.contains("SAMPLE clause error"))
+        );
+    }
+
+    #[test]
+    fn test_map_projection_validation() {
+        let fails = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            match SemanticAnalyzer::new(test_config()).analyze(&query) {
+                Ok(result) => !result.errors.is_empty(),
+                Err(_) => true,
+            }
+        };
+        assert!(!fails(
+            "MATCH (n:Person) RETURN n {.name, double: n.age * 2}"
+        ));
+        assert!(fails("MATCH (n:Person) RETURN m {.name}"));
+        assert!(fails("MATCH (n:Person) RETURN n {.name, name: n.age}"));
+        assert!(fails("MATCH (n:Person) RETURN n {.name, total: count(*)}"));
+    }
+
+    #[test]
+    fn test_exists_subquery_validation() {
+        let fails = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            match SemanticAnalyzer::new(test_config()).analyze(&query) {
+                Ok(result) => !result.errors.is_empty(),
+                Err(_) => true,
+            }
+        };
+        assert!(!fails(
+            "MATCH (p:Person) WHERE EXISTS { MATCH (p)-[:KNOWS]->(f:Person) WHERE f.age > 30 } \
+             RETURN p.name"
+        ));
+        // Variables of the subquery are not visible outside of it
+        assert!(fails(
+            "MATCH (p:Person) WHERE EXISTS { (p)-[:KNOWS]->(f:Person) } RETURN f.name"
+        ));
+        // Its predicate only sees the variables of its patterns
+        assert!(fails(
+            "MATCH (p:Person), (c:Company) \
+             WHERE EXISTS { (p)-[:KNOWS]->(f:Person) WHERE f.name = c.name } RETURN p.name"
+        ));
+        assert!(fails(
+            "MATCH (p:Person) WHERE EXISTS { (p)-[:KNOWS]->(f:Person) WHERE count(f) > 1 } \
+             RETURN p.name"
+        ));
+    }
+
+    #[test]
+    fn test_count_subquery_validation() {
+        let fails = |cypher: &str| {
+            let query = crate::parser::parse_cypher_query(cypher).unwrap();
+            match SemanticAnalyzer::new(test_config()).analyze(&query) {
+                Ok(result) => !result.errors.is_empty(),
+                Err(_) => true,
+            }
+        };
+        assert!(!fails(
+            "MATCH (p:Person) WHERE COUNT { (p)-[:KNOWS]->(f:Person) WHERE f.age > 30 } > 1 \
+             RETURN p.name, COUNT { (p)-[:KNOWS]->() } AS friends"
+        ));
+        assert!(fails(
+            "MATCH (p:Person) RETURN p.name, COUNT { (p)-[:KNOWS]->(f:Person) } AS n, f.name"
+        ));
+        assert!(fails(
+            "MATCH (p:Person) RETURN COUNT { (p)-[:KNOWS]->(f:Person) WHERE sum(f.age) > 1 }"
+        ));
+    }
 }
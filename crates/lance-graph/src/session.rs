@@ -0,0 +1,634 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Query sessions with temporary graphs
+//!
+//! A [`GraphSession`] runs successive Cypher queries against one graph
+//! configuration and set of in-memory datasets. The result of a query can be
+//! registered as a [`TempGraph`]: its batches become node labels and
+//! relationship types that later queries in the same session can MATCH,
+//! alongside the base graph. Temporary graphs live only in the session; nothing
+//! is written to storage.
+//!
+//! Further graphs can be registered by name. Patterns reference their labels
+//! and relationship types qualified by the graph name, e.g.
+//! `(a:Person)-[:WORKS_AT]->(c:crm:Company)` or `-[:crm:OWNS]->`, so one
+//! query can join nodes and relationships from several graphs. Qualified
+//! labels of a temporary graph resolve to its (unqualified) labels.
+//!
+//! The session's [`CompatibilityMode`] applies to every query it runs or
+//! describes, replacing the mode of the query itself, so a server can hold
+//! its clients to strict openCypher in one place.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::session::{GraphSession, TempGraph};
+//!
+//! let mut session = GraphSession::new(config, datasets);
+//! let hubs = session
+//!     .execute("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN b.id AS id, count(*) AS degree")
+//!     .await?;
+//! session.create_temp_graph("hubs", TempGraph::new().with_nodes("Hub", "id", hubs))?;
+//! let top = session
+//!     .execute("MATCH (h:Hub) WHERE h.degree > 10 RETURN h.id")
+//!     .await?;
+//!
+//! session.register_graph("crm", crm_config, crm_datasets)?;
+//! let accounts = session
+//!     .execute("MATCH (h:Hub)-[:crm:OWNS]->(a:crm:Account) RETURN h.id, a.name")
+//!     .await?;
+//! ```
+
+use crate::ast::CypherQuery as CypherAST;
+use crate::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use crate::script::CypherScript;
+use crate::semantic::CompatibilityMode;
+use arrow_array::RecordBatch;
+use arrow_schema::{Field, Schema, SchemaRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Node and relationship tables materialized from query results
+///
+/// Result columns are named like `p.name` unless aliased; since such names
+/// cannot be addressed as properties, dots are replaced by underscores
+/// (`p.name` becomes `p_name`) in column names and key fields.
+#[derive(Debug, Clone, Default)]
+pub struct TempGraph {
+    nodes: Vec<(NodeMapping, RecordBatch)>,
+    relationships: Vec<(RelationshipMapping, RecordBatch)>,
+}
+
+impl TempGraph {
+    /// Create an empty temporary graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose the rows of `batch` as nodes labelled `label`, keyed by `id_field`
+    pub fn with_nodes(mut self, label: &str, id_field: &str, batch: RecordBatch) -> Self {
+        self.nodes.push((
+            NodeMapping::new(label.to_string(), temp_column_name(id_field)),
+            temp_batch(batch),
+        ));
+        self
+    }
+
+    /// Expose the rows of `batch` as `rel_type` relationships between the
+    /// nodes keyed by `source_field` and `target_field`
+    pub fn with_relationships(
+        mut self,
+        rel_type: &str,
+        source_field: &str,
+        target_field: &str,
+        batch: RecordBatch,
+    ) -> Self {
+        self.relationships.push((
+            RelationshipMapping::new(
+                rel_type.to_string(),
+                temp_column_name(source_field),
+                temp_column_name(target_field),
+            ),
+            temp_batch(batch),
+        ));
+        self
+    }
+
+    /// Labels and relationship types this graph defines
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|(m, _)| m.label.as_str()).chain(
+            self.relationships
+                .iter()
+                .map(|(m, _)| m.relationship_type.as_str()),
+        )
+    }
+}
+
+/// Successive queries over one graph plus session-local temporary graphs
+#[derive(Debug, Clone)]
+pub struct GraphSession {
+    config: GraphConfig,
+    datasets: HashMap<String, RecordBatch>,
+    /// Keyed by lowercase graph name
+    temp_graphs: HashMap<String, TempGraph>,
+    /// Graphs referenced through qualified labels, keyed by lowercase name
+    graphs: HashMap<String, NamedGraph>,
+    /// Accepted Cypher syntax extensions of every query of the session
+    compatibility_mode: CompatibilityMode,
+}
+
+/// A graph registered with [`GraphSession::register_graph`]
+#[derive(Debug, Clone)]
+struct NamedGraph {
+    config: GraphConfig,
+    datasets: HashMap<String, RecordBatch>,
+}
+
+impl GraphSession {
+    /// Create a session over `datasets`, keyed by label / relationship type
+    pub fn new(config: GraphConfig, datasets: HashMap<String, RecordBatch>) -> Self {
+        Self {
+            config,
+            datasets,
+            temp_graphs: HashMap::new(),
+            graphs: HashMap::new(),
+            compatibility_mode: CompatibilityMode::default(),
+        }
+    }
+
+    /// Choose whether the session's queries accept Neo4j syntax extensions
+    /// (default) or only strict openCypher
+    pub fn with_compatibility_mode(mut self, mode: CompatibilityMode) -> Self {
+        self.compatibility_mode = mode;
+        self
+    }
+
+    /// Get the syntax compatibility mode of the session's queries
+    pub fn compatibility_mode(&self) -> CompatibilityMode {
+        self.compatibility_mode
+    }
+
+    /// Parse and execute `query` against the base graph and temporary graphs
+    pub async fn execute(&self, query: &str) -> Result<RecordBatch> {
+        self.execute_query(CypherQuery::new(query)?).await
+    }
+
+    /// Execute a prepared query (e.g. one with parameters) in this session
+    ///
+    /// The query's own configuration and compatibility mode are replaced by
+    /// the session's.
+    pub async fn execute_query(&self, mut query: CypherQuery) -> Result<RecordBatch> {
+        self.resolve_qualified_names(query.ast_mut())?;
+        let query = query
+            .with_config(self.effective_config()?)
+            .with_compatibility_mode(self.compatibility_mode);
+        query.execute(self.effective_datasets(), None).await
+    }
+
+    /// Execute the statements of `script`, separated by semicolons, one
+    /// after another in this session
+    ///
+    /// Returns one result per statement; see [`CypherScript`].
+    pub async fn execute_script(&self, script: &str) -> Result<Vec<RecordBatch>> {
+        let script = CypherScript::new(script)?;
+        let mut results = Vec::with_capacity(script.len());
+        for (index, statement) in script.statements().iter().enumerate() {
+            let result = self
+                .execute_query(statement.clone())
+                .await
+                .map_err(|e| script.error(index, e))?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Result schema of `query` in this session, without executing it
+    ///
+    /// See [`CypherQuery::describe`].
+    pub fn describe(&self, query: &str) -> Result<SchemaRef> {
+        let mut query = CypherQuery::new(query)?;
+        self.resolve_qualified_names(query.ast_mut())?;
+        let schemas = self
+            .effective_datasets()
+            .into_iter()
+            .map(|(name, batch)| (name, batch.schema()))
+            .collect();
+        query
+            .with_config(self.effective_config()?)
+            .with_compatibility_mode(self.compatibility_mode)
+            .describe(&schemas)
+    }
+
+    /// Register `graph` under `name` for the following queries of the session
+    ///
+    /// Fails if `name` is taken or the graph redefines a label or relationship
+    /// type of the base graph or of another temporary graph.
+    pub fn create_temp_graph(&mut self, name: &str, graph: TempGraph) -> Result<()> {
+        let key = name.to_lowercase();
+        self.require_unused_graph_name(name)?;
+        for defined in graph.names() {
+            if self.defines(defined) {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Temporary graph '{}' redefines '{}', which already exists in the session",
+                        name, defined
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+        self.temp_graphs.insert(key, graph);
+        Ok(())
+    }
+
+    /// Register the graph `config` over `datasets` under `name`
+    ///
+    /// Its labels and relationship types are only reachable qualified by
+    /// `name` (`(n:name:Label)`, `-[:name:TYPE]->`), so they may repeat those
+    /// of other graphs. Labels stored in a shared table (see
+    /// [`NodeMapping::with_label_column`]) are not supported.
+    pub fn register_graph(
+        &mut self,
+        name: &str,
+        config: GraphConfig,
+        datasets: HashMap<String, RecordBatch>,
+    ) -> Result<()> {
+        self.require_unused_graph_name(name)?;
+        if let Some(mapping) = config
+            .node_mappings
+            .values()
+            .find(|m| m.label_column.is_some())
+        {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!(
+                    "shared-table label '{}' in named graph '{}'",
+                    mapping.label, name
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        self.graphs
+            .insert(name.to_lowercase(), NamedGraph { config, datasets });
+        Ok(())
+    }
+
+    /// Forget the temporary graph `name`; returns whether it existed
+    pub fn drop_temp_graph(&mut self, name: &str) -> bool {
+        self.temp_graphs.remove(&name.to_lowercase()).is_some()
+    }
+
+    /// Names of the registered temporary graphs, sorted
+    pub fn temp_graph_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.temp_graphs.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Fail if a temporary or named graph is already called `name`
+    fn require_unused_graph_name(&self, name: &str) -> Result<()> {
+        let key = name.to_lowercase();
+        if self.temp_graphs.contains_key(&key) || self.graphs.contains_key(&key) {
+            return Err(GraphError::ConfigError {
+                message: format!("Graph '{}' already exists in the session", name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rewrite graph-qualified labels and relationship types in `ast`
+    ///
+    /// The parser reads `(n:g:Label)` as the labels `[g, Label]`; when `g`
+    /// names a graph of the session the pair is replaced by the name under
+    /// which the session exposes `Label` of `g`.
+    fn resolve_qualified_names(&self, ast: &mut CypherAST) -> Result<()> {
+        ast.try_for_each_pattern_names_mut(|names, is_label| {
+            self.resolve_qualified(names, is_label)
+        })
+    }
+
+    /// Replace a leading `[graph, name]` pair in `names` by the exposed name
+    fn resolve_qualified(&self, names: &mut Vec<String>, is_label: bool) -> Result<()> {
+        let [graph, name, ..] = names.as_slice() else {
+            return Ok(());
+        };
+        let key = graph.to_lowercase();
+        let resolved = if let Some(named) = self.graphs.get(&key) {
+            let defined = if is_label {
+                named.config.get_node_mapping(name).is_some()
+            } else {
+                named.config.get_relationship_mapping(name).is_some()
+            };
+            defined.then(|| qualified_name(graph, name))
+        } else if let Some(temp) = self.temp_graphs.get(&key) {
+            temp.names()
+                .find(|n| n.eq_ignore_ascii_case(name))
+                .map(str::to_string)
+        } else {
+            // Not a graph name: an ordinary multi-label pattern
+            return Ok(());
+        };
+
+        let resolved = resolved.ok_or_else(|| GraphError::ConfigError {
+            message: format!(
+                "Graph '{}' has no {} '{}'",
+                graph,
+                if is_label {
+                    "label"
+                } else {
+                    "relationship type"
+                },
+                name
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+        names.splice(0..2, [resolved]);
+        Ok(())
+    }
+
+    /// Whether `name` is a label, relationship type or dataset of the session
+    fn defines(&self, name: &str) -> bool {
+        self.config.get_node_mapping(name).is_some()
+            || self.config.get_relationship_mapping(name).is_some()
+            || self.datasets.keys().any(|k| k.eq_ignore_ascii_case(name))
+            || self
+                .temp_graphs
+                .values()
+                .flat_map(TempGraph::names)
+                .any(|n| n.eq_ignore_ascii_case(name))
+    }
+
+    /// Base configuration extended with the temporary graphs' mappings
+    fn effective_config(&self) -> Result<GraphConfig> {
+        let mut config = self.config.clone();
+        for graph in self.temp_graphs.values() {
+            for (mapping, _) in &graph.nodes {
+                config
+                    .node_mappings
+                    .insert(mapping.label.to_lowercase(), mapping.clone());
+            }
+            for (mapping, _) in &graph.relationships {
+                config
+                    .relationship_mappings
+                    .insert(mapping.relationship_type.to_lowercase(), mapping.clone());
+            }
+        }
+        for (graph_name, graph) in &self.graphs {
+            for mapping in graph.config.node_mappings.values() {
+                let mut mapping = mapping.clone();
+                mapping.label = qualified_name(graph_name, &mapping.label);
+                mapping.view_of = mapping
+                    .view_of
+                    .map(|base| qualified_name(graph_name, &base));
+                mapping.source_table = mapping
+                    .source_table
+                    .map(|table| qualified_name(graph_name, &table));
+                config
+                    .node_mappings
+                    .insert(mapping.label.to_lowercase(), mapping);
+            }
+            for mapping in graph.config.relationship_mappings.values() {
+                let mut mapping = mapping.clone();
+                mapping.relationship_type = qualified_name(graph_name, &mapping.relationship_type);
+                config
+                    .relationship_mappings
+                    .insert(mapping.relationship_type.to_lowercase(), mapping);
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Base datasets plus the temporary graphs' batches
+    fn effective_datasets(&self) -> HashMap<String, RecordBatch> {
+        let mut datasets = self.datasets.clone();
+        for graph in self.temp_graphs.values() {
+            for (mapping, batch) in &graph.nodes {
+                datasets.insert(mapping.label.clone(), batch.clone());
+            }
+            for (mapping, batch) in &graph.relationships {
+                datasets.insert(mapping.relationship_type.clone(), batch.clone());
+            }
+        }
+        for (graph_name, graph) in &self.graphs {
+            for (table, batch) in &graph.datasets {
+                datasets.insert(qualified_name(graph_name, table), batch.clone());
+            }
+        }
+        datasets
+    }
+}
+
+/// Name under which the session exposes `name` of the named graph `graph`
+fn qualified_name(graph: &str, name: &str) -> String {
+    format!("{}__{}", graph.to_lowercase(), name)
+}
+
+/// Column name under which a result column is exposed in a temporary graph
+fn temp_column_name(name: &str) -> String {
+    name.replace('.', "_")
+}
+
+/// `batch` with its columns renamed by [`temp_column_name`]
+fn temp_batch(batch: RecordBatch) -> RecordBatch {
+    let schema = batch.schema();
+    if !schema.fields().iter().any(|f| f.name().contains('.')) {
+        return batch;
+    }
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|f| f.as_ref().clone().with_name(temp_column_name(f.name())))
+        .collect();
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    batch
+        .with_schema(schema)
+        .expect("renaming columns keeps the batch valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Int64Array, StringArray};
+    use arrow_schema::DataType;
+
+    fn session() -> GraphSession {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        let people = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4]))],
+        )
+        .unwrap();
+        let knows = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("src_id", DataType::Int64, false),
+                Field::new("dst_id", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 1])),
+                Arc::new(Int64Array::from(vec![4, 4, 4, 2])),
+            ],
+        )
+        .unwrap();
+        let datasets =
+            HashMap::from([("Person".to_string(), people), ("KNOWS".to_string(), knows)]);
+        GraphSession::new(config, datasets)
+    }
+
+    #[tokio::test]
+    async fn test_temp_graph_is_matched_by_later_queries() {
+        let mut session = session();
+        let followed = session
+            .execute("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN b.id, count(*) AS followers")
+            .await
+            .unwrap();
+        session
+            .create_temp_graph(
+                "popular",
+                TempGraph::new().with_nodes("Popular", "b.id", followed),
+            )
+            .unwrap();
+
+        let result = session
+            .execute("MATCH (p:Popular) WHERE p.followers > 1 RETURN p.b_id")
+            .await
+            .unwrap();
+        let ids = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(ids.value(0), 4);
+
+        assert!(session.drop_temp_graph("POPULAR"));
+        assert!(session
+            .execute("MATCH (p:Popular) RETURN p.b_id")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_compatibility_mode_applies_to_every_query() {
+        let cypher = "MATCH (p:Person) WHERE exists(p.id) RETURN p.id";
+        assert_eq!(session().execute(cypher).await.unwrap().num_rows(), 4);
+
+        let strict = session().with_compatibility_mode(CompatibilityMode::OpenCypher);
+        let error = strict.execute(cypher).await.unwrap_err();
+        assert!(
+            error.to_string().contains("not part of openCypher"),
+            "{}",
+            error
+        );
+        // A query's own mode does not override the session's
+        let query = CypherQuery::new(cypher)
+            .unwrap()
+            .with_compatibility_mode(CompatibilityMode::Neo4j);
+        assert!(strict.execute_query(query).await.is_err());
+        assert!(strict.describe(cypher).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_script_returns_one_result_per_statement() {
+        let session = session();
+        let results = session
+            .execute_script(
+                "// Everyone, then who follows person 4\n\
+                 MATCH (p:Person) RETURN count(*) AS people;\n\
+                 MATCH (a:Person)-[:KNOWS]->(b:Person) WHERE b.id = 4 /* in */ RETURN a.id;",
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].num_rows(), 1);
+        assert_eq!(results[1].num_rows(), 3);
+
+        let err = session
+            .execute_script("MATCH (p:Person) RETURN p.id; MATCH (x:Missing) RETURN x.id")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Statement 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_temp_graph_cannot_shadow_base_graph() {
+        let mut session = session();
+        let batch = session.datasets["Person"].clone();
+        let err = session
+            .create_temp_graph("g", TempGraph::new().with_nodes("person", "id", batch))
+            .unwrap_err();
+        assert!(err.to_string().contains("redefines"), "{}", err);
+        assert!(session.temp_graph_names().is_empty());
+    }
+
+    fn crm_graph() -> (GraphConfig, HashMap<String, RecordBatch>) {
+        let config = GraphConfig::builder()
+            .with_node_label("Company", "id")
+            // Same label as the base graph, different dataset
+            .with_node_label("Person", "id")
+            .with_relationship("WORKS_AT", "person_id", "company_id")
+            .build()
+            .unwrap();
+        let companies = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20])),
+                Arc::new(StringArray::from(vec!["Acme", "Globex"])),
+            ],
+        )
+        .unwrap();
+        let contacts = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![100]))],
+        )
+        .unwrap();
+        let works_at = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("person_id", DataType::Int64, false),
+                Field::new("company_id", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 4])),
+                Arc::new(Int64Array::from(vec![10, 20])),
+            ],
+        )
+        .unwrap();
+        let datasets = HashMap::from([
+            ("Company".to_string(), companies),
+            ("Person".to_string(), contacts),
+            ("WORKS_AT".to_string(), works_at),
+        ]);
+        (config, datasets)
+    }
+
+    #[tokio::test]
+    async fn test_qualified_labels_join_across_graphs() {
+        let mut session = session();
+        let (config, datasets) = crm_graph();
+        session.register_graph("crm", config, datasets).unwrap();
+
+        let result = session
+            .execute(
+                "MATCH (p:Person)-[:crm:WORKS_AT]->(c:crm:Company) \
+                 RETURN p.id, c.name ORDER BY p.id",
+            )
+            .await
+            .unwrap();
+        let names = result
+            .column_by_name("c.name")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(names.value(0), "Acme");
+        assert_eq!(names.value(1), "Globex");
+
+        // Unqualified labels keep resolving to the base graph
+        let base = session
+            .execute("MATCH (p:Person) RETURN p.id")
+            .await
+            .unwrap();
+        assert_eq!(base.num_rows(), 4);
+        let crm = session
+            .execute("MATCH (p:crm:Person) RETURN p.id")
+            .await
+            .unwrap();
+        assert_eq!(crm.num_rows(), 1);
+
+        let err = session
+            .execute("MATCH (x:crm:Invoice) RETURN x.id")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no label 'Invoice'"), "{}", err);
+    }
+}
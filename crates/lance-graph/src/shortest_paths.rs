@@ -0,0 +1,432 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Hop bounds for shortest path patterns
+//!
+//! `MATCH p = shortestPath((a:Station {name: 'A'})-[:ROUTE*]-(b:Station {name: 'B'}))`
+//! is planned like any variable-length pattern, one join chain per hop count
+//! up to the maximum, keeping the paths of the fewest hops between each pair
+//! of end nodes. Unrolled to the default maximum, most of those chains only
+//! produce paths longer than the shortest ones.
+//!
+//! Before planning, a breadth-first search from every start node finds the
+//! length of the shortest path to each end node it reaches. A search stops as
+//! soon as it reached every end node, and the pattern is then capped at the
+//! longest of those lengths, so no join chain beyond it is built.
+//!
+//! The search reads start nodes, end nodes and relationships through the
+//! planner, with the labels and inline properties of the pattern, so it sees
+//! the same graph as the unrolled plan. Patterns it cannot mirror keep their
+//! declared range: end nodes without a label of their own or keyed by
+//! several columns, and paths constrained by `all(...)` predicates.
+
+use crate::ast::{
+    BooleanExpression, CypherQuery as CypherAST, GraphPattern, LengthRange, MatchClause,
+    NodePattern, PathPattern, PathSegment, PropertyRef, ReadingClause, RelationshipPattern,
+    ReturnClause, ReturnItem, ValueExpression, WhereClause,
+};
+use crate::config::GraphConfig;
+use crate::error::Result;
+use crate::query::CypherQuery;
+use arrow::array::{Array, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow_array::RecordBatch;
+use datafusion::execution::context::SessionContext;
+use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Variables of the nodes the probe queries return
+const FROM: &str = "probe_from";
+const TO: &str = "probe_to";
+
+/// The AST of `query` with the maximum length of its shortest path patterns
+/// lowered to the longest shortest path found, or `None` if no pattern
+/// could be bounded
+pub(crate) async fn bound_path_lengths(
+    query: &CypherQuery,
+    catalog: &Arc<dyn GraphSourceCatalog>,
+    ctx: &SessionContext,
+) -> Result<Option<CypherAST>> {
+    let ast = query.ast();
+    let config = query.require_config()?;
+    let mut bounded = ast.clone();
+    let mut changed = false;
+
+    let clauses = bounded
+        .reading_clauses
+        .iter_mut()
+        .chain(bounded.post_with_reading_clauses.iter_mut());
+    for clause in clauses {
+        let ReadingClause::Match(match_clause) = clause else {
+            continue;
+        };
+        let where_clauses = [
+            &ast.where_clause,
+            &ast.post_with_where_clause,
+            &match_clause.where_clause,
+        ];
+        let mut bounds = Vec::new();
+        for (index, pattern) in match_clause.patterns.iter().enumerate() {
+            let GraphPattern::Path(path) = pattern else {
+                continue;
+            };
+            if path.shortest.is_none() || constrains_path(path, &where_clauses) {
+                continue;
+            }
+            let Some(probe) = Probe::new(path, config) else {
+                continue;
+            };
+            let max_hops = path.segments[0]
+                .relationship
+                .length
+                .as_ref()
+                .and_then(|length| length.max)
+                .unwrap_or(crate::MAX_VARIABLE_LENGTH_HOPS);
+            let longest = probe
+                .longest_shortest_path(query, ast, catalog, ctx, max_hops)
+                .await?;
+            if longest < max_hops {
+                bounds.push((index, longest));
+            }
+        }
+
+        for (index, longest) in bounds {
+            let GraphPattern::Path(path) = &mut match_clause.patterns[index] else {
+                continue;
+            };
+            let length = path.segments[0]
+                .relationship
+                .length
+                .get_or_insert(LengthRange {
+                    min: None,
+                    max: None,
+                });
+            // With no end node reachable, the single-hop plan matches nothing
+            length.max = Some(longest.max(length.min.unwrap_or(1)).max(1));
+            changed = true;
+        }
+    }
+
+    Ok(changed.then_some(bounded))
+}
+
+/// Whether an `all(...)` predicate of the WHERE clauses ranges over `path`
+fn constrains_path(path: &PathPattern, where_clauses: &[&Option<WhereClause>]) -> bool {
+    let Some(variable) = &path.variable else {
+        return false;
+    };
+    where_clauses
+        .iter()
+        .filter_map(|clause| clause.as_ref())
+        .any(|clause| ranges_over(&clause.expression, variable))
+}
+
+fn ranges_over(expression: &BooleanExpression, variable: &str) -> bool {
+    match expression {
+        BooleanExpression::AllInPath { path, .. } => path.eq_ignore_ascii_case(variable),
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            ranges_over(left, variable) || ranges_over(right, variable)
+        }
+        BooleanExpression::Not(inner) => ranges_over(inner, variable),
+        _ => false,
+    }
+}
+
+/// The node and relationship sets the unrolled plan of a shortest path
+/// pattern joins
+///
+/// Intermediate nodes carry the label of the start node; only the last hop
+/// reaches the end node label and its inline properties.
+struct Probe {
+    start: NodePattern,
+    end: NodePattern,
+    /// A node of the start label, as intermediate nodes are scanned
+    step: NodePattern,
+    relationship: RelationshipPattern,
+    start_key: String,
+    end_key: String,
+}
+
+impl Probe {
+    fn new(path: &PathPattern, config: &GraphConfig) -> Option<Self> {
+        let [segment] = path.segments.as_slice() else {
+            return None;
+        };
+        let start_label = path.start_node.labels.first()?;
+        let end_label = segment.end_node.labels.first()?;
+        let single_key = |label: &str| match config.get_node_mapping(label)?.key_columns()[..] {
+            [key] => Some(key.to_string()),
+            _ => None,
+        };
+        let start_key = single_key(start_label)?;
+        let end_key = single_key(end_label)?;
+
+        let node = |variable: &str, base: &NodePattern| NodePattern {
+            variable: Some(variable.to_string()),
+            labels: base.labels.clone(),
+            properties: base.properties.clone(),
+        };
+        let mut relationship = segment.relationship.clone();
+        relationship.variable = None;
+        relationship.length = None;
+        Some(Self {
+            start: node(FROM, &path.start_node),
+            end: node(TO, &segment.end_node),
+            step: NodePattern {
+                variable: Some(TO.to_string()),
+                labels: path.start_node.labels.clone(),
+                properties: HashMap::new(),
+            },
+            relationship,
+            start_key,
+            end_key,
+        })
+    }
+
+    /// Length of the longest of the shortest paths between start and end
+    /// nodes, searching at most `max_hops` hops
+    async fn longest_shortest_path(
+        &self,
+        query: &CypherQuery,
+        ast: &CypherAST,
+        catalog: &Arc<dyn GraphSourceCatalog>,
+        ctx: &SessionContext,
+        max_hops: u32,
+    ) -> Result<u32> {
+        let mut intermediate = self.start.clone();
+        intermediate.properties.clear();
+
+        let starts = keys(
+            &query
+                .collect_ast(
+                    probe_ast(
+                        ast,
+                        GraphPattern::Node(self.start.clone()),
+                        &[(FROM, self.start_key.as_str())],
+                    ),
+                    catalog.clone(),
+                    ctx,
+                )
+                .await?,
+        )?;
+        let ends = keys(
+            &query
+                .collect_ast(
+                    probe_ast(
+                        ast,
+                        GraphPattern::Node(self.end.clone()),
+                        &[(TO, self.end_key.as_str())],
+                    ),
+                    catalog.clone(),
+                    ctx,
+                )
+                .await?,
+        )?;
+        let steps = adjacency(
+            &query
+                .collect_ast(
+                    probe_ast(
+                        ast,
+                        self.edge_pattern(intermediate.clone(), self.step.clone()),
+                        &[
+                            (FROM, self.start_key.as_str()),
+                            (TO, self.start_key.as_str()),
+                        ],
+                    ),
+                    catalog.clone(),
+                    ctx,
+                )
+                .await?,
+        )?;
+        let last_steps = adjacency(
+            &query
+                .collect_ast(
+                    probe_ast(
+                        ast,
+                        self.edge_pattern(intermediate, self.end.clone()),
+                        &[(FROM, self.start_key.as_str()), (TO, self.end_key.as_str())],
+                    ),
+                    catalog.clone(),
+                    ctx,
+                )
+                .await?,
+        )?;
+
+        let ends: HashSet<String> = ends.into_iter().collect();
+        Ok(longest_shortest_path(&starts, &ends, &steps, &last_steps, max_hops).unwrap_or(0))
+    }
+
+    fn edge_pattern(&self, from: NodePattern, to: NodePattern) -> GraphPattern {
+        GraphPattern::Path(PathPattern {
+            variable: None,
+            start_node: from,
+            segments: vec![PathSegment {
+                relationship: self.relationship.clone(),
+                end_node: to,
+            }],
+            shortest: None,
+        })
+    }
+}
+
+/// `MATCH <pattern> RETURN DISTINCT <variable.property>...` under the
+/// options of `ast`
+fn probe_ast(ast: &CypherAST, pattern: GraphPattern, returns: &[(&str, &str)]) -> CypherAST {
+    let mut probe = ast.clone();
+    probe.reading_clauses = vec![ReadingClause::Match(MatchClause {
+        patterns: vec![pattern],
+        optional: false,
+        where_clause: None,
+    })];
+    probe.where_clause = None;
+    probe.with_clause = None;
+    probe.post_with_reading_clauses = Vec::new();
+    probe.post_with_where_clause = None;
+    probe.return_clause = ReturnClause {
+        distinct: true,
+        distinct_on: Vec::new(),
+        items: returns
+            .iter()
+            .map(|(variable, property)| ReturnItem {
+                expression: ValueExpression::Property(PropertyRef {
+                    variable: variable.to_string(),
+                    property: property.to_string(),
+                }),
+                alias: None,
+            })
+            .collect(),
+    };
+    probe.order_by = None;
+    probe.limit = None;
+    probe.skip = None;
+    probe.sample = None;
+    probe.procedure = None;
+    probe
+}
+
+/// Non-null values of column `index` as strings
+fn column_strings(batches: &[RecordBatch], index: usize) -> Result<Vec<Option<String>>> {
+    let mut values = Vec::new();
+    for batch in batches {
+        let column = cast(batch.column(index), &DataType::Utf8)?;
+        let strings = column
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .expect("cast to Utf8 yields a StringArray");
+        values.extend(
+            (0..strings.len()).map(|i| strings.is_valid(i).then(|| strings.value(i).to_string())),
+        );
+    }
+    Ok(values)
+}
+
+fn keys(batches: &[RecordBatch]) -> Result<Vec<String>> {
+    Ok(column_strings(batches, 0)?.into_iter().flatten().collect())
+}
+
+fn adjacency(batches: &[RecordBatch]) -> Result<HashMap<String, Vec<String>>> {
+    let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, to) in column_strings(batches, 0)?
+        .into_iter()
+        .zip(column_strings(batches, 1)?)
+    {
+        if let (Some(from), Some(to)) = (from, to) {
+            adjacency.entry(from).or_default().push(to);
+        }
+    }
+    Ok(adjacency)
+}
+
+/// Longest of the shortest path lengths from each of `starts` to the `ends`
+/// it reaches within `max_hops`, or `None` if it reaches none
+///
+/// Paths take any number of `steps` followed by one of `last_steps`. The
+/// search from a start node stops once it reached every end node.
+fn longest_shortest_path(
+    starts: &[String],
+    ends: &HashSet<String>,
+    steps: &HashMap<String, Vec<String>>,
+    last_steps: &HashMap<String, Vec<String>>,
+    max_hops: u32,
+) -> Option<u32> {
+    let mut longest = None;
+    for start in starts {
+        let mut remaining: HashSet<&String> = ends.iter().collect();
+        let mut visited: HashSet<&String> = HashSet::from([start]);
+        let mut frontier = vec![start];
+        let mut hops = 1;
+        while !frontier.is_empty() && !remaining.is_empty() && hops <= max_hops {
+            // End nodes one last step away from the frontier are `hops` hops away
+            for node in &frontier {
+                for end in last_steps.get(*node).into_iter().flatten() {
+                    if remaining.remove(end) {
+                        longest = longest.max(Some(hops));
+                    }
+                }
+            }
+            let mut next = Vec::new();
+            for node in &frontier {
+                for neighbor in steps.get(*node).into_iter().flatten() {
+                    if visited.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            frontier = next;
+            hops += 1;
+        }
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(pairs: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+        for (from, to) in pairs {
+            adjacency
+                .entry(from.to_string())
+                .or_default()
+                .push(to.to_string());
+        }
+        adjacency
+    }
+
+    #[test]
+    fn test_search_stops_at_longest_shortest_path() {
+        // a -> b -> c -> d, plus the shortcut a -> c
+        let graph = edges(&[("a", "b"), ("b", "c"), ("c", "d"), ("a", "c")]);
+        let starts = vec!["a".to_string()];
+        let ends: HashSet<String> = ["c", "d"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(
+            longest_shortest_path(&starts, &ends, &graph, &graph, 20),
+            Some(2)
+        );
+        assert_eq!(
+            longest_shortest_path(&starts, &ends, &graph, &graph, 1),
+            Some(1)
+        );
+        let unreachable: HashSet<String> = ["z".to_string()].into();
+        assert_eq!(
+            longest_shortest_path(&starts, &unreachable, &graph, &graph, 20),
+            None
+        );
+    }
+
+    #[test]
+    fn test_longest_over_all_start_nodes() {
+        // b only reaches d through c and e
+        let graph = edges(&[("a", "d"), ("b", "c"), ("c", "e"), ("e", "d")]);
+        let starts = vec!["a".to_string(), "b".to_string()];
+        let ends: HashSet<String> = ["d".to_string()].into();
+        assert_eq!(
+            longest_shortest_path(&starts, &ends, &graph, &graph, 20),
+            Some(3)
+        );
+    }
+}
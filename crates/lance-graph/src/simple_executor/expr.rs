@@ -215,6 +215,40 @@ pub(crate) fn to_df_value_expr_simple(
         VE::VectorSimilarity { .. } => lit(1.0f32),
         VE::Parameter(_) => lit(0),
         VE::VectorLiteral(_) => lit(0.0f32),
+        VE::Case { branches, default } => {
+            // Conditions the simple translator cannot express make the whole CASE null
+            let when_then: Option<Vec<_>> = branches
+                .iter()
+                .map(|branch| {
+                    Some((
+                        Box::new(to_df_boolean_expr_simple(&branch.condition)?),
+                        Box::new(to_df_value_expr_simple(&branch.value)),
+                    ))
+                })
+                .collect();
+            match when_then {
+                Some(when_then) => Expr::Case(datafusion::logical_expr::expr::Case::new(
+                    None,
+                    when_then,
+                    default
+                        .as_deref()
+                        .map(|d| Box::new(to_df_value_expr_simple(d))),
+                )),
+                None => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+            }
+        }
+        // List and pattern comprehensions, reductions and COUNT subqueries
+        // are not supported in simple executor
+        VE::ListComprehension { .. }
+        | VE::Reduce { .. }
+        | VE::PatternComprehension { .. }
+        | VE::CountSubquery { .. } => Expr::Literal(datafusion::scalar::ScalarValue::Null, None),
+        VE::MapProjection { items, .. } => datafusion::functions::core::expr_fn::named_struct(
+            items
+                .iter()
+                .flat_map(|item| [lit(item.key.clone()), to_df_value_expr_simple(&item.value)])
+                .collect(),
+        ),
     }
 }
 
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
-use crate::case_insensitive::qualify_column;
+use crate::case_insensitive::{qualify_column, qualify_key_pairs};
 use crate::error::{GraphError, Result};
 use datafusion::logical_expr::JoinType;
 
@@ -210,25 +210,21 @@ impl<'a> PathExecutor<'a> {
                 .get(&current_node_alias.to_lowercase())
                 .unwrap();
             let rel_map = self.rel_maps.get(&s.rel_alias.to_lowercase()).unwrap();
-            let (left_key, right_key) = match s.dir {
+            let rel_keys = match s.dir {
                 crate::ast::RelationshipDirection::Outgoing
-                | crate::ast::RelationshipDirection::Undirected => (
-                    qualify_column(current_node_alias, &node_map.id_field),
-                    qualify_column(&s.rel_alias, &rel_map.source_id_field),
-                ),
-                crate::ast::RelationshipDirection::Incoming => (
-                    qualify_column(current_node_alias, &node_map.id_field),
-                    qualify_column(&s.rel_alias, &rel_map.target_id_field),
-                ),
+                | crate::ast::RelationshipDirection::Undirected => rel_map.source_key_columns(),
+                crate::ast::RelationshipDirection::Incoming => rel_map.target_key_columns(),
             };
+            let (left_keys, right_keys) = qualify_key_pairs(
+                current_node_alias,
+                &node_map.key_columns(),
+                &s.rel_alias,
+                &rel_keys,
+            )?;
+            let left_refs: Vec<&str> = left_keys.iter().map(String::as_str).collect();
+            let right_refs: Vec<&str> = right_keys.iter().map(String::as_str).collect();
             df = df
-                .join(
-                    rel_df,
-                    JoinType::Inner,
-                    &[left_key.as_str()],
-                    &[right_key.as_str()],
-                    None,
-                )
+                .join(rel_df, JoinType::Inner, &left_refs, &right_refs, None)
                 .map_err(|e| GraphError::PlanError {
                     message: format!("Join failed (node->rel): {}", e),
                     location: snafu::Location::new(file!(), line!(), column!()),
@@ -236,25 +232,21 @@ impl<'a> PathExecutor<'a> {
 
             let end_df = self.open_aliased(s.end_label, &s.end_alias).await?;
             let end_node_map = self.node_maps.get(&s.end_alias.to_lowercase()).unwrap();
-            let (left_key2, right_key2) = match s.dir {
+            let rel_keys2 = match s.dir {
                 crate::ast::RelationshipDirection::Outgoing
-                | crate::ast::RelationshipDirection::Undirected => (
-                    qualify_column(&s.rel_alias, &rel_map.target_id_field),
-                    qualify_column(&s.end_alias, &end_node_map.id_field),
-                ),
-                crate::ast::RelationshipDirection::Incoming => (
-                    qualify_column(&s.rel_alias, &rel_map.source_id_field),
-                    qualify_column(&s.end_alias, &end_node_map.id_field),
-                ),
+                | crate::ast::RelationshipDirection::Undirected => rel_map.target_key_columns(),
+                crate::ast::RelationshipDirection::Incoming => rel_map.source_key_columns(),
             };
+            let (left_keys2, right_keys2) = qualify_key_pairs(
+                &s.rel_alias,
+                &rel_keys2,
+                &s.end_alias,
+                &end_node_map.key_columns(),
+            )?;
+            let left_refs2: Vec<&str> = left_keys2.iter().map(String::as_str).collect();
+            let right_refs2: Vec<&str> = right_keys2.iter().map(String::as_str).collect();
             df = df
-                .join(
-                    end_df,
-                    JoinType::Inner,
-                    &[left_key2.as_str()],
-                    &[right_key2.as_str()],
-                    None,
-                )
+                .join(end_df, JoinType::Inner, &left_refs2, &right_refs2, None)
                 .map_err(|e| GraphError::PlanError {
                     message: format!("Join failed (rel->node): {}", e),
                     location: snafu::Location::new(file!(), line!(), column!()),
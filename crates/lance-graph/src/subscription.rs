@@ -0,0 +1,395 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Standing queries over growing datasets
+//!
+//! Monitoring applications watch a query for new results as data arrives
+//! instead of re-running it and diffing. A [`SubscriptionManager`] keeps
+//! registered queries together with the dataset versions their results
+//! reflect; each [`SubscriptionManager::poll`] checks the datasets of the
+//! namespace for new versions and notifies the callback of every affected
+//! subscription.
+//!
+//! When datasets only gained fragments and the query is made of MATCH
+//! patterns, WHERE filters and a plain RETURN projection (no aggregation,
+//! `DISTINCT`, `ORDER BY`, `SKIP`, `LIMIT`, `SAMPLE`, `OPTIONAL MATCH`,
+//! `UNWIND`, `WITH` or variable-length relationships), the query is
+//! re-evaluated over the appended rows only and the callback receives the
+//! result rows they add as [`SubscriptionEvent::Appended`]. A relationship
+//! joined to appended nodes is read in full, but unchanged rows are never
+//! matched against each other again.
+//!
+//! Any other change — deleted or updated rows, a schema change, a dataset
+//! read by two pattern elements of the query growing, or a query outside
+//! that subset — re-runs the query in full and delivers the complete result
+//! as [`SubscriptionEvent::Reset`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::subscription::{SubscriptionEvent, SubscriptionManager};
+//!
+//! let subscriptions = SubscriptionManager::new(namespace);
+//! let query = CypherQuery::new("MATCH (t:Transfer) WHERE t.amount > 10000 RETURN t.id")?
+//!     .with_config(config);
+//! let (id, current) = subscriptions
+//!     .register(query, |event: &SubscriptionEvent| match event {
+//!         SubscriptionEvent::Appended { rows } => alert(rows),
+//!         SubscriptionEvent::Reset { result } => reload(result),
+//!     })
+//!     .await?;
+//!
+//! // After writers appended to the datasets, e.g. on a timer
+//! subscriptions.poll().await?;
+//! ```
+
+use crate::ast::{GraphPattern, ReadingClause};
+use crate::batch::table_reads;
+use crate::datafusion_planner::expression::contains_aggregate;
+use crate::error::Result;
+use crate::query::CypherQuery;
+use arrow::compute::concat_batches;
+use arrow_array::RecordBatch;
+use datafusion::datasource::{MemTable, TableProvider};
+use futures::TryStreamExt;
+use lance::datafusion::LanceTableProvider;
+use lance::dataset::Dataset;
+use lance_graph_catalog::DirNamespace;
+use lance_namespace::LanceNamespace;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Identifier of a registered subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(pub u64);
+
+impl fmt::Display for SubscriptionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "subscription_{}", self.0)
+    }
+}
+
+/// Change to the result of a subscribed query
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionEvent {
+    /// Result rows added by rows appended to the datasets
+    Appended { rows: RecordBatch },
+    /// The query was re-run in full; this is its complete new result
+    Reset { result: RecordBatch },
+}
+
+type Callback = Box<dyn Fn(&SubscriptionEvent) + Send + Sync>;
+
+struct Subscription {
+    query: CypherQuery,
+    callback: Callback,
+    /// Datasets at the versions the last delivered result reflects
+    datasets: HashMap<String, Arc<Dataset>>,
+}
+
+/// Registry of standing queries over the datasets of a namespace
+pub struct SubscriptionManager {
+    namespace: Arc<dyn LanceNamespace + Send + Sync>,
+    next_id: AtomicU64,
+    subscriptions: Mutex<HashMap<SubscriptionId, Subscription>>,
+}
+
+impl SubscriptionManager {
+    /// Manager watching the datasets of `namespace`
+    pub fn new(namespace: DirNamespace) -> Self {
+        Self {
+            namespace: Arc::new(namespace),
+            next_id: AtomicU64::new(1),
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register `query`, calling `callback` whenever a later
+    /// [`poll`](Self::poll) finds its result changed
+    ///
+    /// Returns the id of the subscription and the current result.
+    pub async fn register(
+        &self,
+        query: CypherQuery,
+        callback: impl Fn(&SubscriptionEvent) + Send + Sync + 'static,
+    ) -> Result<(SubscriptionId, RecordBatch)> {
+        let datasets = self.open(&query).await?;
+        let result = execute(&query, &providers(&datasets, &HashMap::new())).await?;
+
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subscriptions.lock().await.insert(
+            id,
+            Subscription {
+                query,
+                callback: Box::new(callback),
+                datasets,
+            },
+        );
+        Ok((id, result))
+    }
+
+    /// Stop notifying subscription `id`; returns whether it was registered
+    pub async fn unregister(&self, id: SubscriptionId) -> bool {
+        self.subscriptions.lock().await.remove(&id).is_some()
+    }
+
+    /// Ids of the registered subscriptions, oldest first
+    pub async fn subscriptions(&self) -> Vec<SubscriptionId> {
+        let mut ids: Vec<_> = self.subscriptions.lock().await.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Re-evaluate every subscription whose datasets changed since it was
+    /// last evaluated, and notify its callback
+    ///
+    /// Returns the number of notifications delivered. Appends that add no
+    /// result rows are not notified. Stops at the first subscription failing
+    /// to re-evaluate; it is retried, from the same versions, on the next
+    /// poll.
+    pub async fn poll(&self) -> Result<usize> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        let mut ids: Vec<_> = subscriptions.keys().copied().collect();
+        ids.sort();
+
+        let mut delivered = 0;
+        for id in ids {
+            let subscription = subscriptions.get_mut(&id).expect("id was just listed");
+            let latest = self.open(&subscription.query).await?;
+            let mut changed: Vec<&String> = latest
+                .iter()
+                .filter(|(name, dataset)| {
+                    subscription.datasets[*name].version().version != dataset.version().version
+                })
+                .map(|(name, _)| name)
+                .collect();
+            if changed.is_empty() {
+                continue;
+            }
+            changed.sort();
+
+            let appended = changed
+                .iter()
+                .all(|name| is_append(&subscription.datasets[*name], &latest[*name]));
+            let event = if appended && is_incremental(&subscription.query, &changed)? {
+                let rows = appended_rows(
+                    &subscription.query,
+                    &subscription.datasets,
+                    &latest,
+                    &changed,
+                )
+                .await?;
+                (rows.num_rows() > 0).then_some(SubscriptionEvent::Appended { rows })
+            } else {
+                let result =
+                    execute(&subscription.query, &providers(&latest, &HashMap::new())).await?;
+                Some(SubscriptionEvent::Reset { result })
+            };
+
+            subscription.datasets = latest;
+            if let Some(event) = event {
+                (subscription.callback)(&event);
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Open the latest version of every dataset `query` reads
+    async fn open(&self, query: &CypherQuery) -> Result<HashMap<String, Arc<Dataset>>> {
+        query
+            .open_namespace_datasets(self.namespace.clone(), query.namespace_tables()?)
+            .await
+    }
+}
+
+/// Whether `new` is `old` with fragments appended and nothing else changed
+fn is_append(old: &Dataset, new: &Dataset) -> bool {
+    old.schema() == new.schema()
+        && old
+            .fragments()
+            .iter()
+            .all(|fragment| new.fragments().contains(fragment))
+}
+
+/// Whether the result rows `query` gains from appends to the `changed`
+/// datasets can be computed from the appended rows alone
+fn is_incremental(query: &CypherQuery, changed: &[&String]) -> Result<bool> {
+    let ast = query.ast();
+    let projection_only = ast.with_clause.is_none()
+        && ast.post_with_reading_clauses.is_empty()
+        && ast.post_with_where_clause.is_none()
+        && ast.order_by.is_none()
+        && ast.limit.is_none()
+        && ast.skip.is_none()
+        && ast.sample.is_none()
+        && ast.procedure.is_none()
+        && !ast.return_clause.distinct
+        && ast.return_clause.distinct_on.is_empty()
+        && !ast
+            .return_clause
+            .items
+            .iter()
+            .any(|item| contains_aggregate(&item.expression));
+    let plain_matches = ast.reading_clauses.iter().all(|clause| match clause {
+        ReadingClause::Match(match_clause) => {
+            !match_clause.optional
+                && match_clause.patterns.iter().all(|pattern| match pattern {
+                    GraphPattern::Node(_) => true,
+                    GraphPattern::Path(path) => path.segments.iter().all(|segment| {
+                        segment.relationship.length.is_none()
+                            && !segment.relationship.types.is_empty()
+                    }),
+                })
+        }
+        ReadingClause::Unwind(_) | ReadingClause::TableFunction(_) => false,
+    });
+    if !projection_only || !plain_matches {
+        return Ok(false);
+    }
+
+    // The delta of a join is only the join of the deltas when every input
+    // is a distinct dataset
+    let reads = table_reads(query)?;
+    Ok(changed
+        .iter()
+        .all(|name| reads.get(*name).copied().unwrap_or(0) <= 1))
+}
+
+/// Result rows `query` gains from the fragments appended between the `old`
+/// and `new` versions of the `changed` datasets
+///
+/// With the changed datasets ordered, the delta is the union over each of
+/// them of the query evaluated with that dataset restricted to its appended
+/// rows, the datasets before it at their new version and the ones after it
+/// at their old version.
+async fn appended_rows(
+    query: &CypherQuery,
+    old: &HashMap<String, Arc<Dataset>>,
+    new: &HashMap<String, Arc<Dataset>>,
+    changed: &[&String],
+) -> Result<RecordBatch> {
+    let mut batches = Vec::with_capacity(changed.len());
+    for (position, name) in changed.iter().enumerate() {
+        let mut overrides: HashMap<String, Arc<dyn TableProvider>> = HashMap::new();
+        for later in &changed[position + 1..] {
+            overrides.insert((*later).clone(), lance_provider(old[*later].clone()));
+        }
+        overrides.insert(
+            (*name).clone(),
+            appended_fragments(&old[*name], &new[*name]).await?,
+        );
+        batches.push(execute(query, &providers(new, &overrides)).await?);
+    }
+    let schema = batches[0].schema();
+    Ok(concat_batches(&schema, &batches)?)
+}
+
+/// In-memory table of the rows of the fragments `new` has and `old` lacks,
+/// with the columns of the Lance provider of `new`
+async fn appended_fragments(old: &Dataset, new: &Dataset) -> Result<Arc<dyn TableProvider>> {
+    let fragments: Vec<_> = new
+        .fragments()
+        .iter()
+        .filter(|fragment| !old.fragments().contains(fragment))
+        .cloned()
+        .collect();
+    let schema = LanceTableProvider::new(Arc::new(new.clone()), true, true).schema();
+
+    let mut scanner = new.scan();
+    scanner
+        .with_fragments(fragments)
+        .with_row_id()
+        .with_row_address();
+    let batches: Vec<RecordBatch> = scanner.try_into_stream().await?.try_collect().await?;
+    let batches = batches
+        .iter()
+        .map(|batch| {
+            let indices = schema
+                .fields()
+                .iter()
+                .map(|field| batch.schema().index_of(field.name()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(batch.project(&indices)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(MemTable::try_new(schema, vec![batches])?))
+}
+
+fn lance_provider(dataset: Arc<Dataset>) -> Arc<dyn TableProvider> {
+    Arc::new(LanceTableProvider::new(dataset, true, true))
+}
+
+/// Providers over `datasets`, with `overrides` taking precedence
+fn providers(
+    datasets: &HashMap<String, Arc<Dataset>>,
+    overrides: &HashMap<String, Arc<dyn TableProvider>>,
+) -> HashMap<String, Arc<dyn TableProvider>> {
+    datasets
+        .iter()
+        .map(|(name, dataset)| {
+            let provider = overrides
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| lance_provider(dataset.clone()));
+            (name.clone(), provider)
+        })
+        .collect()
+}
+
+async fn execute(
+    query: &CypherQuery,
+    providers: &HashMap<String, Arc<dyn TableProvider>>,
+) -> Result<RecordBatch> {
+    let (catalog, ctx) = query.catalog_and_context_from_providers(providers)?;
+    query
+        .execute_with_catalog_and_context(Arc::new(catalog), ctx)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GraphConfig;
+
+    fn query(cypher: &str) -> CypherQuery {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_node_label("Company", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        CypherQuery::new(cypher).unwrap().with_config(config)
+    }
+
+    #[test]
+    fn test_incremental_queries() {
+        let person = "person".to_string();
+        let knows = "knows".to_string();
+        let incremental =
+            |cypher: &str, changed: &[&String]| is_incremental(&query(cypher), changed).unwrap();
+
+        assert!(incremental(
+            "MATCH (p:Person) WHERE p.age > 30 RETURN p.name, p.age * 2 AS double",
+            &[&person]
+        ));
+        // Person is read twice, KNOWS once
+        let join = "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name";
+        assert!(incremental(join, &[&knows]));
+        assert!(!incremental(join, &[&person]));
+
+        for cypher in [
+            "MATCH (p:Person) RETURN count(p)",
+            "MATCH (p:Person) RETURN DISTINCT p.name",
+            "MATCH (p:Person) RETURN p.name ORDER BY p.name LIMIT 3",
+            "MATCH (p:Person) WITH p.name AS name RETURN name",
+            "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) RETURN a.name, b.name",
+            "MATCH (a:Person)-[:KNOWS*1..2]->(b:Company) RETURN b.name",
+        ] {
+            assert!(!incremental(cypher, &[&person, &knows]), "{}", cypher);
+        }
+    }
+}
@@ -0,0 +1,729 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! `CALL graph.summary()`: a quick health check of the whole graph
+//!
+//! Returns one row per metric with the columns `kind` (`node` or
+//! `relationship`), `name` (label or relationship type), `property` (set for
+//! per-property metrics), `metric`, `value` and `item` (the value a
+//! `most_common` row counts):
+//!
+//! - `count`: live rows of the label or type (soft-deleted rows excluded)
+//! - `size_bytes`: size of the backing dataset, on disk for Lance datasets and
+//!   in memory for in-memory tables
+//! - `dataset_version` (Lance datasets only): version of the dataset the
+//!   metrics describe
+//! - `null_ratio`: share of rows where the property is null
+//! - `distinct_count` (integer, date and string properties): approximate
+//!   number of distinct values
+//! - `value_min` / `_p25` / `_p50` / `_p75` / `_max` (numeric and temporal
+//!   properties with values): an equi-depth histogram of four buckets, with
+//!   approximate quartiles; temporal values are milliseconds since the Unix
+//!   epoch
+//! - `most_common` (string properties): share of all rows holding `item`, for
+//!   the [`MOST_COMMON_VALUES`] most frequent values
+//! - `out_degree_mean` / `_p50` / `_p90` / `_p95` / `_p99` / `_max`
+//!   (relationships only): outgoing relationships per source node that has at
+//!   least one
+//! - `in_degree_mean` / `_p50` / `_p90` / `_p95` / `_p99` / `_max`
+//!   (relationships only): incoming relationships per target node that has at
+//!   least one
+//!
+//! View labels are skipped; they are filters over their base label.
+//!
+//! Summaries of Lance-backed labels and types are cached per dataset version,
+//! so repeated calls only rescan the datasets written to since the last one.
+
+use crate::ast::ProcedureCall;
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use arrow_array::{Array, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::execution::context::SessionContext;
+use datafusion::functions_aggregate::expr_fn::{
+    approx_distinct, approx_percentile_cont, count, max, min,
+};
+use datafusion::logical_expr::{cast, lit, Expr};
+use datafusion::prelude::ident;
+use lance::datafusion::LanceTableProvider;
+use lance::dataset::statistics::DatasetStatisticsExt;
+use lance::dataset::ROW_ID;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Name of the summary procedure, matched case-insensitively
+pub const SUMMARY_PROCEDURE: &str = "graph.summary";
+
+/// Summaries of Lance-backed elements keyed by (dataset uri, version, element name)
+type CacheKey = (String, u64, String);
+
+static SUMMARY_CACHE: LazyLock<Mutex<HashMap<CacheKey, ElementSummary>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Metrics of one label or relationship type
+#[derive(Debug, Clone, PartialEq)]
+struct ElementSummary {
+    count: i64,
+    size_bytes: Option<u64>,
+    /// Version of the summarized Lance dataset
+    dataset_version: Option<u64>,
+    properties: Vec<PropertySummary>,
+    /// Out-degree distribution, see [`OUT_DEGREE_METRICS`]
+    out_degree: Option<[f64; 6]>,
+    /// In-degree distribution, see [`IN_DEGREE_METRICS`]
+    in_degree: Option<[f64; 6]>,
+}
+
+/// Most frequent values listed per string property
+pub const MOST_COMMON_VALUES: usize = 10;
+
+/// Metrics of one property
+#[derive(Debug, Clone, PartialEq)]
+struct PropertySummary {
+    name: String,
+    null_ratio: f64,
+    distinct_count: Option<f64>,
+    /// Bounds of the equi-depth histogram, see [`HISTOGRAM_METRICS`]
+    histogram: Option<[f64; 5]>,
+    /// Most frequent values and their share of all rows, most frequent first
+    most_common: Vec<(String, f64)>,
+}
+
+/// Metrics of the out-degree distribution of a relationship type, in order
+pub(crate) const OUT_DEGREE_METRICS: [&str; 6] = [
+    "out_degree_mean",
+    "out_degree_p50",
+    "out_degree_p90",
+    "out_degree_p95",
+    "out_degree_p99",
+    "out_degree_max",
+];
+
+/// Metrics of the in-degree distribution of a relationship type, in order
+pub(crate) const IN_DEGREE_METRICS: [&str; 6] = [
+    "in_degree_mean",
+    "in_degree_p50",
+    "in_degree_p90",
+    "in_degree_p95",
+    "in_degree_p99",
+    "in_degree_max",
+];
+
+/// Metrics of the histogram bounds of a numeric property, in order
+pub(crate) const HISTOGRAM_METRICS: [&str; 5] = [
+    "value_min",
+    "value_p25",
+    "value_p50",
+    "value_p75",
+    "value_max",
+];
+
+/// Whether the distinct values of a column of this type are counted
+fn counts_distinct(data_type: &DataType) -> bool {
+    data_type.is_integer()
+        || matches!(
+            data_type,
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Date32 | DataType::Date64
+        )
+}
+
+/// Whether a column of this type gets a histogram
+fn has_histogram(data_type: &DataType) -> bool {
+    data_type.is_integer()
+        || data_type.is_floating()
+        || matches!(
+            data_type,
+            DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _)
+        )
+}
+
+/// Whether the most common values of a column of this type are listed
+fn lists_most_common(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+/// Histogram values of `column`: numbers as they are, temporal values as
+/// milliseconds since the Unix epoch
+fn histogram_values(column: Expr, data_type: &DataType) -> Expr {
+    match data_type {
+        DataType::Date32 => {
+            cast(cast(column, DataType::Int32), DataType::Float64) * lit(86_400_000.0)
+        }
+        DataType::Date64 => cast(cast(column, DataType::Int64), DataType::Float64),
+        DataType::Timestamp(_, _) => cast(
+            cast(
+                cast(column, DataType::Timestamp(TimeUnit::Millisecond, None)),
+                DataType::Int64,
+            ),
+            DataType::Float64,
+        ),
+        _ => cast(column, DataType::Float64),
+    }
+}
+
+/// Fail unless `call` names a known procedure with valid arguments
+pub(crate) fn check_procedure(call: &ProcedureCall) -> Result<()> {
+    if crate::graph_diff::is_diff_call(call) {
+        return crate::graph_diff::check_diff_call(call);
+    }
+    if crate::query_monitor::is_monitor_call(call) {
+        return crate::query_monitor::check_call(call);
+    }
+    if !call.name.eq_ignore_ascii_case(SUMMARY_PROCEDURE) {
+        return Err(GraphError::UnsupportedFeature {
+            feature: format!(
+                "procedure '{}'; supported procedures: {}, {}, {}, {}",
+                call.name,
+                SUMMARY_PROCEDURE,
+                crate::graph_diff::DIFF_PROCEDURE,
+                crate::query_monitor::LIST_QUERIES_PROCEDURE,
+                crate::query_monitor::KILL_QUERY_PROCEDURE
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    if !call.arguments.is_empty() {
+        return Err(GraphError::PlanError {
+            message: format!("{}() takes no arguments", SUMMARY_PROCEDURE),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(())
+}
+
+/// Schema of the rows returned by `CALL graph.summary()`
+pub fn summary_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("property", DataType::Utf8, true),
+        Field::new("metric", DataType::Utf8, false),
+        Field::new("value", DataType::Float64, false),
+        Field::new("item", DataType::Utf8, true),
+    ]))
+}
+
+/// Summarize every label and relationship type of `config` over the tables of `ctx`
+///
+/// Tables are looked up by lowercase label / relationship type (or source
+/// table), the way query execution registers them.
+pub(crate) async fn graph_summary(
+    config: &GraphConfig,
+    ctx: &SessionContext,
+) -> Result<RecordBatch> {
+    let mut rows = SummaryRows::default();
+
+    let mut nodes: Vec<_> = config
+        .node_mappings
+        .values()
+        .filter(|m| m.view_of.is_none())
+        .collect();
+    nodes.sort_by(|a, b| a.label.cmp(&b.label));
+    for mapping in nodes {
+        let mut filters = Vec::new();
+        if let Some(column) = &mapping.label_column {
+            filters.push(ident(column).eq(lit(mapping.label.as_str())));
+        }
+        if let Some(column) = &mapping.soft_delete_column {
+            filters.push(ident(column).is_null());
+        }
+        let summary =
+            summarize_element(ctx, mapping.table_name(), &mapping.label, filters, None).await?;
+        rows.push_element("node", &mapping.label, &summary);
+    }
+
+    let mut relationships: Vec<_> = config.relationship_mappings.values().collect();
+    relationships.sort_by(|a, b| a.relationship_type.cmp(&b.relationship_type));
+    for mapping in relationships {
+        let mut filters = Vec::new();
+        if let Some(column) = &mapping.soft_delete_column {
+            filters.push(ident(column).is_null());
+        }
+        let sources = mapping.source_key_columns();
+        let targets = mapping.target_key_columns();
+        let summary = summarize_element(
+            ctx,
+            &mapping.relationship_type,
+            &mapping.relationship_type,
+            filters,
+            Some((&sources, &targets)),
+        )
+        .await?;
+        rows.push_element("relationship", &mapping.relationship_type, &summary);
+    }
+
+    rows.finish()
+}
+
+/// Summarize the rows of `table` passing `filters`, reusing a cached summary
+/// of the same Lance dataset version
+async fn summarize_element(
+    ctx: &SessionContext,
+    table: &str,
+    name: &str,
+    filters: Vec<Expr>,
+    endpoint_columns: Option<(&[&str], &[&str])>,
+) -> Result<ElementSummary> {
+    let table = table.to_lowercase();
+    let provider =
+        ctx.table_provider(table.as_str())
+            .await
+            .map_err(|e| GraphError::ConfigError {
+                message: format!("No table registered for '{}': {}", name, e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+    let dataset = provider
+        .as_any()
+        .downcast_ref::<LanceTableProvider>()
+        .map(|p| p.dataset());
+    let key = dataset
+        .as_ref()
+        .map(|d| (d.uri().to_string(), d.version().version, name.to_string()));
+    if let Some(key) = &key {
+        if let Some(cached) = SUMMARY_CACHE.lock().unwrap().get(key) {
+            return Ok(cached.clone());
+        }
+    }
+
+    let mut df = ctx.table(table.as_str()).await?;
+    for filter in filters {
+        df = df.filter(filter)?;
+    }
+
+    // Row count and per-column metrics in one scan; Lance row id and address
+    // columns are not properties
+    let columns: Vec<(String, DataType)> = df
+        .schema()
+        .fields()
+        .iter()
+        .filter(|f| f.name() != ROW_ID && f.name() != "_rowaddr")
+        .map(|f| (f.name().clone(), f.data_type().clone()))
+        .collect();
+    let mut aggregates = vec![count(lit(1)).alias("__rows")];
+    for (i, (name, data_type)) in columns.iter().enumerate() {
+        let column = ident(name);
+        aggregates.push(count(column.clone()).alias(format!("__non_null_{}", i)));
+        if counts_distinct(data_type) {
+            aggregates.push(
+                cast(approx_distinct(column.clone()), DataType::Float64)
+                    .alias(format!("__distinct_{}", i)),
+            );
+        }
+        if has_histogram(data_type) {
+            let values = histogram_values(column.clone(), data_type);
+            let bounds = [
+                min(values.clone()),
+                approx_percentile_cont(values.clone().sort(true, false), lit(0.25), None),
+                approx_percentile_cont(values.clone().sort(true, false), lit(0.5), None),
+                approx_percentile_cont(values.clone().sort(true, false), lit(0.75), None),
+                max(values),
+            ];
+            for (j, bound) in bounds.into_iter().enumerate() {
+                aggregates
+                    .push(cast(bound, DataType::Float64).alias(format!("__bound_{}_{}", i, j)));
+            }
+        }
+    }
+    let metrics = df.clone().aggregate(vec![], aggregates)?.collect().await?;
+    let metrics = metrics.first();
+    let count_of = |name: &str| -> i64 {
+        metrics
+            .and_then(|b| b.column_by_name(name))
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .filter(|a| !a.is_empty())
+            .map(|a| a.value(0))
+            .unwrap_or(0)
+    };
+    // Null for columns without values
+    let value_of = |name: &str| -> Option<f64> {
+        metrics
+            .and_then(|b| b.column_by_name(name))
+            .and_then(|c| c.as_any().downcast_ref::<Float64Array>())
+            .filter(|a| !a.is_empty() && a.is_valid(0))
+            .map(|a| a.value(0))
+    };
+    let rows = count_of("__rows");
+    let mut properties = Vec::with_capacity(columns.len());
+    for (i, (name, data_type)) in columns.iter().enumerate() {
+        let most_common = if lists_most_common(data_type) && rows > 0 {
+            most_common_values(&df, name, rows).await?
+        } else {
+            Vec::new()
+        };
+        let null_ratio = if rows == 0 {
+            0.0
+        } else {
+            1.0 - count_of(&format!("__non_null_{}", i)) as f64 / rows as f64
+        };
+        let bounds: Option<Vec<f64>> = (0..HISTOGRAM_METRICS.len())
+            .map(|j| value_of(&format!("__bound_{}_{}", i, j)))
+            .collect();
+        properties.push(PropertySummary {
+            name: name.clone(),
+            null_ratio,
+            distinct_count: value_of(&format!("__distinct_{}", i)),
+            histogram: bounds.and_then(|bounds| bounds.try_into().ok()),
+            most_common,
+        });
+    }
+
+    let (out_degree, in_degree) = match endpoint_columns {
+        Some((sources, targets)) => (
+            Some(degree_distribution(df.clone(), sources).await?),
+            Some(degree_distribution(df, targets).await?),
+        ),
+        None => (None, None),
+    };
+
+    let size_bytes = match &dataset {
+        Some(dataset) => Some(
+            dataset
+                .calculate_data_stats()
+                .await?
+                .fields
+                .iter()
+                .map(|f| f.bytes_on_disk)
+                .sum(),
+        ),
+        None => memory_size(&provider).await,
+    };
+
+    let summary = ElementSummary {
+        count: rows,
+        size_bytes,
+        dataset_version: key.as_ref().map(|(_, version, _)| *version),
+        properties,
+        out_degree,
+        in_degree,
+    };
+    if let Some(key) = key {
+        let mut cache = SUMMARY_CACHE.lock().unwrap();
+        // Older versions of the dataset are never asked for again
+        cache.retain(|(uri, _, element), _| !(uri == &key.0 && element == &key.2));
+        cache.insert(key, summary.clone());
+    }
+    Ok(summary)
+}
+
+/// The [`MOST_COMMON_VALUES`] most frequent values of `column` with their
+/// share of all `rows`; ties are listed in value order
+async fn most_common_values(
+    df: &datafusion::dataframe::DataFrame,
+    column: &str,
+    rows: i64,
+) -> Result<Vec<(String, f64)>> {
+    let batches = df
+        .clone()
+        .filter(ident(column).is_not_null())?
+        .aggregate(
+            vec![cast(ident(column), DataType::Utf8).alias("__value")],
+            vec![count(lit(1)).alias("__frequency")],
+        )?
+        .sort(vec![
+            ident("__frequency").sort(false, false),
+            ident("__value").sort(true, false),
+        ])?
+        .limit(0, Some(MOST_COMMON_VALUES))?
+        .collect()
+        .await?;
+
+    let mut values = Vec::new();
+    for batch in &batches {
+        let value = batch
+            .column_by_name("__value")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let frequency = batch
+            .column_by_name("__frequency")
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+        if let (Some(value), Some(frequency)) = (value, frequency) {
+            for row in 0..batch.num_rows() {
+                values.push((
+                    value.value(row).to_string(),
+                    frequency.value(row) as f64 / rows as f64,
+                ));
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Mean, nearest-rank p50 / p90 / p95 / p99 and max of the relationships per
+/// node referenced by `endpoints`
+async fn degree_distribution(
+    df: datafusion::dataframe::DataFrame,
+    endpoints: &[&str],
+) -> Result<[f64; 6]> {
+    let keys: Vec<Expr> = endpoints.iter().map(|c| ident(*c)).collect();
+    let mut df = df;
+    if let Some(not_null) = keys
+        .iter()
+        .cloned()
+        .map(Expr::is_not_null)
+        .reduce(Expr::and)
+    {
+        df = df.filter(not_null)?;
+    }
+    let batches = df
+        .aggregate(keys, vec![count(lit(1)).alias("__degree")])?
+        .select(vec![ident("__degree")])?
+        .collect()
+        .await?;
+
+    let mut degrees: Vec<i64> = batches
+        .iter()
+        .filter_map(|b| b.column(0).as_any().downcast_ref::<Int64Array>())
+        .flat_map(|a| a.values().iter().copied())
+        .collect();
+    if degrees.is_empty() {
+        return Ok([0.0; 6]);
+    }
+    degrees.sort_unstable();
+    let rank = |p: f64| {
+        let idx = ((p * degrees.len() as f64).ceil() as usize).clamp(1, degrees.len()) - 1;
+        degrees[idx] as f64
+    };
+    let mean = degrees.iter().sum::<i64>() as f64 / degrees.len() as f64;
+    Ok([
+        mean,
+        rank(0.5),
+        rank(0.9),
+        rank(0.95),
+        rank(0.99),
+        *degrees.last().unwrap() as f64,
+    ])
+}
+
+/// In-memory size of a [`MemTable`], `None` for other providers
+async fn memory_size(provider: &Arc<dyn TableProvider>) -> Option<u64> {
+    let table = provider.as_any().downcast_ref::<MemTable>()?;
+    let mut bytes = 0;
+    for partition in &table.batches {
+        for batch in partition.read().await.iter() {
+            bytes += batch.get_array_memory_size() as u64;
+        }
+    }
+    Some(bytes)
+}
+
+/// Columns of the summary batch under construction
+#[derive(Default)]
+struct SummaryRows {
+    kind: Vec<&'static str>,
+    name: Vec<String>,
+    property: Vec<Option<String>>,
+    metric: Vec<&'static str>,
+    value: Vec<f64>,
+    item: Vec<Option<String>>,
+}
+
+impl SummaryRows {
+    fn push(
+        &mut self,
+        kind: &'static str,
+        name: &str,
+        property: Option<&str>,
+        metric: &'static str,
+        value: f64,
+    ) {
+        self.kind.push(kind);
+        self.name.push(name.to_string());
+        self.property.push(property.map(str::to_string));
+        self.metric.push(metric);
+        self.value.push(value);
+        self.item.push(None);
+    }
+
+    fn push_element(&mut self, kind: &'static str, name: &str, summary: &ElementSummary) {
+        self.push(kind, name, None, "count", summary.count as f64);
+        if let Some(bytes) = summary.size_bytes {
+            self.push(kind, name, None, "size_bytes", bytes as f64);
+        }
+        if let Some(version) = summary.dataset_version {
+            self.push(kind, name, None, "dataset_version", version as f64);
+        }
+        if let Some(degrees) = summary.out_degree {
+            for (metric, degree) in OUT_DEGREE_METRICS.into_iter().zip(degrees) {
+                self.push(kind, name, None, metric, degree);
+            }
+        }
+        if let Some(degrees) = summary.in_degree {
+            for (metric, degree) in IN_DEGREE_METRICS.into_iter().zip(degrees) {
+                self.push(kind, name, None, metric, degree);
+            }
+        }
+        for property in &summary.properties {
+            let column = Some(property.name.as_str());
+            self.push(kind, name, column, "null_ratio", property.null_ratio);
+            if let Some(distinct) = property.distinct_count {
+                self.push(kind, name, column, "distinct_count", distinct);
+            }
+            if let Some(bounds) = property.histogram {
+                for (metric, bound) in HISTOGRAM_METRICS.into_iter().zip(bounds) {
+                    self.push(kind, name, column, metric, bound);
+                }
+            }
+            for (item, frequency) in &property.most_common {
+                self.push(kind, name, column, "most_common", *frequency);
+                *self.item.last_mut().unwrap() = Some(item.clone());
+            }
+        }
+    }
+
+    fn finish(self) -> Result<RecordBatch> {
+        RecordBatch::try_new(
+            summary_schema(),
+            vec![
+                Arc::new(StringArray::from(self.kind)),
+                Arc::new(StringArray::from(self.name)),
+                Arc::new(StringArray::from(self.property)),
+                Arc::new(StringArray::from(self.metric)),
+                Arc::new(Float64Array::from(self.value)),
+                Arc::new(StringArray::from(self.item)),
+            ],
+        )
+        .map_err(|e| GraphError::ExecutionError {
+            message: format!("Failed to build graph summary: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::CypherQuery;
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap()
+    }
+
+    fn datasets() -> HashMap<String, RecordBatch> {
+        let people = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("city", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec![
+                    Some("Oslo"),
+                    None,
+                    None,
+                    Some("Rome"),
+                ])),
+            ],
+        )
+        .unwrap();
+        let knows = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("src_id", DataType::Int64, false),
+                Field::new("dst_id", DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 1, 1, 2])),
+                Arc::new(Int64Array::from(vec![2, 3, 4, 3])),
+            ],
+        )
+        .unwrap();
+        HashMap::from([("Person".to_string(), people), ("KNOWS".to_string(), knows)])
+    }
+
+    fn metric(batch: &RecordBatch, name: &str, property: Option<&str>, metric: &str) -> f64 {
+        let column = |i: usize| {
+            batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+        };
+        let values = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        (0..batch.num_rows())
+            .find(|&row| {
+                column(1).value(row) == name
+                    && column(3).value(row) == metric
+                    && (!column(2).is_null(row)).then(|| column(2).value(row)) == property
+            })
+            .map(|row| values.value(row))
+            .unwrap_or_else(|| panic!("no {} {} for {}", metric, property.unwrap_or(""), name))
+    }
+
+    #[tokio::test]
+    async fn test_graph_summary() {
+        let summary = CypherQuery::new("CALL graph.summary()")
+            .unwrap()
+            .with_config(config())
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+        assert_eq!(summary.schema(), summary_schema());
+
+        assert_eq!(metric(&summary, "Person", None, "count"), 4.0);
+        assert_eq!(metric(&summary, "Person", Some("city"), "null_ratio"), 0.5);
+        assert_eq!(
+            metric(&summary, "Person", Some("city"), "distinct_count"),
+            2.0
+        );
+        assert_eq!(metric(&summary, "Person", Some("id"), "value_min"), 1.0);
+        assert_eq!(metric(&summary, "Person", Some("id"), "value_max"), 4.0);
+        let median = metric(&summary, "Person", Some("id"), "value_p50");
+        assert!((1.0..=4.0).contains(&median), "{}", median);
+        assert!(metric(&summary, "Person", None, "size_bytes") > 0.0);
+        assert_eq!(metric(&summary, "KNOWS", None, "count"), 4.0);
+        // Out-degrees are 3 (node 1) and 1 (node 2)
+        assert_eq!(metric(&summary, "KNOWS", None, "out_degree_p50"), 1.0);
+        assert_eq!(metric(&summary, "KNOWS", None, "out_degree_max"), 3.0);
+        assert_eq!(metric(&summary, "KNOWS", None, "out_degree_mean"), 2.0);
+        // In-degrees are 1 (node 2), 2 (node 3) and 1 (node 4)
+        assert_eq!(metric(&summary, "KNOWS", None, "in_degree_p50"), 1.0);
+        assert_eq!(metric(&summary, "KNOWS", None, "in_degree_p95"), 2.0);
+        assert_eq!(metric(&summary, "KNOWS", None, "in_degree_max"), 2.0);
+        assert!((metric(&summary, "KNOWS", None, "in_degree_mean") - 4.0 / 3.0).abs() < 1e-9);
+
+        // Each city is held by one of the four people
+        let items = summary
+            .column(5)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let common: Vec<&str> = (0..summary.num_rows())
+            .filter(|&row| items.is_valid(row))
+            .map(|row| items.value(row))
+            .collect();
+        assert_eq!(common, vec!["Oslo", "Rome"]);
+        assert_eq!(
+            metric(&summary, "Person", Some("city"), "most_common"),
+            0.25
+        );
+    }
+
+    #[test]
+    fn test_unknown_procedure_is_rejected() {
+        let call = |name: &str, arguments| ProcedureCall {
+            name: name.to_string(),
+            arguments,
+        };
+        assert!(check_procedure(&call("GRAPH.SUMMARY", vec![])).is_ok());
+        assert!(check_procedure(&call("db.labels", vec![])).is_err());
+        assert!(check_procedure(&call(
+            "graph.summary",
+            vec![crate::ast::ValueExpression::Variable("x".to_string())]
+        ))
+        .is_err());
+
+        let err = CypherQuery::new("CALL db.labels()")
+            .unwrap()
+            .with_config(config())
+            .describe(&HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("db.labels"), "{}", err);
+    }
+}
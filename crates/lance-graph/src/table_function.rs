@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! User-registered table functions
+//!
+//! A [`TableFunction`] produces rows from outside the graph (an API, another
+//! store, a generator) as a stream of record batches. Registered with
+//! [`crate::CypherQuery::with_table_function`], it is called from a reading
+//! clause and its columns are bound as variables by `YIELD`:
+//!
+//! ```text
+//! CALL weather.forecast('Berlin', $days) YIELD day, temperature AS t
+//! MATCH (e:Event) WHERE e.day = day
+//! RETURN e.name, t
+//! ```
+//!
+//! Arguments must be literals or parameters; they are evaluated when the
+//! query is planned, and the function is called once when the query runs.
+//! The yielded rows are combined with the rows of the clauses before the call
+//! as a cross product, so join them with `WHERE`. Function names are
+//! case-insensitive.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::table_function::TableFunction;
+//!
+//! #[derive(Debug)]
+//! struct Forecast { client: WeatherClient }
+//!
+//! #[async_trait::async_trait]
+//! impl TableFunction for Forecast {
+//!     fn schema(&self) -> SchemaRef {
+//!         forecast_schema()
+//!     }
+//!
+//!     async fn call(&self, arguments: Vec<ScalarValue>) -> Result<BoxStream<'static, Result<RecordBatch>>> {
+//!         let city = arguments[0].to_string();
+//!         Ok(self.client.forecast(&city).await?.into_batches().boxed())
+//!     }
+//! }
+//! ```
+
+use crate::ast::{PropertyValue, ValueExpression};
+use crate::error::{GraphError, Result};
+use arrow_array::RecordBatch;
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion::catalog::streaming::StreamingTable;
+use datafusion::datasource::TableProvider;
+use datafusion::error::DataFusionError;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
+use datafusion::scalar::ScalarValue;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A function producing rows that `CALL ... YIELD` binds into a query
+#[async_trait]
+pub trait TableFunction: Send + Sync + fmt::Debug {
+    /// Schema of every batch the function returns; `YIELD` picks its columns
+    fn schema(&self) -> SchemaRef;
+
+    /// Produce the rows for `arguments`, in call order
+    async fn call(
+        &self,
+        arguments: Vec<ScalarValue>,
+    ) -> Result<BoxStream<'static, Result<RecordBatch>>>;
+}
+
+/// One call of a table function as a DataFusion table
+///
+/// The function is called when the table is scanned, not when it is planned.
+pub(crate) fn call_provider(
+    function: Arc<dyn TableFunction>,
+    arguments: Vec<ScalarValue>,
+) -> Result<Arc<dyn TableProvider>> {
+    let schema = function.schema();
+    let partition = Arc::new(Call {
+        function,
+        arguments,
+        schema: schema.clone(),
+    });
+    let table =
+        StreamingTable::try_new(schema, vec![partition]).map_err(|e| GraphError::PlanError {
+            message: format!("Invalid table function schema: {}", e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    Ok(Arc::new(table))
+}
+
+/// Evaluate the arguments of a call: literals, or parameters bound in
+/// `parameters`
+pub(crate) fn evaluate_arguments(
+    name: &str,
+    arguments: &[ValueExpression],
+    parameters: &HashMap<String, serde_json::Value>,
+) -> Result<Vec<ScalarValue>> {
+    arguments
+        .iter()
+        .map(|argument| {
+            let value = match argument {
+                ValueExpression::Parameter(parameter)
+                | ValueExpression::Literal(PropertyValue::Parameter(parameter)) => parameters
+                    .get(parameter)
+                    .and_then(PropertyValue::from_json)
+                    .ok_or_else(|| GraphError::PlanError {
+                        message: format!(
+                            "Missing or non-scalar value for parameter ${} of {}",
+                            parameter, name
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })?,
+                ValueExpression::Literal(value) => value.clone(),
+                other => {
+                    return Err(GraphError::PlanError {
+                        message: format!(
+                        "Arguments of table function {} must be literals or parameters, got {:?}",
+                        name, other
+                    ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                }
+            };
+            Ok(match value {
+                PropertyValue::String(s) => ScalarValue::Utf8(Some(s)),
+                PropertyValue::Integer(i) => ScalarValue::Int64(Some(i)),
+                PropertyValue::Float(f) => ScalarValue::Float64(Some(f)),
+                PropertyValue::Boolean(b) => ScalarValue::Boolean(Some(b)),
+                PropertyValue::Null => ScalarValue::Null,
+                PropertyValue::Parameter(_) | PropertyValue::Property(_) => {
+                    return Err(GraphError::PlanError {
+                        message: format!(
+                            "Arguments of table function {} must be literals or parameters",
+                            name
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+                }
+            })
+        })
+        .collect()
+}
+
+/// The single partition of a call, calling the function on execution
+struct Call {
+    function: Arc<dyn TableFunction>,
+    arguments: Vec<ScalarValue>,
+    schema: SchemaRef,
+}
+
+impl fmt::Debug for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Call")
+            .field("function", &self.function)
+            .field("arguments", &self.arguments)
+            .finish()
+    }
+}
+
+impl PartitionStream for Call {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let function = self.function.clone();
+        let arguments = self.arguments.clone();
+        let external = |e: GraphError| DataFusionError::External(Box::new(e));
+        let batches = futures::stream::once(async move { function.call(arguments).await })
+            .map_err(external)
+            .map_ok(move |batches| batches.map_err(external))
+            .try_flatten();
+        Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            batches.boxed(),
+        ))
+    }
+}
@@ -0,0 +1,693 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Query templates with typed slots
+//!
+//! Applications that build Cypher by formatting strings are open to injection:
+//! a label or value containing `)` or `'` changes the query's structure. A
+//! [`QueryTemplate`] is parsed once with `$name` slots in place of the varying
+//! parts, and [`QueryTemplate::render`] substitutes bound arguments into the
+//! parsed AST, so an argument can only ever fill its slot.
+//!
+//! Slots are typed by where they appear:
+//!
+//! - `(n:$label)` and `-[:$type]->` are label / relationship type slots,
+//! - `n.$prop` is a property slot,
+//! - every other `$name` (in WHERE, RETURN, property maps, `IN $list`) is a
+//!   value slot.
+//!
+//! Identifier arguments must be plain identifiers, and labels and relationship
+//! types must exist in the template's configuration when one is set. Value
+//! arguments become literals: scalars, numeric arrays as vectors (e.g. for
+//! `vector_distance`), and arrays expanded in place for `IN $list`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::template::{QueryTemplate, TemplateArgs};
+//!
+//! let template = QueryTemplate::new(
+//!     "MATCH (n:$label) WHERE n.$prop >= $min RETURN n.name",
+//! )?
+//! .with_config(config);
+//! let query = template.render(
+//!     &TemplateArgs::new()
+//!         .identifier("label", "Person")
+//!         .identifier("prop", "age")
+//!         .value("min", 30),
+//! )?;
+//! let result = query.execute(datasets, None).await?;
+//! ```
+
+use crate::ast::CypherQuery as CypherAST;
+use crate::ast::{
+    BooleanExpression, GraphPattern, NodePattern, PropertyValue, ReadingClause,
+    RelationshipPattern, SampleMethod, ValueExpression,
+};
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use crate::query::CypherQuery;
+use std::collections::{BTreeMap, HashMap};
+
+/// What a template slot stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotKind {
+    /// Node label, e.g. `(n:$label)`
+    Label,
+    /// Relationship type, e.g. `-[:$type]->`
+    RelationshipType,
+    /// Property name, e.g. `n.$prop`
+    Property,
+    /// Literal value, e.g. `n.age > $min`
+    Value,
+}
+
+impl SlotKind {
+    fn is_identifier(self) -> bool {
+        self != Self::Value
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Label => "label",
+            Self::RelationshipType => "relationship type",
+            Self::Property => "property",
+            Self::Value => "value",
+        }
+    }
+}
+
+/// Arguments for [`QueryTemplate::render`]
+#[derive(Debug, Clone, Default)]
+pub struct TemplateArgs {
+    identifiers: HashMap<String, String>,
+    values: HashMap<String, serde_json::Value>,
+}
+
+impl TemplateArgs {
+    /// Create an empty argument set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind a label, relationship type or property slot
+    pub fn identifier<K: Into<String>, V: Into<String>>(mut self, slot: K, name: V) -> Self {
+        self.identifiers.insert(slot.into(), name.into());
+        self
+    }
+
+    /// Bind a value slot
+    pub fn value<K: Into<String>, V: Into<serde_json::Value>>(mut self, slot: K, value: V) -> Self {
+        self.values.insert(slot.into(), value.into());
+        self
+    }
+}
+
+/// A parsed Cypher query with typed `$name` slots
+#[derive(Debug, Clone)]
+pub struct QueryTemplate {
+    query: CypherQuery,
+    slots: BTreeMap<String, SlotKind>,
+}
+
+impl QueryTemplate {
+    /// Parse `template` and infer the kind of each slot
+    ///
+    /// Fails if the template does not parse or uses one slot name for
+    /// different kinds (e.g. both as a label and as a value).
+    pub fn new(template: &str) -> Result<Self> {
+        let mut query = CypherQuery::new(template)?;
+        let mut slots = BTreeMap::new();
+        visit_slots(query.ast_mut(), &mut |site: SlotSite<'_>| {
+            let (name, kind) = site.slot();
+            match slots.insert(name.to_string(), kind) {
+                Some(previous) if previous != kind => Err(template_error(format!(
+                    "Slot ${} is used both as a {} and as a {}",
+                    name,
+                    previous.describe(),
+                    kind.describe()
+                ))),
+                _ => Ok(()),
+            }
+        })?;
+        Ok(Self { query, slots })
+    }
+
+    /// Validate labels and relationship types against `config` and use it
+    /// for the rendered queries
+    pub fn with_config(mut self, config: GraphConfig) -> Self {
+        self.query = self.query.with_config(config);
+        self
+    }
+
+    /// Slots of the template by name
+    pub fn slots(&self) -> &BTreeMap<String, SlotKind> {
+        &self.slots
+    }
+
+    /// Substitute `args` into the template's AST
+    ///
+    /// Every slot must be bound with an argument of its kind, and every
+    /// argument must name a slot.
+    pub fn render(&self, args: &TemplateArgs) -> Result<CypherQuery> {
+        for (name, kind) in &self.slots {
+            let bound = if kind.is_identifier() {
+                args.identifiers.contains_key(name)
+            } else {
+                args.values.contains_key(name)
+            };
+            if !bound {
+                return Err(template_error(format!(
+                    "Slot ${} needs a {} argument",
+                    name,
+                    kind.describe()
+                )));
+            }
+        }
+        let unknown = args
+            .identifiers
+            .keys()
+            .filter(|name| !self.slots.get(*name).is_some_and(|k| k.is_identifier()))
+            .chain(
+                args.values
+                    .keys()
+                    .filter(|name| self.slots.get(*name) != Some(&SlotKind::Value)),
+            )
+            .next();
+        if let Some(name) = unknown {
+            return Err(template_error(format!(
+                "Argument '{}' does not match a slot of that kind",
+                name
+            )));
+        }
+
+        let mut query = self.query.clone();
+        let config = query.config().cloned();
+        visit_slots(query.ast_mut(), &mut |site: SlotSite<'_>| {
+            site.bind(args, config.as_ref())
+        })?;
+        Ok(query)
+    }
+}
+
+/// Replace `$name` values that have a scalar query parameter with literals
+///
+/// Used by [`CypherQuery::with_parameter`] queries at planning time. `IN $list`
+/// is planned from the parameter map directly, and parameters that are
+/// missing or not scalars are left for the planner to report.
+pub(crate) fn bind_value_parameters(
+    ast: &mut CypherAST,
+    parameters: &HashMap<String, serde_json::Value>,
+) -> Result<()> {
+    let scalar = |name: &str| parameters.get(name).and_then(PropertyValue::from_json);
+    visit_slots(ast, &mut |site: SlotSite<'_>| {
+        match site {
+            SlotSite::Value(target) => {
+                if let Some(value) = match &*target {
+                    ValueExpression::Parameter(name) => scalar(name),
+                    _ => None,
+                } {
+                    *target = ValueExpression::Literal(value);
+                }
+            }
+            SlotSite::PatternValue(target) => {
+                if let Some(value) = match &*target {
+                    PropertyValue::Parameter(name) => scalar(name),
+                    _ => None,
+                } {
+                    *target = value;
+                }
+            }
+            SlotSite::Name(..) | SlotSite::Property(_) | SlotSite::List(_) => {}
+        }
+        Ok(())
+    })
+}
+
+/// A place in the AST holding a slot
+enum SlotSite<'a> {
+    /// Label or relationship type in a pattern
+    Name(&'a mut String, SlotKind),
+    /// Property name of a property reference
+    Property(&'a mut String),
+    /// `$name` in an expression
+    Value(&'a mut ValueExpression),
+    /// `$name` in a pattern property map
+    PatternValue(&'a mut PropertyValue),
+    /// `IN $name`
+    List(&'a mut Vec<ValueExpression>),
+}
+
+impl SlotSite<'_> {
+    /// Name (without `$`) and kind of the slot
+    fn slot(&self) -> (&str, SlotKind) {
+        match self {
+            Self::Name(name, kind) => (&name[1..], *kind),
+            Self::Property(name) => (&name[1..], SlotKind::Property),
+            Self::Value(ValueExpression::Parameter(name))
+            | Self::PatternValue(PropertyValue::Parameter(name)) => (name, SlotKind::Value),
+            Self::List(list) => match list.as_slice() {
+                [ValueExpression::Parameter(name)] => (name, SlotKind::Value),
+                _ => unreachable!("list sites hold a single parameter"),
+            },
+            _ => unreachable!("value sites hold a parameter"),
+        }
+    }
+
+    /// Replace the slot by its argument
+    fn bind(self, args: &TemplateArgs, config: Option<&GraphConfig>) -> Result<()> {
+        let (name, kind) = self.slot();
+        let name = name.to_string();
+        if kind.is_identifier() {
+            let identifier = &args.identifiers[&name];
+            validate_identifier(&name, kind, identifier, config)?;
+            match self {
+                Self::Name(target, _) | Self::Property(target) => *target = identifier.clone(),
+                _ => unreachable!("identifier slots are names"),
+            }
+            return Ok(());
+        }
+
+        let value = &args.values[&name];
+        match self {
+            Self::Value(target) => *target = value_expression(&name, value)?,
+            Self::PatternValue(target) => *target = literal(&name, value)?,
+            Self::List(target) => {
+                *target = match value {
+                    serde_json::Value::Array(items) => items
+                        .iter()
+                        .map(|item| literal(&name, item).map(ValueExpression::Literal))
+                        .collect::<Result<_>>()?,
+                    scalar => vec![ValueExpression::Literal(literal(&name, scalar)?)],
+                }
+            }
+            _ => unreachable!("value slots are values"),
+        }
+        Ok(())
+    }
+}
+
+/// Check an identifier argument before it is placed in the AST
+fn validate_identifier(
+    slot: &str,
+    kind: SlotKind,
+    identifier: &str,
+    config: Option<&GraphConfig>,
+) -> Result<()> {
+    // Same grammar as the parser's identifiers
+    if identifier.is_empty() || !identifier.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(template_error(format!(
+            "Argument for slot ${} is not a valid {} name: '{}'",
+            slot,
+            kind.describe(),
+            identifier
+        )));
+    }
+    let known = match (kind, config) {
+        (SlotKind::Label, Some(config)) => config.get_node_mapping(identifier).is_some(),
+        (SlotKind::RelationshipType, Some(config)) => {
+            config.get_relationship_mapping(identifier).is_some()
+        }
+        _ => true,
+    };
+    if !known {
+        return Err(template_error(format!(
+            "Argument for slot ${} names unknown {} '{}'",
+            slot,
+            kind.describe(),
+            identifier
+        )));
+    }
+    Ok(())
+}
+
+/// Literal for a scalar argument
+fn literal(slot: &str, value: &serde_json::Value) -> Result<PropertyValue> {
+    PropertyValue::from_json(value).ok_or_else(|| {
+        template_error(format!(
+            "Argument for slot ${} must be a scalar, got {}",
+            slot, value
+        ))
+    })
+}
+
+/// Expression for a value argument: a literal, or a vector for numeric arrays
+fn value_expression(slot: &str, value: &serde_json::Value) -> Result<ValueExpression> {
+    if let serde_json::Value::Array(items) = value {
+        let vector: Option<Vec<f32>> = items
+            .iter()
+            .map(|item| item.as_f64().map(|f| f as f32))
+            .collect();
+        return vector.map(ValueExpression::VectorLiteral).ok_or_else(|| {
+            template_error(format!(
+                "Argument for slot ${} must be a scalar or a numeric array, got {}",
+                slot, value
+            ))
+        });
+    }
+    literal(slot, value).map(ValueExpression::Literal)
+}
+
+fn template_error(message: String) -> GraphError {
+    GraphError::PlanError {
+        message,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+type Visit<'f> = dyn FnMut(SlotSite<'_>) -> Result<()> + 'f;
+
+/// Call `f` on every slot of `ast`
+fn visit_slots(ast: &mut CypherAST, f: &mut Visit<'_>) -> Result<()> {
+    for clause in ast
+        .reading_clauses
+        .iter_mut()
+        .chain(ast.post_with_reading_clauses.iter_mut())
+    {
+        match clause {
+            ReadingClause::Match(match_clause) => {
+                for pattern in &mut match_clause.patterns {
+                    visit_pattern(pattern, f)?;
+                }
+                if let Some(where_clause) = &mut match_clause.where_clause {
+                    visit_boolean(&mut where_clause.expression, f)?;
+                }
+            }
+            ReadingClause::Unwind(unwind) => visit_value(&mut unwind.expression, f)?,
+            ReadingClause::TableFunction(call) => {
+                for argument in &mut call.arguments {
+                    visit_value(argument, f)?;
+                }
+            }
+        }
+    }
+    for where_clause in [&mut ast.where_clause, &mut ast.post_with_where_clause]
+        .into_iter()
+        .flatten()
+    {
+        visit_boolean(&mut where_clause.expression, f)?;
+    }
+    if let Some(with_clause) = &mut ast.with_clause {
+        for item in &mut with_clause.items {
+            visit_value(&mut item.expression, f)?;
+        }
+        for item in with_clause.order_by.iter_mut().flat_map(|o| &mut o.items) {
+            visit_value(&mut item.expression, f)?;
+        }
+    }
+    if let Some(SampleMethod::RowsPer { by, .. }) = &mut ast.sample {
+        visit_value(by, f)?;
+    }
+    for item in &mut ast.return_clause.items {
+        visit_value(&mut item.expression, f)?;
+    }
+    for item in ast.order_by.iter_mut().flat_map(|o| &mut o.items) {
+        visit_value(&mut item.expression, f)?;
+    }
+    Ok(())
+}
+
+fn visit_pattern(pattern: &mut GraphPattern, f: &mut Visit<'_>) -> Result<()> {
+    match pattern {
+        GraphPattern::Node(node) => visit_node(node, f),
+        GraphPattern::Path(path) => {
+            visit_node(&mut path.start_node, f)?;
+            for segment in &mut path.segments {
+                visit_relationship(&mut segment.relationship, f)?;
+                visit_node(&mut segment.end_node, f)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn visit_node(node: &mut NodePattern, f: &mut Visit<'_>) -> Result<()> {
+    for label in &mut node.labels {
+        if label.starts_with('$') {
+            f(SlotSite::Name(label, SlotKind::Label))?;
+        }
+    }
+    for value in node.properties.values_mut() {
+        visit_property_value(value, f)?;
+    }
+    Ok(())
+}
+
+fn visit_relationship(rel: &mut RelationshipPattern, f: &mut Visit<'_>) -> Result<()> {
+    for rel_type in &mut rel.types {
+        if rel_type.starts_with('$') {
+            f(SlotSite::Name(rel_type, SlotKind::RelationshipType))?;
+        }
+    }
+    for value in rel.properties.values_mut() {
+        visit_property_value(value, f)?;
+    }
+    Ok(())
+}
+
+fn visit_property_value(value: &mut PropertyValue, f: &mut Visit<'_>) -> Result<()> {
+    match value {
+        PropertyValue::Parameter(_) => f(SlotSite::PatternValue(value)),
+        PropertyValue::Property(property) if property.property.starts_with('$') => {
+            f(SlotSite::Property(&mut property.property))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn visit_boolean(expr: &mut BooleanExpression, f: &mut Visit<'_>) -> Result<()> {
+    use BooleanExpression as BE;
+    match expr {
+        BE::Comparison { left, right, .. } => {
+            visit_value(left, f)?;
+            visit_value(right, f)
+        }
+        BE::And(left, right) | BE::Or(left, right) => {
+            visit_boolean(left, f)?;
+            visit_boolean(right, f)
+        }
+        BE::Not(inner) => visit_boolean(inner, f),
+        BE::Exists(property) => {
+            if property.property.starts_with('$') {
+                f(SlotSite::Property(&mut property.property))?;
+            }
+            Ok(())
+        }
+        BE::In { expression, list } => {
+            visit_value(expression, f)?;
+            if matches!(list.as_slice(), [ValueExpression::Parameter(_)]) {
+                return f(SlotSite::List(list));
+            }
+            for item in list {
+                visit_value(item, f)?;
+            }
+            Ok(())
+        }
+        BE::Like { expression, .. }
+        | BE::ILike { expression, .. }
+        | BE::Contains { expression, .. }
+        | BE::StartsWith { expression, .. }
+        | BE::EndsWith { expression, .. }
+        | BE::RegexMatch { expression, .. }
+        | BE::IsNull(expression)
+        | BE::IsNotNull(expression) => visit_value(expression, f),
+        BE::AllInPath { predicate, .. } => visit_boolean(predicate, f),
+        BE::ListPredicate {
+            list, predicate, ..
+        } => {
+            visit_value(list, f)?;
+            visit_boolean(predicate, f)
+        }
+        BE::ExistsSubquery {
+            patterns,
+            where_clause,
+        } => {
+            for pattern in patterns {
+                visit_pattern(pattern, f)?;
+            }
+            match where_clause {
+                Some(predicate) => visit_boolean(predicate, f),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+fn visit_value(expr: &mut ValueExpression, f: &mut Visit<'_>) -> Result<()> {
+    use ValueExpression as VE;
+    match expr {
+        VE::Parameter(_) => f(SlotSite::Value(expr)),
+        VE::Property(property) => {
+            if property.property.starts_with('$') {
+                f(SlotSite::Property(&mut property.property))?;
+            }
+            Ok(())
+        }
+        VE::Literal(value) => visit_property_value(value, f),
+        VE::ScalarFunction { args, .. } | VE::AggregateFunction { args, .. } => {
+            for arg in args {
+                visit_value(arg, f)?;
+            }
+            Ok(())
+        }
+        VE::Arithmetic { left, right, .. }
+        | VE::VectorDistance { left, right, .. }
+        | VE::VectorSimilarity { left, right, .. } => {
+            visit_value(left, f)?;
+            visit_value(right, f)
+        }
+        VE::Case { branches, default } => {
+            for branch in branches {
+                visit_boolean(&mut branch.condition, f)?;
+                visit_value(&mut branch.value, f)?;
+            }
+            match default {
+                Some(default) => visit_value(default, f),
+                None => Ok(()),
+            }
+        }
+        VE::ListComprehension {
+            list,
+            predicate,
+            projection,
+            ..
+        } => {
+            visit_value(list, f)?;
+            if let Some(predicate) = predicate {
+                visit_boolean(predicate, f)?;
+            }
+            match projection {
+                Some(projection) => visit_value(projection, f),
+                None => Ok(()),
+            }
+        }
+        VE::Reduce {
+            initial,
+            list,
+            expression,
+            ..
+        } => {
+            visit_value(initial, f)?;
+            visit_value(list, f)?;
+            visit_value(expression, f)
+        }
+        VE::PatternComprehension {
+            pattern,
+            predicate,
+            projection,
+        } => {
+            visit_node(&mut pattern.start_node, f)?;
+            for segment in &mut pattern.segments {
+                visit_relationship(&mut segment.relationship, f)?;
+                visit_node(&mut segment.end_node, f)?;
+            }
+            if let Some(predicate) = predicate {
+                visit_boolean(predicate, f)?;
+            }
+            visit_value(projection, f)
+        }
+        VE::MapProjection { items, .. } => {
+            for item in items {
+                visit_value(&mut item.value, f)?;
+            }
+            Ok(())
+        }
+        VE::CountSubquery {
+            patterns,
+            where_clause,
+        } => {
+            for pattern in patterns {
+                visit_pattern(pattern, f)?;
+            }
+            match where_clause {
+                Some(predicate) => visit_boolean(predicate, f),
+                None => Ok(()),
+            }
+        }
+        VE::Variable(_) | VE::VectorLiteral(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{ComparisonOperator, PropertyRef};
+
+    fn template() -> QueryTemplate {
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap();
+        QueryTemplate::new("MATCH (n:$label) WHERE n.$prop >= $min RETURN n.name")
+            .unwrap()
+            .with_config(config)
+    }
+
+    #[test]
+    fn test_slots_are_typed_by_position() {
+        let slots = template().slots().clone();
+        assert_eq!(slots["label"], SlotKind::Label);
+        assert_eq!(slots["prop"], SlotKind::Property);
+        assert_eq!(slots["min"], SlotKind::Value);
+    }
+
+    #[test]
+    fn test_render_substitutes_into_ast() {
+        let query = template()
+            .render(
+                &TemplateArgs::new()
+                    .identifier("label", "Person")
+                    .identifier("prop", "age")
+                    .value("min", "30') OR true OR ('"),
+            )
+            .unwrap();
+        let ast = query.ast();
+        assert_eq!(ast.get_node_labels(), vec!["Person".to_string()]);
+        // The hostile string stays a single literal
+        assert_eq!(
+            ast.where_clause.as_ref().unwrap().expression,
+            BooleanExpression::Comparison {
+                left: ValueExpression::Property(PropertyRef {
+                    variable: "n".to_string(),
+                    property: "age".to_string(),
+                }),
+                operator: ComparisonOperator::GreaterThanOrEqual,
+                right: ValueExpression::Literal(PropertyValue::String(
+                    "30') OR true OR ('".to_string()
+                )),
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_rejects_bad_identifiers() {
+        let args = |label: &str, prop: &str| {
+            TemplateArgs::new()
+                .identifier("label", label)
+                .identifier("prop", prop)
+                .value("min", 30)
+        };
+        let err = template().render(&args("Company", "age")).unwrap_err();
+        assert!(err.to_string().contains("unknown label"), "{}", err);
+        let err = template()
+            .render(&args("Person", "age) RETURN n //"))
+            .unwrap_err();
+        assert!(err.to_string().contains("not a valid property"), "{}", err);
+        let err = template()
+            .render(&TemplateArgs::new().identifier("label", "Person"))
+            .unwrap_err();
+        assert!(err.to_string().contains("needs a"), "{}", err);
+    }
+
+    #[test]
+    fn test_list_argument_expands_in_list() {
+        let query = QueryTemplate::new("MATCH (n:Person) WHERE n.id IN $ids RETURN n.name")
+            .unwrap()
+            .render(&TemplateArgs::new().value("ids", serde_json::json!([1, 2, 3])))
+            .unwrap();
+        match &query.ast().where_clause.as_ref().unwrap().expression {
+            BooleanExpression::In { list, .. } => assert_eq!(list.len(), 3),
+            other => panic!("Expected IN expression, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,636 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Dates, datetimes and durations
+//!
+//! Temporal values are Arrow temporal types, so they compare and compute
+//! natively with date and timestamp columns:
+//!
+//! | Cypher     | Arrow                               |
+//! |------------|-------------------------------------|
+//! | `date`     | `Date32`                            |
+//! | `datetime` | `Timestamp(Nanosecond, "+00:00")`   |
+//! | `duration` | `Interval(MonthDayNano)`            |
+//!
+//! - `date()` and `datetime()` are today and now, fixed for the whole query;
+//!   `date(x)` and `datetime(x)` convert an ISO 8601 string (`'2024-03-01'`,
+//!   `'2024-03-01T12:30:00+02:00'`) or another temporal value. Datetimes
+//!   without an offset are UTC.
+//! - `duration('P1Y2M3DT4H5M6.5S')` is an ISO 8601 duration, and
+//!   `duration.between(a, b)` the time from `a` to `b` (in nanoseconds, so
+//!   compare it with durations of hours, minutes and seconds).
+//! - `date.truncate(unit, x)` and `datetime.truncate(unit, x)` round down to
+//!   the start of a year, quarter, month, week, day, hour, minute, second,
+//!   millisecond or microsecond, for bucketing.
+//! - Components are read as properties: `e.at.year`, or `at.hour` for a
+//!   temporal value bound by UNWIND, YIELD or WITH.
+//!
+//! ```text
+//! MATCH (e:Event)
+//! WHERE e.at >= datetime($since) AND e.at < datetime($since) + duration('P7D')
+//! RETURN date.truncate('day', e.at) AS day, count(*) AS events
+//! ```
+//!
+//! Arguments of the temporal functions may be parameters, which are
+//! substituted before the query is analyzed.
+
+use crate::ast::{
+    BooleanExpression, CypherQuery as CypherAST, GraphPattern, PropertyRef, PropertyValue,
+    ReadingClause, SampleMethod, ValueExpression,
+};
+use crate::datafusion_planner::expression::to_df_value_expr;
+use crate::error::{GraphError, Result};
+use arrow::datatypes::{DataType, IntervalMonthDayNano, IntervalUnit, TimeUnit};
+use datafusion::functions::datetime::expr_fn::{current_date, date_part, date_trunc, now};
+use datafusion::logical_expr::{cast, lit, Expr};
+use datafusion::scalar::ScalarValue;
+use std::collections::{HashMap, HashSet};
+
+/// Name under which component accessors (`x.year`) are planned, as
+/// `temporal.component(x, 'year')`
+pub(crate) const COMPONENT_FUNCTION: &str = "temporal.component";
+
+/// The temporal functions, lowercase
+const FUNCTIONS: &[&str] = &[
+    "date",
+    "datetime",
+    "duration",
+    "duration.between",
+    "date.truncate",
+    "datetime.truncate",
+    COMPONENT_FUNCTION,
+];
+
+/// Components readable from dates and datetimes, lowercase
+const COMPONENTS: &[&str] = &[
+    "year",
+    "quarter",
+    "month",
+    "week",
+    "day",
+    "dayofweek",
+    "ordinalday",
+    "hour",
+    "minute",
+    "second",
+    "millisecond",
+    "microsecond",
+    "nanosecond",
+    "epochseconds",
+    "epochmillis",
+];
+
+/// Units `date.truncate` and `datetime.truncate` round down to
+const TRUNCATION_UNITS: &[&str] = &[
+    "year",
+    "quarter",
+    "month",
+    "week",
+    "day",
+    "hour",
+    "minute",
+    "second",
+    "millisecond",
+    "microsecond",
+];
+
+/// Arrow type of datetimes
+pub(crate) fn datetime_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into()))
+}
+
+/// Arrow type of durations
+pub(crate) fn duration_type() -> DataType {
+    DataType::Interval(IntervalUnit::MonthDayNano)
+}
+
+/// Whether `name` (lowercase) is a temporal function
+pub(crate) fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+/// Arrow type and nullability of a call of the temporal function `name`
+/// (lowercase), given whether any argument is nullable
+pub(crate) fn result_type(name: &str, nullable: bool) -> (DataType, bool) {
+    let data_type = match name {
+        "date" | "date.truncate" => DataType::Date32,
+        "datetime" | "datetime.truncate" => datetime_type(),
+        "duration" | "duration.between" => duration_type(),
+        _ => DataType::Int64,
+    };
+    (data_type, nullable)
+}
+
+/// Check the arguments of a call of the temporal function `name` (lowercase)
+pub(crate) fn check_call(name: &str, args: &[ValueExpression]) -> Result<()> {
+    use ValueExpression as VE;
+    match (name, args) {
+        ("date" | "datetime", []) => Ok(()),
+        ("date" | "datetime", [VE::Literal(PropertyValue::String(text))]) => {
+            let data_type = if name == "date" {
+                DataType::Date32
+            } else {
+                datetime_type()
+            };
+            ScalarValue::Utf8(Some(text.clone()))
+                .cast_to(&data_type)
+                .map(|_| ())
+                .map_err(|_| temporal_error(format!("Invalid ISO 8601 {} '{}'", name, text)))
+        }
+        ("date" | "datetime", [VE::Literal(PropertyValue::Null)]) => Ok(()),
+        ("date" | "datetime", [VE::Literal(value)]) => Err(temporal_error(format!(
+            "{} takes an ISO 8601 string or a temporal value, got {:?}",
+            name.to_uppercase(),
+            value
+        ))),
+        ("date" | "datetime", [_]) => Ok(()),
+        ("duration", [VE::Literal(PropertyValue::String(text))]) => parse_duration(text)
+            .map(|_| ())
+            .ok_or_else(|| temporal_error(format!("Invalid ISO 8601 duration '{}'", text))),
+        ("duration", [VE::Parameter(_)]) => Ok(()),
+        ("duration", _) => Err(temporal_error(
+            "DURATION takes an ISO 8601 duration string, e.g. duration('P1DT2H')".to_string(),
+        )),
+        ("duration.between", [_, _]) => Ok(()),
+        ("date.truncate" | "datetime.truncate", [VE::Literal(PropertyValue::String(unit)), _]) => {
+            check_listed("truncation unit", unit, TRUNCATION_UNITS)
+        }
+        (COMPONENT_FUNCTION, [_, VE::Literal(PropertyValue::String(component))]) => {
+            check_listed("temporal component", component, COMPONENTS)
+        }
+        ("date.truncate" | "datetime.truncate", _) => Err(temporal_error(format!(
+            "{} takes a unit string and a temporal value, e.g. {}('month', e.at)",
+            name.to_uppercase(),
+            name
+        ))),
+        _ => Err(temporal_error(format!(
+            "{} got {} arguments",
+            name.to_uppercase(),
+            args.len()
+        ))),
+    }
+}
+
+fn check_listed(kind: &str, value: &str, supported: &[&str]) -> Result<()> {
+    if supported.contains(&value.to_lowercase().as_str()) {
+        return Ok(());
+    }
+    Err(temporal_error(format!(
+        "Unsupported {} '{}'; supported: {}",
+        kind,
+        value,
+        supported.join(", ")
+    )))
+}
+
+/// DataFusion expression of a call of the temporal function `name`
+/// (lowercase), checked by [`check_call`]
+pub(crate) fn to_df_expr(name: &str, args: &[ValueExpression]) -> Expr {
+    use ValueExpression as VE;
+    let null = || Expr::Literal(ScalarValue::Null, None);
+    match (name, args) {
+        ("date", []) => current_date(),
+        ("date", [value]) => cast(to_df_value_expr(value), DataType::Date32),
+        ("datetime", []) => now(),
+        ("datetime", [value]) => cast(to_df_value_expr(value), datetime_type()),
+        ("duration", [VE::Literal(PropertyValue::String(text))]) => match parse_duration(text) {
+            Some(duration) => lit(ScalarValue::IntervalMonthDayNano(Some(duration))),
+            None => null(),
+        },
+        ("duration.between", [from, to]) => cast(
+            cast(to_df_value_expr(to), datetime_type())
+                - cast(to_df_value_expr(from), datetime_type()),
+            duration_type(),
+        ),
+        (
+            "date.truncate" | "datetime.truncate",
+            [VE::Literal(PropertyValue::String(unit)), value],
+        ) => {
+            let truncated = date_trunc(
+                lit(unit.to_lowercase()),
+                cast(to_df_value_expr(value), datetime_type()),
+            );
+            if name == "date.truncate" {
+                cast(truncated, DataType::Date32)
+            } else {
+                truncated
+            }
+        }
+        (COMPONENT_FUNCTION, [value, VE::Literal(PropertyValue::String(component))]) => {
+            component_expr(to_df_value_expr(value), &component.to_lowercase())
+        }
+        _ => null(),
+    }
+}
+
+/// `component` of the temporal `value`, as Cypher numbers it
+fn component_expr(value: Expr, component: &str) -> Expr {
+    let part = |part: &str| cast(date_part(lit(part), value.clone()), DataType::Int64);
+    match component {
+        // Monday is 1 in Cypher, Sunday 0 in date_part
+        "dayofweek" => (part("dow") + lit(6i64)) % lit(7i64) + lit(1i64),
+        "ordinalday" => part("doy"),
+        // date_part counts sub-second units from the start of the minute,
+        // Cypher from the start of the second
+        "millisecond" => part("millisecond") % lit(1_000i64),
+        "microsecond" => part("microsecond") % lit(1_000_000i64),
+        "nanosecond" => part("nanosecond") % lit(1_000_000_000i64),
+        "epochseconds" => part("epoch"),
+        "epochmillis" => cast(
+            date_part(lit("epoch"), value.clone()) * lit(1_000.0),
+            DataType::Int64,
+        ),
+        other => part(other),
+    }
+}
+
+/// Parse an ISO 8601 duration such as `P1Y2M10DT2H30M15.5S` or `P2W`
+pub(crate) fn parse_duration(text: &str) -> Option<IntervalMonthDayNano> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let body = text.strip_prefix('P').or_else(|| text.strip_prefix('p'))?;
+    let (date_part, time_part) = match body.find(['T', 't']) {
+        Some(split) => (&body[..split], Some(&body[split + 1..])),
+        None => (body, None),
+    };
+    if body.is_empty() || time_part == Some("") {
+        return None;
+    }
+
+    let (mut months, mut days, mut nanos) = (0i64, 0i64, 0i128);
+    for (amount, designator) in designated(date_part)? {
+        if amount.fract() != 0.0 {
+            return None;
+        }
+        let amount = amount as i64;
+        match designator {
+            'Y' => months += amount * 12,
+            'M' => months += amount,
+            'W' => days += amount * 7,
+            'D' => days += amount,
+            _ => return None,
+        }
+    }
+    for (amount, designator) in designated(time_part.unwrap_or_default())? {
+        let unit: f64 = match designator {
+            'H' => 3_600e9,
+            'M' => 60e9,
+            'S' => 1e9,
+            _ => return None,
+        };
+        nanos += (amount * unit).round() as i128;
+    }
+
+    let sign = if negative { -1 } else { 1 };
+    Some(IntervalMonthDayNano::new(
+        i32::try_from(sign * months).ok()?,
+        i32::try_from(sign * days).ok()?,
+        i64::try_from(sign as i128 * nanos).ok()?,
+    ))
+}
+
+/// The `<number><designator>` pairs of one part of a duration
+fn designated(part: &str) -> Option<Vec<(f64, char)>> {
+    let mut pairs = Vec::new();
+    let mut number = String::new();
+    for c in part.chars() {
+        if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+            number.push(c);
+        } else {
+            pairs.push((number.parse().ok()?, c.to_ascii_uppercase()));
+            number.clear();
+        }
+    }
+    number.is_empty().then_some(pairs)
+}
+
+fn temporal_error(message: String) -> GraphError {
+    GraphError::PlanError {
+        message,
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+/// Rewrite component accessors of temporal values bound by UNWIND, YIELD or
+/// WITH (`at.year`) into calls of [`COMPONENT_FUNCTION`]
+///
+/// The parser can only tell `e.at.year` is an accessor; `at.year` reads a
+/// property unless `at` is a value rather than a node or relationship.
+pub(crate) fn resolve_accessors(ast: &mut CypherAST) {
+    let values = value_variables(ast);
+    if values.is_empty() {
+        return;
+    }
+    for_each_value_mut(ast, &mut |expr| {
+        if let ValueExpression::Property(PropertyRef { variable, property }) = expr {
+            if values.contains(&variable.to_lowercase())
+                && COMPONENTS.contains(&property.to_lowercase().as_str())
+            {
+                let value = ValueExpression::Variable(variable.clone());
+                let component = property.clone();
+                *expr = accessor(value, &component);
+            }
+        }
+        Ok(())
+    })
+    .expect("rewriting accessors cannot fail");
+}
+
+/// `value.component` as a call of [`COMPONENT_FUNCTION`]
+pub(crate) fn accessor(value: ValueExpression, component: &str) -> ValueExpression {
+    ValueExpression::ScalarFunction {
+        name: COMPONENT_FUNCTION.to_string(),
+        args: vec![
+            value,
+            ValueExpression::Literal(PropertyValue::String(component.to_string())),
+        ],
+    }
+}
+
+/// Variables of `ast` bound to values rather than to graph elements,
+/// lowercase
+fn value_variables(ast: &CypherAST) -> HashSet<String> {
+    let mut values = HashSet::new();
+    let mut elements = HashSet::new();
+    for clause in ast
+        .reading_clauses
+        .iter()
+        .chain(&ast.post_with_reading_clauses)
+    {
+        match clause {
+            ReadingClause::Match(match_clause) => {
+                for pattern in &match_clause.patterns {
+                    let mut nodes = vec![];
+                    match pattern {
+                        GraphPattern::Node(node) => nodes.push(&node.variable),
+                        GraphPattern::Path(path) => {
+                            nodes.push(&path.start_node.variable);
+                            for segment in &path.segments {
+                                nodes.push(&segment.relationship.variable);
+                                nodes.push(&segment.end_node.variable);
+                            }
+                        }
+                    }
+                    elements.extend(nodes.into_iter().flatten().map(|v| v.to_lowercase()));
+                }
+            }
+            ReadingClause::Unwind(unwind) => {
+                values.insert(unwind.alias.to_lowercase());
+            }
+            ReadingClause::TableFunction(call) => {
+                values.extend(
+                    call.yields
+                        .iter()
+                        .map(|item| item.variable().to_lowercase()),
+                );
+            }
+        }
+    }
+    for item in ast.with_clause.iter().flat_map(|with| &with.items) {
+        let Some(alias) = &item.alias else {
+            continue;
+        };
+        let is_value = match &item.expression {
+            ValueExpression::Variable(v) => values.contains(&v.to_lowercase()),
+            _ => true,
+        };
+        if is_value {
+            values.insert(alias.to_lowercase());
+        }
+    }
+    values.retain(|v| !elements.contains(v));
+    values
+}
+
+/// Substitute parameters passed to temporal functions (`datetime($since)`)
+/// with their values
+pub(crate) fn bind_parameters(
+    ast: &mut CypherAST,
+    parameters: &HashMap<String, serde_json::Value>,
+) -> Result<()> {
+    for_each_value_mut(ast, &mut |expr| {
+        let ValueExpression::ScalarFunction { name, args } = expr else {
+            return Ok(());
+        };
+        if !is_function(&name.to_lowercase()) {
+            return Ok(());
+        }
+        for arg in args {
+            let ValueExpression::Parameter(parameter) = arg else {
+                continue;
+            };
+            let value = parameters
+                .get(parameter.as_str())
+                .and_then(PropertyValue::from_json)
+                .ok_or_else(|| {
+                    temporal_error(format!(
+                        "Missing or non-scalar value for parameter ${} of {}",
+                        parameter, name
+                    ))
+                })?;
+            *arg = ValueExpression::Literal(value);
+        }
+        Ok(())
+    })
+}
+
+pub(crate) type Visit<'f> = dyn FnMut(&mut ValueExpression) -> Result<()> + 'f;
+
+/// Call `f` on every value expression of `ast`, innermost first
+pub(crate) fn for_each_value_mut(ast: &mut CypherAST, f: &mut Visit<'_>) -> Result<()> {
+    for clause in ast
+        .reading_clauses
+        .iter_mut()
+        .chain(ast.post_with_reading_clauses.iter_mut())
+    {
+        match clause {
+            ReadingClause::Match(match_clause) => {
+                if let Some(where_clause) = &mut match_clause.where_clause {
+                    visit_boolean(&mut where_clause.expression, f)?;
+                }
+            }
+            ReadingClause::Unwind(unwind) => visit_value(&mut unwind.expression, f)?,
+            ReadingClause::TableFunction(call) => {
+                for argument in &mut call.arguments {
+                    visit_value(argument, f)?;
+                }
+            }
+        }
+    }
+    for where_clause in [&mut ast.where_clause, &mut ast.post_with_where_clause]
+        .into_iter()
+        .flatten()
+    {
+        visit_boolean(&mut where_clause.expression, f)?;
+    }
+    if let Some(with_clause) = &mut ast.with_clause {
+        for item in &mut with_clause.items {
+            visit_value(&mut item.expression, f)?;
+        }
+        for item in with_clause.order_by.iter_mut().flat_map(|o| &mut o.items) {
+            visit_value(&mut item.expression, f)?;
+        }
+    }
+    if let Some(SampleMethod::RowsPer { by, .. }) = &mut ast.sample {
+        visit_value(by, f)?;
+    }
+    for expr in &mut ast.return_clause.distinct_on {
+        visit_value(expr, f)?;
+    }
+    for item in &mut ast.return_clause.items {
+        visit_value(&mut item.expression, f)?;
+    }
+    for item in ast.order_by.iter_mut().flat_map(|o| &mut o.items) {
+        visit_value(&mut item.expression, f)?;
+    }
+    Ok(())
+}
+
+fn visit_boolean(expr: &mut BooleanExpression, f: &mut Visit<'_>) -> Result<()> {
+    use BooleanExpression as BE;
+    match expr {
+        BE::Comparison { left, right, .. } => {
+            visit_value(left, f)?;
+            visit_value(right, f)
+        }
+        BE::And(left, right) | BE::Or(left, right) => {
+            visit_boolean(left, f)?;
+            visit_boolean(right, f)
+        }
+        BE::Not(inner) => visit_boolean(inner, f),
+        BE::In { expression, list } => {
+            visit_value(expression, f)?;
+            for item in list {
+                visit_value(item, f)?;
+            }
+            Ok(())
+        }
+        BE::Like { expression, .. }
+        | BE::ILike { expression, .. }
+        | BE::Contains { expression, .. }
+        | BE::StartsWith { expression, .. }
+        | BE::EndsWith { expression, .. }
+        | BE::RegexMatch { expression, .. }
+        | BE::IsNull(expression)
+        | BE::IsNotNull(expression) => visit_value(expression, f),
+        BE::AllInPath { predicate, .. } => visit_boolean(predicate, f),
+        BE::ListPredicate {
+            list, predicate, ..
+        } => {
+            visit_value(list, f)?;
+            visit_boolean(predicate, f)
+        }
+        BE::ExistsSubquery {
+            where_clause: Some(predicate),
+            ..
+        } => visit_boolean(predicate, f),
+        BE::Exists(_) | BE::ExistsSubquery { .. } => Ok(()),
+    }
+}
+
+pub(crate) fn visit_value(expr: &mut ValueExpression, f: &mut Visit<'_>) -> Result<()> {
+    use ValueExpression as VE;
+    match expr {
+        VE::ScalarFunction { args, .. } | VE::AggregateFunction { args, .. } => {
+            for arg in args {
+                visit_value(arg, f)?;
+            }
+        }
+        VE::Arithmetic { left, right, .. }
+        | VE::VectorDistance { left, right, .. }
+        | VE::VectorSimilarity { left, right, .. } => {
+            visit_value(left, f)?;
+            visit_value(right, f)?;
+        }
+        VE::Case { branches, default } => {
+            for branch in branches {
+                visit_boolean(&mut branch.condition, f)?;
+                visit_value(&mut branch.value, f)?;
+            }
+            if let Some(default) = default {
+                visit_value(default, f)?;
+            }
+        }
+        VE::ListComprehension {
+            list,
+            predicate,
+            projection,
+            ..
+        } => {
+            visit_value(list, f)?;
+            if let Some(predicate) = predicate {
+                visit_boolean(predicate, f)?;
+            }
+            if let Some(projection) = projection {
+                visit_value(projection, f)?;
+            }
+        }
+        VE::Reduce {
+            initial,
+            list,
+            expression,
+            ..
+        } => {
+            visit_value(initial, f)?;
+            visit_value(list, f)?;
+            visit_value(expression, f)?;
+        }
+        VE::PatternComprehension {
+            predicate,
+            projection,
+            ..
+        } => {
+            if let Some(predicate) = predicate {
+                visit_boolean(predicate, f)?;
+            }
+            visit_value(projection, f)?;
+        }
+        VE::MapProjection { items, .. } => {
+            for item in items {
+                visit_value(&mut item.value, f)?;
+            }
+        }
+        VE::CountSubquery {
+            where_clause: Some(predicate),
+            ..
+        } => visit_boolean(predicate, f)?,
+        VE::Property(_)
+        | VE::Variable(_)
+        | VE::Literal(_)
+        | VE::Parameter(_)
+        | VE::VectorLiteral(_)
+        | VE::CountSubquery { .. } => {}
+    }
+    f(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        let hour = 3_600_000_000_000i64;
+        assert_eq!(
+            parse_duration("P1Y2M10DT2H30M"),
+            Some(IntervalMonthDayNano::new(14, 10, 2 * hour + hour / 2))
+        );
+        assert_eq!(
+            parse_duration("PT1.5S"),
+            Some(IntervalMonthDayNano::new(0, 0, 1_500_000_000))
+        );
+        assert_eq!(
+            parse_duration("P2W"),
+            Some(IntervalMonthDayNano::new(0, 14, 0))
+        );
+        assert_eq!(
+            parse_duration("-P1D"),
+            Some(IntervalMonthDayNano::new(0, -1, 0))
+        );
+        for invalid in ["", "P", "PT", "1D", "P1H", "PT1D", "P1.5D", "P1DT"] {
+            assert_eq!(parse_duration(invalid), None, "{}", invalid);
+        }
+    }
+}
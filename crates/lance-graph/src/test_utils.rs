@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Deterministic synthetic graphs for integration tests
+//!
+//! Enabled with the `test_utils` feature. A [`SyntheticGraph`] describes node
+//! labels, relationship types with their degree distribution and optional
+//! embedding columns; the same description and seed always produce the same
+//! rows, so tests can assert on exact results without shipping fixtures.
+//!
+//! Node tables have the columns `id` (Int64), `name` (Utf8), `bucket` (Int64,
+//! `id % 10`), `score` (Float64 in `[0, 1)`) and, when an embedding dimension
+//! is set, `embedding` (FixedSizeList<Float32> of unit length). Relationship
+//! tables have `src_id`, `dst_id` (Int64) and `weight` (Float64 in `[0, 1)`).
+//!
+//! [`SyntheticGraph::write_lance`] lays the datasets out the way
+//! [`crate::DirNamespace`] expects them:
+//!
+//! ```ignore
+//! use lance_graph::test_utils::{DegreeDistribution, SyntheticGraph};
+//! use lance_graph::{CypherQuery, DirNamespace};
+//!
+//! let graph = SyntheticGraph::new()
+//!     .with_seed(7)
+//!     .with_node_label("Person", 1_000)
+//!     .with_embedding_dim("Person", 32)
+//!     .with_relationship("KNOWS", "Person", "Person", DegreeDistribution::power_law(1, 50, 2.1));
+//! let config = graph.write_lance(dir.path().to_str().unwrap()).await?;
+//!
+//! let query = CypherQuery::new("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN count(*)")?
+//!     .with_config(config);
+//! let result = query
+//!     .execute_with_namespace(DirNamespace::new(dir.path().to_string_lossy()), None)
+//!     .await?;
+//! ```
+
+use crate::config::GraphConfig;
+use crate::error::{GraphError, Result};
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder};
+use arrow_array::{
+    ArrayRef, Float64Array, Int64Array, RecordBatch, RecordBatchIterator, StringArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Node key column of every generated node table
+pub const ID_COLUMN: &str = "id";
+/// Source key column of every generated relationship table
+pub const SOURCE_COLUMN: &str = "src_id";
+/// Target key column of every generated relationship table
+pub const TARGET_COLUMN: &str = "dst_id";
+/// Embedding column of node tables with an embedding dimension
+pub const EMBEDDING_COLUMN: &str = "embedding";
+
+/// Number of outgoing relationships generated for each source node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DegreeDistribution {
+    /// Every source node has exactly this many relationships
+    Constant(usize),
+    /// Degrees drawn uniformly from `min..=max`
+    Uniform { min: usize, max: usize },
+    /// Degrees drawn from a power law bounded to `min..=max`; larger
+    /// exponents make high degrees rarer
+    PowerLaw {
+        min: usize,
+        max: usize,
+        exponent: f64,
+    },
+}
+
+impl DegreeDistribution {
+    pub fn uniform(min: usize, max: usize) -> Self {
+        Self::Uniform { min, max }
+    }
+
+    pub fn power_law(min: usize, max: usize, exponent: f64) -> Self {
+        Self::PowerLaw { min, max, exponent }
+    }
+
+    fn validate(&self, rel_type: &str) -> Result<()> {
+        let valid = match *self {
+            Self::Constant(_) => true,
+            Self::Uniform { min, max } => min <= max,
+            Self::PowerLaw { min, max, exponent } => {
+                min >= 1 && min <= max && exponent.is_finite() && exponent > 1.0
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(GraphError::ConfigError {
+                message: format!("Invalid degree distribution for '{}': {:?}", rel_type, self),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+        }
+    }
+
+    fn sample(&self, rng: &mut SplitMix64) -> usize {
+        match *self {
+            Self::Constant(degree) => degree,
+            Self::Uniform { min, max } => min + rng.below((max - min + 1) as u64) as usize,
+            Self::PowerLaw { min, max, exponent } => {
+                // Inverse CDF of a Pareto distribution bounded to [min, max + 1)
+                let one_minus = 1.0 - exponent;
+                let low = (min as f64).powf(one_minus);
+                let high = ((max + 1) as f64).powf(one_minus);
+                let x = (low + rng.next_f64() * (high - low)).powf(1.0 / one_minus);
+                (x.floor() as usize).clamp(min, max)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NodeSpec {
+    label: String,
+    count: usize,
+    embedding_dim: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct RelationshipSpec {
+    rel_type: String,
+    source_label: String,
+    target_label: String,
+    degree: DegreeDistribution,
+}
+
+/// Description of a synthetic property graph
+#[derive(Debug, Clone)]
+pub struct SyntheticGraph {
+    seed: u64,
+    nodes: Vec<NodeSpec>,
+    relationships: Vec<RelationshipSpec>,
+}
+
+impl Default for SyntheticGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntheticGraph {
+    pub fn new() -> Self {
+        Self {
+            seed: 42,
+            nodes: Vec::new(),
+            relationships: Vec::new(),
+        }
+    }
+
+    /// Seed of the generator; the same seed always yields the same graph
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Add `count` nodes labelled `label`, with ids `0..count`
+    pub fn with_node_label(mut self, label: impl Into<String>, count: usize) -> Self {
+        self.nodes.push(NodeSpec {
+            label: label.into(),
+            count,
+            embedding_dim: None,
+        });
+        self
+    }
+
+    /// Give the nodes of `label` an `embedding` column of `dim` dimensions
+    pub fn with_embedding_dim(mut self, label: &str, dim: usize) -> Self {
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.label == label) {
+            node.embedding_dim = Some(dim);
+        }
+        self
+    }
+
+    /// Add relationships of `rel_type` from every `source_label` node to
+    /// distinct random `target_label` nodes
+    pub fn with_relationship(
+        mut self,
+        rel_type: impl Into<String>,
+        source_label: impl Into<String>,
+        target_label: impl Into<String>,
+        degree: DegreeDistribution,
+    ) -> Self {
+        self.relationships.push(RelationshipSpec {
+            rel_type: rel_type.into(),
+            source_label: source_label.into(),
+            target_label: target_label.into(),
+            degree,
+        });
+        self
+    }
+
+    /// Graph configuration mapping the generated tables
+    pub fn config(&self) -> Result<GraphConfig> {
+        let mut builder = GraphConfig::builder();
+        for node in &self.nodes {
+            builder = builder.with_node_label(node.label.as_str(), ID_COLUMN);
+        }
+        for rel in &self.relationships {
+            builder =
+                builder.with_relationship(rel.rel_type.as_str(), SOURCE_COLUMN, TARGET_COLUMN);
+        }
+        builder.build()
+    }
+
+    /// Generate every table in memory, keyed by label or relationship type
+    pub fn generate(&self) -> Result<HashMap<String, RecordBatch>> {
+        let mut tables = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut rng = SplitMix64::new(self.seed, index as u64);
+            tables.insert(node.label.clone(), node_batch(node, &mut rng)?);
+        }
+        for (index, rel) in self.relationships.iter().enumerate() {
+            rel.degree.validate(&rel.rel_type)?;
+            let count = |label: &str| {
+                self.nodes
+                    .iter()
+                    .find(|n| n.label == label)
+                    .map(|n| n.count)
+                    .ok_or_else(|| GraphError::ConfigError {
+                        message: format!(
+                            "Relationship '{}' references unknown label '{}'",
+                            rel.rel_type, label
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+            };
+            let sources = count(&rel.source_label)?;
+            let targets = count(&rel.target_label)?;
+            let no_self_loops = rel.source_label == rel.target_label;
+            let mut rng = SplitMix64::new(self.seed, (self.nodes.len() + index) as u64);
+            tables.insert(
+                rel.rel_type.clone(),
+                relationship_batch(rel, sources, targets, no_self_loops, &mut rng)?,
+            );
+        }
+        Ok(tables)
+    }
+
+    /// Write every table as the Lance dataset `<base_uri>/<name>.lance`,
+    /// replacing existing ones, and return the matching configuration
+    pub async fn write_lance(&self, base_uri: &str) -> Result<GraphConfig> {
+        let config = self.config()?;
+        let base_uri = base_uri.trim_end_matches('/');
+        for (name, batch) in self.generate()? {
+            let uri = format!("{}/{}.lance", base_uri, name);
+            let schema = batch.schema();
+            let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+            Dataset::write(
+                reader,
+                &uri,
+                Some(WriteParams {
+                    mode: WriteMode::Overwrite,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(|e| GraphError::ExecutionError {
+                message: format!("Failed to write '{}': {}", uri, e),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        }
+        Ok(config)
+    }
+}
+
+fn node_batch(node: &NodeSpec, rng: &mut SplitMix64) -> Result<RecordBatch> {
+    let ids: Vec<i64> = (0..node.count as i64).collect();
+    let mut fields = vec![
+        Field::new(ID_COLUMN, DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("bucket", DataType::Int64, false),
+        Field::new("score", DataType::Float64, false),
+    ];
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from(ids.clone())),
+        Arc::new(StringArray::from(
+            ids.iter()
+                .map(|id| format!("{}_{}", node.label, id))
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            ids.iter().map(|id| id % 10).collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            (0..node.count).map(|_| rng.next_f64()).collect::<Vec<_>>(),
+        )),
+    ];
+
+    if let Some(dim) = node.embedding_dim {
+        let mut builder = FixedSizeListBuilder::new(Float32Builder::new(), dim as i32);
+        for _ in 0..node.count {
+            let vector: Vec<f32> = (0..dim)
+                .map(|_| rng.next_f64() as f32 * 2.0 - 1.0)
+                .collect();
+            let norm = vector
+                .iter()
+                .map(|v| v * v)
+                .sum::<f32>()
+                .sqrt()
+                .max(f32::EPSILON);
+            for v in vector {
+                builder.values().append_value(v / norm);
+            }
+            builder.append(true);
+        }
+        fields.push(Field::new(
+            EMBEDDING_COLUMN,
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dim as i32,
+            ),
+            false,
+        ));
+        columns.push(Arc::new(builder.finish()));
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(|e| {
+        GraphError::ExecutionError {
+            message: format!("Failed to build '{}' nodes: {}", node.label, e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        }
+    })
+}
+
+fn relationship_batch(
+    rel: &RelationshipSpec,
+    sources: usize,
+    targets: usize,
+    no_self_loops: bool,
+    rng: &mut SplitMix64,
+) -> Result<RecordBatch> {
+    let mut src_ids = Vec::new();
+    let mut dst_ids = Vec::new();
+    for source in 0..sources {
+        let available = if no_self_loops {
+            targets.saturating_sub(1)
+        } else {
+            targets
+        };
+        let degree = rel.degree.sample(rng).min(available);
+        let mut chosen = HashSet::with_capacity(degree);
+        while chosen.len() < degree {
+            let target = rng.below(targets as u64) as usize;
+            if no_self_loops && target == source {
+                continue;
+            }
+            if chosen.insert(target) {
+                src_ids.push(source as i64);
+                dst_ids.push(target as i64);
+            }
+        }
+    }
+    let weights: Vec<f64> = (0..src_ids.len()).map(|_| rng.next_f64()).collect();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(SOURCE_COLUMN, DataType::Int64, false),
+        Field::new(TARGET_COLUMN, DataType::Int64, false),
+        Field::new("weight", DataType::Float64, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(src_ids)),
+            Arc::new(Int64Array::from(dst_ids)),
+            Arc::new(Float64Array::from(weights)),
+        ],
+    )
+    .map_err(|e| GraphError::ExecutionError {
+        message: format!("Failed to build '{}' relationships: {}", rel.rel_type, e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Small portable generator so output does not depend on a `rand` version
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Independent stream `stream` of the generator seeded with `seed`
+    fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: seed ^ stream.wrapping_mul(0xD1B5_4A32_D192_ED03),
+        };
+        rng.next_u64();
+        rng
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `0..bound`
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            0
+        } else {
+            self.next_u64() % bound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+
+    fn social_graph(seed: u64) -> SyntheticGraph {
+        SyntheticGraph::new()
+            .with_seed(seed)
+            .with_node_label("Person", 50)
+            .with_embedding_dim("Person", 8)
+            .with_node_label("Company", 5)
+            .with_relationship(
+                "KNOWS",
+                "Person",
+                "Person",
+                DegreeDistribution::power_law(1, 20, 2.0),
+            )
+            .with_relationship(
+                "WORKS_AT",
+                "Person",
+                "Company",
+                DegreeDistribution::Constant(1),
+            )
+    }
+
+    #[test]
+    fn test_generation_is_deterministic_per_seed() {
+        let first = social_graph(1).generate().unwrap();
+        let again = social_graph(1).generate().unwrap();
+        let other = social_graph(2).generate().unwrap();
+
+        assert_eq!(first["KNOWS"], again["KNOWS"]);
+        assert_eq!(first["Person"], again["Person"]);
+        assert_ne!(first["KNOWS"], other["KNOWS"]);
+    }
+
+    #[test]
+    fn test_relationships_respect_degree_bounds() {
+        let tables = social_graph(3).generate().unwrap();
+        let knows = &tables["KNOWS"];
+        let src = knows
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let dst = knows
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+
+        let mut degrees: HashMap<i64, usize> = HashMap::new();
+        let mut edges = HashSet::new();
+        for i in 0..knows.num_rows() {
+            assert_ne!(src.value(i), dst.value(i), "self loop");
+            assert!(edges.insert((src.value(i), dst.value(i))), "duplicate edge");
+            *degrees.entry(src.value(i)).or_default() += 1;
+        }
+        assert_eq!(degrees.len(), 50);
+        assert!(degrees.values().all(|d| (1..=20).contains(d)));
+        assert_eq!(tables["WORKS_AT"].num_rows(), 50);
+    }
+
+    #[test]
+    fn test_embeddings_have_requested_dimension() {
+        let tables = social_graph(4).generate().unwrap();
+        let person = &tables["Person"];
+        let embedding = person.column_by_name(EMBEDDING_COLUMN).unwrap();
+        assert_eq!(
+            embedding.data_type(),
+            &DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 8)
+        );
+        assert_eq!(embedding.len(), 50);
+        assert!(tables["Company"].column_by_name(EMBEDDING_COLUMN).is_none());
+    }
+
+    #[test]
+    fn test_unknown_label_is_rejected() {
+        let graph = SyntheticGraph::new()
+            .with_node_label("Person", 3)
+            .with_relationship("LIKES", "Person", "Post", DegreeDistribution::Constant(1));
+        assert!(graph.generate().is_err());
+    }
+}
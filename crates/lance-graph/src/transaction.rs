@@ -0,0 +1,783 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Staged graph writes published together
+//!
+//! Lance commits each dataset on its own, so a script whose statements write
+//! to several datasets could otherwise leave the graph half-written when one
+//! of them fails. Before a statement runs, [`StagedGraph`] shallow-clones
+//! the datasets it writes to into a staging directory next to them (only
+//! the manifest is copied); the statement runs against those clones and
+//! against the live versions of every other dataset. If a statement fails,
+//! the live datasets are left untouched.
+//!
+//! Rows created by the statements are kept in a [`DeltaStore`] that later
+//! statements read along with the clones (see [`crate::delta`]), and are
+//! committed to the clones before rows are changed in place and before
+//! publishing.
+//!
+//! [`StagedGraph::publish`] turns the difference between each changed clone
+//! and the live version it was cloned from into an uncommitted Lance
+//! transaction based on that version, writing all their data files first.
+//! Rows are told apart by their identity (the key columns of nodes, the
+//! endpoint keys of relationship types without parallel edges), and only
+//! rows that were added, changed or removed are written. Relationship types
+//! with parallel edges have no row identity, so an existing dataset of one
+//! cannot be staged.
+//!
+//! Before committing anything, every transaction is checked against the
+//! commits other writers made to its dataset since the base version, the
+//! way [`crate::conflict`] checks single writes: commits that changed none
+//! of the fragments the transaction rewrites (e.g. appends) are kept, and
+//! any other fails the publication with [`GraphError::WriteConflict`],
+//! publishing nothing. The transactions are then committed one dataset after
+//! the other, and Lance checks each commit once more. Lance has no commits
+//! spanning several datasets, so publishing is not atomic across them:
+//! readers can see some datasets published and others not yet, and a writer
+//! committing between the check and the commits, or a crash while
+//! committing, leaves the datasets committed before it published. The error
+//! names them.
+
+use crate::ast::CypherQuery as CypherAST;
+use crate::config::GraphConfig;
+use crate::delta::DeltaStore;
+use crate::error::{GraphError, Result};
+use crate::graph_writer::store_params;
+use crate::query::CypherQuery;
+use arrow_array::{RecordBatch, RecordBatchIterator};
+use arrow_schema::{ArrowError, Schema};
+use datafusion::common::Column;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance::dataset::transaction::{Operation, Transaction};
+use lance::dataset::{
+    CommitBuilder, Dataset, InsertBuilder, MergeInsertBuilder, UncommittedMergeInsert, WhenMatched,
+    WhenNotMatched, WhenNotMatchedBySource, WriteMode, WriteParams,
+};
+use lance::io::{ObjectStore, ObjectStoreRegistry};
+use lance_graph_catalog::DirNamespace;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Versions of a graph dataset when its staging clone was made
+#[derive(Debug)]
+struct StagedTable {
+    name: String,
+    /// Latest live version, or `None` if the dataset did not exist
+    base_version: Option<u64>,
+    /// Version of the staging clone right after cloning
+    copied_version: Option<u64>,
+}
+
+/// A change to one live dataset, written but not committed
+enum Change {
+    /// The dataset is new
+    Create(Transaction),
+    /// Rows were added to, changed in or removed from the dataset
+    Merge(UncommittedMergeInsert),
+}
+
+/// Clones of the graph datasets a script writes to, which its writes go to
+/// until they are published
+#[derive(Debug)]
+pub(crate) struct StagedGraph {
+    live: DirNamespace,
+    config: GraphConfig,
+    staging_uri: String,
+    /// `live`, with the staged tables resolved to their clones
+    staging: Arc<DirNamespace>,
+    tables: Vec<StagedTable>,
+    /// Created rows not committed to the staging clones yet
+    delta: Arc<DeltaStore>,
+}
+
+impl StagedGraph {
+    /// Start staging writes to the datasets of `config` in `live`
+    pub(crate) fn begin(live: DirNamespace, config: &GraphConfig) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let staging_uri = format!(
+            "{}/_staging_{}_{}",
+            live.base_uri(),
+            std::process::id(),
+            nanos
+        );
+        Self {
+            staging: Arc::new(live.clone()),
+            live,
+            config: config.clone(),
+            staging_uri,
+            tables: Vec::new(),
+            delta: Arc::new(DeltaStore::new()),
+        }
+    }
+
+    /// The namespace statements run against
+    pub(crate) fn namespace(&self) -> Arc<DirNamespace> {
+        self.staging.clone()
+    }
+
+    /// Run `statement` against the staging clones and the rows staged by
+    /// the statements before it, cloning the datasets it writes to first
+    pub(crate) async fn execute(&mut self, statement: &CypherQuery) -> Result<RecordBatch> {
+        let tables = crate::writing::written_tables(statement)?;
+        self.stage(&tables).await?;
+        if !reads_staged_rows(statement.ast()) {
+            self.commit_staged().await?;
+        }
+        statement
+            .clone()
+            .with_delta(self.delta.clone())
+            .execute_with_namespace_arc(self.namespace(), None)
+            .await
+    }
+
+    /// Commit the changes of every staging clone to its live dataset and
+    /// remove the staging directory
+    ///
+    /// Fails with [`GraphError::WriteConflict`] if another writer made a
+    /// conflicting commit to a changed dataset since it was cloned.
+    pub(crate) async fn publish(self) -> Result<()> {
+        let result = match self.commit_staged().await {
+            Ok(()) => self.publish_changes().await,
+            Err(e) => Err(e),
+        };
+        self.discard().await;
+        result
+    }
+
+    /// Remove the staging directory, leaving the live datasets untouched
+    pub(crate) async fn discard(&self) {
+        // Leftover clones are harmless; a failed cleanup must not hide the
+        // outcome of the script
+        if let Ok((store, path)) = ObjectStore::from_uri_and_params(
+            Arc::new(ObjectStoreRegistry::default()),
+            &self.staging_uri,
+            &store_params(&self.live, ""),
+        )
+        .await
+        {
+            let _ = store.remove_dir_all(path).await;
+        }
+    }
+
+    /// Clone the latest version of every dataset of `tables` not staged yet
+    async fn stage(&mut self, tables: &[String]) -> Result<()> {
+        for name in tables {
+            if self
+                .tables
+                .iter()
+                .any(|table| table.name.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+            let uri = format!("{}/{}.lance", self.staging_uri, name);
+            let (base_version, copied_version) =
+                match crate::graph_diff::open_latest(&self.live, name).await? {
+                    Some(mut dataset) => {
+                        require_identity(&self.config, name)?;
+                        let version = dataset.version().version;
+                        let copy = dataset
+                            .shallow_clone(&uri, version, Some(store_params(&self.live, name)))
+                            .await
+                            .map_err(|e| GraphError::ExecutionError {
+                                message: format!("Failed to stage '{}': {}", name, e),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            })?;
+                        (Some(version), Some(copy.version().version))
+                    }
+                    None => (None, None),
+                };
+            self.staging = Arc::new((*self.staging).clone().with_table_location(name, uri));
+            self.tables.push(StagedTable {
+                name: name.clone(),
+                base_version,
+                copied_version,
+            });
+        }
+        Ok(())
+    }
+
+    /// Append the staged rows to the staging clones, creating the datasets
+    /// the statements created
+    async fn commit_staged(&self) -> Result<()> {
+        for staged in self.delta.take() {
+            let uri = self.staging.table_uri(&staged.table);
+            let mode = if staged.new {
+                WriteMode::Create
+            } else {
+                WriteMode::Append
+            };
+            let schema = staged.batches[0].schema();
+            write(
+                &self.staging,
+                &staged.table,
+                &uri,
+                schema,
+                staged.batches,
+                mode,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn publish_changes(&self) -> Result<()> {
+        // Write the data files of every change before committing any
+        let mut pending = Vec::new();
+        for table in &self.tables {
+            let Some(staged) = crate::graph_diff::open_latest(&self.staging, &table.name).await?
+            else {
+                continue;
+            };
+            if Some(staged.version().version) != table.copied_version {
+                pending.push((table, self.prepare(table, &staged).await?));
+            }
+        }
+        for (table, change) in &pending {
+            self.check(table, change).await?;
+        }
+
+        let mut published: Vec<&str> = Vec::new();
+        for (table, change) in pending {
+            if let Err(e) = self.commit(table, change).await {
+                if published.is_empty() {
+                    return Err(e);
+                }
+                let message = format!("{}; already published: {}", e, published.join(", "));
+                let location = snafu::Location::new(file!(), line!(), column!());
+                return Err(match e {
+                    GraphError::WriteConflict { .. } => {
+                        GraphError::WriteConflict { message, location }
+                    }
+                    _ => GraphError::ExecutionError { message, location },
+                });
+            }
+            published.push(&table.name);
+        }
+        Ok(())
+    }
+
+    /// Write the rows `staged` gained or lost since it was cloned as an
+    /// uncommitted transaction on the version of the live dataset it was
+    /// cloned from
+    async fn prepare(&self, table: &StagedTable, staged: &Dataset) -> Result<Change> {
+        let uri = self.live.table_uri(&table.name);
+        let failed = |e: lance::Error| GraphError::ExecutionError {
+            message: format!("Failed to publish '{}': {}", table.name, e),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        };
+
+        let Some(base_version) = table.base_version else {
+            // Created by the script: the whole clone is new
+            let batches = scan(staged).await?;
+            let params = WriteParams {
+                mode: WriteMode::Create,
+                store_params: Some(store_params(&self.live, &table.name)),
+                ..Default::default()
+            };
+            let transaction = InsertBuilder::new(uri.as_str())
+                .with_params(&params)
+                .execute_uncommitted(batches)
+                .await
+                .map_err(failed)?;
+            return Ok(Change::Create(transaction));
+        };
+
+        let live = crate::graph_diff::open_latest(&self.live, &table.name)
+            .await?
+            .ok_or_else(|| GraphError::WriteConflict {
+                message: format!(
+                    "'{}' was removed while the script ran; nothing was published",
+                    table.name
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+        let base = live.checkout_version(base_version).await.map_err(failed)?;
+        // Rows are matched by their identity; unchanged rows are left where
+        // they are
+        let keys = require_identity(&self.config, &table.name)?;
+        let when_matched = match changed_rows(&Schema::from(staged.schema()), &keys)? {
+            Some(condition) => WhenMatched::update_if(&base, &condition).map_err(failed)?,
+            None => WhenMatched::DoNothing,
+        };
+        let source: SendableRecordBatchStream = staged
+            .scan()
+            .try_into_stream()
+            .await
+            .map_err(failed)?
+            .into();
+        let merged = MergeInsertBuilder::try_new(Arc::new(base), keys)
+            .map_err(failed)?
+            .when_matched(when_matched)
+            .when_not_matched(WhenNotMatched::InsertAll)
+            .when_not_matched_by_source(WhenNotMatchedBySource::Delete)
+            .try_build()
+            .map_err(failed)?
+            .execute_uncommitted(source)
+            .await
+            .map_err(failed)?;
+        Ok(Change::Merge(merged))
+    }
+
+    /// Fail with [`GraphError::WriteConflict`] if another writer committed to
+    /// the live dataset of `table` since it was cloned in a way `change`
+    /// cannot be published over
+    async fn check(&self, table: &StagedTable, change: &Change) -> Result<()> {
+        let name = &table.name;
+        let live = crate::graph_diff::open_latest(&self.live, name).await?;
+        let (live, base_version) = match (live, table.base_version) {
+            (None, None) => return Ok(()),
+            (Some(live), None) => return Err(created_meanwhile(name, &live)),
+            (None, Some(_)) => return Err(removed_meanwhile(name)),
+            (Some(live), Some(base_version)) => (live, base_version),
+        };
+        let latest_version = live.version().version;
+        if latest_version == base_version {
+            return Ok(());
+        }
+
+        let rewritten = rewritten_fragments(change);
+        let base = live.checkout_version(base_version).await?;
+        let current: HashMap<u64, _> = live
+            .get_fragments()
+            .into_iter()
+            .map(|fragment| (fragment.id() as u64, fragment.metadata().clone()))
+            .collect();
+        let overlapping: BTreeSet<u64> = base
+            .get_fragments()
+            .iter()
+            .filter(|fragment| current.get(&(fragment.id() as u64)) != Some(fragment.metadata()))
+            .map(|fragment| fragment.id() as u64)
+            .filter(|id| rewritten.contains(id))
+            .collect();
+        if overlapping.is_empty() {
+            return Ok(());
+        }
+        Err(GraphError::WriteConflict {
+            message: format!(
+                "'{}' advanced from version {} to {} while the script ran, changing fragments \
+                 the script rewrites ({:?}); nothing was published",
+                name, base_version, latest_version, overlapping
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+    }
+
+    /// Commit `change` to the live dataset of `table`
+    async fn commit(&self, table: &StagedTable, change: Change) -> Result<()> {
+        let name = &table.name;
+        let uri = self.live.table_uri(name);
+        let live = crate::graph_diff::open_latest(&self.live, name).await?;
+        let builder = match (live, &change) {
+            (Some(live), Change::Merge(_)) => CommitBuilder::new(Arc::new(live)),
+            (None, Change::Create(_)) => CommitBuilder::new(uri.as_str()),
+            (Some(live), Change::Create(_)) => return Err(created_meanwhile(name, &live)),
+            (None, Change::Merge(_)) => return Err(removed_meanwhile(name)),
+        };
+        let builder = builder.with_store_params(store_params(&self.live, name));
+        let committed = match change {
+            Change::Create(transaction) => builder.execute(transaction).await,
+            Change::Merge(merged) => {
+                let builder = match merged.affected_rows {
+                    Some(rows) => builder.with_affected_rows(rows),
+                    None => builder,
+                };
+                builder.execute(merged.transaction).await
+            }
+        };
+        committed.map_err(|e| crate::conflict::commit_error(name, e))?;
+        Ok(())
+    }
+}
+
+/// Whether `ast` runs on the rows staged so far: reads, CREATE and MERGE do,
+/// while SET, REMOVE, DELETE and procedures need them committed
+fn reads_staged_rows(ast: &CypherAST) -> bool {
+    ast.set_clause.is_none()
+        && ast.remove_clause.is_none()
+        && ast.delete_clause.is_none()
+        && ast.procedure.is_none()
+}
+
+fn created_meanwhile(table: &str, live: &Dataset) -> GraphError {
+    GraphError::WriteConflict {
+        message: format!(
+            "'{}' was created by another writer at version {} while the script ran",
+            table,
+            live.version().version
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+fn removed_meanwhile(table: &str) -> GraphError {
+    GraphError::WriteConflict {
+        message: format!("'{}' was removed while the script ran", table),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+/// Fragments of its base version that `change` rewrites or deletes from
+fn rewritten_fragments(change: &Change) -> BTreeSet<u64> {
+    let Change::Merge(merged) = change else {
+        return BTreeSet::new();
+    };
+    match &merged.transaction.operation {
+        Operation::Update {
+            updated_fragments,
+            removed_fragment_ids: removed,
+            ..
+        }
+        | Operation::Delete {
+            updated_fragments,
+            deleted_fragment_ids: removed,
+            ..
+        } => updated_fragments
+            .iter()
+            .map(|fragment| fragment.id)
+            .chain(removed.iter().copied())
+            .collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+/// Condition on a merge's `source` and `target` rows matched on `keys` that
+/// holds if one of their other columns differs, or `None` if there are none
+fn changed_rows(schema: &Schema, keys: &[String]) -> Result<Option<String>> {
+    let side = |relation: &str, name: &str| Expr::Column(Column::new(Some(relation), name));
+    let differences: Vec<Expr> = schema
+        .fields()
+        .iter()
+        .map(|field| field.name())
+        .filter(|name| !keys.iter().any(|key| key.eq_ignore_ascii_case(name)))
+        .map(|name| {
+            let (source, target) = (side("source", name), side("target", name));
+            // `<>` is null if either side is null
+            source
+                .clone()
+                .not_eq(target.clone())
+                .or(source.is_null().not_eq(target.is_null()))
+        })
+        .collect();
+    if differences.is_empty() {
+        return Ok(None);
+    }
+    crate::writing::lance_sql(&crate::writing::balanced_or(differences)).map(Some)
+}
+
+/// Columns identifying a row of `table`; fails for tables without an
+/// identity, whose changes cannot be told apart from their other rows
+fn require_identity(config: &GraphConfig, table: &str) -> Result<Vec<String>> {
+    identity(config, table).ok_or_else(|| GraphError::UnsupportedFeature {
+        feature: format!(
+            "writing to the existing dataset '{}' from an atomic script: its rows have no \
+             identity; disallow parallel edges of the type to give them one",
+            table
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Columns identifying a row of `table`, or `None` if only all its columns
+/// together do (relationship types with parallel edges)
+fn identity(config: &GraphConfig, table: &str) -> Option<Vec<String>> {
+    let to_strings = |columns: Vec<&str>| columns.into_iter().map(str::to_string).collect();
+    if let Some(mapping) = config
+        .node_mappings
+        .values()
+        .find(|mapping| mapping.table_name().eq_ignore_ascii_case(table))
+    {
+        let mut columns: Vec<String> = to_strings(mapping.key_columns());
+        // Labels sharing a table may reuse keys
+        columns.extend(mapping.label_column.clone());
+        return Some(columns);
+    }
+    config
+        .get_relationship_mapping(table)
+        .filter(|mapping| !mapping.allow_parallel_edges)
+        .map(|mapping| to_strings(mapping.edge_key_columns()))
+}
+
+async fn scan(dataset: &Dataset) -> Result<Vec<RecordBatch>> {
+    Ok(dataset
+        .scan()
+        .try_into_stream()
+        .await?
+        .try_collect()
+        .await?)
+}
+
+/// Write `batches` as the dataset of `table` at `uri`
+async fn write(
+    namespace: &DirNamespace,
+    table: &str,
+    uri: &str,
+    schema: Arc<Schema>,
+    batches: Vec<RecordBatch>,
+    mode: WriteMode,
+) -> Result<Dataset> {
+    let reader = RecordBatchIterator::new(batches.into_iter().map(Ok::<_, ArrowError>), schema);
+    Dataset::write(
+        reader,
+        uri,
+        Some(WriteParams {
+            mode,
+            store_params: Some(store_params(namespace, table)),
+            ..Default::default()
+        }),
+    )
+    .await
+    .map_err(|e| GraphError::ExecutionError {
+        message: format!("Failed to write '{}': {}", uri, e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RelationshipMapping;
+    use arrow_array::Int64Array;
+    use arrow_schema::{DataType, Field};
+    use std::path::Path;
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .build()
+            .unwrap()
+    }
+
+    fn people(ids: Vec<i64>) -> (Arc<Schema>, Vec<RecordBatch>) {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))]).unwrap();
+        (schema, vec![batch])
+    }
+
+    async fn rows(namespace: &DirNamespace) -> (u64, usize) {
+        let dataset = crate::graph_diff::open_latest(namespace, "Person")
+            .await
+            .unwrap()
+            .unwrap();
+        let rows = dataset.count_rows(None).await.unwrap();
+        (dataset.version().version, rows)
+    }
+
+    /// Write `ids` to the Person dataset of `namespace`
+    async fn append(namespace: &DirNamespace, ids: Vec<i64>, mode: WriteMode) {
+        let (schema, batches) = people(ids);
+        let uri = namespace.table_uri("Person");
+        write(namespace, "Person", &uri, schema, batches, mode)
+            .await
+            .unwrap();
+    }
+
+    async fn staged_graph(live: &DirNamespace) -> StagedGraph {
+        let mut staged = StagedGraph::begin(live.clone(), &config());
+        staged.stage(&["Person".to_string()]).await.unwrap();
+        staged
+    }
+
+    #[tokio::test]
+    async fn test_publish_bumps_each_changed_dataset_once() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        append(&live, vec![1], WriteMode::Create).await;
+
+        let staged = staged_graph(&live).await;
+        let staging_uri = staged.staging_uri.clone();
+        for id in [2, 3] {
+            append(&staged.namespace(), vec![id], WriteMode::Append).await;
+        }
+        assert_eq!(rows(&live).await, (1, 1));
+
+        staged.publish().await.unwrap();
+        assert_eq!(rows(&live).await, (2, 3));
+        assert!(!Path::new(&staging_uri).exists());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_append_is_kept() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        append(&live, vec![1], WriteMode::Create).await;
+
+        let staged = staged_graph(&live).await;
+        append(&staged.namespace(), vec![2], WriteMode::Append).await;
+
+        // Another writer appends to the live dataset in the meantime
+        append(&live, vec![9], WriteMode::Append).await;
+
+        staged.publish().await.unwrap();
+        assert_eq!(rows(&live).await, (3, 3));
+    }
+
+    /// Delete the rows matching `filter` from the Person dataset of `namespace`
+    async fn delete(namespace: &DirNamespace, filter: &str) {
+        let mut dataset = crate::graph_diff::open_latest(namespace, "Person")
+            .await
+            .unwrap()
+            .unwrap();
+        dataset.delete(filter).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_rows_are_not_rewritten() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        append(&live, vec![1], WriteMode::Create).await;
+
+        let staged = staged_graph(&live).await;
+        append(&staged.namespace(), vec![2], WriteMode::Append).await;
+
+        // Another writer deletes a row the script left alone
+        delete(&live, "id = 1").await;
+
+        staged.publish().await.unwrap();
+        assert_eq!(rows(&live).await, (3, 1));
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_commit_is_rejected() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        append(&live, vec![1, 2], WriteMode::Create).await;
+
+        let staged = staged_graph(&live).await;
+        delete(&staged.namespace(), "id = 1").await;
+
+        // Another writer deletes from the fragment the script rewrites
+        delete(&live, "id = 2").await;
+
+        let err = staged.publish().await.unwrap_err();
+        assert!(matches!(err, GraphError::WriteConflict { .. }), "{}", err);
+        assert_eq!(rows(&live).await, (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_conflict_on_any_dataset_publishes_nothing() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_node_label("Robot", "id")
+            .build()
+            .unwrap();
+        let (schema, batches) = people(vec![1, 2]);
+        for table in ["Person", "Robot"] {
+            let uri = live.table_uri(table);
+            write(
+                &live,
+                table,
+                &uri,
+                schema.clone(),
+                batches.clone(),
+                WriteMode::Create,
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut staged = StagedGraph::begin(live.clone(), &config);
+        staged
+            .stage(&["Person".to_string(), "Robot".to_string()])
+            .await
+            .unwrap();
+        append(&staged.namespace(), vec![3], WriteMode::Append).await;
+        let mut robots = crate::graph_diff::open_latest(&staged.namespace(), "Robot")
+            .await
+            .unwrap()
+            .unwrap();
+        robots.delete("id = 1").await.unwrap();
+
+        // The conflict is on the dataset published last
+        let mut robots = crate::graph_diff::open_latest(&live, "Robot")
+            .await
+            .unwrap()
+            .unwrap();
+        robots.delete("id = 2").await.unwrap();
+
+        let err = staged.publish().await.unwrap_err();
+        assert!(matches!(err, GraphError::WriteConflict { .. }), "{}", err);
+        assert!(err.to_string().contains("nothing was published"), "{}", err);
+        assert_eq!(rows(&live).await, (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_datasets_without_row_identity_are_not_staged() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("src_id", DataType::Int64, false),
+            Field::new("dst_id", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 1])),
+                Arc::new(Int64Array::from(vec![2, 2])),
+            ],
+        )
+        .unwrap();
+        let uri = live.table_uri("KNOWS");
+        write(&live, "KNOWS", &uri, schema, vec![batch], WriteMode::Create)
+            .await
+            .unwrap();
+
+        let parallel = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap();
+        let mut staged = StagedGraph::begin(live.clone(), &parallel);
+        let err = staged.stage(&["KNOWS".to_string()]).await.unwrap_err();
+        assert!(err.to_string().contains("no identity"), "{}", err);
+
+        let single = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship_mapping(
+                RelationshipMapping::new("KNOWS", "src_id", "dst_id").with_parallel_edges(false),
+            )
+            .build()
+            .unwrap();
+        let mut staged = StagedGraph::begin(live, &single);
+        staged.stage(&["KNOWS".to_string()]).await.unwrap();
+        staged.discard().await;
+    }
+
+    #[tokio::test]
+    async fn test_only_written_datasets_are_staged() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let live = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+        append(&live, vec![1], WriteMode::Create).await;
+
+        let mut staged = StagedGraph::begin(live.clone(), &config());
+        let read = CypherQuery::new("MATCH (p:Person) RETURN p.id")
+            .unwrap()
+            .with_config(config());
+        staged.execute(&read).await.unwrap();
+        assert!(staged.tables.is_empty());
+        assert_eq!(
+            staged.namespace().table_uri("Person"),
+            live.table_uri("Person")
+        );
+
+        let create = CypherQuery::new("CREATE (:Person {id: 2})")
+            .unwrap()
+            .with_config(config());
+        staged.execute(&create).await.unwrap();
+        assert_eq!(staged.tables.len(), 1);
+        assert!(staged
+            .namespace()
+            .table_uri("Person")
+            .starts_with(&staged.staging_uri));
+        staged.publish().await.unwrap();
+        assert_eq!(rows(&live).await, (2, 2));
+    }
+}
@@ -0,0 +1,768 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Index-backed candidates for vector top-k queries
+//!
+//! `MATCH (d:Doc) RETURN d.title ORDER BY vector_distance(d.title_vec, $q, l2) LIMIT 10`
+//! is answered exactly by scoring every row. When the label declares the
+//! embedding column as vector indexed
+//! ([`crate::config::NodeMapping::with_vector_properties`]), the nearest rows
+//! are first retrieved through the Lance index on the column the function
+//! names, and the match is restricted to them (`d.id IN [...]`) before exact
+//! scoring. A label may carry several embedding columns, each searched
+//! through its own index.
+//!
+//! A weighted fusion of such terms over the same node, e.g.
+//! `ORDER BY 0.7 * vector_similarity(n.title_vec, $q) + 0.3 * vector_similarity(n.body_vec, $q) DESC`,
+//! retrieves the top-k of every index involved and scores the union of
+//! those candidates exactly. Rows that rank high on the fused score without
+//! ranking high on any single column can be missed.
+//!
+//! Relationships declare vector indexed columns the same way
+//! ([`crate::config::RelationshipMapping::with_vector_properties`]), so
+//! `MATCH (a)-[r:RATED]->(b) RETURN ... ORDER BY vector_distance(r.embedding, $q) LIMIT 10`
+//! searches the index of the edge dataset and restricts the edges by the
+//! source and target ids of the nearest ones.
+//!
+//! The rewrite applies to a single node or single-hop pattern without WHERE
+//! filters, read from a Lance dataset by a mapping with no filters of its
+//! own; any other query is scored exactly. A declared column without a vector index in the dataset
+//! is reported as an error rather than silently scanned.
+
+use crate::ast::{
+    ArithmeticOperator, BooleanExpression, CypherQuery as CypherAST, DistanceMetric, GraphPattern,
+    MatchClause, OrderByItem, PropertyRef, PropertyValue, ReadingClause, SortDirection,
+    ValueExpression, WhereClause,
+};
+use crate::config::{GraphConfig, NodeMapping};
+use crate::error::{GraphError, Result};
+use crate::lance_vector_search::VectorSearch;
+use arrow::array::{Array, ArrayRef, Int64Array, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::TableSource;
+use lance::datafusion::LanceTableProvider;
+use lance::Dataset;
+use lance_graph_catalog::GraphSourceCatalog;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A vector function of an entity property against a constant query vector
+#[derive(Debug, Clone, PartialEq)]
+struct VectorTerm {
+    variable: String,
+    property: String,
+    query: Vec<f32>,
+    metric: DistanceMetric,
+}
+
+/// Index searches shared by the queries of a batch
+///
+/// Queries ranking by the same query vector over the same index version with
+/// the same `k` reuse the candidates of the first search.
+#[derive(Debug, Default)]
+pub(crate) struct SeedCache {
+    seeds: Mutex<HashMap<String, Option<Vec<Vec<ValueExpression>>>>>,
+}
+
+/// Replace `$param` arguments of vector functions with the bound vectors
+///
+/// Parameters that are not arrays of numbers are left in place.
+pub(crate) fn bind_vector_parameters(
+    ast: &mut CypherAST,
+    parameters: &HashMap<String, serde_json::Value>,
+) {
+    if parameters.is_empty() {
+        return;
+    }
+    for item in &mut ast.return_clause.items {
+        bind_value(&mut item.expression, parameters);
+    }
+    if let Some(order_by) = &mut ast.order_by {
+        for item in &mut order_by.items {
+            bind_value(&mut item.expression, parameters);
+        }
+    }
+    if let Some(with) = &mut ast.with_clause {
+        for item in &mut with.items {
+            bind_value(&mut item.expression, parameters);
+        }
+        if let Some(order_by) = &mut with.order_by {
+            for item in &mut order_by.items {
+                bind_value(&mut item.expression, parameters);
+            }
+        }
+    }
+    for clause in [&mut ast.where_clause, &mut ast.post_with_where_clause]
+        .into_iter()
+        .flatten()
+    {
+        bind_boolean(&mut clause.expression, parameters);
+    }
+    for clause in ast
+        .reading_clauses
+        .iter_mut()
+        .chain(ast.post_with_reading_clauses.iter_mut())
+    {
+        if let ReadingClause::Match(MatchClause {
+            where_clause: Some(where_clause),
+            ..
+        }) = clause
+        {
+            bind_boolean(&mut where_clause.expression, parameters);
+        }
+    }
+}
+
+fn bind_value(expr: &mut ValueExpression, parameters: &HashMap<String, serde_json::Value>) {
+    match expr {
+        ValueExpression::VectorDistance { left, right, .. }
+        | ValueExpression::VectorSimilarity { left, right, .. } => {
+            for side in [left, right] {
+                if let ValueExpression::Parameter(name) = side.as_ref() {
+                    if let Some(vector) = parameters.get(name).and_then(vector_value) {
+                        **side = ValueExpression::VectorLiteral(vector);
+                    }
+                }
+            }
+        }
+        ValueExpression::Arithmetic { left, right, .. } => {
+            bind_value(left, parameters);
+            bind_value(right, parameters);
+        }
+        ValueExpression::ScalarFunction { args, .. }
+        | ValueExpression::AggregateFunction { args, .. } => {
+            for arg in args {
+                bind_value(arg, parameters);
+            }
+        }
+        ValueExpression::Case { branches, default } => {
+            for branch in branches {
+                bind_boolean(&mut branch.condition, parameters);
+                bind_value(&mut branch.value, parameters);
+            }
+            if let Some(default) = default {
+                bind_value(default, parameters);
+            }
+        }
+        ValueExpression::ListComprehension {
+            list,
+            predicate,
+            projection,
+            ..
+        } => {
+            bind_value(list, parameters);
+            if let Some(predicate) = predicate {
+                bind_boolean(predicate, parameters);
+            }
+            if let Some(projection) = projection {
+                bind_value(projection, parameters);
+            }
+        }
+        ValueExpression::Reduce {
+            initial,
+            list,
+            expression,
+            ..
+        } => {
+            bind_value(initial, parameters);
+            bind_value(list, parameters);
+            bind_value(expression, parameters);
+        }
+        ValueExpression::PatternComprehension {
+            predicate,
+            projection,
+            ..
+        } => {
+            if let Some(predicate) = predicate {
+                bind_boolean(predicate, parameters);
+            }
+            bind_value(projection, parameters);
+        }
+        ValueExpression::MapProjection { items, .. } => {
+            for item in items {
+                bind_value(&mut item.value, parameters);
+            }
+        }
+        ValueExpression::CountSubquery {
+            where_clause: Some(predicate),
+            ..
+        } => bind_boolean(predicate, parameters),
+        _ => {}
+    }
+}
+
+fn bind_boolean(expr: &mut BooleanExpression, parameters: &HashMap<String, serde_json::Value>) {
+    match expr {
+        BooleanExpression::Comparison { left, right, .. } => {
+            bind_value(left, parameters);
+            bind_value(right, parameters);
+        }
+        BooleanExpression::And(left, right) | BooleanExpression::Or(left, right) => {
+            bind_boolean(left, parameters);
+            bind_boolean(right, parameters);
+        }
+        BooleanExpression::Not(inner) => bind_boolean(inner, parameters),
+        BooleanExpression::In { expression, .. }
+        | BooleanExpression::Like { expression, .. }
+        | BooleanExpression::ILike { expression, .. }
+        | BooleanExpression::Contains { expression, .. }
+        | BooleanExpression::StartsWith { expression, .. }
+        | BooleanExpression::EndsWith { expression, .. }
+        | BooleanExpression::RegexMatch { expression, .. }
+        | BooleanExpression::IsNull(expression)
+        | BooleanExpression::IsNotNull(expression) => bind_value(expression, parameters),
+        BooleanExpression::AllInPath { predicate, .. } => bind_boolean(predicate, parameters),
+        BooleanExpression::ListPredicate {
+            list, predicate, ..
+        } => {
+            bind_value(list, parameters);
+            bind_boolean(predicate, parameters);
+        }
+        BooleanExpression::ExistsSubquery {
+            where_clause: Some(predicate),
+            ..
+        } => bind_boolean(predicate, parameters),
+        BooleanExpression::Exists(_) | BooleanExpression::ExistsSubquery { .. } => {}
+    }
+}
+
+fn vector_value(value: &serde_json::Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+/// Restrict a vector top-k query to the candidates found through the index
+///
+/// Returns `None` when the query does not qualify; see the module
+/// documentation.
+pub(crate) async fn restrict_to_candidates(
+    ast: &CypherAST,
+    config: &GraphConfig,
+    catalog: &dyn GraphSourceCatalog,
+    seeds: Option<&SeedCache>,
+) -> Result<Option<CypherAST>> {
+    let Some(limit) = ast.limit else {
+        return Ok(None);
+    };
+    if ast.procedure.is_some()
+        || ast.with_clause.is_some()
+        || !ast.post_with_reading_clauses.is_empty()
+        || ast.where_clause.is_some()
+        || ast.sample.is_some()
+        || ast.return_clause.distinct
+        || !ast.return_clause.distinct_on.is_empty()
+        || ast
+            .return_clause
+            .items
+            .iter()
+            .any(|item| matches!(item.expression, ValueExpression::AggregateFunction { .. }))
+    {
+        return Ok(None);
+    }
+
+    let [ReadingClause::Match(match_clause)] = ast.reading_clauses.as_slice() else {
+        return Ok(None);
+    };
+    let [pattern] = match_clause.patterns.as_slice() else {
+        return Ok(None);
+    };
+    let Some(target) = candidate_target(pattern, config, catalog, ast.include_deleted) else {
+        return Ok(None);
+    };
+
+    let Some(terms) = ast
+        .order_by
+        .as_ref()
+        .and_then(|order_by| order_by.items.first())
+        .and_then(|item| ranking_terms(item, ast))
+    else {
+        return Ok(None);
+    };
+    if terms.iter().any(|term| {
+        term.variable != target.variable
+            || !target
+                .vector_properties
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(&term.property))
+    }) {
+        return Ok(None);
+    }
+    let Some(dataset) = lance_dataset(target.source) else {
+        return Ok(None);
+    };
+
+    // Candidates of every index, in rank order without duplicates
+    let k = limit + ast.skip.unwrap_or(0);
+    let mut lists: Vec<Vec<ValueExpression>> = vec![Vec::new(); target.key_columns.len()];
+    for term in &terms {
+        let keys = match seeds {
+            Some(seeds) => {
+                let key = format!(
+                    "{}@{}|{}|{:?}|{:?}|{}|{:?}",
+                    dataset.uri(),
+                    dataset.version().version,
+                    term.property,
+                    term.query,
+                    term.metric,
+                    k,
+                    target.key_columns
+                );
+                let cached = seeds.seeds.lock().unwrap().get(&key).cloned();
+                match cached {
+                    Some(keys) => keys,
+                    None => {
+                        let keys =
+                            nearest_keys(&dataset, target.owner, term, &target.key_columns, k)
+                                .await?;
+                        seeds.seeds.lock().unwrap().insert(key, keys.clone());
+                        keys
+                    }
+                }
+            }
+            None => nearest_keys(&dataset, target.owner, term, &target.key_columns, k).await?,
+        };
+        let Some(keys) = keys else {
+            return Ok(None);
+        };
+        for (list, values) in lists.iter_mut().zip(keys) {
+            for value in values {
+                if !list.contains(&value) {
+                    list.push(value);
+                }
+            }
+        }
+    }
+
+    let expression = target
+        .key_columns
+        .iter()
+        .zip(lists)
+        .map(|(column, list)| BooleanExpression::In {
+            expression: ValueExpression::Property(PropertyRef {
+                variable: target.variable.to_string(),
+                property: column.to_string(),
+            }),
+            list,
+        })
+        .reduce(|left, right| BooleanExpression::And(Box::new(left), Box::new(right)))
+        .expect("a candidate target has key columns");
+    let mut restricted = ast.clone();
+    restricted.where_clause = Some(WhereClause { expression });
+    Ok(Some(restricted))
+}
+
+/// The variable a vector top-k query ranks and how to restrict it
+struct CandidateTarget<'a> {
+    variable: &'a str,
+    /// Label or relationship type, for error messages
+    owner: &'a str,
+    source: Option<Arc<dyn TableSource>>,
+    /// Columns restricted to the candidates' values
+    key_columns: Vec<&'a str>,
+    vector_properties: &'a [String],
+}
+
+/// The node of `(n:Label)`, or the relationship of `(a)-[r:TYPE]->(b)`,
+/// when every row of its dataset is a match candidate
+///
+/// Edges are restricted by their source and target ids, which admits every
+/// edge between the candidates' endpoints; exact scoring then ranks them.
+fn candidate_target<'a>(
+    pattern: &'a GraphPattern,
+    config: &'a GraphConfig,
+    catalog: &dyn GraphSourceCatalog,
+    include_deleted: bool,
+) -> Option<CandidateTarget<'a>> {
+    match pattern {
+        GraphPattern::Node(node) => {
+            let (Some(variable), [label]) = (&node.variable, node.labels.as_slice()) else {
+                return None;
+            };
+            let mapping = config.get_node_mapping(label)?;
+            if !node.properties.is_empty() || !is_unfiltered(mapping, include_deleted) {
+                return None;
+            }
+            Some(CandidateTarget {
+                variable,
+                owner: &mapping.label,
+                source: catalog.node_source(mapping.table_name()),
+                key_columns: vec![mapping.id_field.as_str()],
+                vector_properties: &mapping.vector_properties,
+            })
+        }
+        GraphPattern::Path(path) => {
+            let [segment] = path.segments.as_slice() else {
+                return None;
+            };
+            let relationship = &segment.relationship;
+            let (Some(variable), [rel_type]) =
+                (&relationship.variable, relationship.types.as_slice())
+            else {
+                return None;
+            };
+            let mapping = config.get_relationship_mapping(rel_type)?;
+            let key_columns = vec![
+                mapping.source_id_field.as_str(),
+                mapping.target_id_field.as_str(),
+            ];
+            let keys_visible = mapping.property_fields.is_empty()
+                || key_columns.iter().all(|column| {
+                    mapping
+                        .property_fields
+                        .iter()
+                        .any(|p| p.eq_ignore_ascii_case(column))
+                });
+            if relationship.length.is_some()
+                || !relationship.properties.is_empty()
+                || mapping.filter_conditions.is_some()
+                || mapping.type_field.is_some()
+                || !mapping.source_key_fields.is_empty()
+                || !mapping.target_key_fields.is_empty()
+                || (mapping.soft_delete_column.is_some() && !include_deleted)
+                || !keys_visible
+            {
+                return None;
+            }
+            // Endpoint filters could reject every candidate edge
+            for end in [&path.start_node, &segment.end_node] {
+                if !end.properties.is_empty() || end.labels.len() > 1 {
+                    return None;
+                }
+                if let Some(label) = end.labels.first() {
+                    if !is_unfiltered(config.get_node_mapping(label)?, include_deleted) {
+                        return None;
+                    }
+                }
+            }
+            Some(CandidateTarget {
+                variable,
+                owner: &mapping.relationship_type,
+                source: catalog.relationship_source(&mapping.relationship_type),
+                key_columns,
+                vector_properties: &mapping.vector_properties,
+            })
+        }
+    }
+}
+
+/// Whether every row of the label's dataset is a match candidate
+fn is_unfiltered(mapping: &NodeMapping, include_deleted: bool) -> bool {
+    mapping.view_of.is_none()
+        && mapping.filter_conditions.is_none()
+        && mapping.label_column.is_none()
+        && mapping.key_fields.is_empty()
+        && (mapping.soft_delete_column.is_none() || include_deleted)
+}
+
+/// The vector terms `item` ranks by, when nearer rows sort first on each
+///
+/// Accepts a single vector function or a weighted sum of them with constant
+/// weights, e.g. `0.7 * vector_similarity(...) + 0.3 * vector_similarity(...)`.
+fn ranking_terms(item: &OrderByItem, ast: &CypherAST) -> Option<Vec<VectorTerm>> {
+    let mut terms = Vec::new();
+    let ascending = item.direction == SortDirection::Ascending;
+    collect_terms(resolve_alias(&item.expression, ast), 1.0, &mut terms)?;
+    terms
+        .into_iter()
+        .map(|(term, nearest_first_ascending)| {
+            (nearest_first_ascending == ascending).then_some(term)
+        })
+        .collect()
+}
+
+/// Collect the vector terms of a weighted sum scaled by `weight`, each with
+/// whether an ascending order sorts its nearest rows first
+fn collect_terms(
+    expression: &ValueExpression,
+    weight: f64,
+    terms: &mut Vec<(VectorTerm, bool)>,
+) -> Option<()> {
+    let constant = |expression: &ValueExpression| match expression {
+        ValueExpression::Literal(PropertyValue::Integer(i)) => Some(*i as f64),
+        ValueExpression::Literal(PropertyValue::Float(f)) => Some(*f),
+        _ => None,
+    };
+    match expression {
+        ValueExpression::VectorDistance { .. } => {
+            terms.push((vector_term(expression)?, weight > 0.0));
+        }
+        ValueExpression::VectorSimilarity { .. } => {
+            terms.push((vector_term(expression)?, weight < 0.0));
+        }
+        ValueExpression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => match operator {
+            ArithmeticOperator::Add => {
+                collect_terms(left, weight, terms)?;
+                collect_terms(right, weight, terms)?;
+            }
+            ArithmeticOperator::Subtract => {
+                collect_terms(left, weight, terms)?;
+                collect_terms(right, -weight, terms)?;
+            }
+            ArithmeticOperator::Multiply => {
+                let (factor, term) = match (constant(left), constant(right)) {
+                    (Some(factor), None) => (factor, right),
+                    (None, Some(factor)) => (factor, left),
+                    _ => return None,
+                };
+                if factor == 0.0 {
+                    return None;
+                }
+                collect_terms(term, weight * factor, terms)?;
+            }
+            ArithmeticOperator::Divide => {
+                let divisor = constant(right).filter(|d| *d != 0.0)?;
+                collect_terms(left, weight / divisor, terms)?;
+            }
+            ArithmeticOperator::Modulo => return None,
+        },
+        _ => return None,
+    }
+    Some(())
+}
+
+/// The property and query vector a vector function compares
+fn vector_term(expression: &ValueExpression) -> Option<VectorTerm> {
+    let (left, right, metric) = match expression {
+        ValueExpression::VectorDistance {
+            left,
+            right,
+            metric,
+        }
+        | ValueExpression::VectorSimilarity {
+            left,
+            right,
+            metric,
+        } => (left, right, metric),
+        _ => return None,
+    };
+    let (property, query) = match (left.as_ref(), right.as_ref()) {
+        (ValueExpression::Property(p), ValueExpression::VectorLiteral(v))
+        | (ValueExpression::VectorLiteral(v), ValueExpression::Property(p)) => (p, v),
+        _ => return None,
+    };
+    Some(VectorTerm {
+        variable: property.variable.clone(),
+        property: property.property.clone(),
+        query: query.clone(),
+        metric: metric.clone(),
+    })
+}
+
+/// The RETURN expression aliased `expression`, or `expression` itself
+fn resolve_alias<'a>(expression: &'a ValueExpression, ast: &'a CypherAST) -> &'a ValueExpression {
+    let ValueExpression::Variable(name) = expression else {
+        return expression;
+    };
+    ast.return_clause
+        .items
+        .iter()
+        .find(|item| item.alias.as_deref() == Some(name.as_str()))
+        .map(|item| &item.expression)
+        .unwrap_or(expression)
+}
+
+/// Lance dataset behind a catalog source, if it is one
+fn lance_dataset(source: Option<Arc<dyn TableSource>>) -> Option<Arc<Dataset>> {
+    let provider = source_as_provider(&source?).ok()?;
+    let lance = provider.as_any().downcast_ref::<LanceTableProvider>()?;
+    Some(lance.dataset())
+}
+
+/// Values of `key_columns` in the `k` rows nearest to the term's query
+/// vector, as one list of literals per column
+///
+/// Returns `None` when there are no rows or a key is neither integers nor
+/// strings.
+async fn nearest_keys(
+    dataset: &Dataset,
+    owner: &str,
+    term: &VectorTerm,
+    key_columns: &[&str],
+    k: u64,
+) -> Result<Option<Vec<Vec<ValueExpression>>>> {
+    let column = |name: &str| {
+        dataset
+            .schema()
+            .fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+            .map(|f| f.name.clone())
+    };
+    let Some(vector_column) = column(&term.property) else {
+        return Ok(None);
+    };
+    let Some(key_columns) = key_columns
+        .iter()
+        .map(|name| column(name))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Ok(None);
+    };
+
+    let search = VectorSearch::new(&vector_column)
+        .query_vector(term.query.clone())
+        .metric(term.metric.clone())
+        .top_k(k as usize)
+        .include_distance(false);
+    if search.vector_index_name(dataset).await?.is_none() {
+        return Err(GraphError::ConfigError {
+            message: format!(
+                "Property '{}' of '{}' is declared as vector indexed, but its dataset has no vector index on '{}'",
+                term.property, owner, vector_column
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+
+    let batch = search.search_lance(dataset).await?;
+    if batch.num_rows() == 0 {
+        return Ok(None);
+    }
+    let mut lists = Vec::with_capacity(key_columns.len());
+    for name in &key_columns {
+        let Some(values) = batch.column_by_name(name) else {
+            return Ok(None);
+        };
+        let Some(literals) = id_literals(values)? else {
+            return Ok(None);
+        };
+        lists.push(literals);
+    }
+    Ok(Some(lists))
+}
+
+fn id_literals(ids: &ArrayRef) -> Result<Option<Vec<ValueExpression>>> {
+    let cast_error = |e: arrow::error::ArrowError| GraphError::ExecutionError {
+        message: format!("Failed to read candidate ids: {}", e),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    };
+    let data_type = ids.data_type();
+    if data_type.is_integer() {
+        let ids = cast(ids, &DataType::Int64).map_err(cast_error)?;
+        let ids = ids.as_any().downcast_ref::<Int64Array>().unwrap();
+        Ok(Some(
+            ids.iter()
+                .flatten()
+                .map(|id| ValueExpression::Literal(PropertyValue::Integer(id)))
+                .collect(),
+        ))
+    } else if matches!(
+        data_type,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+    ) {
+        let ids = cast(ids, &DataType::Utf8).map_err(cast_error)?;
+        let ids = ids.as_any().downcast_ref::<StringArray>().unwrap();
+        Ok(Some(
+            ids.iter()
+                .flatten()
+                .map(|id| ValueExpression::Literal(PropertyValue::String(id.to_string())))
+                .collect(),
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_cypher_query;
+
+    #[test]
+    fn test_ranking_term_follows_the_named_column() {
+        let mut ast = parse_cypher_query(
+            "MATCH (d:Doc) RETURN d.title, vector_distance(d.body_vec, $q, l2) AS dist \
+             ORDER BY dist ASC LIMIT 3",
+        )
+        .unwrap();
+        let parameters = HashMap::from([("q".to_string(), serde_json::json!([1.0, 0.0]))]);
+        bind_vector_parameters(&mut ast, &parameters);
+
+        let item = &ast.order_by.as_ref().unwrap().items[0];
+        let [term] = ranking_terms(item, &ast).unwrap().try_into().unwrap();
+        assert_eq!(term.variable, "d");
+        assert_eq!(term.property, "body_vec");
+        assert_eq!(term.query, vec![1.0, 0.0]);
+
+        // Farthest-first orderings are not top-k nearest neighbor searches
+        let descending = OrderByItem {
+            direction: SortDirection::Descending,
+            ..item.clone()
+        };
+        assert!(ranking_terms(&descending, &ast).is_none());
+    }
+
+    #[test]
+    fn test_relationship_target_restricts_edge_endpoints() {
+        use crate::config::RelationshipMapping;
+        use lance_graph_catalog::InMemoryCatalog;
+
+        let config = GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_relationship_mapping(
+                RelationshipMapping::new("RATED", "src", "dst")
+                    .with_vector_properties(vec!["embedding".to_string()]),
+            )
+            .build()
+            .unwrap();
+        let catalog = InMemoryCatalog::new();
+        let target = |query: &str| {
+            let ast = parse_cypher_query(query).unwrap();
+            let ReadingClause::Match(match_clause) = &ast.reading_clauses[0] else {
+                panic!("Expected MATCH");
+            };
+            candidate_target(&match_clause.patterns[0], &config, &catalog, false)
+                .map(|t| (t.variable.to_string(), t.key_columns.join(",")))
+        };
+
+        assert_eq!(
+            target("MATCH (a:Person)-[r:RATED]->(b) RETURN r"),
+            Some(("r".to_string(), "src,dst".to_string()))
+        );
+        // Variable-length paths and endpoint filters are scored exactly
+        assert_eq!(target("MATCH (a)-[r:RATED*1..2]->(b) RETURN a"), None);
+        assert_eq!(
+            target("MATCH (a:Person {name: 'Alice'})-[r:RATED]->(b) RETURN r"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_weighted_similarity_fusion_ranks_by_every_column() {
+        let mut ast = parse_cypher_query(
+            "MATCH (n:Doc) RETURN n.title \
+             ORDER BY 0.7 * vector_similarity(n.title_vec, $q) \
+             + 0.3 * vector_similarity(n.body_vec, $q) DESC LIMIT 5",
+        )
+        .unwrap();
+        let parameters = HashMap::from([("q".to_string(), serde_json::json!([1.0, 0.0]))]);
+        bind_vector_parameters(&mut ast, &parameters);
+
+        let item = &ast.order_by.as_ref().unwrap().items[0];
+        let properties: Vec<_> = ranking_terms(item, &ast)
+            .unwrap()
+            .into_iter()
+            .map(|term| term.property)
+            .collect();
+        assert_eq!(properties, vec!["title_vec", "body_vec"]);
+
+        // Subtracting a similarity ranks its nearest rows last
+        let ast = parse_cypher_query(
+            "MATCH (n:Doc) RETURN n.title \
+             ORDER BY vector_similarity(n.title_vec, [1.0, 0.0]) \
+             - vector_similarity(n.body_vec, [1.0, 0.0]) DESC LIMIT 5",
+        )
+        .unwrap();
+        let item = &ast.order_by.as_ref().unwrap().items[0];
+        assert!(ranking_terms(item, &ast).is_none());
+    }
+}
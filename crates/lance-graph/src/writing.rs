@@ -0,0 +1,2552 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Writing clauses: `CREATE`, `MERGE`, `SET`, `REMOVE` and `DELETE`
+//!
+//! A writing query is planned into a [`WritePlan`]: every node pattern that
+//! is not bound by the reading clauses becomes a row of its label's dataset,
+//! and every relationship a row of its type's dataset, keyed by the key
+//! columns of its endpoints. Property values must be literals or parameters;
+//! parameters are bound from the query's parameter map while planning.
+//!
+//! Without reading clauses the patterns are created once. Otherwise the
+//! reading clauses run first and the patterns are created once per matched
+//! row, attaching created relationships to the matched nodes. The rows are
+//! appended through a [`BufferedGraphWriter`] rooted at the namespace, one
+//! commit per dataset, so new datasets are laid out like [`DirNamespace`]
+//! expects them. Appends to an existing dataset are cast to its schema.
+//! Within [`CypherScript::execute_atomically`] the appended rows are staged
+//! in memory instead, where later statements of the script read them.
+//!
+//! MERGE writes its pattern the same way, but looks every row up by its
+//! identity first: nodes by the key columns of their label (and the label
+//! column of shared tables), relationships by their endpoint keys when the
+//! type disallows parallel edges and by all written columns otherwise. Rows
+//! that already exist are matched instead of written; an existing row with
+//! the same key but different property values is a conflict, and rows marked
+//! in the mapping's soft-delete column never match. Each element of the
+//! pattern is matched or created on its own. The remaining rows are inserted
+//! with a Lance merge-insert on their identity, so a row another writer
+//! committed after the lookup is matched rather than inserted twice. MERGEs
+//! on one dataset run one at a time within a process; Lance 1.0 treats
+//! concurrent inserts as compatible, so writers in other processes can still
+//! both create the same element.
+//!
+//! The result is a single row with the number of created nodes and
+//! relationships.
+//!
+//! SET is planned into a [`SetPlan`]: the reading clauses return the keys of
+//! every updated node, and each node variable becomes one Lance update of its
+//! label's dataset, restricted to the matched keys (and to the label's rows of
+//! a shared table). Properties are set from literals, parameters and
+//! parameter maps (`SET n += $changes`); `SET n:Label` moves a node to another
+//! label of the same shared table by rewriting its label column. Key
+//! properties cannot be set. The result holds the number of properties and
+//! labels set.
+//!
+//! REMOVE is planned the same way: `REMOVE n.prop` sets the property to null
+//! and `REMOVE n:Label` clears the label column of a shared table, leaving
+//! the row without a label. Removing a label the node does not have does
+//! nothing. The result holds the number of properties and labels removed.
+//!
+//! DELETE reads the keys of the matched nodes and the identities of the
+//! matched relationships the same way, then removes them with Lance deletes:
+//! relationships first, then nodes. Deleting a node that still has
+//! relationships fails unless the clause is `DETACH DELETE`, which first
+//! deletes them. A node's relationships are those whose type declares the
+//! node's label as its source or target label
+//! ([`RelationshipMapping::with_endpoints`]), so deleting nodes needs every
+//! relationship type to declare its endpoint labels.
+//! Labels and types with a soft delete column are marked deleted instead of
+//! removed. The result holds the number of deleted nodes and relationships.
+//!
+//! SET, REMOVE and DELETE record the versions of the datasets they change
+//! before reading the graph, and fail with [`GraphError::WriteConflict`]
+//! if another writer changed the rows they matched in the meantime (see
+//! [`crate::conflict`]).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lance_graph::{CypherQuery, DirNamespace};
+//!
+//! let query = CypherQuery::new(
+//!     "MATCH (m:Person {name: 'Bob'}) \
+//!      CREATE (n:Person {id: $id, name: $name})-[:KNOWS]->(m)",
+//! )?
+//! .with_config(config)
+//! .with_parameter("id", 3)
+//! .with_parameter("name", "Carol");
+//! let stats = query
+//!     .execute_with_namespace(DirNamespace::new("/data/graph"), None)
+//!     .await?;
+//!
+//! // Running this twice creates Carol once
+//! let merged = CypherQuery::new("MERGE (n:Person {id: 3, name: 'Carol'})")?
+//!     .with_config(config)
+//!     .execute_with_namespace(DirNamespace::new("/data/graph"), None)
+//!     .await?;
+//!
+//! let updated = CypherQuery::new("MATCH (n:Person {id: 3}) SET n.age = $age, n += $changes")?
+//!     .with_config(config)
+//!     .with_parameter("age", 29)
+//!     .with_parameter("changes", serde_json::json!({"city": "Lisbon"}))
+//!     .execute_with_namespace(DirNamespace::new("/data/graph"), None)
+//!     .await?;
+//!
+//! let removed = CypherQuery::new("MATCH (n:Person {id: 3}) REMOVE n.city")?
+//!     .with_config(config)
+//!     .execute_with_namespace(DirNamespace::new("/data/graph"), None)
+//!     .await?;
+//!
+//! let deleted = CypherQuery::new("MATCH (n:Person {id: 3}) DETACH DELETE n")?
+//!     .with_config(config)
+//!     .execute_with_namespace(DirNamespace::new("/data/graph"), None)
+//!     .await?;
+//! ```
+//!
+//! [`CypherScript::execute_atomically`]: crate::script::CypherScript::execute_atomically
+//! [`RelationshipMapping::with_endpoints`]: crate::config::RelationshipMapping::with_endpoints
+
+use crate::ast::{
+    CypherQuery as CypherAST, GraphPattern, NodePattern, PropertyMap, PropertyRef, PropertyValue,
+    ReadingClause, RelationshipDirection, RelationshipPattern, RemoveItem, ReturnClause,
+    ReturnItem, SetItem, ValueExpression,
+};
+use crate::config::GraphConfig;
+use crate::conflict::{commit_error, WriteBase};
+use crate::error::{GraphError, Result};
+use crate::graph_writer::{BufferedGraphWriter, WriterOptions};
+use crate::query::CypherQuery;
+use arrow::compute::concat_batches;
+use arrow_array::{ArrayRef, Int64Array, RecordBatch};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::logical_expr::{ident, lit, Expr};
+use datafusion::scalar::ScalarValue;
+use datafusion_sql::unparser::dialect::CustomDialectBuilder;
+use datafusion_sql::unparser::Unparser;
+use futures::TryStreamExt;
+use lance::dataset::{Dataset, UpdateBuilder};
+use lance_graph_catalog::DirNamespace;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+/// Column of a write result holding the number of created nodes
+pub const NODES_CREATED_COLUMN: &str = "nodes_created";
+
+/// Column of a write result holding the number of created relationships
+pub const RELATIONSHIPS_CREATED_COLUMN: &str = "relationships_created";
+
+/// Column of a DELETE result holding the number of deleted nodes
+pub const NODES_DELETED_COLUMN: &str = "nodes_deleted";
+
+/// Column of a DELETE result holding the number of deleted relationships
+pub const RELATIONSHIPS_DELETED_COLUMN: &str = "relationships_deleted";
+
+/// Column of a SET result holding the number of properties set
+pub const PROPERTIES_SET_COLUMN: &str = "properties_set";
+
+/// Column of a SET result holding the number of labels set
+pub const LABELS_SET_COLUMN: &str = "labels_set";
+
+/// Column of a REMOVE result holding the number of properties removed
+pub const PROPERTIES_REMOVED_COLUMN: &str = "properties_removed";
+
+/// Column of a REMOVE result holding the number of labels removed
+pub const LABELS_REMOVED_COLUMN: &str = "labels_removed";
+
+/// Column values of one row to write
+type Row = Vec<(String, ScalarValue)>;
+
+/// A node the clause writes for every matched row
+#[derive(Debug, Clone, PartialEq)]
+struct NodeWrite {
+    /// Dataset the node is appended to
+    table: String,
+    label: String,
+    values: Row,
+    /// Values of the label's key columns, in declaration order
+    keys: Vec<ScalarValue>,
+    /// Columns MERGE looks the node up by
+    identity: Vec<String>,
+}
+
+/// The node at one end of a created relationship
+#[derive(Debug, Clone, PartialEq)]
+enum Endpoint {
+    /// Index of a node created by the same clause
+    Created(usize),
+    /// Variable bound by the reading clauses
+    Bound(String),
+}
+
+/// A relationship the clause writes for every matched row
+#[derive(Debug, Clone, PartialEq)]
+struct RelationshipWrite {
+    rel_type: String,
+    source: Endpoint,
+    target: Endpoint,
+    source_columns: Vec<String>,
+    target_columns: Vec<String>,
+    values: Row,
+    /// Columns MERGE looks the relationship up by; `None` for all written
+    /// columns
+    identity: Option<Vec<String>>,
+}
+
+/// The rows a CREATE or MERGE query writes
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WritePlan {
+    /// Whether existing rows are matched instead of written (MERGE)
+    merge: bool,
+    nodes: Vec<NodeWrite>,
+    relationships: Vec<RelationshipWrite>,
+    /// Reading clauses returning the key columns of the bound endpoints;
+    /// `None` when the query has no reading clauses
+    reads: Option<CypherAST>,
+    /// Position of the first key column of each bound endpoint in the result
+    /// of `reads`
+    bound_columns: HashMap<String, usize>,
+}
+
+impl WritePlan {
+    /// Plan the CREATE or MERGE clause of `ast`
+    pub(crate) fn new(
+        ast: &CypherAST,
+        config: &GraphConfig,
+        parameters: &HashMap<String, serde_json::Value>,
+    ) -> Result<Self> {
+        let (clause, patterns) = match (&ast.create_clause, &ast.merge_clause) {
+            (Some(create), _) => ("CREATE", create.patterns.as_slice()),
+            (None, Some(merge)) => ("MERGE", std::slice::from_ref(&merge.pattern)),
+            (None, None) => {
+                return Err(GraphError::PlanError {
+                    message: "Query has no CREATE or MERGE clause".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        };
+
+        let mut planner = WritePlanner {
+            clause,
+            config,
+            parameters,
+            bound: bound_nodes(ast),
+            created: HashMap::new(),
+            nodes: Vec::new(),
+            relationships: Vec::new(),
+        };
+        for pattern in patterns {
+            match pattern {
+                GraphPattern::Node(node) => {
+                    planner.endpoint(node)?;
+                }
+                GraphPattern::Path(path) => {
+                    if path.variable.is_some() || path.shortest.is_some() {
+                        return Err(GraphError::UnsupportedFeature {
+                            feature: format!("path variables and shortest paths in {}", clause),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        });
+                    }
+                    let mut previous = planner.endpoint(&path.start_node)?;
+                    for segment in &path.segments {
+                        let next = planner.endpoint(&segment.end_node)?;
+                        planner.relationship(&segment.relationship, &previous, &next)?;
+                        previous = next;
+                    }
+                }
+            }
+        }
+
+        let WritePlanner {
+            nodes,
+            relationships,
+            bound,
+            ..
+        } = planner;
+        let (reads, bound_columns) = if ast.reading_clauses.is_empty() {
+            (None, HashMap::new())
+        } else {
+            let endpoints = relationships
+                .iter()
+                .flat_map(|rel| [&rel.source, &rel.target])
+                .filter_map(|endpoint| match endpoint {
+                    Endpoint::Bound(variable) => Some(variable),
+                    Endpoint::Created(_) => None,
+                });
+            let (reads, columns) = reading_query(ast, config, clause, &bound, endpoints)?;
+            (Some(reads), columns)
+        };
+        Ok(Self {
+            merge: ast.merge_clause.is_some(),
+            nodes,
+            relationships,
+            reads,
+            bound_columns,
+        })
+    }
+
+    /// Rows to append per dataset, in first-written order, for the rows
+    /// matched by the reading clauses (`None`: a single row without bindings)
+    fn rows(&self, matched: Option<&RecordBatch>) -> Result<Vec<(String, Vec<Row>)>> {
+        let num_rows = matched.map_or(1, RecordBatch::num_rows);
+        let mut tables: Vec<(String, Vec<Row>)> = Vec::new();
+        let mut push = |table: &str, row: Row| match tables.iter_mut().find(|(t, _)| t == table) {
+            Some((_, rows)) => rows.push(row),
+            None => tables.push((table.to_string(), vec![row])),
+        };
+
+        for row in 0..num_rows {
+            for node in &self.nodes {
+                push(&node.table, node.values.clone());
+            }
+            for rel in &self.relationships {
+                let mut values = rel.values.clone();
+                let ends = [
+                    (&rel.source, &rel.source_columns),
+                    (&rel.target, &rel.target_columns),
+                ];
+                for (endpoint, columns) in ends {
+                    let keys = self.endpoint_keys(endpoint, columns.len(), matched, row)?;
+                    values.extend(columns.iter().cloned().zip(keys));
+                }
+                push(&rel.rel_type, values);
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Datasets the clause writes to
+    fn tables(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .map(|node| node.table.as_str())
+            .chain(self.relationships.iter().map(|rel| rel.rel_type.as_str()))
+            .collect()
+    }
+
+    /// Columns MERGE looks up rows of `table` by; `None` for all written
+    /// columns
+    fn identity(&self, table: &str) -> Option<Vec<String>> {
+        match self.nodes.iter().find(|node| node.table == table) {
+            Some(node) => Some(node.identity.clone()),
+            None => self
+                .relationships
+                .iter()
+                .find(|rel| rel.rel_type == table)
+                .and_then(|rel| rel.identity.clone()),
+        }
+    }
+
+    /// Key values of `endpoint` in matched row `row`
+    fn endpoint_keys(
+        &self,
+        endpoint: &Endpoint,
+        width: usize,
+        matched: Option<&RecordBatch>,
+        row: usize,
+    ) -> Result<Vec<ScalarValue>> {
+        match endpoint {
+            Endpoint::Created(index) => Ok(self.nodes[*index].keys.clone()),
+            Endpoint::Bound(variable) => {
+                let (Some(batch), Some(first)) = (matched, self.bound_columns.get(variable)) else {
+                    return Err(GraphError::PlanError {
+                        message: format!("Variable '{}' is not bound by MATCH", variable),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                };
+                (*first..*first + width)
+                    .map(|column| Ok(ScalarValue::try_from_array(batch.column(column), row)?))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Walks the written patterns, resolving labels, types and property values
+struct WritePlanner<'a> {
+    /// Clause name for error messages
+    clause: &'static str,
+    config: &'a GraphConfig,
+    parameters: &'a HashMap<String, serde_json::Value>,
+    /// Labels of the node variables bound by the reading clauses
+    bound: HashMap<String, Option<String>>,
+    /// Node variables created so far, with their index in `nodes`
+    created: HashMap<String, usize>,
+    nodes: Vec<NodeWrite>,
+    relationships: Vec<RelationshipWrite>,
+}
+
+impl WritePlanner<'_> {
+    /// Resolve a node pattern to a bound or created node, creating it if new
+    fn endpoint(&mut self, node: &NodePattern) -> Result<Endpoint> {
+        if let Some(variable) = &node.variable {
+            let existing = match self.created.get(variable) {
+                Some(index) => Some(Endpoint::Created(*index)),
+                None => self
+                    .bound
+                    .contains_key(variable)
+                    .then(|| Endpoint::Bound(variable.clone())),
+            };
+            if let Some(existing) = existing {
+                if !node.labels.is_empty() || !node.properties.is_empty() {
+                    return Err(GraphError::InvalidPattern {
+                        message: format!(
+                            "{} cannot add labels or properties to the existing node '{}'",
+                            self.clause, variable
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    });
+                }
+                return Ok(existing);
+            }
+        }
+
+        let [label] = node.labels.as_slice() else {
+            return Err(GraphError::InvalidPattern {
+                message: format!(
+                    "{} needs exactly one label per new node, got {} for '{}'",
+                    self.clause,
+                    node.labels.len(),
+                    node.variable.as_deref().unwrap_or("anonymous node")
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+        let mapping =
+            self.config
+                .get_node_mapping(label)
+                .ok_or_else(|| GraphError::ConfigError {
+                    message: format!("Cannot create nodes of unknown label '{}'", label),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })?;
+        if let Some(base) = &mapping.view_of {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Cannot create nodes of view label '{}'; create '{}' nodes instead",
+                    label, base
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+
+        let mut values = self.values(&node.properties)?;
+        if let Some(column) = &mapping.label_column {
+            values.push((
+                column.clone(),
+                ScalarValue::Utf8(Some(mapping.label.clone())),
+            ));
+        }
+        let keys = mapping
+            .key_columns()
+            .into_iter()
+            .map(|key| {
+                values
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, value)| value.clone())
+                    .filter(|value| !value.is_null())
+                    .ok_or_else(|| GraphError::InvalidPattern {
+                        message: format!(
+                            "{} of a '{}' node must set its key property '{}'",
+                            self.clause, label, key
+                        ),
+                        location: snafu::Location::new(file!(), line!(), column!()),
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut identity: Vec<String> = mapping
+            .key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        identity.extend(mapping.label_column.clone());
+
+        self.nodes.push(NodeWrite {
+            table: mapping.table_name().to_string(),
+            label: mapping.label.clone(),
+            values,
+            keys,
+            identity,
+        });
+        let index = self.nodes.len() - 1;
+        if let Some(variable) = &node.variable {
+            self.created.insert(variable.clone(), index);
+        }
+        Ok(Endpoint::Created(index))
+    }
+
+    /// Add a relationship between `left` and `right` of a path pattern
+    fn relationship(
+        &mut self,
+        rel: &RelationshipPattern,
+        left: &Endpoint,
+        right: &Endpoint,
+    ) -> Result<()> {
+        let [rel_type] = rel.types.as_slice() else {
+            return Err(GraphError::InvalidPattern {
+                message: format!(
+                    "{} needs exactly one relationship type, got {}",
+                    self.clause,
+                    rel.types.len()
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+        if rel.length.is_some() {
+            return Err(GraphError::InvalidPattern {
+                message: format!("{} cannot write variable-length relationships", self.clause),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+        let (source, target) = match rel.direction {
+            RelationshipDirection::Outgoing => (left, right),
+            RelationshipDirection::Incoming => (right, left),
+            RelationshipDirection::Undirected => {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "{} needs a direction for relationship '{}'",
+                        self.clause, rel_type
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        };
+        let mapping = self
+            .config
+            .get_relationship_mapping(rel_type)
+            .ok_or_else(|| GraphError::ConfigError {
+                message: format!("Cannot create relationships of unknown type '{}'", rel_type),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+
+        let source_columns: Vec<String> = mapping
+            .source_key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let target_columns: Vec<String> = mapping
+            .target_key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        for (endpoint, columns) in [(source, &source_columns), (target, &target_columns)] {
+            let width = self.key_width(endpoint)?;
+            if width != columns.len() {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "'{}' references its endpoints by {} column(s), but the node is keyed by {}",
+                        rel_type,
+                        columns.len(),
+                        width
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+
+        self.relationships.push(RelationshipWrite {
+            rel_type: mapping.relationship_type.clone(),
+            source: source.clone(),
+            target: target.clone(),
+            source_columns,
+            target_columns,
+            values: self.values(&rel.properties)?,
+            identity: (!mapping.allow_parallel_edges).then(|| {
+                mapping
+                    .edge_key_columns()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            }),
+        });
+        Ok(())
+    }
+
+    /// Number of key columns of the node at `endpoint`
+    fn key_width(&self, endpoint: &Endpoint) -> Result<usize> {
+        match endpoint {
+            Endpoint::Created(index) => Ok(self.nodes[*index].keys.len()),
+            Endpoint::Bound(variable) => {
+                Ok(bound_key_columns(self.config, self.clause, &self.bound, variable)?.len())
+            }
+        }
+    }
+
+    /// Resolve a property map to column values, in property name order
+    fn values(&self, properties: &HashMap<String, PropertyValue>) -> Result<Row> {
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let value = scalar(&properties[name], self.parameters, self.clause)?;
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+}
+
+/// `value` as a scalar, binding parameters from `parameters`
+fn scalar(
+    value: &PropertyValue,
+    parameters: &HashMap<String, serde_json::Value>,
+    clause: &str,
+) -> Result<ScalarValue> {
+    Ok(match value {
+        PropertyValue::String(s) => ScalarValue::Utf8(Some(s.clone())),
+        PropertyValue::Integer(i) => ScalarValue::Int64(Some(*i)),
+        PropertyValue::Float(f) => ScalarValue::Float64(Some(*f)),
+        PropertyValue::Boolean(b) => ScalarValue::Boolean(Some(*b)),
+        PropertyValue::Null => ScalarValue::Null,
+        PropertyValue::Parameter(name) => {
+            let value = parameters.get(name).ok_or_else(|| GraphError::PlanError {
+                message: format!("Missing value for parameter ${}", name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+            let literal = PropertyValue::from_json(value).ok_or_else(|| GraphError::PlanError {
+                message: format!(
+                    "Parameter ${} used as a property value must be a scalar, got {}",
+                    name, value
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })?;
+            return scalar(&literal, parameters, clause);
+        }
+        PropertyValue::Property(PropertyRef { variable, property }) => {
+            return Err(GraphError::UnsupportedFeature {
+                feature: format!(
+                    "property reference {}.{} as a {} property value",
+                    variable, property, clause
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+        }
+    })
+}
+
+/// A node variable bound by the reading clauses, located in its dataset
+#[derive(Debug, Clone, PartialEq)]
+struct BoundNode {
+    variable: String,
+    /// Label owning the dataset, i.e. the variable's label with views resolved
+    label: String,
+    /// Dataset the node's label is stored in
+    table: String,
+    key_columns: Vec<String>,
+    /// Label column and label keeping the variable's rows of a shared table
+    label_filter: Option<(String, String)>,
+    /// Column marking deleted rows, if the label soft-deletes
+    soft_delete_column: Option<String>,
+}
+
+impl BoundNode {
+    /// Filter selecting the live rows of this node with one of `keys`
+    fn filter(&self, schema: &ArrowSchema, keys: &[Vec<ScalarValue>]) -> Result<Expr> {
+        let mut filter = key_filter(schema, &self.table, &self.key_columns, keys)?;
+        if let Some((column, label)) = &self.label_filter {
+            let field = schema_field(schema, &self.table, column)?;
+            filter = filter.and(ident(field.name()).eq(lit(label.clone())));
+        }
+        if let Some(column) = &self.soft_delete_column {
+            let field = schema_field(schema, &self.table, column)?;
+            filter = filter.and(ident(field.name()).is_null());
+        }
+        Ok(filter)
+    }
+}
+
+/// The updates of one node variable of a SET or REMOVE clause
+#[derive(Debug, Clone, PartialEq)]
+struct NodeUpdate {
+    node: BoundNode,
+    /// Properties to set, null for removed ones; later items override
+    /// earlier ones
+    values: Row,
+    /// Label column and new label, for `SET n:Label`
+    relabel: Option<(String, String)>,
+    /// Label column to clear, for `REMOVE n:Label`
+    unlabel: Option<String>,
+}
+
+/// The updates a SET or REMOVE query applies to the matched nodes
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SetPlan {
+    /// Whether the clause is REMOVE
+    remove: bool,
+    updates: Vec<NodeUpdate>,
+    /// Reading clauses returning the key columns of the updated nodes
+    reads: CypherAST,
+    /// Position of the first key column of each updated node in the result
+    /// of `reads`
+    bound_columns: HashMap<String, usize>,
+}
+
+impl SetPlan {
+    /// Plan the SET or REMOVE clause of `ast`
+    pub(crate) fn new(
+        ast: &CypherAST,
+        config: &GraphConfig,
+        parameters: &HashMap<String, serde_json::Value>,
+    ) -> Result<Self> {
+        let clause = match (&ast.set_clause, &ast.remove_clause) {
+            (Some(_), _) => "SET",
+            (None, Some(_)) => "REMOVE",
+            (None, None) => {
+                return Err(GraphError::PlanError {
+                    message: "Query has no SET or REMOVE clause".to_string(),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                })
+            }
+        };
+        require_reading_clauses(ast, clause, "nodes to update")?;
+
+        let bound = bound_nodes(ast);
+        let mut updates: Vec<NodeUpdate> = Vec::new();
+        for item in ast.set_clause.iter().flat_map(|set| &set.items) {
+            let variable = item.variable();
+            let update = node_update(&mut updates, config, &bound, clause, variable)?;
+            match item {
+                SetItem::Property { target, value } => {
+                    let name = property_name(&target.property, parameters)?;
+                    update.set(name, scalar(value, parameters, "SET")?);
+                }
+                SetItem::Properties {
+                    properties: PropertyMap::Literal(properties),
+                    ..
+                } => {
+                    let mut names: Vec<&String> = properties.keys().collect();
+                    names.sort();
+                    for name in names {
+                        let value = scalar(&properties[name], parameters, "SET")?;
+                        update.set(name.clone(), value);
+                    }
+                }
+                SetItem::Properties {
+                    properties: PropertyMap::Parameter(param),
+                    ..
+                } => {
+                    let Some(serde_json::Value::Object(map)) = parameters.get(param) else {
+                        return Err(GraphError::PlanError {
+                            message: format!(
+                                "Parameter ${} used in SET {} += ${} must be a map",
+                                param, variable, param
+                            ),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        });
+                    };
+                    for (name, value) in map {
+                        let literal = PropertyValue::from_json(value).ok_or_else(|| {
+                            GraphError::PlanError {
+                                message: format!(
+                                    "Property '{}' of parameter ${} must be a scalar, got {}",
+                                    name, param, value
+                                ),
+                                location: snafu::Location::new(file!(), line!(), column!()),
+                            }
+                        })?;
+                        update.set(name.clone(), scalar(&literal, parameters, "SET")?);
+                    }
+                }
+                SetItem::Labels { labels, .. } => {
+                    let [label] = labels.as_slice() else {
+                        return Err(GraphError::UnsupportedFeature {
+                            feature: format!(
+                                "setting several labels on '{}'; a node has a single label",
+                                variable
+                            ),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        });
+                    };
+                    update.relabel = Some(relabel(config, &update.node, label)?);
+                }
+            }
+        }
+        for item in ast.remove_clause.iter().flat_map(|remove| &remove.items) {
+            let update = node_update(&mut updates, config, &bound, clause, item.variable())?;
+            match item {
+                RemoveItem::Property { target } => {
+                    let name = property_name(&target.property, parameters)?;
+                    update.set(name, ScalarValue::Null);
+                }
+                RemoveItem::Labels { labels, .. } => {
+                    for label in labels {
+                        if let Some(column) = unlabel(config, &update.node, label)? {
+                            update.unlabel = Some(column);
+                        }
+                    }
+                }
+            }
+        }
+
+        for update in &updates {
+            if let Some((name, _)) = update.values.iter().find(|(name, _)| {
+                update
+                    .node
+                    .key_columns
+                    .iter()
+                    .any(|key| key.eq_ignore_ascii_case(name))
+            }) {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "{} cannot change the key property '{}' of '{}'",
+                        clause, name, update.node.variable
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+        }
+
+        let variables = updates.iter().map(|update| &update.node.variable);
+        let (reads, bound_columns) = reading_query(ast, config, clause, &bound, variables)?;
+        Ok(Self {
+            remove: clause == "REMOVE",
+            updates,
+            reads,
+            bound_columns,
+        })
+    }
+
+    /// Datasets the clause writes to
+    fn tables(&self) -> Vec<&str> {
+        self.updates
+            .iter()
+            .map(|update| update.node.table.as_str())
+            .collect()
+    }
+}
+
+impl NodeUpdate {
+    /// Set property `name` to `value`, replacing an earlier value
+    fn set(&mut self, name: String, value: ScalarValue) {
+        match self
+            .values
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(&name))
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.values.push((name, value)),
+        }
+    }
+
+    /// Apply the update to the rows of `dataset` with one of `keys`; returns
+    /// the number of updated rows
+    async fn apply(
+        &self,
+        dataset: Dataset,
+        keys: &[Vec<ScalarValue>],
+        base: &mut WriteBase,
+    ) -> Result<u64> {
+        let schema = ArrowSchema::from(dataset.schema());
+        let filter = lance_sql(&self.node.filter(&schema, keys)?)?;
+        base.check(&dataset, &self.node.table, &filter).await?;
+        let mut builder = UpdateBuilder::new(Arc::new(dataset)).update_where(&filter)?;
+        for (name, value) in self.values.iter().chain(&self.relabel_value()) {
+            let field = schema_field(&schema, &self.node.table, name)?;
+            if value.is_null() && !field.is_nullable() {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "Cannot clear column '{}' of {}, which is not nullable",
+                        field.name(),
+                        self.node.table
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            let value = lance_sql(&lit(value.cast_to(field.data_type())?))?;
+            builder = builder.set(field.name(), &value)?;
+        }
+        let result = builder
+            .build()?
+            .execute()
+            .await
+            .map_err(|e| commit_error(&self.node.table, e))?;
+        base.advance(&self.node.table, result.new_dataset.version().version);
+        Ok(result.rows_updated)
+    }
+
+    /// The label column value written by `SET n:Label` or `REMOVE n:Label`,
+    /// if any
+    fn relabel_value(&self) -> Option<(String, ScalarValue)> {
+        let relabel = self
+            .relabel
+            .as_ref()
+            .map(|(column, label)| (column.clone(), ScalarValue::Utf8(Some(label.clone()))));
+        relabel.or_else(|| {
+            self.unlabel
+                .as_ref()
+                .map(|column| (column.clone(), ScalarValue::Utf8(None)))
+        })
+    }
+}
+
+/// The update of bound node `variable` in `updates`, added if it has none yet
+fn node_update<'u>(
+    updates: &'u mut Vec<NodeUpdate>,
+    config: &GraphConfig,
+    bound: &HashMap<String, Option<String>>,
+    clause: &str,
+    variable: &str,
+) -> Result<&'u mut NodeUpdate> {
+    let index = match updates.iter().position(|u| u.node.variable == variable) {
+        Some(index) => index,
+        None => {
+            let Some(node) = bound_node(config, bound, clause, variable)? else {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "{} can only update nodes bound by MATCH, and '{}' is not one",
+                        clause, variable
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            };
+            updates.push(NodeUpdate {
+                node,
+                values: Vec::new(),
+                relabel: None,
+                unlabel: None,
+            });
+            updates.len() - 1
+        }
+    };
+    Ok(&mut updates[index])
+}
+
+/// A relationship variable of a DELETE clause
+#[derive(Debug, Clone, PartialEq)]
+struct RelationshipDelete {
+    variable: String,
+    rel_type: String,
+    /// Columns identifying the relationship; `None` for all columns of its
+    /// dataset
+    identity: Option<Vec<String>>,
+    /// Column marking deleted rows, if the type soft-deletes
+    soft_delete_column: Option<String>,
+}
+
+/// The nodes and relationships a DELETE query removes
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DeletePlan {
+    /// Whether relationships of deleted nodes are deleted with them
+    detach: bool,
+    nodes: Vec<BoundNode>,
+    relationships: Vec<RelationshipDelete>,
+}
+
+impl DeletePlan {
+    /// Plan the DELETE clause of `ast`
+    pub(crate) fn new(ast: &CypherAST, config: &GraphConfig) -> Result<Self> {
+        let Some(delete) = &ast.delete_clause else {
+            return Err(GraphError::PlanError {
+                message: "Query has no DELETE clause".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+        require_reading_clauses(ast, "DELETE", "elements to delete")?;
+
+        let bound = bound_nodes(ast);
+        let bound_rels = bound_relationships(ast);
+        let mut nodes: Vec<BoundNode> = Vec::new();
+        let mut relationships: Vec<RelationshipDelete> = Vec::new();
+        for variable in &delete.variables {
+            if nodes.iter().any(|node| &node.variable == variable)
+                || relationships.iter().any(|rel| &rel.variable == variable)
+            {
+                continue;
+            }
+            if let Some(node) = bound_node(config, &bound, "DELETE", variable)? {
+                nodes.push(node);
+                continue;
+            }
+            let Some(rel) = bound_rels.get(variable) else {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "DELETE can only delete nodes and relationships bound by MATCH, and '{}' is not one",
+                        variable
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            };
+            if rel.length.is_some() {
+                return Err(GraphError::UnsupportedFeature {
+                    feature: format!("DELETE of the variable-length relationship '{}'", variable),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            let [rel_type] = rel.types.as_slice() else {
+                return Err(GraphError::InvalidPattern {
+                    message: format!(
+                        "DELETE needs exactly one type on relationship '{}', got {}",
+                        variable,
+                        rel.types.len()
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            };
+            let mapping = config.get_relationship_mapping(rel_type).ok_or_else(|| {
+                GraphError::ConfigError {
+                    message: format!("No relationship mapping for type '{}'", rel_type),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                }
+            })?;
+            relationships.push(RelationshipDelete {
+                variable: variable.clone(),
+                rel_type: mapping.relationship_type.clone(),
+                identity: (!mapping.allow_parallel_edges).then(|| {
+                    mapping
+                        .edge_key_columns()
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect()
+                }),
+                soft_delete_column: mapping.soft_delete_column.clone(),
+            });
+        }
+        Ok(Self {
+            detach: delete.detach,
+            nodes,
+            relationships,
+        })
+    }
+
+    /// Datasets the clause writes to; DETACH DELETE and the check for
+    /// remaining relationships may touch every relationship type
+    fn tables<'a>(&'a self, config: &'a GraphConfig) -> Vec<&'a str> {
+        let mut tables: Vec<&str> = self.nodes.iter().map(|node| node.table.as_str()).collect();
+        if !self.nodes.is_empty() {
+            tables.extend(
+                config
+                    .relationship_mappings
+                    .values()
+                    .map(|mapping| mapping.relationship_type.as_str()),
+            );
+        }
+        tables.extend(self.relationships.iter().map(|rel| rel.rel_type.as_str()));
+        tables
+    }
+}
+
+/// Fail unless `ast` has reading clauses binding the elements `clause` writes
+fn require_reading_clauses(ast: &CypherAST, clause: &str, elements: &str) -> Result<()> {
+    if ast.reading_clauses.is_empty() {
+        return Err(GraphError::InvalidPattern {
+            message: format!("{} needs a MATCH clause binding the {}", clause, elements),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    }
+    Ok(())
+}
+
+/// Bound node `variable` located in its dataset; `None` if the reading
+/// clauses bind no node of that name
+fn bound_node(
+    config: &GraphConfig,
+    bound: &HashMap<String, Option<String>>,
+    clause: &str,
+    variable: &str,
+) -> Result<Option<BoundNode>> {
+    let label = match bound.get(variable) {
+        Some(Some(label)) => label,
+        Some(None) => {
+            return Err(GraphError::InvalidPattern {
+                message: format!("{} needs a label on '{}' in MATCH", clause, variable),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+        }
+        None => return Ok(None),
+    };
+    let (base, _) = config.resolve_view_chain(label)?;
+    let mapping = config
+        .get_node_mapping(&base)
+        .ok_or_else(|| GraphError::ConfigError {
+            message: format!("No node mapping for label '{}'", label),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    Ok(Some(BoundNode {
+        variable: variable.to_string(),
+        label: mapping.label.clone(),
+        table: mapping.table_name().to_string(),
+        key_columns: mapping
+            .key_columns()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        label_filter: mapping
+            .label_column
+            .clone()
+            .map(|column| (column, mapping.label.clone())),
+        soft_delete_column: mapping.soft_delete_column.clone(),
+    }))
+}
+
+/// Label column and label that move `node` to `label`; only labels sharing
+/// the node's table through a label column can be set
+fn relabel(config: &GraphConfig, node: &BoundNode, label: &str) -> Result<(String, String)> {
+    let mapping = config
+        .get_node_mapping(label)
+        .ok_or_else(|| GraphError::ConfigError {
+            message: format!("Cannot set unknown label '{}'", label),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    match (&node.label_filter, &mapping.label_column) {
+        (Some((column, _)), Some(target))
+            if mapping.view_of.is_none()
+                && mapping.table_name() == node.table
+                && target.eq_ignore_ascii_case(column) =>
+        {
+            Ok((column.clone(), mapping.label.clone()))
+        }
+        _ => Err(GraphError::UnsupportedFeature {
+            feature: format!(
+                "SET {}:{}; only labels sharing the node's table through a label column can be set",
+                node.variable, label
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        }),
+    }
+}
+
+/// Label column cleared by removing `label` from `node`; `None` when the
+/// node does not have the label
+fn unlabel(config: &GraphConfig, node: &BoundNode, label: &str) -> Result<Option<String>> {
+    let mapping = config
+        .get_node_mapping(label)
+        .ok_or_else(|| GraphError::ConfigError {
+            message: format!("Cannot remove unknown label '{}'", label),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    match &node.label_filter {
+        Some((column, current)) if mapping.view_of.is_none() => Ok(mapping
+            .label
+            .eq_ignore_ascii_case(current)
+            .then(|| column.clone())),
+        _ => Err(GraphError::UnsupportedFeature {
+            feature: format!(
+                "REMOVE {}:{}; only labels stored in a label column can be removed",
+                node.variable, label
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        }),
+    }
+}
+
+/// Property `name`, bound from a string parameter when written as `$name`
+fn property_name(name: &str, parameters: &HashMap<String, serde_json::Value>) -> Result<String> {
+    let Some(param) = name.strip_prefix('$') else {
+        return Ok(name.to_string());
+    };
+    match parameters.get(param) {
+        Some(serde_json::Value::String(name)) => Ok(name.clone()),
+        Some(other) => Err(GraphError::PlanError {
+            message: format!(
+                "Parameter ${} used as a property name must be a string, got {}",
+                param, other
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        }),
+        None => Err(GraphError::PlanError {
+            message: format!("Missing value for parameter ${}", param),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        }),
+    }
+}
+
+/// Field `name` of the dataset of `table`, matched case-insensitively
+fn schema_field(schema: &ArrowSchema, table: &str, name: &str) -> Result<Field> {
+    schema
+        .fields()
+        .iter()
+        .find(|f| f.name().eq_ignore_ascii_case(name))
+        .map(|f| f.as_ref().clone())
+        .ok_or_else(|| GraphError::ExecutionError {
+            message: format!("'{}' has no column '{}'", table, name),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+}
+
+/// Keys per IN list of a key filter
+const KEY_FILTER_BATCH: usize = 1024;
+
+/// Filter selecting the rows whose `columns` hold one of `keys`
+///
+/// Single-column keys are looked up in IN lists of at most
+/// [`KEY_FILTER_BATCH`] values, composite keys by the equalities of their
+/// columns; the lookups are combined in a balanced disjunction, so large key
+/// sets do not nest deeply.
+pub(crate) fn key_filter(
+    schema: &ArrowSchema,
+    table: &str,
+    columns: &[String],
+    keys: &[Vec<ScalarValue>],
+) -> Result<Expr> {
+    let fields = columns
+        .iter()
+        .map(|name| schema_field(schema, table, name))
+        .collect::<Result<Vec<_>>>()?;
+    let equals = |field: &Field, value: &ScalarValue| -> Result<Expr> {
+        if value.is_null() {
+            return Ok(ident(field.name()).is_null());
+        }
+        Ok(ident(field.name()).eq(lit(value.cast_to(field.data_type())?)))
+    };
+    let lookups = match fields.as_slice() {
+        [field] => {
+            let mut values = Vec::with_capacity(keys.len());
+            let mut null = false;
+            for value in keys.iter().map(|key| &key[0]) {
+                if value.is_null() {
+                    null = true;
+                } else {
+                    values.push(lit(value.cast_to(field.data_type())?));
+                }
+            }
+            let mut lookups: Vec<Expr> = values
+                .chunks(KEY_FILTER_BATCH)
+                .map(|batch| ident(field.name()).in_list(batch.to_vec(), false))
+                .collect();
+            if null {
+                lookups.push(ident(field.name()).is_null());
+            }
+            lookups
+        }
+        _ => keys
+            .iter()
+            .map(|key| {
+                let conjuncts = fields
+                    .iter()
+                    .zip(key)
+                    .map(|(field, value)| equals(field, value))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(conjuncts
+                    .into_iter()
+                    .reduce(Expr::and)
+                    .unwrap_or_else(|| lit(true)))
+            })
+            .collect::<Result<Vec<_>>>()?,
+    };
+    Ok(balanced_or(lookups))
+}
+
+/// The disjunction of `exprs` as a balanced tree; `false` if there are none
+pub(crate) fn balanced_or(mut exprs: Vec<Expr>) -> Expr {
+    match exprs.len() {
+        0 => lit(false),
+        1 => exprs.pop().unwrap(),
+        len => {
+            let right = exprs.split_off(len / 2);
+            balanced_or(exprs).or(balanced_or(right))
+        }
+    }
+}
+
+/// `expr` as the SQL text Lance's filters, updates and deletes take, with
+/// identifiers quoted in backticks
+pub(crate) fn lance_sql(expr: &Expr) -> Result<String> {
+    let dialect = CustomDialectBuilder::new()
+        .with_identifier_quote_style('`')
+        .build();
+    Ok(Unparser::new(&dialect).expr_to_sql(expr)?.to_string())
+}
+
+/// Value marking a row deleted in a soft delete column of `data_type`:
+/// `true`, or the current time in milliseconds since the Unix epoch
+fn deletion_mark(data_type: &DataType) -> Result<ScalarValue> {
+    if data_type == &DataType::Boolean {
+        return Ok(ScalarValue::Boolean(Some(true)));
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default();
+    Ok(ScalarValue::Int64(Some(now)).cast_to(data_type)?)
+}
+
+/// Delete the rows of `dataset` matching `filter`, or mark them in
+/// `soft_delete_column`; returns the number of deleted rows
+async fn delete_rows(
+    mut dataset: Dataset,
+    table: &str,
+    filter: &str,
+    soft_delete_column: Option<&str>,
+    base: &mut WriteBase,
+) -> Result<u64> {
+    base.check(&dataset, table, filter).await?;
+    match soft_delete_column {
+        Some(column) => {
+            let field = schema_field(&ArrowSchema::from(dataset.schema()), table, column)?;
+            let mark = lance_sql(&lit(deletion_mark(field.data_type())?))?;
+            let result = UpdateBuilder::new(Arc::new(dataset))
+                .update_where(filter)?
+                .set(field.name(), &mark)?
+                .build()?
+                .execute()
+                .await
+                .map_err(|e| commit_error(table, e))?;
+            base.advance(table, result.new_dataset.version().version);
+            Ok(result.rows_updated)
+        }
+        None => {
+            let rows = dataset.count_rows(Some(filter.to_string())).await? as u64;
+            if rows > 0 {
+                dataset
+                    .delete(filter)
+                    .await
+                    .map_err(|e| commit_error(table, e))?;
+                base.advance(table, dataset.version().version);
+            }
+            Ok(rows)
+        }
+    }
+}
+
+/// Node variables bound by the MATCH clauses of `ast`, with their first label
+fn bound_nodes(ast: &CypherAST) -> HashMap<String, Option<String>> {
+    let mut bound: HashMap<String, Option<String>> = HashMap::new();
+    let mut bind = |node: &NodePattern| {
+        if let Some(variable) = &node.variable {
+            let label = bound.entry(variable.clone()).or_default();
+            if label.is_none() {
+                *label = node.labels.first().cloned();
+            }
+        }
+    };
+    for clause in &ast.reading_clauses {
+        let ReadingClause::Match(match_clause) = clause else {
+            continue;
+        };
+        for pattern in &match_clause.patterns {
+            match pattern {
+                GraphPattern::Node(node) => bind(node),
+                GraphPattern::Path(path) => {
+                    bind(&path.start_node);
+                    for segment in &path.segments {
+                        bind(&segment.end_node);
+                    }
+                }
+            }
+        }
+    }
+    bound
+}
+
+/// Relationship variables bound by the MATCH clauses of `ast`
+fn bound_relationships(ast: &CypherAST) -> HashMap<String, &RelationshipPattern> {
+    let mut bound = HashMap::new();
+    for clause in &ast.reading_clauses {
+        let ReadingClause::Match(match_clause) = clause else {
+            continue;
+        };
+        for pattern in &match_clause.patterns {
+            let GraphPattern::Path(path) = pattern else {
+                continue;
+            };
+            for segment in &path.segments {
+                if let Some(variable) = &segment.relationship.variable {
+                    bound
+                        .entry(variable.clone())
+                        .or_insert(&segment.relationship);
+                }
+            }
+        }
+    }
+    bound
+}
+
+/// Key columns of the label of bound node `variable`
+fn bound_key_columns<'a>(
+    config: &'a GraphConfig,
+    clause: &str,
+    bound: &HashMap<String, Option<String>>,
+    variable: &str,
+) -> Result<Vec<&'a str>> {
+    let Some(Some(label)) = bound.get(variable) else {
+        return Err(GraphError::InvalidPattern {
+            message: format!(
+                "{} needs a label on '{}' in MATCH to attach relationships to it",
+                clause, variable
+            ),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        });
+    };
+    let (base, _) = config.resolve_view_chain(label)?;
+    let mapping = config
+        .get_node_mapping(&base)
+        .ok_or_else(|| GraphError::ConfigError {
+            message: format!("No node mapping for label '{}'", label),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })?;
+    Ok(mapping.key_columns())
+}
+
+/// The reading clauses of `ast` returning the key columns of every bound
+/// node in `variables`; also returns where each node's keys start
+fn reading_query<'a>(
+    ast: &CypherAST,
+    config: &GraphConfig,
+    clause: &str,
+    bound: &HashMap<String, Option<String>>,
+    variables: impl IntoIterator<Item = &'a String>,
+) -> Result<(CypherAST, HashMap<String, usize>)> {
+    let mut columns: Vec<(String, Vec<String>)> = Vec::new();
+    for variable in variables {
+        if columns.iter().any(|(existing, _)| existing == variable) {
+            continue;
+        }
+        let keys = bound_key_columns(config, clause, bound, variable)?;
+        columns.push((
+            variable.clone(),
+            keys.into_iter().map(str::to_string).collect(),
+        ));
+    }
+    Ok(returning(ast, &columns))
+}
+
+/// The reading clauses of `ast` returning the given properties of each
+/// variable; also returns where each variable's properties start
+fn returning(
+    ast: &CypherAST,
+    columns: &[(String, Vec<String>)],
+) -> (CypherAST, HashMap<String, usize>) {
+    let mut items = Vec::new();
+    let mut positions = HashMap::new();
+    for (variable, properties) in columns {
+        positions.insert(variable.clone(), items.len());
+        for property in properties {
+            items.push(ReturnItem {
+                expression: ValueExpression::Property(PropertyRef {
+                    variable: variable.clone(),
+                    property: property.clone(),
+                }),
+                alias: Some(format!("__write_{}", items.len())),
+            });
+        }
+    }
+    // Without properties only the number of matched rows matters
+    if items.is_empty() {
+        items.push(ReturnItem {
+            expression: ValueExpression::Literal(PropertyValue::Integer(1)),
+            alias: Some("__write_0".to_string()),
+        });
+    }
+
+    let reads = CypherAST {
+        return_clause: ReturnClause {
+            distinct: false,
+            distinct_on: Vec::new(),
+            items,
+        },
+        create_clause: None,
+        merge_clause: None,
+        set_clause: None,
+        remove_clause: None,
+        delete_clause: None,
+        call_subquery: None,
+        ..ast.clone()
+    };
+    (reads, positions)
+}
+
+/// Datasets the CREATE, MERGE, SET, REMOVE or DELETE clause of `query` may
+/// write to; empty for read-only queries
+pub(crate) fn written_tables(query: &CypherQuery) -> Result<Vec<String>> {
+    let ast = query.ast();
+    let sets = ast.set_clause.is_some() || ast.remove_clause.is_some();
+    let writes = ast.create_clause.is_some() || ast.merge_clause.is_some();
+    if !sets && !writes && ast.delete_clause.is_none() {
+        return Ok(Vec::new());
+    }
+    let config = query.require_config()?;
+    let bound = query.bind_label_parameters()?;
+    let (set_plan, delete_plan, write_plan);
+    // Clauses take precedence as when the query is executed
+    let tables = if sets {
+        set_plan = SetPlan::new(bound.ast(), config, bound.parameters())?;
+        set_plan.tables()
+    } else if ast.delete_clause.is_some() {
+        delete_plan = DeletePlan::new(bound.ast(), config)?;
+        delete_plan.tables(config)
+    } else {
+        write_plan = WritePlan::new(bound.ast(), config, bound.parameters())?;
+        write_plan.tables()
+    };
+    Ok(tables.into_iter().map(str::to_string).collect())
+}
+
+/// Run the CREATE or MERGE clause of `query`, appending its new rows to the
+/// datasets of `namespace`
+pub(crate) async fn execute_writes(
+    query: &CypherQuery,
+    namespace: Arc<DirNamespace>,
+) -> Result<RecordBatch> {
+    let config = query.require_config()?;
+    let query = query.bind_label_parameters()?;
+    let plan = WritePlan::new(query.ast(), config, query.parameters())?;
+
+    let matched = match &plan.reads {
+        Some(reads) => {
+            let batches = collect_reads(&query, &namespace, reads).await?;
+            let schema = match batches.first() {
+                Some(batch) => batch.schema(),
+                None => Arc::new(ArrowSchema::empty()),
+            };
+            Some(concat_batches(&schema, &batches)?)
+        }
+        None => None,
+    };
+
+    // Inside a write transaction the rows are staged instead of committed,
+    // so nothing may be flushed while they are buffered
+    let options = match query.delta() {
+        Some(_) => WriterOptions::default()
+            .with_max_buffered_rows(usize::MAX)
+            .with_max_buffer_age(Duration::MAX),
+        None => WriterOptions::default(),
+    };
+    let mut writer =
+        BufferedGraphWriter::with_namespace(config.clone(), (*namespace).clone(), options);
+    let mut nodes_created = 0;
+    let mut relationships_created = 0;
+    for (table, mut rows) in plan.rows(matched.as_ref())? {
+        let uri = writer.table_uri(&table);
+        let staged = query
+            .delta()
+            .map(|delta| delta.batches(&table))
+            .unwrap_or_default();
+        let node = plan.nodes.iter().find(|node| node.table == table);
+
+        // Outside a write transaction, the MERGEs of this process on a
+        // dataset run one at a time from looking rows up to committing
+        let lock = (plan.merge && query.delta().is_none()).then(|| merge_lock(&uri));
+        let _guard = match &lock {
+            Some(lock) => Some(lock.lock().await),
+            None => None,
+        };
+        let dataset = writer.open_dataset(&table).await?;
+        let mut merge_keys = None;
+        if plan.merge {
+            let identity = plan.identity(&table);
+            let soft_delete_column = match node {
+                Some(node) => config
+                    .get_node_mapping(&node.label)
+                    .and_then(|mapping| mapping.soft_delete_column.clone()),
+                None => config
+                    .get_relationship_mapping(&table)
+                    .and_then(|mapping| mapping.soft_delete_column.clone()),
+            };
+            rows = unmatched_rows(
+                dataset.as_ref(),
+                &table,
+                identity.as_deref(),
+                soft_delete_column.as_deref(),
+                &staged,
+                rows,
+            )
+            .await?;
+            if rows.is_empty() {
+                continue;
+            }
+            // Rows staged in a write transaction go to its private copy like
+            // created ones; other rows are inserted with a merge-insert on
+            // the identity, so rows another writer committed since the
+            // lookup are matched instead of duplicated
+            if query.delta().is_none() {
+                let mut keys = identity.unwrap_or_else(|| written_columns(&rows));
+                keys.extend(soft_delete_column);
+                merge_keys = Some(keys);
+            }
+        }
+
+        let staged_schema = staged.first().map(RecordBatch::schema);
+        let batch = table_batch(dataset.as_ref(), &table, staged_schema, &rows)?;
+        match (node, merge_keys) {
+            (Some(node), None) => {
+                nodes_created += batch.num_rows() as u64;
+                writer.create_nodes(&node.label, batch).await?;
+            }
+            (None, None) => {
+                relationships_created += batch.num_rows() as u64;
+                writer.create_relationships(&table, batch).await?;
+            }
+            (Some(node), Some(keys)) => {
+                nodes_created += writer.merge_nodes(&node.label, keys, batch).await?;
+            }
+            (None, Some(keys)) => {
+                relationships_created += writer.merge_relationships(&table, keys, batch).await?;
+            }
+        }
+    }
+    if let Some(delta) = query.delta() {
+        for (table, batches) in writer.take_appends() {
+            let new = writer.open_dataset(&table).await?.is_none();
+            delta.append(&table, new, batches);
+        }
+    }
+    writer.flush().await?;
+
+    counters(&[
+        (NODES_CREATED_COLUMN, nodes_created),
+        (RELATIONSHIPS_CREATED_COLUMN, relationships_created),
+    ])
+}
+
+/// Run the SET or REMOVE clause of `query`, updating the matched nodes in
+/// the datasets of `namespace`
+pub(crate) async fn execute_set(
+    query: &CypherQuery,
+    namespace: Arc<DirNamespace>,
+) -> Result<RecordBatch> {
+    let config = query.require_config()?;
+    let query = query.bind_label_parameters()?;
+    let plan = SetPlan::new(query.ast(), config, query.parameters())?;
+    let mut base = WriteBase::capture(&namespace, plan.tables()).await?;
+    let matched = collect_reads(&query, &namespace, &plan.reads).await?;
+
+    let clause = if plan.remove { "REMOVE" } else { "SET" };
+    let mut properties = 0;
+    let mut labels = 0;
+    for update in &plan.updates {
+        let node = &update.node;
+        let first = plan.bound_columns[&node.variable];
+        let keys = matched_keys(&matched, first, node.key_columns.len())?;
+        if keys.is_empty() || (update.values.is_empty() && update.relabel_value().is_none()) {
+            continue;
+        }
+        let dataset = require_dataset(&namespace, &node.table, clause).await?;
+        let rows = update.apply(dataset, &keys, &mut base).await?;
+        properties += rows * update.values.len() as u64;
+        if update.relabel_value().is_some() {
+            labels += rows;
+        }
+    }
+    if plan.remove {
+        return counters(&[
+            (PROPERTIES_REMOVED_COLUMN, properties),
+            (LABELS_REMOVED_COLUMN, labels),
+        ]);
+    }
+    counters(&[
+        (PROPERTIES_SET_COLUMN, properties),
+        (LABELS_SET_COLUMN, labels),
+    ])
+}
+
+/// Run the DELETE clause of `query`, removing the matched nodes and
+/// relationships from the datasets of `namespace`
+pub(crate) async fn execute_delete(
+    query: &CypherQuery,
+    namespace: Arc<DirNamespace>,
+) -> Result<RecordBatch> {
+    let config = query.require_config()?;
+    let query = query.bind_label_parameters()?;
+    let plan = DeletePlan::new(query.ast(), config)?;
+    let mut base = WriteBase::capture(&namespace, plan.tables(config)).await?;
+
+    // Relationships matching on all columns return every column of their
+    // dataset
+    let mut columns: Vec<(String, Vec<String>)> = plan
+        .nodes
+        .iter()
+        .map(|node| (node.variable.clone(), node.key_columns.clone()))
+        .collect();
+    for rel in &plan.relationships {
+        let identity = match &rel.identity {
+            Some(identity) => identity.clone(),
+            None => {
+                let dataset = require_dataset(&namespace, &rel.rel_type, "DELETE").await?;
+                ArrowSchema::from(dataset.schema())
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().clone())
+                    .collect()
+            }
+        };
+        columns.push((rel.variable.clone(), identity));
+    }
+    let (reads, bound_columns) = returning(query.ast(), &columns);
+    let matched = collect_reads(&query, &namespace, &reads).await?;
+    let keys = columns
+        .iter()
+        .map(|(variable, identity)| matched_keys(&matched, bound_columns[variable], identity.len()))
+        .collect::<Result<Vec<_>>>()?;
+    let (node_keys, rel_keys) = keys.split_at(plan.nodes.len());
+    let (_, rel_identities) = columns.split_at(plan.nodes.len());
+
+    // Relationships go first, so deleting both ends of a matched
+    // relationship does not need DETACH
+    let mut relationships_deleted = 0;
+    for ((rel, (_, identity)), keys) in plan.relationships.iter().zip(rel_identities).zip(rel_keys)
+    {
+        if keys.is_empty() {
+            continue;
+        }
+        let dataset = require_dataset(&namespace, &rel.rel_type, "DELETE").await?;
+        let schema = ArrowSchema::from(dataset.schema());
+        let mut filter = key_filter(&schema, &rel.rel_type, identity, keys)?;
+        if let Some(column) = &rel.soft_delete_column {
+            let field = schema_field(&schema, &rel.rel_type, column)?;
+            filter = filter.and(ident(field.name()).is_null());
+        }
+        let filter = lance_sql(&filter)?;
+        relationships_deleted += delete_rows(
+            dataset,
+            &rel.rel_type,
+            &filter,
+            rel.soft_delete_column.as_deref(),
+            &mut base,
+        )
+        .await?;
+    }
+
+    let mut nodes_deleted = 0;
+    for (node, keys) in plan.nodes.iter().zip(node_keys) {
+        if keys.is_empty() {
+            continue;
+        }
+        relationships_deleted +=
+            delete_incident_relationships(config, &namespace, node, keys, plan.detach, &mut base)
+                .await?;
+        let dataset = require_dataset(&namespace, &node.table, "DELETE").await?;
+        let filter = lance_sql(&node.filter(&ArrowSchema::from(dataset.schema()), keys)?)?;
+        nodes_deleted += delete_rows(
+            dataset,
+            &node.table,
+            &filter,
+            node.soft_delete_column.as_deref(),
+            &mut base,
+        )
+        .await?;
+    }
+    counters(&[
+        (NODES_DELETED_COLUMN, nodes_deleted),
+        (RELATIONSHIPS_DELETED_COLUMN, relationships_deleted),
+    ])
+}
+
+/// Delete the relationships starting or ending at one of `keys` of `node`
+/// (`detach`), or fail if there are any
+///
+/// Only the ends of relationship types declared to start or end at the node's
+/// label are looked at, so every type must declare its endpoint labels.
+async fn delete_incident_relationships(
+    config: &GraphConfig,
+    namespace: &DirNamespace,
+    node: &BoundNode,
+    keys: &[Vec<ScalarValue>],
+    detach: bool,
+    base: &mut WriteBase,
+) -> Result<u64> {
+    let mut mappings: Vec<_> = config.relationship_mappings.values().collect();
+    mappings.sort_by(|a, b| a.relationship_type.cmp(&b.relationship_type));
+
+    let mut deleted = 0;
+    for mapping in mappings {
+        let table = &mapping.relationship_type;
+        let (Some(source_label), Some(target_label)) =
+            (&mapping.source_label, &mapping.target_label)
+        else {
+            return Err(GraphError::ConfigError {
+                message: format!(
+                    "Relationship type '{}' must declare its endpoint labels for DELETE to find the relationships of '{}'",
+                    table, node.variable
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        };
+        let mut ends: Vec<Vec<String>> = Vec::new();
+        for (label, columns) in [
+            (source_label, mapping.source_key_columns()),
+            (target_label, mapping.target_key_columns()),
+        ] {
+            if !config
+                .resolve_view_chain(label)?
+                .0
+                .eq_ignore_ascii_case(&node.label)
+            {
+                continue;
+            }
+            if columns.len() != node.key_columns.len() {
+                return Err(GraphError::ConfigError {
+                    message: format!(
+                        "Relationship type '{}' references '{}' nodes by {} columns, but their key has {}",
+                        table,
+                        label,
+                        columns.len(),
+                        node.key_columns.len()
+                    ),
+                    location: snafu::Location::new(file!(), line!(), column!()),
+                });
+            }
+            ends.push(columns.into_iter().map(str::to_string).collect());
+        }
+        if ends.is_empty() {
+            continue;
+        }
+        let Some(dataset) = crate::graph_diff::open_latest(namespace, table).await? else {
+            continue;
+        };
+        let schema = ArrowSchema::from(dataset.schema());
+        let mut filter = balanced_or(
+            ends.iter()
+                .map(|columns| key_filter(&schema, table, columns, keys))
+                .collect::<Result<Vec<_>>>()?,
+        );
+        if let Some(column) = &mapping.soft_delete_column {
+            let field = schema_field(&schema, table, column)?;
+            filter = filter.and(ident(field.name()).is_null());
+        }
+        let filter = lance_sql(&filter)?;
+
+        if detach {
+            deleted += delete_rows(
+                dataset,
+                table,
+                &filter,
+                mapping.soft_delete_column.as_deref(),
+                base,
+            )
+            .await?;
+        } else if dataset.count_rows(Some(filter)).await? > 0 {
+            return Err(GraphError::ExecutionError {
+                message: format!(
+                    "Cannot delete node '{}' while it still has '{}' relationships; use DETACH DELETE",
+                    node.variable, table
+                ),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+    }
+    Ok(deleted)
+}
+
+/// Run `reads` over the datasets of `namespace`
+async fn collect_reads(
+    query: &CypherQuery,
+    namespace: &Arc<DirNamespace>,
+    reads: &CypherAST,
+) -> Result<Vec<RecordBatch>> {
+    let (catalog, ctx) = query
+        .build_catalog_and_context_from_namespace(namespace.clone())
+        .await?;
+    query
+        .collect_ast(reads.clone(), Arc::new(catalog), &ctx)
+        .await
+}
+
+/// The distinct values of the `width` columns starting at `first` of
+/// `matched`; rows where they are all null (unmatched OPTIONAL MATCH
+/// elements) are skipped
+fn matched_keys(
+    matched: &[RecordBatch],
+    first: usize,
+    width: usize,
+) -> Result<Vec<Vec<ScalarValue>>> {
+    let mut seen = HashSet::new();
+    let mut keys: Vec<Vec<ScalarValue>> = Vec::new();
+    for batch in matched {
+        for row in 0..batch.num_rows() {
+            let key = (first..first + width)
+                .map(|column| ScalarValue::try_from_array(batch.column(column), row))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            if !key.iter().all(ScalarValue::is_null) && seen.insert(key.clone()) {
+                keys.push(key);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Latest version of the dataset of `table`, which `clause` writes to
+async fn require_dataset(namespace: &DirNamespace, table: &str, clause: &str) -> Result<Dataset> {
+    crate::graph_diff::open_latest(namespace, table)
+        .await?
+        .ok_or_else(|| GraphError::ExecutionError {
+            message: format!("No dataset '{}' for {} to write to", table, clause),
+            location: snafu::Location::new(file!(), line!(), column!()),
+        })
+}
+
+/// A single-row result of the named counters
+fn counters(values: &[(&str, u64)]) -> Result<RecordBatch> {
+    let schema = Arc::new(ArrowSchema::new(
+        values
+            .iter()
+            .map(|(name, _)| Field::new(*name, DataType::Int64, false))
+            .collect::<Vec<_>>(),
+    ));
+    let columns = values
+        .iter()
+        .map(|(_, value)| Arc::new(Int64Array::from(vec![*value as i64])) as ArrayRef)
+        .collect();
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Fail if `ast` writes; only namespace-backed execution has datasets to
+/// write to
+pub(crate) fn check_read_only(ast: &CypherAST) -> Result<()> {
+    let clause = if ast.create_clause.is_some() {
+        "CREATE"
+    } else if ast.merge_clause.is_some() {
+        "MERGE"
+    } else if ast.set_clause.is_some() {
+        "SET"
+    } else if ast.remove_clause.is_some() {
+        "REMOVE"
+    } else if ast.delete_clause.is_some() {
+        "DELETE"
+    } else {
+        return Ok(());
+    };
+    Err(GraphError::UnsupportedFeature {
+        feature: format!(
+            "{} without a namespace to write to; use execute_with_namespace",
+            clause
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    })
+}
+
+/// Column `name` of `row`
+fn row_value<'a>(row: &'a Row, name: &str) -> Option<&'a ScalarValue> {
+    row.iter()
+        .find(|(column, _)| column.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Columns written by any of `rows`, in first-written order
+fn written_columns(rows: &[Row]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (name, _) in rows.iter().flatten() {
+        if !columns
+            .iter()
+            .any(|column| column.eq_ignore_ascii_case(name))
+        {
+            columns.push(name.clone());
+        }
+    }
+    columns
+}
+
+/// Lock serializing the MERGEs of this process on the dataset at `uri`
+fn merge_lock(uri: &str) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+        LazyLock::new(|| Mutex::new(HashMap::new()));
+    LOCKS
+        .lock()
+        .unwrap()
+        .entry(uri.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Values of the `identity` columns of `row` (all of its columns when `None`),
+/// missing columns as nulls
+fn identity_values(row: &Row, identity: Option<&[String]>) -> Vec<(String, ScalarValue)> {
+    match identity {
+        Some(columns) => columns
+            .iter()
+            .map(|name| {
+                let value = row_value(row, name).cloned().unwrap_or(ScalarValue::Null);
+                (name.clone(), value)
+            })
+            .collect(),
+        None => row.clone(),
+    }
+}
+
+fn merge_conflict(table: &str, key: &[(String, ScalarValue)], column: &str) -> GraphError {
+    let key = key
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ");
+    GraphError::ExecutionError {
+        message: format!(
+            "MERGE conflict on '{}' {{{}}}: an element with this key already has a different '{}'",
+            table, key, column
+        ),
+        location: snafu::Location::new(file!(), line!(), column!()),
+    }
+}
+
+/// Whether `a` and `b` hold the same value once cast to `data_type`
+fn same_value(a: &ScalarValue, b: &ScalarValue, data_type: &DataType) -> Result<bool> {
+    if a.is_null() || b.is_null() {
+        return Ok(a.is_null() && b.is_null());
+    }
+    Ok(a.cast_to(data_type)? == b.cast_to(data_type)?)
+}
+
+/// The rows of `rows` that MERGE has to create: rows whose identity no other
+/// row, written before, staged or live in `dataset`, has. Fails when a row
+/// with the same identity holds different values for the other written
+/// columns.
+async fn unmatched_rows(
+    dataset: Option<&Dataset>,
+    table: &str,
+    identity: Option<&[String]>,
+    soft_delete_column: Option<&str>,
+    staged: &[RecordBatch],
+    rows: Vec<Row>,
+) -> Result<Vec<Row>> {
+    // Rows repeated by the matched rows are written once
+    let mut candidates: Vec<(Vec<(String, ScalarValue)>, Row)> = Vec::new();
+    let mut seen: HashMap<Vec<(String, ScalarValue)>, usize> = HashMap::new();
+    for row in rows {
+        let key = identity_values(&row, identity);
+        match seen.get(&key) {
+            Some(&index) => {
+                let existing = &candidates[index].1;
+                if let Some((column, _)) = row
+                    .iter()
+                    .find(|(name, value)| row_value(existing, name) != Some(value))
+                {
+                    return Err(merge_conflict(table, &key, column));
+                }
+            }
+            None => {
+                seen.insert(key.clone(), candidates.len());
+                candidates.push((key, row));
+            }
+        }
+    }
+
+    let mut existing = staged.to_vec();
+    if let Some(dataset) = dataset {
+        match identity_matches(dataset, soft_delete_column, &candidates).await? {
+            Some(batches) => existing.extend(batches),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    // First row of each existing batch per identity, indexed by the batch
+    // and the identity columns it has
+    let mut indexes: HashMap<(usize, Vec<usize>), HashMap<Vec<ScalarValue>, usize>> =
+        HashMap::new();
+    let mut unmatched = Vec::new();
+    'candidates: for (key, row) in candidates {
+        for (batch_index, batch) in existing.iter().enumerate() {
+            let schema = batch.schema();
+            let position = |name: &str| {
+                schema
+                    .fields()
+                    .iter()
+                    .position(|f| f.name().eq_ignore_ascii_case(name))
+            };
+            // Identity columns the batch lacks match any value
+            let columns: Vec<(usize, &ScalarValue)> = key
+                .iter()
+                .filter_map(|(name, value)| position(name).map(|column| (column, value)))
+                .collect();
+            let positions: Vec<usize> = columns.iter().map(|(column, _)| *column).collect();
+            let index = match indexes.entry((batch_index, positions.clone())) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    let mut index = HashMap::new();
+                    for row in 0..batch.num_rows() {
+                        let values = positions
+                            .iter()
+                            .map(|column| ScalarValue::try_from_array(batch.column(*column), row))
+                            .collect::<std::result::Result<Vec<_>, _>>()?;
+                        index.entry(values).or_insert(row);
+                    }
+                    entry.insert(index)
+                }
+            };
+            let probe = columns
+                .iter()
+                .map(|(column, value)| value.cast_to(schema.field(*column).data_type()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let Some(&matched) = index.get(&probe) else {
+                continue;
+            };
+
+            for (name, expected) in &row {
+                let Some(column) = position(name) else {
+                    continue;
+                };
+                let actual = ScalarValue::try_from_array(batch.column(column), matched)?;
+                if !same_value(expected, &actual, schema.field(column).data_type())? {
+                    return Err(merge_conflict(table, &key, name));
+                }
+            }
+            continue 'candidates;
+        }
+        unmatched.push(row);
+    }
+    Ok(unmatched)
+}
+
+/// The live rows of `dataset` holding the identity of one of `candidates`,
+/// or `None` if the identities are empty and every candidate matches
+async fn identity_matches(
+    dataset: &Dataset,
+    soft_delete_column: Option<&str>,
+    candidates: &[(Vec<(String, ScalarValue)>, Row)],
+) -> Result<Option<Vec<RecordBatch>>> {
+    let schema = ArrowSchema::from(dataset.schema());
+    let field = |name: &str| {
+        schema
+            .fields()
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+    };
+    // Columns the dataset lacks cannot match; writing them fails later
+    if candidates
+        .iter()
+        .flat_map(|(key, _)| key)
+        .any(|(name, _)| field(name).is_none())
+    {
+        return Ok(Some(Vec::new()));
+    }
+
+    let mut lookups = Vec::new();
+    for (key, _) in candidates {
+        let mut conjuncts = Vec::new();
+        for (name, value) in key {
+            let field = field(name).expect("checked above");
+            let column = ident(field.name());
+            conjuncts.push(if value.is_null() {
+                column.is_null()
+            } else {
+                column.eq(lit(value.cast_to(field.data_type())?))
+            });
+        }
+        lookups.extend(conjuncts.into_iter().reduce(Expr::and));
+    }
+    let Some(mut filter) = lookups.into_iter().reduce(Expr::or) else {
+        return Ok(None);
+    };
+    if let Some(field) = soft_delete_column.and_then(field) {
+        filter = filter.and(ident(field.name()).is_null());
+    }
+    let mut scanner = dataset.scan();
+    scanner.filter_expr(filter);
+    Ok(Some(scanner.try_into_stream().await?.try_collect().await?))
+}
+
+/// `rows` as a batch of `dataset`, or of a new dataset whose
+/// column types are those of the rows staged for it or of the written values
+fn table_batch(
+    dataset: Option<&Dataset>,
+    table: &str,
+    staged: Option<SchemaRef>,
+    rows: &[Row],
+) -> Result<RecordBatch> {
+    let schema: SchemaRef = match dataset {
+        Some(dataset) => Arc::new(ArrowSchema::from(dataset.schema())),
+        None => staged.unwrap_or_else(|| infer_schema(rows)),
+    };
+
+    for (name, _) in rows.iter().flatten() {
+        if !schema
+            .fields()
+            .iter()
+            .any(|f| f.name().eq_ignore_ascii_case(name))
+        {
+            return Err(GraphError::ExecutionError {
+                message: format!("'{}' has no column '{}' for CREATE to set", table, name),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            });
+        }
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let values = rows
+                .iter()
+                .map(|row| {
+                    let value = row
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(field.name()))
+                        .map(|(_, value)| value.cast_to(field.data_type()))
+                        .unwrap_or_else(|| ScalarValue::try_from(field.data_type()))?;
+                    if value.is_null() && !field.is_nullable() {
+                        return Err(GraphError::ExecutionError {
+                            message: format!(
+                                "CREATE leaves the non-nullable column '{}' of '{}' empty",
+                                field.name(),
+                                table
+                            ),
+                            location: snafu::Location::new(file!(), line!(), column!()),
+                        });
+                    }
+                    Ok(value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(ScalarValue::iter_to_array(values)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Nullable columns in first-written order, typed by their first non-null value
+fn infer_schema(rows: &[Row]) -> SchemaRef {
+    let mut fields: Vec<(String, Option<DataType>)> = Vec::new();
+    for (name, value) in rows.iter().flatten() {
+        let data_type = (!value.is_null()).then(|| value.data_type());
+        match fields
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        {
+            Some((_, existing)) => {
+                if existing.is_none() {
+                    *existing = data_type;
+                }
+            }
+            None => fields.push((name.clone(), data_type)),
+        }
+    }
+    Arc::new(ArrowSchema::new(
+        fields
+            .into_iter()
+            .map(|(name, data_type)| Field::new(name, data_type.unwrap_or(DataType::Utf8), true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeMapping;
+    use crate::parser::parse_cypher_query;
+
+    fn config() -> GraphConfig {
+        GraphConfig::builder()
+            .with_node_label("Person", "id")
+            .with_node_label("City", "name")
+            .with_relationship("KNOWS", "src_id", "dst_id")
+            .build()
+            .unwrap()
+    }
+
+    fn plan(cypher: &str, parameters: &[(&str, serde_json::Value)]) -> Result<WritePlan> {
+        let parameters = parameters
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        WritePlan::new(&parse_cypher_query(cypher).unwrap(), &config(), &parameters)
+    }
+
+    #[test]
+    fn test_plan_binds_parameters_and_endpoint_keys() {
+        let plan = plan(
+            "CREATE (n:Person {id: $id, name: $name})-[:KNOWS {since: 2020}]->(m:Person {id: 2})",
+            &[("id", 1.into()), ("name", "Alice".into())],
+        )
+        .unwrap();
+        assert!(plan.reads.is_none());
+
+        let rows = plan.rows(None).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, "Person");
+        assert_eq!(
+            rows[0].1,
+            vec![
+                vec![
+                    ("id".to_string(), ScalarValue::Int64(Some(1))),
+                    ("name".to_string(), ScalarValue::Utf8(Some("Alice".into()))),
+                ],
+                vec![("id".to_string(), ScalarValue::Int64(Some(2)))],
+            ]
+        );
+        assert_eq!(rows[1].0, "KNOWS");
+        assert_eq!(
+            rows[1].1,
+            vec![vec![
+                ("since".to_string(), ScalarValue::Int64(Some(2020))),
+                ("src_id".to_string(), ScalarValue::Int64(Some(1))),
+                ("dst_id".to_string(), ScalarValue::Int64(Some(2))),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_plan_reads_keys_of_bound_endpoints() {
+        let plan = plan(
+            "MATCH (m:Person) WHERE m.name = 'Bob' CREATE (m)<-[:KNOWS]-(n:Person {id: 3})",
+            &[],
+        )
+        .unwrap();
+        let reads = plan.reads.as_ref().unwrap();
+        assert!(reads.create_clause.is_none());
+        assert_eq!(reads.return_clause.items.len(), 1);
+        assert_eq!(plan.bound_columns.get("m"), Some(&0));
+        assert_eq!(
+            plan.relationships[0].source,
+            Endpoint::Created(0),
+            "incoming relationships start at the right-hand node"
+        );
+        assert_eq!(plan.relationships[0].target, Endpoint::Bound("m".into()));
+    }
+
+    #[test]
+    fn test_plan_rejects_unwritable_patterns() {
+        let message = |cypher: &str, parameters: &[(&str, serde_json::Value)]| {
+            plan(cypher, parameters).unwrap_err().to_string()
+        };
+        assert!(message("CREATE (n {id: 1})", &[]).contains("exactly one label"));
+        assert!(message("CREATE (n:Person {name: 'x'})", &[]).contains("key property 'id'"));
+        assert!(message("CREATE (n:Person {id: $id})", &[]).contains("$id"));
+        assert!(
+            message("CREATE (n:Person {id: $id})", &[("id", vec![1].into())])
+                .contains("must be a scalar")
+        );
+        assert!(
+            message("CREATE (:Person {id: 1})-[:KNOWS]-(:Person {id: 2})", &[])
+                .contains("direction")
+        );
+        assert!(message(
+            "CREATE (:Person {id: 1})-[:KNOWS]->(:City {name: 'x'}), (:Ghost {id: 1})",
+            &[]
+        )
+        .contains("unknown label 'Ghost'"));
+        assert!(
+            message("MATCH (m) CREATE (:Person {id: 1})-[:KNOWS]->(m)", &[])
+                .contains("needs a label on 'm'")
+        );
+        assert!(message("MATCH (m:Person) CREATE (m {id: 1})", &[]).contains("existing node"));
+    }
+
+    #[test]
+    fn test_merge_plan_identities() {
+        assert!(plan("MERGE (n {id: 1})", &[])
+            .unwrap_err()
+            .to_string()
+            .contains("MERGE needs exactly one label"));
+
+        let plan = plan(
+            "MATCH (m:Person {id: 2}) MERGE (n:Person {id: 1, name: 'Alice'})-[:KNOWS]->(m)",
+            &[],
+        )
+        .unwrap();
+        assert!(plan.merge);
+        assert_eq!(plan.identity("Person"), Some(vec!["id".to_string()]));
+        // Parallel edges are allowed, so relationships match on all columns
+        assert_eq!(plan.identity("KNOWS"), None);
+        assert!(plan.reads.as_ref().unwrap().merge_clause.is_none());
+    }
+
+    #[test]
+    fn test_set_plan() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", "kind"))
+            .with_node_mapping(
+                NodeMapping::new("Employee", "id").with_label_column("nodes", "kind"),
+            )
+            .with_node_label("City", "name")
+            .build()
+            .unwrap();
+        let set_plan = |cypher: &str, parameters: &[(&str, serde_json::Value)]| {
+            let parameters = parameters
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect();
+            SetPlan::new(&parse_cypher_query(cypher).unwrap(), &config, &parameters)
+        };
+
+        let plan = set_plan(
+            "MATCH (n:Person) WHERE n.name = 'Bob' SET n.age = 30, n += $changes, n.age = $age, n:Employee",
+            &[("changes", serde_json::json!({"city": "Lisbon"})), ("age", 31.into())],
+        )
+        .unwrap();
+        assert_eq!(plan.bound_columns.get("n"), Some(&0));
+        assert!(plan.reads.set_clause.is_none());
+        let [update] = plan.updates.as_slice() else {
+            panic!("expected one update, got {:?}", plan.updates);
+        };
+        assert_eq!(update.node.table, "nodes");
+        assert_eq!(
+            update.node.label_filter,
+            Some(("kind".to_string(), "Person".to_string()))
+        );
+        assert_eq!(
+            update.values,
+            vec![
+                ("age".to_string(), ScalarValue::Int64(Some(31))),
+                ("city".to_string(), ScalarValue::Utf8(Some("Lisbon".into()))),
+            ]
+        );
+        assert_eq!(
+            update.relabel,
+            Some(("kind".to_string(), "Employee".to_string()))
+        );
+
+        let message = |cypher: &str, parameters: &[(&str, serde_json::Value)]| {
+            set_plan(cypher, parameters).unwrap_err().to_string()
+        };
+        assert!(message("MATCH (n:Person) SET n.id = 2", &[]).contains("key property 'id'"));
+        assert!(message("MATCH (n:Person) SET m.age = 2", &[]).contains("'m' is not one"));
+        assert!(message("MATCH (n:Person) SET n:City", &[]).contains("label column"));
+        assert!(message(
+            "MATCH (n:Person) SET n += $changes",
+            &[("changes", 1.into())]
+        )
+        .contains("must be a map"));
+        assert_eq!(lance_sql(&lit("O'Hara")).unwrap(), "'O''Hara'");
+        assert_eq!(lance_sql(&ident("first name")).unwrap(), "`first name`");
+    }
+
+    #[test]
+    fn test_remove_plan() {
+        let config = GraphConfig::builder()
+            .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", "kind"))
+            .with_node_mapping(
+                NodeMapping::new("Employee", "id").with_label_column("nodes", "kind"),
+            )
+            .with_node_label("City", "name")
+            .build()
+            .unwrap();
+        let remove_plan = |cypher: &str| {
+            SetPlan::new(
+                &parse_cypher_query(cypher).unwrap(),
+                &config,
+                &HashMap::new(),
+            )
+        };
+
+        let plan = remove_plan("MATCH (n:Person) REMOVE n.age, n:Employee, n:Person").unwrap();
+        assert!(plan.remove);
+        assert!(plan.reads.remove_clause.is_none());
+        let [update] = plan.updates.as_slice() else {
+            panic!("expected one update, got {:?}", plan.updates);
+        };
+        assert_eq!(update.values, vec![("age".to_string(), ScalarValue::Null)]);
+        assert_eq!(update.unlabel.as_deref(), Some("kind"));
+        assert_eq!(
+            update.relabel_value(),
+            Some(("kind".to_string(), ScalarValue::Utf8(None)))
+        );
+
+        let plan = remove_plan("MATCH (n:Person) REMOVE n:Employee").unwrap();
+        assert_eq!(plan.updates[0].relabel_value(), None);
+
+        let message = |cypher: &str| remove_plan(cypher).unwrap_err().to_string();
+        assert!(message("MATCH (n:Person) REMOVE n.id").contains("key property 'id'"));
+        assert!(message("MATCH (c:City) REMOVE c:City").contains("label column"));
+        assert!(message("REMOVE n.age").contains("MATCH clause"));
+    }
+
+    #[test]
+    fn test_delete_plan() {
+        let delete_plan =
+            |cypher: &str| DeletePlan::new(&parse_cypher_query(cypher).unwrap(), &config());
+
+        let plan =
+            delete_plan("MATCH (a:Person)-[r:KNOWS]->(b:Person) DETACH DELETE r, a, a").unwrap();
+        assert!(plan.detach);
+        assert_eq!(plan.nodes.len(), 1);
+        assert_eq!(plan.nodes[0].key_columns, vec!["id".to_string()]);
+        assert_eq!(plan.nodes[0].label_filter, None);
+        // Parallel edges are allowed, so relationships match on all columns
+        assert_eq!(plan.relationships[0].rel_type, "KNOWS");
+        assert_eq!(plan.relationships[0].identity, None);
+
+        let message = |cypher: &str| delete_plan(cypher).unwrap_err().to_string();
+        assert!(message("MATCH (a:Person) DELETE b").contains("'b' is not one"));
+        assert!(message("MATCH (a) DELETE a").contains("needs a label on 'a'"));
+        assert!(
+            message("MATCH (a:Person)-[r:KNOWS*1..2]->(b:Person) DELETE r")
+                .contains("variable-length")
+        );
+    }
+
+    #[test]
+    fn test_key_filter() {
+        let schema = ArrowSchema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let columns = ["id".to_string(), "name".to_string()];
+        let keys = vec![
+            vec![
+                ScalarValue::Int32(Some(1)),
+                ScalarValue::Utf8(Some("O'Hara".into())),
+            ],
+            vec![ScalarValue::Int64(Some(2)), ScalarValue::Null],
+        ];
+        assert_eq!(
+            key_filter(&schema, "Person", &columns, &keys).unwrap(),
+            ident("ID")
+                .eq(lit(1i64))
+                .and(ident("name").eq(lit("O'Hara")))
+                .or(ident("ID").eq(lit(2i64)).and(ident("name").is_null()))
+        );
+
+        // Single-column keys are looked up in batched IN lists
+        let ids: Vec<_> = (0..KEY_FILTER_BATCH as i64 + 1)
+            .map(|id| vec![ScalarValue::Int64(Some(id))])
+            .chain([vec![ScalarValue::Int64(None)]])
+            .collect();
+        let in_list =
+            |range: std::ops::Range<i64>| ident("ID").in_list(range.map(lit).collect(), false);
+        assert_eq!(
+            key_filter(&schema, "Person", &["id".to_string()], &ids).unwrap(),
+            in_list(0..KEY_FILTER_BATCH as i64).or(in_list(
+                KEY_FILTER_BATCH as i64..KEY_FILTER_BATCH as i64 + 1
+            )
+            .or(ident("ID").is_null()))
+        );
+        let names = [vec![ScalarValue::Utf8(Some("O'Hara".into()))]];
+        let filter = key_filter(&schema, "Person", &["name".to_string()], &names).unwrap();
+        assert_eq!(lance_sql(&filter).unwrap(), "`name` IN ('O''Hara')");
+        assert!(key_filter(&schema, "Person", &["age".to_string()], &keys)
+            .unwrap_err()
+            .to_string()
+            .contains("no column 'age'"));
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_rows_dedupes_candidates() {
+        let row = |id: i64, name: &str| -> Row {
+            vec![
+                ("id".to_string(), ScalarValue::Int64(Some(id))),
+                (
+                    "name".to_string(),
+                    ScalarValue::Utf8(Some(name.to_string())),
+                ),
+            ]
+        };
+        let identity = ["id".to_string()];
+
+        let rows = unmatched_rows(
+            None,
+            "Person",
+            Some(&identity),
+            None,
+            &[],
+            vec![row(1, "Alice"), row(2, "Bob"), row(1, "Alice")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows, vec![row(1, "Alice"), row(2, "Bob")]);
+
+        let err = unmatched_rows(
+            None,
+            "Person",
+            Some(&identity),
+            None,
+            &[],
+            vec![row(1, "Alice"), row(1, "Alicia")],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("different 'name'"));
+
+        // Rows staged by earlier statements of a transaction match too
+        let staged = table_batch(None, "Person", None, &[row(1, "Alice")]).unwrap();
+        let rows = unmatched_rows(
+            None,
+            "Person",
+            Some(&identity),
+            None,
+            &[staged],
+            vec![row(1, "Alice"), row(2, "Bob")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows, vec![row(2, "Bob")]);
+    }
+}
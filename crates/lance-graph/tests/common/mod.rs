@@ -0,0 +1,176 @@
+//! Graphs and result readers shared by the integration tests
+
+// Each test binary uses its own subset of the helpers
+#![allow(dead_code)]
+
+use arrow_array::{
+    Array, ArrayRef, Int64Array, ListArray, RecordBatch, RecordBatchIterator, StringArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance_graph::config::{GraphConfig, RelationshipMapping};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// `Person` nodes keyed by `id` and `KNOWS` relationships between them from
+/// `src_id` to `dst_id`
+pub fn config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_relationship_mapping(
+            RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                .with_endpoints("Person", "Person"),
+        )
+        .build()
+        .unwrap()
+}
+
+/// `cypher` over the graph of [`config`]
+pub fn query(cypher: &str) -> CypherQuery {
+    CypherQuery::new(cypher).unwrap().with_config(config())
+}
+
+/// `Person` rows with the columns `id`, `name` and `age`
+pub fn person_batch(ids: Vec<i64>, names: Vec<&str>, ages: Vec<i64>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(ids)),
+            Arc::new(StringArray::from(names)),
+            Arc::new(Int64Array::from(ages)),
+        ],
+    )
+    .unwrap()
+}
+
+/// `KNOWS` rows from `src` to `dst`
+pub fn knows_batch(src: Vec<i64>, dst: Vec<i64>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("src_id", DataType::Int64, false),
+        Field::new("dst_id", DataType::Int64, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(src)),
+            Arc::new(Int64Array::from(dst)),
+        ],
+    )
+    .unwrap()
+}
+
+/// Alice (30), Bob (25), Carol (40) and Dave (35), with ids 1 to 4; Alice
+/// knows Bob, Carol and Dave, Bob knows Carol and Carol knows Alice
+pub fn social_graph() -> HashMap<String, RecordBatch> {
+    HashMap::from([
+        (
+            "Person".to_string(),
+            person_batch(
+                vec![1, 2, 3, 4],
+                vec!["Alice", "Bob", "Carol", "Dave"],
+                vec![30, 25, 40, 35],
+            ),
+        ),
+        (
+            "KNOWS".to_string(),
+            knows_batch(vec![1, 1, 1, 2, 3], vec![2, 3, 4, 3, 1]),
+        ),
+    ])
+}
+
+/// A namespace over the datasets in `dir`
+pub fn namespace(dir: &Path) -> DirNamespace {
+    DirNamespace::new(dir.to_string_lossy().into_owned())
+}
+
+/// Write `batch` to the Lance dataset at `path`
+pub async fn write_dataset(path: &Path, batch: RecordBatch, mode: WriteMode) {
+    let schema = batch.schema();
+    let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+    Dataset::write(
+        reader,
+        path.to_str().unwrap(),
+        Some(WriteParams {
+            mode,
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+}
+
+/// A column of a result, by name or position
+pub trait ColumnRef {
+    fn array<'a>(&self, batch: &'a RecordBatch) -> &'a ArrayRef;
+}
+
+impl ColumnRef for &str {
+    fn array<'a>(&self, batch: &'a RecordBatch) -> &'a ArrayRef {
+        batch
+            .column_by_name(self)
+            .unwrap_or_else(|| panic!("no column '{}' in {:?}", self, batch.schema()))
+    }
+}
+
+impl ColumnRef for usize {
+    fn array<'a>(&self, batch: &'a RecordBatch) -> &'a ArrayRef {
+        batch.column(*self)
+    }
+}
+
+fn downcast<T: 'static>(array: &ArrayRef) -> &T {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .unwrap_or_else(|| panic!("unexpected column type {}", array.data_type()))
+}
+
+/// The values of a Utf8 column, nulls as empty strings
+pub fn strings(batch: &RecordBatch, column: impl ColumnRef) -> Vec<String> {
+    optional_strings(batch, column)
+        .into_iter()
+        .map(Option::unwrap_or_default)
+        .collect()
+}
+
+/// The values of a Utf8 column
+pub fn optional_strings(batch: &RecordBatch, column: impl ColumnRef) -> Vec<Option<String>> {
+    downcast::<StringArray>(column.array(batch))
+        .iter()
+        .map(|value| value.map(str::to_string))
+        .collect()
+}
+
+/// The values of an Int64 column without nulls
+pub fn ints(batch: &RecordBatch, column: impl ColumnRef) -> Vec<i64> {
+    downcast::<Int64Array>(column.array(batch))
+        .values()
+        .to_vec()
+}
+
+/// The values of an Int64 column
+pub fn optional_ints(batch: &RecordBatch, column: impl ColumnRef) -> Vec<Option<i64>> {
+    downcast::<Int64Array>(column.array(batch)).iter().collect()
+}
+
+/// The non-null elements of each list of a column of Utf8 lists
+pub fn string_lists(batch: &RecordBatch, column: impl ColumnRef) -> Vec<Vec<String>> {
+    let array = downcast::<ListArray>(column.array(batch));
+    (0..array.len())
+        .map(|row| {
+            let values = array.value(row);
+            downcast::<StringArray>(&values)
+                .iter()
+                .flatten()
+                .map(str::to_string)
+                .collect()
+        })
+        .collect()
+}
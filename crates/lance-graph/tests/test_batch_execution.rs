@@ -0,0 +1,108 @@
+use arrow_array::RecordBatch;
+use lance::dataset::WriteMode;
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+
+mod common;
+
+use common::{config, ints, knows_batch, namespace, person_batch, write_dataset};
+
+fn people() -> RecordBatch {
+    person_batch(
+        vec![1, 2, 3, 4],
+        vec!["Alice", "Bob", "Carol", "David"],
+        vec![28, 34, 29, 42],
+    )
+}
+
+fn friendships() -> RecordBatch {
+    knows_batch(vec![1, 1, 2, 3], vec![2, 3, 4, 4])
+}
+
+fn queries() -> Vec<CypherQuery> {
+    [
+        "MATCH (p:Person) WHERE p.age > 30 RETURN p.name ORDER BY p.name",
+        "MATCH (p:Person) RETURN count(p) AS people",
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name ORDER BY a.name, b.name",
+    ]
+    .iter()
+    .map(|cypher| CypherQuery::new(cypher).unwrap().with_config(config()))
+    .collect()
+}
+
+#[tokio::test]
+async fn test_batch_matches_individual_execution() {
+    let datasets = HashMap::from([
+        ("Person".to_string(), people()),
+        ("KNOWS".to_string(), friendships()),
+    ]);
+    let queries = queries();
+
+    let results = CypherQuery::execute_batch(&queries, datasets.clone())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), queries.len());
+    for (query, result) in queries.iter().zip(&results) {
+        let expected = query.execute(datasets.clone(), None).await.unwrap();
+        assert_eq!(result, &expected, "{}", query.query_text());
+    }
+}
+
+#[tokio::test]
+async fn test_namespace_batch_shares_scans() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        people(),
+        WriteMode::Create,
+    )
+    .await;
+    write_dataset(
+        &tmp_dir.path().join("KNOWS.lance"),
+        friendships(),
+        WriteMode::Create,
+    )
+    .await;
+    let namespace = namespace(tmp_dir.path());
+    let queries = queries();
+
+    let results = CypherQuery::execute_batch_with_namespace(&queries, namespace.clone())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), queries.len());
+    for (query, result) in queries.iter().zip(&results) {
+        let expected = query
+            .execute_with_namespace(namespace.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(result, &expected, "{}", query.query_text());
+    }
+
+    assert_eq!(ints(&results[1], 0), vec![4]);
+}
+
+#[tokio::test]
+async fn test_empty_batch_and_failing_query() {
+    let datasets = HashMap::from([("Person".to_string(), people())]);
+    assert!(CypherQuery::execute_batch(&[], datasets.clone())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mut queries = queries();
+    queries.truncate(1);
+    queries.push(
+        CypherQuery::new("MATCH (c:Company) RETURN c.name")
+            .unwrap()
+            .with_config(
+                GraphConfig::builder()
+                    .with_node_label("Company", "id")
+                    .build()
+                    .unwrap(),
+            ),
+    );
+    assert!(CypherQuery::execute_batch(&queries, datasets)
+        .await
+        .is_err());
+}
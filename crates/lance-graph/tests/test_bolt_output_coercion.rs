@@ -0,0 +1,122 @@
+//! Bolt output coercion snapshot
+//!
+//! Locks how every supported Arrow type is converted by
+//! [`OutputCoercion::bolt`], the mapping a Bolt server would write to the
+//! wire. This is not a Bolt round-trip: nothing is encoded as PackStream or
+//! read back by a driver. Graph values (nodes, relationships, paths) and
+//! spatial points are not result types yet, so they have no entries. Set
+//! `LANCE_GRAPH_UPDATE_SNAPSHOTS` to rewrite the snapshot after an intended
+//! change.
+
+use arrow_array::types::{Float32Type, Int32Type, Int64Type, IntervalMonthDayNanoType};
+use arrow_array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, DictionaryArray,
+    DurationMillisecondArray, FixedSizeListArray, Float32Array, Float64Array, Int64Array,
+    IntervalMonthDayNanoArray, ListArray, NullArray, RecordBatch, StringArray, StructArray,
+    Time64NanosecondArray, TimestampMicrosecondArray, TimestampSecondArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field};
+use lance_graph::output::OutputCoercion;
+use lance_graph::plan_snapshot::UPDATE_SNAPSHOTS_ENV;
+use std::sync::Arc;
+
+const SNAPSHOT_FILE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/snapshots/bolt_output_coercion.txt"
+);
+
+fn column(name: &'static str, array: impl Array + 'static) -> (&'static str, ArrayRef) {
+    (name, Arc::new(array))
+}
+
+/// One single-row column per supported Arrow type
+fn columns() -> Vec<(&'static str, ArrayRef)> {
+    let decimal = Decimal128Array::from(vec![12345])
+        .with_precision_and_scale(10, 2)
+        .unwrap();
+    let person = StructArray::from(vec![
+        (
+            Arc::new(Field::new("name", DataType::Utf8, false)),
+            Arc::new(StringArray::from(vec!["Alice"])) as ArrayRef,
+        ),
+        (
+            Arc::new(Field::new("age", DataType::Int64, false)),
+            Arc::new(Int64Array::from(vec![30])) as ArrayRef,
+        ),
+    ]);
+    let segment: DictionaryArray<Int32Type> = vec!["gold"].into_iter().collect();
+    vec![
+        column("null", NullArray::new(1)),
+        column("boolean", BooleanArray::from(vec![true])),
+        column("int64", Int64Array::from(vec![-42])),
+        column("uint64_max", UInt64Array::from(vec![u64::MAX])),
+        column("float64", Float64Array::from(vec![1.5])),
+        column("float32", Float32Array::from(vec![0.1])),
+        column("decimal128", decimal),
+        column("utf8", StringArray::from(vec!["hello"])),
+        column("binary", BinaryArray::from(vec![&[1u8, 2, 255][..]])),
+        column("date32", Date32Array::from(vec![19_737])),
+        column(
+            "time64",
+            Time64NanosecondArray::from(vec![34_200_000_000_001]),
+        ),
+        column(
+            "timestamp",
+            TimestampMicrosecondArray::from(vec![1_705_311_000_250_000]),
+        ),
+        column(
+            "timestamp_zoned",
+            TimestampSecondArray::from(vec![1_717_243_200]).with_timezone("+02:00"),
+        ),
+        column("duration", DurationMillisecondArray::from(vec![90_500])),
+        column(
+            "interval",
+            IntervalMonthDayNanoArray::from(vec![IntervalMonthDayNanoType::make_value(
+                14,
+                3,
+                1_500_000_000,
+            )]),
+        ),
+        column(
+            "list",
+            ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![
+                Some(1),
+                Some(2),
+                Some(3),
+            ])]),
+        ),
+        column(
+            "vector",
+            FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                vec![Some(vec![Some(0.1), Some(0.5)])],
+                2,
+            ),
+        ),
+        column("struct", person),
+        column("dictionary", segment),
+    ]
+}
+
+#[test]
+fn test_bolt_output_coercion_snapshot() {
+    let batch = RecordBatch::try_from_iter(columns()).unwrap();
+    let row = &OutputCoercion::bolt().rows(&batch).unwrap()[0];
+    let actual: String = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(row)
+        .map(|(field, value)| format!("{}: {:?}\n", field.name(), value))
+        .collect();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        std::fs::write(SNAPSHOT_FILE, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(SNAPSHOT_FILE).unwrap();
+    assert_eq!(
+        actual, expected,
+        "Bolt output coercion changed; rerun with {} set if intended",
+        UPDATE_SNAPSHOTS_ENV
+    );
+}
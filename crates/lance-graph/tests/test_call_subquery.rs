@@ -0,0 +1,111 @@
+use lance_graph::config::GraphConfig;
+use lance_graph::{CypherQuery, ExperimentalFeature};
+
+mod common;
+
+use common::{config, ints, social_graph, strings};
+
+fn query(cypher: &str) -> CypherQuery {
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config())
+        .with_experimental_feature(ExperimentalFeature::CallSubquery)
+}
+
+#[tokio::test]
+async fn test_correlated_subquery_runs_per_outer_row() {
+    // The two oldest people each person knows; Dave knows nobody and is dropped
+    let result = query(
+        "MATCH (p:Person) \
+         CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name AS friend ORDER BY f.age DESC LIMIT 2 } \
+         RETURN p.name AS person, friend ORDER BY person, friend",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        strings(&result, "person"),
+        vec!["Alice", "Alice", "Bob", "Carol"]
+    );
+    assert_eq!(
+        strings(&result, "friend"),
+        vec!["Carol", "Dave", "Carol", "Alice"]
+    );
+}
+
+#[tokio::test]
+async fn test_uncorrelated_subquery_runs_once() {
+    let result = query(
+        "MATCH (p:Person) WHERE p.age > 30 \
+         CALL { MATCH (n:Person) RETURN count(n) AS total } \
+         RETURN p.name, total ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    // Unaliased items keep the names they have without CALL
+    assert_eq!(strings(&result, "p.name"), vec!["Carol", "Dave"]);
+    assert_eq!(ints(&result, "total"), vec![4, 4]);
+}
+
+#[tokio::test]
+async fn test_aggregation_over_subquery_rows() {
+    let result = query(
+        "MATCH (p:Person) \
+         CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.age AS age } \
+         RETURN p.name AS person, count(age) AS friends, max(age) AS oldest ORDER BY person",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "person"), vec!["Alice", "Bob", "Carol"]);
+    assert_eq!(ints(&result, "friends"), vec![3, 1, 1]);
+    assert_eq!(ints(&result, "oldest"), vec![40, 40, 30]);
+}
+
+#[tokio::test]
+async fn test_call_subquery_errors() {
+    // Returned expressions need a name to be read after CALL
+    let err = query(
+        "MATCH (p:Person) CALL { WITH p MATCH (p)-[:KNOWS]->(f:Person) RETURN f.name } \
+         RETURN p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("without an alias"), "{}", err);
+
+    // Only node variables can be imported
+    let err =
+        query("MATCH (p:Person)-[r:KNOWS]->(f:Person) CALL { WITH r RETURN 1 AS one } RETURN one")
+            .execute(social_graph(), None)
+            .await
+            .unwrap_err();
+    assert!(err.to_string().contains("imports 'r'"), "{}", err);
+
+    // CALL subqueries are experimental, and rejected unless opted in to
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    let err = CypherQuery::new(
+        "MATCH (p:Person) CALL { MATCH (n:Person) RETURN count(n) AS total } RETURN total",
+    )
+    .unwrap()
+    .with_config(config)
+    .execute(social_graph(), None)
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("'call_subquery'"), "{}", err);
+
+    // There is no single plan to explain
+    let err =
+        query("MATCH (p:Person) CALL { MATCH (n:Person) RETURN count(n) AS total } RETURN total")
+            .explain(social_graph())
+            .await
+            .unwrap_err();
+    assert!(err.to_string().contains("CALL subqueries"), "{}", err);
+}
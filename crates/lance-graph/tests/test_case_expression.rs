@@ -0,0 +1,119 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::{optional_ints, optional_strings};
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, true),
+        Field::new("city", DataType::Utf8, true),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(Int64Array::from(vec![Some(25), Some(45), Some(70), None])),
+            Arc::new(StringArray::from(vec![
+                Some("Paris"),
+                Some("Berlin"),
+                Some("Paris"),
+                None,
+            ])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+#[tokio::test]
+async fn test_searched_case_buckets_rows() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.name AS name, \
+                CASE WHEN p.age < 30 THEN 'young' WHEN p.age < 60 THEN 'adult' ELSE 'senior' END AS bucket \
+         ORDER BY name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    // A null age satisfies no WHEN condition and falls through to ELSE
+    assert_eq!(
+        optional_strings(&result, "bucket"),
+        vec![
+            Some("young".to_string()),
+            Some("adult".to_string()),
+            Some("senior".to_string()),
+            Some("senior".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_simple_case_without_else_is_null() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.name AS name, CASE p.city WHEN 'Paris' THEN 1 WHEN 'Berlin' THEN 2 END AS code \
+         ORDER BY name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        optional_ints(&result, "code"),
+        vec![Some(1), Some(2), Some(1), None]
+    );
+}
+
+#[tokio::test]
+async fn test_case_in_where_and_order_by() {
+    // Missing ages count as 0, and Paris sorts first
+    let result = query(
+        "MATCH (p:Person) WHERE CASE WHEN p.age IS NULL THEN 0 ELSE p.age END < 50 \
+         RETURN p.name AS name \
+         ORDER BY CASE p.city WHEN 'Paris' THEN 0 ELSE 1 END, name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        optional_strings(&result, "name"),
+        vec![
+            Some("Alice".to_string()),
+            Some("Bob".to_string()),
+            Some("Dave".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_aggregate_over_case() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN sum(CASE WHEN p.city = 'Paris' THEN 1 ELSE 0 END) AS parisians",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(optional_ints(&result, "parisians"), vec![Some(2)]);
+}
@@ -0,0 +1,152 @@
+use arrow_array::{Array, Int64Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance_graph::clustering::validate_sort_orders;
+use lance_graph::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+fn person_batch(ids: Vec<i64>) -> RecordBatch {
+    let names: Vec<String> = ids.iter().map(|id| format!("person{}", id)).collect();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(ids)),
+            Arc::new(StringArray::from(names)),
+        ],
+    )
+    .unwrap()
+}
+
+fn knows_batch(pairs: Vec<(i64, i64)>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("src_id", DataType::Int64, false),
+        Field::new("dst_id", DataType::Int64, false),
+    ]));
+    let (src, dst): (Vec<i64>, Vec<i64>) = pairs.into_iter().unzip();
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(src)),
+            Arc::new(Int64Array::from(dst)),
+        ],
+    )
+    .unwrap()
+}
+
+/// Append `batch` as a new fragment of the dataset at `path`
+async fn append(path: &Path, batch: RecordBatch) {
+    let schema = batch.schema();
+    let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+    let mode = if path.exists() {
+        WriteMode::Append
+    } else {
+        WriteMode::Create
+    };
+    Dataset::write(
+        reader,
+        path.to_str().unwrap(),
+        Some(WriteParams {
+            mode,
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap();
+}
+
+fn config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_mapping(NodeMapping::new("Person", "id").with_sorted_by(vec!["id".to_string()]))
+        .with_relationship_mapping(
+            RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                .with_sorted_by(vec!["src_id".to_string()]),
+        )
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_sorted_scans_report_their_order() {
+    let datasets = || {
+        HashMap::from([
+            ("Person".to_string(), person_batch(vec![1, 2, 3, 4])),
+            (
+                "KNOWS".to_string(),
+                knows_batch(vec![(1, 2), (2, 3), (3, 4)]),
+            ),
+        ])
+    };
+    let query = CypherQuery::new("MATCH (p:Person) WHERE p.id > 1 RETURN p.name ORDER BY p.id")
+        .unwrap()
+        .with_config(config());
+
+    let result = query.execute(datasets(), None).await.unwrap();
+    let names = result
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        names.iter().flatten().collect::<Vec<_>>(),
+        vec!["person2", "person3", "person4"]
+    );
+
+    let plan = query.explain(datasets()).await.unwrap();
+    assert!(plan.contains("SortedScanExec: sorted_by=[id]"), "{}", plan);
+
+    // Traversals over sorted datasets return the same rows
+    let result =
+        CypherQuery::new("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.id, b.id ORDER BY a.id")
+            .unwrap()
+            .with_config(config())
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+    let targets = result
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(targets.values().to_vec(), vec![2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_validate_sort_orders_against_fragments() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+    let person = tmp_dir.path().join("Person.lance");
+    let knows = tmp_dir.path().join("KNOWS.lance");
+    append(&person, person_batch(vec![1, 2])).await;
+    append(&person, person_batch(vec![3, 4])).await;
+    append(&knows, knows_batch(vec![(1, 2), (2, 3)])).await;
+    assert!(validate_sort_orders(&config(), &namespace)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // A fragment starting before the previous one ended, and edges out of
+    // order within their fragment
+    append(&person, person_batch(vec![0])).await;
+    append(&knows, knows_batch(vec![(3, 1), (2, 1)])).await;
+    let violations = validate_sort_orders(&config(), &namespace).await.unwrap();
+    assert_eq!(violations.len(), 2, "{:?}", violations);
+    let knows_violation = &violations[0];
+    assert_eq!(knows_violation.table, "KNOWS");
+    assert_eq!(knows_violation.fragment_id, 1);
+    assert!(knows_violation.message.contains("within the fragment"));
+    let person_violation = &violations[1];
+    assert_eq!(person_violation.table, "Person");
+    assert_eq!(person_violation.fragment_id, 2);
+    assert!(
+        person_violation.message.contains("before fragment 1 ends"),
+        "{}",
+        person_violation
+    );
+}
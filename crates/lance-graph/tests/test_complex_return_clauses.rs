@@ -70,6 +70,15 @@ fn create_graph_config() -> GraphConfig {
             id_field: "id".to_string(),
             property_fields: vec!["name".to_string()],
             filter_conditions: None,
+            view_of: None,
+            key_fields: Vec::new(),
+            source_table: None,
+            label_column: None,
+            soft_delete_column: None,
+            indexed_properties: Vec::new(),
+            ngram_properties: Vec::new(),
+            vector_properties: Vec::new(),
+            sorted_by: Vec::new(),
         })
         .build()
         .unwrap()
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{ints, query, social_graph, strings};
+
+#[tokio::test]
+async fn test_count_in_where() {
+    let result = query(
+        "MATCH (p:Person) WHERE COUNT { (p)-[:KNOWS]->(:Person) } >= 2 \
+         RETURN p.name ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice"]);
+}
+
+#[tokio::test]
+async fn test_count_in_return_is_zero_without_matches() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.name, COUNT { MATCH (p)-[:KNOWS]->(:Person) } AS friends \
+         ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        strings(&result, "p.name"),
+        vec!["Alice", "Bob", "Carol", "Dave"]
+    );
+    assert_eq!(ints(&result, "friends"), vec![3, 1, 1, 0]);
+}
+
+#[tokio::test]
+async fn test_count_with_where_and_other_conjuncts() {
+    // Carol's only friend is Alice, who is not older than 30
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE p.age >= 30 AND COUNT { (p)-[:KNOWS]->(f:Person) WHERE f.age > 30 } > 0 \
+         RETURN p.name, COUNT { (p)<-[:KNOWS]-(:Person) } AS followers",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice"]);
+    assert_eq!(ints(&result, "followers"), vec![1]);
+}
+
+#[tokio::test]
+async fn test_count_with_relationship_type_parameter() {
+    let result = query(
+        "MATCH (p:Person) WHERE COUNT { (p)<-[:$rel]-(:Person) } > 1 \
+         RETURN p.name ORDER BY p.name",
+    )
+    .with_parameter("rel", "KNOWS")
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Carol"]);
+}
+
+#[tokio::test]
+async fn test_count_after_with_is_rejected() {
+    let result = query(
+        "MATCH (p:Person) WITH p \
+         RETURN p.name, COUNT { (p)-[:KNOWS]->(:Person) } AS friends",
+    )
+    .execute(social_graph(), None)
+    .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,180 @@
+use arrow_array::RecordBatch;
+use lance::dataset::WriteMode;
+use lance_graph::config::GraphConfig;
+use lance_graph::{CypherQuery, DirNamespace, GraphError};
+use std::collections::HashMap;
+use std::path::Path;
+
+mod common;
+
+use common::{
+    ints, knows_batch, namespace, optional_strings, person_batch, query, strings, write_dataset,
+};
+
+/// Namespace with Alice(28), Bob(34) and Alice -> Bob
+async fn graph(dir: &Path) -> DirNamespace {
+    write_dataset(
+        &dir.join("Person.lance"),
+        person_batch(vec![1, 2], vec!["Alice", "Bob"], vec![28, 34]),
+        WriteMode::Create,
+    )
+    .await;
+    write_dataset(
+        &dir.join("KNOWS.lance"),
+        knows_batch(vec![1], vec![2]),
+        WriteMode::Create,
+    )
+    .await;
+    namespace(dir)
+}
+
+/// (nodes_created, relationships_created) of a CREATE result
+fn created(batch: &RecordBatch) -> (i64, i64) {
+    (
+        ints(batch, "nodes_created")[0],
+        ints(batch, "relationships_created")[0],
+    )
+}
+
+async fn friendships(dir: &Path) -> Vec<(String, String)> {
+    let result = query(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name ORDER BY a.name, b.name",
+    )
+    .execute_with_namespace(namespace(dir), None)
+    .await
+    .unwrap();
+    strings(&result, 0)
+        .into_iter()
+        .zip(strings(&result, 1))
+        .collect()
+}
+
+fn pair(a: &str, b: &str) -> (String, String) {
+    (a.to_string(), b.to_string())
+}
+
+#[tokio::test]
+async fn test_create_appends_nodes_and_relationships_with_parameters() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    let result = query(
+        "CREATE (n:Person {id: $id, name: $name, age: $age})\
+         -[:KNOWS]->(m:Person {id: 4, name: 'David', age: 40})",
+    )
+    .with_parameter("id", 3)
+    .with_parameter("name", "Carol")
+    .with_parameter("age", 29)
+    .execute_with_namespace(namespace.clone(), None)
+    .await
+    .unwrap();
+    assert_eq!(created(&result), (2, 1));
+
+    assert_eq!(
+        friendships(tmp_dir.path()).await,
+        vec![pair("Alice", "Bob"), pair("Carol", "David")]
+    );
+
+    // Label and relationship type parameters pick the written datasets
+    let result = query("CREATE (n:$label {id: 5, name: 'Eve', age: 31})")
+        .with_parameter("label", "Person")
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (1, 0));
+
+    let result = query("MATCH (a:Person {id: 5}), (b:Person {id: 1}) CREATE (a)-[:$rel]->(b)")
+        .with_parameter("rel", "KNOWS")
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (0, 1));
+
+    assert_eq!(
+        friendships(tmp_dir.path()).await,
+        vec![
+            pair("Alice", "Bob"),
+            pair("Carol", "David"),
+            pair("Eve", "Alice")
+        ]
+    );
+
+    // Appends must fill the dataset's non-nullable columns
+    let err = query("CREATE (n:Person {id: 5})")
+        .execute_with_namespace(namespace, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("non-nullable column 'name'"));
+}
+
+#[tokio::test]
+async fn test_match_create_attaches_relationships_to_matched_nodes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    let result = query(
+        "MATCH (m:Person) WHERE m.age > 30 \
+         CREATE (n:Person {id: 5, name: 'Eve', age: 22}), (n)-[:KNOWS]->(m)",
+    )
+    .execute_with_namespace(namespace.clone(), None)
+    .await
+    .unwrap();
+    assert_eq!(created(&result), (1, 1));
+    assert_eq!(
+        friendships(tmp_dir.path()).await,
+        vec![pair("Alice", "Bob"), pair("Eve", "Bob")]
+    );
+
+    // Patterns are created once per matched row, so no match creates nothing
+    let result = query(
+        "MATCH (m:Person) WHERE m.age > 90 \
+         CREATE (:Person {id: 6, name: 'Frank', age: 50})-[:KNOWS]->(m)",
+    )
+    .execute_with_namespace(namespace, None)
+    .await
+    .unwrap();
+    assert_eq!(created(&result), (0, 0));
+}
+
+#[tokio::test]
+async fn test_create_initializes_missing_datasets() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+
+    let result = CypherQuery::new("CREATE (:Person {id: 1, name: 'Alice'}), (:Person {id: 2})")
+        .unwrap()
+        .with_config(config.clone())
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (2, 0));
+
+    let result = CypherQuery::new("MATCH (p:Person) RETURN p.id, p.name ORDER BY p.id")
+        .unwrap()
+        .with_config(config)
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        optional_strings(&result, 1),
+        vec![Some("Alice".to_string()), None]
+    );
+}
+
+#[tokio::test]
+async fn test_create_requires_a_namespace() {
+    let mut datasets = HashMap::new();
+    datasets.insert(
+        "Person".to_string(),
+        person_batch(vec![1], vec!["Alice"], vec![28]),
+    );
+    datasets.insert("KNOWS".to_string(), knows_batch(vec![], vec![]));
+    let err = query("CREATE (:Person {id: 2, name: 'Bob', age: 34})")
+        .execute(datasets, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, GraphError::UnsupportedFeature { .. }));
+}
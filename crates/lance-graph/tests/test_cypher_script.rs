@@ -0,0 +1,180 @@
+use lance_graph::script::CypherScript;
+use std::path::Path;
+
+mod common;
+
+use common::{config, ints, namespace, strings};
+
+#[tokio::test]
+async fn test_script_statements_see_earlier_writes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let script = CypherScript::new(
+        "// People first\n\
+         CREATE (:Person {id: 1, name: 'Alice'}), (:Person {id: 2, name: $second});\n\
+         /* then who knows whom; the semicolon in here is ignored */\n\
+         MATCH (a:Person), (b:Person) WHERE a.id = 1 AND b.id = 2 CREATE (a)-[:KNOWS]->(b);\n\
+         MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name;\n",
+    )
+    .unwrap()
+    .with_config(config())
+    .with_parameter("second", "Bob");
+
+    let results = script
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(ints(&results[0], "nodes_created")[0], 2);
+    assert_eq!(ints(&results[1], "relationships_created")[0], 1);
+    assert_eq!(strings(&results[2], 0), vec!["Alice"]);
+    assert_eq!(strings(&results[2], 1), vec!["Bob"]);
+}
+
+#[tokio::test]
+async fn test_script_stops_at_the_failing_statement() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let script = CypherScript::new(
+        "CREATE (:Person {id: 1, name: 'Alice'});\
+         MATCH (x:Unknown) RETURN x.name;\
+         CREATE (:Person {id: 2, name: 'Bob'})",
+    )
+    .unwrap()
+    .with_config(config());
+
+    let err = script
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Statement 2"), "{}", err);
+
+    // The first statement's writes are kept, the third never ran
+    let people = CypherScript::new("MATCH (p:Person) RETURN p.name")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+    assert_eq!(strings(&people[0], 0), vec!["Alice"]);
+}
+
+async fn version(dir: &Path, table: &str) -> u64 {
+    let uri = dir.join(format!("{}.lance", table));
+    lance::Dataset::open(uri.to_str().unwrap())
+        .await
+        .unwrap()
+        .version()
+        .version
+}
+
+/// Entries of `dir` other than the datasets, such as leftover staging copies
+fn leftovers(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.ends_with(".lance"))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_atomic_script_publishes_one_version_per_dataset() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    CypherScript::new("CREATE (:Person {id: 1, name: 'Alice'})")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+    assert_eq!(version(tmp_dir.path(), "Person").await, 1);
+
+    let results = CypherScript::new(
+        "CREATE (:Person {id: 2, name: 'Bob'});\
+         CREATE (:Person {id: 3, name: 'Carol'});\
+         MATCH (a:Person), (b:Person) WHERE a.id = 1 AND b.id > 1 CREATE (a)-[:KNOWS]->(b);\
+         MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name ORDER BY b.name",
+    )
+    .unwrap()
+    .with_config(config())
+    .execute_atomically(namespace(tmp_dir.path()))
+    .await
+    .unwrap();
+
+    // The last statement already sees the staged writes
+    assert_eq!(ints(&results[2], "relationships_created")[0], 2);
+    assert_eq!(strings(&results[3], 1), vec!["Bob", "Carol"]);
+
+    // Two CREATEs on Person, published as one version
+    assert_eq!(version(tmp_dir.path(), "Person").await, 2);
+    assert_eq!(version(tmp_dir.path(), "KNOWS").await, 1);
+    assert!(leftovers(tmp_dir.path()).is_empty());
+}
+
+#[tokio::test]
+async fn test_failing_atomic_script_publishes_nothing() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    CypherScript::new("CREATE (:Person {id: 1, name: 'Alice'})")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+
+    let err = CypherScript::new(
+        "CREATE (:Person {id: 2, name: 'Bob'});\
+         MATCH (a:Person), (b:Person) WHERE a.id = 1 AND b.id = 2 CREATE (a)-[:KNOWS]->(b);\
+         MATCH (x:Unknown) RETURN x.name",
+    )
+    .unwrap()
+    .with_config(config())
+    .execute_atomically(namespace(tmp_dir.path()))
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("Statement 3"), "{}", err);
+
+    // Neither Bob nor the KNOWS dataset made it out of staging
+    assert_eq!(version(tmp_dir.path(), "Person").await, 1);
+    assert!(!tmp_dir.path().join("KNOWS.lance").exists());
+    assert!(leftovers(tmp_dir.path()).is_empty());
+    let people = CypherScript::new("MATCH (p:Person) RETURN p.name")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+    assert_eq!(strings(&people[0], 0), vec!["Alice"]);
+}
+
+#[tokio::test]
+async fn test_atomic_script_reads_its_uncommitted_merges() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    CypherScript::new("CREATE (:Person {id: 1, name: 'Alice'})")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()))
+        .await
+        .unwrap();
+
+    let results = CypherScript::new(
+        "MERGE (:Person {id: 2, name: 'Bob'});\
+         MATCH (p:Person) WHERE p.id = 2 RETURN p.name;\
+         MERGE (:Person {id: 2, name: 'Bob'});\
+         MATCH (p:Person) WHERE p.id = 2 SET p.name = 'Robert';\
+         MERGE (:Person {id: 3, name: 'Carol'});\
+         MATCH (p:Person) RETURN p.name ORDER BY p.name",
+    )
+    .unwrap()
+    .with_config(config())
+    .execute_atomically(namespace(tmp_dir.path()))
+    .await
+    .unwrap();
+
+    // The second MERGE matches the node the first one staged
+    assert_eq!(ints(&results[0], "nodes_created")[0], 1);
+    assert_eq!(strings(&results[1], 0), vec!["Bob"]);
+    assert_eq!(ints(&results[2], "nodes_created")[0], 0);
+    assert_eq!(ints(&results[3], "properties_set")[0], 1);
+    assert_eq!(strings(&results[5], 0), vec!["Alice", "Carol", "Robert"]);
+
+    assert_eq!(version(tmp_dir.path(), "Person").await, 2);
+    assert!(leftovers(tmp_dir.path()).is_empty());
+}
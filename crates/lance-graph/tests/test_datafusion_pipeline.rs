@@ -1,4 +1,4 @@
-use arrow_array::{Array, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_array::{Array, Float64Array, Int64Array, ListArray, RecordBatch, StringArray};
 use arrow_schema::{DataType, Field, Schema};
 use lance_graph::config::GraphConfig;
 use lance_graph::{CypherQuery, ExecutionStrategy};
@@ -1139,6 +1139,22 @@ async fn test_datafusion_two_hop_distinct() {
     assert_eq!(names, vec!["Charlie", "David", "Eve"]);
 }
 
+#[tokio::test]
+async fn test_datafusion_two_hop_distinct_on_entity() {
+    // Query: one row per destination, whichever path reached it
+    let out = execute_test_query(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person)-[:KNOWS]->(c:Person) \
+         RETURN DISTINCT ON (c) c.name, a.name AS via",
+    )
+    .await;
+
+    assert_eq!(out.num_columns(), 2);
+    let mut names = get_string_column(&out, 0);
+    names.sort();
+
+    assert_eq!(names, vec!["Charlie", "David", "Eve"]);
+}
+
 #[tokio::test]
 async fn test_datafusion_two_hop_no_results() {
     // Query: Two-hop starting from Eve (who has no outgoing edges)
@@ -4512,7 +4528,7 @@ async fn test_collect_with_grouping() {
 #[tokio::test]
 async fn test_collect_with_null_values() {
     // Test COLLECT handles NULL values correctly
-    // David has NULL city, so collecting cities should include the null
+    // David has NULL city, which collect() skips
     let person_batch = create_person_dataset();
     let config = GraphConfig::builder()
         .with_node_label("Person", "id")
@@ -4535,10 +4551,70 @@ async fn test_collect_with_null_values() {
     // COLLECT returns a single row with an array
     assert_eq!(result.num_rows(), 1);
 
-    // Verify the column exists and has 5 elements (including the NULL)
-    let all_cities_col = result.column_by_name("all_cities").unwrap();
-    // The array should have been created successfully
-    assert!(!all_cities_col.is_empty());
+    // The list holds the four non-null cities
+    let all_cities = result
+        .column_by_name("all_cities")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    assert_eq!(all_cities.value(0).len(), 4);
+    assert_eq!(all_cities.value(0).null_count(), 0);
+}
+
+#[tokio::test]
+async fn test_collect_distinct_feeds_unwind() {
+    // collect(DISTINCT ...) deduplicates before building the list, and the
+    // list column can be unwound again downstream
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_relationship("KNOWS", "src_person_id", "dst_person_id")
+        .build()
+        .unwrap();
+    let datasets = || {
+        HashMap::from([
+            ("Person".to_string(), create_person_dataset()),
+            ("KNOWS".to_string(), create_knows_dataset()),
+        ])
+    };
+
+    let query = CypherQuery::new(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) \
+         RETURN collect(a.name) AS names, collect(DISTINCT a.name) AS distinct_names",
+    )
+    .unwrap()
+    .with_config(config.clone());
+    let result = query.execute(datasets(), None).await.unwrap();
+    let list_len = |column: &str| {
+        result
+            .column_by_name(column)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap()
+            .value(0)
+            .len()
+    };
+    assert_eq!(list_len("names"), 5);
+    assert_eq!(list_len("distinct_names"), 4);
+
+    let query = CypherQuery::new(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) WITH collect(DISTINCT a.name) AS names \
+         UNWIND names AS name RETURN name ORDER BY name",
+    )
+    .unwrap()
+    .with_config(config);
+    let result = query.execute(datasets(), None).await.unwrap();
+    let names = result
+        .column_by_name("name")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        names.iter().flatten().collect::<Vec<_>>(),
+        vec!["Alice", "Bob", "Charlie", "David"]
+    );
 }
 
 // ============================================================================
@@ -4617,6 +4693,60 @@ async fn test_with_aggregation() {
     assert!(result.column_by_name("total").is_some());
 }
 
+#[tokio::test]
+async fn test_with_distinct() {
+    // Alice knows two people; WITH DISTINCT passes her on once
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_relationship("KNOWS", "src_person_id", "dst_person_id")
+        .build()
+        .unwrap();
+    let datasets = || {
+        HashMap::from([
+            ("Person".to_string(), create_person_dataset()),
+            ("KNOWS".to_string(), create_knows_dataset()),
+        ])
+    };
+
+    let query = CypherQuery::new(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) WITH DISTINCT a.name AS name \
+         RETURN name ORDER BY name",
+    )
+    .unwrap()
+    .with_config(config.clone());
+    let result = query.execute(datasets(), None).await.unwrap();
+    let names = result
+        .column_by_name("name")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        names.iter().flatten().collect::<Vec<_>>(),
+        vec!["Alice", "Bob", "Charlie", "David"]
+    );
+
+    // Aggregates over the deduplicated rows
+    let query = CypherQuery::new(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) WITH DISTINCT a.name AS name \
+         RETURN count(name) AS total, count(DISTINCT name) AS distinct_total",
+    )
+    .unwrap()
+    .with_config(config);
+    let result = query.execute(datasets(), None).await.unwrap();
+    let total = |column: &str| {
+        result
+            .column_by_name(column)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0)
+    };
+    assert_eq!(total("total"), 4);
+    assert_eq!(total("distinct_total"), 4);
+}
+
 #[tokio::test]
 async fn test_with_order_by_limit_and_where() {
     // Test WITH with ORDER BY, LIMIT, and post-WITH WHERE filter
@@ -5101,3 +5231,76 @@ async fn test_datafusion_variable_reuse_multi_pattern_optimization() {
         person_scan_count
     );
 }
+
+#[tokio::test]
+async fn test_datafusion_optional_match_keeps_unmatched_rows() {
+    let result = execute_test_query(
+        "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) \
+         RETURN a.name, b.name ORDER BY a.name, b.name",
+    )
+    .await;
+
+    let friends = result
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let rows: Vec<(String, Option<&str>)> = get_string_column(&result, 0)
+        .into_iter()
+        .zip(friends.iter())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("Alice".to_string(), Some("Bob")),
+            ("Alice".to_string(), Some("Charlie")),
+            ("Bob".to_string(), Some("Charlie")),
+            ("Charlie".to_string(), Some("David")),
+            ("David".to_string(), Some("Eve")),
+            // Eve knows nobody but is kept with a null friend
+            ("Eve".to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_datafusion_optional_match_where_nulls_instead_of_dropping() {
+    let result = execute_test_query(
+        "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) WHERE b.age > 30 \
+         RETURN a.name, b.name ORDER BY a.name",
+    )
+    .await;
+
+    let friends = result
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let rows: Vec<(String, Option<&str>)> = get_string_column(&result, 0)
+        .into_iter()
+        .zip(friends.iter())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("Alice".to_string(), Some("Bob")),
+            ("Bob".to_string(), None),
+            ("Charlie".to_string(), Some("David")),
+            ("David".to_string(), None),
+            ("Eve".to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_datafusion_optional_match_null_aware_outer_where() {
+    // The query-level WHERE sees the nulls of unmatched rows
+    let result = execute_test_query(
+        "MATCH (a:Person) OPTIONAL MATCH (a)-[:KNOWS]->(b:Person) WHERE b.age > 30 \
+         WITH a.name AS name, b.name AS friend WHERE friend IS NULL \
+         RETURN name ORDER BY name",
+    )
+    .await;
+
+    assert_eq!(get_string_column(&result, 0), vec!["Bob", "David", "Eve"]);
+}
@@ -1,4 +1,4 @@
-use arrow_array::{BooleanArray, Int64Array, RecordBatch, StringArray};
+use arrow_array::{BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
 use arrow_schema::{DataType, Field, Schema};
 use lance_graph::config::GraphConfig;
 use lance_graph::{CypherQuery, ExecutionStrategy};
@@ -1214,3 +1214,73 @@ async fn test_disconnected_with_aggregation() {
         .unwrap();
     assert_eq!(count.value(0), 7);
 }
+
+#[tokio::test]
+async fn test_seeded_random_functions_are_reproducible() {
+    let run = |seed: u64| async move {
+        let graph = animals_graph();
+        CypherQuery::new(
+            "MATCH (a:Animal) RETURN a.name, rand() AS r, randomUUID() AS uid, timestamp() AS ts \
+             ORDER BY a.name",
+        )
+        .unwrap()
+        .with_config(graph.config)
+        .with_seed(seed)
+        .execute(graph.datasets, Some(ExecutionStrategy::DataFusion))
+        .await
+        .unwrap()
+    };
+    let first = run(7).await;
+    let second = run(7).await;
+    let other = run(8).await;
+
+    assert_eq!(first.column(1), second.column(1));
+    assert_eq!(first.column(2), second.column(2));
+    assert_ne!(first.column(1), other.column(1));
+
+    let r = first
+        .column(1)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert!(r.values().iter().all(|v| (0.0..1.0).contains(v)));
+    let ts = first
+        .column(3)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    // Milliseconds since the epoch, after 2020-01-01
+    assert!(ts.value(0) > 1_577_836_800_000);
+}
+
+#[tokio::test]
+async fn test_sample_clause() {
+    let run = |cypher: &'static str| async move {
+        let graph = animals_graph();
+        CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(graph.config)
+            .with_seed(11)
+            .execute(graph.datasets, Some(ExecutionStrategy::DataFusion))
+            .await
+            .unwrap()
+    };
+
+    let sample = run("MATCH (a:Animal) SAMPLE 3 ROWS RETURN a.name ORDER BY a.name").await;
+    assert_eq!(sample.num_rows(), 3);
+    let again = run("MATCH (a:Animal) SAMPLE 3 ROWS RETURN a.name ORDER BY a.name").await;
+    assert_eq!(sample.column(0), again.column(0));
+
+    // One animal per leg count: 6, 2 and 4
+    let stratified =
+        run("MATCH (a:Animal) SAMPLE 1 ROWS PER a.legs RETURN a.legs ORDER BY a.legs").await;
+    let legs = stratified
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(legs.values().to_vec(), vec![2, 4, 6]);
+
+    let all = run("MATCH (a:Animal) SAMPLE 100 PERCENT RETURN a.name").await;
+    assert_eq!(all.num_rows(), 5);
+}
@@ -1,6 +1,7 @@
-use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_array::{Int64Array, ListArray, RecordBatch, StringArray, StructArray};
 use arrow_schema::{DataType, Field, Schema};
 use lance_graph::config::GraphConfig;
+use lance_graph::expansion::{ExpansionLimits, PathTruncation};
 use lance_graph::{CypherQuery, ExecutionStrategy};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -834,3 +835,279 @@ async fn test_varlength_all_pairs_reachability() {
         "Should find at least 15 connected pairs"
     );
 }
+
+async fn reachable_names(cypher: &str) -> Vec<String> {
+    let query = CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(create_complex_graph_config());
+
+    let mut datasets = HashMap::new();
+    datasets.insert("Person".to_string(), create_complex_person_dataset());
+    datasets.insert("KNOWS".to_string(), create_complex_knows_dataset());
+
+    let out = query
+        .execute(datasets, Some(ExecutionStrategy::DataFusion))
+        .await
+        .unwrap();
+    let names = out
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    (0..out.num_rows())
+        .map(|i| names.value(i).to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_varlength_relationship_property_filter_applies_per_hop() {
+    // Only strength-5 edges: Alice->Bob, then Bob->Eve
+    let names = reachable_names(
+        "MATCH (a:Person {name: 'Alice'})-[:KNOWS*1..2 {strength: 5}]->(b:Person) \
+         RETURN DISTINCT b.name ORDER BY b.name",
+    )
+    .await;
+    assert_eq!(names, vec!["Bob", "Eve"]);
+}
+
+#[tokio::test]
+async fn test_varlength_path_predicates_prune_intermediate_nodes() {
+    // Two-hop targets are Diana, Eve, Henry (via Bob) and Frank, Diana (via
+    // Charlie); excluding Charlie drops Frank but keeps Diana through Bob
+    let names = reachable_names(
+        "MATCH p = (a:Person {name: 'Alice'})-[:KNOWS*2..2]->(b:Person) \
+         WHERE all(x IN nodes(p) WHERE x.name <> 'Charlie') \
+         RETURN DISTINCT b.name ORDER BY b.name",
+    )
+    .await;
+    assert_eq!(names, vec!["Diana", "Eve", "Henry"]);
+
+    // Remaining WHERE conjuncts still filter the endpoints
+    let names = reachable_names(
+        "MATCH p = (a:Person {name: 'Alice'})-[:KNOWS*2..2]->(b:Person) \
+         WHERE all(x IN nodes(p) WHERE x.name <> 'Charlie') AND b.age > 30 \
+         RETURN DISTINCT b.name ORDER BY b.name",
+    )
+    .await;
+    assert_eq!(names, vec!["Diana", "Henry"]);
+
+    // Relationship predicates: Alice->Charlie (3) and Bob->Henry (3) are pruned
+    let names = reachable_names(
+        "MATCH p = (a:Person {name: 'Alice'})-[:KNOWS*1..2]->(b:Person) \
+         WHERE all(r IN relationships(p) WHERE r.strength >= 4) \
+         RETURN DISTINCT b.name ORDER BY b.name",
+    )
+    .await;
+    assert_eq!(names, vec!["Bob", "Diana", "Eve"]);
+}
+
+async fn execute_with_limits(cypher: &str, limits: ExpansionLimits) -> RecordBatch {
+    let query = CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(create_complex_graph_config())
+        .with_expansion_limits(limits);
+
+    let mut datasets = HashMap::new();
+    datasets.insert("Person".to_string(), create_complex_person_dataset());
+    datasets.insert("KNOWS".to_string(), create_complex_knows_dataset());
+
+    query
+        .execute(datasets, Some(ExecutionStrategy::DataFusion))
+        .await
+        .unwrap()
+}
+
+fn string_column(batch: &RecordBatch, index: usize) -> Vec<String> {
+    let values = batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    (0..batch.num_rows())
+        .map(|i| values.value(i).to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_varlength_per_source_cap_keeps_shortest_paths() {
+    let out = execute_with_limits(
+        "MATCH (a:Person {name: 'Alice'})-[:KNOWS*1..3]->(b:Person) \
+         RETURN b.name ORDER BY b.name",
+        ExpansionLimits::new().with_max_paths_per_source(2),
+    )
+    .await;
+
+    // Alice's two one-hop neighbours win over every longer path
+    assert_eq!(string_column(&out, 0), vec!["Bob", "Charlie"]);
+    assert_eq!(
+        PathTruncation::from_schema(&out.schema()),
+        PathTruncation {
+            per_source: true,
+            max_paths: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_varlength_global_cap_marks_truncation() {
+    let out = execute_with_limits(
+        "MATCH (a:Person)-[:KNOWS*1..2]->(b:Person) \
+         RETURN a.name, b.name ORDER BY a.name, b.name",
+        ExpansionLimits::new().with_max_paths(3),
+    )
+    .await;
+
+    // One-hop paths ordered by source then target id: 1->2, 1->3, 2->4
+    assert_eq!(string_column(&out, 0), vec!["Alice", "Alice", "Bob"]);
+    assert_eq!(string_column(&out, 1), vec!["Bob", "Charlie", "Diana"]);
+    let truncation = PathTruncation::from_schema(&out.schema());
+    assert!(truncation.max_paths);
+    assert!(!truncation.per_source);
+}
+
+#[tokio::test]
+async fn test_varlength_limits_not_reached_leave_result_unmarked() {
+    let out = execute_with_limits(
+        "MATCH (a:Person {name: 'Alice'})-[:KNOWS*1..2]->(b:Person) RETURN b.name",
+        ExpansionLimits::new()
+            .with_max_paths_per_source(100)
+            .with_max_paths(100),
+    )
+    .await;
+
+    // Bob, Charlie, then Diana, Eve, Henry, Frank, Diana
+    assert_eq!(out.num_rows(), 7);
+    assert!(!PathTruncation::from_schema(&out.schema()).is_truncated());
+}
+
+#[tokio::test]
+async fn test_varlength_unbounded_reaches_through_cycle() {
+    // `*` follows paths up to the hop cap; the Henry -> Alice edge brings
+    // Alice back into her own reach
+    let names = reachable_names(
+        "MATCH (a:Person {name: 'Alice'})-[:KNOWS*]->(b:Person) \
+         RETURN DISTINCT b.name ORDER BY b.name",
+    )
+    .await;
+    assert_eq!(
+        names,
+        vec!["Alice", "Bob", "Charlie", "Diana", "Eve", "Frank", "Grace", "Henry", "Iris", "Jack"]
+    );
+}
+
+#[tokio::test]
+async fn test_varlength_path_length_binding() {
+    let out = execute_with_limits(
+        "MATCH p = (a:Person {name: 'Alice'})-[:KNOWS*1..2]->(b:Person) \
+         RETURN b.name AS name, length(p) AS hops ORDER BY hops, name",
+        ExpansionLimits::new(),
+    )
+    .await;
+
+    assert_eq!(
+        string_column(&out, 0),
+        vec!["Bob", "Charlie", "Diana", "Diana", "Eve", "Frank", "Henry"]
+    );
+    let hops = out.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(hops.values().to_vec(), vec![1, 1, 2, 2, 2, 2, 2]);
+
+    // Shortest route from Alice to Jack: Alice -> Bob/Charlie -> Diana -> Jack
+    let out = execute_with_limits(
+        "MATCH p = (a:Person {name: 'Alice'})-[:KNOWS*1..5]->(b:Person {name: 'Jack'}) \
+         RETURN min(length(p)) AS shortest",
+        ExpansionLimits::new(),
+    )
+    .await;
+    let shortest = out.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(shortest.value(0), 3);
+}
+
+/// Node ids of each path object in column `index`
+fn path_nodes(batch: &RecordBatch, index: usize) -> Vec<Vec<i64>> {
+    let paths = batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    let nodes = paths
+        .column_by_name("nodes")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    (0..batch.num_rows())
+        .map(|i| {
+            let ids = nodes.value(i);
+            ids.as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_shortest_path_returns_path_object() {
+    let out = execute_with_limits(
+        "MATCH p = shortestPath((a:Person {name: 'Alice'})-[:KNOWS*]->(b:Person {name: 'Jack'})) \
+         RETURN length(p) AS hops, p",
+        ExpansionLimits::new(),
+    )
+    .await;
+
+    assert_eq!(out.num_rows(), 1);
+    let hops = out.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(hops.value(0), 3);
+    // Alice -> Bob or Charlie -> Diana -> Jack
+    let nodes = &path_nodes(&out, 1)[0];
+    assert_eq!(nodes.len(), 4);
+    assert_eq!((nodes[0], nodes[2], nodes[3]), (1, 4, 10));
+    assert!(nodes[1] == 2 || nodes[1] == 3);
+}
+
+#[tokio::test]
+async fn test_all_shortest_paths_keeps_every_path_of_shortest_length() {
+    let out = execute_with_limits(
+        "MATCH p = allShortestPaths((a:Person {name: 'Alice'})-[:KNOWS*]->(b:Person {name: 'Jack'})) \
+         RETURN length(p) AS hops, p",
+        ExpansionLimits::new(),
+    )
+    .await;
+
+    let hops = out.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(hops.values().to_vec(), vec![3, 3]);
+    let mut paths = path_nodes(&out, 1);
+    paths.sort();
+    assert_eq!(paths, vec![vec![1, 2, 4, 10], vec![1, 3, 4, 10]]);
+}
+
+#[tokio::test]
+async fn test_shortest_path_per_end_node() {
+    // One shortest path to every node Alice reaches, herself through the cycle
+    let out = execute_with_limits(
+        "MATCH p = shortestPath((a:Person {name: 'Alice'})-[:KNOWS*]->(b:Person)) \
+         RETURN b.name AS name, length(p) AS hops ORDER BY name",
+        ExpansionLimits::new(),
+    )
+    .await;
+
+    assert_eq!(
+        string_column(&out, 0),
+        vec!["Alice", "Bob", "Charlie", "Diana", "Eve", "Frank", "Grace", "Henry", "Iris", "Jack"]
+    );
+    let hops = out.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(hops.values().to_vec(), vec![3, 1, 1, 2, 2, 2, 3, 2, 3, 3]);
+
+    // A path predicate keeps the declared range and still yields the shortest
+    // path avoiding Diana
+    let out = execute_with_limits(
+        "MATCH p = shortestPath((a:Person {name: 'Alice'})-[:KNOWS*..6]->(b:Person {name: 'Jack'})) \
+         WHERE all(x IN nodes(p) WHERE x.name <> 'Diana') \
+         RETURN length(p) AS hops",
+        ExpansionLimits::new(),
+    )
+    .await;
+    let hops = out.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(hops.values().to_vec(), vec![4]);
+}
@@ -749,3 +749,34 @@ async fn test_vector_literal_in_return_clause() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_order_by_weighted_similarity_fusion() -> Result<()> {
+    let (config, datasets) = create_person_graph_with_embeddings();
+
+    // Eve sits between both query vectors, so she leads the fused score
+    // while neither single similarity ranks her first
+    let query = CypherQuery::new(
+        "MATCH (p:Person) RETURN p.name \
+         ORDER BY 0.5 * vector_similarity(p.embedding, $a) \
+         + 0.5 * vector_similarity(p.embedding, $b) DESC LIMIT 2",
+    )?
+    .with_config(config)
+    .with_parameter("a", serde_json::json!([1.0, 0.0, 0.0]))
+    .with_parameter("b", serde_json::json!([0.0, 1.0, 0.0]));
+
+    let result = query
+        .execute(datasets, Some(ExecutionStrategy::DataFusion))
+        .await?;
+
+    assert_eq!(result.num_rows(), 2);
+    let names = result
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(names.value(0), "Eve");
+    assert_eq!(names.value(1), "Bob");
+
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteMode};
+use lance_graph::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+use common::{config, ints, knows_batch, namespace, strings, write_dataset};
+
+fn person_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("deleted_at", DataType::Int64, true),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            Arc::new(Int64Array::from(vec![None, None, None])),
+        ],
+    )
+    .unwrap()
+}
+
+/// Namespace with Alice, Bob, Carol and Alice -> Bob -> Carol
+async fn graph(dir: &Path) -> DirNamespace {
+    write_dataset(&dir.join("Person.lance"), person_batch(), WriteMode::Create).await;
+    write_dataset(
+        &dir.join("KNOWS.lance"),
+        knows_batch(vec![1, 2], vec![2, 3]),
+        WriteMode::Create,
+    )
+    .await;
+    namespace(dir)
+}
+
+/// (nodes_deleted, relationships_deleted) of a DELETE result
+fn deleted(batch: &RecordBatch) -> (i64, i64) {
+    (
+        ints(batch, "nodes_deleted")[0],
+        ints(batch, "relationships_deleted")[0],
+    )
+}
+
+async fn run(dir: &Path, config: &GraphConfig, cypher: &str) -> lance_graph::Result<RecordBatch> {
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config.clone())
+        .execute_with_namespace(namespace(dir), None)
+        .await
+}
+
+async fn names(dir: &Path, config: &GraphConfig) -> Vec<String> {
+    let result = run(
+        dir,
+        config,
+        "MATCH (n:Person) RETURN n.name ORDER BY n.name",
+    )
+    .await
+    .unwrap();
+    strings(&result, 0)
+}
+
+async fn friendships(dir: &Path) -> Vec<String> {
+    let result = run(
+        dir,
+        &config(),
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name ORDER BY a.name, b.name",
+    )
+    .await
+    .unwrap();
+    strings(&result, 0)
+        .into_iter()
+        .zip(strings(&result, 1))
+        .map(|(a, b)| format!("{}->{}", a, b))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_delete_relationships_and_their_nodes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    graph(tmp_dir.path()).await;
+
+    let result = run(
+        tmp_dir.path(),
+        &config(),
+        "MATCH (a:Person {id: 1})-[r:KNOWS]->(b:Person) DELETE r",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (0, 1));
+    assert_eq!(friendships(tmp_dir.path()).await, vec!["Bob->Carol"]);
+
+    // Deleting Carol's only relationship with her needs no DETACH
+    let result = run(
+        tmp_dir.path(),
+        &config(),
+        "MATCH (b:Person)-[r:KNOWS]->(c:Person {id: 3}) DELETE r, c",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (1, 1));
+    assert_eq!(names(tmp_dir.path(), &config()).await, vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_detach_delete_removes_incident_relationships() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    graph(tmp_dir.path()).await;
+
+    let err = run(
+        tmp_dir.path(),
+        &config(),
+        "MATCH (n:Person {id: 2}) DELETE n",
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("use DETACH DELETE"));
+    assert_eq!(names(tmp_dir.path(), &config()).await.len(), 3);
+
+    let result = run(
+        tmp_dir.path(),
+        &config(),
+        "MATCH (n:Person {id: 2}) DETACH DELETE n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (1, 2));
+    assert_eq!(
+        names(tmp_dir.path(), &config()).await,
+        vec!["Alice", "Carol"]
+    );
+    assert!(friendships(tmp_dir.path()).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_only_touches_relationships_of_the_nodes_label() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    graph(tmp_dir.path()).await;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let companies = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 3])),
+            Arc::new(StringArray::from(vec!["Acme", "Initech"])),
+        ],
+    )
+    .unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Company.lance"),
+        companies,
+        WriteMode::Create,
+    )
+    .await;
+    // Bob works at companies 1 and 3, which share their ids with Alice and Carol
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("person_id", DataType::Int64, false),
+        Field::new("company_id", DataType::Int64, false),
+    ]));
+    let works_at = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![2, 2])),
+            Arc::new(Int64Array::from(vec![1, 3])),
+        ],
+    )
+    .unwrap();
+    write_dataset(
+        &tmp_dir.path().join("WORKS_AT.lance"),
+        works_at,
+        WriteMode::Create,
+    )
+    .await;
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_node_label("Company", "id")
+        .with_relationship_mapping(
+            RelationshipMapping::new("KNOWS", "src_id", "dst_id")
+                .with_endpoints("Person", "Person"),
+        )
+        .with_relationship_mapping(
+            RelationshipMapping::new("WORKS_AT", "person_id", "company_id")
+                .with_endpoints("Person", "Company"),
+        )
+        .build()
+        .unwrap();
+
+    let result = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (n:Person {id: 1}) DETACH DELETE n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (1, 1));
+
+    // Carol's id is a company's id, but she has no WORKS_AT relationships
+    let result = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (b:Person)-[r:KNOWS]->(c:Person {id: 3}) DELETE r, c",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (1, 1));
+
+    let err = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (c:Company {id: 1}) DELETE c",
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("'WORKS_AT' relationships"),
+        "{}",
+        err
+    );
+
+    let result = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (p:Person)-[:WORKS_AT]->(c:Company) RETURN c.name ORDER BY c.name",
+    )
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, 0), vec!["Acme", "Initech"]);
+
+    // Without declared endpoints, a node's relationships are unknown
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_relationship("KNOWS", "src_id", "dst_id")
+        .build()
+        .unwrap();
+    let err = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (n:Person {id: 2}) DETACH DELETE n",
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("endpoint labels"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_delete_marks_soft_deleted_labels() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(),
+        WriteMode::Create,
+    )
+    .await;
+    let config = GraphConfig::builder()
+        .with_node_mapping(NodeMapping::new("Person", "id").with_soft_delete_column("deleted_at"))
+        .build()
+        .unwrap();
+
+    let result = run(
+        tmp_dir.path(),
+        &config,
+        "MATCH (n:Person) WHERE n.name = 'Alice' DELETE n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(deleted(&result), (1, 0));
+    assert_eq!(names(tmp_dir.path(), &config).await, vec!["Bob", "Carol"]);
+
+    let dataset = Dataset::open(tmp_dir.path().join("Person.lance").to_str().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(dataset.count_rows(None).await.unwrap(), 3);
+    assert_eq!(
+        dataset
+            .count_rows(Some("deleted_at IS NOT NULL".to_string()))
+            .await
+            .unwrap(),
+        1
+    );
+}
@@ -0,0 +1,53 @@
+use arrow_array::RecordBatch;
+use lance_graph::config::{GraphConfig, NodeMapping};
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+
+mod common;
+
+use common::{person_batch, strings};
+
+/// `Person` nodes with indexed `name` and `age`, so ORs across them become a
+/// union of scans
+fn config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_mapping(
+            NodeMapping::new("Person", "id")
+                .with_indexed_properties(vec!["name".to_string(), "age".to_string()]),
+        )
+        .build()
+        .unwrap()
+}
+
+async fn names(people: RecordBatch) -> Vec<String> {
+    let result = CypherQuery::new(
+        "MATCH (n:Person) WHERE n.name = 'Alice' OR n.age = 30 \
+         RETURN n.name ORDER BY n.name",
+    )
+    .unwrap()
+    .with_config(config())
+    .execute(HashMap::from([("Person".to_string(), people)]), None)
+    .await
+    .unwrap();
+    strings(&result, "n.name")
+}
+
+#[tokio::test]
+async fn test_row_matching_both_disjuncts_is_returned_once() {
+    let people = person_batch(
+        vec![1, 2, 3],
+        vec!["Alice", "Bob", "Carol"],
+        vec![30, 30, 25],
+    );
+    assert_eq!(names(people).await, vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_duplicate_rows_keep_their_multiplicity() {
+    let people = person_batch(
+        vec![1, 1, 2, 2, 3],
+        vec!["Alice", "Alice", "Bob", "Bob", "Carol"],
+        vec![30, 30, 35, 35, 30],
+    );
+    assert_eq!(names(people).await, vec!["Alice", "Alice", "Carol"]);
+}
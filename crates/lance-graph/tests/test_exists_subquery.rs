@@ -0,0 +1,108 @@
+mod common;
+
+use common::{query, social_graph, strings};
+
+#[tokio::test]
+async fn test_exists_keeps_each_matching_row_once() {
+    // Alice knows three people but is returned once
+    let result = query(
+        "MATCH (p:Person) WHERE EXISTS { (p)-[:KNOWS]->(:Person) } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Bob", "Carol"]);
+}
+
+#[tokio::test]
+async fn test_exists_with_where() {
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE EXISTS { MATCH (p)-[:KNOWS]->(f:Person) WHERE f.age > 35 } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_not_exists_with_other_conjuncts() {
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE p.age > 30 AND NOT EXISTS { (p)-[:KNOWS]->(f:Person) WHERE f.age < 30 } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Carol", "Dave"]);
+
+    let result =
+        query("MATCH (p:Person) WHERE NOT EXISTS { (p)-[:KNOWS]->(:Person) } RETURN p.name")
+            .execute(social_graph(), None)
+            .await
+            .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Dave"]);
+}
+
+#[tokio::test]
+async fn test_parameters_inside_exists() {
+    // Relationship type and IN-list parameters are bound inside the subquery
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE EXISTS { (p)-[:$rel]->(f:Person) WHERE f.name IN $names } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .with_parameter("rel", "KNOWS")
+    .with_parameter("names", serde_json::json!(["Dave", "Alice"]))
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Carol"]);
+
+    // Scalar parameters are bound inside the subquery as well
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE EXISTS { (p)-[:KNOWS]->(f:Person) WHERE f.age >= $min } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .with_parameter("min", 40)
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Bob"]);
+
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE EXISTS { (p)-[:KNOWS]->(:Person {id: $id}) } \
+         RETURN p.name ORDER BY p.name",
+    )
+    .with_parameter("id", 3)
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_exists_under_or_is_rejected() {
+    let result = query(
+        "MATCH (p:Person) \
+         WHERE p.age > 30 OR EXISTS { (p)-[:KNOWS]->(:Person) } \
+         RETURN p.name",
+    )
+    .execute(social_graph(), None)
+    .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,228 @@
+use arrow_array::RecordBatch;
+use futures::TryStreamExt;
+use lance::dataset::{Dataset, WriteMode};
+use lance_graph::graph_diff::{graph_diff, ElementDiff, GraphVersion};
+use lance_graph::{CypherQuery, DirNamespace};
+use serde_json::json;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+mod common;
+
+use common::{config, ints, knows_batch, namespace, person_batch, strings, write_dataset};
+
+/// Version 1: Alice(28), Bob(34) and Alice -> Bob
+/// Version 2 (nightly rebuild): Bob(35), Carol(29) and Bob -> Carol
+async fn rebuilt_graph(dir: &Path) -> DirNamespace {
+    let people = dir.join("Person.lance");
+    let knows = dir.join("KNOWS.lance");
+    write_dataset(
+        &people,
+        person_batch(vec![1, 2], vec!["Alice", "Bob"], vec![28, 34]),
+        WriteMode::Create,
+    )
+    .await;
+    write_dataset(&knows, knows_batch(vec![1], vec![2]), WriteMode::Create).await;
+    write_dataset(
+        &people,
+        person_batch(vec![2, 3], vec!["Bob", "Carol"], vec![35, 29]),
+        WriteMode::Overwrite,
+    )
+    .await;
+    write_dataset(&knows, knows_batch(vec![2], vec![3]), WriteMode::Overwrite).await;
+    namespace(dir)
+}
+
+/// (change, first column) of every changed row, sorted
+async fn changes(element: ElementDiff) -> Vec<(String, i64)> {
+    let batches: Vec<RecordBatch> = element.changes.try_collect().await.unwrap();
+    let mut rows = Vec::new();
+    for batch in batches {
+        rows.extend(strings(&batch, 0).into_iter().zip(ints(&batch, 1)));
+    }
+    rows.sort();
+    rows
+}
+
+fn change(kind: &str, first: i64) -> (String, i64) {
+    (kind.to_string(), first)
+}
+
+#[tokio::test]
+async fn test_diff_of_rebuilt_graph() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = rebuilt_graph(tmp_dir.path()).await;
+
+    let mut diff = graph_diff(
+        &config(),
+        &namespace,
+        GraphVersion::Version(1),
+        GraphVersion::Version(2),
+    )
+    .await
+    .unwrap();
+    assert_eq!(diff.len(), 2);
+    let knows = diff.pop().unwrap();
+    let people = diff.pop().unwrap();
+
+    assert_eq!((people.kind, people.name.as_str()), ("node", "Person"));
+    assert_eq!((people.from_version, people.to_version), (Some(1), Some(2)));
+    assert_eq!(people.key_columns, vec!["id"]);
+    assert_eq!(
+        changes(people).await,
+        vec![
+            change("added", 3),
+            change("changed", 2),
+            change("removed", 1)
+        ]
+    );
+
+    // Parallel edges are allowed, so relationships match on all columns
+    assert_eq!(knows.key_columns, vec!["src_id", "dst_id"]);
+    assert_eq!(
+        changes(knows).await,
+        vec![change("added", 2), change("removed", 1)]
+    );
+}
+
+#[tokio::test]
+async fn test_diff_of_appends_and_deletes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = rebuilt_graph(tmp_dir.path()).await;
+    let people = tmp_dir.path().join("Person.lance");
+
+    write_dataset(
+        &people,
+        person_batch(vec![4], vec!["Dave"], vec![50]),
+        WriteMode::Append,
+    )
+    .await;
+    let diff = graph_diff(
+        &config(),
+        &namespace,
+        GraphVersion::Version(2),
+        GraphVersion::Latest,
+    )
+    .await
+    .unwrap();
+    let mut diff = diff.into_iter();
+    assert_eq!(
+        changes(diff.next().unwrap()).await,
+        vec![change("added", 4)]
+    );
+    assert!(changes(diff.next().unwrap()).await.is_empty());
+
+    let mut dataset = Dataset::open(people.to_str().unwrap()).await.unwrap();
+    dataset.delete("id = 3").await.unwrap();
+    let diff = graph_diff(
+        &config(),
+        &namespace,
+        GraphVersion::Version(3),
+        GraphVersion::Latest,
+    )
+    .await
+    .unwrap();
+    let people_diff = diff.into_iter().next().unwrap();
+    assert_eq!(people_diff.to_version, Some(4));
+    assert_eq!(changes(people_diff).await, vec![change("removed", 3)]);
+
+    // Before the datasets existed every row is new
+    let diff = graph_diff(
+        &config(),
+        &namespace,
+        GraphVersion::AsOf(UNIX_EPOCH),
+        GraphVersion::Version(1),
+    )
+    .await
+    .unwrap();
+    let people_diff = diff.into_iter().next().unwrap();
+    assert_eq!(people_diff.from_version, None);
+    assert_eq!(
+        changes(people_diff).await,
+        vec![change("added", 1), change("added", 2)]
+    );
+
+    let err = graph_diff(
+        &config(),
+        &namespace,
+        GraphVersion::Version(1),
+        GraphVersion::Version(9),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(err.to_string().contains("has no version 9"));
+}
+
+#[tokio::test]
+async fn test_diff_procedure() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = rebuilt_graph(tmp_dir.path()).await;
+
+    let result = CypherQuery::new("CALL graph.diff($from, 2)")
+        .unwrap()
+        .with_config(config())
+        .with_parameter("from", 1)
+        .execute_with_namespace(namespace, None)
+        .await
+        .unwrap();
+    let column = |name: &str| strings(&result, name);
+    let json = |name: &str| {
+        column(name)
+            .iter()
+            .map(|value| serde_json::from_str::<serde_json::Value>(value).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let (kinds, names, changes, keys, properties) = (
+        column("kind"),
+        column("name"),
+        column("change"),
+        json("key"),
+        json("properties"),
+    );
+
+    let mut rows: Vec<_> = (0..result.num_rows())
+        .map(|i| {
+            (
+                kinds[i].clone(),
+                names[i].clone(),
+                changes[i].clone(),
+                keys[i].to_string(),
+            )
+        })
+        .collect();
+    rows.sort();
+    let row = |kind: &str, name: &str, change: &str, key: serde_json::Value| {
+        (
+            kind.to_string(),
+            name.to_string(),
+            change.to_string(),
+            key.to_string(),
+        )
+    };
+    assert_eq!(
+        rows,
+        vec![
+            row("node", "Person", "added", json!({"id": 3})),
+            row("node", "Person", "changed", json!({"id": 2})),
+            row("node", "Person", "removed", json!({"id": 1})),
+            row(
+                "relationship",
+                "KNOWS",
+                "added",
+                json!({"src_id": 2, "dst_id": 3})
+            ),
+            row(
+                "relationship",
+                "KNOWS",
+                "removed",
+                json!({"src_id": 1, "dst_id": 2})
+            ),
+        ]
+    );
+
+    let bob = (0..result.num_rows())
+        .find(|i| changes[*i] == "changed")
+        .unwrap();
+    assert_eq!(properties[bob], json!({"id": 2, "name": "Bob", "age": 35}));
+}
@@ -0,0 +1,166 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray, StructArray};
+use arrow_schema::{DataType, Field, Schema};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::{ints, optional_strings, query, string_lists};
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let person_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+    ]));
+    let person = RecordBatch::try_new(
+        person_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            Arc::new(Int64Array::from(vec![30, 25, 35])),
+        ],
+    )
+    .unwrap();
+    let knows_schema = Arc::new(Schema::new(vec![
+        Field::new("src_id", DataType::Int64, false),
+        Field::new("dst_id", DataType::Int64, false),
+        Field::new("since", DataType::Int64, false),
+    ]));
+    let knows = RecordBatch::try_new(
+        knows_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(Int64Array::from(vec![2, 3])),
+            Arc::new(Int64Array::from(vec![2020, 2021])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person), ("KNOWS".to_string(), knows)])
+}
+
+#[tokio::test]
+async fn test_labels_type_and_id() {
+    let result = query(
+        "MATCH (a:Person)-[r:KNOWS]->(b:Person) \
+         RETURN a.name, labels(a), type(r), id(b) ORDER BY a.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        optional_strings(&result, "a.name"),
+        vec![Some("Alice".to_string()), Some("Bob".to_string())]
+    );
+    // Unaliased calls keep their own column names
+    assert_eq!(
+        string_lists(&result, "labels(a)"),
+        vec![vec!["Person"], vec!["Person"]]
+    );
+    assert_eq!(
+        optional_strings(&result, "type(r)"),
+        vec![Some("KNOWS".to_string()), Some("KNOWS".to_string())]
+    );
+    assert_eq!(ints(&result, "id(b)"), vec![2, 3]);
+}
+
+#[tokio::test]
+async fn test_keys_and_properties() {
+    let result = query(
+        "MATCH (a:Person)-[r:KNOWS]->(b:Person) WHERE a.id = 1 \
+         RETURN keys(a) AS node_keys, properties(a) AS node, \
+         keys(r) AS rel_keys, properties(r) AS rel",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        string_lists(&result, "node_keys"),
+        vec![vec!["id", "name", "age"]]
+    );
+    // Endpoint columns are not properties of the relationship
+    assert_eq!(string_lists(&result, "rel_keys"), vec![vec!["since"]]);
+
+    let node = result.column_by_name("node").unwrap();
+    let node = node.as_any().downcast_ref::<StructArray>().unwrap();
+    let name = node.column_by_name("name").unwrap();
+    let name = name.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(name.value(0), "Alice");
+    let rel = result.column_by_name("rel").unwrap();
+    let rel = rel.as_any().downcast_ref::<StructArray>().unwrap();
+    assert_eq!(rel.num_columns(), 1);
+    let since = rel.column_by_name("since").unwrap();
+    let since = since.as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(since.value(0), 2020);
+}
+
+#[tokio::test]
+async fn test_introspection_in_predicates_and_optional_matches() {
+    let result = query(
+        "MATCH (a:Person)-[r:KNOWS]->(b:Person) \
+         WHERE id(b) = 3 AND type(r) = 'KNOWS' RETURN a.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        optional_strings(&result, "a.name"),
+        vec![Some("Bob".to_string())]
+    );
+
+    // Carol knows nobody, so her relationship has no type
+    let result = query(
+        "MATCH (p:Person) OPTIONAL MATCH (p)-[r:KNOWS]->(f:Person) \
+         RETURN p.name, type(r) AS kind ORDER BY p.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        optional_strings(&result, "kind"),
+        vec![Some("KNOWS".to_string()), Some("KNOWS".to_string()), None]
+    );
+}
+
+#[tokio::test]
+async fn test_introspection_errors() {
+    let error = query("MATCH (p:Person) RETURN type(p)")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("takes a relationship variable"),
+        "{}",
+        error
+    );
+
+    let error = query("MATCH (p:Person) RETURN labels(p.name)")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("single node"), "{}", error);
+
+    let error = query("MATCH (a:Person)-[r:KNOWS]->(b) RETURN id(r)")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("no id column"), "{}", error);
+}
+
+#[test]
+fn test_describe_introspection_calls() {
+    let schemas: HashMap<_, _> = datasets()
+        .into_iter()
+        .map(|(name, batch)| (name, batch.schema()))
+        .collect();
+    let described = query("MATCH (p:Person) RETURN labels(p), properties(p), id(p)")
+        .describe(&schemas)
+        .unwrap();
+    assert_eq!(described.field(0).name(), "labels(p)");
+    assert!(matches!(described.field(0).data_type(), DataType::List(_)));
+    let DataType::Struct(fields) = described.field(1).data_type() else {
+        panic!("properties() is not a struct: {:?}", described.field(1));
+    };
+    assert_eq!(fields.len(), 3);
+    assert_eq!(described.field(2).data_type(), &DataType::Int64);
+}
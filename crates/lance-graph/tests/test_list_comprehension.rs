@@ -0,0 +1,145 @@
+use arrow_array::{Array, Int64Array, ListArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::strings;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+        Field::new("city", DataType::Utf8, false),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(Int64Array::from(vec![25, 45, 70, 35])),
+            Arc::new(StringArray::from(vec!["Paris", "Berlin", "Paris", "Paris"])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+/// Sorted integer elements of every list in `column`
+fn integer_lists(batch: &RecordBatch, column: &str) -> Vec<Vec<i64>> {
+    let array = batch
+        .column_by_name(column)
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    (0..array.len())
+        .map(|i| {
+            let values = array.value(i);
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            let mut values: Vec<i64> = values.iter().flatten().collect();
+            values.sort();
+            values
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_comprehension_over_collected_aggregate() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.city AS city, [a IN collect(p.age) WHERE a > 30 | a * 2] AS doubled \
+         ORDER BY city",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "city"), vec!["Berlin", "Paris"]);
+    assert_eq!(
+        integer_lists(&result, "doubled"),
+        vec![vec![90], vec![70, 140]]
+    );
+}
+
+#[tokio::test]
+async fn test_comprehension_over_with_alias() {
+    // Without a projection the kept elements themselves are returned
+    let result = query(
+        "MATCH (p:Person) WITH p.city AS city, collect(p.age) AS ages \
+         RETURN city, [a IN ages WHERE a < 50] AS younger, [a IN ages] AS everyone \
+         ORDER BY city",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        integer_lists(&result, "younger"),
+        vec![vec![45], vec![25, 35]]
+    );
+    assert_eq!(
+        integer_lists(&result, "everyone"),
+        vec![vec![45], vec![25, 35, 70]]
+    );
+}
+
+#[tokio::test]
+async fn test_comprehension_reads_row_columns() {
+    // The predicate compares each element with a property of the same row
+    let result = query(
+        "MATCH (p:Person) WITH p.city AS city, collect(p.age) AS ages, min(p.age) AS youngest \
+         RETURN city, [a IN ages WHERE a > youngest | a - youngest] AS gaps \
+         ORDER BY city",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(integer_lists(&result, "gaps"), vec![vec![], vec![10, 45]]);
+}
+
+#[tokio::test]
+async fn test_unwind_comprehension_with_parameter() {
+    let result = query(
+        "MATCH (p:Person) WITH collect(p.age) AS ages \
+         UNWIND [a IN ages WHERE a >= $min | a + 1] AS age \
+         RETURN age ORDER BY age",
+    )
+    .with_parameter("min", 40)
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    let ages = result
+        .column_by_name("age")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(ages.values().to_vec(), vec![46, 71]);
+}
+
+#[tokio::test]
+async fn test_aggregate_inside_comprehension_is_rejected() {
+    let result = query(
+        "MATCH (p:Person) WITH collect(p.age) AS ages \
+         RETURN [a IN ages | count(a)] AS counts",
+    )
+    .execute(datasets(), None)
+    .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,109 @@
+use arrow_array::{Int64Array, ListArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::optional_ints;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("city", DataType::Utf8, false),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(StringArray::from(vec!["Paris", "Berlin", "Paris", "Paris"])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+async fn execute(cypher: &str) -> RecordBatch {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap()
+}
+
+fn integer_list(batch: &RecordBatch, column: &str) -> Vec<i64> {
+    let array = batch
+        .column_by_name(column)
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    let values = array.value(0);
+    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+    values.iter().flatten().collect()
+}
+
+#[tokio::test]
+async fn test_unwind_range() {
+    let result = execute("UNWIND range(1, 100) AS x RETURN sum(x) AS total").await;
+    assert_eq!(optional_ints(&result, "total"), vec![Some(5050)]);
+
+    // Ranges include their end and may step down
+    let result = execute("UNWIND range(10, 0, -5) AS x RETURN x").await;
+    assert_eq!(
+        optional_ints(&result, "x"),
+        vec![Some(10), Some(5), Some(0)]
+    );
+}
+
+#[tokio::test]
+async fn test_size_of_collected_values() {
+    let result = execute(
+        "MATCH (p:Person) RETURN p.city AS city, size(collect(p.name)) AS people ORDER BY city",
+    )
+    .await;
+    assert_eq!(optional_ints(&result, "people"), vec![Some(1), Some(3)]);
+}
+
+#[tokio::test]
+async fn test_head_last_tail_and_reverse() {
+    let result = execute(
+        "MATCH (p:Person) WHERE p.id = 1 \
+         RETURN head(range(p.id, 5)) AS first, last(range(p.id, 5)) AS final, \
+         tail(range(p.id, 5)) AS rest, reverse(range(p.id, 3)) AS reversed, \
+         size(range(p.id, 5)) AS n, head(range(5, p.id)) AS empty",
+    )
+    .await;
+    assert_eq!(optional_ints(&result, "first"), vec![Some(1)]);
+    assert_eq!(optional_ints(&result, "final"), vec![Some(5)]);
+    assert_eq!(integer_list(&result, "rest"), vec![2, 3, 4, 5]);
+    assert_eq!(integer_list(&result, "reversed"), vec![3, 2, 1]);
+    assert_eq!(optional_ints(&result, "n"), vec![Some(5)]);
+    // Empty lists have no head
+    assert_eq!(optional_ints(&result, "empty"), vec![None]);
+}
+
+#[tokio::test]
+async fn test_size_and_reverse_of_strings() {
+    let result = execute(
+        "MATCH (p:Person) WHERE p.id = 3 RETURN size(p.name) AS n, reverse(p.name) AS backwards",
+    )
+    .await;
+    assert_eq!(optional_ints(&result, "n"), vec![Some(5)]);
+    let backwards = result
+        .column_by_name("backwards")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(backwards.value(0), "loraC");
+}
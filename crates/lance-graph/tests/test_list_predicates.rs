@@ -0,0 +1,123 @@
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::strings;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let mut tags = ListBuilder::new(StringBuilder::new());
+    for person_tags in [
+        Some(vec!["rust", "go"]),
+        Some(vec!["go"]),
+        Some(vec![]),
+        None,
+    ] {
+        match person_tags {
+            Some(values) => {
+                for tag in values {
+                    tags.values().append_value(tag);
+                }
+                tags.append(true);
+            }
+            None => tags.append(false),
+        }
+    }
+    let tags = tags.finish();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("tags", tags.data_type().clone(), true),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(tags),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap()
+}
+
+async fn names(cypher: &str) -> Vec<String> {
+    let result = CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config())
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    strings(&result, "n.name")
+}
+
+#[tokio::test]
+async fn test_any_with_parameter() {
+    let result = CypherQuery::new(
+        "MATCH (n:Person) WHERE any(x IN n.tags WHERE x = $tag) RETURN n.name ORDER BY n.name",
+    )
+    .unwrap()
+    .with_config(config())
+    .with_parameter("tag", "go")
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "n.name"), vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_all_and_none_hold_for_empty_lists() {
+    // Dave's null list makes both predicates null
+    assert_eq!(
+        names(
+            "MATCH (n:Person) WHERE all(x IN n.tags WHERE x = 'go') RETURN n.name ORDER BY n.name"
+        )
+        .await,
+        vec!["Bob", "Carol"]
+    );
+    assert_eq!(
+        names(
+            "MATCH (n:Person) WHERE none(x IN n.tags WHERE x = 'rust') RETURN n.name ORDER BY n.name"
+        )
+        .await,
+        vec!["Bob", "Carol"]
+    );
+    assert_eq!(
+        names(
+            "MATCH (n:Person) WHERE NOT any(t IN n.tags WHERE t STARTS WITH 'r') \
+             RETURN n.name ORDER BY n.name"
+        )
+        .await,
+        vec!["Bob", "Carol"]
+    );
+}
+
+#[tokio::test]
+async fn test_single_reads_the_row_of_its_list() {
+    // Only Carol's range(1, 3) has exactly one element above 2
+    assert_eq!(
+        names("MATCH (n:Person) WHERE single(x IN range(1, n.id) WHERE x > 2) RETURN n.name").await,
+        vec!["Carol"]
+    );
+    // Elements compare with other columns of their row
+    assert_eq!(
+        names(
+            "MATCH (n:Person) WHERE any(x IN range(1, 3) WHERE x * 2 = n.id) \
+             RETURN n.name ORDER BY n.name"
+        )
+        .await,
+        vec!["Bob", "Dave"]
+    );
+}
@@ -0,0 +1,159 @@
+use arrow_array::{Array, ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use lance_graph::lookup::{LookupJoin, LookupSource};
+use lance_graph::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+
+use common::query;
+
+/// Scores and segments held in memory, counting the keys it is asked for
+#[derive(Debug)]
+struct FeatureStore {
+    features: HashMap<i64, (f64, &'static str)>,
+    keys_requested: AtomicUsize,
+}
+
+impl FeatureStore {
+    fn new() -> Self {
+        Self {
+            features: HashMap::from([(1, (0.9, "gold")), (3, (0.4, "silver"))]),
+            keys_requested: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LookupSource for FeatureStore {
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("score", DataType::Float64, true),
+            Field::new("segment", DataType::Utf8, true),
+        ]))
+    }
+
+    async fn get(&self, keys: ArrayRef) -> Result<RecordBatch> {
+        let keys = keys.as_any().downcast_ref::<Int64Array>().unwrap();
+        self.keys_requested.fetch_add(keys.len(), Ordering::SeqCst);
+        let found: Vec<_> = keys.values().iter().map(|k| self.features.get(k)).collect();
+        let scores: Float64Array = found.iter().map(|f| f.map(|(score, _)| *score)).collect();
+        let segments: StringArray = found.iter().map(|f| f.map(|(_, s)| *s)).collect();
+        Ok(RecordBatch::try_new(
+            self.schema(),
+            vec![Arc::new(scores), Arc::new(segments)],
+        )?)
+    }
+}
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let person_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let person = RecordBatch::try_new(
+        person_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+        ],
+    )
+    .unwrap();
+    let knows_schema = Arc::new(Schema::new(vec![
+        Field::new("src_id", DataType::Int64, false),
+        Field::new("dst_id", DataType::Int64, false),
+    ]));
+    let knows = RecordBatch::try_new(
+        knows_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(Int64Array::from(vec![3, 3, 1])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person), ("KNOWS".to_string(), knows)])
+}
+
+#[tokio::test]
+async fn test_lookup_join_appends_external_values() {
+    let store = Arc::new(FeatureStore::new());
+    let result = query("MATCH (p:Person) RETURN p.id, p.name ORDER BY p.id")
+        .with_lookup_join(LookupJoin::new("p.id", store.clone()).with_prefix("p."))
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = result
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().as_str())
+        .collect();
+    assert_eq!(names, vec!["p.id", "p.name", "p.score", "p.segment"]);
+    let scores = result
+        .column_by_name("p.score")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(
+        scores.iter().collect::<Vec<_>>(),
+        vec![Some(0.9), None, Some(0.4)]
+    );
+    let segments = result
+        .column_by_name("p.segment")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert!(segments.is_null(1));
+    assert_eq!(segments.value(2), "silver");
+}
+
+#[tokio::test]
+async fn test_lookup_join_fetches_each_key_once() {
+    // Carol is the target of two edges, but looked up once
+    let store = Arc::new(FeatureStore::new());
+    let result = query("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.id ORDER BY a.name")
+        .with_lookup_join(LookupJoin::new("b.id", store.clone()))
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(result.num_rows(), 3);
+    let scores = result
+        .column_by_name("score")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(
+        scores.iter().collect::<Vec<_>>(),
+        vec![Some(0.4), Some(0.4), Some(0.9)]
+    );
+    assert!(store.keys_requested.load(Ordering::SeqCst) <= 2);
+
+    let plan = query("MATCH (p:Person) RETURN p.id")
+        .with_lookup_join(LookupJoin::new("p.id", store))
+        .explain(datasets())
+        .await
+        .unwrap();
+    assert!(plan.contains("LookupJoinExec: keys=[p.id]"), "{}", plan);
+}
+
+#[tokio::test]
+async fn test_lookup_join_rejects_unknown_key_columns() {
+    let error = query("MATCH (p:Person) RETURN p.name")
+        .with_lookup_join(LookupJoin::new("p.id", Arc::new(FeatureStore::new())))
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Lookup key column p.id is not a result column"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,141 @@
+use arrow_array::{Array, Int64Array, ListArray, RecordBatch, StringArray, StructArray};
+use arrow_schema::{DataType, Field, Schema};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::query;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let person_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+    ]));
+    let person = RecordBatch::try_new(
+        person_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            Arc::new(Int64Array::from(vec![30, 25, 40])),
+        ],
+    )
+    .unwrap();
+
+    let knows_schema = Arc::new(Schema::new(vec![
+        Field::new("src_id", DataType::Int64, false),
+        Field::new("dst_id", DataType::Int64, false),
+    ]));
+    let knows = RecordBatch::try_new(
+        knows_schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 1, 2])),
+            Arc::new(Int64Array::from(vec![2, 3, 3])),
+        ],
+    )
+    .unwrap();
+
+    HashMap::from([("Person".to_string(), person), ("KNOWS".to_string(), knows)])
+}
+
+fn map_column<'a>(batch: &'a RecordBatch, column: &str) -> &'a StructArray {
+    batch
+        .column_by_name(column)
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap()
+}
+
+fn strings(array: &dyn Array) -> Vec<String> {
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    (0..array.len())
+        .map(|i| array.value(i).to_string())
+        .collect()
+}
+
+fn integers(array: &dyn Array) -> Vec<i64> {
+    let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+    array.values().to_vec()
+}
+
+#[tokio::test]
+async fn test_map_projection_of_properties_and_expressions() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p {.name, .age, next: p.age + 1} AS person ORDER BY p.age",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    let person = map_column(&result, "person");
+    let keys: Vec<&str> = person.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(keys, vec!["name", "age", "next"]);
+    assert_eq!(
+        strings(person.column_by_name("name").unwrap().as_ref()),
+        vec!["Bob", "Alice", "Carol"]
+    );
+    assert_eq!(
+        integers(person.column_by_name("next").unwrap().as_ref()),
+        vec![26, 31, 41]
+    );
+}
+
+#[tokio::test]
+async fn test_map_projection_with_projected_variables() {
+    // `friends` is an alias from WITH, included under its own name
+    let result = query(
+        "MATCH (p:Person)-[:KNOWS]->(f:Person) \
+         WITH p.name AS name, count(f) AS friends \
+         RETURN name {friends, label: name} AS summary ORDER BY name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    let summary = map_column(&result, "summary");
+    assert_eq!(
+        integers(summary.column_by_name("friends").unwrap().as_ref()),
+        vec![2, 1]
+    );
+    assert_eq!(
+        strings(summary.column_by_name("label").unwrap().as_ref()),
+        vec!["Alice", "Bob"]
+    );
+}
+
+#[tokio::test]
+async fn test_map_projection_with_pattern_comprehension() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p {.name, knows: [(p)-[:KNOWS]->(f:Person) | f.name]} AS person \
+         ORDER BY p.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+
+    let person = map_column(&result, "person");
+    assert_eq!(
+        strings(person.column_by_name("name").unwrap().as_ref()),
+        vec!["Alice", "Bob", "Carol"]
+    );
+    let knows = person
+        .column_by_name("knows")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    let sizes: Vec<usize> = (0..knows.len()).map(|i| knows.value(i).len()).collect();
+    assert_eq!(sizes, vec![2, 1, 0]);
+}
+
+#[tokio::test]
+async fn test_map_projection_rejects_aggregates() {
+    let result = query("MATCH (p:Person) RETURN p {.name, total: count(*)} AS person")
+        .execute(datasets(), None)
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,209 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance::dataset::WriteMode;
+use lance_graph::config::{GraphConfig, NodeMapping, RelationshipMapping};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+use common::{ints, knows_batch, namespace, person_batch, query, strings, write_dataset};
+
+/// Namespace with Alice(28), Bob(34) and Alice -> Bob
+async fn graph(dir: &Path) -> DirNamespace {
+    write_dataset(
+        &dir.join("Person.lance"),
+        person_batch(vec![1, 2], vec!["Alice", "Bob"], vec![28, 34]),
+        WriteMode::Create,
+    )
+    .await;
+    write_dataset(
+        &dir.join("KNOWS.lance"),
+        knows_batch(vec![1], vec![2]),
+        WriteMode::Create,
+    )
+    .await;
+    namespace(dir)
+}
+
+/// (nodes_created, relationships_created) of a MERGE result
+fn created(batch: &RecordBatch) -> (i64, i64) {
+    (
+        ints(batch, "nodes_created")[0],
+        ints(batch, "relationships_created")[0],
+    )
+}
+
+async fn friendships(dir: &Path) -> Vec<(String, String)> {
+    let result = query(
+        "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name ORDER BY a.name, b.name",
+    )
+    .execute_with_namespace(namespace(dir), None)
+    .await
+    .unwrap();
+    strings(&result, 0)
+        .into_iter()
+        .zip(strings(&result, 1))
+        .collect()
+}
+
+fn pair(a: &str, b: &str) -> (String, String) {
+    (a.to_string(), b.to_string())
+}
+
+#[tokio::test]
+async fn test_merge_is_idempotent() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    let merge = || {
+        query(
+            "MERGE (n:Person {id: $id, name: 'Carol', age: 29})\
+             -[:KNOWS]->(m:Person {id: 1, name: 'Alice', age: 28})",
+        )
+        .with_parameter("id", 3)
+    };
+    let result = merge()
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (1, 1), "Alice already exists");
+
+    let result = merge()
+        .execute_with_namespace(namespace, None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (0, 0));
+    assert_eq!(
+        friendships(tmp_dir.path()).await,
+        vec![pair("Alice", "Bob"), pair("Carol", "Alice")]
+    );
+}
+
+#[tokio::test]
+async fn test_match_merge_relationships_between_matched_nodes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    // Alice -> Bob exists; Bob -> Alice does not
+    let result = query("MATCH (a:Person), (b:Person) WHERE a.id <> b.id MERGE (a)-[:KNOWS]->(b)")
+        .execute_with_namespace(namespace, None)
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (0, 1));
+    assert_eq!(
+        friendships(tmp_dir.path()).await,
+        vec![pair("Alice", "Bob"), pair("Bob", "Alice")]
+    );
+}
+
+#[tokio::test]
+async fn test_merge_rejects_conflicting_properties() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    let err = query("MERGE (n:Person {id: 2, name: 'Robert', age: 34})")
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("different 'name'"));
+}
+
+#[tokio::test]
+async fn test_concurrent_merges_create_one_node() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let namespace = graph(tmp_dir.path()).await;
+
+    let merge = || {
+        query("MERGE (:Person {id: 7, name: 'Grace', age: 40})")
+            .execute_with_namespace(namespace.clone(), None)
+    };
+    let (first, second) = tokio::join!(merge(), merge());
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(created(&first).0 + created(&second).0, 1);
+
+    let result = query("MATCH (p:Person) WHERE p.id = 7 RETURN p.name")
+        .execute_with_namespace(namespace, None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&result, 0), vec!["Grace"]);
+}
+
+#[tokio::test]
+async fn test_merge_ignores_soft_deleted_nodes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+        Field::new("deleted_at", DataType::Int64, true),
+    ]));
+    let people = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob"])),
+            Arc::new(Int64Array::from(vec![28, 34])),
+            Arc::new(Int64Array::from(vec![None, Some(1_700_000_000_000)])),
+        ],
+    )
+    .unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        people,
+        WriteMode::Create,
+    )
+    .await;
+    let config = GraphConfig::builder()
+        .with_node_mapping(NodeMapping::new("Person", "id").with_soft_delete_column("deleted_at"))
+        .build()
+        .unwrap();
+    let run = |cypher: &str| {
+        CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(config.clone())
+            .execute_with_namespace(namespace(tmp_dir.path()), None)
+    };
+
+    // Bob is deleted, so merging him creates him anew
+    let result = run("MERGE (:Person {id: 2, name: 'Bob', age: 35})")
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (1, 0));
+    let result = run("MERGE (:Person {id: 1, name: 'Alice', age: 28})")
+        .await
+        .unwrap();
+    assert_eq!(created(&result), (0, 0));
+
+    let result = run("MATCH (p:Person) RETURN p.name ORDER BY p.name")
+        .await
+        .unwrap();
+    assert_eq!(strings(&result, 0), vec!["Alice", "Bob"]);
+}
+
+#[tokio::test]
+async fn test_merge_matches_relationships_by_endpoints_without_parallel_edges() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .with_relationship_mapping(
+            RelationshipMapping::new("KNOWS", "src_id", "dst_id").with_parallel_edges(false),
+        )
+        .build()
+        .unwrap();
+    let merge = |since: i64| {
+        let query =
+            CypherQuery::new("MERGE (:Person {id: 1})-[:KNOWS {since: $since}]->(:Person {id: 2})")
+                .unwrap()
+                .with_config(config.clone())
+                .with_parameter("since", since);
+        let namespace = namespace(tmp_dir.path());
+        async move { query.execute_with_namespace(namespace, None).await }
+    };
+
+    assert_eq!(created(&merge(2020).await.unwrap()), (2, 1));
+    assert_eq!(created(&merge(2020).await.unwrap()), (0, 0));
+    let err = merge(2021).await.unwrap_err();
+    assert!(err.to_string().contains("different 'since'"));
+}
@@ -0,0 +1,137 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::{optional_strings, strings};
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("nickname", DataType::Utf8, true),
+        Field::new("city", DataType::Utf8, true),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(StringArray::from(vec![
+                Some("Ali"),
+                None,
+                Some("Caz"),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("Paris"),
+                Some("Berlin"),
+                None,
+                Some("Paris"),
+            ])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+#[tokio::test]
+async fn test_coalesce_and_null_if_in_projection() {
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.name, coalesce(p.nickname, p.city, 'nobody') AS called, \
+         nullIf(p.city, 'Paris') AS elsewhere ORDER BY p.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        optional_strings(&result, "called"),
+        vec![
+            Some("Ali".to_string()),
+            Some("Berlin".to_string()),
+            Some("Caz".to_string()),
+            Some("Paris".to_string()),
+        ]
+    );
+    // A NULL first argument stays NULL
+    assert_eq!(
+        optional_strings(&result, "elsewhere"),
+        vec![None, Some("Berlin".to_string()), None, None]
+    );
+}
+
+#[tokio::test]
+async fn test_coalesce_and_null_if_in_predicates() {
+    let result =
+        query("MATCH (p:Person) WHERE coalesce(p.city, 'Unknown') = 'Unknown' RETURN p.name")
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+    assert_eq!(strings(&result, "p.name"), vec!["Carol"]);
+
+    let result = query(
+        "MATCH (p:Person) WHERE nullIf(p.city, 'Paris') IS NULL RETURN p.name ORDER BY p.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "p.name"), vec!["Alice", "Carol", "Dave"]);
+}
+
+#[tokio::test]
+async fn test_coalesce_in_order_by() {
+    // Sorts by Ali, Bob, Caz, Dave
+    let result = query("MATCH (p:Person) RETURN p.name ORDER BY coalesce(p.nickname, p.name) DESC")
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        strings(&result, "p.name"),
+        vec!["Dave", "Carol", "Bob", "Alice"]
+    );
+}
+
+#[tokio::test]
+async fn test_null_arguments() {
+    let result = query(
+        "MATCH (p:Person) WHERE p.id = 1 \
+         RETURN coalesce(null, p.name) AS first, nullIf(p.name, null) AS kept, \
+         nullIf(p.name, 'Alice') AS dropped",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(
+        optional_strings(&result, "first"),
+        vec![Some("Alice".to_string())]
+    );
+    // Nothing equals NULL, so the value is kept
+    assert_eq!(
+        optional_strings(&result, "kept"),
+        vec![Some("Alice".to_string())]
+    );
+    assert!(result.column_by_name("dropped").unwrap().is_null(0));
+
+    let error = query("MATCH (p:Person) RETURN coalesce()")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("at least 1 argument"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,158 @@
+use arrow::compute::cast;
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder};
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use lance_graph::config::GraphConfig;
+use lance_graph::output::{OutputCoercion, OutputValue, TemporalOutput, Unrepresentable};
+use lance_graph::CypherQuery;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let at = StringArray::from(vec!["2024-01-15T09:30:00", "2024-01-20T17:45:30.250"]);
+    let mut embedding = FixedSizeListBuilder::new(Float32Builder::new(), 2);
+    for values in [[0.1, 0.5], [1.0, -2.5]] {
+        embedding.values().append_slice(&values);
+        embedding.append(true);
+    }
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("views", DataType::Int64, false),
+        Field::new(
+            "at",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+        ),
+        Field::new(
+            "embedding",
+            DataType::new_fixed_size_list(DataType::Float32, 2, true),
+            false,
+        ),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(Int64Array::from(vec![42, 9_007_199_254_740_993])),
+            cast(&at, &DataType::Timestamp(TimeUnit::Microsecond, None)).unwrap(),
+            Arc::new(embedding.finish()),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Post".to_string(), batch)])
+}
+
+async fn execute(cypher: &str) -> RecordBatch {
+    let config = GraphConfig::builder()
+        .with_node_label("Post", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_json_output() {
+    let result = execute(
+        "MATCH (p:Post) RETURN p.views, p.at, p.embedding, \
+         duration.between(p.at, datetime('2024-01-16T00:00:00Z')) AS age ORDER BY p.id",
+    )
+    .await;
+    let rows = OutputCoercion::json().json_rows(&result).unwrap();
+    assert_eq!(
+        serde_json::Value::from(rows),
+        json!([
+            {
+                "p.views": 42,
+                "p.at": "2024-01-15T09:30:00",
+                "p.embedding": [0.1, 0.5],
+                "age": "PT14H30M",
+            },
+            {
+                // Beyond 2^53, so a string rather than a rounded number
+                "p.views": "9007199254740993",
+                "p.at": "2024-01-20T17:45:30.250",
+                "p.embedding": [1.0, -2.5],
+                "age": "PT-113H-45M-30.25S",
+            },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_bolt_output_keeps_native_values() {
+    let result = execute("MATCH (p:Post) RETURN p.views, p.at ORDER BY p.id").await;
+    let rows = OutputCoercion::bolt().rows(&result).unwrap();
+    assert_eq!(
+        rows[1],
+        vec![
+            OutputValue::Integer(9_007_199_254_740_993),
+            OutputValue::LocalDateTime {
+                seconds: 1_705_772_730,
+                nanoseconds: 250_000_000,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_unrepresentable_numbers_follow_the_policy() {
+    let batch = RecordBatch::try_from_iter(vec![(
+        "n",
+        Arc::new(UInt64Array::from(vec![7, u64::MAX])) as ArrayRef,
+    )])
+    .unwrap();
+
+    let rows = OutputCoercion::bolt().rows(&batch).unwrap();
+    assert_eq!(rows[0], vec![OutputValue::Integer(7)]);
+    assert_eq!(
+        rows[1],
+        vec![OutputValue::String("18446744073709551615".to_string())]
+    );
+
+    let lossy = OutputCoercion::bolt().with_unrepresentable(Unrepresentable::Float);
+    assert_eq!(
+        lossy.rows(&batch).unwrap()[1],
+        vec![OutputValue::Float(u64::MAX as f64)]
+    );
+
+    let strict = OutputCoercion::bolt().with_unrepresentable(Unrepresentable::Error);
+    let error = strict.rows(&batch).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("18446744073709551615 has no exact representation"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_zoned_timestamps_carry_their_offset() {
+    let at = StringArray::from(vec!["2024-06-01T12:00:00Z"]);
+    let zoned = DataType::Timestamp(TimeUnit::Second, Some("+02:00".into()));
+    let batch = RecordBatch::try_from_iter(vec![("at", cast(&at, &zoned).unwrap())]).unwrap();
+
+    let native = OutputCoercion::new().rows(&batch).unwrap();
+    assert_eq!(
+        native[0][0],
+        OutputValue::DateTime {
+            seconds: 1_717_243_200,
+            nanoseconds: 0,
+            offset_seconds: 7_200,
+        }
+    );
+    let iso = OutputCoercion::new()
+        .with_temporal(TemporalOutput::IsoString)
+        .rows(&batch)
+        .unwrap();
+    assert_eq!(
+        iso[0][0],
+        OutputValue::String("2024-06-01T14:00:00+02:00".to_string())
+    );
+}
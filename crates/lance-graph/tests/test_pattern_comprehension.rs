@@ -0,0 +1,88 @@
+use arrow_array::RecordBatch;
+
+mod common;
+
+use common::{query, social_graph, string_lists, strings};
+
+/// Sorted string elements of every list in `column`
+fn sorted_string_lists(batch: &RecordBatch, column: &str) -> Vec<Vec<String>> {
+    assert_eq!(batch.column_by_name(column).unwrap().null_count(), 0);
+    string_lists(batch, column)
+        .into_iter()
+        .map(|mut values| {
+            values.sort();
+            values
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_comprehension_collects_per_outer_row() {
+    // Dave knows nobody and keeps his row with an empty list
+    let result = query(
+        "MATCH (p:Person) \
+         RETURN p.name AS person, [(p)-[:KNOWS]->(f:Person) | f.name] AS friends \
+         ORDER BY person",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        strings(&result, "person"),
+        vec!["Alice", "Bob", "Carol", "Dave"]
+    );
+    assert_eq!(
+        sorted_string_lists(&result, "friends"),
+        vec![
+            vec!["Bob".to_string(), "Carol".to_string(), "Dave".to_string()],
+            vec!["Carol".to_string()],
+            vec!["Alice".to_string()],
+            vec![],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_comprehension_predicate_and_several_comprehensions() {
+    let result = query(
+        "MATCH (p:Person) WHERE p.age >= 30 \
+         RETURN p.name AS person, \
+                [(p)-[:KNOWS]->(f:Person) WHERE f.age > 30 | f.name] AS older, \
+                [(p)<-[:KNOWS]-(g:Person) | g.name] AS known_by \
+         ORDER BY person",
+    )
+    .execute(social_graph(), None)
+    .await
+    .unwrap();
+
+    assert_eq!(strings(&result, "person"), vec!["Alice", "Carol", "Dave"]);
+    assert_eq!(
+        sorted_string_lists(&result, "older"),
+        vec![
+            vec!["Carol".to_string(), "Dave".to_string()],
+            vec![],
+            vec![]
+        ]
+    );
+    assert_eq!(
+        sorted_string_lists(&result, "known_by"),
+        vec![
+            vec!["Carol".to_string()],
+            vec!["Alice".to_string(), "Bob".to_string()],
+            vec!["Alice".to_string()],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_comprehension_after_with_is_rejected() {
+    let result = query(
+        "MATCH (p:Person) WITH p \
+         RETURN [(p)-[:KNOWS]->(f:Person) | f.name] AS friends",
+    )
+    .execute(social_graph(), None)
+    .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,86 @@
+use arrow_array::RecordBatch;
+use lance_graph::plan_snapshot::{PlanCorpus, PlanSnapshotter};
+use std::collections::HashMap;
+
+mod common;
+
+use common::{config, knows_batch, person_batch};
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    HashMap::from([
+        ("Person".to_string(), person_batch(vec![], vec![], vec![])),
+        ("KNOWS".to_string(), knows_batch(vec![], vec![])),
+    ])
+}
+
+fn corpus() -> PlanCorpus {
+    PlanCorpus::new()
+        .with_query("adults", "MATCH (p:Person) WHERE p.age > 30 RETURN p.name")
+        .with_query(
+            "friends",
+            "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name",
+        )
+}
+
+#[tokio::test]
+async fn test_missing_snapshots_are_mismatches() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = PlanSnapshotter::new(config(), datasets(), dir.path())
+        .with_update(false)
+        .check(&corpus())
+        .await
+        .unwrap();
+
+    assert_eq!(report.mismatches.len(), 2);
+    assert!(report.mismatches.iter().all(|m| m.expected.is_none()));
+    assert!(!report.is_unchanged());
+}
+
+#[tokio::test]
+async fn test_recorded_snapshots_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let written = PlanSnapshotter::new(config(), datasets(), dir.path())
+        .with_update(true)
+        .check(&corpus())
+        .await
+        .unwrap();
+    assert_eq!(written.written, vec!["adults", "friends"]);
+
+    let snapshot = std::fs::read_to_string(dir.path().join("adults.plan")).unwrap();
+    assert!(snapshot.contains("TableScan"), "{}", snapshot);
+
+    let report = PlanSnapshotter::new(config(), datasets(), dir.path())
+        .with_update(false)
+        .check(&corpus())
+        .await
+        .unwrap();
+    assert_eq!(report.unchanged, vec!["adults", "friends"]);
+    report.assert_unchanged();
+}
+
+#[tokio::test]
+async fn test_changed_plan_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    PlanSnapshotter::new(config(), datasets(), dir.path())
+        .with_update(true)
+        .check(&corpus())
+        .await
+        .unwrap();
+    std::fs::write(dir.path().join("adults.plan"), "TableScan: person\n").unwrap();
+
+    let report = PlanSnapshotter::new(config(), datasets(), dir.path())
+        .with_update(false)
+        .check(&corpus())
+        .await
+        .unwrap();
+    assert_eq!(report.unchanged, vec!["friends"]);
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].name, "adults");
+    assert_eq!(
+        report.mismatches[0].expected.as_deref(),
+        Some("TableScan: person")
+    );
+
+    let panic = std::panic::catch_unwind(|| report.assert_unchanged());
+    assert!(panic.is_err());
+}
@@ -0,0 +1,237 @@
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use lance_graph::config::GraphConfig;
+use lance_graph::lookup::{LookupJoin, LookupSource};
+use lance_graph::query_monitor::{ActiveQuery, QueryMonitor, QueryPhase};
+use lance_graph::{CypherQuery, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+use common::strings;
+
+/// A store that never answers, keeping queries that look values up running
+#[derive(Debug)]
+struct Unresponsive;
+
+/// A store that never answers and counts the lookups still waiting for it
+#[derive(Debug, Default)]
+struct Waiting {
+    pending: Arc<AtomicUsize>,
+}
+
+/// Counts a waiting lookup until dropped
+struct PendingLookup(Arc<AtomicUsize>);
+
+impl Drop for PendingLookup {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait::async_trait]
+impl LookupSource for Waiting {
+    fn schema(&self) -> SchemaRef {
+        Unresponsive.schema()
+    }
+
+    async fn get(&self, _keys: ArrayRef) -> Result<RecordBatch> {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let _pending = PendingLookup(self.pending.clone());
+        futures::future::pending().await
+    }
+}
+
+/// A store that blocks its thread before answering, ignoring cancellation
+#[derive(Debug)]
+struct Blocking;
+
+#[async_trait::async_trait]
+impl LookupSource for Blocking {
+    fn schema(&self) -> SchemaRef {
+        Unresponsive.schema()
+    }
+
+    async fn get(&self, _keys: ArrayRef) -> Result<RecordBatch> {
+        std::thread::sleep(Duration::from_millis(500));
+        futures::future::pending().await
+    }
+}
+
+#[async_trait::async_trait]
+impl LookupSource for Unresponsive {
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new(
+            "score",
+            DataType::Float64,
+            true,
+        )]))
+    }
+
+    async fn get(&self, _keys: ArrayRef) -> Result<RecordBatch> {
+        futures::future::pending().await
+    }
+}
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn query(cypher: &str, monitor: &Arc<QueryMonitor>) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .with_query_monitor(monitor.clone())
+}
+
+/// Wait until `monitor` lists an executing query
+async fn executing(monitor: &QueryMonitor) -> Vec<ActiveQuery> {
+    loop {
+        let active = monitor.active_queries();
+        if active.iter().any(|q| q.phase == QueryPhase::Executing) {
+            return active;
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_running_queries_are_listed_and_killed() {
+    let monitor = Arc::new(QueryMonitor::new());
+    let cypher = "MATCH (p:Person) RETURN p.id";
+    let stalled =
+        query(cypher, &monitor).with_lookup_join(LookupJoin::new("p.id", Arc::new(Unresponsive)));
+    let running = tokio::spawn(async move { stalled.execute(datasets(), None).await });
+
+    let active = executing(&monitor).await;
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].query, cypher);
+    let id = active[0].id;
+
+    let listed = query("CALL dbms.listQueries()", &monitor)
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&listed, "query_id"), vec![id.to_string()]);
+    assert_eq!(strings(&listed, "query"), vec![cypher]);
+    assert_eq!(strings(&listed, "phase"), vec!["executing"]);
+
+    let killed = query(&format!("CALL dbms.killQuery('{}')", id), &monitor)
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    let flags = killed
+        .column_by_name("killed")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .unwrap();
+    assert!(flags.value(0));
+
+    let error = running.await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("was killed"), "{}", error);
+    assert!(monitor.active_queries().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kill_returns_once_the_query_is_torn_down() {
+    let monitor = Arc::new(QueryMonitor::new());
+    let source = Arc::new(Waiting::default());
+    let pending = source.pending.clone();
+    let stalled = query("MATCH (p:Person) RETURN p.id", &monitor)
+        .with_lookup_join(LookupJoin::new("p.id", source));
+    let running = tokio::spawn(async move { stalled.execute(datasets(), None).await });
+
+    let id = executing(&monitor).await[0].id;
+    while pending.load(Ordering::SeqCst) == 0 {
+        tokio::task::yield_now().await;
+    }
+    assert!(monitor.kill(id).await.unwrap());
+    // The lookup in flight was dropped with the operator running it
+    assert_eq!(pending.load(Ordering::SeqCst), 0);
+    assert!(monitor.active_queries().is_empty());
+    assert!(!monitor.kill(id).await.unwrap());
+
+    let error = running.await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("was killed"), "{}", error);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kill_times_out_when_the_query_is_not_torn_down() {
+    let monitor = Arc::new(QueryMonitor::new().with_kill_timeout(Duration::from_millis(50)));
+    let stalled = query("MATCH (p:Person) RETURN p.id", &monitor)
+        .with_lookup_join(LookupJoin::new("p.id", Arc::new(Blocking)));
+    let running = tokio::spawn(async move { stalled.execute(datasets(), None).await });
+
+    let id = executing(&monitor).await[0].id;
+    let error = monitor.kill(id).await.unwrap_err();
+    assert!(error.to_string().contains("not torn down"), "{}", error);
+
+    // The query stops once its store answers
+    let error = running.await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("was killed"), "{}", error);
+    assert!(!monitor.kill(id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_finished_queries_are_not_listed() {
+    let monitor = Arc::new(QueryMonitor::new());
+    let result = query("MATCH (p:Person) RETURN p.name", &monitor)
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(result.num_rows(), 3);
+    assert!(monitor.active_queries().is_empty());
+
+    // Nothing is running under that id anymore
+    let killed = query("CALL dbms.killQuery(1)", &monitor)
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&killed, "query_id"), vec!["query_1"]);
+    assert!(!killed
+        .column_by_name("killed")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .unwrap()
+        .value(0));
+}
+
+#[tokio::test]
+async fn test_monitor_procedures_need_a_monitor() {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    let error = CypherQuery::new("CALL dbms.listQueries()")
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("needs a query monitor"),
+        "{}",
+        error
+    );
+}
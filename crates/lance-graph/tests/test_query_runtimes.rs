@@ -0,0 +1,93 @@
+use arrow_array::{Array, Int64Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteParams};
+use lance_graph::config::GraphConfig;
+use lance_graph::runtime::{QueryRuntimes, WorkloadClass};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn person_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+        ],
+    )
+    .unwrap()
+}
+
+fn query(class: WorkloadClass, runtimes: &Arc<QueryRuntimes>) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new("MATCH (p:Person) WHERE p.id > 1 RETURN p.name ORDER BY p.name")
+        .unwrap()
+        .with_config(config)
+        .with_runtimes(runtimes.clone())
+        .with_workload_class(class)
+}
+
+fn names(batch: &RecordBatch) -> Vec<String> {
+    let names = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    (0..names.len())
+        .map(|i| names.value(i).to_string())
+        .collect()
+}
+
+#[test]
+fn test_queries_run_on_the_runtime_of_their_workload_class() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let analytic = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("graph-analytic")
+        .enable_all()
+        .build()
+        .unwrap();
+    let caller = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let runtimes = Arc::new(
+        QueryRuntimes::new().with_runtime(WorkloadClass::Analytic, analytic.handle().clone()),
+    );
+
+    caller.block_on(async {
+        let datasets = HashMap::from([("Person".to_string(), person_batch())]);
+        for class in [WorkloadClass::Interactive, WorkloadClass::Analytic] {
+            let result = query(class, &runtimes)
+                .execute(datasets.clone(), None)
+                .await
+                .unwrap();
+            assert_eq!(names(&result), vec!["Bob", "Carol"], "{}", class);
+        }
+
+        let batch = person_batch();
+        let schema = batch.schema();
+        Dataset::write(
+            RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema),
+            tmp_dir.path().join("Person.lance").to_str().unwrap(),
+            Some(WriteParams::default()),
+        )
+        .await
+        .unwrap();
+        let result = query(WorkloadClass::Analytic, &runtimes)
+            .execute_with_namespace(
+                DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned()),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(names(&result), vec!["Bob", "Carol"]);
+    });
+}
@@ -0,0 +1,105 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::{optional_ints, strings};
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+        Field::new("city", DataType::Utf8, false),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "Dave"])),
+            Arc::new(Int64Array::from(vec![25, 45, 70, 35])),
+            Arc::new(StringArray::from(vec!["Paris", "Berlin", "Paris", "Paris"])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), person)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+#[tokio::test]
+async fn test_reduce_over_collected_aggregate() {
+    let result = query(
+        "MATCH (p:Person) WITH p.city AS city, collect(p.age) AS ages \
+         RETURN city, reduce(total = 0, a IN ages | total + a) AS total ORDER BY city",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "city"), vec!["Berlin", "Paris"]);
+    assert_eq!(optional_ints(&result, "total"), vec![Some(45), Some(130)]);
+}
+
+#[tokio::test]
+async fn test_reduce_in_predicate() {
+    // Sums 1, 3, 6 and 10 for ids 1 to 4
+    let result = query(
+        "MATCH (p:Person) WHERE reduce(s = 0, x IN range(1, p.id) | s + x) > 5 \
+         RETURN p.name ORDER BY p.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "p.name"), vec!["Carol", "Dave"]);
+}
+
+#[tokio::test]
+async fn test_reduce_reads_the_row_of_its_list() {
+    let result = query(
+        "MATCH (p:Person) WHERE p.id = 2 \
+         RETURN reduce(acc = p.age, x IN range(1, p.id) | acc + x * p.id) AS weighted, \
+         reduce(acc = p.age, x IN range(5, p.id) | acc + x) AS empty, \
+         reduce(acc = 0, x IN null | acc + x) AS missing",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    // 45 + 1 * 2 + 2 * 2
+    assert_eq!(optional_ints(&result, "weighted"), vec![Some(51)]);
+    // Empty lists reduce to the initial value, null lists to null
+    assert_eq!(optional_ints(&result, "empty"), vec![Some(45)]);
+    assert!(result.column_by_name("missing").unwrap().is_null(0));
+}
+
+#[tokio::test]
+async fn test_reduce_rejects_aggregates_and_reused_variables() {
+    let error = query("MATCH (p:Person) RETURN reduce(acc = 0, x IN [1, 2] | acc + count(x))")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Aggregates are not allowed"),
+        "{}",
+        error
+    );
+
+    let error = query("MATCH (p:Person) RETURN reduce(x = 0, x IN [1, 2] | x + 1)")
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("distinct accumulator"),
+        "{}",
+        error
+    );
+}
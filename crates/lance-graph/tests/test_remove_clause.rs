@@ -0,0 +1,153 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance::dataset::WriteMode;
+use lance_graph::config::{GraphConfig, NodeMapping};
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+use common::{ints, namespace, optional_ints, strings, write_dataset};
+
+/// A shared "nodes" table of Person and Employee rows, told apart by `kind`
+async fn write_nodes(dir: &Path) -> GraphConfig {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("kind", DataType::Utf8, true),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, true),
+    ]));
+    let nodes = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Person", "Person", "Employee"])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            Arc::new(Int64Array::from(vec![28, 34, 41])),
+        ],
+    )
+    .unwrap();
+    write_dataset(&dir.join("nodes.lance"), nodes, WriteMode::Create).await;
+    GraphConfig::builder()
+        .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", "kind"))
+        .with_node_mapping(NodeMapping::new("Employee", "id").with_label_column("nodes", "kind"))
+        .build()
+        .unwrap()
+}
+
+/// (properties_removed, labels_removed) of a REMOVE result
+fn counters(batch: &RecordBatch) -> (i64, i64) {
+    (
+        ints(batch, "properties_removed")[0],
+        ints(batch, "labels_removed")[0],
+    )
+}
+
+#[tokio::test]
+async fn test_remove_property_nulls_the_column() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let config = write_nodes(tmp_dir.path()).await;
+
+    let result = CypherQuery::new("MATCH (n:Person) WHERE n.name = 'Bob' REMOVE n.age")
+        .unwrap()
+        .with_config(config.clone())
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(counters(&result), (1, 0));
+
+    let people = CypherQuery::new("MATCH (n:Person) RETURN n.name, n.age ORDER BY n.name")
+        .unwrap()
+        .with_config(config)
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&people, 0), vec!["Alice", "Bob"]);
+    assert_eq!(optional_ints(&people, 1), vec![Some(28), None]);
+}
+
+#[tokio::test]
+async fn test_remove_label_clears_the_label_column() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let config = write_nodes(tmp_dir.path()).await;
+    let query = |cypher: &str| {
+        CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(config.clone())
+    };
+
+    // Alice is not an Employee, so there is nothing to remove
+    let result = query("MATCH (n:Person {name: 'Alice'}) REMOVE n:Employee")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(counters(&result), (0, 0));
+
+    let result = query("MATCH (n:Person {name: 'Bob'}) REMOVE n:Person")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(counters(&result), (0, 1));
+
+    let people = query("MATCH (n:Person) RETURN n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&people, 0), vec!["Alice"]);
+    let employees = query("MATCH (n:Employee) RETURN n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&employees, 0), vec!["Carol"]);
+}
+
+#[tokio::test]
+async fn test_remove_rejects_keys_and_required_columns() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let config = write_nodes(tmp_dir.path()).await;
+    let query = |cypher: &str| {
+        CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(config.clone())
+    };
+
+    let err = query("MATCH (n:Person) REMOVE n.id")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("REMOVE cannot change the key property 'id'"));
+
+    let err = query("MATCH (n:Person) REMOVE n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not nullable"));
+
+    let people = query("MATCH (n:Person) WHERE n.age IS NOT NULL RETURN n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(people.num_rows(), 2, "failed removals change nothing");
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("age", DataType::Int64, true),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1])),
+            Arc::new(Int64Array::from(vec![28])),
+        ],
+    )
+    .unwrap();
+    let err = query("MATCH (n:Person) REMOVE n.age")
+        .execute(HashMap::from([("Person".to_string(), person)]), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("REMOVE without a namespace"));
+}
@@ -0,0 +1,189 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance::dataset::WriteMode;
+use lance_graph::config::{GraphConfig, NodeMapping};
+use lance_graph::CypherQuery;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::{ints, namespace, strings, write_dataset};
+
+fn person_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, true),
+        Field::new("city", DataType::Utf8, true),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+            Arc::new(Int64Array::from(vec![Some(28), Some(34), None])),
+            Arc::new(StringArray::from(vec![Some("Paris"), None, None])),
+        ],
+    )
+    .unwrap()
+}
+
+fn config() -> GraphConfig {
+    GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap()
+}
+
+/// (properties_set, labels_set) of a SET result
+fn counters(batch: &RecordBatch) -> (i64, i64) {
+    (
+        ints(batch, "properties_set")[0],
+        ints(batch, "labels_set")[0],
+    )
+}
+
+#[tokio::test]
+async fn test_set_properties_of_matched_nodes() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(),
+        WriteMode::Create,
+    )
+    .await;
+
+    let result =
+        CypherQuery::new("MATCH (n:Person) WHERE n.id > 1 SET n.age = $age, n += $changes")
+            .unwrap()
+            .with_config(config())
+            .with_parameter("age", 40)
+            .with_parameter("changes", json!({"city": "Lisbon"}))
+            .execute_with_namespace(namespace(tmp_dir.path()), None)
+            .await
+            .unwrap();
+    assert_eq!(counters(&result), (4, 0), "two properties on Bob and Carol");
+
+    let people = CypherQuery::new("MATCH (n:Person) RETURN n.name, n.city, n.age ORDER BY n.name")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&people, 0), vec!["Alice", "Bob", "Carol"]);
+    assert_eq!(strings(&people, 1), vec!["Paris", "Lisbon", "Lisbon"]);
+    assert_eq!(ints(&people, 2), vec![28, 40, 40]);
+}
+
+#[tokio::test]
+async fn test_set_updating_a_dataset_twice_does_not_conflict_with_itself() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(),
+        WriteMode::Create,
+    )
+    .await;
+
+    let result =
+        CypherQuery::new("MATCH (a:Person {id: 1}), (b:Person {id: 2}) SET a.age = 29, b.age = 35")
+            .unwrap()
+            .with_config(config())
+            .execute_with_namespace(namespace(tmp_dir.path()), None)
+            .await
+            .unwrap();
+    assert_eq!(counters(&result), (2, 0));
+
+    let people = CypherQuery::new("MATCH (n:Person) WHERE n.id < 3 RETURN n.age ORDER BY n.id")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(ints(&people, 0), vec![29, 35]);
+}
+
+#[tokio::test]
+async fn test_set_label_rewrites_the_label_column() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let nodes = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["Person", "Person"])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob"])),
+        ],
+    )
+    .unwrap();
+    write_dataset(
+        &tmp_dir.path().join("nodes.lance"),
+        nodes,
+        WriteMode::Create,
+    )
+    .await;
+    let config = GraphConfig::builder()
+        .with_node_mapping(NodeMapping::new("Person", "id").with_label_column("nodes", "kind"))
+        .with_node_mapping(NodeMapping::new("Employee", "id").with_label_column("nodes", "kind"))
+        .build()
+        .unwrap();
+    let query = |cypher: &str| {
+        CypherQuery::new(cypher)
+            .unwrap()
+            .with_config(config.clone())
+    };
+
+    let result = query("MATCH (n:Person {name: 'Bob'}) SET n:$label")
+        .with_parameter("label", "Employee")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(counters(&result), (0, 1));
+
+    let employees = query("MATCH (n:Employee) RETURN n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&employees, 0), vec!["Bob"]);
+    let people = query("MATCH (n:Person) RETURN n.name")
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap();
+    assert_eq!(strings(&people, 0), vec!["Alice"]);
+}
+
+#[tokio::test]
+async fn test_set_rejects_key_changes_and_in_memory_execution() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(),
+        WriteMode::Create,
+    )
+    .await;
+
+    let err = CypherQuery::new("MATCH (n:Person) SET n.id = 7")
+        .unwrap()
+        .with_config(config())
+        .execute_with_namespace(namespace(tmp_dir.path()), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("key property 'id'"));
+
+    let err = CypherQuery::new("MATCH (n:Person) SET n.age = 1")
+        .unwrap()
+        .with_config(config())
+        .execute(
+            HashMap::from([("Person".to_string(), person_batch())]),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("SET without a namespace"));
+}
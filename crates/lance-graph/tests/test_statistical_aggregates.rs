@@ -0,0 +1,121 @@
+use arrow_array::{Array, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("team", DataType::Utf8, false),
+        Field::new("score", DataType::Int64, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5, 6])),
+            Arc::new(StringArray::from(vec!["a", "a", "a", "a", "b", "b"])),
+            Arc::new(Int64Array::from(vec![
+                Some(2),
+                Some(4),
+                Some(4),
+                Some(10),
+                Some(7),
+                None,
+            ])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Player".to_string(), batch)])
+}
+
+async fn run(cypher: &str) -> RecordBatch {
+    let config = GraphConfig::builder()
+        .with_node_label("Player", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap()
+}
+
+fn floats(batch: &RecordBatch, column: &str) -> Vec<Option<f64>> {
+    batch
+        .column_by_name(column)
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap()
+        .iter()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_standard_deviations() {
+    let result = run(
+        "MATCH (p:Player) RETURN p.team AS team, stDev(p.score) AS sample, \
+         stDevP(p.score) AS population ORDER BY team",
+    )
+    .await;
+    // Team a scores 2, 4, 4, 10: mean 5, squared deviations sum to 36
+    let close = |actual: Option<f64>, expected: f64| {
+        assert!((actual.unwrap() - expected).abs() < 1e-9, "{:?}", actual)
+    };
+    close(floats(&result, "sample")[0], 12.0f64.sqrt());
+    close(floats(&result, "population")[0], 3.0);
+    // A single non-null value deviates by nothing
+    assert_eq!(floats(&result, "sample")[1], Some(0.0));
+    assert_eq!(floats(&result, "population")[1], Some(0.0));
+}
+
+#[tokio::test]
+async fn test_percentiles() {
+    let result = run("MATCH (p:Player) WHERE p.team = 'a' \
+         RETURN percentileCont(p.score, 0.5) AS median, percentileCont(p.score, 0.4) AS cont, \
+         percentileDisc(p.score, 0.5) AS disc, percentileDisc(p.score, 1.0) AS highest, \
+         percentileDisc(p.score, 0.0) AS lowest")
+    .await;
+    assert_eq!(floats(&result, "median"), vec![Some(4.0)]);
+    // Position 0.4 * 3 = 1.2 between 4 and 4
+    assert_eq!(floats(&result, "cont"), vec![Some(4.0)]);
+
+    // percentileDisc returns one of the values, in their type
+    let ints = |column: &str| {
+        result
+            .column_by_name(column)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0)
+    };
+    assert_eq!(ints("disc"), 4);
+    assert_eq!(ints("highest"), 10);
+    assert_eq!(ints("lowest"), 2);
+
+    // NULLs are skipped, per group
+    let result = run("MATCH (p:Player) RETURN p.team AS team, \
+         percentileCont(p.score, 0.75) AS upper ORDER BY team")
+    .await;
+    // Team a: position 0.75 * 3 = 2.25 between 4 and 10
+    assert_eq!(floats(&result, "upper"), vec![Some(5.5), Some(7.0)]);
+}
+
+#[tokio::test]
+async fn test_percentile_must_be_a_fraction() {
+    let config = GraphConfig::builder()
+        .with_node_label("Player", "id")
+        .build()
+        .unwrap();
+    let err = CypherQuery::new("MATCH (p:Player) RETURN percentileCont(p.score, 1.5)")
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("between 0.0 and 1.0"), "{}", err);
+}
@@ -0,0 +1,161 @@
+use arrow_array::{Array, Int64Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance_graph::config::GraphConfig;
+use lance_graph::cost::{GraphStatistics, Histogram, PropertyStatistics};
+use lance_graph::{CypherQuery, DirNamespace};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn books() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("year", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["Dune", "Emma", "Ulysses"])),
+            Arc::new(Int64Array::from(vec![1990, 2005, 2024])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Book".to_string(), batch)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Book", "id")
+        .build()
+        .unwrap();
+    let statistics = GraphStatistics::new().with_node_property(
+        "Book",
+        "year",
+        PropertyStatistics {
+            histogram: Some(Histogram {
+                bounds: vec![1990.0, 2005.0, 2024.0],
+            }),
+            ..Default::default()
+        },
+    );
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .with_statistics(statistics)
+}
+
+#[tokio::test]
+async fn test_contradicted_filter_returns_typed_empty_result() {
+    let query = query("MATCH (b:Book) WHERE b.year > 3000 RETURN b.title, b.year");
+    let result = query.execute(books(), None).await.unwrap();
+    assert_eq!(result.num_rows(), 0);
+    let schema = result.schema();
+    assert_eq!(
+        schema.field_with_name("b.title").unwrap().data_type(),
+        &DataType::Utf8
+    );
+    assert_eq!(
+        schema.field_with_name("b.year").unwrap().data_type(),
+        &DataType::Int64
+    );
+
+    let plan = query.explain(books()).await.unwrap();
+    assert!(plan.contains("pruned_filters"), "{}", plan);
+    assert!(
+        plan.contains("b.year > 3000 is always false: Book.year is within [1990, 2024]"),
+        "{}",
+        plan
+    );
+    // The physical plan reads no dataset
+    assert!(!plan.contains("DataSourceExec"), "{}", plan);
+}
+
+#[tokio::test]
+async fn test_filters_within_the_bounds_are_kept() {
+    let query = query("MATCH (b:Book) WHERE b.year >= 2024 OR b.year < 0 RETURN b.title");
+    let result = query.execute(books(), None).await.unwrap();
+    let titles = result
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(titles.iter().flatten().collect::<Vec<_>>(), vec!["Ulysses"]);
+
+    let plan = query.explain(books()).await.unwrap();
+    assert!(!plan.contains("pruned_filters"), "{}", plan);
+}
+
+#[tokio::test]
+async fn test_statistics_of_an_older_dataset_version_do_not_prune() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let uri = tmp_dir.path().join("Book.lance");
+    let write = |batch: RecordBatch, mode: WriteMode| {
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok::<_, ArrowError>(batch)], schema);
+        let uri = uri.to_str().unwrap().to_string();
+        async move {
+            Dataset::write(
+                reader,
+                &uri,
+                Some(WriteParams {
+                    mode,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        }
+    };
+    write(books().remove("Book").unwrap(), WriteMode::Create).await;
+    let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+    let config = GraphConfig::builder()
+        .with_node_label("Book", "id")
+        .build()
+        .unwrap();
+
+    let summary = CypherQuery::new("CALL graph.summary()")
+        .unwrap()
+        .with_config(config.clone())
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap();
+    let statistics = GraphStatistics::from_summary(&summary).unwrap();
+    assert_eq!(statistics.node("Book").unwrap().dataset_version, Some(1));
+
+    let late_books = || async {
+        let result = CypherQuery::new("MATCH (b:Book) WHERE b.year > 3000 RETURN b.title")
+            .unwrap()
+            .with_config(config.clone())
+            .with_statistics(statistics.clone())
+            .execute_with_namespace(namespace.clone(), None)
+            .await
+            .unwrap();
+        let titles = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        titles
+            .iter()
+            .flatten()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert!(late_books().await.is_empty());
+
+    // A book written after the summary lies outside its bounds
+    let schema = books()["Book"].schema();
+    let late = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![4])),
+            Arc::new(StringArray::from(vec!["Foundation 3001"])),
+            Arc::new(Int64Array::from(vec![3001])),
+        ],
+    )
+    .unwrap();
+    write(late, WriteMode::Append).await;
+    assert_eq!(late_books().await, vec!["Foundation 3001"]);
+}
@@ -0,0 +1,126 @@
+use arrow_array::{Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use lance_graph::coalesce::BatchCoalescing;
+use lance_graph::config::GraphConfig;
+use lance_graph::{CypherQuery, GraphError};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn person_datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("age", DataType::Int64, false),
+    ]));
+    let person = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol", "David"])),
+            Arc::new(Int64Array::from(vec![28, 34, 29, 42])),
+        ],
+    )
+    .unwrap();
+
+    let mut datasets = HashMap::new();
+    datasets.insert("Person".to_string(), person);
+    datasets
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+fn names(batches: &[RecordBatch]) -> Vec<String> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            let column = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .clone();
+            (0..column.len()).map(move |i| column.value(i).to_string())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_execute_into_sends_batches_to_a_concurrent_consumer() {
+    let query = query("MATCH (p:Person) WHERE p.age > 28 RETURN p.name ORDER BY p.name");
+    let (sender, receiver) = mpsc::channel(1);
+
+    let consumer = tokio::spawn(receiver.collect::<Vec<RecordBatch>>());
+    query.execute_into(person_datasets(), sender).await.unwrap();
+    let batches = consumer.await.unwrap();
+
+    assert_eq!(names(&batches), vec!["Bob", "Carol", "David"]);
+}
+
+#[tokio::test]
+async fn test_execute_into_stops_when_receiver_is_dropped() {
+    let query = query("MATCH (p:Person) RETURN p.name");
+    let (sender, receiver) = mpsc::channel(1);
+    drop(receiver);
+
+    query.execute_into(person_datasets(), sender).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_with_callback_sees_every_row() {
+    let query = query("MATCH (p:Person) RETURN p.name ORDER BY p.name");
+    let mut batches = Vec::new();
+
+    query
+        .execute_with_callback(person_datasets(), |batch| {
+            batches.push(batch);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(names(&batches), vec!["Alice", "Bob", "Carol", "David"]);
+}
+
+#[tokio::test]
+async fn test_execute_with_callback_error_stops_execution() {
+    let query = query("MATCH (p:Person) RETURN p.name");
+
+    let err = query
+        .execute_with_callback(person_datasets(), |_| {
+            Err(GraphError::ExecutionError {
+                message: "consumer is full".to_string(),
+                location: snafu::Location::new(file!(), line!(), column!()),
+            })
+        })
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("consumer is full"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_batch_coalescing_merges_streamed_batches() {
+    let query = query("MATCH (p:Person) WHERE p.age > 28 RETURN p.name ORDER BY p.name")
+        .with_batch_coalescing(BatchCoalescing::new().with_target_rows(1000));
+    let mut batches = Vec::new();
+
+    query
+        .execute_with_callback(person_datasets(), |batch| {
+            batches.push(batch);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+    // Below the target, every row arrives in the final flush
+    assert_eq!(batches.len(), 1);
+    assert_eq!(names(&batches), vec!["Bob", "Carol", "David"]);
+}
@@ -0,0 +1,160 @@
+use arrow_array::{Int64Array, ListArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::optional_strings;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("tags", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec![Some("  Ada Lovelace "), None])),
+            Arc::new(StringArray::from(vec![Some("math,poetry"), Some("chess")])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), batch)])
+}
+
+async fn run(cypher: &str) -> RecordBatch {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_string_functions() {
+    let result = run(
+        "MATCH (p:Person) RETURN trim(p.name) AS trimmed, ltrim(p.name) AS ltrimmed, \
+         rtrim(p.name) AS rtrimmed, toUpper(trim(p.name)) AS upper, \
+         replace(trim(p.name), 'Lovelace', 'King') AS replaced, \
+         substring(trim(p.name), 4) AS rest, substring(trim(p.name), 0, 3) AS first, \
+         left(trim(p.name), 2) AS head, right(trim(p.name), 3) AS tail, \
+         reverse(trim(p.name)) AS reversed ORDER BY p.id",
+    )
+    .await;
+    let first = |column: &str| optional_strings(&result, column)[0].clone().unwrap();
+    assert_eq!(first("trimmed"), "Ada Lovelace");
+    assert_eq!(first("ltrimmed"), "Ada Lovelace ");
+    assert_eq!(first("rtrimmed"), "  Ada Lovelace");
+    assert_eq!(first("upper"), "ADA LOVELACE");
+    assert_eq!(first("replaced"), "Ada King");
+    assert_eq!(first("rest"), "Lovelace");
+    assert_eq!(first("first"), "Ada");
+    assert_eq!(first("head"), "Ad");
+    assert_eq!(first("tail"), "ace");
+    assert_eq!(first("reversed"), "ecalevoL adA");
+
+    // NULL in, NULL out
+    for column in ["trimmed", "replaced", "rest", "head", "reversed"] {
+        assert_eq!(optional_strings(&result, column)[1], None, "{}", column);
+    }
+}
+
+#[tokio::test]
+async fn test_split_returns_a_list() {
+    let result = run("MATCH (p:Person) RETURN split(p.tags, ',') AS tags ORDER BY p.id").await;
+    let tags = result
+        .column_by_name("tags")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    let row = |i: usize| {
+        tags.value(i)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .iter()
+            .flatten()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(row(0), vec!["math", "poetry"]);
+    assert_eq!(row(1), vec!["chess"]);
+
+    // The list unwinds like any other
+    let result =
+        run("MATCH (p:Person) UNWIND split(p.tags, ',') AS tag RETURN tag ORDER BY tag").await;
+    assert_eq!(
+        optional_strings(&result, "tag"),
+        vec![
+            Some("chess".to_string()),
+            Some("math".to_string()),
+            Some("poetry".to_string())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_string_function_arity_is_checked() {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    let err = CypherQuery::new("MATCH (p:Person) RETURN replace(p.name, 'a')")
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("REPLACE requires 3 arguments"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_regex_match() {
+    // The pattern must match the whole string, and NULLs match nothing
+    let result = run("MATCH (p:Person) WHERE p.tags =~ 'ch.*' RETURN p.id").await;
+    assert_eq!(result.num_rows(), 1);
+    let result = run("MATCH (p:Person) WHERE p.tags =~ 'ch' RETURN p.id").await;
+    assert_eq!(result.num_rows(), 0);
+    let result = run(
+        "MATCH (p:Person) WHERE p.name =~ '(?i)\\s*ada.*' OR p.tags =~ '[a-z]+,[a-z]+' \
+         RETURN p.id",
+    )
+    .await;
+    assert_eq!(result.num_rows(), 1);
+    let result = run("MATCH (p:Person) WHERE NOT p.name =~ 'Bob' RETURN p.id").await;
+    assert_eq!(result.num_rows(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_regex_is_rejected() {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    let err = CypherQuery::new("MATCH (p:Person) WHERE p.name =~ 'A(' RETURN p.id")
+        .unwrap()
+        .with_config(config)
+        .execute(datasets(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("Invalid regular expression 'A('"),
+        "{}",
+        err
+    );
+}
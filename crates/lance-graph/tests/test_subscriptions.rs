@@ -0,0 +1,157 @@
+use lance::dataset::WriteMode;
+use lance_graph::subscription::{SubscriptionEvent, SubscriptionManager};
+use lance_graph::DirNamespace;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+use common::{ints, knows_batch, namespace, person_batch, query, strings, write_dataset};
+
+/// Namespace with Alice(28), Bob(34) and Alice -> Bob
+async fn graph(dir: &Path) -> DirNamespace {
+    write_dataset(
+        &dir.join("Person.lance"),
+        person_batch(vec![1, 2], vec!["Alice", "Bob"], vec![28, 34]),
+        WriteMode::Create,
+    )
+    .await;
+    write_dataset(
+        &dir.join("KNOWS.lance"),
+        knows_batch(vec![1], vec![2]),
+        WriteMode::Create,
+    )
+    .await;
+    namespace(dir)
+}
+
+type Events = Arc<Mutex<Vec<SubscriptionEvent>>>;
+
+fn recorder() -> (Events, impl Fn(&SubscriptionEvent) + Send + Sync + 'static) {
+    let events: Events = Arc::default();
+    let sink = events.clone();
+    (events, move |event: &SubscriptionEvent| {
+        sink.lock().unwrap().push(event.clone())
+    })
+}
+
+#[tokio::test]
+async fn test_filter_subscription_receives_appended_matches() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let subscriptions = SubscriptionManager::new(graph(tmp_dir.path()).await);
+    let (events, callback) = recorder();
+
+    let (id, current) = subscriptions
+        .register(
+            query("MATCH (p:Person) WHERE p.age > 30 RETURN p.name"),
+            callback,
+        )
+        .await
+        .unwrap();
+    assert_eq!(strings(&current, 0), vec!["Bob"]);
+    assert_eq!(subscriptions.poll().await.unwrap(), 0);
+
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(vec![3, 4], vec!["Carol", "David"], vec![29, 42]),
+        WriteMode::Append,
+    )
+    .await;
+    assert_eq!(subscriptions.poll().await.unwrap(), 1);
+    {
+        let events = events.lock().unwrap();
+        let SubscriptionEvent::Appended { rows } = &events[0] else {
+            panic!("expected appended rows, got {:?}", events[0]);
+        };
+        assert_eq!(strings(rows, 0), vec!["David"]);
+    }
+
+    // Appends matching nothing are not notified
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(vec![5], vec!["Eve"], vec![19]),
+        WriteMode::Append,
+    )
+    .await;
+    assert_eq!(subscriptions.poll().await.unwrap(), 0);
+
+    assert!(subscriptions.unregister(id).await);
+    assert!(subscriptions.subscriptions().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_join_subscription_receives_new_edges() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let subscriptions = SubscriptionManager::new(graph(tmp_dir.path()).await);
+    let (events, callback) = recorder();
+    subscriptions
+        .register(
+            query("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name, b.name"),
+            callback,
+        )
+        .await
+        .unwrap();
+
+    write_dataset(
+        &tmp_dir.path().join("KNOWS.lance"),
+        knows_batch(vec![2], vec![1]),
+        WriteMode::Append,
+    )
+    .await;
+    assert_eq!(subscriptions.poll().await.unwrap(), 1);
+    {
+        let events = events.lock().unwrap();
+        let SubscriptionEvent::Appended { rows } = &events[0] else {
+            panic!("expected appended rows, got {:?}", events[0]);
+        };
+        assert_eq!(strings(rows, 0), vec!["Bob"]);
+        assert_eq!(strings(rows, 1), vec!["Alice"]);
+    }
+
+    // Person is read by both ends of the pattern: growing it re-runs the query
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(vec![3], vec!["Carol"], vec![29]),
+        WriteMode::Append,
+    )
+    .await;
+    write_dataset(
+        &tmp_dir.path().join("KNOWS.lance"),
+        knows_batch(vec![3], vec![3]),
+        WriteMode::Append,
+    )
+    .await;
+    assert_eq!(subscriptions.poll().await.unwrap(), 1);
+    let events = events.lock().unwrap();
+    let SubscriptionEvent::Reset { result } = &events[1] else {
+        panic!("expected a reset, got {:?}", events[1]);
+    };
+    assert_eq!(result.num_rows(), 3);
+}
+
+#[tokio::test]
+async fn test_aggregate_subscription_is_reset_on_append() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let subscriptions = SubscriptionManager::new(graph(tmp_dir.path()).await);
+    let (events, callback) = recorder();
+    subscriptions
+        .register(
+            query("MATCH (p:Person) RETURN count(p) AS people"),
+            callback,
+        )
+        .await
+        .unwrap();
+
+    write_dataset(
+        &tmp_dir.path().join("Person.lance"),
+        person_batch(vec![3], vec!["Carol"], vec![29]),
+        WriteMode::Append,
+    )
+    .await;
+    assert_eq!(subscriptions.poll().await.unwrap(), 1);
+    let events = events.lock().unwrap();
+    let SubscriptionEvent::Reset { result } = &events[0] else {
+        panic!("expected a reset, got {:?}", events[0]);
+    };
+    assert_eq!(ints(result, 0), vec![3]);
+}
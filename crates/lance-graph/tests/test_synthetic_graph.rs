@@ -0,0 +1,49 @@
+use arrow_array::{Array, Int64Array};
+use lance_graph::test_utils::{DegreeDistribution, SyntheticGraph};
+use lance_graph::{CypherQuery, DirNamespace};
+
+fn count(batch: &arrow_array::RecordBatch) -> i64 {
+    batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .value(0)
+}
+
+#[tokio::test]
+async fn test_synthetic_graph_round_trips_through_lance() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let graph = SyntheticGraph::new()
+        .with_seed(11)
+        .with_node_label("Person", 40)
+        .with_embedding_dim("Person", 4)
+        .with_relationship(
+            "KNOWS",
+            "Person",
+            "Person",
+            DegreeDistribution::uniform(2, 4),
+        );
+    let config = graph
+        .write_lance(tmp_dir.path().to_str().unwrap())
+        .await
+        .unwrap();
+    let expected_edges = graph.generate().unwrap()["KNOWS"].num_rows() as i64;
+    let namespace = DirNamespace::new(tmp_dir.path().to_string_lossy().into_owned());
+
+    let query = CypherQuery::new("MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN count(*) AS n")
+        .unwrap()
+        .with_config(config.clone());
+    let result = query
+        .execute_with_namespace(namespace.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(count(&result), expected_edges);
+    assert!((80..=160).contains(&expected_edges));
+
+    let query = CypherQuery::new("MATCH (p:Person) WHERE p.bucket = 3 RETURN count(p) AS n")
+        .unwrap()
+        .with_config(config);
+    let result = query.execute_with_namespace(namespace, None).await.unwrap();
+    assert_eq!(count(&result), 4);
+}
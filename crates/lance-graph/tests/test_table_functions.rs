@@ -0,0 +1,182 @@
+use arrow_array::{Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::scalar::ScalarValue;
+use futures::stream::{self, BoxStream, StreamExt};
+use lance_graph::config::GraphConfig;
+use lance_graph::table_function::TableFunction;
+use lance_graph::{CypherQuery, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+
+use common::ints;
+
+/// `gen.range(n)`: the integers `0..n`, two per batch
+#[derive(Debug, Default)]
+struct Range {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl TableFunction for Range {
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("value", DataType::Int64, false),
+            Field::new("label", DataType::Utf8, false),
+        ]))
+    }
+
+    async fn call(
+        &self,
+        arguments: Vec<ScalarValue>,
+    ) -> Result<BoxStream<'static, Result<RecordBatch>>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let ScalarValue::Int64(Some(n)) = arguments[0] else {
+            panic!("expected an integer, got {:?}", arguments);
+        };
+        let schema = self.schema();
+        let values: Vec<i64> = (0..n).collect();
+        let batches: Vec<Result<RecordBatch>> = values
+            .chunks(2)
+            .map(|chunk| {
+                let labels: Vec<String> = chunk.iter().map(|v| format!("#{}", v)).collect();
+                Ok(RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(chunk.to_vec())),
+                        Arc::new(StringArray::from(labels)),
+                    ],
+                )
+                .unwrap())
+            })
+            .collect();
+        Ok(stream::iter(batches).boxed())
+    }
+}
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 7])),
+            Arc::new(StringArray::from(vec!["Alice", "Bob", "Carol"])),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Person".to_string(), batch)])
+}
+
+fn query(cypher: &str, range: Arc<Range>) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Person", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher)
+        .unwrap()
+        .with_config(config)
+        .with_table_function("gen.range", range)
+}
+
+#[tokio::test]
+async fn test_yielded_columns_are_returned() {
+    let range = Arc::new(Range::default());
+    let result = query(
+        "CALL gen.range(5) YIELD value, label AS name RETURN value, name ORDER BY value",
+        range.clone(),
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(ints(&result, "value"), vec![0, 1, 2, 3, 4]);
+    let names = result
+        .column_by_name("name")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(names.value(3), "#3");
+    assert_eq!(range.calls.load(Ordering::SeqCst), 1);
+
+    // Arguments can be parameters, and names are case-insensitive
+    let result = query(
+        "CALL GEN.Range($n) YIELD value WHERE value > 1 RETURN value ORDER BY value",
+        Arc::new(Range::default()),
+    )
+    .with_parameter("n", 4)
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(ints(&result, "value"), vec![2, 3]);
+}
+
+#[tokio::test]
+async fn test_yielded_rows_join_with_matches() {
+    let result = query(
+        "MATCH (p:Person) CALL gen.range(3) YIELD value \
+         WHERE p.id = value RETURN p.name, value ORDER BY value",
+        Arc::new(Range::default()),
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(ints(&result, "value"), vec![1, 2]);
+    let names = result
+        .column_by_name("p.name")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        names.iter().flatten().collect::<Vec<_>>(),
+        vec!["Alice", "Bob"]
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_functions_and_columns_are_rejected() {
+    let error = query(
+        "CALL gen.nothing(3) YIELD value RETURN value",
+        Arc::new(Range::default()),
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Unknown table function: gen.nothing"),
+        "{}",
+        error
+    );
+
+    let error = query(
+        "CALL gen.range(3) YIELD total RETURN total",
+        Arc::new(Range::default()),
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("available columns: value, label"),
+        "{}",
+        error
+    );
+
+    // Yielding a bound variable again is a semantic error
+    let error = query(
+        "MATCH (value:Person) CALL gen.range(3) YIELD value RETURN value",
+        Arc::new(Range::default()),
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("already bound"), "{}", error);
+}
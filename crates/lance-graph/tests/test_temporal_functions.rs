@@ -0,0 +1,180 @@
+use arrow::compute::cast;
+use arrow_array::{Int64Array, IntervalMonthDayNanoArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use lance_graph::config::GraphConfig;
+use lance_graph::CypherQuery;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+use common::ints;
+
+fn datasets() -> HashMap<String, RecordBatch> {
+    let at = StringArray::from(vec![
+        "2024-01-15T09:30:00",
+        "2024-01-20T17:45:30.250",
+        "2024-03-04T08:00:00",
+    ]);
+    let day = StringArray::from(vec!["2024-01-15", "2024-01-20", "2024-03-04"]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new(
+            "at",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+        ),
+        Field::new("day", DataType::Date32, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["launch", "review", "release"])),
+            cast(&at, &DataType::Timestamp(TimeUnit::Microsecond, None)).unwrap(),
+            cast(&day, &DataType::Date32).unwrap(),
+        ],
+    )
+    .unwrap();
+    HashMap::from([("Event".to_string(), batch)])
+}
+
+fn query(cypher: &str) -> CypherQuery {
+    let config = GraphConfig::builder()
+        .with_node_label("Event", "id")
+        .build()
+        .unwrap();
+    CypherQuery::new(cypher).unwrap().with_config(config)
+}
+
+fn strings(batch: &RecordBatch, column: &str) -> Vec<String> {
+    let column = cast(batch.column_by_name(column).unwrap(), &DataType::Utf8).unwrap();
+    let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+    column.iter().map(|v| v.unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_filter_by_dates_and_datetimes() {
+    let result =
+        query("MATCH (e:Event) WHERE e.at >= datetime($since) RETURN e.name ORDER BY e.at")
+            .with_parameter("since", "2024-01-16T00:00:00Z")
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+    assert_eq!(strings(&result, "e.name"), vec!["review", "release"]);
+
+    let result =
+        query("MATCH (e:Event) WHERE e.day < date('2024-02-01') RETURN e.name ORDER BY e.day")
+            .execute(datasets(), None)
+            .await
+            .unwrap();
+    assert_eq!(strings(&result, "e.name"), vec!["launch", "review"]);
+
+    // Durations shift datetimes
+    let result = query(
+        "MATCH (e:Event) WHERE e.at + duration('P1DT12H') < datetime('2024-01-17T00:00:00Z') \
+         RETURN e.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "e.name"), vec!["launch"]);
+}
+
+#[tokio::test]
+async fn test_bucket_by_truncated_dates() {
+    let cypher = "MATCH (e:Event) \
+                  RETURN date.truncate('month', e.at) AS month, count(*) AS events \
+                  ORDER BY month";
+    let result = query(cypher).execute(datasets(), None).await.unwrap();
+    assert_eq!(
+        result.schema().field(0).data_type(),
+        &DataType::Date32,
+        "{:?}",
+        result.schema()
+    );
+    assert_eq!(strings(&result, "month"), vec!["2024-01-01", "2024-03-01"]);
+    assert_eq!(ints(&result, "events"), vec![2, 1]);
+
+    let schemas = HashMap::from([("Event".to_string(), datasets()["Event"].schema())]);
+    let described = query(cypher).describe(&schemas).unwrap();
+    assert_eq!(described.field(0).data_type(), &DataType::Date32);
+}
+
+#[tokio::test]
+async fn test_component_accessors() {
+    let result = query("MATCH (e:Event) RETURN e.name, e.at.hour ORDER BY e.at")
+        .execute(datasets(), None)
+        .await
+        .unwrap();
+    assert_eq!(ints(&result, "e.at.hour"), vec![9, 17, 8]);
+
+    // Components of values bound by WITH; Monday is day 1 of the week
+    let result = query(
+        "MATCH (e:Event) WITH e.at AS at \
+         RETURN at.year AS year, at.month AS month, at.dayOfWeek AS weekday, \
+         at.millisecond AS ms ORDER BY at",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(ints(&result, "year"), vec![2024, 2024, 2024]);
+    assert_eq!(ints(&result, "month"), vec![1, 1, 3]);
+    assert_eq!(ints(&result, "weekday"), vec![1, 6, 1]);
+    assert_eq!(ints(&result, "ms"), vec![0, 250, 0]);
+}
+
+#[tokio::test]
+async fn test_durations_between_datetimes() {
+    let result = query(
+        "MATCH (e:Event) RETURN e.name, \
+         duration.between(datetime('2024-01-15T00:00:00Z'), e.at) AS elapsed ORDER BY e.at",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    let elapsed = result
+        .column_by_name("elapsed")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<IntervalMonthDayNanoArray>()
+        .unwrap();
+    let hours = |h: i64| h * 3_600_000_000_000;
+    assert_eq!(elapsed.value(0).nanoseconds, hours(9) + hours(1) / 2);
+
+    let result = query(
+        "MATCH (e:Event) \
+         WHERE duration.between(e.at, datetime('2024-01-21T00:00:00Z')) < duration('PT12H') \
+         RETURN e.name",
+    )
+    .execute(datasets(), None)
+    .await
+    .unwrap();
+    assert_eq!(strings(&result, "e.name"), vec!["review"]);
+}
+
+#[tokio::test]
+async fn test_invalid_temporal_arguments_are_rejected() {
+    for (cypher, expected) in [
+        (
+            "MATCH (e:Event) RETURN date.truncate('fortnight', e.at)",
+            "Unsupported truncation unit 'fortnight'",
+        ),
+        (
+            "MATCH (e:Event) WHERE e.at < datetime() + duration('soon') RETURN e.name",
+            "Invalid ISO 8601 duration 'soon'",
+        ),
+        (
+            "MATCH (e:Event) WHERE e.day = date('2024-13-45') RETURN e.name",
+            "Invalid ISO 8601 date '2024-13-45'",
+        ),
+        (
+            "MATCH (e:Event) RETURN e.at.fortnight",
+            "Unsupported temporal component 'fortnight'",
+        ),
+    ] {
+        let error = query(cypher).execute(datasets(), None).await.unwrap_err();
+        assert!(error.to_string().contains(expected), "{}", error);
+    }
+}